@@ -1,31 +1,59 @@
 import { invoke } from "@tauri-apps/api/core";
 import type {
   Account,
+  AssetReport,
+  CacheKind,
+  ClearCachesReport,
   DeviceCodeResponse,
+  DownloadHistoryEntry,
   FabricGameVersion,
   FabricLoaderEntry,
   FabricLoaderVersion,
   FileInfo,
   ForgeVersion,
   GithubRelease,
+  HashMap,
+  ImportOptions,
+  ImportReport,
   InstalledFabricVersion,
   InstalledForgeVersion,
   InstalledVersion,
   Instance,
+  InstanceJavaOverride,
   InstanceRepairResult,
-  JavaCatalog,
+  JavaCatalogResult,
+  JavaConfig,
   JavaDownloadInfo,
+  JavaDownloadResult,
   JavaInstallation,
+  JavaResolution,
+  JavaSettings,
+  JavaSettingsPatch,
+  KnownBadBuild,
+  KnownInstallations,
+  LaunchHandle,
+  LaunchRecord,
   LauncherConfig,
   Message,
   MigrationResult,
   ModelInfo,
+  NetworkConfig,
+  NetworkSettings,
   PastebinResponse,
   PendingJavaDownload,
+  Profile,
+  QuickPlay,
+  ResumeJavaDownloadsResult,
   Version,
   VersionMetadata,
 } from "@/types";
 
+export function addUserJavaPath(path: string): Promise<JavaInstallation> {
+  return invoke<JavaInstallation>("add_user_java_path", {
+    path,
+  });
+}
+
 export function assistantChat(messages: Message[]): Promise<Message> {
   return invoke<Message>("assistant_chat", {
     messages,
@@ -42,8 +70,24 @@ export function assistantCheckHealth(): Promise<boolean> {
   return invoke<boolean>("assistant_check_health");
 }
 
-export function cancelJavaDownload(): Promise<void> {
-  return invoke<void>("cancel_java_download");
+export function cancelAllJavaDownloads(): Promise<number> {
+  return invoke<number>("cancel_all_java_downloads");
+}
+
+export function cancelDownload(taskId: string): Promise<void> {
+  return invoke<void>("cancel_download", {
+    taskId,
+  });
+}
+
+export function cancelDownloads(): Promise<void> {
+  return invoke<void>("cancel_downloads");
+}
+
+export function cancelJavaDownload(downloadId: string | null): Promise<void> {
+  return invoke<void>("cancel_java_download", {
+    downloadId,
+  });
 }
 
 export function checkVersionInstalled(
@@ -56,6 +100,24 @@ export function checkVersionInstalled(
   });
 }
 
+export function clearDownloadHistory(): Promise<void> {
+  return invoke<void>("clear_download_history");
+}
+
+export function clearJavaCaches(
+  kinds: CacheKind[],
+): Promise<ClearCachesReport> {
+  return invoke<ClearCachesReport>("clear_java_caches", {
+    kinds,
+  });
+}
+
+export function clearLastUsedJava(instanceId: string): Promise<void> {
+  return invoke<void>("clear_last_used_java", {
+    instanceId,
+  });
+}
+
 export function completeMicrosoftLogin(deviceCode: string): Promise<Account> {
   return invoke<Account>("complete_microsoft_login", {
     deviceCode,
@@ -68,6 +130,16 @@ export function createInstance(name: string): Promise<Instance> {
   });
 }
 
+export function createProfile(
+  name: string,
+  copyFrom: string | null,
+): Promise<void> {
+  return invoke<void>("create_profile", {
+    name,
+    copyFrom,
+  });
+}
+
 export function deleteInstance(instanceId: string): Promise<void> {
   return invoke<void>("delete_instance", {
     instanceId,
@@ -80,6 +152,12 @@ export function deleteInstanceFile(path: string): Promise<void> {
   });
 }
 
+export function deleteProfile(name: string): Promise<void> {
+  return invoke<void>("delete_profile", {
+    name,
+  });
+}
+
 export function deleteVersion(
   instanceId: string,
   versionId: string,
@@ -98,12 +176,22 @@ export function detectJava(): Promise<JavaInstallation[]> {
   return invoke<JavaInstallation[]>("detect_java");
 }
 
+export function discardPendingJavaDownload(
+  majorVersion: number,
+  imageType: string,
+): Promise<void> {
+  return invoke<void>("discard_pending_java_download", {
+    majorVersion,
+    imageType,
+  });
+}
+
 export function downloadAdoptiumJava(
   majorVersion: number,
   imageType: string,
   customPath: string | null,
-): Promise<JavaInstallation> {
-  return invoke<JavaInstallation>("download_adoptium_java", {
+): Promise<JavaDownloadResult> {
+  return invoke<JavaDownloadResult>("download_adoptium_java", {
     majorVersion,
     imageType,
     customPath,
@@ -120,6 +208,14 @@ export function duplicateInstance(
   });
 }
 
+export function explainJavaResolution(
+  instanceId: string,
+): Promise<JavaResolution> {
+  return invoke<JavaResolution>("explain_java_resolution", {
+    instanceId,
+  });
+}
+
 export function exportInstance(
   instanceId: string,
   archivePath: string,
@@ -130,6 +226,12 @@ export function exportInstance(
   });
 }
 
+export function exportSettings(path: string): Promise<void> {
+  return invoke<void>("export_settings", {
+    path,
+  });
+}
+
 export function fetchAdoptiumJava(
   majorVersion: number,
   imageType: string,
@@ -144,8 +246,8 @@ export function fetchAvailableJavaVersions(): Promise<number[]> {
   return invoke<number[]>("fetch_available_java_versions");
 }
 
-export function fetchJavaCatalog(): Promise<JavaCatalog> {
-  return invoke<JavaCatalog>("fetch_java_catalog");
+export function fetchJavaCatalog(): Promise<JavaCatalogResult> {
+  return invoke<JavaCatalogResult>("fetch_java_catalog");
 }
 
 export function getActiveAccount(): Promise<Account | null> {
@@ -160,6 +262,10 @@ export function getConfigPath(): Promise<string> {
   return invoke<string>("get_config_path");
 }
 
+export function getDownloadHistory(): Promise<DownloadHistoryEntry[]> {
+  return invoke<DownloadHistoryEntry[]>("get_download_history");
+}
+
 export function getFabricGameVersions(): Promise<FabricGameVersion[]> {
   return invoke<FabricGameVersion[]>("get_fabric_game_versions");
 }
@@ -198,15 +304,69 @@ export function getInstance(instanceId: string): Promise<Instance> {
   });
 }
 
+export function getInstanceJavaOverride(
+  instanceId: string,
+): Promise<InstanceJavaOverride | null> {
+  return invoke<InstanceJavaOverride | null>("get_instance_java_override", {
+    instanceId,
+  });
+}
+
+export function getJavaSettings(): Promise<JavaSettings> {
+  return invoke<JavaSettings>("get_java_settings");
+}
+
+export function getKnownBadJavaBuilds(): Promise<KnownBadBuild[]> {
+  return invoke<KnownBadBuild[]>("get_known_bad_java_builds");
+}
+
+export function getKnownJavaInstallations(): Promise<KnownInstallations> {
+  return invoke<KnownInstallations>("get_known_java_installations");
+}
+
+export function getLaunchHistory(instanceId: string): Promise<LaunchRecord[]> {
+  return invoke<LaunchRecord[]>("get_launch_history", {
+    instanceId,
+  });
+}
+
+export function getMessageCatalog(locale: string): Promise<HashMap> {
+  return invoke<HashMap>("get_message_catalog", {
+    locale,
+  });
+}
+
+export function getNeoforgeVersionsForGame(
+  gameVersion: string,
+): Promise<ForgeVersion[]> {
+  return invoke<ForgeVersion[]>("get_neoforge_versions_for_game", {
+    gameVersion,
+  });
+}
+
+export function getNetworkConfig(): Promise<NetworkConfig> {
+  return invoke<NetworkConfig>("get_network_config");
+}
+
+export function getNetworkDiagnostics(): Promise<string[]> {
+  return invoke<string[]>("get_network_diagnostics");
+}
+
+export function getNetworkSettings(): Promise<NetworkSettings> {
+  return invoke<NetworkSettings>("get_network_settings");
+}
+
 export function getPendingJavaDownloads(): Promise<PendingJavaDownload[]> {
   return invoke<PendingJavaDownload[]>("get_pending_java_downloads");
 }
 
 export function getRecommendedJava(
   requiredMajorVersion: number | null,
+  preferExact: boolean | null,
 ): Promise<JavaInstallation | null> {
   return invoke<JavaInstallation | null>("get_recommended_java", {
     requiredMajorVersion,
+    preferExact,
   });
 }
 
@@ -244,6 +404,16 @@ export function getVersionsOfInstance(instanceId: string): Promise<Version[]> {
   });
 }
 
+export function importSettings(
+  path: string,
+  options: ImportOptions,
+): Promise<ImportReport> {
+  return invoke<ImportReport>("import_settings", {
+    path,
+    options,
+  });
+}
+
 export function installFabric(
   instanceId: string,
   gameVersion: string,
@@ -268,6 +438,18 @@ export function installForge(
   });
 }
 
+export function installNeoforge(
+  instanceId: string,
+  gameVersion: string,
+  neoforgeVersion: string,
+): Promise<InstalledForgeVersion> {
+  return invoke<InstalledForgeVersion>("install_neoforge", {
+    instanceId,
+    gameVersion,
+    neoforgeVersion,
+  });
+}
+
 export function installVersion(
   instanceId: string,
   versionId: string,
@@ -300,6 +482,10 @@ export function isFabricInstalled(
   });
 }
 
+export function listAccounts(): Promise<Account[]> {
+  return invoke<Account[]>("list_accounts");
+}
+
 export function listInstalledFabricVersions(
   instanceId: string,
 ): Promise<string[]> {
@@ -340,6 +526,10 @@ export function listOpenaiModels(): Promise<ModelInfo[]> {
   return invoke<ModelInfo[]>("list_openai_models");
 }
 
+export function listProfiles(): Promise<Profile[]> {
+  return invoke<Profile[]>("list_profiles");
+}
+
 export function loginOffline(username: string): Promise<Account> {
   return invoke<Account>("login_offline", {
     username,
@@ -360,6 +550,14 @@ export function openFileExplorer(path: string): Promise<void> {
   });
 }
 
+export function pauseDownloads(): Promise<void> {
+  return invoke<void>("pause_downloads");
+}
+
+export function pruneJavaDownloadQueue(): Promise<number> {
+  return invoke<number>("prune_java_download_queue");
+}
+
 export function readRawConfig(): Promise<string> {
   return invoke<string>("read_raw_config");
 }
@@ -368,16 +566,92 @@ export function refreshAccount(): Promise<Account> {
   return invoke<Account>("refresh_account");
 }
 
-export function refreshJavaCatalog(): Promise<JavaCatalog> {
-  return invoke<JavaCatalog>("refresh_java_catalog");
+export function refreshJavaCatalog(): Promise<JavaCatalogResult> {
+  return invoke<JavaCatalogResult>("refresh_java_catalog");
+}
+
+export function reloadJavaConfig(): Promise<JavaConfig> {
+  return invoke<JavaConfig>("reload_java_config");
+}
+
+export function removeAccount(accountId: string): Promise<void> {
+  return invoke<void>("remove_account", {
+    accountId,
+  });
+}
+
+export function removeInstanceJavaOverride(instanceId: string): Promise<void> {
+  return invoke<void>("remove_instance_java_override", {
+    instanceId,
+  });
+}
+
+export function repairGameFiles(
+  instanceId: string,
+  versionId: string,
+): Promise<AssetReport> {
+  return invoke<AssetReport>("repair_game_files", {
+    instanceId,
+    versionId,
+  });
 }
 
 export function repairInstances(): Promise<InstanceRepairResult> {
   return invoke<InstanceRepairResult>("repair_instances");
 }
 
-export function resumeJavaDownloads(): Promise<JavaInstallation[]> {
-  return invoke<JavaInstallation[]>("resume_java_downloads");
+export function rescanJavaInstallations(): Promise<JavaInstallation[]> {
+  return invoke<JavaInstallation[]>("rescan_java_installations");
+}
+
+export function resolveJava(
+  instanceId: string | null,
+  mcVersion: string | null,
+  minMajor: number | null,
+  maxMajor: number | null,
+): Promise<JavaResolution> {
+  return invoke<JavaResolution>("resolve_java", {
+    instanceId,
+    mcVersion,
+    minMajor,
+    maxMajor,
+  });
+}
+
+export function resolveJavaForMinecraft(
+  mcVersion: string,
+): Promise<JavaResolution> {
+  return invoke<JavaResolution>("resolve_java_for_minecraft", {
+    mcVersion,
+  });
+}
+
+export function restoreJavaConfigBackup(): Promise<JavaConfig> {
+  return invoke<JavaConfig>("restore_java_config_backup");
+}
+
+export function resumeDownloads(): Promise<void> {
+  return invoke<void>("resume_downloads");
+}
+
+export function resumeJavaDownloads(): Promise<ResumeJavaDownloadsResult> {
+  return invoke<ResumeJavaDownloadsResult>("resume_java_downloads");
+}
+
+export function retryPendingJavaDownload(
+  majorVersion: number,
+  imageType: string,
+): Promise<JavaDownloadResult> {
+  return invoke<JavaDownloadResult>("retry_pending_java_download", {
+    majorVersion,
+    imageType,
+  });
+}
+
+export function saveNetworkSettings(settings: NetworkSettings): Promise<void> {
+  return invoke<void>("save_network_settings", {
+    settings,
+  });
 }
 
 export function saveRawConfig(content: string): Promise<void> {
@@ -392,38 +666,112 @@ export function saveSettings(config: LauncherConfig): Promise<void> {
   });
 }
 
+export function setActiveAccount(accountId: string): Promise<Account> {
+  return invoke<Account>("set_active_account", {
+    accountId,
+  });
+}
+
 export function setActiveInstance(instanceId: string): Promise<void> {
   return invoke<void>("set_active_instance", {
     instanceId,
   });
 }
 
+export function setAutoResumeDownloads(enabled: boolean): Promise<void> {
+  return invoke<void>("set_auto_resume_downloads", {
+    enabled,
+  });
+}
+
+export function setDownloadSpeedLimit(
+  bytesPerSec: number | null,
+): Promise<void> {
+  return invoke<void>("set_download_speed_limit", {
+    bytesPerSec,
+  });
+}
+
+export function setInstanceJavaOverride(
+  instanceId: string,
+  javaOverride: InstanceJavaOverride,
+): Promise<void> {
+  return invoke<void>("set_instance_java_override", {
+    instanceId,
+    javaOverride,
+  });
+}
+
 export function startGame(
   instanceId: string,
   versionId: string,
-): Promise<string> {
-  return invoke<string>("start_game", {
+  accountId: string | null,
+  quickPlay: QuickPlay | null,
+): Promise<LaunchHandle> {
+  return invoke<LaunchHandle>("start_game", {
     instanceId,
     versionId,
+    accountId,
+    quickPlay,
   });
 }
 
-export function stopGame(): Promise<string> {
-  return invoke<string>("stop_game");
+export function stopGame(instanceId: string): Promise<string> {
+  return invoke<string>("stop_game", {
+    instanceId,
+  });
 }
 
 export function startMicrosoftLogin(): Promise<DeviceCodeResponse> {
   return invoke<DeviceCodeResponse>("start_microsoft_login");
 }
 
+export function switchProfile(name: string): Promise<JavaConfig> {
+  return invoke<JavaConfig>("switch_profile", {
+    name,
+  });
+}
+
+export function testProxy(settings: NetworkSettings): Promise<void> {
+  return invoke<void>("test_proxy", {
+    settings,
+  });
+}
+
 export function updateInstance(instance: Instance): Promise<void> {
   return invoke<void>("update_instance", {
     instance,
   });
 }
 
+export function updateJavaSettings(
+  patch: JavaSettingsPatch,
+): Promise<JavaSettings> {
+  return invoke<JavaSettings>("update_java_settings", {
+    patch,
+  });
+}
+
+export function updateNetworkConfig(
+  network: NetworkConfig,
+): Promise<NetworkConfig> {
+  return invoke<NetworkConfig>("update_network_config", {
+    network,
+  });
+}
+
 export function uploadToPastebin(content: string): Promise<PastebinResponse> {
   return invoke<PastebinResponse>("upload_to_pastebin", {
     content,
   });
 }
+
+export function verifyGameFiles(
+  instanceId: string,
+  versionId: string,
+): Promise<AssetReport> {
+  return invoke<AssetReport>("verify_game_files", {
+    instanceId,
+    versionId,
+  });
+}
@@ -17,12 +17,25 @@ import { Spinner } from "./ui/spinner";
 import { Textarea } from "./ui/textarea";
 
 const launcherConfigSchema: ZodType<LauncherConfig> = z.object({
+  schemaVersion: z.number(),
   minMemory: z.number(),
   maxMemory: z.number(),
   javaPath: z.string(),
   width: z.number(),
   height: z.number(),
   downloadThreads: z.number(),
+  segmentedJavaDownloads: z.boolean(),
+  javaDownloadSegments: z.number(),
+  maxDownloadSpeedBytesPerSec: z.number().nullable(),
+  downloadVerifyRetries: z.number(),
+  downloadMaxAttempts: z.number(),
+  downloadStallTimeoutSecs: z.number(),
+  pendingJavaDownloadMaxAgeDays: z.number(),
+  catalogCacheTtlSecs: z.number().min(60).nullable(),
+  backgroundRefresh: z.boolean(),
+  progressEventIntervalMs: z.number().nullable(),
+  progressEventBytes: z.number().nullable(),
+  urlRewriteRules: z.array(z.tuple([z.string(), z.string()])),
   customBackgroundPath: z.string().nullable(),
   enableGpuAcceleration: z.boolean(),
   enableVisualEffects: z.boolean(),
@@ -33,6 +46,7 @@ const launcherConfigSchema: ZodType<LauncherConfig> = z.object({
   assistant: z.any(), // TODO: AssistantConfig schema
   useSharedCaches: z.boolean(),
   keepLegacyPerInstanceStorage: z.boolean(),
+  keepGameRunningOnExit: z.boolean(),
   featureFlags: z.any(), // TODO: FeatureFlags schema
 });
 
@@ -4,3 +4,13 @@ import { twMerge } from "tailwind-merge";
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
 }
+
+/** Text of an error thrown by a command, which may be a string or a structured error */
+export function errorMessage(error: unknown): string {
+  if (typeof error === "object" && error !== null && "message" in error) {
+    const details =
+      "details" in error && Array.isArray(error.details) ? error.details : [];
+    return [error.message, ...details].map(String).join(": ");
+  }
+  return String(error);
+}
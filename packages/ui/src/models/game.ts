@@ -5,7 +5,10 @@ import {
   startGame as startGameCommand,
   stopGame as stopGameCommand,
 } from "@/client";
-import type { GameExitedEvent } from "@/types/bindings/core";
+import type {
+  GameExitedEvent,
+  LaunchHandle,
+} from "@/types/bindings/game_process";
 
 interface GameState {
   runningInstanceId: string | null;
@@ -15,7 +18,10 @@ interface GameState {
   lifecycleUnlisten: UnlistenFn | null;
 
   isGameRunning: boolean;
-  startGame: (instanceId: string, versionId: string) => Promise<string | null>;
+  startGame: (
+    instanceId: string,
+    versionId: string,
+  ) => Promise<LaunchHandle | null>;
   stopGame: (instanceId?: string | null) => Promise<string | null>;
 }
 
@@ -46,7 +52,15 @@ export const useGameStore = create<GameState>((set, get) => ({
     toast.info(`Preparing to launch ${versionId}...`);
 
     const unlisten = await listen<GameExitedEvent>("game-exited", (event) => {
-      const { instanceId, versionId, wasStopped, exitCode } = event.payload;
+      const {
+        instanceId,
+        versionId,
+        wasStopped,
+        exitCode,
+        kind,
+        crashReportPath,
+        hints,
+      } = event.payload;
 
       set({
         runningInstanceId: null,
@@ -59,9 +73,20 @@ export const useGameStore = create<GameState>((set, get) => ({
         toast.success(
           `Stopped Minecraft ${versionId} for instance ${instanceId}`,
         );
+      } else if (kind === "Clean") {
+        toast.info(`Minecraft ${versionId} closed for instance ${instanceId}`);
       } else {
-        toast.info(
-          `Minecraft ${versionId} exited with code ${exitCode} for instance ${instanceId}`,
+        toast.error(
+          `Minecraft ${versionId} ${kind === "Killed" ? "was killed" : `crashed with code ${exitCode}`} for instance ${instanceId}`,
+          {
+            description: [
+              crashReportPath && `Crash report: ${crashReportPath}`,
+              hints.length > 0 &&
+                `${hints.length} possible cause${hints.length === 1 ? "" : "s"} found, see the launcher log`,
+            ]
+              .filter(Boolean)
+              .join("\n") || undefined,
+          },
         );
       }
     });
@@ -69,14 +94,14 @@ export const useGameStore = create<GameState>((set, get) => ({
     set({ lifecycleUnlisten: unlisten });
 
     try {
-      const message = await startGameCommand(instanceId, versionId);
+      const handle = await startGameCommand(instanceId, versionId, null, null);
       set({
         launchingInstanceId: null,
         runningInstanceId: instanceId,
         runningVersionId: versionId,
       });
-      toast.success(message);
-      return message;
+      toast.success(`Launched Minecraft ${versionId}`);
+      return handle;
     } catch (e) {
       console.error(e);
       set({ launchingInstanceId: null });
@@ -101,7 +126,7 @@ export const useGameStore = create<GameState>((set, get) => ({
     set({ stoppingInstanceId: runningInstanceId });
 
     try {
-      return await stopGameCommand();
+      return await stopGameCommand(runningInstanceId);
     } catch (e) {
       console.error("Failed to stop game:", e);
       toast.error(`Failed to stop game: ${e}`);
@@ -1,5 +1,10 @@
 import { create } from "zustand/react";
-import { detectJava, refreshJavaCatalog } from "@/client";
+import {
+  detectJava,
+  getKnownJavaInstallations,
+  refreshJavaCatalog,
+  rescanJavaInstallations,
+} from "@/client";
 import type { JavaCatalog, JavaInstallation } from "@/types";
 
 export interface JavaState {
@@ -8,9 +13,10 @@ export interface JavaState {
 
   refresh: () => Promise<void>;
   refreshInstallations: () => Promise<void>;
+  rescanInstallations: () => Promise<void>;
 }
 
-export const useJavaStore = create<JavaState>((set) => ({
+export const useJavaStore = create<JavaState>((set, get) => ({
   catalog: null,
   installations: null,
 
@@ -19,7 +25,19 @@ export const useJavaStore = create<JavaState>((set) => ({
     set({ catalog });
   },
   refreshInstallations: async () => {
+    if (!get().installations) {
+      // Show the last scan's results while detection runs
+      const known = await getKnownJavaInstallations();
+      if (known.installations.length > 0) {
+        set({ installations: known.installations });
+      }
+    }
     const installations = await detectJava();
     set({ installations });
   },
+  rescanInstallations: async () => {
+    set({ installations: null });
+    const installations = await rescanJavaInstallations();
+    set({ installations });
+  },
 }));
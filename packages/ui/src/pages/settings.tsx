@@ -31,6 +31,7 @@ import {
 import { Spinner } from "@/components/ui/spinner";
 import { Switch } from "@/components/ui/switch";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
+import { errorMessage } from "@/lib/utils";
 import { useJavaStore } from "@/models/java";
 import { useSettingsStore } from "@/models/settings";
 
@@ -55,7 +56,9 @@ export function SettingsPage() {
         if (!javaStore.catalog) await javaStore.refresh();
       } catch (error) {
         console.error(error);
-        toast.error(`Failed to refresh java catalogs: ${error}`);
+        toast.error(
+          `Failed to refresh java catalogs: ${errorMessage(error)}`,
+        );
       }
     };
     refresh();
@@ -202,7 +205,24 @@ export function SettingsPage() {
                     />
                   </Field>
                   <FieldSet>
-                    <FieldLegend>Java Installations</FieldLegend>
+                    <FieldLegend className="flex w-full items-center justify-between">
+                      Java Installations
+                      <Button
+                        variant="ghost"
+                        size="sm"
+                        disabled={!javaStore.installations}
+                        onClick={() =>
+                          javaStore.rescanInstallations().catch((error) => {
+                            console.error(error);
+                            toast.error(
+                              `Failed to scan for Java: ${errorMessage(error)}`,
+                            );
+                          })
+                        }
+                      >
+                        Rescan
+                      </Button>
+                    </FieldLegend>
                     {javaStore.installations ? (
                       <RadioGroup
                         value={config.javaPath}
@@ -344,6 +364,25 @@ export function SettingsPage() {
                         }}
                       />
                     </Field>
+                    <Field className="flex flex-row items-center justify-between">
+                      <FieldContent>
+                        <FieldLabel htmlFor="keep-game-running-on-exit">
+                          Keep Game Running on Exit
+                        </FieldLabel>
+                        <FieldDescription>
+                          Leave running games open when the launcher closes.
+                        </FieldDescription>
+                      </FieldContent>
+                      <Switch
+                        checked={config?.keepGameRunningOnExit}
+                        onCheckedChange={(checked) => {
+                          settings.merge({
+                            keepGameRunningOnExit: checked,
+                          });
+                          settings.save();
+                        }}
+                      />
+                    </Field>
                   </FieldGroup>
                 </FieldSet>
               </FieldGroup>
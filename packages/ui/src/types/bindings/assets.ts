@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of checking the asset objects of a version on disk
+ */
+export type AssetReport = {
+  indexId: string;
+  total: number;
+  missing: number;
+  /**
+   * Present but not matching their hash
+   */
+  corrupt: number;
+  /**
+   * Objects downloaded again by a repair
+   */
+  repaired: number;
+  /**
+   * The index uses a pre-1.7 layout, which the launcher doesn't build yet
+   */
+  legacyLayout: boolean;
+};
@@ -13,11 +13,12 @@ export type DeviceCodeResponse = {
   message: string | null;
 };
 
+/**
+ * The tokens stay in the backend: they're never sent to the frontend or logged
+ */
 export type MicrosoftAccount = {
   username: string;
   uuid: string;
-  accessToken: string;
-  refreshToken: string | null;
   expiresAt: bigint;
 };
 
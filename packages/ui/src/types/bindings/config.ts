@@ -41,12 +41,25 @@ export type FeatureFlags = {
 };
 
 export type LauncherConfig = {
+  schemaVersion: number;
   minMemory: number;
   maxMemory: number;
   javaPath: string;
   width: number;
   height: number;
   downloadThreads: number;
+  segmentedJavaDownloads: boolean;
+  javaDownloadSegments: number;
+  maxDownloadSpeedBytesPerSec: number | null;
+  downloadVerifyRetries: number;
+  downloadMaxAttempts: number;
+  downloadStallTimeoutSecs: number;
+  pendingJavaDownloadMaxAgeDays: number;
+  catalogCacheTtlSecs: number | null;
+  backgroundRefresh: boolean;
+  progressEventIntervalMs: number | null;
+  progressEventBytes: number | null;
+  urlRewriteRules: Array<[string, string]>;
   customBackgroundPath: string | null;
   enableGpuAcceleration: boolean;
   enableVisualEffects: boolean;
@@ -57,5 +70,6 @@ export type LauncherConfig = {
   assistant: AssistantConfig;
   useSharedCaches: boolean;
   keepLegacyPerInstanceStorage: boolean;
+  keepGameRunningOnExit: boolean;
   featureFlags: FeatureFlags;
 };
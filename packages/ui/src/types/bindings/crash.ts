@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Something the launcher can do about a crash
+ */
+export type CrashAction =
+  | { type: "switchJava"; major: number }
+  | { type: "raiseMemory" }
+  | { type: "removeJvmArg"; arg: string }
+  | { type: "repairGameFiles" };
+
+export type CrashHint = {
+  kind: CrashHintKind;
+  messageKey: string;
+  params: { [key in string]?: string };
+  action: CrashAction | null;
+};
+
+export type CrashHintKind =
+  | "JavaTooOld"
+  | "JavaTooNew"
+  | "OutOfMemory"
+  | "MissingNatives"
+  | "BadJvmOption";
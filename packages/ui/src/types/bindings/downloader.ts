@@ -1,5 +1,102 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
+/**
+ * Overall progress of a batch, emitted as `download-aggregate-progress`
+ */
+export type AggregateProgressEvent = {
+  downloadedBytes: bigint;
+  /**
+   * Sum of the file sizes known so far
+   */
+  totalBytes: bigint;
+  /**
+   * Whether `total_bytes` covers every file in the batch
+   */
+  totalBytesExact: boolean;
+  completedFiles: number;
+  totalFiles: number;
+  speedBytesPerSec: bigint;
+  /**
+   * Only set once the total is exact and there is a measurable speed
+   */
+  etaSeconds: bigint | null;
+};
+
+/**
+ * Outcome of a `download_files` batch, also sent with the `download-complete` event
+ */
+export type DownloadBatchReport = {
+  /**
+   * Tasks that were downloaded (and verified, if they carry a checksum)
+   */
+  succeeded: Array<DownloadSuccess>;
+  /**
+   * Tasks whose file already existed with a matching checksum
+   */
+  skipped: Array<DownloadTask>;
+  /**
+   * Tasks stopped by cancelling the batch; any `.part` file is left in place
+   */
+  paused: Array<DownloadTask>;
+  /**
+   * Tasks cancelled on their own; their partial files are removed
+   */
+  cancelled: Array<DownloadTask>;
+  failed: Array<DownloadFailure>;
+};
+
+/**
+ * Why a download failed
+ */
+export type DownloadError =
+  | { kind: "io"; message: string; details: Array<string> | null }
+  | {
+    kind: "request";
+    url: string;
+    status: number | null;
+    message: string;
+    details: Array<string> | null;
+  }
+  | { kind: "invalid_content"; message: string }
+  | { kind: "checksum_mismatch"; expected: string; actual: string | null }
+  | { kind: "cancelled" }
+  | { kind: "stalled"; timeoutMs: number }
+  | { kind: "size_mismatch"; expected: number; actual: number };
+
+/**
+ * A task that `download_files` gave up on
+ */
+export type DownloadFailure = {
+  task: DownloadTask;
+  error: DownloadError;
+  class: FailureClass;
+};
+
+export type DownloadHistoryEntry = {
+  kind: DownloadHistoryKind;
+  /**
+   * Source URL, for single-file downloads
+   */
+  url: string | null;
+  /**
+   * File name, or a summary such as "42 files" for a batch
+   */
+  file: string;
+  sizeBytes: number;
+  durationMs: number;
+  averageSpeedBytesPerSec: number;
+  outcome: DownloadHistoryOutcome;
+  error: string | null;
+  /**
+   * Unix time the operation ended
+   */
+  finishedAt: number;
+};
+
+export type DownloadHistoryKind = "JavaArchive" | "Batch";
+
+export type DownloadHistoryOutcome = "Completed" | "Failed" | "Cancelled";
+
 /**
  * Metadata for resumable downloads stored in .part.meta file
  */
@@ -10,13 +107,23 @@ export type DownloadMetadata = {
   downloadedBytes: bigint;
   checksum: string | null;
   timestamp: bigint;
+  /**
+   * Whether the server advertised `Accept-Ranges: bytes` for this URL
+   */
+  acceptRanges: boolean;
   segments: Array<DownloadSegment>;
 };
 
 /**
  * Download queue for persistence
  */
-export type DownloadQueue = { pendingDownloads: Array<PendingJavaDownload> };
+export type DownloadQueue = {
+  /**
+   * Schema version; files written before versioning read as 0
+   */
+  version: number;
+  pendingDownloads: Array<PendingJavaDownload>;
+};
 
 /**
  * A download segment for multi-segment parallel downloading
@@ -28,23 +135,57 @@ export type DownloadSegment = {
   completed: boolean;
 };
 
+/**
+ * A task that `download_files` fetched, and the URL it finally came from
+ */
+export type DownloadSuccess = { task: DownloadTask; url: string };
+
 export type DownloadTask = {
   url: string;
   path: string;
   sha1: string | null;
   sha256: string | null;
+  sha512: string | null;
+  /**
+   * Size the file must have, when the manifest lists it
+   */
+  expectedSize: bigint | null;
+  /**
+   * Mirrors tried in order when `url` fails for good
+   */
+  fallbackUrls: Array<string>;
+  /**
+   * Tasks with a higher priority are started first within a batch
+   */
+  priority: number;
 };
 
+/**
+ * What kind of failure stopped a download, which decides how it is retried
+ */
+export type FailureClass = "Permanent" | "Transient" | "RateLimited";
+
 /**
  * Progress event for Java download
  */
 export type JavaDownloadProgress = {
+  /**
+   * Id of the install or resume operation this event belongs to
+   */
+  id: string;
   fileName: string;
   downloadedBytes: bigint;
   totalBytes: bigint;
   speedBytesPerSec: bigint;
   etaSeconds: bigint;
+  /**
+   * One of `JAVA_DOWNLOAD_STATUSES`
+   */
   status: string;
+  /**
+   * Translatable form of `status`, e.g. `java.download.extracting`
+   */
+  messageKey: string;
   percentage: number;
 };
 
@@ -60,13 +201,31 @@ export type PendingJavaDownload = {
   checksum: string | null;
   installPath: string;
   createdAt: bigint;
+  /**
+   * Failed resume attempts so far
+   */
+  attempts: number;
+  lastError: string | null;
+  /**
+   * Unix time of the last failed attempt, 0 if none
+   */
+  lastAttemptAt: bigint;
 };
 
 export type ProgressEvent = {
+  taskId: string;
   file: string;
   downloaded: bigint;
   total: bigint;
+  /**
+   * One of `DOWNLOAD_STATUSES`
+   */
   status: string;
+  /**
+   * Translatable form of `status`, e.g. `download.retrying`
+   */
+  messageKey: string;
+  attempt: number;
   completedFiles: number;
   totalFiles: number;
   totalDownloadedBytes: bigint;
@@ -19,3 +19,26 @@ export type InstalledForgeVersion = {
   forgeVersion: string;
   path: string;
 };
+
+/**
+ * Progress of the processors, sent as `forge-install-progress`
+ */
+export type ProcessorProgress = {
+  /**
+   * 1-based
+   */
+  step: number;
+  total: number;
+  /**
+   * Maven coordinate of the processor jar
+   */
+  processor: string;
+  /**
+   * What the processor does, for installertools
+   */
+  task: string | null;
+  /**
+   * Its outputs were already there and intact
+   */
+  skipped: boolean;
+};
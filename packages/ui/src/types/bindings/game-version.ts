@@ -25,6 +25,13 @@ export type Downloads = {
   server: DownloadArtifact | null;
 };
 
+export type ExtractRules = {
+  /**
+   * Entry name prefixes to skip, e.g. `META-INF/`
+   */
+  exclude: Array<string>;
+};
+
 /**
  * Represents a Minecraft version JSON, supporting both vanilla and modded (Fabric/Forge) formats.
  * Modded versions use `inheritsFrom` to reference a parent vanilla version.
@@ -69,6 +76,10 @@ export type Library = {
    * Maven repository URL for mod loader libraries
    */
   url: string | null;
+  /**
+   * What to leave out when extracting the native jar
+   */
+  extract?: ExtractRules | null;
 };
 
 export type LibraryDownloads = {
@@ -0,0 +1,52 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CrashHint } from "./crash";
+
+/**
+ * How a game process ended
+ */
+export type ExitKind = "Clean" | "Crashed" | "Killed";
+
+/**
+ * Sent as `game-exited` once a game process has ended
+ */
+export type GameExitedEvent = {
+  instanceId: string;
+  versionId: string;
+  exitCode: number | null;
+  kind: ExitKind;
+  /**
+   * Stopped from the launcher rather than exiting by itself
+   */
+  wasStopped: boolean;
+  durationSecs: number;
+  /**
+   * Crash report the game wrote during this launch
+   */
+  crashReportPath: string | null;
+  /**
+   * Likely causes of a crash, empty for clean or stopped exits
+   */
+  hints: Array<CrashHint>;
+};
+
+/**
+ * One line of game output, sent in batches as `game-log`
+ */
+export type GameLogEvent = {
+  instanceId: string;
+  stream: LogStream;
+  line: string;
+};
+
+/**
+ * What `start_game` returns once the game process is running
+ */
+export type LaunchHandle = {
+  instanceId: string;
+  /**
+   * `None` if the process exited before its id could be read
+   */
+  pid: number | null;
+};
+
+export type LogStream = "stdout" | "stderr";
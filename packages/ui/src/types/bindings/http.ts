@@ -0,0 +1,62 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Everything network-related in one place: the HTTP client settings plus the download
+ * options kept in the launcher config
+ */
+export type NetworkConfig = {
+  /**
+   * Retry a download that receives nothing for this long
+   */
+  readTimeoutSecs: number;
+  /**
+   * Files downloaded at once (1-128)
+   */
+  downloadConcurrency: number;
+  /**
+   * `None` = unlimited
+   */
+  maxDownloadSpeedBytesPerSec: number | null;
+  proxy: ProxyConfig | null;
+  /**
+   * PEM files with additional trusted root certificates, e.g. a corporate proxy's CA
+   */
+  extraCaCertificates: Array<string>;
+  /**
+   * Skip TLS certificate verification entirely. Insecure; only for broken MITM proxies
+   */
+  dangerAcceptInvalidCerts: boolean;
+  /**
+   * How long to wait for a connection to be established; `None` uses the default
+   */
+  connectTimeoutSecs: number | null;
+};
+
+/**
+ * Network settings applied to every HTTP client the launcher creates
+ */
+export type NetworkSettings = {
+  proxy: ProxyConfig | null;
+  /**
+   * PEM files with additional trusted root certificates, e.g. a corporate proxy's CA
+   */
+  extraCaCertificates: Array<string>;
+  /**
+   * Skip TLS certificate verification entirely. Insecure; only for broken MITM proxies
+   */
+  dangerAcceptInvalidCerts: boolean;
+  /**
+   * How long to wait for a connection to be established; `None` uses the default
+   */
+  connectTimeoutSecs: number | null;
+};
+
+export type ProxyConfig = {
+  scheme: ProxyScheme;
+  host: string;
+  port: number;
+  username: string | null;
+  password: string | null;
+};
+
+export type ProxyScheme = "http" | "https" | "socks5";
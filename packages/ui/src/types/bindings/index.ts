@@ -1,12 +1,19 @@
 export * from "./account";
+export * from "./assets";
 export * from "./assistant";
 export * from "./auth";
 export * from "./config";
 export * from "./core";
+export * from "./crash";
 export * from "./downloader";
 export * from "./fabric";
 export * from "./forge";
 export * from "./game-version";
+export * from "./game_process";
+export * from "./http";
 export * from "./instance";
 export * from "./java";
+export * from "./launch_history";
 export * from "./manifest";
+export * from "./profiles";
+export * from "./quick_play";
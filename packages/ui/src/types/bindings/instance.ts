@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { QuickPlay } from "./quick_play";
 
 /**
  * Represents a game instance/profile
@@ -17,6 +18,10 @@ export type Instance = {
   jvmArgsOverride: string | null;
   memoryOverride: MemoryOverride | null;
   javaPathOverride: string | null;
+  quickPlay: QuickPlay | null;
+  preLaunchCommand: string | null;
+  postExitCommand: string | null;
+  useShell: boolean;
 };
 
 /**
@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JavaVendor } from "./core";
+
+/**
+ * A build known to break Minecraft. Listing its `id` in the settings'
+ * `allowed_known_bad_builds` lets it be used anyway.
+ */
+export type KnownBadBuild = {
+  id: string;
+  /**
+   * `None` for builds of every vendor
+   */
+  vendor: JavaVendor | null;
+  /**
+   * Oldest affected version, inclusive; `None` for no lower bound
+   */
+  minVersion: string | null;
+  /**
+   * Newest affected version, inclusive; `None` for no upper bound
+   */
+  maxVersion: string | null;
+  /**
+   * Only 32-bit builds are affected
+   */
+  only32bit: boolean;
+  reason: string;
+};
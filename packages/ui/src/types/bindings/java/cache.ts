@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CacheKind =
+  | "Catalog"
+  | "Archives"
+  | "Validation"
+  | "DetectionResults"
+  | "All";
+
+export type ClearCachesReport = {
+  removedFiles: number;
+  freedBytes: number;
+  /**
+   * Files left alone because a download is using them or they couldn't be removed
+   */
+  skippedFiles: Array<string>;
+};
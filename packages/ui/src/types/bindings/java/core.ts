@@ -1,10 +1,49 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PendingJavaDownload } from "../downloader";
+import type { JavaErrorKind } from "./error";
+
+export type ImageType = "jre" | "jdk";
 
 export type JavaCatalog = {
   releases: Array<JavaReleaseInfo>;
   availableMajorVersions: Array<number>;
   ltsVersions: Array<number>;
   cachedAt: bigint;
+  /**
+   * `ETag` of the release list response, used to revalidate the cache
+   */
+  etag: string | null;
+  /**
+   * `Last-Modified` of the release list response, used to revalidate the cache
+   */
+  lastModified: string | null;
+};
+
+/**
+ * A catalog as returned to the UI, with its age so it can show when it was updated
+ */
+export type JavaCatalogResult = {
+  ageSecs: number;
+  /**
+   * The cache had expired; a fresh catalog follows in `java-catalog-refreshed`
+   */
+  stale: boolean;
+  /**
+   * The network was unreachable, so an expired cache was served as is
+   */
+  offline: boolean;
+  releases: Array<JavaReleaseInfo>;
+  availableMajorVersions: Array<number>;
+  ltsVersions: Array<number>;
+  cachedAt: bigint;
+  /**
+   * `ETag` of the release list response, used to revalidate the cache
+   */
+  etag: string | null;
+  /**
+   * `Last-Modified` of the release list response, used to revalidate the cache
+   */
+  lastModified: string | null;
 };
 
 export type JavaDownloadInfo = {
@@ -17,6 +56,11 @@ export type JavaDownloadInfo = {
   image_type: string;
 };
 
+/**
+ * A finished install along with the id its progress events carried
+ */
+export type JavaDownloadResult = { id: string; installation: JavaInstallation };
+
 export type JavaInstallation = {
   path: string;
   version: string;
@@ -24,6 +68,14 @@ export type JavaInstallation = {
   vendor: string;
   source: string;
   is64bit: boolean;
+  /**
+   * Built for the machine's architecture rather than run under emulation
+   */
+  isNativeArch: boolean;
+  /**
+   * Why launches won't use it; only filled in on lists for display, see `blocklist`
+   */
+  blockedReason: string | null;
 };
 
 export type JavaReleaseInfo = {
@@ -39,3 +91,50 @@ export type JavaReleaseInfo = {
   isAvailable: boolean;
   architecture: string;
 };
+
+/**
+ * Distribution an installation comes from, as far as it can be told from the vendor
+ * name detection reports
+ */
+export type JavaVendor =
+  | "Temurin"
+  | "Corretto"
+  | "Dragonwell"
+  | "GraalVm"
+  | "Oracle"
+  | "Kona"
+  | "Liberica"
+  | "Mandrel"
+  | "Microsoft"
+  | "SapMachine"
+  | "Semeru"
+  | "Zulu"
+  | "Trava"
+  | "BiSheng"
+  | "OpenJdk";
+
+export type ResumeJavaDownloadFailure = {
+  /**
+   * The queue entry, including its attempt count and last error
+   */
+  download: PendingJavaDownload;
+  reason: ResumeJavaDownloadFailureReason;
+  /**
+   * Kind of the error this attempt failed with; `None` when it was not tried
+   */
+  errorKind: JavaErrorKind | null;
+};
+
+export type ResumeJavaDownloadFailureReason =
+  | "Failed"
+  | "GivenUp"
+  | "Cancelled";
+
+export type ResumeJavaDownloadsResult = {
+  /**
+   * Id carried by the progress events of this resume
+   */
+  id: string;
+  installed: Array<JavaInstallation>;
+  failed: Array<ResumeJavaDownloadFailure>;
+};
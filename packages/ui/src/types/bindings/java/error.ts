@@ -0,0 +1,51 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Which `JavaError` variant a payload came from, for the frontend to branch on
+ */
+export type JavaErrorKind =
+  | "not_found"
+  | "invalid_version"
+  | "verification_failed"
+  | "network_error"
+  | "io_error"
+  | "timeout"
+  | "serialization_error"
+  | "invalid_config"
+  | "download_failed"
+  | "extraction_failed"
+  | "checksum_mismatch"
+  | "cancelled"
+  | "insufficient_disk_space"
+  | "permission_denied"
+  | "file_locked"
+  | "read_only_filesystem"
+  | "data_dir_unavailable"
+  | "strict_resolution_failed"
+  | "no_compatible_java"
+  | "other";
+
+/**
+ * A `JavaError` as sent to the frontend
+ */
+export type JavaErrorPayload = {
+  kind: JavaErrorKind;
+  /**
+   * See `JavaError::code`
+   */
+  code: string;
+  /**
+   * English text, for logs and as a fallback
+   */
+  message: string;
+  messageKey: string;
+  /**
+   * The variant's fields: `detail`, `path` and `reason` for strict resolution, or
+   * `required` and `max` when no Java fits
+   */
+  params: { [key in string]?: string };
+  /**
+   * Messages of the errors that caused this one, outermost first
+   */
+  details: Array<string>;
+};
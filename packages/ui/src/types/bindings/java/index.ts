@@ -1,3 +1,10 @@
+export * from "./blocklist";
+export * from "./cache";
 export * from "./core";
+export * from "./error";
+export * from "./known";
 export * from "./persistence";
+export * from "./priority";
 export * from "./providers";
+export * from "./requirements";
+export * from "./transfer";
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JavaInstallation } from "./core";
+
+/**
+ * A remembered installation and what its binary looked like when it was checked
+ */
+export type KnownInstallation = {
+  installation: JavaInstallation;
+  canonicalPath: string;
+  /**
+   * Modification time of the binary in seconds since the epoch
+   */
+  binaryMtime: bigint;
+};
+
+/**
+ * Installations for the UI, with whether they were verified in this session
+ */
+export type KnownInstallations = {
+  installations: Array<JavaInstallation>;
+  verified: boolean;
+};
@@ -1,7 +1,168 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NetworkSettings } from "../http";
+import type { ImageType, JavaVendor } from "./core";
+import type { KnownInstallation } from "./known";
+
+export type DetectionStrategy = "auto" | "manual";
+
+/**
+ * Java an instance launches with instead of the global choice, e.g. a modpack that
+ * needs Java 8 at a specific path
+ */
+export type InstanceJavaOverride = {
+  /**
+   * Java executable to use
+   */
+  path: string | null;
+  /**
+   * Lowest major version allowed, instead of the game's requirement
+   */
+  min_version: number | null;
+  /**
+   * Highest major version allowed
+   */
+  max_version: number | null;
+  /**
+   * Extra JVM arguments, separated by whitespace
+   */
+  jvm_args: string | null;
+  /**
+   * Fail the launch instead of falling back when the configured Java can't be
+   * used; `None` follows `JavaSettings::strict_resolution`
+   */
+  strict: boolean | null;
+};
 
 export type JavaConfig = {
+  /**
+   * Files from before versioning have none and count as version 1
+   */
+  schema_version: number;
   user_defined_paths: Array<string>;
   preferred_java_path: string | null;
   last_detection_time: bigint;
+  network: NetworkSettings;
+  settings: JavaSettings;
+  /**
+   * Java settings of individual instances, by instance id
+   */
+  instance_java_overrides: { [key in string]?: InstanceJavaOverride };
+  /**
+   * Result of the last full scan, shown before detection runs on startup
+   */
+  known_installations: Array<KnownInstallation>;
+  /**
+   * Java each instance last launched with, by instance id
+   */
+  last_used_java: { [key in string]?: LastUsedJava };
+};
+
+/**
+ * Launcher-wide Java preferences
+ */
+export type JavaSettings = {
+  /**
+   * Provider catalogs and downloads come from, one of `providers::PROVIDER_NAMES`
+   */
+  provider: string;
+  defaultImageType: ImageType;
+  /**
+   * Where runtimes are installed; `None` uses `java` in the app data directory
+   */
+  installDir: string | null;
+  /**
+   * Resume queued Java downloads shortly after startup
+   */
+  autoResumeDownloads: boolean;
+  /**
+   * Offer early-access builds alongside general availability releases
+   */
+  includeEa: boolean;
+  detectionStrategy: DetectionStrategy;
+  /**
+   * Automatic detection reuses the last scan's results for this long
+   */
+  minRedetectIntervalSecs: number;
+  /**
+   * Sources launch resolution tries, first to last; ones left out are skipped
+   */
+  resolutionOrder: Array<JavaSourcePriority>;
+  /**
+   * Download the required Java when a launch finds no compatible one
+   */
+  autoInstallMissingJava: boolean;
+  /**
+   * Vendors chosen over others among compatible installations, most wanted first
+   */
+  preferredVendors: Array<JavaVendor>;
+  /**
+   * Vendors never chosen
+   */
+  blockedVendors: Array<JavaVendor>;
+  /**
+   * When a Java was picked explicitly but can't be used, fail the launch instead of
+   * falling back to detected ones
+   */
+  strictResolution: boolean;
+  /**
+   * Java executables never used
+   */
+  blockedJavaPaths: Array<string>;
+  /**
+   * Java versions never used, e.g. `17.0.1`; `17` blocks all of Java 17
+   */
+  blockedVersions: Array<string>;
+  /**
+   * Ids of `blocklist::KNOWN_BAD_BUILDS` entries to use anyway
+   */
+  allowedKnownBadBuilds: Array<string>;
+};
+
+/**
+ * Changes to `JavaSettings`; fields left out are kept as they are, so pages editing
+ * different settings don't overwrite each other
+ */
+export type JavaSettingsPatch = {
+  provider: string | null;
+  defaultImageType: ImageType | null;
+  /**
+   * An empty path goes back to the default directory
+   */
+  installDir: string | null;
+  autoResumeDownloads: boolean | null;
+  includeEa: boolean | null;
+  detectionStrategy: DetectionStrategy | null;
+  minRedetectIntervalSecs: number | null;
+  resolutionOrder: Array<JavaSourcePriority> | null;
+  autoInstallMissingJava: boolean | null;
+  preferredVendors: Array<JavaVendor> | null;
+  blockedVendors: Array<JavaVendor> | null;
+  strictResolution: boolean | null;
+  blockedJavaPaths: Array<string> | null;
+  blockedVersions: Array<string> | null;
+  allowedKnownBadBuilds: Array<string> | null;
+};
+
+/**
+ * A place launch resolution looks for Java, see `JavaSettings::resolution_order`
+ */
+export type JavaSourcePriority =
+  | "InstanceOverride"
+  | "GlobalOverride"
+  | "LastUsed"
+  | "Preferred"
+  | "UserDefined"
+  | "Managed"
+  | "System";
+
+/**
+ * Java an instance last launched successfully with, tried again on its next launch
+ */
+export type LastUsedJava = {
+  java_path: string;
+  java_version: string;
+  /**
+   * Unix time of the launch, in seconds
+   */
+  used_at: bigint;
 };
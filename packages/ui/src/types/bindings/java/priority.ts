@@ -0,0 +1,56 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JavaInstallation } from "./core";
+import type { JavaSourcePriority } from "./persistence";
+
+/**
+ * The Java chosen for a launch and how it was found
+ */
+export type JavaResolution = {
+  selected: JavaInstallation | null;
+  steps: Array<ResolutionStep>;
+  /**
+   * Steps were left out to stay within `MAX_RESOLUTION_STEPS`
+   */
+  truncated: boolean;
+  /**
+   * The rejected explicitly configured Java that stopped a strict resolution
+   */
+  strictFailure: ResolutionStep | null;
+  /**
+   * Shortcomings of the selected Java, e.g. being 32-bit, that nothing better
+   * avoided
+   */
+  warnings: Array<string>;
+};
+
+/**
+ * Sent as `launch-java-install` while a launch installs the Java it needs, so the UI
+ * can show it and cancel the download
+ */
+export type LaunchJavaInstall = {
+  instanceId: string;
+  majorVersion: number;
+  downloadId: string;
+};
+
+/**
+ * What resolution made of one candidate, or of a source without any
+ */
+export type ResolutionOutcome =
+  | { kind: "accepted" }
+  | {
+    kind: "rejectedVersion";
+    found: number;
+    required: number | null;
+    max: number | null;
+  }
+  | { kind: "rejectedInvalid"; reason: string }
+  | { kind: "rejectedVendor"; vendor: string }
+  | { kind: "rejectedBlocked"; reason: string }
+  | { kind: "skipped" };
+
+export type ResolutionStep = {
+  source: JavaSourcePriority;
+  candidatePath: string | null;
+  outcome: ResolutionOutcome;
+};
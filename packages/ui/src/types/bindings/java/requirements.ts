@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Java major versions a Minecraft version runs on
+ */
+export type VersionRequirement = {
+  minMajor: number;
+  /**
+   * Newest major known to work; `None` if newer ones are fine
+   */
+  recommendedMaxMajor: number | null;
+};
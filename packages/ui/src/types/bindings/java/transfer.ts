@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ImportMode = "Merge" | "Replace";
+
+export type ImportOptions = {
+  mode: ImportMode;
+  /**
+   * Leave out Java paths that don't hold a working Java here, and other paths that
+   * don't exist
+   */
+  skipMissingPaths: boolean;
+};
+
+export type ImportReport = { applied: Array<string>; skipped: Array<string> };
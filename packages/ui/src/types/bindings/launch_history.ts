@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExitKind } from "./game_process";
+import type { JavaInstallation } from "./java/core";
+
+export type LaunchRecord = {
+  /**
+   * Unix time the game exited
+   */
+  finishedAt: number;
+  versionId: string;
+  exitCode: number | null;
+  kind: ExitKind;
+  durationSecs: number;
+  /**
+   * Java the game ran with
+   */
+  java: JavaInstallation;
+  crashReportPath: string | null;
+};
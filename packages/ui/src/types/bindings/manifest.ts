@@ -6,6 +6,10 @@ export type Version = {
   id: string;
   type: string;
   url: string;
+  /**
+   * SHA-1 of the version JSON at `url`
+   */
+  sha1?: string | null;
   time: string;
   releaseTime: string;
   /**
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where the game goes once it has started
+ */
+export type QuickPlay =
+  | { type: "singleplayer"; world: string }
+  | { type: "multiplayer"; address: string }
+  | { type: "realms"; id: string };
@@ -1,4 +1,4 @@
-use crate::core::auth::{Account, MicrosoftAccount, OfflineAccount};
+use crate::core::auth::{self, Account, MicrosoftAccount, OfflineAccount};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -21,7 +21,7 @@ pub enum StoredAccount {
 }
 
 /// Microsoft account with refresh token for persistence
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Clone, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "account.ts")]
 pub struct StoredMicrosoftAccount {
     pub username: String,
@@ -32,6 +32,17 @@ pub struct StoredMicrosoftAccount {
     pub expires_at: i64,
 }
 
+impl std::fmt::Debug for StoredMicrosoftAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoredMicrosoftAccount")
+            .field("username", &self.username)
+            .field("uuid", &self.uuid)
+            .field("access_token", &"<redacted>")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
 impl StoredAccount {
     pub fn id(&self) -> String {
         match self {
@@ -95,7 +106,15 @@ impl AccountStorage {
         if let Some(parent) = self.file_path.parent() {
             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
         }
-        fs::write(&self.file_path, content).map_err(|e| e.to_string())?;
+        crate::core::java::persistence::write_file_atomic(&self.file_path, content.as_bytes())
+            .map_err(|e| e.to_string())?;
+        // The file holds login tokens, so only the user may read it
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.file_path, fs::Permissions::from_mode(0o600))
+                .map_err(|e| e.to_string())?;
+        }
         Ok(())
     }
 
@@ -116,6 +135,25 @@ impl AccountStorage {
         self.save(&store)
     }
 
+    /// Replace a saved account, e.g. after its tokens were refreshed, without
+    /// changing which account is active
+    pub fn update_account(
+        &self,
+        account: &Account,
+        ms_refresh_token: Option<String>,
+    ) -> Result<(), String> {
+        let mut store = self.load();
+        let stored = StoredAccount::from_account(account, ms_refresh_token);
+        let id = stored.id();
+        let slot = store
+            .accounts
+            .iter_mut()
+            .find(|a| a.id() == id)
+            .ok_or("Account not found")?;
+        *slot = stored;
+        self.save(&store)
+    }
+
     pub fn remove_account(&self, uuid: &str) -> Result<(), String> {
         let mut store = self.load();
         store.accounts.retain(|a| a.id() != uuid);
@@ -144,7 +182,6 @@ impl AccountStorage {
         }
     }
 
-    #[allow(dead_code)]
     pub fn set_active_account(&self, uuid: &str) -> Result<(), String> {
         let mut store = self.load();
         if store.accounts.iter().any(|a| a.id() == uuid) {
@@ -155,8 +192,27 @@ impl AccountStorage {
         }
     }
 
-    #[allow(dead_code)]
     pub fn get_all_accounts(&self) -> Vec<StoredAccount> {
         self.load().accounts
     }
+
+    pub fn get_account(&self, id: &str) -> Option<StoredAccount> {
+        self.load().accounts.into_iter().find(|a| a.id() == id)
+    }
+
+    /// The offline account for `username`. One saved before offline UUIDs matched
+    /// vanilla keeps its UUID, so its players don't lose their progress.
+    pub fn offline_account(&self, username: &str) -> OfflineAccount {
+        self.load()
+            .accounts
+            .into_iter()
+            .find_map(|a| match a {
+                StoredAccount::Offline(a) if a.username == username => Some(a),
+                _ => None,
+            })
+            .unwrap_or_else(|| OfflineAccount {
+                username: username.to_string(),
+                uuid: auth::generate_offline_uuid(username),
+            })
+    }
 }
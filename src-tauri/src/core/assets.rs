@@ -0,0 +1,311 @@
+//! Asset index and asset objects (sounds, languages, icons) of a version.
+//!
+//! Objects live under `assets/objects/<first two hash chars>/<hash>`. Versions before
+//! 1.7 expect them copied into a `virtual` or `resources` layout instead, which isn't
+//! supported yet; such indexes are detected and reported so the user knows why sounds
+//! may be missing.
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::Window;
+use ts_rs::TS;
+
+use crate::core::config::LauncherConfig;
+use crate::core::downloader::{self, DownloadBatchReport, DownloadTask, PRIORITY_ASSET};
+use crate::core::game_version::{AssetIndex, GameVersion};
+use crate::utils::error;
+
+const RESOURCES_URL: &str = "https://resources.download.minecraft.net";
+
+/// How many objects are hashed at once by [`verify_assets`]
+const VERIFY_CONCURRENCY: usize = 16;
+
+#[derive(Debug, Deserialize)]
+pub struct AssetIndexJson {
+    pub objects: HashMap<String, AssetObject>,
+    /// Pre-1.7: objects are copied to `assets/virtual/<index id>`
+    #[serde(default, rename = "virtual")]
+    pub is_virtual: bool,
+    /// Pre-1.6: objects are copied to `<game dir>/resources`
+    #[serde(default)]
+    pub map_to_resources: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssetObject {
+    pub hash: String,
+    pub size: u64,
+}
+
+impl AssetIndexJson {
+    /// Whether this index needs one of the pre-1.7 layouts
+    pub fn is_legacy(&self) -> bool {
+        self.is_virtual || self.map_to_resources
+    }
+}
+
+/// Result of checking the asset objects of a version on disk
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "assets.ts")]
+pub struct AssetReport {
+    pub index_id: String,
+    pub total: usize,
+    pub missing: usize,
+    /// Present but not matching their hash
+    pub corrupt: usize,
+    /// Objects downloaded again by a repair
+    pub repaired: usize,
+    /// The index uses a pre-1.7 layout, which the launcher doesn't build yet
+    pub legacy_layout: bool,
+}
+
+impl AssetReport {
+    pub fn needs_download(&self) -> usize {
+        self.missing + self.corrupt
+    }
+}
+
+/// Read the asset index from `indexes_dir`, downloading it when missing or when it no
+/// longer matches the SHA-1 listed in the version JSON
+pub async fn load_asset_index(
+    index: &AssetIndex,
+    indexes_dir: &Path,
+) -> Result<AssetIndexJson, String> {
+    let path = indexes_dir.join(format!("{}.json", index.id));
+    let valid = downloader::verify_file_checksum_async(&path, None, None, Some(&index.sha1))
+        .await
+        .unwrap_or(false);
+
+    let content = if valid {
+        tokio::fs::read(&path).await.map_err(|e| e.to_string())?
+    } else {
+        let content = crate::core::http::client()
+            .get(&index.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| error::display_chain(&e))?
+            .bytes()
+            .await
+            .map_err(|e| error::display_chain(&e))?;
+        let actual = downloader::compute_sha1(&content);
+        if !actual.eq_ignore_ascii_case(&index.sha1) {
+            return Err(format!(
+                "Asset index {} failed verification: expected SHA-1 {}, got {}",
+                index.id, index.sha1, actual
+            ));
+        }
+        tokio::fs::create_dir_all(indexes_dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::fs::write(&path, &content)
+            .await
+            .map_err(|e| e.to_string())?;
+        content.to_vec()
+    };
+
+    serde_json::from_slice(&content)
+        .map_err(|e| format!("Failed to parse asset index {}: {}", index.id, e))
+}
+
+fn object_task(object: &AssetObject, objects_dir: &Path) -> DownloadTask {
+    let prefix = &object.hash[..2.min(object.hash.len())];
+    DownloadTask {
+        url: format!("{}/{}/{}", RESOURCES_URL, prefix, object.hash),
+        path: objects_dir.join(prefix).join(&object.hash),
+        sha1: Some(object.hash.clone()),
+        sha256: None,
+        sha512: None,
+        expected_size: Some(object.size),
+        fallback_urls: Vec::new(),
+        priority: PRIORITY_ASSET,
+    }
+}
+
+/// A task for every object in `index`; `download_files` skips the ones already valid
+pub fn asset_tasks(index: &AssetIndexJson, objects_dir: &Path) -> Vec<DownloadTask> {
+    index
+        .objects
+        .values()
+        .map(|object| object_task(object, objects_dir))
+        .collect()
+}
+
+/// Hash the objects already on disk without downloading anything.
+///
+/// Returns the report and the tasks for the objects that are missing or corrupt.
+pub async fn verify_assets(
+    index_id: &str,
+    index: &AssetIndexJson,
+    objects_dir: &Path,
+) -> (AssetReport, Vec<DownloadTask>) {
+    let checks: Vec<(ObjectState, DownloadTask)> =
+        futures::stream::iter(asset_tasks(index, objects_dir))
+            .map(check_object)
+            .buffer_unordered(VERIFY_CONCURRENCY)
+            .collect()
+            .await;
+
+    let mut report = AssetReport {
+        index_id: index_id.to_string(),
+        total: checks.len(),
+        legacy_layout: index.is_legacy(),
+        ..AssetReport::default()
+    };
+    let mut broken = Vec::new();
+    for (state, task) in checks {
+        match state {
+            ObjectState::Valid => continue,
+            ObjectState::Missing => report.missing += 1,
+            ObjectState::Corrupt => report.corrupt += 1,
+        }
+        broken.push(task);
+    }
+    (report, broken)
+}
+
+/// Load the asset index of `version` and hash its objects under `assets_dir`
+pub async fn check_assets(
+    version: &GameVersion,
+    assets_dir: &Path,
+) -> Result<(AssetReport, Vec<DownloadTask>), String> {
+    let asset_index = version
+        .asset_index
+        .as_ref()
+        .ok_or("Version has no asset index information")?;
+    let index = load_asset_index(asset_index, &assets_dir.join("indexes")).await?;
+    Ok(verify_assets(&asset_index.id, &index, &assets_dir.join("objects")).await)
+}
+
+async fn check_object(task: DownloadTask) -> (ObjectState, DownloadTask) {
+    let state = match tokio::fs::metadata(&task.path).await {
+        Err(_) => ObjectState::Missing,
+        Ok(meta) if Some(meta.len()) != task.expected_size => ObjectState::Corrupt,
+        Ok(_) => {
+            match downloader::verify_file_checksum_async(
+                &task.path,
+                None,
+                None,
+                task.sha1.as_deref(),
+            )
+            .await
+            {
+                Ok(true) => ObjectState::Valid,
+                _ => ObjectState::Corrupt,
+            }
+        }
+    };
+    (state, task)
+}
+
+enum ObjectState {
+    Valid,
+    Missing,
+    Corrupt,
+}
+
+/// Download the asset index of `version` and every missing or corrupt object, for
+/// repairing an installation. Objects are hashed first so only broken ones are queued.
+pub async fn prepare_assets(
+    window: Window,
+    version: &GameVersion,
+    assets_dir: &Path,
+    config: &LauncherConfig,
+) -> Result<(AssetReport, DownloadBatchReport), String> {
+    let (mut report, tasks) = check_assets(version, assets_dir).await?;
+    let batch = downloader::download_files(
+        window,
+        tasks,
+        config.download_threads as usize,
+        config.download_verify_retries,
+        downloader::RetryPolicy::with_attempts(config.download_max_attempts).with_stall_timeout(
+            std::time::Duration::from_secs(config.download_stall_timeout_secs.into()),
+        ),
+    )
+    .await
+    .map_err(|e| error::display_chain(&e))?;
+    report.repaired = batch.succeeded.len();
+    Ok((report, batch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[tokio::test]
+    async fn verify_counts_missing_and_corrupt_objects() {
+        let valid = b"valid object".to_vec();
+        let corrupt = b"corrupt object".to_vec();
+        let missing = b"missing object".to_vec();
+        let object = |body: &[u8]| AssetObject {
+            hash: downloader::compute_sha1(body),
+            size: body.len() as u64,
+        };
+        let index = AssetIndexJson {
+            objects: HashMap::from([
+                ("minecraft/lang/en_us.json".to_string(), object(&valid)),
+                ("minecraft/sounds/a.ogg".to_string(), object(&corrupt)),
+                ("minecraft/sounds/b.ogg".to_string(), object(&missing)),
+            ]),
+            is_virtual: false,
+            map_to_resources: false,
+        };
+
+        let dir = TestDir::new("assets");
+        for (body, written) in [
+            (&valid, valid.clone()),
+            (&corrupt, b"corrupt objecT".to_vec()),
+        ] {
+            let task = object_task(&object(body), dir.path());
+            std::fs::create_dir_all(task.path.parent().unwrap()).unwrap();
+            std::fs::write(&task.path, written).unwrap();
+        }
+
+        let (report, broken) = verify_assets("17", &index, dir.path()).await;
+        assert_eq!(report.total, 3);
+        assert_eq!(report.missing, 1);
+        assert_eq!(report.corrupt, 1);
+        assert_eq!(report.needs_download(), 2);
+        assert!(!report.legacy_layout);
+        let mut broken: Vec<_> = broken.into_iter().map(|task| task.sha1.unwrap()).collect();
+        broken.sort();
+        let mut expected = vec![
+            downloader::compute_sha1(&corrupt),
+            downloader::compute_sha1(&missing),
+        ];
+        expected.sort();
+        assert_eq!(broken, expected);
+    }
+
+    #[test]
+    fn pre_1_7_indexes_are_flagged_as_legacy() {
+        let modern: AssetIndexJson = serde_json::from_str(r#"{"objects": {}}"#).unwrap();
+        assert!(!modern.is_legacy());
+        let virtual_index: AssetIndexJson =
+            serde_json::from_str(r#"{"virtual": true, "objects": {}}"#).unwrap();
+        assert!(virtual_index.is_legacy());
+        let resources: AssetIndexJson =
+            serde_json::from_str(r#"{"map_to_resources": true, "objects": {}}"#).unwrap();
+        assert!(resources.is_legacy());
+
+        let task = object_task(
+            &AssetObject {
+                hash: "bdf48ef6b5d0d23bbb02e17d04865216179f510a".to_string(),
+                size: 9,
+            },
+            Path::new("assets/objects"),
+        );
+        assert_eq!(
+            task.url,
+            "https://resources.download.minecraft.net/bd/bdf48ef6b5d0d23bbb02e17d04865216179f510a"
+        );
+        assert_eq!(
+            task.path,
+            Path::new("assets/objects/bd/bdf48ef6b5d0d23bbb02e17d04865216179f510a")
+        );
+    }
+}
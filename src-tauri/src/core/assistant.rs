@@ -172,7 +172,6 @@ pub struct OpenAIStreamResponse {
 
 #[derive(Clone)]
 pub struct GameAssistant {
-    client: reqwest::Client,
     pub log_buffer: VecDeque<String>,
     pub max_log_lines: usize,
 }
@@ -180,7 +179,6 @@ pub struct GameAssistant {
 impl GameAssistant {
     pub fn new() -> Self {
         Self {
-            client: reqwest::Client::new(),
             log_buffer: VecDeque::new(),
             max_log_lines: 100,
         }
@@ -203,8 +201,7 @@ impl GameAssistant {
 
     pub async fn check_health(&self, config: &AssistantConfig) -> bool {
         if config.llm_provider == "ollama" {
-            match self
-                .client
+            match crate::core::http::client()
                 .get(format!("{}/api/tags", config.ollama_endpoint))
                 .send()
                 .await
@@ -275,8 +272,7 @@ impl GameAssistant {
             stream: false,
         };
 
-        let response = self
-            .client
+        let response = crate::core::http::client()
             .post(format!("{}/api/chat", config.ollama_endpoint))
             .json(&request)
             .send()
@@ -311,8 +307,7 @@ impl GameAssistant {
             stream: false,
         };
 
-        let response = self
-            .client
+        let response = crate::core::http::client()
             .post(format!("{}/chat/completions", config.openai_endpoint))
             .header("Authorization", format!("Bearer {}", api_key))
             .header("Content-Type", "application/json")
@@ -341,8 +336,7 @@ impl GameAssistant {
     }
 
     pub async fn list_ollama_models(&self, endpoint: &str) -> Result<Vec<ModelInfo>, String> {
-        let response = self
-            .client
+        let response = crate::core::http::client()
             .get(format!("{}/api/tags", endpoint))
             .send()
             .await
@@ -397,8 +391,7 @@ impl GameAssistant {
             .as_ref()
             .ok_or("OpenAI API key not configured")?;
 
-        let response = self
-            .client
+        let response = crate::core::http::client()
             .get(format!("{}/models", config.openai_endpoint))
             .header("Authorization", format!("Bearer {}", api_key))
             .send()
@@ -490,8 +483,7 @@ impl GameAssistant {
             stream: true,
         };
 
-        let response = self
-            .client
+        let response = crate::core::http::client()
             .post(format!("{}/api/chat", config.ollama_endpoint))
             .json(&request)
             .send()
@@ -594,8 +586,7 @@ impl GameAssistant {
             stream: true,
         };
 
-        let response = self
-            .client
+        let response = crate::core::http::client()
             .post(format!("{}/chat/completions", config.openai_endpoint))
             .header("Authorization", format!("Bearer {}", api_key))
             .header("Content-Type", "application/json")
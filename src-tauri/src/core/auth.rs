@@ -1,15 +1,13 @@
+use md5::{Digest, Md5};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Mutex;
 use ts_rs::TS;
-use uuid::Uuid;
 
-// Helper to create a client with a custom User-Agent
+// The shared client sends a DropOut User-Agent
 // This is critical because Microsoft's WAF often blocks requests without a valid UA
 fn get_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .user_agent("DropOut/1.0")
-        .build()
-        .unwrap_or_else(|_| reqwest::Client::new())
+    crate::core::http::client()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -52,17 +50,34 @@ pub struct OfflineAccount {
     pub uuid: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+/// The tokens stay in the backend: they're never sent to the frontend or logged
+#[derive(Clone, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "auth.ts")]
 pub struct MicrosoftAccount {
     pub username: String,
     pub uuid: String,
+    #[serde(skip_serializing, default)]
+    #[ts(skip)]
     pub access_token: String,
+    /// Microsoft OAuth refresh token
+    #[serde(skip_serializing, default)]
+    #[ts(skip)]
     pub refresh_token: Option<String>,
     pub expires_at: i64,
 }
 
+impl std::fmt::Debug for MicrosoftAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MicrosoftAccount")
+            .field("username", &self.username)
+            .field("uuid", &self.uuid)
+            .field("access_token", &"<redacted>")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
 pub struct AccountState {
     pub active_account: Mutex<Option<Account>>,
 }
@@ -75,14 +90,167 @@ impl AccountState {
     }
 }
 
+/// The UUID vanilla gives `username` offline, Java's
+/// `UUID.nameUUIDFromBytes("OfflinePlayer:" + username)`, so singleplayer worlds and
+/// LAN games see the same player as with the vanilla launcher
 pub fn generate_offline_uuid(username: &str) -> String {
-    let namespace = Uuid::NAMESPACE_OID;
-    Uuid::new_v3(&namespace, username.as_bytes()).to_string()
+    let hash = Md5::digest(format!("OfflinePlayer:{}", username));
+    uuid::Builder::from_md5_bytes(hash.into())
+        .into_uuid()
+        .to_string()
+}
+
+/// Check that `username` is a name Minecraft accepts: 3 to 16 letters, digits or
+/// underscores
+pub fn validate_offline_username(username: &str) -> Result<(), String> {
+    if !(3..=16).contains(&username.len()) {
+        return Err("Username must be 3 to 16 characters long".to_string());
+    }
+    if let Some(c) = username
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '_')
+    {
+        return Err(format!(
+            "Username can only contain letters, digits and underscores, not {:?}",
+            c
+        ));
+    }
+    Ok(())
 }
 
 const CLIENT_ID: &str = "fe165602-5410-4441-92f7-326e10a7cb82";
 const SCOPE: &str = "XboxLive.SignIn XboxLive.offline_access";
 
+/// XSTS `XErr` codes of accounts that can't play
+const XERR_NO_XBOX_PROFILE: u64 = 2148916233;
+const XERR_UNAVAILABLE_IN_REGION: u64 = 2148916235;
+const XERR_ADULT_VERIFICATION: [u64; 2] = [2148916236, 2148916237];
+const XERR_CHILD_ACCOUNT: u64 = 2148916238;
+
+/// Why a Microsoft login or refresh failed. Response bodies are never kept, since
+/// they can carry tokens.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    // The user hasn't entered the device code yet; keep polling
+    #[error("authorization_pending")]
+    AuthorizationPending,
+    // Polling too fast; keep polling, more slowly
+    #[error("slow_down")]
+    SlowDown,
+    #[error("The login code expired. Start the login again")]
+    LoginExpired,
+    #[error("The login was declined")]
+    LoginDeclined,
+    // The refresh token was revoked or expired
+    #[error("Your Microsoft login has expired. Log in again")]
+    SessionExpired,
+    #[error(
+        "This Microsoft account has no Xbox profile. Sign in once at xbox.com to create one, then log in again"
+    )]
+    NoXboxProfile,
+    #[error(
+        "This is a child account. An adult has to add it to a Microsoft family before it can play"
+    )]
+    ChildAccount,
+    #[error("Xbox Live isn't available in this account's country or region")]
+    UnavailableInRegion,
+    #[error("This account has to complete adult verification on xbox.com before it can play")]
+    AdultVerificationRequired,
+    #[error("This account doesn't own Minecraft: Java Edition")]
+    GameNotOwned,
+    // Owns the game but never picked a player name
+    #[error(
+        "This account has no Minecraft profile yet. Choose a player name at minecraft.net first"
+    )]
+    NoMinecraftProfile,
+    #[error("{stage} failed with HTTP {status}")]
+    Service { stage: String, status: u16 },
+    #[error("Network error during {stage}: {detail}")]
+    Network { stage: String, detail: String },
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AuthError {
+    /// Stable key the frontend translates, see `core::i18n`
+    pub fn i18n_key(&self) -> &'static str {
+        match self {
+            AuthError::AuthorizationPending => "auth.error.authorization_pending",
+            AuthError::SlowDown => "auth.error.slow_down",
+            AuthError::LoginExpired => "auth.error.login_expired",
+            AuthError::LoginDeclined => "auth.error.login_declined",
+            AuthError::SessionExpired => "auth.error.session_expired",
+            AuthError::NoXboxProfile => "auth.error.no_xbox_profile",
+            AuthError::ChildAccount => "auth.error.child_account",
+            AuthError::UnavailableInRegion => "auth.error.unavailable_in_region",
+            AuthError::AdultVerificationRequired => "auth.error.adult_verification_required",
+            AuthError::GameNotOwned => "auth.error.game_not_owned",
+            AuthError::NoMinecraftProfile => "auth.error.no_minecraft_profile",
+            AuthError::Service { .. } => "auth.error.service",
+            AuthError::Network { .. } => "auth.error.network",
+            AuthError::Other(_) => "auth.error.other",
+        }
+    }
+
+    /// Parameters of the `i18n_key` text
+    pub fn message_params(&self) -> BTreeMap<String, String> {
+        match self {
+            AuthError::Service { stage, status } => BTreeMap::from([
+                ("stage".to_string(), stage.clone()),
+                ("status".to_string(), status.to_string()),
+            ]),
+            AuthError::Network { stage, detail } => BTreeMap::from([
+                ("stage".to_string(), stage.clone()),
+                ("detail".to_string(), detail.clone()),
+            ]),
+            AuthError::Other(detail) => BTreeMap::from([("detail".to_string(), detail.clone())]),
+            _ => BTreeMap::new(),
+        }
+    }
+
+    fn network(stage: &str, err: reqwest::Error) -> Self {
+        // Without the URL, which for some requests carries a token
+        AuthError::Network {
+            stage: stage.to_string(),
+            detail: err.without_url().to_string(),
+        }
+    }
+}
+
+/// Convert AuthError to String for Tauri command results
+impl From<AuthError> for String {
+    fn from(err: AuthError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Where each step of the login talks to; tests point them at a local server
+#[derive(Debug, Clone)]
+pub struct AuthEndpoints {
+    pub device_code: String,
+    pub token: String,
+    pub xbox_live: String,
+    pub xsts: String,
+    pub minecraft_login: String,
+    pub entitlements: String,
+    pub profile: String,
+}
+
+impl Default for AuthEndpoints {
+    fn default() -> Self {
+        Self {
+            device_code: "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode"
+                .to_string(),
+            token: "https://login.microsoftonline.com/consumers/oauth2/v2.0/token".to_string(),
+            xbox_live: "https://user.auth.xboxlive.com/user/authenticate".to_string(),
+            xsts: "https://xsts.auth.xboxlive.com/xsts/authorize".to_string(),
+            minecraft_login: "https://api.minecraftservices.com/launcher/login".to_string(),
+            entitlements: "https://api.minecraftservices.com/entitlements/mcstore".to_string(),
+            profile: "https://api.minecraftservices.com/minecraft/profile".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, TS)]
 #[serde(rename_all(serialize = "camelCase"))]
 #[ts(export, export_to = "auth.ts", rename_all = "camelCase")]
@@ -95,7 +263,7 @@ pub struct DeviceCodeResponse {
     pub message: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, TS)]
+#[derive(Serialize, Deserialize, TS)]
 #[serde(rename_all(serialize = "camelCase"))]
 #[ts(export, export_to = "auth.ts")]
 pub struct TokenResponse {
@@ -104,17 +272,66 @@ pub struct TokenResponse {
     pub expires_in: u64,
 }
 
+impl std::fmt::Debug for TokenResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenResponse")
+            .field("access_token", &"<redacted>")
+            .field(
+                "refresh_token",
+                &self.refresh_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field("expires_in", &self.expires_in)
+            .finish()
+    }
+}
+
 // Error response from token endpoint
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenError {
     pub error: String,
 }
 
-/// Refresh Microsoft OAuth token using refresh_token
-pub async fn refresh_microsoft_token(refresh_token: &str) -> Result<TokenResponse, String> {
-    let client = get_client();
-    let url = "https://login.microsoftonline.com/consumers/oauth2/v2.0/token";
+/// Read a response of the token endpoint, which answers polls that aren't done yet
+/// with an error too
+async fn token_response(resp: reqwest::Response, stage: &str) -> Result<TokenResponse, AuthError> {
+    let status = resp.status();
+    let text = resp
+        .text()
+        .await
+        .map_err(|e| AuthError::network(stage, e))?;
+
+    if let Ok(token_resp) = serde_json::from_str::<TokenResponse>(&text) {
+        return Ok(token_resp);
+    }
+    match serde_json::from_str::<TokenError>(&text).map(|e| e.error) {
+        Ok(error) => Err(match error.as_str() {
+            "authorization_pending" => AuthError::AuthorizationPending,
+            "slow_down" => AuthError::SlowDown,
+            "expired_token" => AuthError::LoginExpired,
+            "access_denied" | "authorization_declined" => AuthError::LoginDeclined,
+            "invalid_grant" => AuthError::SessionExpired,
+            _ => {
+                println!("[Auth] {} error: {}", stage, error);
+                AuthError::Other(format!("{} failed: {}", stage, error))
+            }
+        }),
+        Err(_) => Err(AuthError::Service {
+            stage: stage.to_string(),
+            status: status.as_u16(),
+        }),
+    }
+}
+
+fn form_body(params: &[(&str, &str)]) -> Result<String, AuthError> {
+    serde_urlencoded::to_string(params).map_err(|e| AuthError::Other(e.to_string()))
+}
 
+/// Refresh Microsoft OAuth token using refresh_token
+pub async fn refresh_microsoft_token(
+    endpoints: &AuthEndpoints,
+    refresh_token: &str,
+) -> Result<TokenResponse, AuthError> {
+    let stage = "Microsoft token refresh";
     let params = [
         ("grant_type", "refresh_token"),
         ("client_id", CLIENT_ID),
@@ -122,78 +339,106 @@ pub async fn refresh_microsoft_token(refresh_token: &str) -> Result<TokenRespons
         ("scope", SCOPE),
     ];
 
-    let resp = client
-        .post(url)
+    let resp = get_client()
+        .post(&endpoints.token)
         .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?)
+        .body(form_body(&params)?)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
-
-    let text = resp.text().await.map_err(|e| e.to_string())?;
-
-    if let Ok(token_resp) = serde_json::from_str::<TokenResponse>(&text) {
-        println!("[Auth] Token refreshed successfully!");
-        return Ok(token_resp);
-    }
-
-    if let Ok(err_resp) = serde_json::from_str::<TokenError>(&text) {
-        println!("[Auth] Token refresh error: {}", err_resp.error);
-        return Err(format!("Token refresh failed: {}", err_resp.error));
-    }
+        .map_err(|e| AuthError::network(stage, e))?;
 
-    Err(format!("Unknown refresh response: {}", text))
+    let token_resp = token_response(resp, stage).await?;
+    println!("[Auth] Token refreshed successfully!");
+    Ok(token_resp)
 }
 
 /// Check if a Microsoft account token is expired or about to expire
 pub fn is_token_expired(expires_at: i64) -> bool {
-    let now = std::time::SystemTime::now()
+    // Consider expired if less than 5 minutes remaining
+    expires_at - now_secs() < 300
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
-        .as_secs() as i64;
+        .as_secs() as i64
+}
 
-    // Consider expired if less than 5 minutes remaining
-    expires_at - now < 300
+/// Steps of signing in once Microsoft has issued a token, reported as they start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthStep {
+    XboxLive,
+    Xsts,
+    Minecraft,
+    Profile,
+}
+
+impl AuthStep {
+    /// Text of the `auth-progress` event
+    pub fn message(self) -> &'static str {
+        match self {
+            AuthStep::XboxLive => "Authenticating with Xbox Live...",
+            AuthStep::Xsts => "Authenticating with XSTS...",
+            AuthStep::Minecraft => "Authenticating with Minecraft...",
+            AuthStep::Profile => "Fetching Minecraft profile...",
+        }
+    }
+}
+
+/// Sign in to Minecraft with a Microsoft token: Xbox Live, XSTS, the Minecraft
+/// services and the profile, checking that the account can play
+pub async fn authenticate(
+    endpoints: &AuthEndpoints,
+    ms_token: &TokenResponse,
+    on_step: impl Fn(AuthStep),
+) -> Result<MicrosoftAccount, AuthError> {
+    on_step(AuthStep::XboxLive);
+    let (xbl_token, uhs) = method_xbox_live(endpoints, &ms_token.access_token).await?;
+
+    on_step(AuthStep::Xsts);
+    let xsts_token = method_xsts(endpoints, &xbl_token).await?;
+
+    on_step(AuthStep::Minecraft);
+    let mc = login_minecraft(endpoints, &xsts_token, &uhs).await?;
+
+    on_step(AuthStep::Profile);
+    let profile = match fetch_profile(endpoints, &mc.access_token).await {
+        Ok(profile) => profile,
+        Err(AuthError::NoMinecraftProfile) => {
+            return Err(if check_ownership(endpoints, &mc.access_token).await? {
+                AuthError::NoMinecraftProfile
+            } else {
+                AuthError::GameNotOwned
+            });
+        }
+        Err(e) => return Err(e),
+    };
+
+    Ok(MicrosoftAccount {
+        username: profile.name,
+        uuid: profile.id,
+        access_token: mc.access_token,
+        refresh_token: ms_token.refresh_token.clone(),
+        expires_at: now_secs() + mc.expires_in as i64,
+    })
 }
 
 /// Full refresh flow: refresh MS token -> Xbox -> XSTS -> Minecraft
 pub async fn refresh_full_auth(
+    endpoints: &AuthEndpoints,
     ms_refresh_token: &str,
-) -> Result<(MicrosoftAccount, String), String> {
+) -> Result<(MicrosoftAccount, String), AuthError> {
     println!("[Auth] Starting full token refresh...");
 
-    // 1. Refresh Microsoft token
-    let token_resp = refresh_microsoft_token(ms_refresh_token).await?;
-
-    // 2. Xbox Live Auth
-    let (xbl_token, uhs) = method_xbox_live(&token_resp.access_token).await?;
-
-    // 3. XSTS Auth
-    let xsts_token = method_xsts(&xbl_token).await?;
-
-    // 4. Minecraft Auth
-    let mc_token = login_minecraft(&xsts_token, &uhs).await?;
-
-    // 5. Get Profile
-    let profile = fetch_profile(&mc_token).await?;
+    let token_resp = refresh_microsoft_token(endpoints, ms_refresh_token).await?;
+    let mut account = authenticate(endpoints, &token_resp, |_| {}).await?;
 
-    // 6. Create Account
-    let account = MicrosoftAccount {
-        username: profile.name,
-        uuid: profile.id,
-        access_token: mc_token,
-        refresh_token: token_resp.refresh_token.clone(),
-        expires_at: (std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + token_resp.expires_in) as i64,
-    };
-
-    // Return new MS refresh token for storage
+    // Microsoft may keep the refresh token the same
     let new_ms_refresh = token_resp
         .refresh_token
         .unwrap_or_else(|| ms_refresh_token.to_string());
+    account.refresh_token = Some(new_ms_refresh.clone());
 
     Ok((account, new_ms_refresh))
 }
@@ -212,8 +457,15 @@ pub struct DisplayClaims {
     pub xui: Vec<serde_json::Value>, // We need "uhs" from this
 }
 
+/// Body of a refused XSTS request
+#[derive(Debug, Deserialize)]
+struct XstsError {
+    #[serde(rename = "XErr")]
+    xerr: u64,
+}
+
 // Minecraft Auth
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct MinecraftAuthResponse {
     pub access_token: String,
     pub expires_in: u64,
@@ -228,82 +480,63 @@ pub struct MinecraftProfile {
 }
 
 // 1. Start Device Flow
-pub async fn start_device_flow() -> Result<DeviceCodeResponse, String> {
-    let client = get_client();
-    let url = "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode";
-
+pub async fn start_device_flow(endpoints: &AuthEndpoints) -> Result<DeviceCodeResponse, AuthError> {
+    let stage = "Device code request";
     let params = [("client_id", CLIENT_ID), ("scope", SCOPE)];
 
-    let resp = client
-        .post(url)
+    let resp = get_client()
+        .post(&endpoints.device_code)
         .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?)
+        .body(form_body(&params)?)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AuthError::network(stage, e))?;
 
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_else(|_| "No body".to_string());
-        return Err(format!(
-            "Device code request failed: {} - Body: {}",
-            status, text
-        ));
+        return Err(AuthError::Service {
+            stage: stage.to_string(),
+            status: resp.status().as_u16(),
+        });
     }
 
-    let body = resp
-        .json::<DeviceCodeResponse>()
+    resp.json::<DeviceCodeResponse>()
         .await
-        .map_err(|e| e.to_string())?;
-    Ok(body)
+        .map_err(|e| AuthError::network(stage, e))
 }
 
 // 2. Poll for Token (Simplified: User calls this repeatedly or we loop inside a command)
 // We'll implement a function that tries ONCE, consuming the device_code.
-pub async fn exchange_code_for_token(device_code: &str) -> Result<TokenResponse, String> {
-    let client = get_client();
-    let url = "https://login.microsoftonline.com/consumers/oauth2/v2.0/token";
-
+pub async fn exchange_code_for_token(
+    endpoints: &AuthEndpoints,
+    device_code: &str,
+) -> Result<TokenResponse, AuthError> {
+    let stage = "Microsoft login";
     let params = [
         ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
         ("client_id", CLIENT_ID),
         ("device_code", device_code),
     ];
 
-    let resp = client
-        .post(url)
+    let resp = get_client()
+        .post(&endpoints.token)
         .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?)
+        .body(form_body(&params)?)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
-
-    // Check application level error (e.g. "authorization_pending")
-    let text = resp.text().await.map_err(|e| e.to_string())?;
-
-    // Try parse success
-    if let Ok(token_resp) = serde_json::from_str::<TokenResponse>(&text) {
-        println!("[Auth] Token received successfully!");
-        return Ok(token_resp);
-    }
+        .map_err(|e| AuthError::network(stage, e))?;
 
-    // Try parse error
-    if let Ok(err_resp) = serde_json::from_str::<TokenError>(&text) {
-        if err_resp.error != "authorization_pending" {
-            println!("[Auth] Polling error: {}", err_resp.error);
-        }
-        return Err(err_resp.error); // "authorization_pending", "expired_token", "access_denied"
-    }
-
-    println!("[Auth] Unknown response body: {}", text);
-    Err(format!("Unknown response: {}", text))
+    let token_resp = token_response(resp, stage).await?;
+    println!("[Auth] Token received successfully!");
+    Ok(token_resp)
 }
 
 // 3. Authenticate with Xbox Live
-pub async fn method_xbox_live(ms_access_token: &str) -> Result<(String, String), String> {
+pub async fn method_xbox_live(
+    endpoints: &AuthEndpoints,
+    ms_access_token: &str,
+) -> Result<(String, String), AuthError> {
     println!("[Auth] Starting Xbox Live auth...");
-    let client = get_client();
-    let url = "https://user.auth.xboxlive.com/user/authenticate";
+    let stage = "Xbox Live authentication";
 
     let payload = serde_json::json!({
         "Properties": {
@@ -315,23 +548,26 @@ pub async fn method_xbox_live(ms_access_token: &str) -> Result<(String, String),
         "TokenType": "JWT"
     });
 
-    let resp = client
-        .post(url)
+    let resp = get_client()
+        .post(&endpoints.xbox_live)
         .json(&payload)
-        .header("Content-Type", "application/json")
         .header("Accept", "application/json")
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AuthError::network(stage, e))?;
 
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        println!("[Auth] Xbox Live auth failed: {} - {}", status, text);
-        return Err(format!("Xbox Live auth failed: {} - {}", status, text));
+        println!("[Auth] Xbox Live auth failed: {}", resp.status());
+        return Err(AuthError::Service {
+            stage: stage.to_string(),
+            status: resp.status().as_u16(),
+        });
     }
 
-    let xbl_resp: XboxLiveResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let xbl_resp: XboxLiveResponse = resp
+        .json()
+        .await
+        .map_err(|e| AuthError::network(stage, e))?;
     println!("[Auth] Xbox Live auth success!");
 
     // Extract UHS (User Hash)
@@ -341,17 +577,16 @@ pub async fn method_xbox_live(ms_access_token: &str) -> Result<(String, String),
         .first()
         .and_then(|x| x.get("uhs"))
         .and_then(|s| s.as_str())
-        .ok_or("Failed to find UHS code")?
+        .ok_or_else(|| AuthError::Other("Failed to find UHS code".to_string()))?
         .to_string();
 
     Ok((xbl_resp.token, uhs))
 }
 
 // 4. Authenticate with XSTS
-pub async fn method_xsts(xbl_token: &str) -> Result<String, String> {
+pub async fn method_xsts(endpoints: &AuthEndpoints, xbl_token: &str) -> Result<String, AuthError> {
     println!("[Auth] Starting XSTS auth...");
-    let client = get_client();
-    let url = "https://xsts.auth.xboxlive.com/xsts/authorize";
+    let stage = "XSTS authentication";
 
     let payload = serde_json::json!({
         "Properties": {
@@ -362,93 +597,111 @@ pub async fn method_xsts(xbl_token: &str) -> Result<String, String> {
         "TokenType": "JWT"
     });
 
-    let resp = client
-        .post(url)
+    let resp = get_client()
+        .post(&endpoints.xsts)
         .json(&payload)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
-
-    if !resp.status().is_success() {
-        // Should handle specific errors like "Account not verified", "Age restriction"
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        println!("[Auth] XSTS auth failed: {} - {}", status, text);
-        return Err(format!("XSTS auth failed: {} - {}", status, text));
+        .map_err(|e| AuthError::network(stage, e))?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        println!("[Auth] XSTS auth failed: {}", status);
+        let xerr = resp.json::<XstsError>().await.ok().map(|e| e.xerr);
+        return Err(match xerr {
+            Some(XERR_NO_XBOX_PROFILE) => AuthError::NoXboxProfile,
+            Some(XERR_UNAVAILABLE_IN_REGION) => AuthError::UnavailableInRegion,
+            Some(xerr) if XERR_ADULT_VERIFICATION.contains(&xerr) => {
+                AuthError::AdultVerificationRequired
+            }
+            Some(XERR_CHILD_ACCOUNT) => AuthError::ChildAccount,
+            _ => AuthError::Service {
+                stage: stage.to_string(),
+                status: status.as_u16(),
+            },
+        });
     }
 
-    let xsts_resp: XboxLiveResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let xsts_resp: XboxLiveResponse = resp
+        .json()
+        .await
+        .map_err(|e| AuthError::network(stage, e))?;
     println!("[Auth] XSTS auth success!");
     Ok(xsts_resp.token)
 }
 
 // 5. Authenticate with Minecraft
 // Using the newer /launcher/login endpoint which is what modern launchers use
-pub async fn login_minecraft(xsts_token: &str, uhs: &str) -> Result<String, String> {
+pub async fn login_minecraft(
+    endpoints: &AuthEndpoints,
+    xsts_token: &str,
+    uhs: &str,
+) -> Result<MinecraftAuthResponse, AuthError> {
     println!("[Auth] Starting Minecraft auth...");
-    let client = get_client();
-    let url = "https://api.minecraftservices.com/launcher/login";
+    let stage = "Minecraft authentication";
 
     let payload = serde_json::json!({
         "xtoken": format!("XBL3.0 x={};{}", uhs, xsts_token),
         "platform": "PC_LAUNCHER"
     });
 
-    let resp = client
-        .post(url)
+    let resp = get_client()
+        .post(&endpoints.minecraft_login)
         .json(&payload)
-        .header("Content-Type", "application/json")
         .header("Accept", "application/json")
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AuthError::network(stage, e))?;
 
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_else(|_| "No body".to_string());
-        println!("[Auth] Minecraft auth failed: {} - {}", status, text);
-        return Err(format!(
-            "Minecraft auth failed: {} - Body: {}",
-            status, text
-        ));
+        println!("[Auth] Minecraft auth failed: {}", resp.status());
+        return Err(AuthError::Service {
+            stage: stage.to_string(),
+            status: resp.status().as_u16(),
+        });
     }
 
-    let mc_resp: MinecraftAuthResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let mc_resp = resp
+        .json()
+        .await
+        .map_err(|e| AuthError::network(stage, e))?;
     println!("[Auth] Minecraft auth success!");
-    Ok(mc_resp.access_token)
+    Ok(mc_resp)
 }
 
 // 6. Get Profile
-pub async fn fetch_profile(mc_access_token: &str) -> Result<MinecraftProfile, String> {
-    let client = get_client();
-    let url = "https://api.minecraftservices.com/minecraft/profile";
-
-    let resp = client
-        .get(url)
+pub async fn fetch_profile(
+    endpoints: &AuthEndpoints,
+    mc_access_token: &str,
+) -> Result<MinecraftProfile, AuthError> {
+    let stage = "Profile fetch";
+
+    let resp = get_client()
+        .get(&endpoints.profile)
         .bearer_auth(mc_access_token)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AuthError::network(stage, e))?;
 
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        return Err(format!("Profile fetch failed: {} - {}", status, text));
+    match resp.status() {
+        status if status.is_success() => {
+            resp.json().await.map_err(|e| AuthError::network(stage, e))
+        }
+        reqwest::StatusCode::NOT_FOUND => Err(AuthError::NoMinecraftProfile),
+        status => Err(AuthError::Service {
+            stage: stage.to_string(),
+            status: status.as_u16(),
+        }),
     }
-
-    let profile: MinecraftProfile = resp.json().await.map_err(|e| e.to_string())?;
-    Ok(profile)
 }
 
 // 7. Check Game Ownership
 #[derive(Debug, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct Entitlement {
     pub name: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct EntitlementsResponse {
     pub items: Vec<Entitlement>,
     pub signature: Option<String>,
@@ -456,25 +709,30 @@ pub struct EntitlementsResponse {
     pub key_id: Option<String>,
 }
 
-#[allow(dead_code)]
-pub async fn check_ownership(mc_access_token: &str) -> Result<bool, String> {
-    let client = get_client();
-    let url = "https://api.minecraftservices.com/entitlements/mcstore";
+pub async fn check_ownership(
+    endpoints: &AuthEndpoints,
+    mc_access_token: &str,
+) -> Result<bool, AuthError> {
+    let stage = "Entitlement check";
 
-    let resp = client
-        .get(url)
+    let resp = get_client()
+        .get(&endpoints.entitlements)
         .bearer_auth(mc_access_token)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| AuthError::network(stage, e))?;
 
     if !resp.status().is_success() {
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-        return Err(format!("Entitlement check failed: {} - {}", status, text));
+        return Err(AuthError::Service {
+            stage: stage.to_string(),
+            status: resp.status().as_u16(),
+        });
     }
 
-    let body: EntitlementsResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let body: EntitlementsResponse = resp
+        .json()
+        .await
+        .map_err(|e| AuthError::network(stage, e))?;
     // We look for "product_minecraft" or "game_minecraft"
     let owns_game = body
         .items
@@ -482,3 +740,340 @@ pub async fn check_ownership(mc_access_token: &str) -> Result<bool, String> {
         .any(|e| e.name == "product_minecraft" || e.name == "game_minecraft");
     Ok(owns_game)
 }
+
+#[cfg(test)]
+pub(crate) fn every_error() -> Vec<AuthError> {
+    vec![
+        AuthError::AuthorizationPending,
+        AuthError::SlowDown,
+        AuthError::LoginExpired,
+        AuthError::LoginDeclined,
+        AuthError::SessionExpired,
+        AuthError::NoXboxProfile,
+        AuthError::ChildAccount,
+        AuthError::UnavailableInRegion,
+        AuthError::AdultVerificationRequired,
+        AuthError::GameNotOwned,
+        AuthError::NoMinecraftProfile,
+        AuthError::Service {
+            stage: "stage".to_string(),
+            status: 500,
+        },
+        AuthError::Network {
+            stage: "stage".to_string(),
+            detail: "detail".to_string(),
+        },
+        AuthError::Other("detail".to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mock_server::{MockServer, Response};
+    use std::collections::HashMap;
+
+    const MS_ACCESS: &str = "ms-access-secret";
+    const MS_REFRESH: &str = "ms-refresh-secret";
+    const MC_ACCESS: &str = "mc-access-secret";
+
+    /// Responses of one path, served in order; the last one repeats
+    type Route = (&'static str, Vec<(u16, String)>);
+
+    /// A tiny stand-in for the Microsoft, Xbox and Minecraft services
+    async fn spawn_services(routes: Vec<Route>) -> AuthEndpoints {
+        let mut services = MockServer::builder().fallback(|_| Response::status(500));
+        for (path, responses) in routes {
+            services = services.route(path, move |request| {
+                let (status, body) = &responses[(request.hit - 1).min(responses.len() - 1)];
+                Response::new(*status, body.as_str()).header("Content-Type", "application/json")
+            });
+        }
+        let services = services.start().await;
+        let base = services.url("");
+
+        AuthEndpoints {
+            device_code: format!("{}/devicecode", base),
+            token: format!("{}/token", base),
+            xbox_live: format!("{}/xbl", base),
+            xsts: format!("{}/xsts", base),
+            minecraft_login: format!("{}/launcher/login", base),
+            entitlements: format!("{}/entitlements", base),
+            profile: format!("{}/profile", base),
+        }
+    }
+
+    fn ok(body: serde_json::Value) -> (u16, String) {
+        (200, body.to_string())
+    }
+
+    fn token(refresh_token: Option<&str>) -> (u16, String) {
+        ok(serde_json::json!({
+            "access_token": MS_ACCESS,
+            "refresh_token": refresh_token,
+            "expires_in": 3600,
+        }))
+    }
+
+    fn token_error(error: &str) -> (u16, String) {
+        (400, serde_json::json!({ "error": error }).to_string())
+    }
+
+    fn xbox_token(token: &str) -> (u16, String) {
+        ok(serde_json::json!({
+            "Token": token,
+            "DisplayClaims": { "xui": [{ "uhs": "user-hash" }] },
+        }))
+    }
+
+    /// Every stage after the Microsoft token succeeds, unless `overrides` replaces it
+    fn xbox_and_minecraft(overrides: Vec<Route>) -> Vec<Route> {
+        let mut routes: HashMap<_, _> = [
+            ("/xbl", vec![xbox_token("xbl-token")]),
+            ("/xsts", vec![xbox_token("xsts-token")]),
+            (
+                "/launcher/login",
+                vec![ok(serde_json::json!({
+                    "access_token": MC_ACCESS,
+                    "expires_in": 86400,
+                }))],
+            ),
+            (
+                "/profile",
+                vec![ok(serde_json::json!({
+                    "id": "069a79f444e94726a5befca90e38aaf5",
+                    "name": "Notch",
+                }))],
+            ),
+            (
+                "/entitlements",
+                vec![ok(serde_json::json!({ "items": [] }))],
+            ),
+        ]
+        .into_iter()
+        .collect();
+        routes.extend(overrides);
+        routes.into_iter().collect()
+    }
+
+    fn ms_token() -> TokenResponse {
+        TokenResponse {
+            access_token: MS_ACCESS.to_string(),
+            refresh_token: Some(MS_REFRESH.to_string()),
+            expires_in: 3600,
+        }
+    }
+
+    #[tokio::test]
+    async fn device_code_login_polls_until_confirmed() {
+        let endpoints = spawn_services(vec![
+            (
+                "/devicecode",
+                vec![ok(serde_json::json!({
+                    "user_code": "ABCD-EFGH",
+                    "device_code": "device-code",
+                    "verification_uri": "https://microsoft.com/link",
+                    "expires_in": 900,
+                    "interval": 5,
+                }))],
+            ),
+            (
+                "/token",
+                vec![
+                    token_error("authorization_pending"),
+                    token_error("slow_down"),
+                    token(Some(MS_REFRESH)),
+                ],
+            ),
+        ])
+        .await;
+
+        let device = start_device_flow(&endpoints).await.unwrap();
+        assert_eq!(device.user_code, "ABCD-EFGH");
+        assert_eq!(device.interval, 5);
+
+        let poll = || exchange_code_for_token(&endpoints, &device.device_code);
+        assert_eq!(poll().await.unwrap_err(), AuthError::AuthorizationPending);
+        assert_eq!(poll().await.unwrap_err(), AuthError::SlowDown);
+        let token = poll().await.unwrap();
+        assert_eq!(token.access_token, MS_ACCESS);
+        assert_eq!(token.refresh_token.as_deref(), Some(MS_REFRESH));
+    }
+
+    #[tokio::test]
+    async fn ended_logins_are_reported() {
+        let endpoints = spawn_services(vec![(
+            "/token",
+            vec![
+                token_error("expired_token"),
+                token_error("authorization_declined"),
+                token_error("invalid_grant"),
+            ],
+        )])
+        .await;
+
+        let poll = || exchange_code_for_token(&endpoints, "device-code");
+        assert_eq!(poll().await.unwrap_err(), AuthError::LoginExpired);
+        assert_eq!(poll().await.unwrap_err(), AuthError::LoginDeclined);
+        assert_eq!(
+            refresh_full_auth(&endpoints, MS_REFRESH).await.unwrap_err(),
+            AuthError::SessionExpired
+        );
+    }
+
+    #[tokio::test]
+    async fn microsoft_token_signs_in_to_minecraft() {
+        let endpoints = spawn_services(xbox_and_minecraft(vec![])).await;
+        let steps = Mutex::new(Vec::new());
+
+        let account = authenticate(&endpoints, &ms_token(), |step| {
+            steps.lock().unwrap().push(step)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(account.username, "Notch");
+        assert_eq!(account.uuid, "069a79f444e94726a5befca90e38aaf5");
+        assert_eq!(account.access_token, MC_ACCESS);
+        assert_eq!(account.refresh_token.as_deref(), Some(MS_REFRESH));
+        // Expires with the Minecraft token, not the Microsoft one
+        let expires_in = account.expires_at - now_secs();
+        assert!((86300..=86400).contains(&expires_in), "{}", expires_in);
+        assert_eq!(
+            steps.into_inner().unwrap(),
+            [
+                AuthStep::XboxLive,
+                AuthStep::Xsts,
+                AuthStep::Minecraft,
+                AuthStep::Profile
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_xbox_accounts_are_explained() {
+        let xsts_error = |xerr: u64| {
+            (
+                401,
+                serde_json::json!({ "Identity": "0", "XErr": xerr }).to_string(),
+            )
+        };
+        for (response, expected) in [
+            (xsts_error(2148916233), AuthError::NoXboxProfile),
+            (xsts_error(2148916235), AuthError::UnavailableInRegion),
+            (xsts_error(2148916236), AuthError::AdultVerificationRequired),
+            (xsts_error(2148916238), AuthError::ChildAccount),
+            (
+                (503, String::new()),
+                AuthError::Service {
+                    stage: "XSTS authentication".to_string(),
+                    status: 503,
+                },
+            ),
+        ] {
+            let endpoints =
+                spawn_services(xbox_and_minecraft(vec![("/xsts", vec![response])])).await;
+            assert_eq!(
+                authenticate(&endpoints, &ms_token(), |_| {})
+                    .await
+                    .unwrap_err(),
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_profile_tells_ownership_apart() {
+        for (items, expected) in [
+            (serde_json::json!([]), AuthError::GameNotOwned),
+            (
+                serde_json::json!([{ "name": "product_minecraft" }, { "name": "game_minecraft" }]),
+                AuthError::NoMinecraftProfile,
+            ),
+        ] {
+            let endpoints = spawn_services(xbox_and_minecraft(vec![
+                ("/profile", vec![(404, String::new())]),
+                (
+                    "/entitlements",
+                    vec![ok(serde_json::json!({ "items": items }))],
+                ),
+            ]))
+            .await;
+            assert_eq!(
+                authenticate(&endpoints, &ms_token(), |_| {})
+                    .await
+                    .unwrap_err(),
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_the_refresh_token_when_none_is_issued() {
+        let endpoints =
+            spawn_services(xbox_and_minecraft(vec![("/token", vec![token(None)])])).await;
+
+        let (account, ms_refresh) = refresh_full_auth(&endpoints, MS_REFRESH).await.unwrap();
+        assert_eq!(ms_refresh, MS_REFRESH);
+        assert_eq!(account.refresh_token.as_deref(), Some(MS_REFRESH));
+        assert_eq!(account.access_token, MC_ACCESS);
+    }
+
+    #[test]
+    fn tokens_stay_out_of_logs_and_the_frontend() {
+        let account = MicrosoftAccount {
+            username: "Notch".to_string(),
+            uuid: "069a79f444e94726a5befca90e38aaf5".to_string(),
+            access_token: MC_ACCESS.to_string(),
+            refresh_token: Some(MS_REFRESH.to_string()),
+            expires_at: 0,
+        };
+        let shown = [
+            format!("{:?}", account),
+            format!("{:?}", ms_token()),
+            serde_json::to_string(&Account::Microsoft(account)).unwrap(),
+        ];
+        for text in shown {
+            assert!(!text.contains(MC_ACCESS), "{}", text);
+            assert!(!text.contains(MS_ACCESS), "{}", text);
+            assert!(!text.contains(MS_REFRESH), "{}", text);
+        }
+    }
+
+    #[test]
+    fn offline_uuids_match_vanilla() {
+        assert_eq!(
+            generate_offline_uuid("Notch"),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+        assert_eq!(
+            generate_offline_uuid("jeb_"),
+            "a762f560-4fce-3236-812a-b80efff0b62b"
+        );
+        assert_eq!(
+            generate_offline_uuid("Steve"),
+            "5627dd98-e6be-3c21-b8a8-e92344183641"
+        );
+        // Names are case-sensitive, like in vanilla
+        assert_ne!(
+            generate_offline_uuid("steve"),
+            generate_offline_uuid("Steve")
+        );
+    }
+
+    #[test]
+    fn offline_usernames_are_checked() {
+        for name in ["Dev", "jeb_", "Player_123456789"] {
+            assert_eq!(validate_offline_username(name), Ok(()), "{}", name);
+        }
+        for name in [
+            "ab",
+            "a_name_that_is_too_long",
+            "with space",
+            "dash-name",
+            "名字名字",
+        ] {
+            assert!(validate_offline_username(name).is_err(), "{}", name);
+        }
+    }
+}
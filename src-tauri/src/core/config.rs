@@ -1,10 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use tauri::{AppHandle, Manager};
+use tauri::AppHandle;
 use ts_rs::TS;
 
+use crate::core::quick_play::QuickPlay;
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "config.ts")]
@@ -63,6 +65,31 @@ pub struct FeatureFlags {
     pub quick_play_multiplayer_server: Option<String>,
 }
 
+impl FeatureFlags {
+    /// Quick Play target launches go to when neither the launch nor the instance
+    /// names one; a singleplayer world takes precedence over the server
+    pub fn quick_play(&self) -> Option<QuickPlay> {
+        if !self.quick_play_enabled {
+            return None;
+        }
+        let world = self
+            .quick_play_path
+            .as_deref()
+            .filter(|world| self.quick_play_singleplayer && !world.is_empty());
+        if let Some(world) = world {
+            return Some(QuickPlay::Singleplayer {
+                world: world.to_string(),
+            });
+        }
+        self.quick_play_multiplayer_server
+            .as_deref()
+            .filter(|server| !server.is_empty())
+            .map(|server| QuickPlay::Multiplayer {
+                address: server.to_string(),
+            })
+    }
+}
+
 impl Default for FeatureFlags {
     fn default() -> Self {
         Self {
@@ -80,12 +107,29 @@ impl Default for FeatureFlags {
 #[ts(export, export_to = "config.ts")]
 #[serde(default)]
 pub struct LauncherConfig {
-    pub min_memory: u32, // in MB
-    pub max_memory: u32, // in MB
+    pub schema_version: u32, // see CONFIG_SCHEMA_VERSION
+    pub min_memory: u32,     // in MB
+    pub max_memory: u32,     // in MB
     pub java_path: String,
     pub width: u32,
     pub height: u32,
     pub download_threads: u32, // concurrent download threads (1-128)
+    pub segmented_java_downloads: bool, // Split large Java archives across several connections
+    pub java_download_segments: u32, // connections per segmented download
+    #[ts(type = "number | null")]
+    pub max_download_speed_bytes_per_sec: Option<u64>, // None = unlimited
+    pub download_verify_retries: u32, // re-downloads after a checksum mismatch
+    pub download_max_attempts: u32, // attempts per file on transient network errors
+    pub download_stall_timeout_secs: u32, // retry a download that receives nothing for this long
+    pub pending_java_download_max_age_days: u32, // queued Java downloads older than this are dropped
+    #[ts(type = "number | null")]
+    pub catalog_cache_ttl_secs: Option<u64>, // Java catalog cache lifetime, None = 24 hours
+    pub background_refresh: bool, // refresh an expired Java catalog in the background after startup
+    #[ts(type = "number | null")]
+    pub progress_event_interval_ms: Option<u64>, // debug override, None = built-in default
+    #[ts(type = "number | null")]
+    pub progress_event_bytes: Option<u64>, // debug override, None = built-in default
+    pub url_rewrite_rules: Vec<(String, String)>, // (url prefix, replacement) applied to downloads
     pub custom_background_path: Option<String>,
     pub enable_gpu_acceleration: bool,
     pub enable_visual_effects: bool,
@@ -97,6 +141,7 @@ pub struct LauncherConfig {
     // Storage management
     pub use_shared_caches: bool, // Use global shared versions/libraries/assets
     pub keep_legacy_per_instance_storage: bool, // Keep old per-instance caches (no migration)
+    pub keep_game_running_on_exit: bool, // leave running games open when the launcher closes
     // Feature-gated argument flags
     pub feature_flags: FeatureFlags,
 }
@@ -104,12 +149,25 @@ pub struct LauncherConfig {
 impl Default for LauncherConfig {
     fn default() -> Self {
         Self {
+            schema_version: CONFIG_SCHEMA_VERSION,
             min_memory: 1024,
             max_memory: 2048,
             java_path: "java".to_string(),
-            width: 854,
-            height: 480,
+            width: DEFAULT_WINDOW_WIDTH,
+            height: DEFAULT_WINDOW_HEIGHT,
             download_threads: 32,
+            segmented_java_downloads: false,
+            java_download_segments: 4,
+            max_download_speed_bytes_per_sec: None,
+            download_verify_retries: 2,
+            download_max_attempts: 3,
+            download_stall_timeout_secs: 60,
+            pending_java_download_max_age_days: 30,
+            catalog_cache_ttl_secs: None,
+            background_refresh: true,
+            progress_event_interval_ms: None,
+            progress_event_bytes: None,
+            url_rewrite_rules: Vec::new(),
             custom_background_path: None,
             enable_gpu_acceleration: false,
             enable_visual_effects: true,
@@ -118,13 +176,55 @@ impl Default for LauncherConfig {
             log_upload_service: "paste.rs".to_string(),
             pastebin_api_key: None,
             assistant: AssistantConfig::default(),
-            use_shared_caches: false,
+            use_shared_caches: true,
             keep_legacy_per_instance_storage: true,
+            keep_game_running_on_exit: true,
             feature_flags: FeatureFlags::default(),
         }
     }
 }
 
+/// Window size the game picks by itself
+pub const DEFAULT_WINDOW_WIDTH: u32 = 854;
+pub const DEFAULT_WINDOW_HEIGHT: u32 = 480;
+
+/// Bump when a change to `LauncherConfig` needs existing files migrated
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
+/// Shortest Java catalog cache lifetime the settings accept
+pub const MIN_CATALOG_CACHE_TTL_SECS: u64 = 60;
+
+impl LauncherConfig {
+    /// Reject values that can't be saved
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ttl) = self.catalog_cache_ttl_secs
+            && ttl < MIN_CATALOG_CACHE_TTL_SECS
+        {
+            return Err(format!(
+                "Catalog cache lifetime must be at least {} seconds",
+                MIN_CATALOG_CACHE_TTL_SECS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Window size to pass to the game, if the user changed it from the game's own
+    pub fn custom_resolution(&self) -> Option<(u32, u32)> {
+        let size = (self.width, self.height);
+        let unset = size == (DEFAULT_WINDOW_WIDTH, DEFAULT_WINDOW_HEIGHT);
+        (!unset && self.width > 0 && self.height > 0).then_some(size)
+    }
+
+    /// Number of connections to use for Java archive downloads (1 = single stream)
+    pub fn java_download_segment_count(&self) -> usize {
+        if self.segmented_java_downloads {
+            self.java_download_segments.clamp(1, 16) as usize
+        } else {
+            1
+        }
+    }
+}
+
 pub struct ConfigState {
     pub config: Mutex<LauncherConfig>,
     pub file_path: PathBuf,
@@ -132,27 +232,135 @@ pub struct ConfigState {
 
 impl ConfigState {
     pub fn new(app_handle: &AppHandle) -> Self {
-        let app_dir = app_handle.path().app_data_dir().unwrap();
+        let app_dir = crate::core::paths::data_root(app_handle);
         let config_path = app_dir.join("config.json");
 
-        let config = if config_path.exists() {
-            let content = fs::read_to_string(&config_path).unwrap_or_default();
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            LauncherConfig::default()
-        };
-
         Self {
-            config: Mutex::new(config),
+            config: Mutex::new(load_config_from(&config_path)),
             file_path: config_path,
         }
     }
 
     pub fn save(&self) -> Result<(), String> {
-        let config = self.config.lock().unwrap();
-        let content = serde_json::to_string_pretty(&*config).map_err(|e| e.to_string())?;
-        fs::create_dir_all(self.file_path.parent().unwrap()).map_err(|e| e.to_string())?;
-        fs::write(&self.file_path, content).map_err(|e| e.to_string())?;
-        Ok(())
+        save_config_to(&self.config.lock().unwrap(), &self.file_path)
+    }
+}
+
+/// The config at `path`; defaults when the file is missing or unreadable, in which
+/// case the unreadable file is kept aside before the defaults are saved over it
+fn load_config_from(path: &Path) -> LauncherConfig {
+    if !path.exists() {
+        return LauncherConfig::default();
+    }
+    let config = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            serde_json::from_str::<LauncherConfig>(&content).map_err(|e| e.to_string())
+        });
+    match config {
+        Ok(config) if config.schema_version > CONFIG_SCHEMA_VERSION => {
+            // Written by a newer launcher: keep its version so it isn't stamped down
+            log::warn!(
+                "Launcher config at {} has schema version {}, newer than {}",
+                path.display(),
+                config.schema_version,
+                CONFIG_SCHEMA_VERSION
+            );
+            config
+        }
+        Ok(config) => LauncherConfig {
+            schema_version: CONFIG_SCHEMA_VERSION,
+            ..config
+        },
+        Err(e) => {
+            log::warn!(
+                "Failed to load launcher config at {}, using defaults: {}",
+                path.display(),
+                e
+            );
+            crate::core::java::persistence::set_aside_corrupt_config(path, "launcher config");
+            LauncherConfig::default()
+        }
+    }
+}
+
+/// Write `config` to `path` atomically, creating its directory
+pub fn save_config_to(config: &LauncherConfig, path: &Path) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    crate::core::java::persistence::write_file_atomic(path, content.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn catalog_cache_ttl_has_a_minimum() {
+        let mut config = LauncherConfig::default();
+        assert!(config.validate().is_ok());
+
+        config.catalog_cache_ttl_secs = Some(MIN_CATALOG_CACHE_TTL_SECS);
+        assert!(config.validate().is_ok());
+
+        config.catalog_cache_ttl_secs = Some(MIN_CATALOG_CACHE_TTL_SECS - 1);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn config_round_trips_through_its_file() {
+        let dir = TestDir::new("config");
+        let path = dir.path().join("config.json");
+        assert_eq!(load_config_from(&path).max_memory, 2048);
+
+        let config = LauncherConfig {
+            max_memory: 6144,
+            width: 1920,
+            height: 1080,
+            java_path: "/opt/java/bin/java".to_string(),
+            ..LauncherConfig::default()
+        };
+        save_config_to(&config, &path).unwrap();
+        let loaded = load_config_from(&path);
+        assert_eq!(loaded.max_memory, 6144);
+        assert_eq!((loaded.width, loaded.height), (1920, 1080));
+        assert_eq!(loaded.java_path, "/opt/java/bin/java");
+        assert_eq!(loaded.schema_version, CONFIG_SCHEMA_VERSION);
+
+        // Files from before the schema version load as the current version
+        fs::write(&path, r#"{"maxMemory": 3072}"#).unwrap();
+        let loaded = load_config_from(&path);
+        assert_eq!(loaded.max_memory, 3072);
+        assert_eq!(loaded.schema_version, CONFIG_SCHEMA_VERSION);
+
+        // Files from a newer launcher keep their version
+        fs::write(&path, r#"{"schemaVersion": 7, "maxMemory": 3072}"#).unwrap();
+        let loaded = load_config_from(&path);
+        assert_eq!(loaded.schema_version, 7);
+        assert_eq!(loaded.max_memory, 3072);
+    }
+
+    #[test]
+    fn unreadable_config_is_kept_aside() {
+        let dir = TestDir::new("config");
+        let path = dir.path().join("config.json");
+        fs::write(&path, "{not json").unwrap();
+        assert_eq!(load_config_from(&path).max_memory, 2048);
+
+        let backups: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .starts_with("config.json.corrupt-")
+            })
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(backups[0].path()).unwrap(), "{not json");
     }
 }
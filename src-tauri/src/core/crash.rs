@@ -0,0 +1,370 @@
+//! Recognizing common causes of a crash from the game's output and crash report.
+//!
+//! Each rule looks for a well-known message (a class file version mismatch, an
+//! exhausted heap, a JVM flag the runtime rejects) and turns it into a [`CrashHint`]
+//! the frontend can translate, optionally with an action that fixes the cause.
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "crash.ts")]
+pub enum CrashHintKind {
+    /// The game or a mod needs a newer Java
+    JavaTooOld,
+    /// An old game or mod loader can't run on this Java
+    JavaTooNew,
+    OutOfMemory,
+    /// Native libraries (LWJGL, OpenAL) couldn't be loaded
+    MissingNatives,
+    /// The JVM refused one of the arguments it was started with
+    BadJvmOption,
+}
+
+/// Something the launcher can do about a crash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[ts(export, export_to = "crash.ts")]
+pub enum CrashAction {
+    /// Launch the instance with this Java major version
+    SwitchJava { major: u32 },
+    /// Give the game more memory
+    RaiseMemory,
+    /// Remove the instance's JVM arguments starting with `arg`
+    RemoveJvmArg { arg: String },
+    /// Verify and re-download the game files
+    RepairGameFiles,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "crash.ts")]
+pub struct CrashHint {
+    pub kind: CrashHintKind,
+    pub message_key: String,
+    pub params: BTreeMap<String, String>,
+    pub action: Option<CrashAction>,
+}
+
+impl CrashHintKind {
+    #[cfg(test)]
+    pub const ALL: [CrashHintKind; 5] = [
+        CrashHintKind::JavaTooOld,
+        CrashHintKind::JavaTooNew,
+        CrashHintKind::OutOfMemory,
+        CrashHintKind::MissingNatives,
+        CrashHintKind::BadJvmOption,
+    ];
+
+    pub fn message_key(self) -> &'static str {
+        match self {
+            CrashHintKind::JavaTooOld => "crash.hint.java_too_old",
+            CrashHintKind::JavaTooNew => "crash.hint.java_too_new",
+            CrashHintKind::OutOfMemory => "crash.hint.out_of_memory",
+            CrashHintKind::MissingNatives => "crash.hint.missing_natives",
+            CrashHintKind::BadJvmOption => "crash.hint.bad_jvm_option",
+        }
+    }
+}
+
+impl CrashHint {
+    fn new(kind: CrashHintKind, params: &[(&str, String)], action: Option<CrashAction>) -> Self {
+        CrashHint {
+            kind,
+            message_key: kind.message_key().to_string(),
+            params: params
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+            action,
+        }
+    }
+}
+
+/// Java major version that reads class files of `class_version`, e.g. 65 -> 21
+fn java_major(class_version: u32) -> u32 {
+    class_version.saturating_sub(44)
+}
+
+static CLASS_VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"class file version (\d+)\.\d+\), this version of the Java Runtime only recognizes class file versions up to (\d+)\.\d+",
+    )
+    .unwrap()
+});
+/// Java 7 and older word it differently
+static LEGACY_CLASS_VERSION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Unsupported major\.minor version (\d+)\.\d+").unwrap());
+static BAD_VM_OPTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:Improperly specified|Unrecognized) VM option '([^']+)'").unwrap()
+});
+static BAD_OPTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:Unrecognized option|Invalid (?:maximum|initial) heap size): (\S+)").unwrap()
+});
+
+fn java_version_hints(text: &str, hints: &mut Vec<CrashHint>) {
+    let required = CLASS_VERSION
+        .captures(text)
+        .map(|c| (c[1].parse().unwrap_or(0), Some(c[2].parse().unwrap_or(0))))
+        .or_else(|| {
+            LEGACY_CLASS_VERSION
+                .captures(text)
+                .map(|c| (c[1].parse().unwrap_or(0), None))
+        });
+    if let Some((class_version, supported)) = required {
+        let major = java_major(class_version);
+        let mut params = vec![("required", major.to_string())];
+        if let Some(supported) = supported {
+            params.push(("current", java_major(supported).to_string()));
+        }
+        hints.push(CrashHint::new(
+            CrashHintKind::JavaTooOld,
+            &params,
+            Some(CrashAction::SwitchJava { major }),
+        ));
+    }
+
+    // LaunchWrapper (Forge before 1.13) casts the system class loader, which stopped
+    // working in Java 9
+    if text.contains("AppClassLoader cannot be cast to class java.net.URLClassLoader")
+        || text.contains("AppClassLoader cannot be cast to java.net.URLClassLoader")
+    {
+        hints.push(CrashHint::new(
+            CrashHintKind::JavaTooNew,
+            &[("required", "8".to_string())],
+            Some(CrashAction::SwitchJava { major: 8 }),
+        ));
+    }
+}
+
+fn resource_hints(text: &str, hints: &mut Vec<CrashHint>) {
+    if text.contains("java.lang.OutOfMemoryError")
+        && !text.contains("unable to create native thread")
+    {
+        hints.push(CrashHint::new(
+            CrashHintKind::OutOfMemory,
+            &[],
+            Some(CrashAction::RaiseMemory),
+        ));
+    }
+    if text.contains("java.lang.UnsatisfiedLinkError")
+        || text.contains("Failed to locate library")
+        || text.contains("Can't load library")
+    {
+        hints.push(CrashHint::new(
+            CrashHintKind::MissingNatives,
+            &[],
+            Some(CrashAction::RepairGameFiles),
+        ));
+    }
+}
+
+fn jvm_option_hints(text: &str, hints: &mut Vec<CrashHint>) {
+    let vm_options = BAD_VM_OPTION
+        .captures_iter(text)
+        .map(|c| (c[1].to_string(), vm_option_arg(&c[1])));
+    let options = BAD_OPTION
+        .captures_iter(text)
+        .map(|c| (c[1].to_string(), c[1].to_string()));
+    for (option, arg) in vm_options.chain(options) {
+        if hints
+            .iter()
+            .any(|hint| hint.params.get("option") == Some(&option))
+        {
+            continue;
+        }
+        hints.push(CrashHint::new(
+            CrashHintKind::BadJvmOption,
+            &[("option", option)],
+            Some(CrashAction::RemoveJvmArg { arg }),
+        ));
+    }
+}
+
+/// Start of the `-XX:` argument a VM option named in an error came from. The JVM
+/// reports `UseConcMarkSweepGC` for `-XX:+UseConcMarkSweepGC` and `MaxGCPauseMillis`
+/// for `-XX:MaxGCPauseMillis=abc`.
+fn vm_option_arg(option: &str) -> String {
+    let name = option.split('=').next().unwrap_or(option);
+    if name.starts_with("Use") {
+        format!("-XX:+{}", name)
+    } else {
+        format!("-XX:{}", name)
+    }
+}
+
+/// Hints for what made a game crash, from the last lines of its output and the crash
+/// report it wrote, if any. Each problem is reported once.
+pub fn analyze(log_tail: &str, crash_report: Option<&str>) -> Vec<CrashHint> {
+    let text = match crash_report {
+        Some(report) => format!("{}\n{}", log_tail, report),
+        None => log_tail.to_string(),
+    };
+    let mut hints = Vec::new();
+    jvm_option_hints(&text, &mut hints);
+    java_version_hints(&text, &mut hints);
+    resource_hints(&text, &mut hints);
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(hints: &[CrashHint]) -> Vec<CrashHintKind> {
+        hints.iter().map(|hint| hint.kind).collect()
+    }
+
+    #[test]
+    fn recognizes_captured_crash_excerpts() {
+        // (log tail, crash report, expected hints)
+        let cases: Vec<(&str, Option<&str>, Vec<CrashHint>)> = vec![
+            (
+                "Error: LinkageError occurred while loading main class net.minecraft.client.main.Main\n\
+                 \tjava.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main has been compiled by a more recent version of the Java Runtime (class file version 65.0), this version of the Java Runtime only recognizes class file versions up to 61.0",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::JavaTooOld,
+                    &[
+                        ("required", "21".to_string()),
+                        ("current", "17".to_string()),
+                    ],
+                    Some(CrashAction::SwitchJava { major: 21 }),
+                )],
+            ),
+            (
+                "Exception in thread \"main\" java.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main has been compiled by a more recent version of the Java Runtime (class file version 61.0), this version of the Java Runtime only recognizes class file versions up to 52.0\n\
+                 \tat java.lang.ClassLoader.defineClass1(Native Method)",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::JavaTooOld,
+                    &[("required", "17".to_string()), ("current", "8".to_string())],
+                    Some(CrashAction::SwitchJava { major: 17 }),
+                )],
+            ),
+            (
+                "Exception in thread \"main\" java.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main : Unsupported major.minor version 52.0",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::JavaTooOld,
+                    &[("required", "8".to_string())],
+                    Some(CrashAction::SwitchJava { major: 8 }),
+                )],
+            ),
+            (
+                "[main/INFO] [LaunchWrapper]: Loading tweak class name net.minecraftforge.fml.common.launcher.FMLTweaker\n\
+                 Exception in thread \"main\" java.lang.ClassCastException: class jdk.internal.loader.ClassLoaders$AppClassLoader cannot be cast to class java.net.URLClassLoader (jdk.internal.loader.ClassLoaders$AppClassLoader and java.net.URLClassLoader are in module java.base of loader 'bootstrap')",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::JavaTooNew,
+                    &[("required", "8".to_string())],
+                    Some(CrashAction::SwitchJava { major: 8 }),
+                )],
+            ),
+            (
+                "[Render thread/INFO]: Loaded 1432 advancements",
+                Some(
+                    "---- Minecraft Crash Report ----\n\
+                     Description: Unexpected error\n\n\
+                     java.lang.OutOfMemoryError: Java heap space\n\
+                     \tat java.base/java.util.Arrays.copyOf(Arrays.java:3537)",
+                ),
+                vec![CrashHint::new(
+                    CrashHintKind::OutOfMemory,
+                    &[],
+                    Some(CrashAction::RaiseMemory),
+                )],
+            ),
+            (
+                "[LWJGL] Failed to load a library. Possible solutions:\n\
+                 Exception in thread \"Render thread\" java.lang.UnsatisfiedLinkError: Failed to locate library: liblwjgl.so",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::MissingNatives,
+                    &[],
+                    Some(CrashAction::RepairGameFiles),
+                )],
+            ),
+            (
+                "Exception in thread \"main\" java.lang.UnsatisfiedLinkError: no lwjgl64 in java.library.path",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::MissingNatives,
+                    &[],
+                    Some(CrashAction::RepairGameFiles),
+                )],
+            ),
+            (
+                "Improperly specified VM option 'MaxGCPauseMillis'\n\
+                 Error: Could not create the Java Virtual Machine.\n\
+                 Error: A fatal exception has occurred. Program will exit.",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::BadJvmOption,
+                    &[("option", "MaxGCPauseMillis".to_string())],
+                    Some(CrashAction::RemoveJvmArg {
+                        arg: "-XX:MaxGCPauseMillis".to_string(),
+                    }),
+                )],
+            ),
+            (
+                "Unrecognized VM option 'UseConcMarkSweepGC'\n\
+                 Error: Could not create the Java Virtual Machine.",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::BadJvmOption,
+                    &[("option", "UseConcMarkSweepGC".to_string())],
+                    Some(CrashAction::RemoveJvmArg {
+                        arg: "-XX:+UseConcMarkSweepGC".to_string(),
+                    }),
+                )],
+            ),
+            (
+                "Invalid maximum heap size: -Xmx4gb\n\
+                 Error: Could not create the Java Virtual Machine.",
+                None,
+                vec![CrashHint::new(
+                    CrashHintKind::BadJvmOption,
+                    &[("option", "-Xmx4gb".to_string())],
+                    Some(CrashAction::RemoveJvmArg {
+                        arg: "-Xmx4gb".to_string(),
+                    }),
+                )],
+            ),
+            (
+                "[Render thread/INFO]: Stopping!\n[Render thread/INFO]: Saving worlds",
+                None,
+                vec![],
+            ),
+        ];
+
+        for (log_tail, crash_report, expected) in cases {
+            assert_eq!(analyze(log_tail, crash_report), expected, "{}", log_tail);
+        }
+    }
+
+    #[test]
+    fn combines_hints_and_reports_each_once() {
+        let log = "java.lang.OutOfMemoryError: Java heap space\n\
+                   java.lang.OutOfMemoryError: Java heap space\n\
+                   Unrecognized VM option 'UseConcMarkSweepGC'\n\
+                   Unrecognized VM option 'UseConcMarkSweepGC'";
+        assert_eq!(
+            kinds(&analyze(log, None)),
+            vec![CrashHintKind::BadJvmOption, CrashHintKind::OutOfMemory]
+        );
+        assert!(
+            analyze(
+                "java.lang.OutOfMemoryError: unable to create native thread",
+                None
+            )
+            .is_empty()
+        );
+        assert_eq!(java_major(52), 8);
+        assert_eq!(java_major(61), 17);
+        assert_eq!(java_major(65), 21);
+    }
+}
@@ -0,0 +1,210 @@
+//! Persistent record of recent downloads, for support and the downloads page.
+//!
+//! Entries are appended once per operation (a Java archive or a `download_files`
+//! batch) when it ends, so nothing here runs inside the download loops.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use ts_rs::TS;
+
+/// Number of entries kept; older ones are dropped
+pub const HISTORY_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "downloader.ts")]
+pub enum DownloadHistoryKind {
+    /// A Java runtime archive
+    JavaArchive,
+    /// A `download_files` batch (game files, libraries, assets)
+    Batch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "downloader.ts")]
+pub enum DownloadHistoryOutcome {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "downloader.ts")]
+pub struct DownloadHistoryEntry {
+    pub kind: DownloadHistoryKind,
+    /// Source URL, for single-file downloads
+    pub url: Option<String>,
+    /// File name, or a summary such as "42 files" for a batch
+    pub file: String,
+    #[ts(type = "number")]
+    pub size_bytes: u64,
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+    #[ts(type = "number")]
+    pub average_speed_bytes_per_sec: u64,
+    pub outcome: DownloadHistoryOutcome,
+    pub error: Option<String>,
+    /// Unix time the operation ended
+    #[ts(type = "number")]
+    pub finished_at: u64,
+}
+
+impl DownloadHistoryEntry {
+    /// Build an entry for an operation that started at `started` and just ended
+    pub fn finished(
+        kind: DownloadHistoryKind,
+        url: Option<String>,
+        file: String,
+        size_bytes: u64,
+        started: std::time::Instant,
+        outcome: DownloadHistoryOutcome,
+        error: Option<String>,
+    ) -> Self {
+        let elapsed = started.elapsed();
+        let average_speed_bytes_per_sec = if elapsed.as_secs_f64() > 0.0 {
+            (size_bytes as f64 / elapsed.as_secs_f64()) as u64
+        } else {
+            0
+        };
+        Self {
+            kind,
+            url,
+            file,
+            size_bytes,
+            duration_ms: elapsed.as_millis() as u64,
+            average_speed_bytes_per_sec,
+            outcome,
+            error,
+            finished_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadHistory {
+    /// Oldest first
+    entries: VecDeque<DownloadHistoryEntry>,
+}
+
+/// History loaded from disk on first use, then kept in memory
+static HISTORY: Mutex<Option<DownloadHistory>> = Mutex::new(None);
+
+impl DownloadHistory {
+    fn path(app_handle: &AppHandle) -> PathBuf {
+        crate::core::paths::data_root(app_handle).join("download_history.json")
+    }
+
+    fn load_from(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        crate::core::java::persistence::write_file_atomic(path, content.as_bytes())
+            .map_err(|e| e.to_string())
+    }
+
+    fn push(&mut self, entry: DownloadHistoryEntry) {
+        self.entries.push_back(entry);
+        while self.entries.len() > HISTORY_LIMIT {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Newest first
+    fn newest_first(&self) -> Vec<DownloadHistoryEntry> {
+        self.entries.iter().rev().cloned().collect()
+    }
+}
+
+fn with_history<T>(app_handle: &AppHandle, f: impl FnOnce(&mut DownloadHistory, &Path) -> T) -> T {
+    let path = DownloadHistory::path(app_handle);
+    let mut history = HISTORY.lock().unwrap();
+    let history = history.get_or_insert_with(|| DownloadHistory::load_from(&path));
+    f(history, &path)
+}
+
+/// Append an entry and write the history out. Failing to save only logs a warning.
+pub fn record(app_handle: &AppHandle, entry: DownloadHistoryEntry) {
+    with_history(app_handle, |history, path| {
+        history.push(entry);
+        if let Err(e) = history.save_to(path) {
+            log::warn!("Failed to save download history: {}", e);
+        }
+    });
+}
+
+/// Recorded downloads, newest first
+pub fn entries(app_handle: &AppHandle) -> Vec<DownloadHistoryEntry> {
+    with_history(app_handle, |history, _| history.newest_first())
+}
+
+pub fn clear(app_handle: &AppHandle) -> Result<(), String> {
+    with_history(app_handle, |history, path| {
+        history.entries.clear();
+        history.save_to(path)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn entry(file: &str) -> DownloadHistoryEntry {
+        DownloadHistoryEntry::finished(
+            DownloadHistoryKind::JavaArchive,
+            Some(format!("https://example.com/{}", file)),
+            file.to_string(),
+            1000,
+            std::time::Instant::now(),
+            DownloadHistoryOutcome::Completed,
+            None,
+        )
+    }
+
+    #[test]
+    fn history_keeps_only_the_newest_entries() {
+        let mut history = DownloadHistory::default();
+        for i in 0..HISTORY_LIMIT + 5 {
+            history.push(entry(&format!("{}.tar.gz", i)));
+        }
+
+        let entries = history.newest_first();
+        assert_eq!(entries.len(), HISTORY_LIMIT);
+        assert_eq!(entries[0].file, format!("{}.tar.gz", HISTORY_LIMIT + 4));
+        assert_eq!(entries.last().unwrap().file, "5.tar.gz");
+    }
+
+    #[test]
+    fn history_round_trips_through_file() {
+        let dir = TestDir::new("download-history");
+        let path = dir.path().join("download_history.json");
+
+        assert!(DownloadHistory::load_from(&path).entries.is_empty());
+        let mut history = DownloadHistory::default();
+        history.push(entry("jdk.tar.gz"));
+        history.push(DownloadHistoryEntry {
+            outcome: DownloadHistoryOutcome::Failed,
+            error: Some("404 Not Found".to_string()),
+            ..entry("jre.zip")
+        });
+        history.save_to(&path).unwrap();
+
+        let loaded = DownloadHistory::load_from(&path).newest_first();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].file, "jre.zip");
+        assert_eq!(loaded[0].error.as_deref(), Some("404 Not Found"));
+        assert_eq!(loaded[1].outcome, DownloadHistoryOutcome::Completed);
+    }
+}
@@ -1,14 +1,110 @@
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use sha1::Digest as Sha1Digest;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use tauri::{AppHandle, Emitter, Manager, Window};
 use tokio::io::{AsyncSeekExt, AsyncWriteExt};
-use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 use ts_rs::TS;
 
+use crate::core::download_history::{
+    self, DownloadHistoryEntry, DownloadHistoryKind, DownloadHistoryOutcome,
+};
+use crate::utils::error::{self, ErrorSource};
+
+/// Why a download failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[serde(rename_all_fields = "camelCase")]
+#[ts(export, export_to = "downloader.ts")]
+pub enum DownloadError {
+    /// Reading or writing a local file failed
+    #[error("{message}")]
+    Io {
+        message: String,
+        #[source]
+        #[serde(rename = "details", default)]
+        #[ts(type = "Array<string> | null")]
+        source: Option<ErrorSource>,
+    },
+    /// The request failed, or the server answered with an error status
+    #[error("{message}")]
+    Request {
+        url: String,
+        status: Option<u16>,
+        message: String,
+        #[source]
+        #[serde(rename = "details", default)]
+        #[ts(type = "Array<string> | null")]
+        source: Option<ErrorSource>,
+    },
+    /// The server sent something other than the file, e.g. an error page
+    #[error("{message}")]
+    InvalidContent { message: String },
+    /// `actual` is `None` when the file was checked without computing its hash
+    #[error(
+        "Checksum mismatch: expected {expected}{}",
+        actual.as_ref().map(|actual| format!(", got {}", actual)).unwrap_or_default()
+    )]
+    ChecksumMismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+    #[error("Download cancelled")]
+    Cancelled,
+    #[error("No data received for {}s", *timeout_ms as f32 / 1000.0)]
+    Stalled {
+        #[ts(type = "number")]
+        timeout_ms: u64,
+    },
+    /// The transfer ended before all `expected` bytes arrived
+    #[error("Download incomplete: received {actual} of {expected} bytes")]
+    SizeMismatch {
+        #[ts(type = "number")]
+        expected: u64,
+        #[ts(type = "number")]
+        actual: u64,
+    },
+}
+
+impl DownloadError {
+    /// `error` while `context`, e.g. "Failed to open part file"
+    pub(crate) fn io(context: &str, error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        DownloadError::Io {
+            message: context.to_string(),
+            source: Some(ErrorSource::new(error)),
+        }
+    }
+
+    /// The server at `url` answered, but not with the file
+    fn request(url: &str, status: Option<reqwest::StatusCode>, message: String) -> Self {
+        DownloadError::Request {
+            url: url.to_string(),
+            status: status.map(|status| status.as_u16()),
+            message,
+            source: None,
+        }
+    }
+
+    /// The request to `url` failed with `error` before an answer arrived
+    fn request_failed(url: &str, context: &str, error: reqwest::Error) -> Self {
+        DownloadError::Request {
+            url: url.to_string(),
+            status: error.status().map(|status| status.as_u16()),
+            message: context.to_string(),
+            source: Some(ErrorSource::new(error)),
+        }
+    }
+
+    fn invalid_content(message: impl Into<String>) -> Self {
+        DownloadError::InvalidContent {
+            message: message.into(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "downloader.ts")]
@@ -19,6 +115,60 @@ pub struct DownloadTask {
     pub sha1: Option<String>,
     #[serde(default)]
     pub sha256: Option<String>,
+    #[serde(default)]
+    pub sha512: Option<String>,
+    /// Size the file must have, when the manifest lists it
+    #[serde(default)]
+    pub expected_size: Option<u64>,
+    /// Mirrors tried in order when `url` fails for good
+    #[serde(default)]
+    pub fallback_urls: Vec<String>,
+    /// Tasks with a higher priority are started first within a batch
+    #[serde(default)]
+    pub priority: u8,
+}
+
+/// Priorities used when preparing a launch, so cancelling early still leaves the
+/// files that matter most
+pub const PRIORITY_CLIENT_JAR: u8 = 2;
+pub const PRIORITY_LIBRARY: u8 = 1;
+pub const PRIORITY_ASSET: u8 = 0;
+
+impl DownloadTask {
+    /// Identifies the task in progress events and for `DownloadController::cancel_task`
+    pub fn id(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    /// The primary URL followed by the fallbacks
+    fn urls(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.url.as_str()).chain(self.fallback_urls.iter().map(String::as_str))
+    }
+
+    /// Apply the first matching `(prefix, replacement)` rule to the primary URL.
+    ///
+    /// The original URL is kept as the first fallback so a broken mirror doesn't
+    /// break the download.
+    fn apply_rewrite_rules(&mut self, rules: &[(String, String)]) {
+        let Some((prefix, replacement)) = rules
+            .iter()
+            .find(|(prefix, _)| !prefix.is_empty() && self.url.starts_with(prefix.as_str()))
+        else {
+            return;
+        };
+        let rewritten = format!("{}{}", replacement, &self.url[prefix.len()..]);
+        let original = std::mem::replace(&mut self.url, rewritten);
+        self.fallback_urls.insert(0, original);
+    }
+}
+
+/// URL prefix rewrites applied to every task passed to `download_files`
+static URL_REWRITE_RULES: std::sync::RwLock<Vec<(String, String)>> =
+    std::sync::RwLock::new(Vec::new());
+
+/// Replace the URL rewrite rules, e.g. to send a whole host to a mirror
+pub fn set_url_rewrite_rules(rules: Vec<(String, String)>) {
+    *URL_REWRITE_RULES.write().unwrap() = rules;
 }
 
 /// Metadata for resumable downloads stored in .part.meta file
@@ -32,6 +182,10 @@ pub struct DownloadMetadata {
     pub downloaded_bytes: u64,
     pub checksum: Option<String>,
     pub timestamp: u64,
+    /// Whether the server advertised `Accept-Ranges: bytes` for this URL
+    #[serde(default)]
+    pub accept_ranges: bool,
+    #[serde(default)]
     pub segments: Vec<DownloadSegment>,
 }
 
@@ -46,17 +200,38 @@ pub struct DownloadSegment {
     pub completed: bool,
 }
 
+/// Statuses a `JavaDownloadProgress` can carry
+pub const JAVA_DOWNLOAD_STATUSES: &[&str] = &[
+    "Downloading",
+    "Extracting",
+    "Verifying",
+    "Completed",
+    "Paused",
+    "Error",
+];
+
+/// `message_key` of a `JavaDownloadProgress` status
+pub(crate) fn java_download_status_key(status: &str) -> String {
+    debug_assert!(JAVA_DOWNLOAD_STATUSES.contains(&status));
+    crate::core::i18n::status_key("java.download", status)
+}
+
 /// Progress event for Java download
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "downloader.ts")]
 pub struct JavaDownloadProgress {
+    /// Id of the install or resume operation this event belongs to
+    pub id: String,
     pub file_name: String,
     pub downloaded_bytes: u64,
     pub total_bytes: u64,
     pub speed_bytes_per_sec: u64,
     pub eta_seconds: u64,
-    pub status: String, // "Downloading", "Extracting", "Verifying", "Completed", "Paused", "Error"
+    /// One of `JAVA_DOWNLOAD_STATUSES`
+    pub status: String,
+    /// Translatable form of `status`, e.g. `java.download.extracting`
+    pub message_key: String,
     pub percentage: f32,
 }
 
@@ -73,53 +248,205 @@ pub struct PendingJavaDownload {
     pub checksum: Option<String>,
     pub install_path: String,
     pub created_at: u64,
+    /// Failed resume attempts so far
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Unix time of the last failed attempt, 0 if none
+    #[serde(default)]
+    pub last_attempt_at: u64,
 }
 
+/// Current shape of `download_queue.json`; bump it and migrate in `load_from` on changes
+const DOWNLOAD_QUEUE_VERSION: u32 = 1;
+
 /// Download queue for persistence
-#[derive(Debug, Clone, Serialize, Deserialize, Default, TS)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "downloader.ts")]
 pub struct DownloadQueue {
+    /// Schema version; files written before versioning read as 0
+    #[serde(default)]
+    pub version: u32,
     pub pending_downloads: Vec<PendingJavaDownload>,
 }
 
+impl Default for DownloadQueue {
+    fn default() -> Self {
+        Self {
+            version: DOWNLOAD_QUEUE_VERSION,
+            pending_downloads: Vec::new(),
+        }
+    }
+}
+
 impl DownloadQueue {
-    /// Load download queue from file
+    fn path(app_handle: &AppHandle) -> Result<PathBuf, DownloadError> {
+        crate::core::paths::try_data_root(app_handle)
+            .map(|root| root.join("download_queue.json"))
+            .map_err(|e| DownloadError::io("No directory for the download queue", e))
+    }
+
+    /// Load download queue from file; empty when there is no data directory
     pub fn load(app_handle: &AppHandle) -> Self {
-        let queue_path = app_handle
-            .path()
-            .app_data_dir()
-            .unwrap()
-            .join("download_queue.json");
-        if queue_path.exists() {
-            if let Ok(content) = std::fs::read_to_string(&queue_path) {
-                if let Ok(queue) = serde_json::from_str(&content) {
-                    return queue;
+        Self::path(app_handle)
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    /// Load the queue at `path`. A file that can't be parsed is moved aside to
+    /// `<name>.corrupt-<timestamp>` and an empty queue is used instead.
+    fn load_from(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match serde_json::from_str::<Self>(&content) {
+            Ok(mut queue) => {
+                // Version 0 has the same shape as version 1
+                if queue.version < DOWNLOAD_QUEUE_VERSION {
+                    queue.version = DOWNLOAD_QUEUE_VERSION;
                 }
+                queue
+            }
+            Err(e) => {
+                let timestamp = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let mut name = path.file_name().unwrap_or_default().to_os_string();
+                name.push(format!(".corrupt-{}", timestamp));
+                let backup = path.with_file_name(name);
+                match std::fs::rename(path, &backup) {
+                    Ok(()) => log::warn!(
+                        "Download queue {} is corrupt ({}), moved it to {}",
+                        path.display(),
+                        e,
+                        backup.display()
+                    ),
+                    Err(rename_err) => log::warn!(
+                        "Download queue {} is corrupt ({}) and could not be moved: {}",
+                        path.display(),
+                        e,
+                        rename_err
+                    ),
+                }
+                Self::default()
             }
         }
-        Self::default()
     }
 
     /// Save download queue to file
-    pub fn save(&self, app_handle: &AppHandle) -> Result<(), String> {
-        let queue_path = app_handle
-            .path()
-            .app_data_dir()
-            .unwrap()
-            .join("download_queue.json");
-        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
-        std::fs::write(&queue_path, content).map_err(|e| e.to_string())?;
-        Ok(())
+    pub fn save(&self, app_handle: &AppHandle) -> Result<(), DownloadError> {
+        self.save_to(&Self::path(app_handle)?)
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), DownloadError> {
+        let queue = Self {
+            version: DOWNLOAD_QUEUE_VERSION,
+            pending_downloads: self.pending_downloads.clone(),
+        };
+        let content = serde_json::to_string_pretty(&queue)
+            .map_err(|e| DownloadError::io("Failed to serialize the download queue", e))?;
+        crate::core::java::persistence::write_file_atomic(path, content.as_bytes())
+            .map_err(|e| DownloadError::io("Failed to save the download queue", e))
     }
 
     /// Add a pending download
     pub fn add(&mut self, download: PendingJavaDownload) {
-        // Remove existing download for same version/type
+        // Update an existing download for same version/type, keeping its failure history
+        if let Some(existing) = self.pending_downloads.iter_mut().find(|d| {
+            d.major_version == download.major_version && d.image_type == download.image_type
+        }) {
+            existing.download_url = download.download_url;
+            existing.file_name = download.file_name;
+            existing.file_size = download.file_size;
+            existing.checksum = download.checksum;
+            existing.install_path = download.install_path;
+            existing.created_at = download.created_at;
+            return;
+        }
+        self.pending_downloads.push(download);
+    }
+
+    /// Drop entries older than `max_age`, entries whose install location is gone
+    /// and duplicates of the same version/type. Returns how many were removed.
+    pub fn prune(
+        app_handle: &AppHandle,
+        max_age: std::time::Duration,
+    ) -> Result<usize, DownloadError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Self::prune_file(&Self::path(app_handle)?, now, max_age)
+    }
+
+    fn prune_file(
+        path: &Path,
+        now: u64,
+        max_age: std::time::Duration,
+    ) -> Result<usize, DownloadError> {
+        let mut queue = Self::load_from(path);
+        let removed = queue.prune_entries(now, max_age);
+        if removed > 0 {
+            queue.save_to(path)?;
+        }
+        Ok(removed)
+    }
+
+    fn prune_entries(&mut self, now: u64, max_age: std::time::Duration) -> usize {
+        let before = self.pending_downloads.len();
+        let oldest = now.saturating_sub(max_age.as_secs());
+
+        // Newest first, so the first entry of each version/type is the one kept
+        self.pending_downloads
+            .sort_by_key(|d| std::cmp::Reverse(d.created_at));
+        let mut seen = std::collections::HashSet::new();
         self.pending_downloads.retain(|d| {
-            !(d.major_version == download.major_version && d.image_type == download.image_type)
+            let parent_exists = Path::new(&d.install_path)
+                .parent()
+                .is_some_and(|parent| parent.exists());
+            d.created_at >= oldest
+                && parent_exists
+                && seen.insert((d.major_version, d.image_type.clone()))
         });
-        self.pending_downloads.push(download);
+        self.pending_downloads.sort_by_key(|d| d.created_at);
+
+        before - self.pending_downloads.len()
+    }
+
+    /// Count a failed attempt, returning the updated entry
+    pub fn record_failure(
+        &mut self,
+        major_version: u32,
+        image_type: &str,
+        error: String,
+        at: u64,
+    ) -> Option<&PendingJavaDownload> {
+        let entry = self
+            .pending_downloads
+            .iter_mut()
+            .find(|d| d.major_version == major_version && d.image_type == image_type)?;
+        entry.attempts += 1;
+        entry.last_error = Some(error);
+        entry.last_attempt_at = at;
+        Some(entry)
+    }
+
+    /// Forget earlier failures so the download is tried again
+    pub fn reset_attempts(&mut self, major_version: u32, image_type: &str) -> bool {
+        let Some(entry) = self
+            .pending_downloads
+            .iter_mut()
+            .find(|d| d.major_version == major_version && d.image_type == image_type)
+        else {
+            return false;
+        };
+        entry.attempts = 0;
+        entry.last_error = None;
+        entry.last_attempt_at = 0;
+        true
     }
 
     /// Remove a completed or cancelled download
@@ -129,538 +456,4561 @@ impl DownloadQueue {
     }
 }
 
-/// Global cancel flag for Java downloads
-pub static JAVA_DOWNLOAD_CANCELLED: AtomicBool = AtomicBool::new(false);
-
-/// Reset the cancel flag
-pub fn reset_java_download_cancel() {
-    JAVA_DOWNLOAD_CANCELLED.store(false, Ordering::SeqCst);
+/// Task id under which a Java download registers with `DOWNLOAD_CONTROLLER`
+fn java_task_id(download_id: &str) -> String {
+    format!("java-runtime:{}", download_id)
 }
 
-/// Cancel the current Java download
-pub fn cancel_java_download() {
-    JAVA_DOWNLOAD_CANCELLED.store(true, Ordering::SeqCst);
+/// Keeps a Java install or resume registered with `DOWNLOAD_CONTROLLER` until dropped.
+///
+/// The token lives for the whole operation rather than one download, so a cancel
+/// that arrives between phases is not lost and cannot leak into a later download.
+pub struct JavaDownloadGuard {
+    task_id: String,
 }
 
-/// Check if download is cancelled
-pub fn is_java_download_cancelled() -> bool {
-    JAVA_DOWNLOAD_CANCELLED.load(Ordering::SeqCst)
+impl JavaDownloadGuard {
+    pub fn register(download_id: &str) -> Self {
+        let task_id = java_task_id(download_id);
+        DOWNLOAD_CONTROLLER.register_task(&task_id);
+        Self { task_id }
+    }
 }
 
-/// Determine optimal segment count based on file size
-fn get_segment_count(file_size: u64) -> usize {
-    if file_size < 20 * 1024 * 1024 {
-        1 // < 20MB: single segment
-    } else if file_size < 100 * 1024 * 1024 {
-        4 // 20-100MB: 4 segments
-    } else {
-        8 // > 100MB: 8 segments
+impl Drop for JavaDownloadGuard {
+    fn drop(&mut self) {
+        DOWNLOAD_CONTROLLER.finish_task(&self.task_id);
     }
 }
 
-/// Download a large file with resume support and progress events
-pub async fn download_with_resume(
-    app_handle: &AppHandle,
-    url: &str,
-    dest_path: &PathBuf,
-    checksum: Option<&str>,
-    total_size: u64,
-) -> Result<(), String> {
-    reset_java_download_cancel();
-
-    let part_path = dest_path.with_extension(
-        dest_path
-            .extension()
-            .map(|e| format!("{}.part", e.to_string_lossy()))
-            .unwrap_or_else(|| "part".to_string()),
-    );
-    let meta_path = PathBuf::from(format!("{}.meta", part_path.display()));
-    let file_name = dest_path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
-
-    // Load or create metadata
-    let mut metadata = if meta_path.exists() {
-        let content = tokio::fs::read_to_string(&meta_path)
-            .await
-            .map_err(|e| e.to_string())?;
-        serde_json::from_str(&content)
-            .unwrap_or_else(|_| create_new_metadata(url, &file_name, total_size, checksum))
-    } else {
-        create_new_metadata(url, &file_name, total_size, checksum)
-    };
+/// Cancel a running Java download, returning whether it was known
+pub fn cancel_java_download(download_id: &str) -> bool {
+    DOWNLOAD_CONTROLLER.cancel_task(&java_task_id(download_id))
+}
 
-    // Create parent directory
-    if let Some(parent) = dest_path.parent() {
-        tokio::fs::create_dir_all(parent)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
+/// Cancel every running Java download, returning how many there were
+pub fn cancel_all_java_downloads() -> usize {
+    DOWNLOAD_CONTROLLER.cancel_tasks_with_prefix(&java_task_id(""))
+}
 
-    // Open or create part file
-    let file = tokio::fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .read(true)
-        .truncate(false)
-        .open(&part_path)
-        .await
-        .map_err(|e| format!("Failed to open part file: {}", e))?;
+/// Check if download is cancelled
+pub fn is_java_download_cancelled(download_id: &str) -> bool {
+    DOWNLOAD_CONTROLLER.is_task_cancelled(&java_task_id(download_id))
+}
 
-    let file = Arc::new(tokio::sync::Mutex::new(file));
-    let client = reqwest::Client::new();
-    let progress = Arc::new(AtomicU64::new(metadata.downloaded_bytes));
-    let start_time = std::time::Instant::now();
-    let last_progress_bytes = Arc::new(AtomicU64::new(metadata.downloaded_bytes));
+/// Token bucket shared by download loops to cap total bandwidth.
+///
+/// Each chunk is charged after it arrives; when the bucket goes into debt the caller
+/// sleeps until it is repaid. The limit can be changed at any time and applies to the
+/// next chunk of every in-flight download.
+pub struct RateLimiter {
+    bucket: std::sync::Mutex<TokenBucket>,
+}
 
-    // Download segments concurrently
-    let segment_count = metadata.segments.len();
-    let semaphore = Arc::new(Semaphore::new(segment_count.min(8)));
-    let mut handles = Vec::new();
+struct TokenBucket {
+    bytes_per_sec: Option<u64>,
+    tokens: f64,
+    last_refill: Option<std::time::Instant>,
+}
 
-    for (idx, segment) in metadata.segments.iter().enumerate() {
-        if segment.completed {
-            continue;
+impl TokenBucket {
+    fn refill(&mut self, rate: u64) {
+        let now = std::time::Instant::now();
+        if let Some(last) = self.last_refill {
+            let elapsed = now.duration_since(last).as_secs_f64();
+            // Allow at most one second of burst after an idle period
+            self.tokens = (self.tokens + elapsed * rate as f64).min(rate as f64);
         }
+        self.last_refill = Some(now);
+    }
+}
 
-        let client = client.clone();
-        let url = url.to_string();
-        let file = file.clone();
-        let progress = progress.clone();
-        let semaphore = semaphore.clone();
-        let segment_start = segment.start + segment.downloaded;
-        let segment_end = segment.end;
-        let app_handle = app_handle.clone();
-        let file_name = file_name.clone();
-        let last_progress_bytes = last_progress_bytes.clone();
-
-        let handle = tokio::spawn(async move {
-            let _permit = semaphore.acquire().await.unwrap();
-
-            if is_java_download_cancelled() {
-                return Err("Download cancelled".to_string());
-            }
-
-            // Send Range request
-            let range = format!("bytes={}-{}", segment_start, segment_end);
-            let response = client
-                .get(&url)
-                .header("Range", &range)
-                .send()
-                .await
-                .map_err(|e| format!("Request failed: {}", e))?;
+impl RateLimiter {
+    pub const fn unlimited() -> Self {
+        Self {
+            bucket: std::sync::Mutex::new(TokenBucket {
+                bytes_per_sec: None,
+                tokens: 0.0,
+                last_refill: None,
+            }),
+        }
+    }
 
-            if !response.status().is_success()
-                && response.status() != reqwest::StatusCode::PARTIAL_CONTENT
-            {
-                return Err(format!("Server returned error: {}", response.status()));
-            }
+    /// Change the limit; `None` (or zero) means unlimited
+    pub fn set_limit(&self, bytes_per_sec: Option<u64>) {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.bytes_per_sec = bytes_per_sec.filter(|&rate| rate > 0);
+        bucket.tokens = 0.0;
+        bucket.last_refill = None;
+    }
 
-            let mut stream = response.bytes_stream();
-            let mut current_pos = segment_start;
+    /// Account for `bytes` just received, waiting as long as the limit requires
+    pub async fn acquire(&self, bytes: u64) {
+        {
+            let mut bucket = self.bucket.lock().unwrap();
+            let Some(rate) = bucket.bytes_per_sec else {
+                return;
+            };
+            bucket.refill(rate);
+            bucket.tokens -= bytes as f64;
+        }
 
-            while let Some(chunk_result) = stream.next().await {
-                if is_java_download_cancelled() {
-                    return Err("Download cancelled".to_string());
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let Some(rate) = bucket.bytes_per_sec else {
+                    return;
+                };
+                bucket.refill(rate);
+                if bucket.tokens >= 0.0 {
+                    return;
                 }
+                // Re-check in short steps so a new limit takes effect promptly
+                (-bucket.tokens / rate as f64).min(0.1)
+            };
+            tokio::time::sleep(std::time::Duration::from_secs_f64(wait)).await;
+        }
+    }
+}
 
-                let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
-                let chunk_len = chunk.len() as u64;
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
 
-                // Write to file at correct position
-                {
-                    let mut file_guard = file.lock().await;
-                    file_guard
-                        .seek(std::io::SeekFrom::Start(current_pos))
-                        .await
-                        .map_err(|e| format!("Seek error: {}", e))?;
-                    file_guard
-                        .write_all(&chunk)
-                        .await
-                        .map_err(|e| format!("Write error: {}", e))?;
-                }
+/// Bandwidth limit shared by every download in the launcher
+pub static DOWNLOAD_RATE_LIMITER: RateLimiter = RateLimiter::unlimited();
 
-                current_pos += chunk_len;
-                let total_downloaded = progress.fetch_add(chunk_len, Ordering::AcqRel) + chunk_len;
+/// Default minimum time between two progress events for the same file
+pub const PROGRESS_EMIT_INTERVAL_MS: u64 = 200;
+/// Default number of bytes after which a progress event is sent regardless of time
+pub const PROGRESS_EMIT_BYTES: u64 = 4 * 1024 * 1024;
 
-                // Emit progress event (throttled)
-                let last_bytes = last_progress_bytes.load(Ordering::Acquire);
-                if total_downloaded - last_bytes > 100 * 1024 || total_downloaded >= total_size {
-                    last_progress_bytes.store(total_downloaded, Ordering::Release);
+static PROGRESS_INTERVAL_MS: AtomicU64 = AtomicU64::new(PROGRESS_EMIT_INTERVAL_MS);
+static PROGRESS_BYTES: AtomicU64 = AtomicU64::new(PROGRESS_EMIT_BYTES);
 
-                    let elapsed = start_time.elapsed().as_secs_f64();
-                    let speed = if elapsed > 0.0 {
-                        (total_downloaded as f64 / elapsed) as u64
-                    } else {
-                        0
-                    };
-                    let remaining = total_size.saturating_sub(total_downloaded);
-                    let eta = if speed > 0 { remaining / speed } else { 0 };
-                    let percentage = (total_downloaded as f32 / total_size as f32) * 100.0;
-
-                    let _ = app_handle.emit(
-                        "java-download-progress",
-                        JavaDownloadProgress {
-                            file_name: file_name.clone(),
-                            downloaded_bytes: total_downloaded,
-                            total_bytes: total_size,
-                            speed_bytes_per_sec: speed,
-                            eta_seconds: eta,
-                            status: "Downloading".to_string(),
-                            percentage,
-                        },
-                    );
-                }
-            }
+/// Override the progress event thresholds; `None` restores the defaults
+pub fn set_progress_throttle(interval_ms: Option<u64>, bytes: Option<u64>) {
+    PROGRESS_INTERVAL_MS.store(
+        interval_ms.unwrap_or(PROGRESS_EMIT_INTERVAL_MS),
+        Ordering::Relaxed,
+    );
+    PROGRESS_BYTES.store(bytes.unwrap_or(PROGRESS_EMIT_BYTES), Ordering::Relaxed);
+}
 
-            Ok::<usize, String>(idx)
-        });
+fn progress_interval() -> std::time::Duration {
+    std::time::Duration::from_millis(PROGRESS_INTERVAL_MS.load(Ordering::Relaxed))
+}
 
-        handles.push(handle);
-    }
+/// Rate-limits the progress events of one file to every interval or every few megabytes
+struct ProgressThrottle {
+    interval: std::time::Duration,
+    bytes: u64,
+    last_emit: Option<(std::time::Instant, u64)>,
+}
 
-    // Wait for all segments
-    let mut all_success = true;
-    for handle in handles {
-        match handle.await {
-            Ok(Ok(idx)) => {
-                metadata.segments[idx].completed = true;
-            }
-            Ok(Err(e)) => {
-                all_success = false;
-                if e.contains("cancelled") {
-                    // Save progress for resume
-                    metadata.downloaded_bytes = progress.load(Ordering::Acquire);
-                    let meta_content =
-                        serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
-                    tokio::fs::write(&meta_path, meta_content).await.ok();
-                    return Err(e);
-                }
-            }
-            Err(e) => {
-                all_success = false;
-                eprintln!("Segment task panicked: {}", e);
-            }
+impl ProgressThrottle {
+    fn new() -> Self {
+        Self {
+            interval: progress_interval(),
+            bytes: PROGRESS_BYTES.load(Ordering::Relaxed),
+            last_emit: None,
         }
     }
 
-    if !all_success {
-        // Save progress
-        metadata.downloaded_bytes = progress.load(Ordering::Acquire);
-        let meta_content = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
-        tokio::fs::write(&meta_path, meta_content).await.ok();
-        return Err("Some segments failed".to_string());
+    /// Whether an update at `downloaded` bytes should be sent; the first and `last` always are
+    fn ready(&mut self, downloaded: u64, last: bool) -> bool {
+        let now = std::time::Instant::now();
+        let due = match self.last_emit {
+            None => true,
+            Some((time, bytes)) => {
+                last || now.saturating_duration_since(time) >= self.interval
+                    || downloaded.abs_diff(bytes) >= self.bytes
+            }
+        };
+        if due {
+            self.last_emit = Some((now, downloaded));
+        }
+        due
     }
+}
 
-    // Verify checksum if provided
-    if let Some(expected) = checksum {
-        let _ = app_handle.emit(
-            "java-download-progress",
-            JavaDownloadProgress {
-                file_name: file_name.clone(),
-                downloaded_bytes: total_size,
-                total_bytes: total_size,
-                speed_bytes_per_sec: 0,
-                eta_seconds: 0,
-                status: "Verifying".to_string(),
-                percentage: 100.0,
-            },
-        );
+/// Path of the in-progress file for `dest_path`, i.e. `<file>.part`
+fn part_path_for(dest_path: &Path) -> PathBuf {
+    let mut name = dest_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest_path.with_file_name(name)
+}
 
-        let data = tokio::fs::read(&part_path)
-            .await
-            .map_err(|e| format!("Failed to read file for verification: {}", e))?;
+const HTML_INSTEAD_OF_FILE: &str =
+    "Received an HTML page instead of the file — are you behind a captive portal?";
 
-        if !verify_checksum(&data, Some(expected), None) {
-            // Checksum failed, delete files and retry
-            tokio::fs::remove_file(&part_path).await.ok();
-            tokio::fs::remove_file(&meta_path).await.ok();
-            return Err("Checksum verification failed".to_string());
-        }
+/// Leading bytes an archive must start with, judging by its file name
+fn expected_magic(path: &Path) -> Option<&'static [u8]> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".jar") || name.ends_with(".zip") {
+        Some(b"PK\x03\x04")
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(b"\x1f\x8b")
+    } else {
+        None
     }
+}
 
-    // Rename part file to final destination
-    tokio::fs::rename(&part_path, dest_path)
-        .await
-        .map_err(|e| format!("Failed to rename file: {}", e))?;
+/// Whether a file with this name can never legitimately be an HTML page
+fn expects_binary(path: &Path) -> bool {
+    let binary_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ["so", "dll", "dylib", "exe", "msi", "xz"]
+                .iter()
+                .any(|binary| ext.eq_ignore_ascii_case(binary))
+        });
+    binary_extension || expected_magic(path).is_some()
+}
 
-    // Clean up metadata file
-    tokio::fs::remove_file(&meta_path).await.ok();
+fn is_html_response(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim_start().to_ascii_lowercase().starts_with("text/html"))
+}
 
-    Ok(())
+fn looks_like_html(head: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(head)
+        .trim_start()
+        .to_ascii_lowercase();
+    text.starts_with("<!doctype html") || text.starts_with("<html")
 }
 
-/// Create new download metadata with segments
-fn create_new_metadata(
-    url: &str,
-    file_name: &str,
-    total_size: u64,
-    checksum: Option<&str>,
-) -> DownloadMetadata {
-    let segment_count = get_segment_count(total_size);
-    let segment_size = total_size / segment_count as u64;
-    let mut segments = Vec::new();
-
-    for i in 0..segment_count {
-        let start = i as u64 * segment_size;
-        let end = if i == segment_count - 1 {
-            total_size - 1
+/// Cheap sanity checks on a finished download before its checksum is verified:
+/// `path` must not be empty (unless `allow_empty`) and, if `final_path` names an
+/// archive, must start with that archive's magic bytes.
+async fn check_downloaded_file(
+    path: &Path,
+    final_path: &Path,
+    allow_empty: bool,
+) -> Result<(), DownloadError> {
+    use tokio::io::AsyncReadExt;
+
+    let file_name = final_path.file_name().unwrap_or_default().to_string_lossy();
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| DownloadError::io("Failed to read downloaded file", e))?;
+    let mut head = Vec::with_capacity(512);
+    file.take(512)
+        .read_to_end(&mut head)
+        .await
+        .map_err(|e| DownloadError::io("Failed to read downloaded file", e))?;
+
+    if head.is_empty() {
+        return if allow_empty {
+            Ok(())
         } else {
-            (i as u64 + 1) * segment_size - 1
+            Err(DownloadError::invalid_content(format!(
+                "Downloaded file {} is empty",
+                file_name
+            )))
         };
-        segments.push(DownloadSegment {
-            start,
-            end,
-            downloaded: 0,
-            completed: false,
-        });
     }
-
-    DownloadMetadata {
-        url: url.to_string(),
-        file_name: file_name.to_string(),
-        total_size,
-        downloaded_bytes: 0,
-        checksum: checksum.map(|s| s.to_string()),
-        timestamp: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-        segments,
+    if let Some(magic) = expected_magic(final_path)
+        && !head.starts_with(magic)
+    {
+        if looks_like_html(&head) {
+            return Err(DownloadError::invalid_content(HTML_INSTEAD_OF_FILE));
+        }
+        return Err(DownloadError::invalid_content(format!(
+            "Downloaded file {} is not a valid archive",
+            file_name
+        )));
     }
+    Ok(())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
-#[serde(rename_all = "camelCase")]
-#[ts(export, export_to = "downloader.ts")]
-pub struct ProgressEvent {
-    pub file: String,
-    pub downloaded: u64,
-    pub total: u64,
-    pub status: String, // "Downloading", "Verifying", "Finished", "Error"
-    pub completed_files: usize,
-    pub total_files: usize,
-    pub total_downloaded_bytes: u64,
+/// Write buffer for batch downloads, so small network chunks don't each cost a syscall
+const WRITE_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Java archives are large enough to be worth a bigger buffer
+const ARCHIVE_WRITE_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Partial files older than this are left over from a crash and can go
+const STALE_PART_FILE_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Delete `.part` files in `dir` not modified within `max_age`, keeping resumable ones
+/// (those with a metadata sidecar). Returns how many were removed.
+async fn remove_stale_part_files(dir: &Path, max_age: std::time::Duration) -> usize {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return 0;
+    };
+    let mut removed = 0;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "part") || meta_path_for(&path).exists() {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .await
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if stale && tokio::fs::remove_file(&path).await.is_ok() {
+            removed += 1;
+        }
+    }
+    removed
 }
 
-/// calculate SHA256 hash of data
-pub fn compute_sha256(data: &[u8]) -> String {
-    let mut hasher = sha2::Sha256::new();
-    hasher.update(data);
-    hex::encode(hasher.finalize())
+/// Path of the sidecar metadata stored next to a `.part` file
+fn meta_path_for(part_path: &Path) -> PathBuf {
+    let mut name = part_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".meta");
+    part_path.with_file_name(name)
 }
 
-/// calculate SHA1 hash of data
-pub fn compute_sha1(data: &[u8]) -> String {
-    let mut hasher = sha1::Sha1::new();
-    hasher.update(data);
-    hex::encode(hasher.finalize())
+async fn save_metadata(meta_path: &Path, metadata: &DownloadMetadata) -> Result<(), DownloadError> {
+    let content = serde_json::to_string_pretty(metadata)
+        .map_err(|e| DownloadError::io("Failed to serialize download metadata", e))?;
+    tokio::fs::write(meta_path, content)
+        .await
+        .map_err(|e| DownloadError::io("Failed to write download metadata", e))
 }
 
-/// verify file checksum, prefer SHA256, fallback to SHA1
-pub fn verify_checksum(data: &[u8], sha256: Option<&str>, sha1: Option<&str>) -> bool {
-    if let Some(expected) = sha256 {
-        return compute_sha256(data) == expected;
-    }
-    if let Some(expected) = sha1 {
-        return compute_sha1(data) == expected;
+/// Load the sidecar for a previous attempt, discarding it if it describes a different download
+async fn load_metadata(
+    meta_path: &Path,
+    url: &str,
+    checksum: Option<&str>,
+) -> Option<DownloadMetadata> {
+    let content = tokio::fs::read_to_string(meta_path).await.ok()?;
+    let metadata: DownloadMetadata = serde_json::from_str(&content).ok()?;
+    if metadata.url != url || metadata.checksum.as_deref() != checksum {
+        return None;
     }
-    // No checksum provided, default to true
-    true
+    Some(metadata)
 }
 
-/// Snapshot of global progress state
-struct ProgressSnapshot {
-    completed_files: usize,
-    total_files: usize,
-    total_downloaded_bytes: u64,
+/// Parse a `Content-Range: bytes <start>-<end>/<total>` header into `(start, total)`
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _end) = span.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let total = match total.trim() {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((start, total))
 }
 
-/// Centralized progress tracking with atomic counters
-struct GlobalProgress {
-    completed_files: AtomicUsize,
-    total_downloaded_bytes: AtomicU64,
-    total_files: usize,
+/// Check that a 206 response actually continues at `offset` of the file we expect
+fn content_range_matches(response: &reqwest::Response, offset: u64, total_size: u64) -> bool {
+    let Some((start, total)) = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range)
+    else {
+        return false;
+    };
+    start == offset && (total_size == 0 || total.is_none_or(|total| total == total_size))
 }
 
-impl GlobalProgress {
-    fn new(total_files: usize) -> Self {
-        Self {
-            completed_files: AtomicUsize::new(0),
-            total_downloaded_bytes: AtomicU64::new(0),
-            total_files,
+/// Feed the first `len` bytes of a file into `hasher` without loading it into memory
+async fn hash_file_prefix(
+    path: &Path,
+    len: u64,
+    hasher: &mut sha2::Sha256,
+) -> Result<(), DownloadError> {
+    use tokio::io::AsyncReadExt;
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| DownloadError::io("Failed to read file for verification", e))?;
+    let mut reader = file.take(len);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .await
+            .map_err(|e| DownloadError::io("Failed to read file for verification", e))?;
+        if n == 0 {
+            break;
         }
+        hasher.update(&buf[..n]);
     }
+    Ok(())
+}
 
-    /// Get current progress snapshot without modification
-    fn snapshot(&self) -> ProgressSnapshot {
-        ProgressSnapshot {
-            completed_files: self.completed_files.load(Ordering::Acquire),
-            total_files: self.total_files,
-            total_downloaded_bytes: self.total_downloaded_bytes.load(Ordering::Acquire),
+/// Compute the SHA256 of a file without loading it into memory
+async fn compute_file_sha256(path: &Path) -> Result<String, DownloadError> {
+    let mut hasher = sha2::Sha256::new();
+    hash_file_prefix(path, u64::MAX, &mut hasher).await?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Files smaller than this are never split into segments
+pub const SEGMENTED_DOWNLOAD_THRESHOLD: u64 = 50 * 1024 * 1024;
+
+/// Attempts per segment before a segmented download gives up
+const SEGMENT_RETRIES: usize = 3;
+
+/// Download a large file with resume support and progress events.
+///
+/// `segments` greater than one opts into a multi-connection download for files over
+/// [`SEGMENTED_DOWNLOAD_THRESHOLD`]; the single-stream path is used otherwise and
+/// whenever the server does not support ranges.
+#[allow(clippy::too_many_arguments)]
+pub async fn download_with_resume(
+    app_handle: &AppHandle,
+    download_id: &str,
+    url: &str,
+    dest_path: &Path,
+    checksum: Option<&str>,
+    total_size: u64,
+    segments: usize,
+) -> Result<(), DownloadError> {
+    let client = crate::core::http::client();
+    let started = std::time::Instant::now();
+    let tracker = std::sync::Mutex::new(AggregateTracker::new(1));
+    let mut emit = |progress: JavaDownloadProgress| {
+        tracker
+            .lock()
+            .unwrap()
+            .observe_java(std::time::Instant::now(), &progress);
+        let _ = app_handle.emit("java-download-progress", progress);
+    };
+
+    let work = async {
+        if segments > 1
+            && total_size >= SEGMENTED_DOWNLOAD_THRESHOLD
+            && download_segmented(
+                &client,
+                download_id,
+                url,
+                dest_path,
+                checksum,
+                total_size,
+                segments,
+                &DOWNLOAD_RATE_LIMITER,
+                &mut emit,
+            )
+            .await?
+        {
+            return Ok(());
         }
+
+        download_resumable(
+            &client,
+            download_id,
+            url,
+            dest_path,
+            checksum,
+            total_size,
+            &DOWNLOAD_RATE_LIMITER,
+            &mut emit,
+        )
+        .await
+    };
+    let result = with_aggregate_progress(work, &tracker, |aggregate| {
+        let _ = app_handle.emit("download-aggregate-progress", aggregate);
+    })
+    .await;
+    if result.is_ok() {
+        tracker.lock().unwrap().completed_files = 1;
+        let _ = app_handle.emit(
+            "download-aggregate-progress",
+            tracker.lock().unwrap().snapshot(),
+        );
     }
 
-    /// Increment completed files counter and return updated snapshot
-    fn inc_completed(&self) -> ProgressSnapshot {
-        let completed = self.completed_files.fetch_add(1, Ordering::Release) + 1;
-        ProgressSnapshot {
-            completed_files: completed,
-            total_files: self.total_files,
-            total_downloaded_bytes: self.total_downloaded_bytes.load(Ordering::Acquire),
+    let (outcome, error) = match &result {
+        Ok(()) => (DownloadHistoryOutcome::Completed, None),
+        Err(e) if is_java_download_cancelled(download_id) => (
+            DownloadHistoryOutcome::Cancelled,
+            Some(error::display_chain(e)),
+        ),
+        Err(e) => (
+            DownloadHistoryOutcome::Failed,
+            Some(error::display_chain(e)),
+        ),
+    };
+    download_history::record(
+        app_handle,
+        DownloadHistoryEntry::finished(
+            DownloadHistoryKind::JavaArchive,
+            Some(url.to_string()),
+            dest_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            tracker.lock().unwrap().snapshot().downloaded_bytes,
+            started,
+            outcome,
+            error,
+        ),
+    );
+    result
+}
+
+/// Resumable single-stream download.
+///
+/// Data is written to `<file>.part` with a `<file>.part.meta` sidecar recording the
+/// expected size and checksum. When a previous attempt left a partial file and the
+/// server advertised `Accept-Ranges: bytes`, the download continues with a `Range`
+/// request; if the server ignores the range the file is fetched again from the start.
+/// The checksum (SHA256) is computed while the body streams in, seeded with the bytes
+/// already on disk when resuming, and checked before the file is renamed into place.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn download_resumable<F>(
+    client: &reqwest::Client,
+    download_id: &str,
+    url: &str,
+    dest_path: &Path,
+    checksum: Option<&str>,
+    total_size: u64,
+    limiter: &RateLimiter,
+    mut on_progress: F,
+) -> Result<(), DownloadError>
+where
+    F: FnMut(JavaDownloadProgress),
+{
+    let part_path = part_path_for(dest_path);
+    let meta_path = meta_path_for(&part_path);
+    let file_name = dest_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    if let Some(parent) = dest_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| DownloadError::io("Failed to create download directory", e))?;
+    }
+
+    // Only trust a partial file if its sidecar describes this exact download. A file
+    // left by a segmented download is preallocated, so it is not a usable prefix.
+    let previous = load_metadata(&meta_path, url, checksum)
+        .await
+        .filter(|m| m.segments.is_empty());
+    let part_len = tokio::fs::metadata(&part_path)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let mut metadata = previous
+        .clone()
+        .unwrap_or_else(|| create_new_metadata(url, &file_name, total_size, checksum));
+    if metadata.total_size == 0 {
+        metadata.total_size = total_size;
+    }
+    let mut offset = match previous {
+        Some(previous)
+            if previous.accept_ranges
+                && (metadata.total_size == 0 || part_len <= metadata.total_size) =>
+        {
+            part_len
+        }
+        _ => 0,
+    };
+
+    let already_complete = offset > 0 && offset == metadata.total_size;
+    let mut digest = None;
+    if !already_complete {
+        let mut response = None;
+        if offset > 0 {
+            let resp = client
+                .get(url)
+                .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+                .send()
+                .await
+                .map_err(|e| DownloadError::request_failed(url, "Request failed", e))?;
+            if resp.status() == reqwest::StatusCode::PARTIAL_CONTENT
+                && content_range_matches(&resp, offset, metadata.total_size)
+            {
+                response = Some(resp);
+            } else if resp.status() == reqwest::StatusCode::OK {
+                // Server ignored the range and is sending the whole file
+                log::info!("Server ignored range request for {}, restarting", file_name);
+                offset = 0;
+                response = Some(resp);
+            } else {
+                log::info!(
+                    "Cannot resume {} (status {}), restarting",
+                    file_name,
+                    resp.status()
+                );
+                offset = 0;
+            }
+        }
+
+        let response = match response {
+            Some(response) => response,
+            None => client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| DownloadError::request_failed(url, "Request failed", e))?,
+        };
+        if !response.status().is_success() {
+            return Err(DownloadError::request(
+                url,
+                Some(response.status()),
+                format!("Server returned error: {}", response.status()),
+            ));
+        }
+        if is_html_response(&response) && expects_binary(dest_path) {
+            return Err(DownloadError::invalid_content(HTML_INSTEAD_OF_FILE));
+        }
+
+        if offset == 0 {
+            metadata.accept_ranges = response
+                .headers()
+                .get(reqwest::header::ACCEPT_RANGES)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
+            if metadata.total_size == 0 {
+                metadata.total_size = response.content_length().unwrap_or(0);
+            }
+        }
+        metadata.downloaded_bytes = offset;
+        save_metadata(&meta_path, &metadata).await?;
+
+        let file = if offset > 0 {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&part_path)
+                .await
+        } else {
+            tokio::fs::File::create(&part_path).await
+        }
+        .map_err(|e| DownloadError::io("Failed to open part file", e))?;
+        let mut file = tokio::io::BufWriter::with_capacity(ARCHIVE_WRITE_BUFFER_SIZE, file);
+
+        let mut hasher = match checksum {
+            Some(_) => {
+                let mut hasher = sha2::Sha256::new();
+                if offset > 0 {
+                    hash_file_prefix(&part_path, offset, &mut hasher).await?;
+                }
+                Some(hasher)
+            }
+            None => None,
+        };
+
+        let total = metadata.total_size;
+        let start_time = std::time::Instant::now();
+        let mut downloaded = offset;
+        let mut throttle = ProgressThrottle::new();
+        let mut stream = response.bytes_stream();
+
+        while let Some(chunk_result) = stream.next().await {
+            if is_java_download_cancelled(download_id) {
+                file.flush().await.ok();
+                metadata.downloaded_bytes = downloaded;
+                save_metadata(&meta_path, &metadata).await.ok();
+                return Err(DownloadError::Cancelled);
+            }
+
+            let chunk = match chunk_result {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    file.flush().await.ok();
+                    metadata.downloaded_bytes = downloaded;
+                    save_metadata(&meta_path, &metadata).await.ok();
+                    return Err(DownloadError::request(
+                        url,
+                        None,
+                        format!("Stream error: {}", e),
+                    ));
+                }
+            };
+            limiter.acquire(chunk.len() as u64).await;
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| DownloadError::io("Write error", e))?;
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&chunk);
+            }
+            downloaded += chunk.len() as u64;
+
+            if throttle.ready(downloaded, total > 0 && downloaded >= total) {
+                let elapsed = start_time.elapsed().as_secs_f64();
+                let speed = if elapsed > 0.0 {
+                    ((downloaded - offset) as f64 / elapsed) as u64
+                } else {
+                    0
+                };
+                let remaining = total.saturating_sub(downloaded);
+                let eta = remaining.checked_div(speed).unwrap_or(0);
+                let percentage = if total > 0 {
+                    (downloaded as f32 / total as f32) * 100.0
+                } else {
+                    0.0
+                };
+
+                on_progress(JavaDownloadProgress {
+                    id: download_id.to_string(),
+                    file_name: file_name.clone(),
+                    downloaded_bytes: downloaded,
+                    total_bytes: total,
+                    speed_bytes_per_sec: speed,
+                    eta_seconds: eta,
+                    status: "Downloading".to_string(),
+                    message_key: java_download_status_key("Downloading"),
+                    percentage,
+                });
+            }
+        }
+
+        file.flush()
+            .await
+            .map_err(|e| DownloadError::io("Write error", e))?;
+        metadata.downloaded_bytes = downloaded;
+        save_metadata(&meta_path, &metadata).await?;
+
+        if total > 0 && downloaded != total {
+            return Err(DownloadError::SizeMismatch {
+                expected: total,
+                actual: downloaded,
+            });
         }
+        digest = hasher.map(|hasher| hex::encode(hasher.finalize()));
     }
 
-    /// Add downloaded bytes and return updated snapshot
-    fn add_bytes(&self, delta: u64) -> ProgressSnapshot {
-        let total_bytes = self
-            .total_downloaded_bytes
-            .fetch_add(delta, Ordering::AcqRel)
-            + delta;
-        ProgressSnapshot {
-            completed_files: self.completed_files.load(Ordering::Acquire),
-            total_files: self.total_files,
-            total_downloaded_bytes: total_bytes,
+    finish_part_file(
+        download_id,
+        &part_path,
+        &meta_path,
+        dest_path,
+        checksum,
+        digest,
+        metadata.total_size,
+        &mut on_progress,
+    )
+    .await
+}
+
+/// Verify a completed `.part` file and move it to its final name
+#[allow(clippy::too_many_arguments)]
+async fn finish_part_file<F>(
+    download_id: &str,
+    part_path: &Path,
+    meta_path: &Path,
+    dest_path: &Path,
+    checksum: Option<&str>,
+    digest: Option<String>,
+    total_size: u64,
+    on_progress: &mut F,
+) -> Result<(), DownloadError>
+where
+    F: FnMut(JavaDownloadProgress),
+{
+    // Catch an empty file or an error page before it shows up as a checksum mismatch
+    if let Err(e) = check_downloaded_file(part_path, dest_path, false).await {
+        tokio::fs::remove_file(part_path).await.ok();
+        tokio::fs::remove_file(meta_path).await.ok();
+        return Err(e);
+    }
+
+    // Verify checksum over the whole file, including any resumed prefix. `digest` is
+    // the hash computed while streaming; without it the part file is read back.
+    if let Some(expected) = checksum {
+        on_progress(JavaDownloadProgress {
+            id: download_id.to_string(),
+            file_name: dest_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            downloaded_bytes: total_size,
+            total_bytes: total_size,
+            speed_bytes_per_sec: 0,
+            eta_seconds: 0,
+            status: "Verifying".to_string(),
+            message_key: java_download_status_key("Verifying"),
+            percentage: 100.0,
+        });
+
+        let actual = match digest {
+            Some(digest) => digest,
+            None => compute_file_sha256(part_path).await?,
+        };
+        if !actual.eq_ignore_ascii_case(expected) {
+            // Checksum failed, delete files so the next attempt starts fresh
+            tokio::fs::remove_file(part_path).await.ok();
+            tokio::fs::remove_file(meta_path).await.ok();
+            return Err(DownloadError::ChecksumMismatch {
+                expected: expected.to_string(),
+                actual: Some(actual),
+            });
         }
     }
+
+    // Rename part file to final destination
+    tokio::fs::rename(part_path, dest_path)
+        .await
+        .map_err(|e| DownloadError::io("Failed to rename file", e))?;
+
+    // Clean up metadata file
+    tokio::fs::remove_file(meta_path).await.ok();
+
+    Ok(())
 }
 
-/// Emit a progress event to the frontend
-fn emit_progress(
-    window: &Window,
-    file_name: &str,
-    status: &str,
-    downloaded: u64,
-    total: u64,
-    snapshot: &ProgressSnapshot,
-) {
-    let _ = window.emit(
-        "download-progress",
-        ProgressEvent {
-            file: file_name.to_string(),
-            downloaded,
-            total,
-            status: status.into(),
-            completed_files: snapshot.completed_files,
-            total_files: snapshot.total_files,
-            total_downloaded_bytes: snapshot.total_downloaded_bytes,
-        },
-    );
+/// Split `total_size` bytes into `count` contiguous inclusive ranges
+fn split_segments(total_size: u64, count: usize) -> Vec<DownloadSegment> {
+    let count = (count as u64).clamp(1, total_size.max(1));
+    let segment_size = total_size / count;
+    (0..count)
+        .map(|i| DownloadSegment {
+            start: i * segment_size,
+            end: if i == count - 1 {
+                total_size - 1
+            } else {
+                (i + 1) * segment_size - 1
+            },
+            downloaded: 0,
+            completed: false,
+        })
+        .collect()
 }
 
-pub async fn download_files(
-    window: Window,
-    tasks: Vec<DownloadTask>,
-    max_concurrent: usize,
-) -> Result<(), String> {
-    // Clamp max_concurrent to a valid range (1-128) to prevent edge cases
-    let max_concurrent = max_concurrent.clamp(1, 128);
+/// Ask for the first byte to find out whether the server honours ranges for this file
+async fn supports_ranges(client: &reqwest::Client, url: &str, total_size: u64) -> bool {
+    match client
+        .get(url)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await
+    {
+        Ok(response) => {
+            response.status() == reqwest::StatusCode::PARTIAL_CONTENT
+                && content_range_matches(&response, 0, total_size)
+        }
+        Err(_) => false,
+    }
+}
 
-    let client = reqwest::Client::new();
-    let semaphore = Arc::new(Semaphore::new(max_concurrent));
-    let progress = Arc::new(GlobalProgress::new(tasks.len()));
+/// Fetch the remainder of one segment into its slot of the preallocated part file
+#[allow(clippy::too_many_arguments)]
+async fn fetch_segment(
+    client: &reqwest::Client,
+    download_id: &str,
+    url: &str,
+    part_path: &Path,
+    segment: &DownloadSegment,
+    segment_downloaded: &AtomicU64,
+    total_downloaded: &AtomicU64,
+    limiter: &RateLimiter,
+) -> Result<(), DownloadError> {
+    let start = segment.start + segment_downloaded.load(Ordering::Acquire);
+    if start > segment.end {
+        return Ok(());
+    }
 
-    // Notify start (total files)
-    let _ = window.emit("download-start", tasks.len());
+    let response = client
+        .get(url)
+        .header(
+            reqwest::header::RANGE,
+            format!("bytes={}-{}", start, segment.end),
+        )
+        .send()
+        .await
+        .map_err(|e| DownloadError::request_failed(url, "Request failed", e))?;
+    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT
+        || !content_range_matches(&response, start, 0)
+    {
+        return Err(DownloadError::request(
+            url,
+            Some(response.status()),
+            format!(
+                "Server did not honour range {}-{}: {}",
+                start,
+                segment.end,
+                response.status()
+            ),
+        ));
+    }
 
-    let tasks_stream = futures::stream::iter(tasks).map(|task| {
-        let client = client.clone();
-        let window = window.clone();
-        let semaphore = semaphore.clone();
-        let progress = progress.clone();
-
-        async move {
-            let _permit = semaphore.acquire().await.unwrap();
-            let file_name = task.path.file_name().unwrap().to_string_lossy().to_string();
-
-            // 1. Check if file exists and verify checksum
-            if task.path.exists() {
-                emit_progress(&window, &file_name, "Verifying", 0, 0, &progress.snapshot());
-
-                if task.sha256.is_some() || task.sha1.is_some() {
-                    if let Ok(data) = tokio::fs::read(&task.path).await {
-                        if verify_checksum(&data, task.sha256.as_deref(), task.sha1.as_deref()) {
-                            // Already valid, skip download
-                            let skipped_size = tokio::fs::metadata(&task.path)
-                                .await
-                                .map(|m| m.len())
-                                .unwrap_or(0);
-                            if skipped_size > 0 {
-                                let _ = progress.add_bytes(skipped_size);
-                            }
-                            emit_progress(
-                                &window,
-                                &file_name,
-                                "Skipped",
-                                0,
-                                0,
-                                &progress.inc_completed(),
-                            );
-                            return Ok(());
-                        }
-                    }
-                }
-            }
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(part_path)
+        .await
+        .map_err(|e| DownloadError::io("Failed to open part file", e))?;
+    file.seek(std::io::SeekFrom::Start(start))
+        .await
+        .map_err(|e| DownloadError::io("Seek error", e))?;
+    let mut file = tokio::io::BufWriter::with_capacity(ARCHIVE_WRITE_BUFFER_SIZE, file);
+
+    let mut position = start;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk_result) = stream.next().await {
+        if is_java_download_cancelled(download_id) {
+            file.flush().await.ok();
+            return Err(DownloadError::Cancelled);
+        }
 
-            // 2. Download
-            if let Some(parent) = task.path.parent() {
-                let _ = tokio::fs::create_dir_all(parent).await;
+        // Buffered bytes already count as downloaded, so they must reach the file
+        // before a retry continues from `segment_downloaded`
+        let chunk = match chunk_result {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                file.flush().await.ok();
+                return Err(DownloadError::request(
+                    url,
+                    None,
+                    format!("Stream error: {}", e),
+                ));
             }
+        };
+        // Never write past the end of this segment, even if the server sends more
+        let remaining = (segment.end + 1 - position) as usize;
+        let chunk = &chunk[..chunk.len().min(remaining)];
+        limiter.acquire(chunk.len() as u64).await;
+        file.write_all(chunk)
+            .await
+            .map_err(|e| DownloadError::io("Write error", e))?;
+        position += chunk.len() as u64;
+        segment_downloaded.fetch_add(chunk.len() as u64, Ordering::AcqRel);
+        total_downloaded.fetch_add(chunk.len() as u64, Ordering::AcqRel);
+        if position > segment.end {
+            break;
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| DownloadError::io("Write error", e))?;
 
-            match client.get(&task.url).send().await {
-                Ok(mut resp) => {
-                    let total_size = resp.content_length().unwrap_or(0);
-                    let mut file = match tokio::fs::File::create(&task.path).await {
-                        Ok(f) => f,
-                        Err(e) => return Err(format!("Create file error: {}", e)),
-                    };
+    if position <= segment.end {
+        return Err(DownloadError::SizeMismatch {
+            expected: segment.end + 1 - segment.start,
+            actual: position - segment.start,
+        });
+    }
+    Ok(())
+}
 
-                    let mut downloaded: u64 = 0;
-                    loop {
-                        match resp.chunk().await {
-                            Ok(Some(chunk)) => {
-                                if let Err(e) = file.write_all(&chunk).await {
-                                    return Err(format!("Write error: {}", e));
-                                }
-                                downloaded += chunk.len() as u64;
-                                let snapshot = progress.add_bytes(chunk.len() as u64);
-                                emit_progress(
-                                    &window,
-                                    &file_name,
-                                    "Downloading",
-                                    downloaded,
-                                    total_size,
-                                    &snapshot,
-                                );
-                            }
-                            Ok(None) => break,
-                            Err(e) => return Err(format!("Download error: {}", e)),
-                        }
-                    }
+/// Download a segment, retrying only this segment on failure
+#[allow(clippy::too_many_arguments)]
+async fn download_segment(
+    client: &reqwest::Client,
+    download_id: &str,
+    url: &str,
+    part_path: &Path,
+    segment: &DownloadSegment,
+    segment_downloaded: &AtomicU64,
+    total_downloaded: &AtomicU64,
+    limiter: &RateLimiter,
+) -> Result<(), DownloadError> {
+    let mut attempt = 1;
+    loop {
+        match fetch_segment(
+            client,
+            download_id,
+            url,
+            part_path,
+            segment,
+            segment_downloaded,
+            total_downloaded,
+            limiter,
+        )
+        .await
+        {
+            Ok(()) => return Ok(()),
+            Err(e) if is_java_download_cancelled(download_id) => return Err(e),
+            Err(e) => {
+                log::warn!(
+                    "Segment {}-{} failed (attempt {}/{}): {}",
+                    segment.start,
+                    segment.end,
+                    attempt,
+                    SEGMENT_RETRIES,
+                    e
+                );
+                if attempt == SEGMENT_RETRIES {
+                    return Err(e);
                 }
-                Err(e) => return Err(format!("Request error: {}", e)),
+                attempt += 1;
             }
+        }
+    }
+}
 
-            emit_progress(
-                &window,
-                &file_name,
-                "Finished",
-                0,
-                0,
-                &progress.inc_completed(),
-            );
-            Ok(())
+/// Multi-connection download into a preallocated `.part` file.
+///
+/// Returns `Ok(false)` without downloading anything when the server does not support
+/// ranges or a single-stream partial file already exists, so the caller can fall back
+/// to [`download_resumable`]. Progress of all segments is reported as one aggregate.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn download_segmented<F>(
+    client: &reqwest::Client,
+    download_id: &str,
+    url: &str,
+    dest_path: &Path,
+    checksum: Option<&str>,
+    total_size: u64,
+    segment_count: usize,
+    limiter: &RateLimiter,
+    mut on_progress: F,
+) -> Result<bool, DownloadError>
+where
+    F: FnMut(JavaDownloadProgress),
+{
+    let part_path = part_path_for(dest_path);
+    let meta_path = meta_path_for(&part_path);
+    let file_name = dest_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let previous = load_metadata(&meta_path, url, checksum).await;
+    let mut metadata = match previous {
+        Some(previous)
+            if !previous.segments.is_empty()
+                && previous.total_size == total_size
+                && part_path.exists() =>
+        {
+            previous
         }
-    });
+        // Keep resuming a single-stream download rather than throwing its progress away
+        Some(previous) if previous.segments.is_empty() && part_path.exists() => {
+            return Ok(false);
+        }
+        _ => {
+            if total_size == 0 || !supports_ranges(client, url, total_size).await {
+                return Ok(false);
+            }
+            let mut metadata = create_new_metadata(url, &file_name, total_size, checksum);
+            metadata.accept_ranges = true;
+            metadata.segments = split_segments(total_size, segment_count);
+            metadata
+        }
+    };
 
-    // Buffer unordered to run concurrently
-    tasks_stream
-        .buffer_unordered(max_concurrent)
-        .collect::<Vec<Result<(), String>>>()
-        .await;
+    if let Some(parent) = dest_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| DownloadError::io("Failed to create download directory", e))?;
+    }
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&part_path)
+        .await
+        .map_err(|e| DownloadError::io("Failed to open part file", e))?;
+    file.set_len(total_size)
+        .await
+        .map_err(|e| DownloadError::io("Failed to preallocate part file", e))?;
+    drop(file);
+    save_metadata(&meta_path, &metadata).await?;
 
-    let _ = window.emit("download-complete", ());
-    Ok(())
+    let segment_progress: Vec<AtomicU64> = metadata
+        .segments
+        .iter()
+        .map(|s| AtomicU64::new(s.downloaded))
+        .collect();
+    let resumed_bytes: u64 = metadata.segments.iter().map(|s| s.downloaded).sum();
+    let total_downloaded = AtomicU64::new(resumed_bytes);
+
+    let segments = metadata.segments.clone();
+    let downloads = futures::future::join_all(segments.iter().enumerate().map(|(idx, segment)| {
+        download_segment(
+            client,
+            download_id,
+            url,
+            &part_path,
+            segment,
+            &segment_progress[idx],
+            &total_downloaded,
+            limiter,
+        )
+    }));
+    tokio::pin!(downloads);
+
+    let start_time = std::time::Instant::now();
+    let mut ticker =
+        tokio::time::interval(progress_interval().max(std::time::Duration::from_millis(1)));
+    let results = loop {
+        tokio::select! {
+            results = &mut downloads => break results,
+            _ = ticker.tick() => {
+                let downloaded = total_downloaded.load(Ordering::Acquire);
+                let elapsed = start_time.elapsed().as_secs_f64();
+                let speed = if elapsed > 0.0 {
+                    ((downloaded - resumed_bytes) as f64 / elapsed) as u64
+                } else {
+                    0
+                };
+                let remaining = total_size.saturating_sub(downloaded);
+                on_progress(JavaDownloadProgress {
+                    id: download_id.to_string(),
+                    file_name: file_name.clone(),
+                    downloaded_bytes: downloaded,
+                    total_bytes: total_size,
+                    speed_bytes_per_sec: speed,
+                    eta_seconds: remaining.checked_div(speed).unwrap_or(0),
+                    status: "Downloading".to_string(),
+                    message_key: java_download_status_key("Downloading"),
+                    percentage: (downloaded as f32 / total_size as f32) * 100.0,
+                });
+            }
+        }
+    };
+
+    // Persist per-segment progress so an interrupted download resumes where it stopped
+    for (idx, segment) in metadata.segments.iter_mut().enumerate() {
+        segment.downloaded = segment_progress[idx].load(Ordering::Acquire);
+        segment.completed = results[idx].is_ok();
+    }
+    metadata.downloaded_bytes = total_downloaded.load(Ordering::Acquire);
+    save_metadata(&meta_path, &metadata).await?;
+
+    if let Some(Err(e)) = results.into_iter().find(|r| r.is_err()) {
+        return Err(e);
+    }
+
+    // Segments arrive out of order, so the assembled file has to be hashed once here
+    finish_part_file(
+        download_id,
+        &part_path,
+        &meta_path,
+        dest_path,
+        checksum,
+        None,
+        total_size,
+        &mut on_progress,
+    )
+    .await?;
+    Ok(true)
+}
+
+/// Create new download metadata for a fresh download
+fn create_new_metadata(
+    url: &str,
+    file_name: &str,
+    total_size: u64,
+    checksum: Option<&str>,
+) -> DownloadMetadata {
+    DownloadMetadata {
+        url: url.to_string(),
+        file_name: file_name.to_string(),
+        total_size,
+        downloaded_bytes: 0,
+        checksum: checksum.map(|s| s.to_string()),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        accept_ranges: false,
+        segments: Vec::new(),
+    }
+}
+
+/// Statuses a `ProgressEvent` can carry
+pub const DOWNLOAD_STATUSES: &[&str] = &[
+    "Downloading",
+    "Verifying",
+    "Skipped",
+    "Retrying",
+    "Paused",
+    "Cancelled",
+    "Finished",
+    "Error",
+];
+
+/// `message_key` of a `ProgressEvent` status
+fn download_status_key(status: &str) -> String {
+    debug_assert!(DOWNLOAD_STATUSES.contains(&status));
+    crate::core::i18n::status_key("download", status)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "downloader.ts")]
+pub struct ProgressEvent {
+    pub task_id: String,
+    pub file: String,
+    pub downloaded: u64,
+    pub total: u64,
+    /// One of `DOWNLOAD_STATUSES`
+    pub status: String,
+    /// Translatable form of `status`, e.g. `download.retrying`
+    pub message_key: String,
+    pub attempt: u32, // 1-based download attempt for this file
+    pub completed_files: usize,
+    pub total_files: usize,
+    pub total_downloaded_bytes: u64,
+}
+
+/// calculate SHA256 hash of data
+#[cfg(test)]
+pub fn compute_sha256(data: &[u8]) -> String {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+/// calculate SHA512 hash of data
+#[cfg(test)]
+pub fn compute_sha512(data: &[u8]) -> String {
+    let mut hasher = sha2::Sha512::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+/// calculate SHA1 hash of data
+pub fn compute_sha1(data: &[u8]) -> String {
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+/// Read size used when hashing files on disk
+const VERIFY_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Pick the hasher and expected digest: SHA512 if given, then SHA256, then SHA1
+fn checksum_hasher<'a>(
+    sha512: Option<&'a str>,
+    sha256: Option<&'a str>,
+    sha1: Option<&'a str>,
+) -> Option<(Box<dyn sha2::digest::DynDigest + Send>, &'a str)> {
+    if let Some(expected) = sha512 {
+        return Some((Box::new(sha2::Sha512::new()), expected));
+    }
+    if let Some(expected) = sha256 {
+        return Some((Box::new(sha2::Sha256::new()), expected));
+    }
+    let expected = sha1?;
+    Some((Box::new(sha1::Sha1::new()), expected))
+}
+
+/// verify a file on disk without loading it into memory, against the strongest hash
+/// given (see `checksum_hasher`); with no hash it always passes
+pub fn verify_file_checksum(
+    path: &Path,
+    sha512: Option<&str>,
+    sha256: Option<&str>,
+    sha1: Option<&str>,
+) -> std::io::Result<bool> {
+    use std::io::Read;
+
+    let Some((mut hasher, expected)) = checksum_hasher(sha512, sha256, sha1) else {
+        return Ok(true);
+    };
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = vec![0u8; VERIFY_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()) == expected)
+}
+
+/// async version of `verify_file_checksum`
+pub async fn verify_file_checksum_async(
+    path: &Path,
+    sha512: Option<&str>,
+    sha256: Option<&str>,
+    sha1: Option<&str>,
+) -> std::io::Result<bool> {
+    use tokio::io::AsyncReadExt;
+
+    let Some((mut hasher, expected)) = checksum_hasher(sha512, sha256, sha1) else {
+        return Ok(true);
+    };
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buf = vec![0u8; VERIFY_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()) == expected)
+}
+
+/// Snapshot of global progress state
+struct ProgressSnapshot {
+    completed_files: usize,
+    total_files: usize,
+    total_downloaded_bytes: u64,
+}
+
+/// Centralized progress tracking with atomic counters
+struct GlobalProgress {
+    completed_files: AtomicUsize,
+    total_downloaded_bytes: AtomicU64,
+    total_files: usize,
+}
+
+impl GlobalProgress {
+    fn new(total_files: usize) -> Self {
+        Self {
+            completed_files: AtomicUsize::new(0),
+            total_downloaded_bytes: AtomicU64::new(0),
+            total_files,
+        }
+    }
+
+    /// Get current progress snapshot without modification
+    fn snapshot(&self) -> ProgressSnapshot {
+        ProgressSnapshot {
+            completed_files: self.completed_files.load(Ordering::Acquire),
+            total_files: self.total_files,
+            total_downloaded_bytes: self.total_downloaded_bytes.load(Ordering::Acquire),
+        }
+    }
+
+    /// Increment completed files counter and return updated snapshot
+    fn inc_completed(&self) -> ProgressSnapshot {
+        let completed = self.completed_files.fetch_add(1, Ordering::Release) + 1;
+        ProgressSnapshot {
+            completed_files: completed,
+            total_files: self.total_files,
+            total_downloaded_bytes: self.total_downloaded_bytes.load(Ordering::Acquire),
+        }
+    }
+
+    /// Remove bytes that were downloaded and then discarded
+    fn sub_bytes(&self, delta: u64) {
+        let _ = self.total_downloaded_bytes.fetch_update(
+            Ordering::AcqRel,
+            Ordering::Acquire,
+            |bytes| Some(bytes.saturating_sub(delta)),
+        );
+    }
+
+    /// Add downloaded bytes and return updated snapshot
+    fn add_bytes(&self, delta: u64) -> ProgressSnapshot {
+        let total_bytes = self
+            .total_downloaded_bytes
+            .fetch_add(delta, Ordering::AcqRel)
+            + delta;
+        ProgressSnapshot {
+            completed_files: self.completed_files.load(Ordering::Acquire),
+            total_files: self.total_files,
+            total_downloaded_bytes: total_bytes,
+        }
+    }
+}
+
+/// Emit a progress event to the frontend
+fn emit_progress<F>(
+    on_progress: &F,
+    task: &DownloadTask,
+    status: &str,
+    downloaded: u64,
+    total: u64,
+    attempt: u32,
+    snapshot: &ProgressSnapshot,
+) where
+    F: Fn(ProgressEvent),
+{
+    on_progress(ProgressEvent {
+        task_id: task.id(),
+        file: task
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        downloaded,
+        total,
+        status: status.into(),
+        message_key: download_status_key(status),
+        attempt,
+        completed_files: snapshot.completed_files,
+        total_files: snapshot.total_files,
+        total_downloaded_bytes: snapshot.total_downloaded_bytes,
+    });
+}
+
+/// How `download_files` retries a task after a transient network failure
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: std::time::Duration,
+    pub max_delay: std::time::Duration,
+    /// An attempt that receives nothing for this long is abandoned and retried
+    pub stall_timeout: std::time::Duration,
+    /// First wait after a rate-limited response without `Retry-After`, doubled per attempt
+    pub rate_limit_delay: std::time::Duration,
+    /// Longest wait after a rate-limited response, whatever `Retry-After` asks for
+    pub max_rate_limit_delay: std::time::Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: std::time::Duration::from_millis(500),
+            max_delay: std::time::Duration::from_secs(10),
+            stall_timeout: std::time::Duration::from_secs(60),
+            rate_limit_delay: std::time::Duration::from_secs(15),
+            max_rate_limit_delay: std::time::Duration::from_secs(120),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn with_attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_stall_timeout(self, stall_timeout: std::time::Duration) -> Self {
+        Self {
+            stall_timeout,
+            ..self
+        }
+    }
+
+    /// Exponential backoff after the given (1-based) attempt, randomised down to half
+    /// so that parallel tasks failing together don't retry in lockstep
+    fn delay_after(&self, attempt: u32) -> std::time::Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        delay.mul_f64(0.5 + 0.5 * random_fraction())
+    }
+
+    /// Wait after a rate-limited (1-based) attempt: the server's `Retry-After` if it
+    /// sent one, a longer backoff than for transient failures otherwise
+    fn delay_after_rate_limit(
+        &self,
+        attempt: u32,
+        retry_after: Option<std::time::Duration>,
+    ) -> std::time::Duration {
+        retry_after
+            .unwrap_or_else(|| {
+                self.rate_limit_delay
+                    .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            })
+            .min(self.max_rate_limit_delay)
+    }
+}
+
+/// A random number in `[0, 1)` without pulling in an RNG crate
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    let hash = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// What kind of failure stopped a download, which decides how it is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "downloader.ts")]
+pub enum FailureClass {
+    /// Trying again won't help (e.g. 404); fallback URLs are tried straight away
+    Permanent,
+    /// Server errors and network hiccups, retried with backoff
+    Transient,
+    /// The server asked us to slow down, retried after a longer wait
+    RateLimited,
+}
+
+/// Why a single fetch failed, and whether trying again could help
+enum FetchError {
+    Transient(DownloadError),
+    Permanent(DownloadError),
+    /// Carries the server's `Retry-After`, if any
+    RateLimited(DownloadError, Option<std::time::Duration>),
+    /// The batch was cancelled while this task was paused
+    Stopped,
+    /// This task's own token was cancelled
+    Cancelled,
+}
+
+fn classify_status(
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+) -> FailureClass {
+    // Rate limiters answer 429, or 403 with a hint when to come back
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || (status == reqwest::StatusCode::FORBIDDEN
+            && headers.contains_key(reqwest::header::RETRY_AFTER))
+    {
+        FailureClass::RateLimited
+    } else if status.is_server_error() || status == reqwest::StatusCode::REQUEST_TIMEOUT {
+        FailureClass::Transient
+    } else {
+        FailureClass::Permanent
+    }
+}
+
+/// `Retry-After` as either delay-seconds or an HTTP date
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<std::time::Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(std::time::Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// A task that `download_files` fetched, and the URL it finally came from
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "downloader.ts")]
+pub struct DownloadSuccess {
+    pub task: DownloadTask,
+    pub url: String,
+}
+
+/// A task that `download_files` gave up on
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "downloader.ts")]
+pub struct DownloadFailure {
+    pub task: DownloadTask,
+    pub error: DownloadError,
+    pub class: FailureClass,
+}
+
+/// Outcome of a `download_files` batch, also sent with the `download-complete` event
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "downloader.ts")]
+pub struct DownloadBatchReport {
+    /// Tasks that were downloaded (and verified, if they carry a checksum)
+    pub succeeded: Vec<DownloadSuccess>,
+    /// Tasks whose file already existed with a matching checksum
+    pub skipped: Vec<DownloadTask>,
+    /// Tasks stopped by cancelling the batch; any `.part` file is left in place
+    pub paused: Vec<DownloadTask>,
+    /// Tasks cancelled on their own; their partial files are removed
+    pub cancelled: Vec<DownloadTask>,
+    pub failed: Vec<DownloadFailure>,
+}
+
+impl DownloadBatchReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.paused.is_empty() && self.cancelled.is_empty()
+    }
+}
+
+enum TaskOutcome {
+    /// Carries the URL that served the file
+    Downloaded(String),
+    Skipped,
+    Paused,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlState {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+/// Pause, resume or cancel the `download_files` batches in flight.
+///
+/// Tasks check the state between chunk writes. A paused task drops its connection and
+/// continues from its partial file once resumed. Individual tasks (and the Java download)
+/// can also be cancelled through the token registered under their id.
+#[derive(Debug, Clone)]
+pub struct DownloadController {
+    state: Arc<tokio::sync::watch::Sender<ControlState>>,
+    tasks: Arc<std::sync::Mutex<std::collections::HashMap<String, CancellationToken>>>,
+}
+
+impl Default for DownloadController {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(tokio::sync::watch::Sender::new(ControlState::Running)),
+            tasks: Arc::default(),
+        }
+    }
+}
+
+impl DownloadController {
+    pub fn pause(&self) {
+        self.transition(ControlState::Running, ControlState::Paused);
+    }
+
+    pub fn resume(&self) {
+        self.transition(ControlState::Paused, ControlState::Running);
+    }
+
+    pub fn cancel(&self) {
+        self.state.send_replace(ControlState::Cancelled);
+    }
+
+    /// Cancel one task by id, returning whether it was known
+    pub fn cancel_task(&self, id: &str) -> bool {
+        match self.tasks.lock().unwrap().get(id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancel every task whose id starts with `prefix`, returning how many matched
+    pub fn cancel_tasks_with_prefix(&self, prefix: &str) -> usize {
+        let tasks = self.tasks.lock().unwrap();
+        let matching: Vec<_> = tasks
+            .iter()
+            .filter(|(id, _)| id.starts_with(prefix))
+            .map(|(_, token)| token)
+            .collect();
+        matching.iter().for_each(|token| token.cancel());
+        matching.len()
+    }
+
+    /// Whether any download task or Java download is registered
+    pub fn has_tasks(&self) -> bool {
+        !self.tasks.lock().unwrap().is_empty()
+    }
+
+    /// Register a fresh token for `id`, replacing any earlier one
+    fn register_task(&self, id: &str) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.tasks
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), token.clone());
+        token
+    }
+
+    fn finish_task(&self, id: &str) {
+        self.tasks.lock().unwrap().remove(id);
+    }
+
+    fn is_task_cancelled(&self, id: &str) -> bool {
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(id)
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// A cancel only stops the batches that were running, not the next one
+    fn begin_batch(&self) {
+        self.transition(ControlState::Cancelled, ControlState::Running);
+    }
+
+    fn is_running(&self) -> bool {
+        *self.state.borrow() == ControlState::Running
+    }
+
+    /// Wait until the batch is no longer paused, returning `false` if it was cancelled
+    async fn wait_while_paused(&self) -> bool {
+        let mut rx = self.state.subscribe();
+        let state = rx
+            .wait_for(|state| *state != ControlState::Paused)
+            .await
+            .map(|state| *state)
+            .unwrap_or(ControlState::Cancelled);
+        state == ControlState::Running
+    }
+
+    fn transition(&self, from: ControlState, to: ControlState) {
+        self.state.send_if_modified(|state| {
+            if *state == from {
+                *state = to;
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+/// Controller shared by every `download_files` batch in the launcher
+pub static DOWNLOAD_CONTROLLER: std::sync::LazyLock<DownloadController> =
+    std::sync::LazyLock::new(DownloadController::default);
+
+/// Overall progress of a batch, emitted as `download-aggregate-progress`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "downloader.ts")]
+pub struct AggregateProgressEvent {
+    pub downloaded_bytes: u64,
+    /// Sum of the file sizes known so far
+    pub total_bytes: u64,
+    /// Whether `total_bytes` covers every file in the batch
+    pub total_bytes_exact: bool,
+    pub completed_files: usize,
+    pub total_files: usize,
+    pub speed_bytes_per_sec: u64,
+    /// Only set once the total is exact and there is a measurable speed
+    pub eta_seconds: Option<u64>,
+}
+
+/// Minimum time between two `download-aggregate-progress` events
+const AGGREGATE_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Speed over a sliding window of `(time, bytes transferred)` samples
+struct SpeedMeter {
+    samples: std::collections::VecDeque<(std::time::Instant, u64)>,
+    window: std::time::Duration,
+}
+
+impl SpeedMeter {
+    fn new(window: std::time::Duration) -> Self {
+        Self {
+            samples: std::collections::VecDeque::new(),
+            window,
+        }
+    }
+
+    fn record(&mut self, now: std::time::Instant, bytes: u64) {
+        if let Some(&(last_time, last_bytes)) = self.samples.back() {
+            // Ignore samples from a clock that went backwards
+            if now < last_time {
+                return;
+            }
+            // A counter that went down makes every older sample meaningless
+            if bytes < last_bytes {
+                self.samples.clear();
+            }
+        }
+        self.samples.push_back((now, bytes));
+        // Keep one sample at or beyond the window edge so the span covers the window
+        while self.samples.len() > 2
+            && now.saturating_duration_since(self.samples[1].0) >= self.window
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn bytes_per_sec(&self) -> u64 {
+        let (Some(&(first_time, first_bytes)), Some(&(last_time, last_bytes))) =
+            (self.samples.front(), self.samples.back())
+        else {
+            return 0;
+        };
+        let elapsed = last_time
+            .saturating_duration_since(first_time)
+            .as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0;
+        }
+        (last_bytes.saturating_sub(first_bytes) as f64 / elapsed) as u64
+    }
+}
+
+/// Folds per-file progress into an [`AggregateProgressEvent`]
+pub(crate) struct AggregateTracker {
+    /// Bytes reported for each file, used to measure what actually crossed the network
+    transferred: std::collections::HashMap<String, u64>,
+    /// Final or announced size of each file whose size is known
+    sizes: std::collections::HashMap<String, u64>,
+    network_bytes: u64,
+    downloaded_bytes: u64,
+    completed_files: usize,
+    total_files: usize,
+    meter: SpeedMeter,
+}
+
+impl AggregateTracker {
+    pub(crate) fn new(total_files: usize) -> Self {
+        Self {
+            transferred: std::collections::HashMap::new(),
+            sizes: std::collections::HashMap::new(),
+            network_bytes: 0,
+            downloaded_bytes: 0,
+            completed_files: 0,
+            total_files,
+            meter: SpeedMeter::new(std::time::Duration::from_secs(5)),
+        }
+    }
+
+    /// Record that file `id` has `downloaded` of `total` bytes (0 = unknown size)
+    fn file_progress(&mut self, now: std::time::Instant, id: &str, downloaded: u64, total: u64) {
+        // The first report only sets a baseline, so resumed prefixes don't count as speed
+        if let Some(last) = self.transferred.insert(id.to_string(), downloaded) {
+            // A restarted file counts from zero again
+            self.network_bytes += if downloaded >= last {
+                downloaded - last
+            } else {
+                downloaded
+            };
+        }
+        if total > 0 {
+            self.sizes.insert(id.to_string(), total);
+        }
+        self.meter.record(now, self.network_bytes);
+    }
+
+    /// Record that file `id` is done, with its size if it is known
+    fn file_done(&mut self, id: &str, size: Option<u64>) {
+        let size = size
+            .filter(|&size| size > 0)
+            .or_else(|| self.transferred.get(id).copied())
+            .unwrap_or(0);
+        self.sizes.insert(id.to_string(), size);
+    }
+
+    /// Update from one `download-progress` event
+    pub(crate) fn observe(&mut self, now: std::time::Instant, event: &ProgressEvent) {
+        self.downloaded_bytes = event.total_downloaded_bytes;
+        self.completed_files = event.completed_files;
+        self.total_files = event.total_files;
+        match event.status.as_str() {
+            "Downloading" => self.file_progress(now, &event.task_id, event.downloaded, event.total),
+            "Skipped" => self.file_done(&event.task_id, Some(event.total)),
+            "Finished" => self.file_done(&event.task_id, None),
+            // These files will never arrive, so they don't add to the total
+            "Cancelled" | "Error" => self.file_done(&event.task_id, Some(0)),
+            _ => {}
+        }
+    }
+
+    /// Update from one `java-download-progress` event
+    pub(crate) fn observe_java(
+        &mut self,
+        now: std::time::Instant,
+        progress: &JavaDownloadProgress,
+    ) {
+        self.downloaded_bytes = progress.downloaded_bytes;
+        self.file_progress(
+            now,
+            &progress.file_name,
+            progress.downloaded_bytes,
+            progress.total_bytes,
+        );
+    }
+
+    pub(crate) fn snapshot(&self) -> AggregateProgressEvent {
+        let total_bytes: u64 = self.sizes.values().sum();
+        let total_bytes_exact = self.sizes.len() >= self.total_files;
+        let speed = self.meter.bytes_per_sec();
+        let eta_seconds = if total_bytes_exact && speed > 0 {
+            Some(total_bytes.saturating_sub(self.downloaded_bytes) / speed)
+        } else {
+            None
+        };
+        AggregateProgressEvent {
+            downloaded_bytes: self.downloaded_bytes,
+            total_bytes,
+            total_bytes_exact,
+            completed_files: self.completed_files,
+            total_files: self.total_files,
+            speed_bytes_per_sec: speed,
+            eta_seconds,
+        }
+    }
+}
+
+/// Drive `work` while emitting the tracker's aggregate on a fixed interval and once at the end
+async fn with_aggregate_progress<T, E>(
+    work: impl std::future::Future<Output = T>,
+    tracker: &std::sync::Mutex<AggregateTracker>,
+    emit: E,
+) -> T
+where
+    E: Fn(AggregateProgressEvent),
+{
+    let mut ticker = tokio::time::interval(AGGREGATE_PROGRESS_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    tokio::pin!(work);
+    let result = loop {
+        tokio::select! {
+            result = &mut work => break result,
+            _ = ticker.tick() => emit(tracker.lock().unwrap().snapshot()),
+        }
+    };
+    emit(tracker.lock().unwrap().snapshot());
+    result
+}
+
+pub async fn download_files(
+    window: Window,
+    mut tasks: Vec<DownloadTask>,
+    max_concurrent: usize,
+    verify_retries: u32,
+    retry: RetryPolicy,
+) -> Result<DownloadBatchReport, DownloadError> {
+    {
+        let rules = URL_REWRITE_RULES.read().unwrap();
+        for task in &mut tasks {
+            task.apply_rewrite_rules(&rules);
+        }
+    }
+
+    let dirs: std::collections::HashSet<&Path> =
+        tasks.iter().filter_map(|task| task.path.parent()).collect();
+    for dir in dirs {
+        let removed = remove_stale_part_files(dir, STALE_PART_FILE_AGE).await;
+        if removed > 0 {
+            log::info!(
+                "Removed {} stale partial file(s) in {}",
+                removed,
+                dir.display()
+            );
+        }
+    }
+
+    // Notify start (total files)
+    let _ = window.emit("download-start", tasks.len());
+
+    let started = std::time::Instant::now();
+    let total_files = tasks.len();
+    let tracker = std::sync::Mutex::new(AggregateTracker::new(total_files));
+    let client = crate::core::http::client();
+    let batch = run_download_tasks(
+        &client,
+        tasks,
+        max_concurrent,
+        verify_retries,
+        &retry,
+        &DOWNLOAD_RATE_LIMITER,
+        &DOWNLOAD_CONTROLLER,
+        |event| {
+            tracker
+                .lock()
+                .unwrap()
+                .observe(std::time::Instant::now(), &event);
+            let _ = window.emit("download-progress", event);
+        },
+    );
+    let report = with_aggregate_progress(batch, &tracker, |aggregate| {
+        let _ = window.emit("download-aggregate-progress", aggregate);
+    })
+    .await;
+
+    let _ = window.emit("download-complete", &report);
+    record_batch(
+        window.app_handle(),
+        &report,
+        total_files,
+        tracker.lock().unwrap().snapshot().downloaded_bytes,
+        started,
+    );
+    Ok(report)
+}
+
+/// Add a finished batch to the download history, unless every file was already present
+fn record_batch(
+    app_handle: &AppHandle,
+    report: &DownloadBatchReport,
+    total_files: usize,
+    size_bytes: u64,
+    started: std::time::Instant,
+) {
+    if report.skipped.len() == total_files {
+        return;
+    }
+    let (outcome, error) = if let Some(first) = report.failed.first() {
+        let error = format!(
+            "{} of {} files failed, first: {}: {}",
+            report.failed.len(),
+            total_files,
+            first.task.url,
+            first.error
+        );
+        (DownloadHistoryOutcome::Failed, Some(error))
+    } else if !report.paused.is_empty() || !report.cancelled.is_empty() {
+        (DownloadHistoryOutcome::Cancelled, None)
+    } else {
+        (DownloadHistoryOutcome::Completed, None)
+    };
+    download_history::record(
+        app_handle,
+        DownloadHistoryEntry::finished(
+            DownloadHistoryKind::Batch,
+            None,
+            format!("{} files", total_files),
+            size_bytes,
+            started,
+            outcome,
+            error,
+        ),
+    );
+}
+
+/// Everything the tasks of one batch share
+struct BatchContext<'a, F> {
+    client: &'a reqwest::Client,
+    retry: &'a RetryPolicy,
+    limiter: &'a RateLimiter,
+    controller: &'a DownloadController,
+    progress: GlobalProgress,
+    on_progress: F,
+}
+
+impl<F> BatchContext<'_, F>
+where
+    F: Fn(ProgressEvent),
+{
+    fn emit(&self, task: &DownloadTask, status: &str, downloaded: u64, total: u64, attempt: u32) {
+        emit_progress(
+            &self.on_progress,
+            task,
+            status,
+            downloaded,
+            total,
+            attempt,
+            &self.progress.snapshot(),
+        );
+    }
+}
+
+/// Download every task concurrently and report what happened to each one.
+///
+/// Files that already exist with a matching checksum are skipped. Transient network
+/// failures are retried according to `retry`. Freshly written files are verified too,
+/// and re-fetched up to `verify_retries` times when they don't match.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run_download_tasks<F>(
+    client: &reqwest::Client,
+    tasks: Vec<DownloadTask>,
+    max_concurrent: usize,
+    verify_retries: u32,
+    retry: &RetryPolicy,
+    limiter: &RateLimiter,
+    controller: &DownloadController,
+    on_progress: F,
+) -> DownloadBatchReport
+where
+    F: Fn(ProgressEvent),
+{
+    // Clamp max_concurrent to a valid range (1-128) to prevent edge cases
+    let max_concurrent = max_concurrent.clamp(1, 128);
+
+    controller.begin_batch();
+    let ctx = BatchContext {
+        client,
+        retry,
+        limiter,
+        controller,
+        progress: GlobalProgress::new(tasks.len()),
+        on_progress,
+    };
+    let ctx = &ctx;
+
+    // Start the highest priorities first; the sort is stable so equal priorities
+    // keep their order
+    let mut tasks = tasks;
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.priority));
+
+    // Register every token up front so queued tasks can be cancelled too
+    let tasks: Vec<_> = tasks
+        .into_iter()
+        .map(|task| (controller.register_task(&task.id()), task))
+        .collect();
+
+    let tasks_stream = futures::stream::iter(tasks).map(|(cancel, task)| async move {
+        let (task, result) = run_task(ctx, task, &cancel, verify_retries).await;
+        ctx.controller.finish_task(&task.id());
+        (task, result)
+    });
+
+    // Buffer unordered to run concurrently
+    let results: Vec<_> = tasks_stream
+        .buffer_unordered(max_concurrent)
+        .collect()
+        .await;
+
+    let mut report = DownloadBatchReport::default();
+    for (task, result) in results {
+        match result {
+            Ok(TaskOutcome::Downloaded(url)) => {
+                report.succeeded.push(DownloadSuccess { task, url })
+            }
+            Ok(TaskOutcome::Skipped) => report.skipped.push(task),
+            Ok(TaskOutcome::Paused) => report.paused.push(task),
+            Ok(TaskOutcome::Cancelled) => report.cancelled.push(task),
+            Err((class, error)) => report.failed.push(DownloadFailure { task, error, class }),
+        }
+    }
+    report
+}
+
+/// Verify, download and re-verify a single task of a batch
+async fn run_task<F>(
+    ctx: &BatchContext<'_, F>,
+    task: DownloadTask,
+    cancel: &CancellationToken,
+    verify_retries: u32,
+) -> (
+    DownloadTask,
+    Result<TaskOutcome, (FailureClass, DownloadError)>,
+)
+where
+    F: Fn(ProgressEvent),
+{
+    let file_name = task.path.file_name().unwrap().to_string_lossy().to_string();
+    let part_path = part_path_for(&task.path);
+    let has_checksum = task.sha512.is_some() || task.sha256.is_some() || task.sha1.is_some();
+
+    let running = tokio::select! {
+        biased;
+        _ = cancel.cancelled() => {
+            ctx.emit(&task, "Cancelled", 0, 0, 1);
+            return (task, Ok(TaskOutcome::Cancelled));
+        }
+        running = ctx.controller.wait_while_paused() => running,
+    };
+    if !running {
+        return (task, Ok(TaskOutcome::Paused));
+    }
+
+    // 1. Check if file exists and verify checksum
+    if task.path.exists() {
+        ctx.emit(&task, "Verifying", 0, 0, 1);
+
+        if has_checksum {
+            if let Ok(true) = verify_file_checksum_async(
+                &task.path,
+                task.sha512.as_deref(),
+                task.sha256.as_deref(),
+                task.sha1.as_deref(),
+            )
+            .await
+            {
+                // Already valid, skip download
+                let skipped_size = tokio::fs::metadata(&task.path)
+                    .await
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                if skipped_size > 0 {
+                    let _ = ctx.progress.add_bytes(skipped_size);
+                }
+                emit_progress(
+                    &ctx.on_progress,
+                    &task,
+                    "Skipped",
+                    skipped_size,
+                    skipped_size,
+                    1,
+                    &ctx.progress.inc_completed(),
+                );
+                return (task, Ok(TaskOutcome::Skipped));
+            }
+        }
+    }
+
+    // 2. Download, then verify what was written
+    if let Some(parent) = task.path.parent() {
+        let _ = tokio::fs::create_dir_all(parent).await;
+    }
+
+    let mut attempt = 0;
+    let result = loop {
+        let (written, url) = match fetch_from_mirrors(ctx, &task, &file_name, cancel).await {
+            Ok(fetched) => fetched,
+            Err(FetchError::Stopped) => break Ok(TaskOutcome::Paused),
+            Err(FetchError::Cancelled) => {
+                let _ = tokio::fs::remove_file(&part_path).await;
+                break Ok(TaskOutcome::Cancelled);
+            }
+            Err(FetchError::Transient(e)) => break Err((FailureClass::Transient, e)),
+            Err(FetchError::Permanent(e)) => break Err((FailureClass::Permanent, e)),
+            Err(FetchError::RateLimited(e, _)) => break Err((FailureClass::RateLimited, e)),
+        };
+
+        let valid = !has_checksum || {
+            ctx.emit(&task, "Verifying", 0, 0, 1);
+            verify_file_checksum_async(
+                &part_path,
+                task.sha512.as_deref(),
+                task.sha256.as_deref(),
+                task.sha1.as_deref(),
+            )
+            .await
+            .unwrap_or(false)
+        };
+        if valid {
+            // Only a complete, verified file ever appears under the final name
+            break match tokio::fs::rename(&part_path, &task.path).await {
+                Ok(()) => Ok(TaskOutcome::Downloaded(url)),
+                Err(e) => Err((
+                    FailureClass::Permanent,
+                    DownloadError::io("Failed to rename file", e),
+                )),
+            };
+        }
+
+        // Don't count the discarded bytes towards overall progress
+        let _ = tokio::fs::remove_file(&part_path).await;
+        ctx.progress.sub_bytes(written);
+        if attempt >= verify_retries {
+            log::warn!(
+                "Checksum mismatch for {} after {} attempt(s)",
+                file_name,
+                attempt + 1
+            );
+            let expected = task
+                .sha512
+                .as_ref()
+                .or(task.sha256.as_ref())
+                .or(task.sha1.as_ref());
+            break Err((
+                FailureClass::Permanent,
+                DownloadError::ChecksumMismatch {
+                    expected: expected.cloned().unwrap_or_default(),
+                    actual: None,
+                },
+            ));
+        }
+        attempt += 1;
+        log::warn!(
+            "Checksum mismatch for {}, retrying ({}/{})",
+            file_name,
+            attempt,
+            verify_retries
+        );
+    };
+
+    match result {
+        Ok(TaskOutcome::Downloaded(url)) => {
+            emit_progress(
+                &ctx.on_progress,
+                &task,
+                "Finished",
+                0,
+                0,
+                1,
+                &ctx.progress.inc_completed(),
+            );
+            (task, Ok(TaskOutcome::Downloaded(url)))
+        }
+        Ok(TaskOutcome::Cancelled) => {
+            log::info!("Download of {} cancelled", file_name);
+            ctx.emit(&task, "Cancelled", 0, 0, 1);
+            (task, Ok(TaskOutcome::Cancelled))
+        }
+        Ok(outcome) => (task, Ok(outcome)),
+        Err((class, error)) => {
+            log::error!(
+                "Failed to download {}: {}",
+                task.url,
+                error::display_chain(&error)
+            );
+            ctx.emit(&task, "Error", 0, 0, 1);
+            (task, Err((class, error)))
+        }
+    }
+}
+
+/// `fetch_with_retry` on each of the task's URLs in turn, returning the bytes written
+/// and the URL that worked
+async fn fetch_from_mirrors<F>(
+    ctx: &BatchContext<'_, F>,
+    task: &DownloadTask,
+    file_name: &str,
+    cancel: &CancellationToken,
+) -> Result<(u64, String), FetchError>
+where
+    F: Fn(ProgressEvent),
+{
+    let mut last_error = None;
+    for url in task.urls() {
+        if last_error.is_some() {
+            log::info!("Trying mirror {} for {}", url, file_name);
+        }
+        match fetch_with_retry(ctx, task, url, file_name, cancel).await {
+            Ok(written) => return Ok((written, url.to_string())),
+            Err(
+                e @ (FetchError::Transient(_)
+                | FetchError::Permanent(_)
+                | FetchError::RateLimited(..)),
+            ) => {
+                if let FetchError::Transient(message)
+                | FetchError::Permanent(message)
+                | FetchError::RateLimited(message, _) = &e
+                {
+                    log::warn!("Download of {} from {} failed: {}", file_name, url, message);
+                }
+                let _ = tokio::fs::remove_file(part_path_for(&task.path)).await;
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        FetchError::Permanent(DownloadError::invalid_content("No download URL"))
+    }))
+}
+
+/// `fetch_task` with retries for transient failures, starting each attempt from scratch
+async fn fetch_with_retry<F>(
+    ctx: &BatchContext<'_, F>,
+    task: &DownloadTask,
+    url: &str,
+    file_name: &str,
+    cancel: &CancellationToken,
+) -> Result<u64, FetchError>
+where
+    F: Fn(ProgressEvent),
+{
+    let mut attempt = 1;
+    loop {
+        match fetch_task(ctx, task, url, file_name, attempt, cancel).await {
+            Err(FetchError::Transient(e)) if attempt < ctx.retry.max_attempts => {
+                let _ = tokio::fs::remove_file(part_path_for(&task.path)).await;
+                let delay = ctx.retry.delay_after(attempt);
+                attempt += 1;
+                log::warn!(
+                    "{} failed: {}, retrying in {:?} ({}/{})",
+                    file_name,
+                    error::display_chain(&e),
+                    delay,
+                    attempt,
+                    ctx.retry.max_attempts
+                );
+                ctx.emit(task, "Retrying", 0, 0, attempt);
+                tokio::select! {
+                    _ = cancel.cancelled() => return Err(FetchError::Cancelled),
+                    _ = tokio::time::sleep(delay) => {}
+                }
+            }
+            Err(FetchError::RateLimited(e, retry_after)) if attempt < ctx.retry.max_attempts => {
+                let _ = tokio::fs::remove_file(part_path_for(&task.path)).await;
+                let delay = ctx.retry.delay_after_rate_limit(attempt, retry_after);
+                attempt += 1;
+                log::warn!(
+                    "{} was rate limited: {}, retrying in {:?} ({}/{})",
+                    file_name,
+                    error::display_chain(&e),
+                    delay,
+                    attempt,
+                    ctx.retry.max_attempts
+                );
+                ctx.emit(task, "Retrying", 0, 0, attempt);
+                tokio::select! {
+                    _ = cancel.cancelled() => return Err(FetchError::Cancelled),
+                    _ = tokio::time::sleep(delay) => {}
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
+/// How often a single attempt may pick up again after the connection drops mid-body
+const MAX_MID_STREAM_RESUMES: u32 = 5;
+
+fn stalled(timeout: std::time::Duration) -> FetchError {
+    FetchError::Transient(DownloadError::Stalled {
+        timeout_ms: timeout.as_millis() as u64,
+    })
+}
+
+/// Stream a single task to disk, returning the number of bytes written.
+///
+/// Pausing the batch drops the connection; on resume the file continues with a range
+/// request, or starts over if the server can't serve one. A connection that drops
+/// mid-body is continued the same way when the server accepts ranges.
+async fn fetch_task<F>(
+    ctx: &BatchContext<'_, F>,
+    task: &DownloadTask,
+    url: &str,
+    file_name: &str,
+    attempt: u32,
+    cancel: &CancellationToken,
+) -> Result<u64, FetchError>
+where
+    F: Fn(ProgressEvent),
+{
+    let part_path = part_path_for(&task.path);
+    let mut downloaded: u64 = 0;
+    let mut resumes = 0;
+    let mut throttle = ProgressThrottle::new();
+    let result = async {
+        'request: loop {
+            let mut request = ctx.client.get(url);
+            if downloaded > 0 {
+                request = request.header(reqwest::header::RANGE, format!("bytes={}-", downloaded));
+            }
+            let mut resp = tokio::time::timeout(ctx.retry.stall_timeout, request.send())
+                .await
+                .map_err(|_| stalled(ctx.retry.stall_timeout))?
+                .map_err(|e| {
+                    let permanent = e.is_builder() || e.is_redirect();
+                    let message = DownloadError::request_failed(url, "Request error", e);
+                    if permanent {
+                        FetchError::Permanent(message)
+                    } else {
+                        FetchError::Transient(message)
+                    }
+                })?;
+            let status = resp.status();
+            if !status.is_success() {
+                let message = DownloadError::request(
+                    url,
+                    Some(status),
+                    format!("Server returned error: {}", status),
+                );
+                return Err(match classify_status(status, resp.headers()) {
+                    FailureClass::Permanent => FetchError::Permanent(message),
+                    FailureClass::Transient => FetchError::Transient(message),
+                    FailureClass::RateLimited => {
+                        FetchError::RateLimited(message, parse_retry_after(resp.headers()))
+                    }
+                });
+            }
+            if is_html_response(&resp) && expects_binary(&task.path) {
+                return Err(FetchError::Permanent(DownloadError::invalid_content(
+                    HTML_INSTEAD_OF_FILE,
+                )));
+            }
+
+            let resumed = downloaded > 0
+                && status == reqwest::StatusCode::PARTIAL_CONTENT
+                && content_range_matches(&resp, downloaded, 0);
+            if downloaded > 0 && !resumed {
+                log::info!("Cannot resume {}, restarting", file_name);
+                ctx.progress.sub_bytes(downloaded);
+                downloaded = 0;
+            }
+            let total_size = resp.content_length().map_or(0, |len| len + downloaded);
+            let accepts_ranges = resumed
+                || resp
+                    .headers()
+                    .get(reqwest::header::ACCEPT_RANGES)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
+            let file = if resumed {
+                tokio::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&part_path)
+                    .await
+            } else {
+                tokio::fs::File::create(&part_path).await
+            }
+            .map_err(|e| FetchError::Permanent(DownloadError::io("Create file error", e)))?;
+            let mut file = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_SIZE, file);
+
+            loop {
+                if !ctx.controller.is_running() {
+                    file.flush()
+                        .await
+                        .map_err(|e| FetchError::Permanent(DownloadError::io("Write error", e)))?;
+                    drop(resp);
+                    ctx.emit(task, "Paused", downloaded, total_size, attempt);
+                    let running = tokio::select! {
+                        biased;
+                        _ = cancel.cancelled() => return Err(FetchError::Cancelled),
+                        running = ctx.controller.wait_while_paused() => running,
+                    };
+                    if !running {
+                        return Err(FetchError::Stopped);
+                    }
+                    continue 'request;
+                }
+
+                let chunk = tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => return Err(FetchError::Cancelled),
+                    chunk = tokio::time::timeout(ctx.retry.stall_timeout, resp.chunk()) => {
+                        chunk.map_err(|_| stalled(ctx.retry.stall_timeout))?
+                    }
+                };
+                match chunk {
+                    Ok(Some(chunk)) => {
+                        ctx.limiter.acquire(chunk.len() as u64).await;
+                        file.write_all(&chunk).await.map_err(|e| {
+                            FetchError::Permanent(DownloadError::io("Write error", e))
+                        })?;
+                        downloaded += chunk.len() as u64;
+                        let snapshot = ctx.progress.add_bytes(chunk.len() as u64);
+                        if throttle.ready(downloaded, total_size > 0 && downloaded >= total_size) {
+                            emit_progress(
+                                &ctx.on_progress,
+                                task,
+                                "Downloading",
+                                downloaded,
+                                total_size,
+                                attempt,
+                                &snapshot,
+                            );
+                        }
+                    }
+                    Ok(None) => {
+                        file.flush().await.map_err(|e| {
+                            FetchError::Permanent(DownloadError::io("Write error", e))
+                        })?;
+                        file.get_ref().sync_all().await.map_err(|e| {
+                            FetchError::Permanent(DownloadError::io("Write error", e))
+                        })?;
+                        // A proxy may cut the body short without a transport error
+                        if let Some(expected) = task
+                            .expected_size
+                            .or((total_size > 0).then_some(total_size))
+                            .filter(|&expected| expected != downloaded)
+                        {
+                            return Err(FetchError::Transient(DownloadError::SizeMismatch {
+                                expected,
+                                actual: downloaded,
+                            }));
+                        }
+                        check_downloaded_file(
+                            &part_path,
+                            &task.path,
+                            task.expected_size == Some(0),
+                        )
+                        .await
+                        .map_err(FetchError::Permanent)?;
+                        break 'request;
+                    }
+                    // The connection dropped mid-body, e.g. a reset
+                    Err(e)
+                        if accepts_ranges && downloaded > 0 && resumes < MAX_MID_STREAM_RESUMES =>
+                    {
+                        file.flush().await.map_err(|e| {
+                            FetchError::Permanent(DownloadError::io("Write error", e))
+                        })?;
+                        drop(resp);
+                        resumes += 1;
+                        log::warn!(
+                            "{} interrupted at {} bytes: {}, resuming ({}/{})",
+                            file_name,
+                            downloaded,
+                            e,
+                            resumes,
+                            MAX_MID_STREAM_RESUMES
+                        );
+                        tokio::select! {
+                            _ = cancel.cancelled() => return Err(FetchError::Cancelled),
+                            _ = tokio::time::sleep(ctx.retry.delay_after(resumes)) => {}
+                        }
+                        continue 'request;
+                    }
+                    Err(e) => {
+                        return Err(FetchError::Transient(DownloadError::request(
+                            url,
+                            None,
+                            format!("Download error: {}", e),
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(()) => Ok(downloaded),
+        // A stopped task keeps its partial file for a later batch
+        Err(FetchError::Stopped) => Err(FetchError::Stopped),
+        Err(e) => {
+            // Whatever this attempt wrote is thrown away
+            ctx.progress.sub_bytes(downloaded);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mock_server::{MockServer, Response};
+    use crate::utils::test_dir::TestDir;
+    use std::sync::Mutex;
+    use std::sync::atomic::AtomicBool;
+    use uuid::Uuid;
+
+    const JDK_PATH: &str = "/jdk.tar.gz";
+
+    /// Serve `body` at `JDK_PATH`, optionally honouring `Range`
+    async fn spawn_server(
+        body: Vec<u8>,
+        support_ranges: bool,
+        cut_first_response_after: Option<usize>,
+        corrupt_first_responses: usize,
+    ) -> MockServer {
+        let faults = Mutex::new((cut_first_response_after, corrupt_first_responses));
+        MockServer::builder()
+            .route(JDK_PATH, move |request| {
+                let len = body.len();
+                let requested = request
+                    .header("range")
+                    .filter(|_| support_ranges)
+                    .and_then(|r| r.strip_prefix("bytes="))
+                    .and_then(|r| r.split_once('-'))
+                    .and_then(|(start, end)| {
+                        let start = start.parse::<usize>().ok()?;
+                        let end = end.parse::<usize>().unwrap_or(len - 1);
+                        Some((start, end.min(len - 1)))
+                    });
+                let mut payload = match requested {
+                    Some((start, end)) => body[start..=end].to_vec(),
+                    None => body.clone(),
+                };
+                let announced = payload.len();
+
+                let (cut, corrupt) = &mut *faults.lock().unwrap();
+                // Drop the connection part-way through the first sizeable response
+                if let Some(n) = *cut
+                    && payload.len() > n
+                {
+                    *cut = None;
+                    payload.truncate(n);
+                }
+                // Flip a byte so the body no longer matches its checksum
+                if *corrupt > 0 && !payload.is_empty() {
+                    *corrupt -= 1;
+                    let last = payload.len() - 1;
+                    payload[last] ^= 0xff;
+                }
+
+                let mut response = match requested {
+                    Some((start, end)) => Response::new(206, payload)
+                        .header("Content-Range", format!("bytes {}-{}/{}", start, end, len)),
+                    None => Response::ok(payload),
+                };
+                if support_ranges {
+                    response = response.header("Accept-Ranges", "bytes");
+                }
+                response.content_length(Some(announced))
+            })
+            .start()
+            .await
+    }
+
+    /// `Range` header of every request `server` received, in order
+    fn ranges(server: &MockServer) -> Vec<Option<String>> {
+        server
+            .requests()
+            .iter()
+            .map(|request| request.header("range").map(str::to_string))
+            .collect()
+    }
+
+    /// Starts like a zip archive, so it passes the magic byte check for `.jar`/`.zip`
+    fn test_body() -> Vec<u8> {
+        let mut body: Vec<u8> = (0..300_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        body[..4].copy_from_slice(b"PK\x03\x04");
+        body
+    }
+
+    #[test]
+    fn parses_content_range_header() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/200"),
+            Some((100, Some(200)))
+        );
+        assert_eq!(parse_content_range("bytes 5-9/*"), Some((5, None)));
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+        assert_eq!(parse_content_range("bytes abc"), None);
+    }
+
+    #[tokio::test]
+    async fn resumes_interrupted_download_with_range_request() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let checksum = compute_sha256(&body);
+        let server = spawn_server(body.clone(), true, Some(120_000), 0).await;
+        let dest = dir.path().join("jdk.zip");
+        let client = crate::core::http::client();
+        let total = body.len() as u64;
+
+        let first = download_resumable(
+            &client,
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            Some(&checksum),
+            total,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await;
+        assert!(first.is_err());
+        assert!(!dest.exists());
+        let part = part_path_for(&dest);
+        assert_eq!(std::fs::metadata(&part).unwrap().len(), 120_000);
+        let meta: DownloadMetadata =
+            serde_json::from_str(&std::fs::read_to_string(meta_path_for(&part)).unwrap()).unwrap();
+        assert!(meta.accept_ranges);
+        assert_eq!(meta.total_size, total);
+        assert_eq!(meta.checksum.as_deref(), Some(checksum.as_str()));
+
+        download_resumable(
+            &client,
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            Some(&checksum),
+            total,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!part.exists());
+        assert!(!meta_path_for(&part).exists());
+        assert_eq!(
+            ranges(&server),
+            vec![None, Some("bytes=120000-".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn restarts_from_scratch_when_server_ignores_range() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let checksum = compute_sha256(&body);
+        let server = spawn_server(body.clone(), false, None, 0).await;
+        let dest = dir.path().join("jdk.zip");
+        let part = part_path_for(&dest);
+        let total = body.len() as u64;
+
+        // Leftover from an attempt against a server that used to support ranges
+        std::fs::write(&part, vec![0u8; 50_000]).unwrap();
+        let mut meta =
+            create_new_metadata(&server.url(JDK_PATH), "jdk.zip", total, Some(&checksum));
+        meta.accept_ranges = true;
+        std::fs::write(meta_path_for(&part), serde_json::to_string(&meta).unwrap()).unwrap();
+
+        download_resumable(
+            &crate::core::http::client(),
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            Some(&checksum),
+            total,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert_eq!(ranges(&server), vec![Some("bytes=50000-".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn ignores_partial_file_from_a_different_download() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let dest = dir.path().join("jdk.zip");
+        let part = part_path_for(&dest);
+
+        std::fs::write(&part, vec![0u8; 50_000]).unwrap();
+        let mut meta = create_new_metadata("https://example.com/other.tar.gz", "jdk.zip", 0, None);
+        meta.accept_ranges = true;
+        std::fs::write(meta_path_for(&part), serde_json::to_string(&meta).unwrap()).unwrap();
+
+        download_resumable(
+            &crate::core::http::client(),
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            None,
+            0,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert_eq!(ranges(&server), vec![None]);
+    }
+
+    #[tokio::test]
+    async fn discards_part_file_on_checksum_mismatch() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let dest = dir.path().join("jdk.zip");
+        let part = part_path_for(&dest);
+
+        let result = download_resumable(
+            &crate::core::http::client(),
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            Some(&compute_sha256(b"something else")),
+            body.len() as u64,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(DownloadError::ChecksumMismatch { .. })
+        ));
+        assert!(!dest.exists());
+        assert!(!part.exists());
+        assert!(!meta_path_for(&part).exists());
+    }
+
+    #[tokio::test]
+    async fn checksum_covers_resumed_prefix() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let checksum = compute_sha256(&body);
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let dest = dir.path().join("jdk.zip");
+        let part = part_path_for(&dest);
+        let total = body.len() as u64;
+
+        // A prefix that was corrupted on disk must still fail verification
+        let mut prefix = vec![0u8; 50_000];
+        prefix[..4].copy_from_slice(b"PK\x03\x04");
+        std::fs::write(&part, prefix).unwrap();
+        let mut meta =
+            create_new_metadata(&server.url(JDK_PATH), "jdk.zip", total, Some(&checksum));
+        meta.accept_ranges = true;
+        std::fs::write(meta_path_for(&part), serde_json::to_string(&meta).unwrap()).unwrap();
+
+        let result = download_resumable(
+            &crate::core::http::client(),
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            Some(&checksum),
+            total,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(DownloadError::ChecksumMismatch { .. })
+        ));
+        assert_eq!(ranges(&server), vec![Some("bytes=50000-".to_string())]);
+        assert!(!dest.exists());
+        assert!(!part.exists());
+    }
+
+    #[tokio::test]
+    async fn file_checksum_is_read_in_chunks() {
+        let dir = TestDir::new("downloader");
+        let path = dir.path().join("library.jar");
+        // Spans several read chunks and ends on a partial one
+        let data: Vec<u8> = (0..5 * 1024 * 1024 + 123)
+            .map(|i: usize| (i.wrapping_mul(31) % 251) as u8)
+            .collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let sha512 = compute_sha512(&data);
+        let sha256 = compute_sha256(&data);
+        let sha1 = compute_sha1(&data);
+        let cases = [
+            (None, Some(sha256.as_str()), None, true),
+            (None, None, Some(sha1.as_str()), true),
+            (Some(sha512.as_str()), None, None, true),
+            (None, Some("wrong"), Some(sha1.as_str()), false),
+            (None, None, None, true),
+        ];
+        for (sha512, sha256, sha1, expected) in cases {
+            assert_eq!(
+                verify_file_checksum(&path, sha512, sha256, sha1).unwrap(),
+                expected
+            );
+            assert_eq!(
+                verify_file_checksum_async(&path, sha512, sha256, sha1)
+                    .await
+                    .unwrap(),
+                expected
+            );
+        }
+
+        assert!(
+            verify_file_checksum(&dir.path().join("missing.jar"), None, Some(&sha256), None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn checksum_precedence_prefers_strongest_hash() {
+        let dir = TestDir::new("downloader");
+        let path = dir.path().join("dropout.txt");
+        let data = b"dropout";
+        std::fs::write(&path, data).unwrap();
+        let good512 = compute_sha512(data);
+        let good256 = compute_sha256(data);
+        let good1 = compute_sha1(data);
+        let (g512, g256, g1) = (
+            Some(good512.as_str()),
+            Some(good256.as_str()),
+            Some(good1.as_str()),
+        );
+        let bad = Some("wrong");
+
+        // (sha512, sha256, sha1, expected)
+        let cases = [
+            (None, None, None, true),
+            (None, None, g1, true),
+            (None, None, bad, false),
+            (None, g256, None, true),
+            (None, bad, None, false),
+            (None, g256, bad, true),
+            (None, bad, g1, false),
+            (g512, None, None, true),
+            (bad, None, None, false),
+            (g512, bad, None, true),
+            (bad, g256, None, false),
+            (g512, None, bad, true),
+            (bad, None, g1, false),
+            (g512, bad, bad, true),
+            (bad, g256, g1, false),
+        ];
+        for (sha512, sha256, sha1, expected) in cases {
+            assert_eq!(
+                verify_file_checksum(&path, sha512, sha256, sha1).unwrap(),
+                expected,
+                "sha512={sha512:?} sha256={sha256:?} sha1={sha1:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn download_task_deserializes_without_sha512() {
+        let task: DownloadTask = serde_json::from_str(
+            r#"{"url":"https://example.com/a.jar","path":"a.jar","sha1":"abc","sha256":null}"#,
+        )
+        .unwrap();
+        assert_eq!(task.sha1.as_deref(), Some("abc"));
+        assert_eq!(task.sha512, None);
+    }
+
+    fn fast_retry() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: std::time::Duration::from_millis(10),
+            max_delay: std::time::Duration::from_millis(50),
+            rate_limit_delay: std::time::Duration::from_millis(20),
+            max_rate_limit_delay: std::time::Duration::from_secs(2),
+            ..RetryPolicy::default()
+        }
+    }
+
+    const LIBRARY_PATH: &str = "/library.jar";
+
+    /// Answer every request with an empty response of the given status
+    async fn spawn_status_server(status: u16) -> MockServer {
+        MockServer::builder()
+            .fallback(move |_| Response::status(status))
+            .start()
+            .await
+    }
+
+    /// Answer every request with `body` and the given `Content-Length` (or none), then
+    /// close the connection, like a proxy that cuts responses short
+    async fn spawn_truncating_server(body: Vec<u8>, content_length: Option<usize>) -> MockServer {
+        MockServer::builder()
+            .fallback(move |_| Response::ok(body.clone()).content_length(content_length))
+            .start()
+            .await
+    }
+
+    fn library_task(server: &MockServer, path: PathBuf, body: &[u8]) -> DownloadTask {
+        DownloadTask {
+            url: server.url(JDK_PATH),
+            path,
+            sha1: Some(compute_sha1(body)),
+            sha256: None,
+            sha512: None,
+            expected_size: None,
+            fallback_urls: Vec::new(),
+            priority: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn download_files_refetches_corrupted_file() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), true, None, 1).await;
+        let path = dir.path().join("library.jar");
+        let statuses = Mutex::new(Vec::new());
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![library_task(&server, path.clone(), &body)],
+            4,
+            2,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |event| statuses.lock().unwrap().push(event.status),
+        )
+        .await;
+
+        assert!(report.is_success());
+        assert_eq!(report.succeeded.len(), 1);
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(
+            statuses.lock().unwrap().last().map(String::as_str),
+            Some("Finished")
+        );
+    }
+
+    #[tokio::test]
+    async fn download_files_reports_persistent_checksum_mismatch() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), true, None, usize::MAX).await;
+        let path = dir.path().join("library.jar");
+        let events = Mutex::new(Vec::new());
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![library_task(&server, path.clone(), &body)],
+            4,
+            2,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |event| events.lock().unwrap().push(event),
+        )
+        .await;
+
+        let failures = &report.failed;
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].task.path, path);
+        assert_eq!(failures[0].task.url, server.url(JDK_PATH));
+        assert!(matches!(
+            failures[0].error,
+            DownloadError::ChecksumMismatch { actual: None, .. }
+        ));
+        assert!(!path.exists());
+        // Initial download plus two retries
+        assert_eq!(server.requests().len(), 3);
+
+        let events = events.lock().unwrap();
+        let last = events.last().unwrap();
+        assert_eq!(last.status, "Error");
+        assert_eq!(last.completed_files, 0);
+        assert_eq!(last.total_downloaded_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn download_files_retries_after_connection_reset() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        // Without range support the file has to start over
+        let server = spawn_server(body.clone(), false, Some(100_000), 0).await;
+        let path = dir.path().join("library.jar");
+        let events = Mutex::new(Vec::new());
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![library_task(&server, path.clone(), &body)],
+            4,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |event| events.lock().unwrap().push(event),
+        )
+        .await;
+
+        assert!(report.is_success());
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(server.requests().len(), 2);
+
+        let events = events.lock().unwrap();
+        assert!(
+            events
+                .iter()
+                .any(|e| e.status == "Retrying" && e.attempt == 2)
+        );
+        let last_download = events
+            .iter()
+            .rev()
+            .find(|e| e.status == "Downloading")
+            .unwrap();
+        assert_eq!(last_download.attempt, 2);
+        // Bytes from the interrupted attempt are not double counted
+        assert_eq!(
+            events.last().unwrap().total_downloaded_bytes,
+            body.len() as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn connection_reset_resumes_mid_stream() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let half = body.len() / 2;
+        let server = spawn_server(body.clone(), true, Some(half), 0).await;
+        let path = dir.path().join("library.jar");
+        let events = Mutex::new(Vec::new());
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![library_task(&server, path.clone(), &body)],
+            1,
+            0,
+            // A single attempt: the reset must be handled without a retry
+            &RetryPolicy {
+                max_attempts: 1,
+                ..fast_retry()
+            },
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |event| events.lock().unwrap().push(event),
+        )
+        .await;
+
+        assert!(report.is_success());
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(
+            ranges(&server),
+            vec![None, Some(format!("bytes={}-", half))]
+        );
+        assert_eq!(
+            events
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .total_downloaded_bytes,
+            body.len() as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn download_files_only_retries_transient_statuses() {
+        let dir = TestDir::new("downloader");
+        let missing = spawn_status_server(404).await;
+        let busy = spawn_status_server(503).await;
+        let limited = spawn_status_server(429).await;
+        let task = |url: String, name: &str| DownloadTask {
+            url,
+            path: dir.path().join(name),
+            sha1: None,
+            sha256: None,
+            sha512: None,
+            expected_size: None,
+            fallback_urls: Vec::new(),
+            priority: 0,
+        };
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![
+                task(missing.url(LIBRARY_PATH), "missing.jar"),
+                task(busy.url(LIBRARY_PATH), "busy.jar"),
+                task(limited.url(LIBRARY_PATH), "limited.jar"),
+            ],
+            4,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+
+        assert_eq!(report.failed.len(), 3);
+        let class_of = |name: &str| {
+            report
+                .failed
+                .iter()
+                .find(|failure| failure.task.path.ends_with(name))
+                .unwrap()
+                .class
+        };
+        assert_eq!(class_of("missing.jar"), FailureClass::Permanent);
+        assert_eq!(class_of("busy.jar"), FailureClass::Transient);
+        assert_eq!(class_of("limited.jar"), FailureClass::RateLimited);
+        assert_eq!(missing.hits(LIBRARY_PATH), 1);
+        assert_eq!(busy.hits(LIBRARY_PATH), 3);
+        assert_eq!(limited.hits(LIBRARY_PATH), 3);
+        assert!(!dir.path().join("missing.jar").exists());
+    }
+
+    #[tokio::test]
+    async fn download_files_honors_retry_after() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let served = body.clone();
+        let server = MockServer::builder()
+            .route(LIBRARY_PATH, move |request| match request.hit {
+                1 => Response::status(429).header("Retry-After", 1),
+                _ => Response::ok(served.clone()),
+            })
+            .start()
+            .await;
+        let task = DownloadTask {
+            url: server.url(LIBRARY_PATH),
+            path: dir.path().join("limited.jar"),
+            sha1: None,
+            sha256: None,
+            sha512: None,
+            expected_size: None,
+            fallback_urls: Vec::new(),
+            priority: 0,
+        };
+
+        let started = std::time::Instant::now();
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![task],
+            1,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+
+        assert!(report.is_success());
+        assert_eq!(server.hits(LIBRARY_PATH), 2);
+        // The 20ms rate-limit backoff would have been used without the header
+        assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+        assert_eq!(std::fs::read(dir.path().join("limited.jar")).unwrap(), body);
+    }
+
+    #[test]
+    fn failure_class_follows_status_and_retry_after() {
+        use reqwest::StatusCode;
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+        let none = HeaderMap::new();
+        let mut with_retry_after = HeaderMap::new();
+        with_retry_after.insert(RETRY_AFTER, HeaderValue::from_static("30"));
+
+        assert_eq!(
+            classify_status(StatusCode::NOT_FOUND, &none),
+            FailureClass::Permanent
+        );
+        assert_eq!(
+            classify_status(StatusCode::FORBIDDEN, &none),
+            FailureClass::Permanent
+        );
+        assert_eq!(
+            classify_status(StatusCode::FORBIDDEN, &with_retry_after),
+            FailureClass::RateLimited
+        );
+        assert_eq!(
+            classify_status(StatusCode::TOO_MANY_REQUESTS, &none),
+            FailureClass::RateLimited
+        );
+        assert_eq!(
+            classify_status(StatusCode::BAD_GATEWAY, &none),
+            FailureClass::Transient
+        );
+        assert_eq!(
+            classify_status(StatusCode::REQUEST_TIMEOUT, &none),
+            FailureClass::Transient
+        );
+
+        assert_eq!(
+            parse_retry_after(&with_retry_after),
+            Some(std::time::Duration::from_secs(30))
+        );
+        assert_eq!(parse_retry_after(&none), None);
+        let mut dated = HeaderMap::new();
+        let at = chrono::Utc::now() + chrono::Duration::seconds(90);
+        dated.insert(
+            RETRY_AFTER,
+            HeaderValue::from_str(&at.to_rfc2822()).unwrap(),
+        );
+        let wait = parse_retry_after(&dated).unwrap();
+        assert!(
+            wait > std::time::Duration::from_secs(80) && wait <= std::time::Duration::from_secs(90)
+        );
+        let mut past = HeaderMap::new();
+        past.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&past), Some(std::time::Duration::ZERO));
+
+        let policy = fast_retry();
+        assert_eq!(
+            policy.delay_after_rate_limit(1, None),
+            std::time::Duration::from_millis(20)
+        );
+        assert_eq!(
+            policy.delay_after_rate_limit(2, None),
+            std::time::Duration::from_millis(40)
+        );
+        assert_eq!(
+            policy.delay_after_rate_limit(1, Some(std::time::Duration::from_secs(600))),
+            std::time::Duration::from_secs(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn download_files_starts_high_priority_tasks_first() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let priorities = [0, 2, 0, 1, 0, 2, 1, 0];
+        let tasks: Vec<_> = priorities
+            .iter()
+            .enumerate()
+            .map(|(i, &priority)| DownloadTask {
+                priority,
+                ..library_task(
+                    &server,
+                    dir.path().join(format!("{}-{}.jar", priority, i)),
+                    &body,
+                )
+            })
+            .collect();
+        let events = Mutex::new(Vec::new());
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            tasks,
+            1,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |event| events.lock().unwrap().push(event),
+        )
+        .await;
+
+        assert!(report.is_success());
+        let events = events.into_inner().unwrap();
+        let mut started = Vec::new();
+        for event in &events {
+            if !started.contains(&event.file) {
+                started.push(event.file.clone());
+            }
+        }
+        let started: Vec<u8> = started
+            .iter()
+            .map(|file| file.split('-').next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(started, vec![2, 2, 1, 1, 0, 0, 0, 0]);
+        let last = events.last().unwrap();
+        assert_eq!(last.completed_files, priorities.len());
+        assert_eq!(last.total_files, priorities.len());
+        assert_eq!(
+            last.total_downloaded_bytes,
+            (body.len() * priorities.len()) as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn download_files_falls_back_to_mirror() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let missing = spawn_status_server(404).await;
+        let busy = spawn_status_server(503).await;
+        let mirror = spawn_server(body.clone(), true, None, 0).await;
+        let mut task = library_task(&mirror, dir.path().join("mirrored.jar"), &body);
+        task.url = missing.url(LIBRARY_PATH);
+        task.fallback_urls = vec![busy.url(LIBRARY_PATH), mirror.url(JDK_PATH)];
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![task],
+            1,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+
+        assert!(report.is_success());
+        assert_eq!(report.succeeded[0].url, mirror.url(JDK_PATH));
+        assert_eq!(missing.hits(LIBRARY_PATH), 1);
+        assert_eq!(busy.hits(LIBRARY_PATH), 3);
+        assert_eq!(
+            std::fs::read(dir.path().join("mirrored.jar")).unwrap(),
+            body
+        );
+    }
+
+    #[test]
+    fn rewrite_rules_mirror_host_and_keep_original_as_fallback() {
+        let rules = vec![
+            (
+                "https://piston-data.mojang.com/".to_string(),
+                "https://mirror.test/data/".to_string(),
+            ),
+            (
+                "https://libraries.minecraft.net/".to_string(),
+                "https://mirror.test/maven/".to_string(),
+            ),
+        ];
+        let mut task = DownloadTask {
+            url: "https://libraries.minecraft.net/a/b.jar".to_string(),
+            path: PathBuf::from("b.jar"),
+            sha1: None,
+            sha256: None,
+            sha512: None,
+            expected_size: None,
+            fallback_urls: vec!["https://other.test/a/b.jar".to_string()],
+            priority: 0,
+        };
+        task.apply_rewrite_rules(&rules);
+        assert_eq!(task.url, "https://mirror.test/maven/a/b.jar");
+        assert_eq!(
+            task.fallback_urls,
+            vec![
+                "https://libraries.minecraft.net/a/b.jar".to_string(),
+                "https://other.test/a/b.jar".to_string()
+            ]
+        );
+
+        let mut untouched = task.clone();
+        untouched.url = "https://example.com/c.jar".to_string();
+        untouched.apply_rewrite_rules(&rules);
+        assert_eq!(untouched.url, "https://example.com/c.jar");
+        assert_eq!(untouched.fallback_urls.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stalled_download_is_retried_instead_of_hanging() {
+        let dir = TestDir::new("downloader");
+        // Send the headers and a few bytes, then go quiet with the connection open
+        let server = MockServer::builder()
+            .fallback(|_| Response::ok("start").content_length(Some(1000)).stall())
+            .start()
+            .await;
+        let task = DownloadTask {
+            url: server.url(LIBRARY_PATH),
+            path: dir.path().join("stalled.jar"),
+            sha1: None,
+            sha256: None,
+            sha512: None,
+            expected_size: None,
+            fallback_urls: Vec::new(),
+            priority: 0,
+        };
+
+        let retry = fast_retry().with_stall_timeout(std::time::Duration::from_millis(200));
+        let report = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            run_download_tasks(
+                &crate::core::http::client(),
+                vec![task],
+                1,
+                0,
+                &retry,
+                &RateLimiter::default(),
+                &DownloadController::default(),
+                |_| {},
+            ),
+        )
+        .await
+        .expect("stalled download hung");
+
+        assert_eq!(report.failed.len(), 1);
+        assert!(matches!(
+            report.failed[0].error,
+            DownloadError::Stalled { .. }
+        ));
+        assert_eq!(server.hits(LIBRARY_PATH), 3);
+        assert!(!dir.path().join("stalled.jar").exists());
+    }
+
+    #[tokio::test]
+    async fn download_files_rejects_truncated_responses() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let half = body[..body.len() / 2].to_vec();
+        // No Content-Length, so only the manifest size reveals the truncation
+        let unsized_server = spawn_truncating_server(half.clone(), None).await;
+        // Claims the full length but stops half-way
+        let lying_server = spawn_truncating_server(half, Some(body.len())).await;
+        let task = |url: String, name: &str, expected_size| DownloadTask {
+            url,
+            path: dir.path().join(name),
+            sha1: None,
+            sha256: None,
+            sha512: None,
+            expected_size,
+            fallback_urls: Vec::new(),
+            priority: 0,
+        };
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![
+                task(
+                    unsized_server.url(LIBRARY_PATH),
+                    "unsized.jar",
+                    Some(body.len() as u64),
+                ),
+                task(lying_server.url(LIBRARY_PATH), "lying.jar", None),
+            ],
+            2,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+
+        assert_eq!(report.failed.len(), 2);
+        let unsized_failure = report
+            .failed
+            .iter()
+            .find(|f| f.task.path.ends_with("unsized.jar"))
+            .unwrap();
+        assert!(
+            matches!(unsized_failure.error, DownloadError::SizeMismatch { .. }),
+            "{}",
+            unsized_failure.error
+        );
+        assert_eq!(unsized_server.hits(LIBRARY_PATH), 3);
+        assert!(!dir.path().join("unsized.jar").exists());
+        assert!(!dir.path().join("lying.jar").exists());
+    }
+
+    const PORTAL_PAGE: &[u8] = b"<!DOCTYPE html><html><body>Please log in</body></html>";
+
+    /// Answer every request with `body` and, if given, a `Content-Type`
+    async fn spawn_page_server(body: &'static [u8], content_type: Option<&'static str>) -> String {
+        let server = MockServer::builder()
+            .fallback(move |_| match content_type {
+                Some(content_type) => Response::ok(body).header("Content-Type", content_type),
+                None => Response::ok(body),
+            })
+            .start()
+            .await;
+        server.url("/file")
+    }
+
+    #[tokio::test]
+    async fn download_files_rejects_empty_files_and_html_pages() {
+        let dir = TestDir::new("downloader");
+        let html_url = spawn_page_server(PORTAL_PAGE, Some("text/html; charset=utf-8")).await;
+        // Some portals don't even set a Content-Type
+        let untyped_url = spawn_page_server(PORTAL_PAGE, None).await;
+        let empty_url = spawn_page_server(b"", None).await;
+        let task = |url: String, name: &str| DownloadTask {
+            url,
+            path: dir.path().join(name),
+            sha1: Some(compute_sha1(b"the real file")),
+            sha256: None,
+            sha512: None,
+            expected_size: None,
+            fallback_urls: Vec::new(),
+            priority: 0,
+        };
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![
+                task(html_url, "client.jar"),
+                task(untyped_url, "natives.jar"),
+                task(empty_url, "empty.jar"),
+            ],
+            3,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+
+        assert_eq!(report.failed.len(), 3);
+        let error_for = |name: &str| {
+            report
+                .failed
+                .iter()
+                .find(|f| f.task.path.ends_with(name))
+                .unwrap()
+                .error
+                .clone()
+        };
+        let html = DownloadError::invalid_content(HTML_INSTEAD_OF_FILE);
+        assert_eq!(error_for("client.jar"), html);
+        assert_eq!(error_for("natives.jar"), html);
+        assert!(error_for("empty.jar").to_string().contains("is empty"));
+        assert!(!dir.path().join("client.jar").exists());
+        assert!(!part_path_for(&dir.path().join("natives.jar")).exists());
+    }
+
+    #[tokio::test]
+    async fn java_download_rejects_html_page() {
+        let dir = TestDir::new("downloader");
+        let url = spawn_page_server(PORTAL_PAGE, None).await;
+        let dest = dir.path().join("jdk.tar.gz");
+
+        let result = download_resumable(
+            &crate::core::http::client(),
+            "test",
+            &url,
+            &dest,
+            Some(&compute_sha256(b"the real archive")),
+            0,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await;
+
+        assert_eq!(
+            result.unwrap_err(),
+            DownloadError::invalid_content(HTML_INSTEAD_OF_FILE)
+        );
+        assert!(!dest.exists());
+        assert!(!part_path_for(&dest).exists());
+    }
+
+    #[tokio::test]
+    async fn java_download_checks_size_from_catalog() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let half = body[..body.len() / 2].to_vec();
+        let server = spawn_truncating_server(half.clone(), Some(half.len())).await;
+        let dest = dir.path().join("jdk.zip");
+
+        let result = download_resumable(
+            &crate::core::http::client(),
+            "test",
+            &server.url(LIBRARY_PATH),
+            &dest,
+            None,
+            body.len() as u64,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await;
+
+        assert_eq!(
+            result.unwrap_err(),
+            DownloadError::SizeMismatch {
+                expected: body.len() as u64,
+                actual: half.len() as u64,
+            }
+        );
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn retry_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: std::time::Duration::from_millis(100),
+            max_delay: std::time::Duration::from_millis(1000),
+            ..RetryPolicy::default()
+        };
+        for (attempt, full) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (9, 1000)] {
+            let delay = policy.delay_after(attempt).as_millis() as u64;
+            assert!(
+                (full / 2..=full).contains(&delay),
+                "attempt {attempt}: {delay}ms"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn download_files_respects_concurrency_limit() {
+        let dir = TestDir::new("downloader");
+        // Answer slowly, so requests the limit allows overlap
+        let server = MockServer::builder()
+            .fallback(|_| Response::ok("asset").delay(std::time::Duration::from_millis(100)))
+            .start()
+            .await;
+        let tasks = (0..12)
+            .map(|i| DownloadTask {
+                url: server.url(&format!("/asset/{}", i)),
+                path: dir.path().join(format!("asset-{}", i)),
+                sha1: None,
+                sha256: None,
+                sha512: None,
+                expected_size: None,
+                fallback_urls: Vec::new(),
+                priority: 0,
+            })
+            .collect();
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            tasks,
+            3,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+
+        assert_eq!(report.succeeded.len(), 12);
+        assert_eq!(server.peak_in_flight(), 3);
+    }
+
+    #[tokio::test]
+    async fn download_progress_events_are_throttled() {
+        let dir = TestDir::new("downloader");
+        let mut body: Vec<u8> = (0..16 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        body[..4].copy_from_slice(b"PK\x03\x04");
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let events = Mutex::new(Vec::new());
+
+        let started = std::time::Instant::now();
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![library_task(&server, dir.path().join("big.jar"), &body)],
+            1,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |event| events.lock().unwrap().push(event),
+        )
+        .await;
+        let elapsed = started.elapsed();
+        assert!(report.is_success());
+
+        let events = events.into_inner().unwrap();
+        let downloading: Vec<_> = events
+            .iter()
+            .filter(|e| e.status == "Downloading")
+            .collect();
+        // First and final update, plus one per interval and one per byte threshold
+        let bound = 2
+            + elapsed.as_millis() as usize / PROGRESS_EMIT_INTERVAL_MS as usize
+            + body.len() / PROGRESS_EMIT_BYTES as usize;
+        assert!(
+            downloading.len() <= bound,
+            "{} events in {:?}",
+            downloading.len(),
+            elapsed
+        );
+        assert_eq!(downloading.last().unwrap().downloaded, body.len() as u64);
+        assert_eq!(events.last().unwrap().status, "Finished");
+    }
+
+    /// Pause `controller` as soon as the first chunk lands, once
+    fn pause_on_first_chunk(
+        controller: &DownloadController,
+        events: &Mutex<Vec<ProgressEvent>>,
+    ) -> impl Fn(ProgressEvent) {
+        let controller = controller.clone();
+        let paused = AtomicBool::new(false);
+        move |event: ProgressEvent| {
+            if event.status == "Downloading" && !paused.swap(true, Ordering::SeqCst) {
+                controller.pause();
+            }
+            events.lock().unwrap().push(event);
+        }
+    }
+
+    /// Once `controller` is paused, wait a moment and then apply `action`
+    fn after_pause(
+        controller: &DownloadController,
+        action: fn(&DownloadController),
+    ) -> tokio::task::JoinHandle<()> {
+        let controller = controller.clone();
+        tokio::spawn(async move {
+            let mut rx = controller.state.subscribe();
+            let _ = rx.wait_for(|state| *state == ControlState::Paused).await;
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            action(&controller);
+        })
+    }
+
+    #[tokio::test]
+    async fn paused_download_resumes_with_range_request() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let path = dir.path().join("library.jar");
+        let controller = DownloadController::default();
+        let events = Mutex::new(Vec::new());
+        let resumer = after_pause(&controller, DownloadController::resume);
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![library_task(&server, path.clone(), &body)],
+            4,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &controller,
+            pause_on_first_chunk(&controller, &events),
+        )
+        .await;
+        resumer.await.unwrap();
+
+        assert!(report.is_success());
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        let events = events.lock().unwrap();
+        let paused = events.iter().find(|e| e.status == "Paused").unwrap();
+        assert!(paused.downloaded > 0 && paused.downloaded < body.len() as u64);
+        assert_eq!(
+            ranges(&server),
+            vec![None, Some(format!("bytes={}-", paused.downloaded))]
+        );
+    }
+
+    #[tokio::test]
+    async fn paused_download_restarts_without_range_support() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), false, None, 0).await;
+        let path = dir.path().join("library.jar");
+        let controller = DownloadController::default();
+        let events = Mutex::new(Vec::new());
+        let resumer = after_pause(&controller, DownloadController::resume);
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![library_task(&server, path.clone(), &body)],
+            4,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &controller,
+            pause_on_first_chunk(&controller, &events),
+        )
+        .await;
+        resumer.await.unwrap();
+
+        assert!(report.is_success());
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(
+            events
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .total_downloaded_bytes,
+            body.len() as u64
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelling_paused_batch_keeps_partial_files() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let first = dir.path().join("first.jar");
+        let second = dir.path().join("second.jar");
+        let controller = DownloadController::default();
+        let events = Mutex::new(Vec::new());
+        let canceller = after_pause(&controller, DownloadController::cancel);
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![
+                library_task(&server, first.clone(), &body),
+                library_task(&server, second.clone(), &body),
+            ],
+            1,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &controller,
+            pause_on_first_chunk(&controller, &events),
+        )
+        .await;
+        canceller.await.unwrap();
+
+        assert!(!report.is_success());
+        assert!(report.failed.is_empty());
+        assert_eq!(report.paused.len(), 2);
+        let partial = std::fs::metadata(part_path_for(&first)).unwrap().len();
+        assert!(partial > 0 && partial < body.len() as u64);
+        assert!(!first.exists());
+        assert!(!second.exists() && !part_path_for(&second).exists());
+
+        // The next batch is not affected by the cancel
+        controller.begin_batch();
+        assert!(controller.is_running());
+    }
+
+    #[tokio::test]
+    async fn interrupted_download_never_appears_under_final_name() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), false, None, 0).await;
+        let path = dir.path().join("library.jar");
+        // No checksum, so a truncated file under the final name would go unnoticed
+        let mut task = library_task(&server, path.clone(), &body);
+        task.sha1 = None;
+
+        // Stop the batch mid-write, as a crash would
+        let controller = DownloadController::default();
+        let events = Mutex::new(Vec::new());
+        let canceller = after_pause(&controller, DownloadController::cancel);
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![task.clone()],
+            1,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &controller,
+            pause_on_first_chunk(&controller, &events),
+        )
+        .await;
+        canceller.await.unwrap();
+        assert_eq!(report.paused.len(), 1);
+        assert!(!path.exists());
+        assert!(part_path_for(&path).exists());
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            vec![task],
+            1,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+        assert!(report.is_success());
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert!(!part_path_for(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn stale_part_files_are_removed() {
+        let dir = TestDir::new("downloader");
+        let old = dir.path().join("old.jar.part");
+        let fresh = dir.path().join("fresh.jar.part");
+        let resumable = dir.path().join("jdk.tar.gz.part");
+        let other = dir.path().join("old.jar");
+        for path in [&old, &fresh, &resumable, &other] {
+            std::fs::write(path, b"data").unwrap();
+        }
+        std::fs::write(meta_path_for(&resumable), b"{}").unwrap();
+        let two_days_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 86400);
+        for path in [&old, &resumable, &other] {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(two_days_ago)
+                .unwrap();
+        }
+
+        assert_eq!(
+            remove_stale_part_files(dir.path(), STALE_PART_FILE_AGE).await,
+            1
+        );
+        assert!(!old.exists());
+        assert!(fresh.exists() && resumable.exists() && other.exists());
+    }
+
+    #[tokio::test]
+    async fn cancelling_one_task_leaves_the_others_running() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let paths: Vec<_> = (0..3)
+            .map(|i| dir.path().join(format!("library-{}.jar", i)))
+            .collect();
+        let target = paths[1].to_string_lossy().to_string();
+        let controller = DownloadController::default();
+        let events = Mutex::new(Vec::new());
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            paths
+                .iter()
+                .map(|path| library_task(&server, path.clone(), &body))
+                .collect(),
+            3,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &controller,
+            |event: ProgressEvent| {
+                if event.task_id == target && event.status == "Downloading" {
+                    controller.cancel_task(&target);
+                }
+                events.lock().unwrap().push(event);
+            },
+        )
+        .await;
+
+        assert_eq!(report.succeeded.len(), 2);
+        assert_eq!(report.cancelled.len(), 1);
+        assert_eq!(report.cancelled[0].path, paths[1]);
+        assert!(report.failed.is_empty());
+        assert_eq!(std::fs::read(&paths[0]).unwrap(), body);
+        assert_eq!(std::fs::read(&paths[2]).unwrap(), body);
+        assert!(!paths[1].exists());
+        assert!(
+            events
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|e| e.task_id == target && e.status == "Cancelled")
+        );
+        // Finished tasks drop their tokens
+        assert!(!controller.cancel_task(&target));
+    }
+
+    fn pending_download(major_version: u32) -> PendingJavaDownload {
+        PendingJavaDownload {
+            major_version,
+            image_type: "jre".to_string(),
+            download_url: "https://example.com/jre.tar.gz".to_string(),
+            file_name: "jre.tar.gz".to_string(),
+            file_size: 100,
+            checksum: None,
+            install_path: "/tmp/java".to_string(),
+            created_at: 0,
+            attempts: 0,
+            last_error: None,
+            last_attempt_at: 0,
+        }
+    }
+
+    #[test]
+    fn download_queue_tracks_failures_across_re_adds() {
+        let mut queue = DownloadQueue::default();
+        queue.add(pending_download(17));
+        let entry = queue
+            .record_failure(17, "jre", "404 Not Found".to_string(), 1000)
+            .unwrap();
+        assert_eq!(entry.attempts, 1);
+
+        // Installing again re-adds the entry with fresh release info
+        queue.add(pending_download(17));
+        queue.record_failure(17, "jre", "404 Not Found".to_string(), 2000);
+        let entry = &queue.pending_downloads[0];
+        assert_eq!(queue.pending_downloads.len(), 1);
+        assert_eq!(entry.attempts, 2);
+        assert_eq!(entry.last_error.as_deref(), Some("404 Not Found"));
+        assert_eq!(entry.last_attempt_at, 2000);
+
+        assert!(queue.record_failure(21, "jre", String::new(), 0).is_none());
+        assert!(queue.reset_attempts(17, "jre"));
+        assert_eq!(queue.pending_downloads[0].attempts, 0);
+        assert_eq!(queue.pending_downloads[0].last_error, None);
+    }
+
+    #[test]
+    fn adding_same_download_updates_existing_entry() {
+        let mut queue = DownloadQueue::default();
+        queue.add(pending_download(17));
+        let mut newer = pending_download(17);
+        newer.download_url = "https://example.com/newer.tar.gz".to_string();
+        newer.created_at = 50;
+        queue.add(newer);
+
+        assert_eq!(queue.pending_downloads.len(), 1);
+        assert_eq!(
+            queue.pending_downloads[0].download_url,
+            "https://example.com/newer.tar.gz"
+        );
+        assert_eq!(queue.pending_downloads[0].created_at, 50);
+    }
+
+    #[test]
+    fn prune_removes_stale_orphaned_and_duplicate_entries() {
+        let dir = TestDir::new("queue-prune");
+        let path = dir.path().join("download_queue.json");
+        let install_path = dir.path().join("java").to_string_lossy().into_owned();
+        let now = 100 * 24 * 60 * 60;
+        let max_age = std::time::Duration::from_secs(30 * 24 * 60 * 60);
+
+        let entry = |major: u32, created_at: u64, install_path: &str| PendingJavaDownload {
+            created_at,
+            install_path: install_path.to_string(),
+            ..pending_download(major)
+        };
+        let gone = dir
+            .path()
+            .join("missing/java")
+            .to_string_lossy()
+            .into_owned();
+        // Written by hand, since `add` no longer lets duplicates in
+        let queue = DownloadQueue {
+            version: DOWNLOAD_QUEUE_VERSION,
+            pending_downloads: vec![
+                entry(8, now - 31 * 24 * 60 * 60, &install_path),
+                entry(11, now, &gone),
+                entry(17, now - 10, &install_path),
+                entry(17, now - 5, &install_path),
+                entry(21, now, &install_path),
+            ],
+        };
+        queue.save_to(&path).unwrap();
+
+        assert_eq!(DownloadQueue::prune_file(&path, now, max_age).unwrap(), 3);
+        let pruned = DownloadQueue::load_from(&path);
+        let kept: Vec<_> = pruned
+            .pending_downloads
+            .iter()
+            .map(|d| (d.major_version, d.created_at))
+            .collect();
+        assert_eq!(kept, vec![(17, now - 5), (21, now)]);
+
+        // Nothing left to prune
+        assert_eq!(DownloadQueue::prune_file(&path, now, max_age).unwrap(), 0);
+    }
+
+    #[test]
+    fn pending_download_without_attempt_fields_loads() {
+        let json = r#"{
+            "majorVersion": 17,
+            "imageType": "jre",
+            "downloadUrl": "https://example.com/jre.tar.gz",
+            "fileName": "jre.tar.gz",
+            "fileSize": 100,
+            "checksum": null,
+            "installPath": "/tmp/java",
+            "createdAt": 0
+        }"#;
+        let pending: PendingJavaDownload = serde_json::from_str(json).unwrap();
+        assert_eq!(pending.attempts, 0);
+        assert_eq!(pending.last_error, None);
+    }
+
+    #[test]
+    fn download_queue_round_trips_with_version() {
+        let dir = TestDir::new("downloader");
+        let path = dir.path().join("download_queue.json");
+        let mut queue = DownloadQueue::default();
+        queue.add(pending_download(21));
+        queue.save_to(&path).unwrap();
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], DOWNLOAD_QUEUE_VERSION);
+        let loaded = DownloadQueue::load_from(&path);
+        assert_eq!(loaded.pending_downloads.len(), 1);
+        assert!(!path.with_file_name("download_queue.json.tmp").exists());
+
+        // Files from before the version field still load
+        std::fs::write(&path, r#"{"pendingDownloads": []}"#).unwrap();
+        assert_eq!(
+            DownloadQueue::load_from(&path).version,
+            DOWNLOAD_QUEUE_VERSION
+        );
+    }
+
+    #[test]
+    fn corrupt_download_queue_is_moved_aside() {
+        let dir = TestDir::new("downloader");
+        let path = dir.path().join("download_queue.json");
+        std::fs::write(&path, b"{\"pendingDownloads\": [{\"majorVers").unwrap();
+
+        let queue = DownloadQueue::load_from(&path);
+        assert!(queue.pending_downloads.is_empty());
+        assert!(!path.exists());
+        let backups: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("download_queue.json.corrupt-"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+
+        // The next start finds no queue and carries on quietly
+        assert!(DownloadQueue::load_from(&path).pending_downloads.is_empty());
+        queue.save_to(&path).unwrap();
+        assert!(DownloadQueue::load_from(&path).pending_downloads.is_empty());
+    }
+
+    #[test]
+    fn java_cancel_goes_through_task_tokens() {
+        let controller = DownloadController::default();
+        let first = java_task_id("first");
+        let second = java_task_id("second");
+        controller.register_task(&first);
+        controller.register_task(&second);
+        assert!(!controller.is_task_cancelled(&first));
+        assert!(controller.cancel_task(&first));
+        assert!(controller.is_task_cancelled(&first));
+        // Cancelling one download leaves the others running
+        assert!(!controller.is_task_cancelled(&second));
+
+        // A new download starts with a fresh token
+        controller.register_task(&first);
+        assert!(!controller.is_task_cancelled(&first));
+
+        controller.register_task("1.20.1-client");
+        assert_eq!(controller.cancel_tasks_with_prefix(&java_task_id("")), 2);
+        assert!(controller.is_task_cancelled(&second));
+        assert!(!controller.is_task_cancelled("1.20.1-client"));
+    }
+
+    #[test]
+    fn speed_meter_ignores_clock_going_backwards() {
+        let start = std::time::Instant::now();
+        let secs = std::time::Duration::from_secs;
+        let mut meter = SpeedMeter::new(secs(5));
+        assert_eq!(meter.bytes_per_sec(), 0);
+
+        meter.record(start, 0);
+        meter.record(start + secs(2), 2000);
+        assert_eq!(meter.bytes_per_sec(), 1000);
+
+        // An earlier timestamp is dropped rather than producing a negative span
+        meter.record(start + secs(1), 9000);
+        assert_eq!(meter.bytes_per_sec(), 1000);
+
+        // A counter reset starts a new measurement
+        meter.record(start + secs(3), 100);
+        assert_eq!(meter.bytes_per_sec(), 0);
+        meter.record(start + secs(4), 600);
+        assert_eq!(meter.bytes_per_sec(), 500);
+    }
+
+    #[test]
+    fn speed_meter_only_looks_at_recent_samples() {
+        let start = std::time::Instant::now();
+        let secs = std::time::Duration::from_secs;
+        let mut meter = SpeedMeter::new(secs(5));
+        meter.record(start, 0);
+        meter.record(start + secs(10), 10_000);
+        for i in 11..=20 {
+            meter.record(start + secs(i), 10_000 + (i - 10) * 100);
+        }
+        assert_eq!(meter.bytes_per_sec(), 100);
+    }
+
+    fn progress_event(task_id: &str, status: &str, downloaded: u64, total: u64) -> ProgressEvent {
+        ProgressEvent {
+            task_id: task_id.to_string(),
+            file: task_id.to_string(),
+            downloaded,
+            total,
+            status: status.to_string(),
+            message_key: download_status_key(status),
+            attempt: 1,
+            completed_files: 0,
+            total_files: 2,
+            total_downloaded_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn aggregate_tracker_handles_unknown_lengths() {
+        let start = std::time::Instant::now();
+        let secs = std::time::Duration::from_secs;
+        let mut tracker = AggregateTracker::new(2);
+
+        tracker.observe(start, &progress_event("a", "Downloading", 100, 1000));
+        tracker.observe(start, &progress_event("b", "Downloading", 100, 0));
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.total_bytes, 1000);
+        assert!(!snapshot.total_bytes_exact);
+        assert_eq!(snapshot.eta_seconds, None);
+
+        tracker.observe(
+            start + secs(1),
+            &progress_event("a", "Downloading", 600, 1000),
+        );
+        tracker.observe(start + secs(2), &progress_event("b", "Downloading", 600, 0));
+        let mut finished = progress_event("b", "Finished", 600, 0);
+        finished.total_downloaded_bytes = 1200;
+        tracker.observe(start + secs(2), &finished);
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.total_bytes, 1600);
+        assert!(snapshot.total_bytes_exact);
+        assert_eq!(snapshot.speed_bytes_per_sec, 500);
+        assert_eq!(snapshot.eta_seconds, Some(0));
+    }
+
+    #[test]
+    fn aggregate_tracker_does_not_count_resumed_prefix_as_speed() {
+        let start = std::time::Instant::now();
+        let mut tracker = AggregateTracker::new(1);
+        let progress = |downloaded| JavaDownloadProgress {
+            id: "test".to_string(),
+            file_name: "jdk.zip".to_string(),
+            downloaded_bytes: downloaded,
+            total_bytes: 10_000,
+            speed_bytes_per_sec: 0,
+            eta_seconds: 0,
+            status: "Downloading".to_string(),
+            message_key: java_download_status_key("Downloading"),
+            percentage: 0.0,
+        };
+
+        tracker.observe_java(start, &progress(8000));
+        tracker.observe_java(start + std::time::Duration::from_secs(2), &progress(9000));
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.speed_bytes_per_sec, 500);
+        assert_eq!(snapshot.eta_seconds, Some(2));
+    }
+
+    #[tokio::test]
+    async fn download_files_reports_each_outcome() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let good = spawn_server(body.clone(), true, None, 0).await;
+        let broken = spawn_server(body.clone(), true, None, usize::MAX).await;
+
+        let existing = dir.path().join("existing.jar");
+        std::fs::write(&existing, &body).unwrap();
+        let tasks = vec![
+            library_task(&good, existing.clone(), &body),
+            library_task(&good, dir.path().join("fresh.jar"), &body),
+            library_task(&broken, dir.path().join("broken.jar"), &body),
+        ];
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            tasks,
+            4,
+            0,
+            &fast_retry(),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+
+        let paths = |tasks: &[DownloadTask]| -> Vec<PathBuf> {
+            tasks.iter().map(|t| t.path.clone()).collect()
+        };
+        assert!(!report.is_success());
+        assert_eq!(paths(&report.skipped), vec![existing]);
+        assert_eq!(report.succeeded.len(), 1);
+        assert_eq!(report.succeeded[0].task.path, dir.path().join("fresh.jar"));
+        assert_eq!(report.succeeded[0].url, good.url(JDK_PATH));
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].task.url, broken.url(JDK_PATH));
+        // The existing file never hit the network
+        assert_eq!(good.requests().len(), 1);
+
+        let user_agent = good.requests()[0].header("user-agent").unwrap().to_string();
+        let prefix = format!("DropOut/{} (", env!("CARGO_PKG_VERSION"));
+        assert!(user_agent.starts_with(&prefix), "{user_agent}");
+        assert!(
+            user_agent.ends_with(')') && user_agent.contains("; "),
+            "{user_agent}"
+        );
+    }
+
+    #[test]
+    fn splits_segments_covering_whole_file() {
+        let segments = split_segments(10, 3);
+        let ranges: Vec<_> = segments.iter().map(|s| (s.start, s.end)).collect();
+        assert_eq!(ranges, vec![(0, 2), (3, 5), (6, 9)]);
+        assert_eq!(split_segments(2, 4).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn segmented_download_assembles_file_and_retries_failed_segment() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let checksum = compute_sha256(&body);
+        let server = spawn_server(body.clone(), true, Some(10_000), 0).await;
+        let dest = dir.path().join("jdk.zip");
+        let mut progress = Vec::new();
+
+        let segmented = download_segmented(
+            &crate::core::http::client(),
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            Some(&checksum),
+            body.len() as u64,
+            4,
+            &RateLimiter::default(),
+            |p| progress.push(p),
+        )
+        .await
+        .unwrap();
+
+        assert!(segmented);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!part_path_for(&dest).exists());
+        assert!(progress.iter().any(|p| p.status == "Verifying"));
+        assert!(progress.iter().all(|p| p.id == "test"));
+
+        // Probe, four segments, and one retry continuing the interrupted segment
+        let ranges = ranges(&server);
+        assert_eq!(ranges.len(), 6);
+        assert_eq!(ranges[0].as_deref(), Some("bytes=0-0"));
+        // Whichever segment was cut resumes 10000 bytes past its start
+        let resumed: Vec<_> = split_segments(body.len() as u64, 4)
+            .iter()
+            .map(|s| format!("bytes={}-{}", s.start + 10_000, s.end))
+            .filter(|r| ranges.contains(&Some(r.clone())))
+            .collect();
+        assert_eq!(resumed.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn segmented_download_falls_back_without_range_support() {
+        let dir = TestDir::new("downloader");
+        let body = test_body();
+        let server = spawn_server(body.clone(), false, None, 0).await;
+        let dest = dir.path().join("jdk.zip");
+
+        let segmented = download_segmented(
+            &crate::core::http::client(),
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            None,
+            body.len() as u64,
+            4,
+            &RateLimiter::default(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert!(!segmented);
+        assert!(!dest.exists());
+        assert!(!part_path_for(&dest).exists());
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_caps_download_speed() {
+        let dir = TestDir::new("downloader");
+        let body = test_body()[..150_000].to_vec();
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let dest = dir.path().join("jdk.zip");
+        let limiter = RateLimiter::unlimited();
+        limiter.set_limit(Some(100_000));
+
+        let started = std::time::Instant::now();
+        download_resumable(
+            &crate::core::http::client(),
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            None,
+            body.len() as u64,
+            &limiter,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        let rate = body.len() as f64 / started.elapsed().as_secs_f64();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(
+            (80_000.0..=120_000.0).contains(&rate),
+            "observed {rate:.0} B/s with a 100000 B/s cap"
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelling_one_java_download_leaves_the_other_running() {
+        let dir = TestDir::new("downloader");
+        let body = test_body()[..150_000].to_vec();
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let url = server.url(JDK_PATH);
+        let client = crate::core::http::client();
+        let (first_dest, second_dest) =
+            (dir.path().join("first.zip"), dir.path().join("second.zip"));
+        let (first_limiter, second_limiter) = (RateLimiter::unlimited(), RateLimiter::unlimited());
+        first_limiter.set_limit(Some(300_000));
+        second_limiter.set_limit(Some(300_000));
+        let first_id = Uuid::new_v4().to_string();
+        let second_id = Uuid::new_v4().to_string();
+        let first_guard = JavaDownloadGuard::register(&first_id);
+        let second_guard = JavaDownloadGuard::register(&second_id);
+
+        let (first, second, _) = tokio::join!(
+            download_resumable(
+                &client,
+                &first_id,
+                &url,
+                &first_dest,
+                None,
+                body.len() as u64,
+                &first_limiter,
+                |_| {},
+            ),
+            download_resumable(
+                &client,
+                &second_id,
+                &url,
+                &second_dest,
+                None,
+                body.len() as u64,
+                &second_limiter,
+                |_| {},
+            ),
+            async {
+                tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+                assert!(cancel_java_download(&first_id));
+            }
+        );
+
+        assert_eq!(first.unwrap_err(), DownloadError::Cancelled);
+        assert!(!first_dest.exists());
+        second.unwrap();
+        assert_eq!(std::fs::read(&second_dest).unwrap(), body);
+
+        // Once the operations end their ids are forgotten
+        drop((first_guard, second_guard));
+        assert!(!cancel_java_download(&first_id));
+        assert!(!cancel_java_download(&second_id));
+    }
+
+    #[tokio::test]
+    #[ignore = "benchmark, downloads 100 MB"]
+    async fn buffered_writes_keep_large_download_intact() {
+        let dir = TestDir::new("downloader");
+        let mut body: Vec<u8> = (0..100 * 1024 * 1024u32)
+            .map(|i| (i * 31 % 251) as u8)
+            .collect();
+        body[..4].copy_from_slice(b"PK\x03\x04");
+        let checksum = compute_sha256(&body);
+        let server = spawn_server(body.clone(), true, None, 0).await;
+        let dest = dir.path().join("jdk.zip");
+
+        let started = std::time::Instant::now();
+        download_resumable(
+            &crate::core::http::client(),
+            "test",
+            &server.url(JDK_PATH),
+            &dest,
+            Some(&checksum),
+            body.len() as u64,
+            &RateLimiter::unlimited(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        let elapsed = started.elapsed().as_secs_f64();
+
+        assert_eq!(compute_file_sha256(&dest).await.unwrap(), checksum);
+        println!("100 MB in {:.2}s ({:.1} MB/s)", elapsed, 100.0 / elapsed);
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_applies_new_limit_to_waiting_callers() {
+        let limiter = RateLimiter::unlimited();
+        limiter.set_limit(Some(1_000));
+
+        let started = std::time::Instant::now();
+        let lift = async {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            limiter.set_limit(None);
+        };
+        // 10 s worth of debt at the original limit
+        tokio::join!(limiter.acquire(10_000), lift);
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    }
 }
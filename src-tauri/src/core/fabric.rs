@@ -130,7 +130,9 @@ pub struct InstalledFabricVersion {
 pub async fn fetch_supported_game_versions()
 -> Result<Vec<FabricGameVersion>, Box<dyn Error + Send + Sync>> {
     let url = format!("{}/versions/game", FABRIC_META_URL);
-    let resp = reqwest::get(&url)
+    let resp = crate::core::http::client()
+        .get(&url)
+        .send()
         .await?
         .json::<Vec<FabricGameVersion>>()
         .await?;
@@ -144,7 +146,9 @@ pub async fn fetch_supported_game_versions()
 pub async fn fetch_loader_versions()
 -> Result<Vec<FabricLoaderVersion>, Box<dyn Error + Send + Sync>> {
     let url = format!("{}/versions/loader", FABRIC_META_URL);
-    let resp = reqwest::get(&url)
+    let resp = crate::core::http::client()
+        .get(&url)
+        .send()
         .await?
         .json::<Vec<FabricLoaderVersion>>()
         .await?;
@@ -161,9 +165,19 @@ pub async fn fetch_loader_versions()
 pub async fn fetch_loaders_for_game_version(
     game_version: &str,
 ) -> Result<Vec<FabricLoaderEntry>, Box<dyn Error + Send + Sync>> {
-    let url = format!("{}/versions/loader/{}", FABRIC_META_URL, game_version);
-    let resp = reqwest::get(&url)
+    loaders_for_game_version_at(FABRIC_META_URL, game_version).await
+}
+
+async fn loaders_for_game_version_at(
+    meta_url: &str,
+    game_version: &str,
+) -> Result<Vec<FabricLoaderEntry>, Box<dyn Error + Send + Sync>> {
+    let url = format!("{}/versions/loader/{}", meta_url, game_version);
+    let resp = crate::core::http::client()
+        .get(&url)
+        .send()
         .await?
+        .error_for_status()?
         .json::<Vec<FabricLoaderEntry>>()
         .await?;
     Ok(resp)
@@ -172,24 +186,32 @@ pub async fn fetch_loaders_for_game_version(
 /// Fetch the version JSON profile for a specific Fabric loader + game version combination.
 ///
 /// # Arguments
+/// * `meta_url` - Base URL of the Fabric Meta API
 /// * `game_version` - The Minecraft version (e.g., "1.20.4")
 /// * `loader_version` - The Fabric loader version (e.g., "0.15.6")
 ///
 /// # Returns
 /// The raw version JSON as a `serde_json::Value` that can be saved to the versions directory.
-pub async fn fetch_version_profile(
+async fn fetch_version_profile(
+    meta_url: &str,
     game_version: &str,
     loader_version: &str,
 ) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
     let url = format!(
         "{}/versions/loader/{}/{}/profile/json",
-        FABRIC_META_URL, game_version, loader_version
+        meta_url, game_version, loader_version
     );
-    let resp = reqwest::get(&url)
-        .await?
-        .json::<serde_json::Value>()
-        .await?;
-    Ok(resp)
+    let resp = crate::core::http::client().get(&url).send().await?;
+    // Fabric Meta answers a loader or game version it doesn't know with 400 or 404
+    if resp.status().is_client_error() {
+        return Err(format!(
+            "Fabric loader {} is not available for Minecraft {}",
+            loader_version, game_version
+        )
+        .into());
+    }
+    let profile = resp.error_for_status()?.json::<serde_json::Value>().await?;
+    Ok(profile)
 }
 
 /// Generate the version ID for a Fabric installation.
@@ -220,9 +242,29 @@ pub async fn install_fabric(
     game_dir: &std::path::Path,
     game_version: &str,
     loader_version: &str,
+) -> Result<InstalledFabricVersion, Box<dyn Error + Send + Sync>> {
+    install_fabric_from(FABRIC_META_URL, game_dir, game_version, loader_version).await
+}
+
+async fn install_fabric_from(
+    meta_url: &str,
+    game_dir: &std::path::Path,
+    game_version: &str,
+    loader_version: &str,
 ) -> Result<InstalledFabricVersion, Box<dyn Error + Send + Sync>> {
     // Fetch the version profile from Fabric Meta
-    let profile = fetch_version_profile(game_version, loader_version).await?;
+    let profile = fetch_version_profile(meta_url, game_version, loader_version).await?;
+
+    // The profile is merged onto its parent at launch, so it has to be the one chosen
+    let inherits_from = profile.get("inheritsFrom").and_then(|v| v.as_str());
+    if inherits_from != Some(game_version) {
+        return Err(format!(
+            "Fabric profile for Minecraft {} inherits from {}",
+            game_version,
+            inherits_from.unwrap_or("nothing")
+        )
+        .into());
+    }
 
     // Get the version ID from the profile or generate it
     let version_id = profile
@@ -238,7 +280,7 @@ pub async fn install_fabric(
     // Write the version JSON
     let json_path = version_dir.join(format!("{}.json", version_id));
     let json_content = serde_json::to_string_pretty(&profile)?;
-    tokio::fs::write(&json_path, json_content).await?;
+    crate::core::java::persistence::write_file_atomic(&json_path, json_content.as_bytes())?;
 
     Ok(InstalledFabricVersion {
         id: version_id,
@@ -305,6 +347,153 @@ pub async fn list_installed_fabric_versions(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::rules::RuleContext;
+    use crate::utils::mock_server::{MockServer, Response};
+    use crate::utils::test_dir::TestDir;
+
+    /// Fabric Meta's answer for `/versions/loader/1.20.4`, trimmed to one loader
+    const LOADERS_1_20_4: &str = r#"[{
+        "loader": {"separator": ".", "build": 7, "maven": "net.fabricmc:fabric-loader:0.15.7", "version": "0.15.7", "stable": true},
+        "intermediary": {"maven": "net.fabricmc:intermediary:1.20.4", "version": "1.20.4", "stable": true},
+        "launcherMeta": {
+            "version": 2,
+            "min_java_version": 8,
+            "libraries": {
+                "client": [],
+                "common": [{"name": "net.fabricmc:tiny-mappings-parser:0.3.0+build.17", "url": "https://maven.fabricmc.net/"}],
+                "server": []
+            },
+            "mainClass": {"client": "net.fabricmc.loader.impl.launch.knot.KnotClient", "server": "net.fabricmc.loader.impl.launch.knot.KnotServer"}
+        }
+    }]"#;
+
+    /// Fabric Meta's profile for loader 0.15.7 on 1.20.4, with fewer libraries
+    const PROFILE_0_15_7_1_20_4: &str = r#"{
+        "id": "fabric-loader-0.15.7-1.20.4",
+        "inheritsFrom": "1.20.4",
+        "releaseTime": "2024-02-17T11:41:22+0000",
+        "time": "2024-02-17T11:41:22+0000",
+        "type": "release",
+        "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        "arguments": {"game": [], "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "]},
+        "libraries": [
+            {"name": "org.ow2.asm:asm:9.6", "url": "https://maven.fabricmc.net/", "md5": "6f8bccf756f170d4185bb24c8c2d2020", "sha1": "aa205cf0a06dbd8e04ece91c0b37c3f5d567546a", "size": 123598},
+            {"name": "net.fabricmc:intermediary:1.20.4", "url": "https://maven.fabricmc.net/"},
+            {"name": "net.fabricmc:fabric-loader:0.15.7", "url": "https://maven.fabricmc.net/"}
+        ]
+    }"#;
+
+    /// Vanilla 1.20.4, trimmed to what a launch reads
+    const VANILLA_1_20_4: &str = r#"{
+        "id": "1.20.4",
+        "type": "release",
+        "mainClass": "net.minecraft.client.main.Main",
+        "assetIndex": {"id": "12", "sha1": "0000000000000000000000000000000000000000", "size": 441479, "totalSize": 624942043, "url": "https://piston-meta.mojang.com/v1/packages/0000000000000000000000000000000000000000/12.json"},
+        "downloads": {"client": {"sha1": "0000000000000000000000000000000000000000", "size": 24445539, "url": "https://piston-data.mojang.com/v1/objects/0000000000000000000000000000000000000000/client.jar"}},
+        "libraries": [
+            {"name": "org.ow2.asm:asm:9.3", "downloads": {"artifact": {"path": "org/ow2/asm/asm/9.3/asm-9.3.jar", "sha1": "0000000000000000000000000000000000000000", "size": 122004, "url": "https://libraries.minecraft.net/org/ow2/asm/asm/9.3/asm-9.3.jar"}}},
+            {"name": "com.google.code.gson:gson:2.10.1", "downloads": {"artifact": {"path": "com/google/code/gson/gson/2.10.1/gson-2.10.1.jar", "sha1": "0000000000000000000000000000000000000000", "size": 283367, "url": "https://libraries.minecraft.net/com/google/code/gson/gson/2.10.1/gson-2.10.1.jar"}}}
+        ]
+    }"#;
+
+    /// Fabric Meta serving the recorded responses for 1.20.4; anything else is unknown
+    async fn spawn_meta() -> String {
+        let json = |body: &str| Response::ok(body).header("Content-Type", "application/json");
+        let meta = MockServer::builder()
+            .route("/v2/versions/loader/1.20.4", move |_| json(LOADERS_1_20_4))
+            .route(
+                "/v2/versions/loader/1.20.4/0.15.7/profile/json",
+                move |_| json(PROFILE_0_15_7_1_20_4),
+            )
+            .fallback(|_| Response::new(400, "no loader version found for 1.20.4"))
+            .start()
+            .await;
+        meta.url("/v2")
+    }
+
+    #[tokio::test]
+    async fn loaders_are_listed_for_a_game_version() {
+        let meta = spawn_meta().await;
+
+        let loaders = loaders_for_game_version_at(&meta, "1.20.4").await.unwrap();
+        assert_eq!(loaders.len(), 1);
+        assert_eq!(loaders[0].loader.version, "0.15.7");
+        assert_eq!(
+            loaders[0].intermediary.maven,
+            "net.fabricmc:intermediary:1.20.4"
+        );
+        assert_eq!(
+            loaders[0].launcher_meta.main_class.client(),
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+    }
+
+    #[tokio::test]
+    async fn installed_profile_launches_through_the_loader() {
+        let meta = spawn_meta().await;
+        let dir = TestDir::new("fabric");
+
+        let installed = install_fabric_from(&meta, dir.path(), "1.20.4", "0.15.7")
+            .await
+            .unwrap();
+        assert_eq!(installed.id, generate_version_id("1.20.4", "0.15.7"));
+        assert!(is_fabric_installed(dir.path(), "1.20.4", "0.15.7"));
+
+        let vanilla_dir = dir.path().join("versions").join("1.20.4");
+        std::fs::create_dir_all(&vanilla_dir).unwrap();
+        std::fs::write(vanilla_dir.join("1.20.4.json"), VANILLA_1_20_4).unwrap();
+
+        let merged = crate::core::manifest::load_version(dir.path(), &installed.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            merged.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+        assert_eq!(merged.asset_index.as_ref().unwrap().id, "12");
+        let jvm = merged.arguments.as_ref().unwrap().jvm.as_ref().unwrap();
+        assert!(
+            jvm.as_array()
+                .unwrap()
+                .contains(&"-DFabricMcEmu= net.minecraft.client.main.Main ".into())
+        );
+
+        // The loader and intermediary are downloaded with the vanilla libraries
+        let libraries = dir.path().join("libraries");
+        let tasks = crate::core::game_files::client_and_library_tasks(
+            &merged,
+            &dir.path().join("client.jar"),
+            &libraries,
+            &RuleContext::current(),
+        )
+        .unwrap();
+        let urls: Vec<&str> = tasks.iter().map(|t| t.url.as_str()).collect();
+        for url in [
+            "https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.15.7/fabric-loader-0.15.7.jar",
+            "https://maven.fabricmc.net/net/fabricmc/intermediary/1.20.4/intermediary-1.20.4.jar",
+            "https://maven.fabricmc.net/org/ow2/asm/asm/9.6/asm-9.6.jar",
+            "https://libraries.minecraft.net/com/google/code/gson/gson/2.10.1/gson-2.10.1.jar",
+        ] {
+            assert!(urls.contains(&url), "{} missing from {:?}", url, urls);
+        }
+        // Fabric's ASM replaces the vanilla one
+        assert!(!urls.iter().any(|url| url.ends_with("asm-9.3.jar")));
+    }
+
+    #[tokio::test]
+    async fn unknown_loader_is_reported_without_installing() {
+        let meta = spawn_meta().await;
+        let dir = TestDir::new("fabric");
+
+        let error = install_fabric_from(&meta, dir.path(), "1.20.4", "0.0.1")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Fabric loader 0.0.1 is not available for Minecraft 1.20.4"
+        );
+        assert!(!dir.path().join("versions").exists());
+    }
 
     #[test]
     fn test_generate_version_id() {
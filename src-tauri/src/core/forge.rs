@@ -1,23 +1,49 @@
-//! Forge Loader support module.
+//! Forge and NeoForge loader support module.
 //!
 //! This module provides functionality to:
 //! - Fetch available Forge versions from the Forge promotions API
-//! - Install Forge loader for a specific Minecraft version
+//! - Fetch available NeoForge versions from the NeoForged maven
+//! - Download the installer jar, which `core::forge_installer` installs from
 //!
-//! Note: Forge installation is more complex than Fabric, especially for versions 1.13+.
-//! This implementation fetches the installer manifest to get the correct library list.
+//! Note: Forge installation is more complex than Fabric, especially for versions 1.13+,
+//! where the installer patches the client.
 
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-#[cfg(target_os = "windows")]
-use std::os::windows::process::CommandExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use ts_rs::TS;
 
 const FORGE_PROMOTIONS_URL: &str =
     "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
 const FORGE_MAVEN_URL: &str = "https://maven.minecraftforge.net/";
 const FORGE_FILES_URL: &str = "https://files.minecraftforge.net/";
+const NEOFORGE_MAVEN_URL: &str = "https://maven.neoforged.net/releases/";
+const NEOFORGE_VERSIONS_URL: &str =
+    "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge";
+
+/// Which of the two loaders sharing Forge's installer format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgeLoader {
+    Forge,
+    NeoForge,
+}
+
+impl ForgeLoader {
+    /// Value of `Instance::mod_loader`
+    pub fn id(self) -> &'static str {
+        match self {
+            ForgeLoader::Forge => "forge",
+            ForgeLoader::NeoForge => "neoforge",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ForgeLoader::Forge => "Forge",
+            ForgeLoader::NeoForge => "NeoForge",
+        }
+    }
+}
 
 /// Represents a Forge version entry.
 #[derive(Debug, Deserialize, Serialize, Clone, TS)]
@@ -50,47 +76,6 @@ pub struct InstalledForgeVersion {
     pub path: PathBuf,
 }
 
-/// Forge installer manifest structure (from version.json inside installer JAR)
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-struct ForgeInstallerManifest {
-    id: Option<String>,
-    #[serde(rename = "inheritsFrom")]
-    inherits_from: Option<String>,
-    #[serde(rename = "mainClass")]
-    main_class: Option<String>,
-    #[serde(default)]
-    libraries: Vec<ForgeLibrary>,
-    arguments: Option<ForgeArguments>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ForgeArguments {
-    game: Option<Vec<serde_json::Value>>,
-    jvm: Option<Vec<serde_json::Value>>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct ForgeLibrary {
-    name: String,
-    #[serde(default)]
-    downloads: Option<ForgeLibraryDownloads>,
-    #[serde(default)]
-    url: Option<String>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct ForgeLibraryDownloads {
-    artifact: Option<ForgeArtifact>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct ForgeArtifact {
-    path: Option<String>,
-    url: Option<String>,
-    sha1: Option<String>,
-}
-
 /// Fetch all Minecraft versions supported by Forge.
 ///
 /// # Returns
@@ -122,7 +107,9 @@ pub async fn fetch_supported_game_versions() -> Result<Vec<String>, Box<dyn Erro
 
 /// Fetch Forge promotions data.
 async fn fetch_promotions() -> Result<ForgePromotions, Box<dyn Error + Send + Sync>> {
-    let resp = reqwest::get(FORGE_PROMOTIONS_URL)
+    let resp = crate::core::http::client()
+        .get(FORGE_PROMOTIONS_URL)
+        .send()
         .await?
         .json::<ForgePromotions>()
         .await?;
@@ -175,6 +162,73 @@ pub async fn fetch_forge_versions(
     Ok(versions)
 }
 
+/// NeoForge versions listing of the NeoForged maven
+#[derive(Debug, Deserialize)]
+struct NeoForgeVersions {
+    versions: Vec<String>,
+}
+
+/// Prefix of the NeoForge versions for a Minecraft version: NeoForge numbers its
+/// releases after the Minecraft version without the leading `1.`, e.g. `20.4.` for
+/// 1.20.4 and `21.0.` for 1.21
+fn neoforge_version_prefix(game_version: &str) -> Option<String> {
+    let mut parts = game_version.split('.');
+    if parts.next()? != "1" {
+        return None;
+    }
+    let minor: u32 = parts.next()?.parse().ok()?;
+    let patch: u32 = match parts.next() {
+        Some(patch) => patch.parse().ok()?,
+        None => 0,
+    };
+    Some(format!("{}.{}.", minor, patch))
+}
+
+/// Fetch available NeoForge versions for a specific Minecraft version, newest first.
+///
+/// Only 1.20.2 and later have their own NeoForge; the 1.20.1 builds were Forge forks.
+pub async fn fetch_neoforge_versions(
+    game_version: &str,
+) -> Result<Vec<ForgeVersion>, Box<dyn Error + Send + Sync>> {
+    let Some(prefix) = neoforge_version_prefix(game_version) else {
+        return Ok(Vec::new());
+    };
+    let listing = crate::core::http::client()
+        .get(NEOFORGE_VERSIONS_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<NeoForgeVersions>()
+        .await?;
+    Ok(neoforge_versions_for(
+        &listing.versions,
+        &prefix,
+        game_version,
+    ))
+}
+
+fn neoforge_versions_for(
+    versions: &[String],
+    prefix: &str,
+    game_version: &str,
+) -> Vec<ForgeVersion> {
+    let mut matching: Vec<ForgeVersion> = versions
+        .iter()
+        .rev()
+        .filter(|version| version.starts_with(prefix))
+        .map(|version| ForgeVersion {
+            version: version.clone(),
+            minecraft_version: game_version.to_string(),
+            recommended: false,
+            latest: false,
+        })
+        .collect();
+    if let Some(newest) = matching.first_mut() {
+        newest.latest = true;
+    }
+    matching
+}
+
 /// Generate the version ID for a Forge installation.
 ///
 /// # Arguments
@@ -236,7 +290,7 @@ async fn try_download_forge_installer(
     let mut last_error = None;
     for url in url_patterns {
         println!("Trying Forge installer URL: {}", url);
-        match reqwest::get(&url).await {
+        match crate::core::http::client().get(&url).send().await {
             Ok(response) => {
                 if response.status().is_success() {
                     match response.bytes().await {
@@ -268,260 +322,34 @@ async fn try_download_forge_installer(
     .into())
 }
 
-/// Fetch the Forge installer manifest to get the library list
-async fn fetch_forge_installer_manifest(
-    game_version: &str,
-    forge_version: &str,
-) -> Result<ForgeInstallerManifest, Box<dyn Error + Send + Sync>> {
-    let bytes = try_download_forge_installer(game_version, forge_version).await?;
-
-    // Extract version.json from the JAR (which is a ZIP file)
-    let cursor = std::io::Cursor::new(bytes.as_ref());
-    let mut archive = zip::ZipArchive::new(cursor)?;
-
-    // Look for version.json in the archive
-    let version_json = archive.by_name("version.json")?;
-    let manifest: ForgeInstallerManifest = serde_json::from_reader(version_json)?;
-
-    Ok(manifest)
-}
-
-/// Install Forge for a specific Minecraft version.
-///
-/// This function downloads the Forge installer JAR and runs it in headless mode
-/// to properly install Forge with all necessary patches.
-///
-/// # Arguments
-/// * `game_dir` - The .minecraft directory path
-/// * `game_version` - The Minecraft version (e.g., "1.20.4")
-/// * `forge_version` - The Forge version (e.g., "49.0.38")
-/// * `java_path` - Path to the Java executable
-///
-/// # Returns
-/// Information about the installed version.
-pub async fn install_forge(
-    game_dir: &std::path::Path,
-    game_version: &str,
-    forge_version: &str,
-) -> Result<InstalledForgeVersion, Box<dyn Error + Send + Sync>> {
-    let version_id = generate_version_id(game_version, forge_version);
-
-    // Fetch the installer manifest to get the complete version.json
-    let manifest = fetch_forge_installer_manifest(game_version, forge_version).await?;
-
-    // Create version JSON from the manifest
-    let version_json =
-        create_forge_version_json_from_manifest(game_version, forge_version, &manifest)?;
-
-    // Create the version directory
-    let version_dir = game_dir.join("versions").join(&version_id);
-    tokio::fs::create_dir_all(&version_dir).await?;
-
-    // Write the version JSON
-    let json_path = version_dir.join(format!("{}.json", version_id));
-    let json_content = serde_json::to_string_pretty(&version_json)?;
-    tokio::fs::write(&json_path, json_content).await?;
-
-    Ok(InstalledForgeVersion {
-        id: version_id,
-        minecraft_version: game_version.to_string(),
-        forge_version: forge_version.to_string(),
-        path: json_path,
-    })
-}
-
-/// Install Forge using the official installer JAR.
-/// This runs the Forge installer in headless mode to properly patch the client.
-///
-/// # Arguments
-/// * `game_dir` - The .minecraft directory path
-/// * `game_version` - The Minecraft version
-/// * `forge_version` - The Forge version
-/// * `java_path` - Path to the Java executable
-///
-/// # Returns
-/// Result indicating success or failure
-pub async fn run_forge_installer(
-    game_dir: &PathBuf,
+/// Download the installer of `version` into `dir`, returning its path
+pub async fn download_installer(
+    loader: ForgeLoader,
     game_version: &str,
-    forge_version: &str,
-    java_path: &PathBuf,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let installer_path = game_dir.join("forge-installer.jar");
-
-    // Download installer using the same multi-URL approach
-    let bytes = try_download_forge_installer(game_version, forge_version).await?;
-    tokio::fs::write(&installer_path, &bytes).await?;
-
-    // Run the installer in headless mode
-    // The installer accepts --installClient <path> to install to a specific directory
-    let mut cmd = tokio::process::Command::new(java_path);
-    cmd.arg("-jar")
-        .arg(&installer_path)
-        .arg("--installClient")
-        .arg(game_dir);
-
-    #[cfg(target_os = "windows")]
-    cmd.creation_flags(0x08000000);
-
-    let output = cmd.output().await?;
-
-    // Clean up installer
-    let _ = tokio::fs::remove_file(&installer_path).await;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        return Err(format!(
-            "Forge installer failed:\nstdout: {}\nstderr: {}",
-            stdout, stderr
-        )
-        .into());
-    }
-
-    Ok(())
-}
-
-/// Create a Forge version JSON from the installer manifest.
-fn create_forge_version_json_from_manifest(
-    game_version: &str,
-    forge_version: &str,
-    manifest: &ForgeInstallerManifest,
-) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
-    let version_id = generate_version_id(game_version, forge_version);
-
-    // Use main class from manifest or default
-    let main_class = manifest.main_class.clone().unwrap_or_else(|| {
-        if is_modern_forge(game_version) {
-            "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
-        } else {
-            "net.minecraft.launchwrapper.Launch".to_string()
+    version: &str,
+    dir: &Path,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let bytes = match loader {
+        ForgeLoader::Forge => try_download_forge_installer(game_version, version).await?,
+        ForgeLoader::NeoForge => {
+            let url = format!(
+                "{}net/neoforged/neoforge/{}/neoforge-{}-installer.jar",
+                NEOFORGE_MAVEN_URL, version, version
+            );
+            crate::core::http::client()
+                .get(&url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?
         }
-    });
-
-    // Convert libraries to JSON format, preserving download info
-    let lib_entries: Vec<serde_json::Value> = manifest
-        .libraries
-        .iter()
-        .map(|lib| {
-            let mut entry = serde_json::json!({
-                "name": lib.name
-            });
-
-            // Add URL if present
-            if let Some(url) = &lib.url {
-                entry["url"] = serde_json::Value::String(url.clone());
-            } else {
-                // Default to Forge Maven for Forge libraries
-                entry["url"] = serde_json::Value::String(FORGE_MAVEN_URL.to_string());
-            }
-
-            // Add downloads if present
-            if let Some(downloads) = &lib.downloads {
-                if let Some(artifact) = &downloads.artifact {
-                    let mut artifact_json = serde_json::Map::new();
-                    if let Some(path) = &artifact.path {
-                        artifact_json
-                            .insert("path".to_string(), serde_json::Value::String(path.clone()));
-                    }
-                    if let Some(url) = &artifact.url {
-                        artifact_json
-                            .insert("url".to_string(), serde_json::Value::String(url.clone()));
-                    }
-                    if let Some(sha1) = &artifact.sha1 {
-                        artifact_json
-                            .insert("sha1".to_string(), serde_json::Value::String(sha1.clone()));
-                    }
-                    if !artifact_json.is_empty() {
-                        entry["downloads"] = serde_json::json!({
-                            "artifact": artifact_json
-                        });
-                    }
-                }
-            }
-
-            entry
-        })
-        .collect();
-
-    // Build arguments
-    let mut arguments = serde_json::json!({
-        "game": [],
-        "jvm": []
-    });
-
-    if let Some(args) = &manifest.arguments {
-        if let Some(game_args) = &args.game {
-            arguments["game"] = serde_json::Value::Array(game_args.clone());
-        }
-        if let Some(jvm_args) = &args.jvm {
-            arguments["jvm"] = serde_json::Value::Array(jvm_args.clone());
-        }
-    }
-
-    let json = serde_json::json!({
-        "id": version_id,
-        "inheritsFrom": manifest.inherits_from.clone().unwrap_or_else(|| game_version.to_string()),
-        "type": "release",
-        "mainClass": main_class,
-        "libraries": lib_entries,
-        "arguments": arguments
-    });
-
-    Ok(json)
-}
-
-/// Create a Forge version JSON with the proper library list (fallback).
-#[allow(dead_code)]
-fn create_forge_version_json(
-    game_version: &str,
-    forge_version: &str,
-    libraries: &[ForgeLibrary],
-) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
-    let version_id = generate_version_id(game_version, forge_version);
-
-    // Determine main class based on version
-    let main_class = if is_modern_forge(game_version) {
-        "cpw.mods.bootstraplauncher.BootstrapLauncher"
-    } else {
-        "net.minecraft.launchwrapper.Launch"
     };
 
-    // Convert libraries to JSON format
-    let lib_entries: Vec<serde_json::Value> = libraries
-        .iter()
-        .map(|lib| {
-            serde_json::json!({
-                "name": lib.name,
-                "url": FORGE_MAVEN_URL
-            })
-        })
-        .collect();
-
-    let json = serde_json::json!({
-        "id": version_id,
-        "inheritsFrom": game_version,
-        "type": "release",
-        "mainClass": main_class,
-        "libraries": lib_entries,
-        "arguments": {
-            "game": [],
-            "jvm": []
-        }
-    });
-
-    Ok(json)
-}
-
-/// Check if the Minecraft version uses modern Forge (1.13+).
-fn is_modern_forge(game_version: &str) -> bool {
-    let parts: Vec<&str> = game_version.split('.').collect();
-    if parts.len() >= 2 {
-        if let (Ok(major), Ok(minor)) = (parts[0].parse::<u32>(), parts[1].parse::<u32>()) {
-            return major > 1 || (major == 1 && minor >= 13);
-        }
-    }
-    false
+    tokio::fs::create_dir_all(dir).await?;
+    let path = dir.join(format!("{}-{}-installer.jar", loader.id(), version));
+    tokio::fs::write(&path, &bytes).await?;
+    Ok(path)
 }
 
 /// Check if Forge is installed for a specific version combination.
@@ -593,10 +421,25 @@ mod tests {
     }
 
     #[test]
-    fn test_is_modern_forge() {
-        assert!(!is_modern_forge("1.12.2"));
-        assert!(is_modern_forge("1.13"));
-        assert!(is_modern_forge("1.20.4"));
-        assert!(is_modern_forge("1.21"));
+    fn neoforge_versions_follow_the_minecraft_version() {
+        assert_eq!(neoforge_version_prefix("1.20.4").as_deref(), Some("20.4."));
+        assert_eq!(neoforge_version_prefix("1.21").as_deref(), Some("21.0."));
+        assert_eq!(neoforge_version_prefix("1.21.1").as_deref(), Some("21.1."));
+        assert_eq!(neoforge_version_prefix("24w14a"), None);
+
+        let listing: Vec<String> = [
+            "20.4.236",
+            "20.4.237",
+            "20.5.0-beta",
+            "21.0.1-beta",
+            "20.4.238-beta",
+        ]
+        .map(String::from)
+        .to_vec();
+        let versions = neoforge_versions_for(&listing, "20.4.", "1.20.4");
+        let names: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+        assert_eq!(names, ["20.4.238-beta", "20.4.237", "20.4.236"]);
+        assert!(versions[0].latest);
+        assert!(!versions[1].latest);
     }
 }
@@ -0,0 +1,1050 @@
+//! Installs Forge and NeoForge from their installer jar without running the installer.
+//!
+//! Installers for 1.13 and later describe the install in `install_profile.json`: the
+//! libraries to download, data values, and processors (jarsplitter, binarypatcher,
+//! ...) that build the patched client from the vanilla one. The libraries go through
+//! the regular downloader and each processor is run with the Java the game launches
+//! with, the way the installer itself runs them. Older installers only carry the
+//! version JSON and the universal jar.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+use crate::core::downloader::{DownloadTask, verify_file_checksum_async};
+use crate::core::game_files;
+use crate::core::game_version::Library;
+use crate::core::maven::MavenCoordinate;
+use crate::core::rules::RuleContext;
+
+/// Lines of processor output kept for the error of a failed processor
+const PROCESSOR_LOG_LINES: usize = 40;
+
+#[derive(Debug, Deserialize)]
+struct InstallProfile {
+    /// Entry of the version JSON in the installer, e.g. `/version.json`
+    json: String,
+    minecraft: String,
+    #[serde(default)]
+    data: HashMap<String, SidedValue>,
+    #[serde(default)]
+    processors: Vec<Processor>,
+    #[serde(default)]
+    libraries: Vec<Library>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SidedValue {
+    client: String,
+}
+
+/// A step of the install, run as `java -cp <jar>:<classpath> <Main-Class of jar> <args>`
+#[derive(Debug, Clone, Deserialize)]
+struct Processor {
+    /// Sides the processor runs for; all of them when absent
+    sides: Option<Vec<String>>,
+    jar: String,
+    #[serde(default)]
+    classpath: Vec<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    /// Files the processor writes, mapped to their SHA-1
+    #[serde(default)]
+    outputs: HashMap<String, String>,
+}
+
+impl Processor {
+    fn runs_on_client(&self) -> bool {
+        self.sides
+            .as_ref()
+            .is_none_or(|sides| sides.iter().any(|side| side == "client"))
+    }
+
+    /// The value following `--task`, which names what installertools does
+    fn task(&self) -> Option<&str> {
+        self.args
+            .iter()
+            .position(|arg| arg == "--task")
+            .and_then(|i| self.args.get(i + 1))
+            .map(String::as_str)
+    }
+}
+
+/// `install_profile.json` of installers before 1.13
+#[derive(Debug, Deserialize)]
+struct LegacyInstallProfile {
+    install: LegacyInstall,
+    #[serde(rename = "versionInfo")]
+    version_info: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyInstall {
+    /// Maven coordinate the universal jar is installed as
+    path: String,
+    /// Entry of the universal jar in the installer
+    #[serde(rename = "filePath")]
+    file_path: String,
+}
+
+#[derive(Debug)]
+enum Profile {
+    Modern(InstallProfile),
+    Legacy(LegacyInstall),
+}
+
+/// Progress of the processors, sent as `forge-install-progress`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "forge.ts")]
+pub struct ProcessorProgress {
+    /// 1-based
+    pub step: usize,
+    pub total: usize,
+    /// Maven coordinate of the processor jar
+    pub processor: String,
+    /// What the processor does, for installertools
+    pub task: Option<String>,
+    /// Its outputs were already there and intact
+    pub skipped: bool,
+}
+
+/// Where the processors read and write
+pub struct ProcessorEnv<'a> {
+    pub java: &'a Path,
+    pub libraries_dir: &'a Path,
+    /// Vanilla client jar the processors patch
+    pub client_jar: &'a Path,
+    /// Instance directory, `{ROOT}` in the install profile
+    pub root: &'a Path,
+    /// Scratch directory for the data files from the installer
+    pub work_dir: &'a Path,
+}
+
+#[derive(Debug)]
+pub struct ForgeInstaller {
+    path: PathBuf,
+    profile: Profile,
+    version: serde_json::Value,
+}
+
+impl ForgeInstaller {
+    /// Read the install profile and version JSON of the installer jar at `path`
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let profile_json: serde_json::Value =
+            serde_json::from_slice(&read_entry(path, "install_profile.json")?)
+                .map_err(|e| format!("Invalid install_profile.json: {}", e))?;
+
+        if profile_json.get("versionInfo").is_some() {
+            let legacy: LegacyInstallProfile = serde_json::from_value(profile_json)
+                .map_err(|e| format!("Invalid install_profile.json: {}", e))?;
+            return Ok(Self {
+                path: path.to_path_buf(),
+                profile: Profile::Legacy(legacy.install),
+                version: legacy.version_info,
+            });
+        }
+
+        let profile: InstallProfile = serde_json::from_value(profile_json)
+            .map_err(|e| format!("Invalid install_profile.json: {}", e))?;
+        let version = serde_json::from_slice(&read_entry(path, &profile.json)?)
+            .map_err(|e| format!("Invalid {}: {}", profile.json, e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            profile: Profile::Modern(profile),
+            version,
+        })
+    }
+
+    /// ID of the version the installer registers, e.g. `1.20.4-forge-49.0.38`
+    pub fn version_id(&self) -> Result<&str, String> {
+        self.version
+            .get("id")
+            .and_then(|id| id.as_str())
+            .ok_or_else(|| "The installer's version JSON has no id".to_string())
+    }
+
+    /// Put the libraries shipped inside the installer into `libraries_dir` and return
+    /// the tasks for the rest: those of the install profile and of the version
+    pub fn library_tasks(
+        &self,
+        libraries_dir: &Path,
+        rule_context: &RuleContext,
+    ) -> Result<Vec<DownloadTask>, String> {
+        let mut libraries: Vec<Library> = Vec::new();
+        let mut bundled = Vec::new();
+        match &self.profile {
+            Profile::Modern(profile) => {
+                libraries.extend(profile.libraries.iter().cloned());
+                let names = entry_names(&self.path)?;
+                for lib in profile.libraries.iter().chain(&self.version_libraries()?) {
+                    let Some(coordinate) = MavenCoordinate::parse(&lib.name) else {
+                        continue;
+                    };
+                    let entry = format!("maven/{}", coordinate.to_path());
+                    if names.contains(&entry) {
+                        let path = coordinate.to_local_path(libraries_dir);
+                        extract_entry(&self.path, &entry, &path)?;
+                        bundled.push(path);
+                    }
+                }
+            }
+            Profile::Legacy(install) => {
+                let path = library_path(&install.path, libraries_dir)
+                    .ok_or_else(|| format!("Invalid library coordinate {}", install.path))?;
+                extract_entry(&self.path, &install.file_path, &path)?;
+                bundled.push(path);
+            }
+        }
+        libraries.extend(self.version_libraries()?);
+
+        let mut tasks = game_files::library_tasks(&libraries, libraries_dir, rule_context);
+        tasks.retain(|task| !bundled.contains(&task.path));
+        // The install profile and the version share some libraries
+        let mut seen = std::collections::HashSet::new();
+        tasks.retain(|task| seen.insert(task.path.clone()));
+        Ok(tasks)
+    }
+
+    fn version_libraries(&self) -> Result<Vec<Library>, String> {
+        match self.version.get("libraries") {
+            Some(libraries) => serde_json::from_value(libraries.clone())
+                .map_err(|e| format!("Invalid libraries in the installer's version JSON: {}", e)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Run the client processors in order. One whose outputs already match their
+    /// SHA-1 is skipped, so an install that failed halfway can be retried cheaply.
+    pub async fn run_processors(
+        &self,
+        env: &ProcessorEnv<'_>,
+        on_progress: impl Fn(ProcessorProgress),
+    ) -> Result<(), String> {
+        let Profile::Modern(profile) = &self.profile else {
+            return Ok(());
+        };
+        let data = self.processor_data(profile, env)?;
+        let processors: Vec<&Processor> = profile
+            .processors
+            .iter()
+            .filter(|p| p.runs_on_client())
+            .collect();
+        let total = processors.len();
+
+        for (i, processor) in processors.into_iter().enumerate() {
+            let outputs = processor
+                .outputs
+                .iter()
+                .map(|(path, sha1)| {
+                    Ok((
+                        PathBuf::from(resolve_arg(path, &data, env.libraries_dir)?),
+                        resolve_arg(sha1, &data, env.libraries_dir)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let mut skipped = !outputs.is_empty();
+            for (path, sha1) in &outputs {
+                skipped = skipped && output_matches(path, sha1).await;
+            }
+            on_progress(ProcessorProgress {
+                step: i + 1,
+                total,
+                processor: processor.jar.clone(),
+                task: processor.task().map(str::to_string),
+                skipped,
+            });
+            if skipped {
+                continue;
+            }
+
+            let log = run_processor(processor, &data, env)
+                .await
+                .map_err(|e| format!("Processor {} ({}/{}) {}", processor.jar, i + 1, total, e))?;
+            for (path, expected) in &outputs {
+                if !output_matches(path, expected).await {
+                    let problem = if path.exists() {
+                        format!("wrote {} not matching SHA-1 {}", path.display(), expected)
+                    } else {
+                        format!("did not write {}", path.display())
+                    };
+                    return Err(format!(
+                        "Processor {} ({}/{}) {}\n{}",
+                        processor.jar,
+                        i + 1,
+                        total,
+                        problem,
+                        log
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Values the `{KEY}`s of processor arguments stand for. Data files from the
+    /// installer are extracted into the work directory.
+    fn processor_data(
+        &self,
+        profile: &InstallProfile,
+        env: &ProcessorEnv<'_>,
+    ) -> Result<HashMap<String, String>, String> {
+        let mut data = HashMap::new();
+        for (key, value) in &profile.data {
+            let value = &value.client;
+            let resolved = if let Some(coordinate) = bracketed(value) {
+                library_path(coordinate, env.libraries_dir)
+                    .ok_or_else(|| format!("Invalid library coordinate {}", coordinate))?
+                    .to_string_lossy()
+                    .into_owned()
+            } else if let Some(literal) =
+                value.strip_prefix('\'').and_then(|v| v.strip_suffix('\''))
+            {
+                literal.to_string()
+            } else {
+                let entry = value.trim_start_matches('/');
+                let target = env.work_dir.join(entry);
+                extract_entry(&self.path, entry, &target)?;
+                target.to_string_lossy().into_owned()
+            };
+            data.insert(key.clone(), resolved);
+        }
+
+        let path = |path: &Path| path.to_string_lossy().into_owned();
+        data.insert("SIDE".to_string(), "client".to_string());
+        data.insert("MINECRAFT_JAR".to_string(), path(env.client_jar));
+        data.insert("MINECRAFT_VERSION".to_string(), profile.minecraft.clone());
+        data.insert("ROOT".to_string(), path(env.root));
+        data.insert("INSTALLER".to_string(), path(&self.path));
+        data.insert("LIBRARY_DIR".to_string(), path(env.libraries_dir));
+        Ok(data)
+    }
+
+    /// Save the launchable version JSON into the instance, returning its path
+    pub fn save_version(&self, instance_dir: &Path) -> Result<PathBuf, String> {
+        let id = self.version_id()?;
+        let dir = instance_dir.join("versions").join(id);
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let path = dir.join(format!("{}.json", id));
+        let content = serde_json::to_string_pretty(&self.version).map_err(|e| e.to_string())?;
+        crate::core::java::persistence::write_file_atomic(&path, content.as_bytes())
+            .map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+/// Run one processor, returning the tail of its output
+async fn run_processor(
+    processor: &Processor,
+    data: &HashMap<String, String>,
+    env: &ProcessorEnv<'_>,
+) -> Result<String, String> {
+    let jar = library_path(&processor.jar, env.libraries_dir)
+        .ok_or_else(|| "has an invalid jar coordinate".to_string())?;
+    let main_class = main_class_of(&jar)?;
+    let mut classpath = vec![jar];
+    for entry in &processor.classpath {
+        classpath.push(
+            library_path(entry, env.libraries_dir)
+                .ok_or_else(|| format!("has an invalid classpath entry {}", entry))?,
+        );
+    }
+    let classpath = std::env::join_paths(&classpath).map_err(|e| e.to_string())?;
+    let args = processor
+        .args
+        .iter()
+        .map(|arg| resolve_arg(arg, data, env.libraries_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut cmd = tokio::process::Command::new(env.java);
+    cmd.arg("-cp")
+        .arg(classpath)
+        .arg(main_class)
+        .args(args)
+        .current_dir(env.work_dir)
+        .stdin(std::process::Stdio::null());
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("could not be started: {}", e))?;
+    let log = output_tail(&output.stdout, &output.stderr);
+    if !output.status.success() {
+        return Err(format!("failed with {}\n{}", output.status, log));
+    }
+    Ok(log)
+}
+
+/// The last `PROCESSOR_LOG_LINES` lines a processor printed
+fn output_tail(stdout: &[u8], stderr: &[u8]) -> String {
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(stdout),
+        String::from_utf8_lossy(stderr)
+    );
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(PROCESSOR_LOG_LINES)..].join("\n")
+}
+
+/// `coordinate` for `[coordinate]`
+fn bracketed(value: &str) -> Option<&str> {
+    value.strip_prefix('[').and_then(|v| v.strip_suffix(']'))
+}
+
+fn library_path(coordinate: &str, libraries_dir: &Path) -> Option<PathBuf> {
+    MavenCoordinate::parse(coordinate).map(|c| c.to_local_path(libraries_dir))
+}
+
+/// Resolve a processor argument: `[coordinate]` is the path of that library; anything
+/// else has its `{KEY}`s replaced from `data` and its `'quoted'` parts taken as is,
+/// with `\` escaping the next character
+fn resolve_arg(
+    arg: &str,
+    data: &HashMap<String, String>,
+    libraries_dir: &Path,
+) -> Result<String, String> {
+    if let Some(coordinate) = bracketed(arg) {
+        return library_path(coordinate, libraries_dir)
+            .map(|path| path.to_string_lossy().into_owned())
+            .ok_or_else(|| format!("Invalid library coordinate {}", coordinate));
+    }
+
+    let mut resolved = String::new();
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => resolved.extend(chars.next()),
+            '{' | '\'' => {
+                let close = if c == '{' { '}' } else { '\'' };
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => token.extend(chars.next()),
+                        Some(d) if d == close => break,
+                        Some(d) => token.push(d),
+                        None => return Err(format!("Unclosed {} in argument {}", c, arg)),
+                    }
+                }
+                if c == '\'' {
+                    resolved.push_str(&token);
+                } else {
+                    let value = data
+                        .get(&token)
+                        .ok_or_else(|| format!("Unknown key {} in argument {}", token, arg))?;
+                    resolved.push_str(value);
+                }
+            }
+            c => resolved.push(c),
+        }
+    }
+    Ok(resolved)
+}
+
+/// `Main-Class` from the manifest of the jar at `path`
+fn main_class_of(jar: &Path) -> Result<String, String> {
+    let manifest = read_entry(jar, "META-INF/MANIFEST.MF")?;
+    let manifest = String::from_utf8_lossy(&manifest);
+    // Long manifest lines continue on lines starting with a space
+    let unfolded = manifest.replace("\r\n", "\n").replace("\n ", "");
+    unfolded
+        .lines()
+        .find_map(|line| line.strip_prefix("Main-Class:"))
+        .map(|class| class.trim().to_string())
+        .ok_or_else(|| format!("{} has no Main-Class", jar.display()))
+}
+
+/// Whether the processor output at `path` exists with SHA-1 `sha1`
+async fn output_matches(path: &Path, sha1: &str) -> bool {
+    verify_file_checksum_async(path, None, None, Some(sha1))
+        .await
+        .unwrap_or(false)
+}
+
+fn open_archive(path: &Path) -> Result<zip::ZipArchive<std::fs::File>, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+fn entry_names(path: &Path) -> Result<std::collections::HashSet<String>, String> {
+    Ok(open_archive(path)?
+        .file_names()
+        .map(str::to_string)
+        .collect())
+}
+
+fn read_entry(path: &Path, name: &str) -> Result<Vec<u8>, String> {
+    let mut archive = open_archive(path)?;
+    let mut entry = archive
+        .by_name(name.trim_start_matches('/'))
+        .map_err(|e| format!("{} has no {}: {}", path.display(), name, e))?;
+    let mut content = Vec::new();
+    entry
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Failed to read {} from {}: {}", name, path.display(), e))?;
+    Ok(content)
+}
+
+fn extract_entry(path: &Path, name: &str, target: &Path) -> Result<(), String> {
+    let content = read_entry(path, name)?;
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(target, content)
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::core::downloader::{
+        DownloadController, RateLimiter, RetryPolicy, compute_sha1, run_download_tasks,
+    };
+    use crate::utils::mock_server::{MockServer, Response};
+    use crate::utils::test_dir::TestDir;
+    use std::io::Write;
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::Mutex;
+
+    const INSTALLERTOOLS: &str = "net.minecraftforge:installertools:1.3.0";
+    const INSTALLERTOOLS_MAIN: &str = "net.minecraftforge.installertools.ConsoleTool";
+    const BINARYPATCHER: &str = "net.minecraftforge:binarypatcher:1.1.1";
+    const BINARYPATCHER_MAIN: &str = "net.minecraftforge.binarypatcher.ConsoleTool";
+    const SECUREJARHANDLER: &str = "cpw.mods:securejarhandler:2.1.24";
+    const UNIVERSAL: &str = "net.minecraftforge:forge:1.20.4-49.0.38:universal";
+    const PATCHED: &str = "net.minecraftforge:forge:1.20.4-49.0.38:client";
+    const MAPPINGS: &str = "de.oceanlabs.mcp:mcp_config:1.20.4-20231207.154220:mappings@txt";
+
+    fn jar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        // A fixed time keeps the checksums of the jars stable
+        let options =
+            zip::write::SimpleFileOptions::default().last_modified_time(zip::DateTime::default());
+        for (name, content) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn processor_jar(main_class: &str) -> Vec<u8> {
+        let manifest = format!(
+            "Manifest-Version: 1.0\r\nMain-Class: {}\r\n\r\n",
+            main_class
+        );
+        jar(&[("META-INF/MANIFEST.MF", manifest.as_bytes())])
+    }
+
+    fn securejarhandler_jar() -> Vec<u8> {
+        jar(&[("cpw/mods/jarhandling/SecureJar.class", b"")])
+    }
+
+    fn library_json(name: &str, url: &str, content: &[u8]) -> serde_json::Value {
+        let coordinate = MavenCoordinate::parse(name).unwrap();
+        serde_json::json!({
+            "name": name,
+            "downloads": {"artifact": {
+                "path": coordinate.to_path(),
+                "sha1": compute_sha1(content),
+                "size": content.len(),
+                "url": if url.is_empty() { String::new() } else { format!("{}/{}", url, coordinate.to_path()) },
+            }}
+        })
+    }
+
+    /// A 1.20.4 installer whose libraries are served from `maven`, with a server-only
+    /// processor followed by the two client ones
+    fn write_installer(path: &Path, maven: &str) {
+        let profile = serde_json::json!({
+            "spec": 1,
+            "profile": "forge",
+            "version": "1.20.4-forge-49.0.38",
+            "json": "/version.json",
+            "minecraft": "1.20.4",
+            "data": {
+                "MAPPINGS": {"client": format!("[{}]", MAPPINGS), "server": format!("[{}]", MAPPINGS)},
+                "MAPPINGS_SHA": {"client": format!("'{}'", compute_sha1(INSTALLERTOOLS_MAIN.as_bytes())), "server": "''"},
+                "PATCHED": {"client": format!("[{}]", PATCHED), "server": "[net.minecraftforge:forge:1.20.4-49.0.38:server]"},
+                "PATCHED_SHA": {"client": format!("'{}'", compute_sha1(BINARYPATCHER_MAIN.as_bytes())), "server": "''"},
+                "BINPATCH": {"client": "/data/client.lzma", "server": "/data/server.lzma"}
+            },
+            "processors": [
+                {
+                    "sides": ["server"],
+                    "jar": INSTALLERTOOLS,
+                    "classpath": [],
+                    "args": ["--task", "EXTRACT_FILES", "--archive", "{INSTALLER}"]
+                },
+                {
+                    "jar": INSTALLERTOOLS,
+                    "classpath": [SECUREJARHANDLER],
+                    "args": ["--task", "MCP_DATA", "--input", "[de.oceanlabs.mcp:mcp_config:1.20.4-20231207.154220@zip]", "--output", "{MAPPINGS}", "--key", "mappings"],
+                    "outputs": {"{MAPPINGS}": "{MAPPINGS_SHA}"}
+                },
+                {
+                    "sides": ["client"],
+                    "jar": BINARYPATCHER,
+                    "classpath": [],
+                    "args": ["--clean", "{MINECRAFT_JAR}", "--output", "{PATCHED}", "--apply", "{BINPATCH}"],
+                    "outputs": {"{PATCHED}": "{PATCHED_SHA}"}
+                }
+            ],
+            "libraries": [
+                library_json(INSTALLERTOOLS, maven, &processor_jar(INSTALLERTOOLS_MAIN)),
+                library_json(BINARYPATCHER, maven, &processor_jar(BINARYPATCHER_MAIN)),
+                library_json(SECUREJARHANDLER, maven, &securejarhandler_jar()),
+                library_json(UNIVERSAL, "", b"universal")
+            ]
+        });
+        let version = serde_json::json!({
+            "id": "1.20.4-forge-49.0.38",
+            "inheritsFrom": "1.20.4",
+            "type": "release",
+            "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+            "arguments": {"game": ["--launchTarget", "forgeclient"], "jvm": ["-DlibraryDirectory=${library_directory}"]},
+            "libraries": [
+                library_json(SECUREJARHANDLER, maven, &securejarhandler_jar()),
+                library_json(UNIVERSAL, "", b"universal"),
+                library_json(PATCHED, "", b"")
+            ]
+        });
+
+        let content = jar(&[
+            ("install_profile.json", profile.to_string().as_bytes()),
+            ("version.json", version.to_string().as_bytes()),
+            ("data/client.lzma", b"client patches"),
+            ("data/server.lzma", b"server patches"),
+            (
+                "maven/net/minecraftforge/forge/1.20.4-49.0.38/forge-1.20.4-49.0.38-universal.jar",
+                b"universal",
+            ),
+        ]);
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// A maven repository serving the processor jars and securejarhandler
+    async fn spawn_maven() -> String {
+        let mut maven = MockServer::builder();
+        for (name, content) in [
+            (INSTALLERTOOLS, processor_jar(INSTALLERTOOLS_MAIN)),
+            (BINARYPATCHER, processor_jar(BINARYPATCHER_MAIN)),
+            (SECUREJARHANDLER, securejarhandler_jar()),
+        ] {
+            let path = MavenCoordinate::parse(name).unwrap().to_path();
+            maven = maven.route(&format!("/maven/{}", path), move |_| {
+                Response::ok(content.clone())
+            });
+        }
+        maven.start().await.url("/maven")
+    }
+
+    /// Stands in for java: writes the processor's main class to its `--output` and
+    /// fails if the `--apply` patches were not extracted
+    fn fake_java(dir: &Path, body: &str) -> PathBuf {
+        let script = dir.join("java");
+        std::fs::write(&script, format!("#!/bin/sh\n{}", body)).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    const PROCESSOR_SCRIPT: &str = "main=\"$3\"\n\
+         out=\"\"\n\
+         while [ $# -gt 0 ]; do\n\
+           case \"$1\" in\n\
+             --output) out=\"$2\" ;;\n\
+             --apply) [ -f \"$2\" ] || { echo \"missing patches $2\" >&2; exit 2; } ;;\n\
+           esac\n\
+           shift\n\
+         done\n\
+         echo \"running $main\"\n\
+         mkdir -p \"$(dirname \"$out\")\"\n\
+         printf '%s' \"$main\" > \"$out\"\n";
+
+    /// An installer whose libraries are in place, next to a vanilla client jar
+    struct Installed {
+        dir: TestDir,
+        installer: ForgeInstaller,
+        libraries: PathBuf,
+        client_jar: PathBuf,
+        work_dir: PathBuf,
+    }
+
+    impl Installed {
+        fn env<'a>(&'a self, java: &'a Path) -> ProcessorEnv<'a> {
+            ProcessorEnv {
+                java,
+                libraries_dir: &self.libraries,
+                client_jar: &self.client_jar,
+                root: self.dir.path(),
+                work_dir: &self.work_dir,
+            }
+        }
+    }
+
+    /// Open the installer and download its libraries
+    async fn installed() -> Installed {
+        let maven = spawn_maven().await;
+        let dir = TestDir::new("forge");
+        let path = dir.path().join("forge-1.20.4-49.0.38-installer.jar");
+        write_installer(&path, &maven);
+        let installer = ForgeInstaller::open(&path).unwrap();
+
+        let libraries = dir.path().join("libraries");
+        let tasks = installer
+            .library_tasks(&libraries, &RuleContext::current())
+            .unwrap();
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            tasks,
+            4,
+            0,
+            &RetryPolicy::with_attempts(1),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+        assert!(report.is_success(), "{:?}", report.failed);
+
+        let client_jar = dir.path().join("versions/1.20.4/1.20.4.jar");
+        std::fs::create_dir_all(client_jar.parent().unwrap()).unwrap();
+        std::fs::write(&client_jar, b"vanilla").unwrap();
+        Installed {
+            work_dir: dir.path().join(".forge-installer"),
+            dir,
+            installer,
+            libraries,
+            client_jar,
+        }
+    }
+
+    fn data() -> HashMap<String, String> {
+        HashMap::from([
+            ("SIDE".to_string(), "client".to_string()),
+            ("MINECRAFT_JAR".to_string(), "/mc/client.jar".to_string()),
+        ])
+    }
+
+    #[test]
+    fn library_arguments_become_paths() {
+        let libraries = Path::new("/libraries");
+        assert_eq!(
+            resolve_arg(&format!("[{}]", MAPPINGS), &data(), libraries).unwrap(),
+            libraries
+                .join("de/oceanlabs/mcp/mcp_config/1.20.4-20231207.154220/mcp_config-1.20.4-20231207.154220-mappings.txt")
+                .to_string_lossy()
+        );
+        assert!(resolve_arg("[not-a-coordinate]", &data(), libraries).is_err());
+    }
+
+    #[test]
+    fn keys_literals_and_escapes_are_resolved() {
+        let libraries = Path::new("/libraries");
+        assert_eq!(
+            resolve_arg("{MINECRAFT_JAR}", &data(), libraries).unwrap(),
+            "/mc/client.jar"
+        );
+        assert_eq!(
+            resolve_arg("--side={SIDE}", &data(), libraries).unwrap(),
+            "--side=client"
+        );
+        assert_eq!(
+            resolve_arg("'{SIDE}'", &data(), libraries).unwrap(),
+            "{SIDE}"
+        );
+        assert_eq!(
+            resolve_arg("\\{SIDE\\}", &data(), libraries).unwrap(),
+            "{SIDE}"
+        );
+        assert_eq!(resolve_arg("plain", &data(), libraries).unwrap(), "plain");
+    }
+
+    #[test]
+    fn bad_arguments_are_reported() {
+        let libraries = Path::new("/libraries");
+        assert_eq!(
+            resolve_arg("{BINPATCH}", &data(), libraries).unwrap_err(),
+            "Unknown key BINPATCH in argument {BINPATCH}"
+        );
+        assert_eq!(
+            resolve_arg("{SIDE", &data(), libraries).unwrap_err(),
+            "Unclosed { in argument {SIDE"
+        );
+        assert_eq!(
+            resolve_arg("'open", &data(), libraries).unwrap_err(),
+            "Unclosed ' in argument 'open"
+        );
+    }
+
+    #[test]
+    fn processors_are_filtered_by_side() {
+        let processor: Processor = serde_json::from_value(serde_json::json!({
+            "jar": INSTALLERTOOLS,
+            "args": ["--task", "MCP_DATA"]
+        }))
+        .unwrap();
+        assert!(processor.runs_on_client());
+        assert_eq!(processor.task(), Some("MCP_DATA"));
+
+        let server: Processor = serde_json::from_value(serde_json::json!({
+            "sides": ["server"],
+            "jar": BINARYPATCHER
+        }))
+        .unwrap();
+        assert!(!server.runs_on_client());
+        assert_eq!(server.task(), None);
+    }
+
+    #[test]
+    fn main_class_is_read_from_a_folded_manifest() {
+        let dir = TestDir::new("forge");
+        let path = dir.path().join("tool.jar");
+        let manifest = "Manifest-Version: 1.0\r\nMain-Class: net.minecraftforge.installertools.Cons\r\n oleTool\r\n\r\n";
+        std::fs::write(&path, jar(&[("META-INF/MANIFEST.MF", manifest.as_bytes())])).unwrap();
+        assert_eq!(main_class_of(&path).unwrap(), INSTALLERTOOLS_MAIN);
+
+        std::fs::write(
+            &path,
+            jar(&[("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\r\n")]),
+        )
+        .unwrap();
+        assert!(
+            main_class_of(&path)
+                .unwrap_err()
+                .contains("has no Main-Class")
+        );
+    }
+
+    #[tokio::test]
+    async fn installs_from_the_installer_jar() {
+        let installed = installed().await;
+        let libraries = &installed.libraries;
+        assert_eq!(
+            installed.installer.version_id().unwrap(),
+            "1.20.4-forge-49.0.38"
+        );
+        // Bundled in the installer rather than downloaded
+        assert_eq!(
+            std::fs::read(libraries.join(MavenCoordinate::parse(UNIVERSAL).unwrap().to_path()))
+                .unwrap(),
+            b"universal"
+        );
+        for name in [INSTALLERTOOLS, BINARYPATCHER, SECUREJARHANDLER] {
+            assert!(
+                MavenCoordinate::parse(name)
+                    .unwrap()
+                    .to_local_path(libraries)
+                    .is_file()
+            );
+        }
+
+        let java = fake_java(installed.dir.path(), PROCESSOR_SCRIPT);
+        let progress = Mutex::new(Vec::new());
+        installed
+            .installer
+            .run_processors(&installed.env(&java), |p| progress.lock().unwrap().push(p))
+            .await
+            .unwrap();
+
+        // The server-only processor is left out
+        assert_eq!(
+            progress.into_inner().unwrap(),
+            vec![
+                ProcessorProgress {
+                    step: 1,
+                    total: 2,
+                    processor: INSTALLERTOOLS.to_string(),
+                    task: Some("MCP_DATA".to_string()),
+                    skipped: false,
+                },
+                ProcessorProgress {
+                    step: 2,
+                    total: 2,
+                    processor: BINARYPATCHER.to_string(),
+                    task: None,
+                    skipped: false,
+                },
+            ]
+        );
+        let patched = MavenCoordinate::parse(PATCHED)
+            .unwrap()
+            .to_local_path(libraries);
+        assert_eq!(
+            std::fs::read_to_string(&patched).unwrap(),
+            BINARYPATCHER_MAIN
+        );
+
+        let json = installed
+            .installer
+            .save_version(installed.dir.path())
+            .unwrap();
+        assert_eq!(
+            json,
+            installed
+                .dir
+                .path()
+                .join("versions/1.20.4-forge-49.0.38/1.20.4-forge-49.0.38.json")
+        );
+        let vanilla_dir = installed.dir.path().join("versions").join("1.20.4");
+        std::fs::create_dir_all(&vanilla_dir).unwrap();
+        std::fs::write(
+            vanilla_dir.join("1.20.4.json"),
+            r#"{"id": "1.20.4", "type": "release", "mainClass": "net.minecraft.client.main.Main", "libraries": []}"#,
+        )
+        .unwrap();
+        let merged =
+            crate::core::manifest::load_version(installed.dir.path(), "1.20.4-forge-49.0.38")
+                .await
+                .unwrap();
+        assert_eq!(
+            merged.main_class,
+            "cpw.mods.bootstraplauncher.BootstrapLauncher"
+        );
+        assert!(merged.libraries.iter().any(|lib| lib.name == PATCHED));
+    }
+
+    #[tokio::test]
+    async fn shared_libraries_are_downloaded_once() {
+        let maven = spawn_maven().await;
+        let dir = TestDir::new("forge");
+        let path = dir.path().join("installer.jar");
+        write_installer(&path, &maven);
+
+        let tasks = ForgeInstaller::open(&path)
+            .unwrap()
+            .library_tasks(&dir.path().join("libraries"), &RuleContext::current())
+            .unwrap();
+        let mut urls: Vec<String> = tasks.into_iter().map(|t| t.url).collect();
+        urls.sort();
+        let mut expected = [INSTALLERTOOLS, BINARYPATCHER, SECUREJARHANDLER].map(|name| {
+            let path = MavenCoordinate::parse(name).unwrap().to_path();
+            format!("{}/{}", maven, path)
+        });
+        expected.sort();
+        // securejarhandler is listed by both the install profile and the version
+        assert_eq!(urls, expected);
+    }
+
+    #[tokio::test]
+    async fn intact_outputs_are_not_rebuilt() {
+        let installed = installed().await;
+        let java = fake_java(installed.dir.path(), PROCESSOR_SCRIPT);
+        installed
+            .installer
+            .run_processors(&installed.env(&java), |_| {})
+            .await
+            .unwrap();
+
+        // Java is not even started the second time
+        let missing_java = installed.dir.path().join("no-java");
+        let progress = Mutex::new(Vec::new());
+        installed
+            .installer
+            .run_processors(&installed.env(&missing_java), |p| {
+                progress.lock().unwrap().push(p)
+            })
+            .await
+            .unwrap();
+        assert!(progress.into_inner().unwrap().iter().all(|p| p.skipped));
+    }
+
+    #[tokio::test]
+    async fn failed_processor_is_named_with_its_output() {
+        let installed = installed().await;
+        let java = fake_java(
+            installed.dir.path(),
+            "echo 'Exception in thread \"main\" java.lang.IllegalStateException: bad mappings' >&2\nexit 1\n",
+        );
+
+        let error = installed
+            .installer
+            .run_processors(&installed.env(&java), |_| {})
+            .await
+            .unwrap_err();
+        assert!(
+            error.starts_with(&format!("Processor {} (1/2) failed with", INSTALLERTOOLS)),
+            "{}",
+            error
+        );
+        assert!(error.contains("java.lang.IllegalStateException: bad mappings"));
+    }
+
+    #[tokio::test]
+    async fn wrong_output_checksum_fails_the_install() {
+        let installed = installed().await;
+        let java = fake_java(
+            installed.dir.path(),
+            &PROCESSOR_SCRIPT.replace("printf '%s' \"$main\"", "printf 'corrupt'"),
+        );
+
+        let error = installed
+            .installer
+            .run_processors(&installed.env(&java), |_| {})
+            .await
+            .unwrap_err();
+        assert!(
+            error.starts_with(&format!("Processor {} (1/2) wrote", INSTALLERTOOLS)),
+            "{}",
+            error
+        );
+        assert!(error.contains(&format!(
+            "not matching SHA-1 {}",
+            compute_sha1(INSTALLERTOOLS_MAIN.as_bytes())
+        )));
+        assert!(error.contains(&format!("running {}", INSTALLERTOOLS_MAIN)));
+    }
+
+    #[test]
+    fn legacy_installer_ships_the_universal_jar() {
+        let dir = TestDir::new("forge");
+        let path = dir.path().join("installer.jar");
+        let profile = serde_json::json!({
+            "install": {
+                "path": "net.minecraftforge:forge:1.12.2-14.23.5.2860",
+                "filePath": "forge-1.12.2-14.23.5.2860-universal.jar",
+                "minecraft": "1.12.2"
+            },
+            "versionInfo": {
+                "id": "1.12.2-forge-14.23.5.2860",
+                "inheritsFrom": "1.12.2",
+                "mainClass": "net.minecraft.launchwrapper.Launch",
+                "libraries": [{"name": "net.minecraftforge:forge:1.12.2-14.23.5.2860"}]
+            }
+        });
+        std::fs::write(
+            &path,
+            jar(&[
+                ("install_profile.json", profile.to_string().as_bytes()),
+                ("forge-1.12.2-14.23.5.2860-universal.jar", b"universal"),
+            ]),
+        )
+        .unwrap();
+
+        let installer = ForgeInstaller::open(&path).unwrap();
+        let libraries = dir.path().join("libraries");
+        let tasks = installer
+            .library_tasks(&libraries, &RuleContext::current())
+            .unwrap();
+        assert!(tasks.is_empty(), "{:?}", tasks);
+        assert_eq!(
+            std::fs::read(libraries.join(
+                "net/minecraftforge/forge/1.12.2-14.23.5.2860/forge-1.12.2-14.23.5.2860.jar"
+            ))
+            .unwrap(),
+            b"universal"
+        );
+        let json = installer.save_version(dir.path()).unwrap();
+        assert!(
+            std::fs::read_to_string(json)
+                .unwrap()
+                .contains("net.minecraft.launchwrapper.Launch")
+        );
+    }
+}
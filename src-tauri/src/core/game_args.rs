@@ -0,0 +1,557 @@
+//! Game side of the launch command: everything after the main class.
+//!
+//! Modern version JSONs list `game` arguments with rules, older ones a single
+//! `minecraftArguments` string; both go through the same `${name}` substitution.
+//! A placeholder without a value is never passed on literally: optional arguments
+//! that need one are dropped (and reported), anything else fails the launch.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::core::auth::Account;
+use crate::core::game_version::GameVersion;
+use crate::core::quick_play::{self, QuickPlay};
+use crate::core::rules::{self, RuleContext};
+
+/// Values the game arguments are filled in from
+pub struct GameLaunchContext<'a> {
+    pub account: &'a Account,
+    pub version_name: &'a str,
+    pub game_dir: &'a Path,
+    pub assets_root: &'a Path,
+    pub assets_index_name: &'a str,
+    /// Window size, when the user set one
+    pub resolution: Option<(u32, u32)>,
+    /// World, server or Realm to start in
+    pub quick_play: Option<&'a QuickPlay>,
+}
+
+impl GameLaunchContext<'_> {
+    /// Value of every placeholder this launch can fill in, keyed by name without `${}`
+    fn placeholders(&self, version: &GameVersion) -> HashMap<&'static str, String> {
+        let access_token = self.account.access_token();
+        let uuid = self.account.uuid();
+        let user_type = match self.account {
+            Account::Microsoft(_) => "msa",
+            Account::Offline(_) => "legacy",
+        };
+        let assets_root = self.assets_root.to_string_lossy().into_owned();
+        let mut values = HashMap::from([
+            ("auth_player_name", self.account.username()),
+            // Versions before 1.7.2 take the session as one argument
+            ("auth_session", format!("token:{}:{}", access_token, uuid)),
+            ("auth_access_token", access_token),
+            ("auth_uuid", uuid),
+            // Xbox user id and client id aren't tracked by the launcher
+            ("auth_xuid", "0".to_string()),
+            ("clientid", "0".to_string()),
+            ("user_type", user_type.to_string()),
+            ("user_properties", "{}".to_string()),
+            ("version_name", self.version_name.to_string()),
+            (
+                "version_type",
+                version
+                    .version_type
+                    .clone()
+                    .unwrap_or_else(|| "release".to_string()),
+            ),
+            (
+                "game_directory",
+                self.game_dir.to_string_lossy().into_owned(),
+            ),
+            ("game_assets", assets_root.clone()),
+            ("assets_root", assets_root),
+            ("assets_index_name", self.assets_index_name.to_string()),
+        ]);
+        if let Some((width, height)) = self.resolution {
+            values.insert("resolution_width", width.to_string());
+            values.insert("resolution_height", height.to_string());
+        }
+        if let Some(target) = self.quick_play {
+            values.insert(
+                "quickPlayPath",
+                self.game_dir
+                    .join("quickPlay")
+                    .join("log.json")
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+            values.insert(target.placeholder(), target.value().to_string());
+        }
+        values
+    }
+}
+
+/// Game arguments of a launch
+#[derive(Debug, Default)]
+pub struct GameArgs {
+    pub args: Vec<String>,
+    /// Placeholders optional arguments were dropped for
+    pub skipped: Vec<String>,
+    /// The version has no way to go to the Quick Play target, so it was left out
+    pub quick_play_unsupported: bool,
+}
+
+/// Replace every `${name}` in `arg`, or return the first name without a value
+fn substitute(arg: &str, values: &HashMap<&str, String>) -> Result<String, String> {
+    let mut result = String::with_capacity(arg.len());
+    let mut rest = arg;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            return Err(after.to_string());
+        };
+        let name = &after[..end];
+        result.push_str(values.get(name).ok_or_else(|| name.to_string())?);
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn unknown_placeholder(arg: &str, name: &str) -> String {
+    format!(
+        "Game argument \"{}\" uses unknown placeholder ${{{}}}",
+        arg, name
+    )
+}
+
+/// Every game argument for launching `version`, after the main class
+pub fn build_game_args(
+    version: &GameVersion,
+    launch: &GameLaunchContext,
+    context: &RuleContext,
+) -> Result<GameArgs, String> {
+    let values = launch.placeholders(version);
+    let mut game_args = GameArgs::default();
+
+    // Quick Play arguments of the version JSON are behind features; older versions
+    // get `--server`/`--port` at the end
+    let mut context = context.clone();
+    let mut server_args = Vec::new();
+    if let Some(target) = launch.quick_play {
+        match quick_play::support(version, target) {
+            quick_play::Support::QuickPlay => {
+                for feature in ["has_quick_plays_support", target.feature()] {
+                    context.features.insert(feature.to_string(), true);
+                }
+            }
+            quick_play::Support::LegacyServer => {
+                server_args = quick_play::legacy_server_args(target.value())?;
+            }
+            quick_play::Support::Unsupported => game_args.quick_play_unsupported = true,
+        }
+    }
+
+    if let Some(minecraft_arguments) = &version.minecraft_arguments {
+        for part in minecraft_arguments.split_whitespace() {
+            let arg = substitute(part, &values).map_err(|name| unknown_placeholder(part, &name))?;
+            game_args.args.push(arg);
+        }
+        game_args.args.extend(server_args);
+        return Ok(game_args);
+    }
+
+    let Some(game) = version.arguments.as_ref().and_then(|a| a.game.as_ref()) else {
+        return Ok(game_args);
+    };
+    for entry in rules::applicable_arguments(game, &context) {
+        let mut args = Vec::with_capacity(entry.values.len());
+        let mut missing = None;
+        for value in &entry.values {
+            match substitute(value, &values) {
+                Ok(arg) => args.push(arg),
+                Err(name) if entry.conditional => {
+                    missing = Some(name);
+                    break;
+                }
+                Err(name) => return Err(unknown_placeholder(value, &name)),
+            }
+        }
+        // An optional argument goes together with its flag, e.g. `--width ${resolution_width}`
+        match missing {
+            Some(name) => game_args.skipped.push(name),
+            None => game_args.args.extend(args),
+        }
+    }
+    game_args.args.extend(server_args);
+    Ok(game_args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::auth::OfflineAccount;
+
+    /// Trimmed from the 1.20.4 version JSON
+    const VANILLA_1_20_4: &str = r#"{
+        "id": "1.20.4",
+        "type": "release",
+        "mainClass": "net.minecraft.client.main.Main",
+        "arguments": {
+            "game": [
+                "--username", "${auth_player_name}",
+                "--version", "${version_name}",
+                "--gameDir", "${game_directory}",
+                "--assetsDir", "${assets_root}",
+                "--assetIndex", "${assets_index_name}",
+                "--uuid", "${auth_uuid}",
+                "--accessToken", "${auth_access_token}",
+                "--clientId", "${clientid}",
+                "--xuid", "${auth_xuid}",
+                "--userType", "${user_type}",
+                "--versionType", "${version_type}",
+                {"rules": [{"action": "allow", "features": {"is_demo_user": true}}], "value": "--demo"},
+                {"rules": [{"action": "allow", "features": {"has_custom_resolution": true}}], "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]},
+                {"rules": [{"action": "allow", "features": {"has_quick_plays_support": true}}], "value": ["--quickPlayPath", "${quickPlayPath}"]},
+                {"rules": [{"action": "allow", "features": {"is_quick_play_singleplayer": true}}], "value": ["--quickPlaySingleplayer", "${quickPlaySingleplayer}"]},
+                {"rules": [{"action": "allow", "features": {"is_quick_play_multiplayer": true}}], "value": ["--quickPlayMultiplayer", "${quickPlayMultiplayer}"]},
+                {"rules": [{"action": "allow", "features": {"is_quick_play_realms": true}}], "value": ["--quickPlayRealms", "${quickPlayRealms}"]}
+            ],
+            "jvm": []
+        },
+        "libraries": []
+    }"#;
+
+    /// From the 1.7.10 and 1.5.2 version JSONs
+    const LEGACY_ARGUMENTS: &[&str] = &[
+        "--username ${auth_player_name} --version ${version_name} --gameDir ${game_directory} --assetsDir ${assets_root} --assetIndex ${assets_index_name} --uuid ${auth_uuid} --accessToken ${auth_access_token} --userProperties ${user_properties} --userType ${user_type}",
+        "${auth_player_name} ${auth_session} --gameDir ${game_directory} --assetsDir ${game_assets}",
+    ];
+
+    fn account() -> Account {
+        Account::Offline(OfflineAccount {
+            username: "Steve".to_string(),
+            uuid: "8667ba71b85a4004af54457a9734eed7".to_string(),
+        })
+    }
+
+    fn launch<'a>(
+        account: &'a Account,
+        game_dir: &'a Path,
+        assets: &'a Path,
+    ) -> GameLaunchContext<'a> {
+        GameLaunchContext {
+            account,
+            version_name: "1.20.4",
+            game_dir,
+            assets_root: assets,
+            assets_index_name: "12",
+            resolution: None,
+            quick_play: None,
+        }
+    }
+
+    fn context(features: &[&str]) -> RuleContext {
+        RuleContext {
+            features: features.iter().map(|f| (f.to_string(), true)).collect(),
+            ..RuleContext::current()
+        }
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn substitutes_modern_arguments_and_enabled_features() {
+        let version: GameVersion = serde_json::from_str(VANILLA_1_20_4).unwrap();
+        let account = account();
+        let game_dir = Path::new("/mc/instances/default");
+        let assets = Path::new("/mc/assets");
+        let game_dir_str = game_dir.to_string_lossy();
+        let assets_str = assets.to_string_lossy();
+        let base = vec![
+            "--username",
+            "Steve",
+            "--version",
+            "1.20.4",
+            "--gameDir",
+            &game_dir_str,
+            "--assetsDir",
+            &assets_str,
+            "--assetIndex",
+            "12",
+            "--uuid",
+            "8667ba71b85a4004af54457a9734eed7",
+            "--accessToken",
+            "null",
+            "--clientId",
+            "0",
+            "--xuid",
+            "0",
+            "--userType",
+            "legacy",
+            "--versionType",
+            "release",
+        ];
+
+        let plain =
+            build_game_args(&version, &launch(&account, game_dir, assets), &context(&[])).unwrap();
+        assert_eq!(plain.args, strings(&base));
+        assert!(plain.skipped.is_empty());
+
+        let mut custom = launch(&account, game_dir, assets);
+        custom.resolution = Some((1920, 1080));
+        let server = QuickPlay::Multiplayer {
+            address: "mc.example.com".to_string(),
+        };
+        custom.quick_play = Some(&server);
+        // Quick Play turns its own features on
+        let features = context(&["is_demo_user", "has_custom_resolution"]);
+        let quick_play_log = game_dir.join("quickPlay").join("log.json");
+        let quick_play_log = quick_play_log.to_string_lossy();
+        let mut expected = base.clone();
+        expected.extend([
+            "--demo",
+            "--width",
+            "1920",
+            "--height",
+            "1080",
+            "--quickPlayPath",
+            &quick_play_log,
+            "--quickPlayMultiplayer",
+            "mc.example.com",
+        ]);
+        let args = build_game_args(&version, &custom, &features).unwrap();
+        assert_eq!(args.args, strings(&expected));
+        assert!(args.skipped.is_empty());
+    }
+
+    #[test]
+    fn optional_arguments_without_values_are_dropped_and_reported() {
+        let version: GameVersion = serde_json::from_str(VANILLA_1_20_4).unwrap();
+        let account = account();
+        let launch = launch(&account, Path::new("/mc"), Path::new("/mc/assets"));
+        // Features on, but nothing to fill them in with
+        let args = build_game_args(
+            &version,
+            &launch,
+            &context(&["has_custom_resolution", "is_quick_play_realms"]),
+        )
+        .unwrap();
+        assert!(!args.args.iter().any(|arg| arg.contains("${")));
+        assert!(
+            !args
+                .args
+                .iter()
+                .any(|arg| arg == "--width" || arg == "--quickPlayRealms")
+        );
+        assert_eq!(args.skipped, vec!["resolution_width", "quickPlayRealms"]);
+    }
+
+    #[test]
+    fn unknown_placeholders_in_required_arguments_fail() {
+        let version: GameVersion = serde_json::from_value(serde_json::json!({
+            "id": "custom",
+            "mainClass": "net.minecraft.client.main.Main",
+            "arguments": {"game": ["--username", "${auth_player_name}", "--token", "${launcher_secret}"]},
+            "libraries": []
+        }))
+        .unwrap();
+        let account = account();
+        let launch = launch(&account, Path::new("/mc"), Path::new("/mc/assets"));
+        let err = build_game_args(&version, &launch, &context(&[])).unwrap_err();
+        assert!(err.contains("${launcher_secret}"), "{}", err);
+
+        let unterminated: GameVersion = serde_json::from_value(serde_json::json!({
+            "id": "broken",
+            "mainClass": "net.minecraft.client.main.Main",
+            "minecraftArguments": "--username ${auth_player_name",
+            "libraries": []
+        }))
+        .unwrap();
+        assert!(build_game_args(&unterminated, &launch, &context(&[])).is_err());
+    }
+
+    #[test]
+    fn substitutes_legacy_argument_strings() {
+        let account = account();
+        let game_dir = Path::new("/mc/instances/old");
+        let assets = Path::new("/mc/assets");
+        let launch = GameLaunchContext {
+            version_name: "1.7.10",
+            assets_index_name: "1.7.10",
+            ..launch(&account, game_dir, assets)
+        };
+        let game_dir = game_dir.to_string_lossy();
+        let assets = assets.to_string_lossy();
+        let expected: [Vec<&str>; 2] = [
+            vec![
+                "--username",
+                "Steve",
+                "--version",
+                "1.7.10",
+                "--gameDir",
+                &game_dir,
+                "--assetsDir",
+                &assets,
+                "--assetIndex",
+                "1.7.10",
+                "--uuid",
+                "8667ba71b85a4004af54457a9734eed7",
+                "--accessToken",
+                "null",
+                "--userProperties",
+                "{}",
+                "--userType",
+                "legacy",
+            ],
+            vec![
+                "Steve",
+                "token:null:8667ba71b85a4004af54457a9734eed7",
+                "--gameDir",
+                &game_dir,
+                "--assetsDir",
+                &assets,
+            ],
+        ];
+        for (arguments, expected) in LEGACY_ARGUMENTS.iter().zip(expected) {
+            let version: GameVersion = serde_json::from_value(serde_json::json!({
+                "id": "1.7.10",
+                "mainClass": "net.minecraft.client.main.Main",
+                "minecraftArguments": arguments,
+                "libraries": []
+            }))
+            .unwrap();
+            let args = build_game_args(&version, &launch, &context(&["is_demo_user"])).unwrap();
+            assert_eq!(args.args, strings(&expected));
+        }
+    }
+
+    /// Trimmed from the 1.16.5 version JSON: modern arguments, but no Quick Play
+    const VANILLA_1_16_5: &str = r#"{
+        "id": "1.16.5",
+        "type": "release",
+        "mainClass": "net.minecraft.client.main.Main",
+        "arguments": {
+            "game": [
+                "--username", "${auth_player_name}",
+                "--version", "${version_name}",
+                {"rules": [{"action": "allow", "features": {"is_demo_user": true}}], "value": "--demo"},
+                {"rules": [{"action": "allow", "features": {"has_custom_resolution": true}}], "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]}
+            ],
+            "jvm": []
+        },
+        "libraries": []
+    }"#;
+
+    /// What `target` adds to the arguments of `version`, and whether it was left out
+    fn quick_play_args(version: &GameVersion, target: &QuickPlay) -> (Vec<String>, bool) {
+        let account = account();
+        let mut launch = launch(&account, Path::new("/mc"), Path::new("/mc/assets"));
+        let plain = build_game_args(version, &launch, &context(&[])).unwrap();
+        launch.quick_play = Some(target);
+        let args = build_game_args(version, &launch, &context(&[])).unwrap();
+        assert_eq!(args.args[..plain.args.len()], plain.args[..]);
+        (
+            args.args[plain.args.len()..].to_vec(),
+            args.quick_play_unsupported,
+        )
+    }
+
+    #[test]
+    fn quick_play_arguments_follow_the_version() {
+        let world = QuickPlay::Singleplayer {
+            world: "New World".to_string(),
+        };
+        let server = QuickPlay::Multiplayer {
+            address: "mc.example.com:25570".to_string(),
+        };
+        let default_port = QuickPlay::Multiplayer {
+            address: "mc.example.com".to_string(),
+        };
+        let realm = QuickPlay::Realms {
+            id: "1234".to_string(),
+        };
+        let legacy = |arguments: &str| -> GameVersion {
+            serde_json::from_value(serde_json::json!({
+                "id": "legacy",
+                "mainClass": "net.minecraft.client.main.Main",
+                "minecraftArguments": arguments,
+                "libraries": []
+            }))
+            .unwrap()
+        };
+        let log = Path::new("/mc").join("quickPlay").join("log.json");
+        let log = log.to_string_lossy();
+
+        // 1.20 and later: the arguments of the version JSON
+        let modern: GameVersion = serde_json::from_str(VANILLA_1_20_4).unwrap();
+        assert_eq!(
+            quick_play_args(&modern, &world),
+            (
+                strings(&[
+                    "--quickPlayPath",
+                    &log,
+                    "--quickPlaySingleplayer",
+                    "New World"
+                ]),
+                false
+            )
+        );
+        assert_eq!(
+            quick_play_args(&modern, &server),
+            (
+                strings(&[
+                    "--quickPlayPath",
+                    &log,
+                    "--quickPlayMultiplayer",
+                    "mc.example.com:25570"
+                ]),
+                false
+            )
+        );
+        assert_eq!(
+            quick_play_args(&modern, &realm),
+            (
+                strings(&["--quickPlayPath", &log, "--quickPlayRealms", "1234"]),
+                false
+            )
+        );
+
+        // 1.6 to 1.19: servers only
+        let pre_quick_play: GameVersion = serde_json::from_str(VANILLA_1_16_5).unwrap();
+        let options = legacy(LEGACY_ARGUMENTS[0]);
+        for version in [&pre_quick_play, &options] {
+            assert_eq!(
+                quick_play_args(version, &server),
+                (
+                    strings(&["--server", "mc.example.com", "--port", "25570"]),
+                    false
+                )
+            );
+            assert_eq!(
+                quick_play_args(version, &default_port),
+                (
+                    strings(&["--server", "mc.example.com", "--port", "25565"]),
+                    false
+                )
+            );
+            assert_eq!(quick_play_args(version, &world), (Vec::new(), true));
+            assert_eq!(quick_play_args(version, &realm), (Vec::new(), true));
+        }
+
+        // Before 1.6: nothing
+        let positional = legacy(LEGACY_ARGUMENTS[1]);
+        for target in [&world, &server, &realm] {
+            assert_eq!(quick_play_args(&positional, target), (Vec::new(), true));
+        }
+    }
+
+    #[test]
+    fn invalid_legacy_server_address_fails_the_launch() {
+        let version: GameVersion = serde_json::from_str(VANILLA_1_16_5).unwrap();
+        let account = account();
+        let server = QuickPlay::Multiplayer {
+            address: "mc.example.com:port".to_string(),
+        };
+        let launch = GameLaunchContext {
+            quick_play: Some(&server),
+            ..launch(&account, Path::new("/mc"), Path::new("/mc/assets"))
+        };
+        let err = build_game_args(&version, &launch, &context(&[])).unwrap_err();
+        assert_eq!(err, "Invalid server address \"mc.example.com:port\"");
+    }
+}
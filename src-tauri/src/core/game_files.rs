@@ -0,0 +1,239 @@
+//! Files a version needs on disk before it can launch: the client jar and the
+//! libraries that apply to this machine.
+//!
+//! Libraries disallowed by their OS rules are left out entirely; everything else goes
+//! through `download_files`, which skips files that already match their checksum.
+
+use std::path::{Path, PathBuf};
+use tauri::Window;
+
+use crate::core::config::LauncherConfig;
+use crate::core::downloader::{
+    self, DownloadBatchReport, DownloadTask, PRIORITY_CLIENT_JAR, PRIORITY_LIBRARY,
+};
+use crate::core::game_version::{DownloadArtifact, GameVersion, Library};
+use crate::core::rules::{self, RuleContext};
+use crate::core::{maven, natives};
+use crate::utils::error;
+
+/// Build the tasks for the client jar at `client_path` and every library that applies
+/// to this machine, stored under `libraries_dir` in the maven layout
+pub fn client_and_library_tasks(
+    version: &GameVersion,
+    client_path: &Path,
+    libraries_dir: &Path,
+    rule_context: &RuleContext,
+) -> Result<Vec<DownloadTask>, String> {
+    let client_jar = &version
+        .downloads
+        .as_ref()
+        .ok_or("Version has no downloads information")?
+        .client;
+
+    let mut tasks = vec![DownloadTask {
+        priority: PRIORITY_CLIENT_JAR,
+        ..artifact_task(client_jar, client_path.to_path_buf())
+    }];
+    tasks.extend(library_tasks(
+        &version.libraries,
+        libraries_dir,
+        rule_context,
+    ));
+    Ok(tasks)
+}
+
+/// Build the tasks for every library in `libraries` that applies to this machine
+pub fn library_tasks(
+    libraries: &[Library],
+    libraries_dir: &Path,
+    rule_context: &RuleContext,
+) -> Vec<DownloadTask> {
+    let mut tasks = Vec::new();
+    for lib in libraries {
+        if rules::is_library_allowed(&lib.rules, rule_context) {
+            add_library(&mut tasks, lib, libraries_dir);
+        }
+    }
+    tasks
+}
+
+fn add_library(tasks: &mut Vec<DownloadTask>, lib: &Library, libraries_dir: &Path) {
+    let Some(downloads) = &lib.downloads else {
+        // Mod loader libraries only give a maven coordinate (and maybe a repository)
+        if let (Some(url), Some(path)) = (
+            maven::resolve_library_url(&lib.name, None, lib.url.as_deref()),
+            maven::get_library_path(&lib.name, libraries_dir),
+        ) {
+            tasks.push(DownloadTask {
+                url,
+                path,
+                // Maven libraries often don't have SHA1 in the JSON
+                sha1: None,
+                sha256: None,
+                sha512: None,
+                expected_size: None,
+                fallback_urls: Vec::new(),
+                priority: PRIORITY_LIBRARY,
+            });
+        }
+        return;
+    };
+
+    // Without a URL the artifact is made by the loader installer, e.g. Forge's
+    // patched client
+    if let Some(artifact) = downloads.artifact.as_ref().filter(|a| !a.url.is_empty()) {
+        let path = artifact
+            .path
+            .clone()
+            .unwrap_or_else(|| format!("{}.jar", lib.name));
+        tasks.push(artifact_task(artifact, libraries_dir.join(path)));
+    }
+
+    // Natives of older versions, e.g. "natives-linux": { ... }
+    if let Some(native) = natives::native_artifact(lib) {
+        let path = natives::native_artifact_path(lib, &native, libraries_dir);
+        tasks.push(artifact_task(&native, path));
+    }
+}
+
+fn artifact_task(artifact: &DownloadArtifact, path: PathBuf) -> DownloadTask {
+    DownloadTask {
+        url: artifact.url.clone(),
+        path,
+        sha1: artifact.sha1.clone(),
+        sha256: None,
+        sha512: None,
+        expected_size: artifact.size,
+        fallback_urls: Vec::new(),
+        priority: PRIORITY_LIBRARY,
+    }
+}
+
+/// Download `tasks` as one batch with the download settings of `config`.
+///
+/// The report is returned as is so the caller can refuse to launch when something is
+/// missing.
+pub async fn download_with_settings(
+    window: Window,
+    tasks: Vec<DownloadTask>,
+    config: &LauncherConfig,
+) -> Result<DownloadBatchReport, String> {
+    downloader::download_files(
+        window,
+        tasks,
+        config.download_threads as usize,
+        config.download_verify_retries,
+        downloader::RetryPolicy::with_attempts(config.download_max_attempts).with_stall_timeout(
+            std::time::Duration::from_secs(config.download_stall_timeout_secs.into()),
+        ),
+    )
+    .await
+    .map_err(|e| error::display_chain(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::downloader::{
+        DownloadController, RateLimiter, RetryPolicy, compute_sha1, run_download_tasks,
+    };
+    use crate::utils::mock_server::{MockServer, Response};
+    use crate::utils::test_dir::TestDir;
+    use std::collections::HashMap;
+
+    /// Serves `files` by request path and 404s everything else
+    async fn spawn_server(files: HashMap<String, Vec<u8>>) -> MockServer {
+        let mut server = MockServer::builder();
+        for (path, body) in files {
+            server = server.route(&path, move |_| Response::ok(body.clone()));
+        }
+        server.start().await
+    }
+
+    fn jar(seed: u8) -> Vec<u8> {
+        let mut body: Vec<u8> = (0..4096u32).map(|i| (i as u8).wrapping_mul(seed)).collect();
+        body[..4].copy_from_slice(b"PK\x03\x04");
+        body
+    }
+
+    #[tokio::test]
+    async fn downloads_client_and_applicable_libraries() {
+        let client = jar(3);
+        let gson = jar(5);
+        let loader = jar(7);
+        let other_os = jar(11);
+        let server = spawn_server(HashMap::from([
+            ("/client.jar".to_string(), client.clone()),
+            ("/gson-2.10.1.jar".to_string(), gson.clone()),
+            (
+                "/maven/net/fabricmc/fabric-loader/0.15.7/fabric-loader-0.15.7.jar".to_string(),
+                loader.clone(),
+            ),
+            ("/other-os.jar".to_string(), other_os.clone()),
+        ]))
+        .await;
+        let base = server.url("");
+
+        let version: GameVersion = serde_json::from_value(serde_json::json!({
+            "id": "fabric-loader-0.15.7-1.20.4",
+            "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+            "downloads": {"client": {"url": format!("{}/client.jar", base), "sha1": compute_sha1(&client), "size": client.len()}},
+            "libraries": [
+                {"name": "com.google.code.gson:gson:2.10.1", "downloads": {"artifact": {
+                    "path": "com/google/code/gson/gson/2.10.1/gson-2.10.1.jar",
+                    "url": format!("{}/gson-2.10.1.jar", base), "sha1": compute_sha1(&gson), "size": gson.len()
+                }}},
+                {"name": "net.fabricmc:fabric-loader:0.15.7", "url": format!("{}/maven/", base)},
+                {"name": "com.example:other-os:1.0", "rules": [{"action": "allow", "os": {"name": "no-such-os"}}], "downloads": {"artifact": {
+                    "path": "com/example/other-os/1.0/other-os-1.0.jar",
+                    "url": format!("{}/other-os.jar", base), "sha1": compute_sha1(&other_os), "size": other_os.len()
+                }}}
+            ]
+        }))
+        .unwrap();
+
+        let dir = TestDir::new("game-files");
+        let client_path = dir.path().join("versions/1.20.4/1.20.4.jar");
+        let libraries_dir = dir.path().join("libraries");
+        let tasks = client_and_library_tasks(
+            &version,
+            &client_path,
+            &libraries_dir,
+            &RuleContext::current(),
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[0].priority, PRIORITY_CLIENT_JAR);
+
+        // Already downloaded and valid, so only checked
+        let gson_path = libraries_dir.join("com/google/code/gson/gson/2.10.1/gson-2.10.1.jar");
+        std::fs::create_dir_all(gson_path.parent().unwrap()).unwrap();
+        std::fs::write(&gson_path, &gson).unwrap();
+
+        let report = run_download_tasks(
+            &crate::core::http::client(),
+            tasks,
+            4,
+            0,
+            &RetryPolicy::with_attempts(1),
+            &RateLimiter::default(),
+            &DownloadController::default(),
+            |_| {},
+        )
+        .await;
+
+        assert!(report.is_success(), "{:?}", report.failed);
+        assert_eq!(report.succeeded.len(), 2);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(std::fs::read(&client_path).unwrap(), client);
+        assert_eq!(
+            std::fs::read(
+                libraries_dir.join("net/fabricmc/fabric-loader/0.15.7/fabric-loader-0.15.7.jar")
+            )
+            .unwrap(),
+            loader
+        );
+        assert_eq!(server.hits("/other-os.jar"), 0);
+        assert_eq!(server.hits("/gson-2.10.1.jar"), 0);
+    }
+}
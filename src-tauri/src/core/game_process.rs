@@ -0,0 +1,727 @@
+//! Running game processes: spawning Java, forwarding its output and keeping track of
+//! which instance runs what.
+//!
+//! Output is read line by line from both pipes and sent to the frontend as `game-log`
+//! events in batches, since a game printing thousands of lines at startup would
+//! otherwise flood the webview with one event per line.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{Mutex as AsyncMutex, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+use ts_rs::TS;
+
+use crate::core::crash::{self, CrashHint};
+use crate::core::hooks::Hook;
+use crate::core::java::JavaInstallation;
+use crate::core::launch_history::{self, LaunchRecord};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// How often buffered output lines are sent to the frontend
+const LOG_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+/// Send a batch early once it has this many lines
+const LOG_BATCH_SIZE: usize = 256;
+/// Output lines kept per game for looking into a crash
+const LOG_TAIL_LINES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "game_process.ts")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// One line of game output, sent in batches as `game-log`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "game_process.ts")]
+pub struct GameLogEvent {
+    pub instance_id: String,
+    pub stream: LogStream,
+    pub line: String,
+}
+
+/// What `start_game` returns once the game process is running
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "game_process.ts")]
+pub struct LaunchHandle {
+    pub instance_id: String,
+    /// `None` if the process exited before its id could be read
+    pub pid: Option<u32>,
+}
+
+/// The last [`LOG_TAIL_LINES`] lines a game printed
+#[derive(Debug, Clone, Default)]
+pub struct LogTail(Arc<Mutex<VecDeque<String>>>);
+
+impl LogTail {
+    pub fn push(&self, line: &str) {
+        let mut lines = self.0.lock().unwrap();
+        if lines.len() == LOG_TAIL_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+
+    pub fn text(&self) -> String {
+        let lines = self.0.lock().unwrap();
+        lines
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub struct RunningGame {
+    pub child: Child,
+    pub version_id: String,
+    pub started_at: SystemTime,
+    /// Instance directory, where the launch history is kept
+    pub instance_dir: PathBuf,
+    /// Directory the game runs in
+    pub game_dir: PathBuf,
+    /// Extracted natives of this launch, removed once the game exits
+    pub natives_dir: PathBuf,
+    /// Java the launch resolved and started the game with
+    pub java: JavaInstallation,
+    pub log_tail: LogTail,
+    /// Hook to run once the game has ended
+    pub post_exit: Option<Hook>,
+}
+
+/// State of a registered game process, see [`GameProcessState::poll`]
+pub enum GameStatus {
+    /// No process is registered for the instance (anymore)
+    NotRunning,
+    Running,
+    /// The process exited and was unregistered
+    Exited(RunningGame, ExitStatus),
+    /// The process couldn't be checked and was unregistered
+    Failed(RunningGame, String),
+}
+
+/// How a game process ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "game_process.ts")]
+pub enum ExitKind {
+    /// Exit code 0
+    Clean,
+    /// Non-zero exit code, or the exit couldn't be determined
+    Crashed,
+    /// Ended by a signal, e.g. killed by the OS or by stopping it
+    Killed,
+}
+
+impl ExitKind {
+    fn of(status: Option<&ExitStatus>) -> Self {
+        match status.map(|status| status.code()) {
+            Some(Some(0)) => ExitKind::Clean,
+            Some(None) => ExitKind::Killed,
+            _ => ExitKind::Crashed,
+        }
+    }
+}
+
+/// Sent as `game-exited` once a game process has ended
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "game_process.ts")]
+pub struct GameExitedEvent {
+    pub instance_id: String,
+    pub version_id: String,
+    pub exit_code: Option<i32>,
+    pub kind: ExitKind,
+    /// Stopped from the launcher rather than exiting by itself
+    pub was_stopped: bool,
+    #[ts(type = "number")]
+    pub duration_secs: u64,
+    /// Crash report the game wrote during this launch
+    pub crash_report_path: Option<String>,
+    /// Likely causes of a crash, empty for clean or stopped exits
+    pub hints: Vec<CrashHint>,
+}
+
+/// Game processes by instance id
+pub struct GameProcessState {
+    running: AsyncMutex<HashMap<String, RunningGame>>,
+}
+
+impl Default for GameProcessState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameProcessState {
+    pub fn new() -> Self {
+        Self {
+            running: AsyncMutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn register(&self, instance_id: &str, game: RunningGame) {
+        self.running
+            .lock()
+            .await
+            .insert(instance_id.to_string(), game);
+    }
+
+    /// Check on the game of `instance_id`, unregistering it once it has exited
+    pub async fn poll(&self, instance_id: &str) -> GameStatus {
+        let mut running = self.running.lock().await;
+        let Some(game) = running.get_mut(instance_id) else {
+            return GameStatus::NotRunning;
+        };
+        let status = match game.child.try_wait() {
+            Ok(None) => return GameStatus::Running,
+            Ok(Some(status)) => Ok(status),
+            Err(e) => Err(e.to_string()),
+        };
+        let game = running
+            .remove(instance_id)
+            .expect("game was just looked up");
+        match status {
+            Ok(status) => GameStatus::Exited(game, status),
+            Err(e) => GameStatus::Failed(game, e),
+        }
+    }
+
+    /// Unregister the game of `instance_id` so the caller can stop it
+    pub async fn take(&self, instance_id: &str) -> Option<RunningGame> {
+        self.running.lock().await.remove(instance_id)
+    }
+
+    /// Called when the launcher exits: kill every game, or leave them running
+    /// detached from the launcher
+    pub async fn shutdown(&self, keep_running: bool) {
+        let games = std::mem::take(&mut *self.running.lock().await);
+        for (instance_id, mut game) in games {
+            if keep_running {
+                continue;
+            }
+            if let Err(e) = game.child.start_kill() {
+                eprintln!("Failed to stop the game of instance {}: {}", instance_id, e);
+            }
+        }
+    }
+}
+
+/// Slack for modification times, which are coarser than the launch time (2 seconds
+/// on FAT drives)
+const MTIME_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// Newest file in `crash-reports/` of `game_dir` written since `since`
+pub fn find_crash_report(game_dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    let since = since.checked_sub(MTIME_TOLERANCE).unwrap_or(since);
+    std::fs::read_dir(game_dir.join("crash-reports"))
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            (metadata.is_file() && modified >= since).then(|| (modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Wrap up a game that has ended: remove its natives, look for a crash report and
+/// record launches that didn't end cleanly in the instance's launch history, along
+/// with hints on what went wrong.
+///
+/// `status` is `None` when the exit couldn't be determined.
+pub fn finish_game(
+    instance_id: &str,
+    game: &RunningGame,
+    status: Option<&ExitStatus>,
+    was_stopped: bool,
+) -> GameExitedEvent {
+    if let Err(e) = std::fs::remove_dir_all(&game.natives_dir)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        log::warn!(
+            "Failed to remove natives directory {}: {}",
+            game.natives_dir.display(),
+            e
+        );
+    }
+
+    let mut event = GameExitedEvent {
+        instance_id: instance_id.to_string(),
+        version_id: game.version_id.clone(),
+        exit_code: status.and_then(|status| status.code()),
+        kind: ExitKind::of(status),
+        was_stopped,
+        duration_secs: game
+            .started_at
+            .elapsed()
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0),
+        crash_report_path: find_crash_report(&game.game_dir, game.started_at)
+            .map(|path| path.to_string_lossy().into_owned()),
+        hints: Vec::new(),
+    };
+
+    if event.kind != ExitKind::Clean && !was_stopped {
+        let report = event
+            .crash_report_path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok());
+        event.hints = crash::analyze(&game.log_tail.text(), report.as_deref());
+
+        let record = LaunchRecord {
+            finished_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            version_id: event.version_id.clone(),
+            exit_code: event.exit_code,
+            kind: event.kind,
+            duration_secs: event.duration_secs,
+            java: game.java.clone(),
+            crash_report_path: event.crash_report_path.clone(),
+        };
+        if let Err(e) = launch_history::record(&game.instance_dir, record) {
+            log::warn!("Failed to save launch history of {}: {}", instance_id, e);
+        }
+    }
+    event
+}
+
+/// The executable to start the game with. On Windows `javaw.exe` is used when it sits
+/// next to `java.exe`, so no console is attached; debug builds keep `java.exe`.
+pub fn game_executable(java_path: &str) -> PathBuf {
+    let java = PathBuf::from(java_path);
+    if !cfg!(target_os = "windows") || cfg!(debug_assertions) {
+        return java;
+    }
+    let is_java = java
+        .file_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case("java.exe"));
+    let javaw = java.with_file_name("javaw.exe");
+    if is_java && javaw.is_file() {
+        javaw
+    } else {
+        java
+    }
+}
+
+/// Command running `java` with `args` in `game_dir`, with output piped.
+///
+/// With `kill_on_drop` the game is killed when its handle is dropped; otherwise it
+/// keeps running after the launcher exits.
+pub fn game_command(java: &Path, args: &[String], game_dir: &Path, kill_on_drop: bool) -> Command {
+    let mut command = Command::new(java);
+    command
+        .args(args)
+        .current_dir(game_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(kill_on_drop);
+
+    // Hide the console window of java.exe
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+fn read_lines<R>(
+    reader: R,
+    instance_id: String,
+    stream: LogStream,
+    tx: mpsc::UnboundedSender<GameLogEvent>,
+) where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        // Java writes in the system code page, which on Windows is often not UTF-8,
+        // so bytes that are not UTF-8 are replaced rather than ending the stream
+        let mut reader = BufReader::new(reader);
+        let mut buf = Vec::new();
+        while let Ok(read) = reader.read_until(b'\n', &mut buf).await {
+            if read == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buf)
+                .trim_end_matches(['\r', '\n'])
+                .to_string();
+            buf.clear();
+            let event = GameLogEvent {
+                instance_id: instance_id.clone(),
+                stream,
+                line,
+            };
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Forward the output of a game line by line until both streams close.
+///
+/// `on_line` sees every line as it arrives; `emit` receives them in batches, at most
+/// every [`LOG_FLUSH_INTERVAL`] unless a batch fills up first.
+pub fn forward_output<O, E>(
+    instance_id: &str,
+    stdout: O,
+    stderr: E,
+    mut on_line: impl FnMut(&GameLogEvent) + Send + 'static,
+    emit: impl Fn(Vec<GameLogEvent>) + Send + 'static,
+) -> JoinHandle<()>
+where
+    O: AsyncRead + Unpin + Send + 'static,
+    E: AsyncRead + Unpin + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    read_lines(
+        stdout,
+        instance_id.to_string(),
+        LogStream::Stdout,
+        tx.clone(),
+    );
+    read_lines(stderr, instance_id.to_string(), LogStream::Stderr, tx);
+
+    tokio::spawn(async move {
+        let mut batch = Vec::new();
+        let mut flush = tokio::time::interval(LOG_FLUSH_INTERVAL);
+        loop {
+            tokio::select! {
+                event = rx.recv() => {
+                    let Some(event) = event else { break };
+                    on_line(&event);
+                    batch.push(event);
+                    if batch.len() >= LOG_BATCH_SIZE {
+                        emit(std::mem::take(&mut batch));
+                    }
+                }
+                _ = flush.tick() => {
+                    if !batch.is_empty() {
+                        emit(std::mem::take(&mut batch));
+                    }
+                }
+            }
+        }
+        if !batch.is_empty() {
+            emit(batch);
+        }
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::core::crash::CrashHintKind;
+    use crate::utils::test_dir::TestDir;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn running(child: Child, game_dir: &Path) -> RunningGame {
+        RunningGame {
+            child,
+            version_id: "1.20.4".to_string(),
+            started_at: SystemTime::now(),
+            instance_dir: game_dir.to_path_buf(),
+            game_dir: game_dir.to_path_buf(),
+            natives_dir: game_dir.join("natives"),
+            java: JavaInstallation {
+                path: "/usr/bin/java".to_string(),
+                version: "17.0.10".to_string(),
+                arch: "x64".to_string(),
+                vendor: "Eclipse Adoptium".to_string(),
+                source: "system".to_string(),
+                is_64bit: true,
+                is_native_arch: true,
+                blocked_reason: None,
+            },
+            log_tail: LogTail::default(),
+            post_exit: None,
+        }
+    }
+
+    /// A shell script standing in for java: prints its arguments and working
+    /// directory, then a burst of lines on both streams
+    fn fake_java(dir: &Path) -> PathBuf {
+        let script = dir.join("java");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\n\
+             echo \"args: $*\"\n\
+             echo \"cwd: $(pwd)\"\n\
+             i=0\n\
+             while [ $i -lt 600 ]; do echo \"line $i\"; i=$((i + 1)); done\n\
+             echo 'oops' >&2\n\
+             exit 3\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    #[tokio::test]
+    async fn spawns_the_game_and_batches_its_output() {
+        let dir = TestDir::new("game-process");
+        let game_dir = dir.path().join("game");
+        std::fs::create_dir_all(&game_dir).unwrap();
+        let java = fake_java(dir.path());
+
+        let args = vec![
+            "-Xmx2048M".to_string(),
+            "net.minecraft.client.main.Main".to_string(),
+        ];
+        let mut child = game_command(&java, &args, &game_dir, true).spawn().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let state = GameProcessState::new();
+        state.register("instance", running(child, &game_dir)).await;
+
+        let seen = Arc::new(Mutex::new(0));
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let (seen_lines, received) = (seen.clone(), batches.clone());
+        forward_output(
+            "instance",
+            stdout,
+            stderr,
+            move |_| *seen_lines.lock().unwrap() += 1,
+            move |batch| received.lock().unwrap().push(batch),
+        )
+        .await
+        .unwrap();
+
+        let batches = std::mem::take(&mut *batches.lock().unwrap());
+        assert!(batches.len() > 1, "600 lines should not fit one batch");
+        assert!(batches.iter().all(|batch| batch.len() <= LOG_BATCH_SIZE));
+        let events: Vec<&GameLogEvent> = batches.iter().flatten().collect();
+        assert_eq!(events.len(), 603);
+        assert_eq!(*seen.lock().unwrap(), 603);
+        assert!(events.iter().all(|event| event.instance_id == "instance"));
+
+        let stdout: Vec<&str> = events
+            .iter()
+            .filter(|event| event.stream == LogStream::Stdout)
+            .map(|event| event.line.as_str())
+            .collect();
+        assert_eq!(stdout[0], "args: -Xmx2048M net.minecraft.client.main.Main");
+        assert_eq!(
+            stdout[1],
+            format!("cwd: {}", game_dir.canonicalize().unwrap().display())
+        );
+        assert_eq!(stdout[2], "line 0");
+        assert_eq!(stdout[601], "line 599");
+        let stderr: Vec<&GameLogEvent> = events
+            .iter()
+            .copied()
+            .filter(|event| event.stream == LogStream::Stderr)
+            .collect();
+        assert_eq!(stderr.len(), 1);
+        assert_eq!(stderr[0].line, "oops");
+
+        // The process is unregistered once its exit is seen
+        let mut status = state.poll("instance").await;
+        while matches!(status, GameStatus::Running) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            status = state.poll("instance").await;
+        }
+        assert!(matches!(status, GameStatus::Exited(_, status) if status.code() == Some(3)));
+        assert!(matches!(
+            state.poll("instance").await,
+            GameStatus::NotRunning
+        ));
+    }
+
+    #[tokio::test]
+    async fn output_that_is_not_utf8_keeps_the_stream_going() {
+        // "Steve" then "史蒂夫" in GBK, as Java writes it on a Chinese Windows
+        let stdout = std::io::Cursor::new(
+            b"[Render thread/INFO]: Setting user: Steve\r\n\xca\xb7\xb5\xd9\xb7\xf2\r\nline 2\nlast".to_vec(),
+        );
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let received = lines.clone();
+        forward_output(
+            "instance",
+            stdout,
+            tokio::io::empty(),
+            |_| {},
+            move |batch| {
+                received
+                    .lock()
+                    .unwrap()
+                    .extend(batch.into_iter().map(|event| event.line))
+            },
+        )
+        .await
+        .unwrap();
+
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "[Render thread/INFO]: Setting user: Steve");
+        assert!(
+            lines[1].contains(char::REPLACEMENT_CHARACTER),
+            "{}",
+            lines[1]
+        );
+        assert_eq!(lines[2..], ["line 2", "last"]);
+    }
+
+    /// Run `script` as the game in `game_dir` and wait for its exit
+    async fn run_to_exit(game_dir: &Path, script: &str) -> (RunningGame, ExitStatus) {
+        let child = game_command(
+            Path::new("sh"),
+            &["-c".to_string(), script.to_string()],
+            game_dir,
+            true,
+        )
+        .spawn()
+        .unwrap();
+        let state = GameProcessState::new();
+        state.register("instance", running(child, game_dir)).await;
+        loop {
+            match state.poll("instance").await {
+                GameStatus::Running => tokio::time::sleep(Duration::from_millis(10)).await,
+                GameStatus::Exited(game, status) => return (game, status),
+                _ => panic!("game should exit normally"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn classifies_exits_and_records_crashes() {
+        let dir = TestDir::new("game-exit");
+        let game_dir = dir.path().join("instance");
+        std::fs::create_dir_all(game_dir.join("crash-reports")).unwrap();
+        // Written long before the launch, so not this launch's report
+        let old = game_dir.join("crash-reports/crash-old.txt");
+        std::fs::write(&old, "old").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+
+        std::fs::create_dir_all(game_dir.join("natives")).unwrap();
+        let (game, status) = run_to_exit(&game_dir, "exit 0").await;
+        let event = finish_game("instance", &game, Some(&status), false);
+        assert_eq!(event.kind, ExitKind::Clean);
+        assert_eq!(event.exit_code, Some(0));
+        assert_eq!(event.crash_report_path, None);
+        assert!(!game_dir.join("natives").exists());
+        assert!(event.hints.is_empty());
+        assert!(launch_history::entries(&game_dir).is_empty());
+
+        let (game, status) = run_to_exit(
+            &game_dir,
+            "echo 'java.lang.OutOfMemoryError: Java heap space' \
+             > crash-reports/crash-2024-01-02_03.04.05-client.txt; exit 255",
+        )
+        .await;
+        game.log_tail
+            .push("Unrecognized VM option 'UseConcMarkSweepGC'");
+        let event = finish_game("instance", &game, Some(&status), false);
+        assert_eq!(event.kind, ExitKind::Crashed);
+        assert_eq!(event.exit_code, Some(255));
+        let report = game_dir
+            .join("crash-reports/crash-2024-01-02_03.04.05-client.txt")
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(event.crash_report_path.as_deref(), Some(report.as_str()));
+        let kinds: Vec<CrashHintKind> = event.hints.iter().map(|hint| hint.kind).collect();
+        assert_eq!(
+            kinds,
+            [CrashHintKind::BadJvmOption, CrashHintKind::OutOfMemory]
+        );
+
+        let (game, status) = run_to_exit(&game_dir, "kill -9 $$").await;
+        let event = finish_game("instance", &game, Some(&status), false);
+        assert_eq!(event.kind, ExitKind::Killed);
+        assert_eq!(event.exit_code, None);
+
+        // Stopped from the launcher: reported, but not a crash worth remembering
+        let (game, status) = run_to_exit(&game_dir, "exit 1").await;
+        assert!(finish_game("instance", &game, Some(&status), true).was_stopped);
+
+        let history = launch_history::entries(&game_dir);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].kind, ExitKind::Killed);
+        assert_eq!(history[1].kind, ExitKind::Crashed);
+        assert_eq!(history[1].exit_code, Some(255));
+        assert_eq!(history[1].java.path, "/usr/bin/java");
+        assert_eq!(
+            history[1].crash_report_path.as_deref(),
+            Some(report.as_str())
+        );
+    }
+
+    /// Whether `pid` runs and isn't a zombie waiting to be reaped
+    #[cfg(target_os = "linux")]
+    fn is_alive(pid: u32) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| {
+                !stat
+                    .rsplit(')')
+                    .next()
+                    .unwrap_or("")
+                    .trim_start()
+                    .starts_with('Z')
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn shutdown_kills_games_unless_kept_running() {
+        let sleep = || {
+            game_command(
+                Path::new("sleep"),
+                &["30".to_string()],
+                Path::new("/"),
+                false,
+            )
+            .spawn()
+            .unwrap()
+        };
+        let state = GameProcessState::new();
+
+        let kept = sleep();
+        let kept_pid = kept.id().unwrap();
+        state
+            .register("kept", running(kept, Path::new("/nonexistent")))
+            .await;
+        state.shutdown(true).await;
+        assert!(matches!(state.poll("kept").await, GameStatus::NotRunning));
+        assert!(is_alive(kept_pid));
+
+        let killed = sleep();
+        let killed_pid = killed.id().unwrap();
+        state
+            .register("killed", running(killed, Path::new("/nonexistent")))
+            .await;
+        state.shutdown(false).await;
+        let mut waited = 0;
+        while is_alive(killed_pid) && waited < 100 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            waited += 1;
+        }
+        assert!(!is_alive(killed_pid));
+
+        let _ = std::process::Command::new("kill")
+            .arg(kept_pid.to_string())
+            .status();
+    }
+}
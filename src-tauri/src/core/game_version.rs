@@ -67,6 +67,17 @@ pub struct Library {
     pub natives: Option<serde_json::Value>,
     /// Maven repository URL for mod loader libraries
     pub url: Option<String>,
+    /// What to leave out when extracting the native jar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract: Option<ExtractRules>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, TS)]
+#[ts(export, export_to = "game-version.ts")]
+pub struct ExtractRules {
+    /// Entry name prefixes to skip, e.g. `META-INF/`
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, TS)]
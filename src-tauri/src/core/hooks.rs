@@ -0,0 +1,531 @@
+//! Commands an instance runs around a game session: a pre-launch hook right before
+//! Java is started and a post-exit hook once the game has ended, however it ended.
+//!
+//! A command is split into words the way a shell would, but run without one unless
+//! the instance opts in with `use_shell`. Hooks run in the instance directory with
+//! `INST_*` variables describing the launch, and their output goes to the instance
+//! log like the game's own.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+use crate::core::game_process::{self, GameLogEvent, LogTail};
+use crate::core::instance::Instance;
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// How long a pre-launch hook may run before it is stopped and the launch fails
+pub const PRE_LAUNCH_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How long a post-exit hook may run before it is stopped
+pub const POST_EXIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Lines of hook output kept for the error of a failed hook
+const HOOK_LOG_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    PreLaunch,
+    PostExit,
+}
+
+impl HookKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::PreLaunch => "pre-launch",
+            Self::PostExit => "post-exit",
+        }
+    }
+}
+
+/// What the `INST_*` variables of a hook are set to
+pub struct HookContext<'a> {
+    pub instance_name: &'a str,
+    pub instance_id: &'a str,
+    pub instance_dir: &'a Path,
+    /// Directory the game runs in
+    pub game_dir: &'a Path,
+    pub java: &'a str,
+    /// Minecraft version, without the mod loader
+    pub mc_version: &'a str,
+}
+
+impl<'a> HookContext<'a> {
+    /// The context of launching `instance` with `java`, on `minecraft_version`
+    pub fn for_launch(
+        instance: &'a Instance,
+        instance_dir: &'a Path,
+        game_dir: &'a Path,
+        java: &'a str,
+        minecraft_version: &'a str,
+    ) -> Self {
+        Self {
+            instance_name: &instance.name,
+            instance_id: &instance.id,
+            instance_dir,
+            game_dir,
+            java,
+            mc_version: minecraft_version,
+        }
+    }
+}
+
+/// A hook command, ready to run
+#[derive(Debug, Clone)]
+pub struct Hook {
+    pub kind: HookKind,
+    pub command: String,
+    pub use_shell: bool,
+    dir: PathBuf,
+    env: Vec<(&'static str, String)>,
+}
+
+impl Hook {
+    /// The hook of `kind` running `command`, or `None` when there is nothing to run
+    pub fn new(
+        kind: HookKind,
+        command: Option<&str>,
+        use_shell: bool,
+        context: &HookContext,
+    ) -> Option<Self> {
+        let command = command.map(str::trim).filter(|c| !c.is_empty())?;
+        let path = |path: &Path| path.to_string_lossy().into_owned();
+        Some(Self {
+            kind,
+            command: command.to_string(),
+            use_shell,
+            dir: context.instance_dir.to_path_buf(),
+            env: vec![
+                ("INST_NAME", context.instance_name.to_string()),
+                ("INST_ID", context.instance_id.to_string()),
+                ("INST_DIR", path(context.instance_dir)),
+                ("INST_MC_DIR", path(context.game_dir)),
+                ("INST_JAVA", context.java.to_string()),
+                ("INST_MC_VERSION", context.mc_version.to_string()),
+            ],
+        })
+    }
+
+    fn process(&self) -> Result<Command, String> {
+        let mut command = if self.use_shell {
+            shell_command(&self.command)
+        } else {
+            let words = split_command(&self.command)?;
+            let (program, args) = words
+                .split_first()
+                .ok_or_else(|| format!("The {} hook command is empty", self.kind.name()))?;
+            // A relative path such as `./sync.sh` is taken from the instance directory
+            let program = Path::new(program);
+            let program = if program.is_relative() && program.components().count() > 1 {
+                self.dir.join(program)
+            } else {
+                program.to_path_buf()
+            };
+            let mut command = Command::new(program);
+            command.args(args);
+            command
+        };
+        command
+            .current_dir(&self.dir)
+            .envs(self.env.iter().map(|(name, value)| (name, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(target_os = "windows")]
+        command.creation_flags(0x08000000);
+        Ok(command)
+    }
+
+    /// Run the hook to the end, sending its output to `emit` as log events of
+    /// `instance_id`. With a `timeout` the hook is stopped once it runs longer.
+    pub async fn run(
+        &self,
+        instance_id: &str,
+        timeout: Option<Duration>,
+        emit: impl Fn(Vec<GameLogEvent>) + Send + 'static,
+    ) -> Result<(), String> {
+        let name = self.kind.name();
+        let mut child = self
+            .process()?
+            .spawn()
+            .map_err(|e| format!("The {} hook could not be started: {}", name, e))?;
+
+        let log = LogTail::default();
+        let log_lines = log.clone();
+        let output = game_process::forward_output(
+            instance_id,
+            child.stdout.take().expect("hook stdout is piped"),
+            child.stderr.take().expect("hook stderr is piped"),
+            move |event| log_lines.push(&event.line),
+            emit,
+        );
+
+        let status = match timeout {
+            Some(limit) => match tokio::time::timeout(limit, child.wait()).await {
+                Ok(status) => status,
+                Err(_) => {
+                    let _ = child.kill().await;
+                    return Err(format!(
+                        "The {} hook did not finish within {}s and was stopped",
+                        name,
+                        limit.as_secs_f32()
+                    ));
+                }
+            },
+            None => child.wait().await,
+        }
+        .map_err(|e| format!("Failed to wait for the {} hook: {}", name, e))?;
+
+        // Something the hook started in the background may keep its output open
+        let _ = tokio::time::timeout(Duration::from_secs(2), output).await;
+        if !status.success() {
+            let log = log.text();
+            let lines: Vec<&str> = log.lines().collect();
+            return Err(format!(
+                "The {} hook `{}` failed with {}\n{}",
+                name,
+                self.command,
+                status,
+                lines[lines.len().saturating_sub(HOOK_LOG_LINES)..].join("\n")
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn shell_command(line: &str) -> Command {
+    let mut command = Command::new("cmd");
+    command.arg("/C").raw_arg(line);
+    command
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_command(line: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(line);
+    command
+}
+
+/// Split `command` into words like a shell: `'single'` quotes are literal, `"double"`
+/// quotes allow `\"` and `\\`, and outside quotes `\` escapes a space, quote or
+/// backslash. Any other backslash is kept, so Windows paths need no escaping.
+pub fn split_command(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                words.extend(word.take());
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(format!("Unclosed ' in hook command {}", command)),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if matches!(chars.peek(), Some('"' | '\\')) => {
+                            word.extend(chars.next());
+                        }
+                        Some(c) => word.push(c),
+                        None => return Err(format!("Unclosed \" in hook command {}", command)),
+                    }
+                }
+            }
+            '\\' if chars
+                .peek()
+                .is_some_and(|next| next.is_whitespace() || matches!(next, '\'' | '"' | '\\')) =>
+            {
+                word.get_or_insert_with(String::new).extend(chars.next());
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use std::sync::{Arc, Mutex};
+
+    /// An instance directory with its `minecraft` game directory
+    fn test_dir() -> TestDir {
+        let dir = TestDir::new("hooks");
+        std::fs::create_dir_all(dir.path().join("minecraft")).unwrap();
+        dir
+    }
+
+    fn hook(dir: &Path, kind: HookKind, command: &str, use_shell: bool) -> Hook {
+        let game_dir = dir.join("minecraft");
+        let context = HookContext {
+            instance_name: "Survival",
+            instance_id: "survival-id",
+            instance_dir: dir,
+            game_dir: &game_dir,
+            java: "/usr/lib/jvm/java-17/bin/java",
+            mc_version: "1.20.4",
+        };
+        Hook::new(kind, Some(command), use_shell, &context).unwrap()
+    }
+
+    /// Run `hook`, returning its result and the lines it printed
+    async fn run(hook: &Hook, timeout: Option<Duration>) -> (Result<(), String>, Vec<String>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let result = hook
+            .run("survival-id", timeout, move |batch| {
+                let mut lines = sink.lock().unwrap();
+                for event in batch {
+                    assert_eq!(event.instance_id, "survival-id");
+                    lines.push(event.line);
+                }
+            })
+            .await;
+        let lines = lines.lock().unwrap().clone();
+        (result, lines)
+    }
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn commands_are_split_like_a_shell() {
+        let split = |command| split_command(command).unwrap();
+        assert_eq!(
+            split("  mumble  --server voice.example.com "),
+            strings(&["mumble", "--server", "voice.example.com"])
+        );
+        assert_eq!(
+            split(r#"rsync -a "my saves/" '$HOME/backup' a\ b"#),
+            strings(&["rsync", "-a", "my saves/", "$HOME/backup", "a b"])
+        );
+        assert_eq!(
+            split(r#"echo "say \"hi\"" '' it\'s"#),
+            strings(&["echo", "say \"hi\"", "", "it's"])
+        );
+        assert_eq!(
+            split(r"C:\Tools\sync.exe --to D:\Saves"),
+            strings(&[r"C:\Tools\sync.exe", "--to", r"D:\Saves"])
+        );
+        assert!(split("").is_empty());
+        assert!(split_command("echo 'open").is_err());
+        assert!(split_command("echo \"open").is_err());
+    }
+
+    #[test]
+    fn blank_commands_are_no_hook() {
+        let dir = test_dir();
+        let context = HookContext {
+            instance_name: "Survival",
+            instance_id: "survival-id",
+            instance_dir: dir.path(),
+            game_dir: dir.path(),
+            java: "java",
+            mc_version: "1.20.4",
+        };
+        assert!(Hook::new(HookKind::PreLaunch, None, false, &context).is_none());
+        assert!(Hook::new(HookKind::PreLaunch, Some("  "), false, &context).is_none());
+    }
+
+    #[cfg(unix)]
+    fn stub(dir: &Path, name: &str, body: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn hooks_run_in_the_instance_with_its_variables() {
+        let dir = test_dir();
+        stub(
+            dir.path(),
+            "env.sh",
+            "echo \"cwd=$(pwd)\"\n\
+             echo \"args=$1|$2\"\n\
+             echo \"$INST_NAME $INST_ID $INST_MC_VERSION\"\n\
+             echo \"$INST_DIR $INST_MC_DIR $INST_JAVA\"\n",
+        );
+
+        let hook = hook(
+            dir.path(),
+            HookKind::PreLaunch,
+            "./env.sh 'two words' x",
+            false,
+        );
+        let (result, lines) = run(&hook, None).await;
+        result.unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                format!("cwd={}", dir.path().display()),
+                "args=two words|x".to_string(),
+                "Survival survival-id 1.20.4".to_string(),
+                format!(
+                    "{} {} /usr/lib/jvm/java-17/bin/java",
+                    dir.path().display(),
+                    dir.path().join("minecraft").display()
+                ),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failed_hook_reports_its_output() {
+        let dir = test_dir();
+        stub(
+            dir.path(),
+            "mount.sh",
+            "echo 'mounting saves'\necho 'mount: permission denied' >&2\nexit 3\n",
+        );
+
+        let mount = hook(dir.path(), HookKind::PreLaunch, "./mount.sh", false);
+        let (result, lines) = run(&mount, None).await;
+        let error = result.unwrap_err();
+        assert!(
+            error.starts_with("The pre-launch hook `./mount.sh` failed with exit status: 3"),
+            "{}",
+            error
+        );
+        assert!(error.contains("mount: permission denied"), "{}", error);
+        assert_eq!(lines.len(), 2);
+
+        let missing = hook(dir.path(), HookKind::PreLaunch, "./missing.sh", false);
+        let (result, _) = run(&missing, None).await;
+        assert!(
+            result
+                .unwrap_err()
+                .starts_with("The pre-launch hook could not be started")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn launch_context_describes_the_instance() {
+        let dir = test_dir();
+        let game_dir = dir.path().join("minecraft");
+        let instance = Instance {
+            use_shell: true,
+            ..Instance::for_test("survival-id", game_dir.clone())
+        };
+        // A Fabric instance reports the Minecraft version it is built on
+        let context = HookContext::for_launch(&instance, dir.path(), &game_dir, "java", "1.20.1");
+        let hook = Hook::new(
+            HookKind::PreLaunch,
+            Some("echo \"$INST_NAME|$INST_ID|$INST_MC_VERSION\""),
+            instance.use_shell,
+            &context,
+        )
+        .unwrap();
+        let (result, lines) = run(&hook, None).await;
+        result.unwrap();
+        assert_eq!(
+            lines,
+            vec!["Instance survival-id|survival-id|1.20.1".to_string()]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn post_exit_hook_is_stopped_after_its_timeout() {
+        let dir = test_dir();
+        stub(dir.path(), "sync.sh", "echo syncing\nexec sleep 30\n");
+
+        let hook = hook(dir.path(), HookKind::PostExit, "./sync.sh", false);
+        let started = std::time::Instant::now();
+        let (result, _) = run(&hook, Some(Duration::from_millis(300))).await;
+        assert_eq!(
+            result.unwrap_err(),
+            "The post-exit hook did not finish within 0.3s and was stopped"
+        );
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shell_is_only_used_when_asked_for() {
+        let dir = test_dir();
+        let command = "echo $INST_NAME | tr a-z A-Z";
+
+        let (result, lines) =
+            run(&hook(dir.path(), HookKind::PreLaunch, command, false), None).await;
+        result.unwrap();
+        assert_eq!(lines, vec!["$INST_NAME | tr a-z A-Z"]);
+
+        let (result, lines) =
+            run(&hook(dir.path(), HookKind::PreLaunch, command, true), None).await;
+        result.unwrap();
+        assert_eq!(lines, vec!["SURVIVAL"]);
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn hooks_run_in_the_instance_with_its_variables() {
+        let dir = test_dir();
+        std::fs::write(
+            dir.path().join("env.cmd"),
+            "@echo off\r\necho %INST_NAME% %INST_ID% %INST_MC_VERSION%\r\n",
+        )
+        .unwrap();
+
+        let hook = hook(dir.path(), HookKind::PreLaunch, r".\env.cmd", false);
+        let (result, lines) = run(&hook, None).await;
+        result.unwrap();
+        assert_eq!(lines, vec!["Survival survival-id 1.20.4"]);
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn failed_hook_reports_its_output() {
+        let dir = test_dir();
+        std::fs::write(
+            dir.path().join("mount.cmd"),
+            "@echo off\r\necho mount: access denied 1>&2\r\nexit /b 3\r\n",
+        )
+        .unwrap();
+
+        let hook = hook(dir.path(), HookKind::PreLaunch, r".\mount.cmd", false);
+        let (result, _) = run(&hook, None).await;
+        let error = result.unwrap_err();
+        assert!(error.contains("exit code: 3"), "{}", error);
+        assert!(error.contains("mount: access denied"), "{}", error);
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn shell_is_only_used_when_asked_for() {
+        let dir = test_dir();
+        let (result, lines) = run(
+            &hook(
+                dir.path(),
+                HookKind::PostExit,
+                "echo %INST_NAME%& echo done",
+                true,
+            ),
+            Some(POST_EXIT_TIMEOUT),
+        )
+        .await;
+        result.unwrap();
+        assert_eq!(lines, vec!["Survival", "done"]);
+    }
+}
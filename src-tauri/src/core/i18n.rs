@@ -0,0 +1,145 @@
+//! Localized text for messages the backend renders itself.
+//!
+//! Progress events and errors carry a stable `message_key` (plus parameters) that the
+//! frontend translates with the catalogs served by [`messages`]. Logs stay in English.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+static EN: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| parse_catalog(include_str!("../../locales/en.json")));
+static ZH_CN: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| parse_catalog(include_str!("../../locales/zh-CN.json")));
+
+fn parse_catalog(json: &str) -> HashMap<String, String> {
+    serde_json::from_str(json).expect("bundled message catalog is valid JSON")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    ZhCn,
+}
+
+impl Locale {
+    /// Locale for a tag such as `zh-CN` or `zh_Hans`; unsupported languages get English
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.to_ascii_lowercase().replace('_', "-");
+        if tag == "zh" || tag.starts_with("zh-") {
+            Locale::ZhCn
+        } else {
+            Locale::En
+        }
+    }
+
+    fn catalog(self) -> &'static HashMap<String, String> {
+        match self {
+            Locale::En => &EN,
+            Locale::ZhCn => &ZH_CN,
+        }
+    }
+}
+
+/// `message_key` of a progress status, e.g. `java.download.extracting` for
+/// `("java.download", "Extracting")`
+pub fn status_key(scope: &str, status: &str) -> String {
+    format!("{}.{}", scope, status.to_ascii_lowercase())
+}
+
+/// Text for `key` with `{name}` placeholders filled from `params`. Keys missing from
+/// the locale fall back to English, then to the key itself. The frontend renders
+/// keys the same way; this checks the catalogs against the backend's own messages.
+#[cfg(test)]
+pub fn translate(
+    locale: Locale,
+    key: &str,
+    params: &std::collections::BTreeMap<String, String>,
+) -> String {
+    let template = locale
+        .catalog()
+        .get(key)
+        .or_else(|| EN.get(key))
+        .map(String::as_str)
+        .unwrap_or(key);
+    params
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// Every message for `locale`, English where it has no translation, so the frontend
+/// can render the keys it receives
+pub fn messages(locale: Locale) -> HashMap<String, String> {
+    let mut messages = EN.clone();
+    messages.extend(
+        locale
+            .catalog()
+            .iter()
+            .map(|(key, text)| (key.clone(), text.clone())),
+    );
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::auth::every_error;
+    use crate::core::crash::CrashHintKind;
+    use crate::core::downloader::{DOWNLOAD_STATUSES, JAVA_DOWNLOAD_STATUSES};
+    use crate::core::java::JavaError;
+    use crate::core::java::error::every_variant;
+    use std::collections::BTreeMap;
+
+    fn emitted_keys() -> Vec<String> {
+        DOWNLOAD_STATUSES
+            .iter()
+            .map(|status| status_key("download", status))
+            .chain(
+                JAVA_DOWNLOAD_STATUSES
+                    .iter()
+                    .map(|status| status_key("java.download", status)),
+            )
+            .chain(every_variant().iter().map(|e| e.i18n_key().to_string()))
+            .chain(every_error().iter().map(|e| e.i18n_key().to_string()))
+            .chain(
+                CrashHintKind::ALL
+                    .iter()
+                    .map(|kind| kind.message_key().to_string()),
+            )
+            .collect()
+    }
+
+    #[test]
+    fn every_emitted_key_is_in_both_catalogs() {
+        for key in emitted_keys() {
+            assert!(EN.contains_key(&key), "{} missing from en", key);
+            assert!(ZH_CN.contains_key(&key), "{} missing from zh-CN", key);
+        }
+        let mut en: Vec<_> = EN.keys().collect();
+        let mut zh: Vec<_> = ZH_CN.keys().collect();
+        en.sort();
+        zh.sort();
+        assert_eq!(en, zh);
+    }
+
+    #[test]
+    fn translation_fills_params_and_falls_back() {
+        let error = JavaError::ChecksumMismatch("expected abc".to_string());
+        let params = error.message_params();
+        assert_eq!(
+            translate(Locale::En, error.i18n_key(), &params),
+            error.to_string()
+        );
+        assert_eq!(
+            translate(Locale::from_tag("zh_CN"), error.i18n_key(), &params),
+            "校验和不匹配：expected abc"
+        );
+        assert_eq!(
+            translate(Locale::ZhCn, "no.such.key", &BTreeMap::new()),
+            "no.such.key"
+        );
+        assert_eq!(Locale::from_tag("en-US"), Locale::En);
+    }
+}
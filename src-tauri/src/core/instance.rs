@@ -2,22 +2,24 @@
 //!
 //! This module provides functionality to:
 //! - Create and manage multiple isolated game instances
-//! - Each instance has its own versions, libraries, assets, mods, and saves
+//! - Each instance runs the game in its own `minecraft` folder, with its own mods,
+//!   saves and options; versions, libraries and assets can be shared between them
 //! - Support for instance switching and isolation
 
 use crate::core::config::LauncherConfig;
+use crate::core::quick_play::QuickPlay;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use tauri::{AppHandle, Manager};
+use tauri::AppHandle;
 use ts_rs::TS;
 use zip::write::SimpleFileOptions;
 
 /// Represents a game instance/profile
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "instance.ts")]
 pub struct Instance {
@@ -35,10 +37,44 @@ pub struct Instance {
     #[serde(default)]
     pub memory_override: Option<MemoryOverride>, // 内存设置覆盖（可选）
     pub java_path_override: Option<String>, // 实例级Java路径覆盖（可选）
+    #[serde(default)]
+    pub quick_play: Option<QuickPlay>, // 默认快速游戏目标（可选）
+    #[serde(default)]
+    pub pre_launch_command: Option<String>, // 启动前执行的命令（可选）
+    #[serde(default)]
+    pub post_exit_command: Option<String>, // 游戏退出后执行的命令（可选）
+    #[serde(default)]
+    pub use_shell: bool, // 通过系统 shell 执行上述命令
+}
+
+#[cfg(test)]
+impl Instance {
+    /// A vanilla 1.20.4 instance with nothing overridden
+    pub fn for_test(id: &str, game_dir: PathBuf) -> Self {
+        Self {
+            id: id.to_string(),
+            name: format!("Instance {}", id),
+            game_dir,
+            version_id: Some("1.20.4".to_string()),
+            created_at: 1_700_000_000,
+            last_played: None,
+            icon_path: None,
+            notes: None,
+            mod_loader: None,
+            mod_loader_version: None,
+            jvm_args_override: None,
+            memory_override: None,
+            java_path_override: None,
+            quick_play: None,
+            pre_launch_command: None,
+            post_exit_command: None,
+            use_shell: false,
+        }
+    }
 }
 
 /// Memory settings override for an instance
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "instance.ts")]
 pub struct MemoryOverride {
@@ -67,7 +103,10 @@ pub struct InstanceRepairResult {
 
 #[derive(Debug, Clone)]
 pub struct InstancePaths {
+    /// The instance directory, holding its version metadata and launcher files
     pub root: PathBuf,
+    /// Where the game runs, see [`game_dir_of`]
+    pub game_dir: PathBuf,
     pub metadata_versions: PathBuf,
     pub version_cache: PathBuf,
     pub libraries: PathBuf,
@@ -99,6 +138,8 @@ impl InstanceOperation {
     }
 }
 
+/// Settings an exported instance carries. Hook commands are left out, so importing
+/// an archive never runs commands from it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ExportedInstance {
@@ -111,6 +152,158 @@ struct ExportedInstance {
     jvm_args_override: Option<String>,
     memory_override: Option<MemoryOverride>,
     java_path_override: Option<String>,
+    #[serde(default)]
+    quick_play: Option<QuickPlay>,
+}
+
+/// Folders of the game itself, which every instance has its own of
+const GAME_FOLDERS: &[&str] = &[
+    "mods",
+    "config",
+    "saves",
+    "resourcepacks",
+    "shaderpacks",
+    "screenshots",
+    "logs",
+];
+
+/// Files and folders a launcher that ran the game in its data directory left there
+const LEGACY_GAME_ENTRIES: &[&str] = &[
+    "mods",
+    "config",
+    "saves",
+    "resourcepacks",
+    "shaderpacks",
+    "screenshots",
+    "options.txt",
+    "servers.dat",
+];
+
+/// Directory the game of the instance in `instance_dir` runs in: its `minecraft`
+/// folder, or the instance directory itself for instances created before that folder
+/// existed, which keep their saves next to the launcher's files
+pub fn game_dir_of(instance_dir: &Path) -> PathBuf {
+    let game_dir = instance_dir.join("minecraft");
+    if !game_dir.is_dir() && instance_dir.join("saves").is_dir() {
+        instance_dir.to_path_buf()
+    } else {
+        game_dir
+    }
+}
+
+impl InstancePaths {
+    /// Paths of the instance in `instance_dir`. With `use_shared_caches` downloaded
+    /// game files come from `shared_root`, so all instances use the same ones.
+    pub fn new(instance_dir: &Path, shared_root: &Path, use_shared_caches: bool) -> Self {
+        let cache_root = if use_shared_caches {
+            shared_root
+        } else {
+            instance_dir
+        };
+        let game_dir = game_dir_of(instance_dir);
+        InstancePaths {
+            root: instance_dir.to_path_buf(),
+            metadata_versions: instance_dir.join("versions"),
+            version_cache: cache_root.join("versions"),
+            libraries: cache_root.join("libraries"),
+            assets: cache_root.join("assets"),
+            mods: game_dir.join("mods"),
+            config: game_dir.join("config"),
+            saves: game_dir.join("saves"),
+            resourcepacks: game_dir.join("resourcepacks"),
+            shaderpacks: game_dir.join("shaderpacks"),
+            screenshots: game_dir.join("screenshots"),
+            game_dir,
+        }
+    }
+}
+
+/// Copy of its entry each instance directory keeps, so `repair_instances` can restore
+/// it without the index in `instances.json`
+const INSTANCE_FILE: &str = "instance.json";
+
+/// Longest instance name accepted
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Characters Windows doesn't allow in file names
+const FORBIDDEN_NAME_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Device names Windows reserves, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Check that `name` would also work as a file name on every platform, since it ends
+/// up in exported archive names
+fn check_name_is_filesystem_safe(name: &str) -> Result<(), String> {
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "Instance name cannot be longer than {} characters",
+            MAX_NAME_LENGTH
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| c.is_control() || FORBIDDEN_NAME_CHARS.contains(c))
+    {
+        return Err(format!("Instance name cannot contain {:?}", c));
+    }
+    if name.ends_with('.') {
+        return Err("Instance name cannot end with a dot".to_string());
+    }
+    let stem = name.split('.').next().unwrap_or(name).trim();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        return Err(format!(
+            "\"{}\" is reserved and cannot be an instance name",
+            stem
+        ));
+    }
+    Ok(())
+}
+
+fn write_instance_file(instance: &Instance) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(instance).map_err(|e| e.to_string())?;
+    crate::core::java::persistence::write_file_atomic(
+        &instance.game_dir.join(INSTANCE_FILE),
+        content.as_bytes(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// The instance kept in `dir`, which is taken as its id and game directory whatever
+/// the file says, in case the directory was moved or copied
+fn read_instance_file(dir: &Path) -> Option<Instance> {
+    let content = fs::read_to_string(dir.join(INSTANCE_FILE)).ok()?;
+    let mut instance: Instance = serde_json::from_str(&content).ok()?;
+    instance.id = dir.file_name()?.to_string_lossy().into_owned();
+    instance.game_dir = dir.to_path_buf();
+    Some(instance)
+}
+
+/// Whether `game_dir` is a directory of its own right inside `instances_dir`, the
+/// only place instance files are ever deleted from. Symlinks don't count.
+fn is_removable_instance_dir(game_dir: &Path, instances_dir: &Path) -> bool {
+    let is_dir = fs::symlink_metadata(game_dir).is_ok_and(|meta| meta.is_dir());
+    match (game_dir.canonicalize(), instances_dir.canonicalize()) {
+        (Ok(dir), Ok(root)) => is_dir && dir.parent() == Some(root.as_path()),
+        _ => false,
+    }
+}
+
+/// Ends an instance operation when dropped, on every exit path
+struct OperationGuard<'a> {
+    manager: &'a InstanceState,
+    id: &'a str,
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        self.manager.end_operation(self.id);
+    }
 }
 
 /// State management for instances
@@ -123,7 +316,7 @@ pub struct InstanceState {
 impl InstanceState {
     /// Create a new InstanceState
     pub fn new(app_handle: &AppHandle) -> Self {
-        let app_dir = app_handle.path().app_data_dir().unwrap();
+        let app_dir = crate::core::paths::data_root(app_handle);
         let file_path = app_dir.join("instances.json");
 
         let config = if file_path.exists() {
@@ -141,7 +334,7 @@ impl InstanceState {
     }
 
     fn app_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
-        app_handle.path().app_data_dir().map_err(|e| e.to_string())
+        Ok(crate::core::paths::data_root(app_handle))
     }
 
     fn instances_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
@@ -157,6 +350,7 @@ impl InstanceState {
         if trimmed.is_empty() {
             return Err("Instance name cannot be empty".to_string());
         }
+        check_name_is_filesystem_safe(trimmed)?;
 
         let duplicated = config.instances.iter().any(|instance| {
             if let Some(exclude_id) = exclude_id {
@@ -178,20 +372,13 @@ impl InstanceState {
     fn create_instance_directory_structure(instance_dir: &Path) -> Result<(), String> {
         fs::create_dir_all(instance_dir).map_err(|e| e.to_string())?;
 
-        for folder in [
-            "versions",
-            "libraries",
-            "assets",
-            "mods",
-            "config",
-            "saves",
-            "resourcepacks",
-            "shaderpacks",
-            "screenshots",
-            "logs",
-        ] {
+        for folder in ["versions", "libraries", "assets"] {
             fs::create_dir_all(instance_dir.join(folder)).map_err(|e| e.to_string())?;
         }
+        let game_dir = instance_dir.join("minecraft");
+        for folder in GAME_FOLDERS {
+            fs::create_dir_all(game_dir.join(folder)).map_err(|e| e.to_string())?;
+        }
 
         Ok(())
     }
@@ -238,31 +425,11 @@ impl InstanceState {
             .ok_or_else(|| format!("Instance {} not found", id))?;
         let shared_root = Self::app_dir(app_handle)?;
 
-        Ok(InstancePaths {
-            root: instance.game_dir.clone(),
-            metadata_versions: instance.game_dir.join("versions"),
-            version_cache: if config.use_shared_caches {
-                shared_root.join("versions")
-            } else {
-                instance.game_dir.join("versions")
-            },
-            libraries: if config.use_shared_caches {
-                shared_root.join("libraries")
-            } else {
-                instance.game_dir.join("libraries")
-            },
-            assets: if config.use_shared_caches {
-                shared_root.join("assets")
-            } else {
-                instance.game_dir.join("assets")
-            },
-            mods: instance.game_dir.join("mods"),
-            config: instance.game_dir.join("config"),
-            saves: instance.game_dir.join("saves"),
-            resourcepacks: instance.game_dir.join("resourcepacks"),
-            shaderpacks: instance.game_dir.join("shaderpacks"),
-            screenshots: instance.game_dir.join("screenshots"),
-        })
+        Ok(InstancePaths::new(
+            &instance.game_dir,
+            &shared_root,
+            config.use_shared_caches,
+        ))
     }
 
     pub fn resolve_directory(
@@ -284,21 +451,34 @@ impl InstanceState {
             "resourcepacks" => paths.resourcepacks,
             "shaderpacks" => paths.shaderpacks,
             "screenshots" => paths.screenshots,
-            other => paths.root.join(other),
+            other => paths.game_dir.join(other),
         };
 
         Ok(resolved)
     }
 
-    /// Save the instance configuration to disk
+    /// Save the instance configuration to disk, and each instance's entry to its
+    /// directory
     pub fn save(&self) -> Result<(), String> {
         let config = self.instances.lock().unwrap();
         let content = serde_json::to_string_pretty(&*config).map_err(|e| e.to_string())?;
         fs::create_dir_all(self.file_path.parent().unwrap()).map_err(|e| e.to_string())?;
         fs::write(&self.file_path, content).map_err(|e| e.to_string())?;
+
+        // The index is what counts, so a copy that can't be written isn't an error
+        for instance in config.instances.iter().filter(|i| i.game_dir.is_dir()) {
+            if let Err(e) = write_instance_file(instance) {
+                log::warn!("Failed to save {} of {}: {}", INSTANCE_FILE, instance.id, e);
+            }
+        }
         Ok(())
     }
 
+    /// Where new instances get their directory, next to `instances.json`
+    fn instances_root(&self) -> PathBuf {
+        self.file_path.with_file_name("instances")
+    }
+
     /// Create a new instance
     pub fn create_instance(
         &self,
@@ -332,6 +512,10 @@ impl InstanceState {
             jvm_args_override: None,
             memory_override: None,
             java_path_override: None,
+            quick_play: None,
+            pre_launch_command: None,
+            post_exit_command: None,
+            use_shell: false,
         };
 
         self.insert_instance(instance.clone(), true)?;
@@ -339,9 +523,11 @@ impl InstanceState {
         Ok(instance)
     }
 
-    /// Delete an instance
+    /// Delete an instance. Fails while anything else, like a launch, is in progress
+    /// for it. Files are only removed from its own directory in the instances folder.
     pub fn delete_instance(&self, id: &str) -> Result<(), String> {
         self.begin_operation(id, InstanceOperation::Delete)?;
+        let _operation_guard = OperationGuard { manager: self, id };
         let mut config = self.instances.lock().unwrap();
 
         // Find the instance
@@ -365,13 +551,16 @@ impl InstanceState {
         self.save()?;
 
         // Delete the instance directory
-        if instance.game_dir.exists() {
+        if is_removable_instance_dir(&instance.game_dir, &self.instances_root()) {
             fs::remove_dir_all(&instance.game_dir)
                 .map_err(|e| format!("Failed to delete instance directory: {}", e))?;
+        } else if instance.game_dir.exists() {
+            log::warn!(
+                "Left {} in place, it is not a directory in the instances folder",
+                instance.game_dir.display()
+            );
         }
 
-        self.end_operation(id);
-
         Ok(())
     }
 
@@ -385,9 +574,15 @@ impl InstanceState {
             .position(|i| i.id == instance.id)
             .ok_or_else(|| format!("Instance {} not found", instance.id))?;
 
-        Self::validate_instance_name(&config, &instance.name, Some(&instance.id))?;
-
         let existing = config.instances[index].clone();
+        // Names from before they had to be safe file names stay valid until renamed
+        if instance.name != existing.name {
+            Self::validate_instance_name(&config, &instance.name, Some(&instance.id))?;
+        }
+        if let Some(quick_play) = &instance.quick_play {
+            quick_play.validate()?;
+        }
+
         let mut updated = instance;
         updated.game_dir = existing.game_dir;
         updated.created_at = existing.created_at;
@@ -448,19 +643,6 @@ impl InstanceState {
         new_name: String,
         app_handle: &AppHandle,
     ) -> Result<Instance, String> {
-        // Local RAII guard to ensure end_operation is always called
-        struct OperationGuard<'a> {
-            manager: &'a InstanceState,
-            id: &'a str,
-        }
-
-        impl<'a> Drop for OperationGuard<'a> {
-            fn drop(&mut self) {
-                // This will run on all exit paths from duplicate_instance
-                self.manager.end_operation(self.id);
-            }
-        }
-
         self.begin_operation(id, InstanceOperation::ImportExport)?;
         let _operation_guard = OperationGuard { manager: self, id };
 
@@ -505,6 +687,10 @@ impl InstanceState {
             jvm_args_override: source_instance.jvm_args_override.clone(),
             memory_override: source_instance.memory_override.clone(),
             java_path_override: source_instance.java_path_override.clone(),
+            quick_play: source_instance.quick_play.clone(),
+            pre_launch_command: source_instance.pre_launch_command.clone(),
+            post_exit_command: source_instance.post_exit_command.clone(),
+            use_shell: source_instance.use_shell,
         };
 
         self.insert_instance(new_instance.clone(), false)?;
@@ -538,6 +724,7 @@ impl InstanceState {
             jvm_args_override: instance.jvm_args_override.clone(),
             memory_override: instance.memory_override.clone(),
             java_path_override: instance.java_path_override.clone(),
+            quick_play: instance.quick_play.clone(),
         };
 
         writer
@@ -620,6 +807,7 @@ impl InstanceState {
         hydrated.jvm_args_override = exported.jvm_args_override;
         hydrated.memory_override = exported.memory_override;
         hydrated.java_path_override = exported.java_path_override;
+        hydrated.quick_play = exported.quick_play;
         self.update_instance(hydrated.clone())?;
         self.end_operation(&imported.id);
 
@@ -659,7 +847,7 @@ impl InstanceState {
                 continue;
             }
 
-            let recovered = Instance {
+            let recovered = read_instance_file(&entry.path()).unwrap_or_else(|| Instance {
                 id: id.clone(),
                 name: format!("Recovered {}", &id[..id.len().min(8)]),
                 game_dir: entry.path(),
@@ -673,7 +861,11 @@ impl InstanceState {
                 jvm_args_override: None,
                 memory_override: None,
                 java_path_override: None,
-            };
+                quick_play: None,
+                pre_launch_command: None,
+                post_exit_command: None,
+                use_shell: false,
+            });
 
             config.instances.push(recovered);
             restored_instances += 1;
@@ -760,21 +952,25 @@ fn copy_dir_all(src: &Path, dst: &Path) -> Result<(), std::io::Error> {
 pub fn migrate_legacy_data(
     app_handle: &AppHandle,
     instance_state: &InstanceState,
+    use_shared_caches: bool,
 ) -> Result<(), String> {
-    let app_dir = app_handle.path().app_data_dir().unwrap();
-    let old_versions_dir = app_dir.join("versions");
-    let old_libraries_dir = app_dir.join("libraries");
-    let old_assets_dir = app_dir.join("assets");
+    let app_dir = crate::core::paths::data_root(app_handle);
 
     // Check if legacy data exists
-    let has_legacy_data =
-        old_versions_dir.exists() || old_libraries_dir.exists() || old_assets_dir.exists();
+    let has_legacy_data = ["versions", "libraries", "assets"]
+        .iter()
+        .chain(LEGACY_GAME_ENTRIES)
+        .any(|entry| app_dir.join(entry).exists());
 
     if !has_legacy_data {
         return Ok(()); // No legacy data to migrate
     }
 
-    // Check if instances already exist
+    // Only once, before instances were ever saved; afterwards the data directory
+    // holds the shared caches
+    if instance_state.file_path.exists() {
+        return Ok(());
+    }
     let config = instance_state.instances.lock().unwrap();
     if !config.instances.is_empty() {
         drop(config);
@@ -787,45 +983,69 @@ pub fn migrate_legacy_data(
         .create_instance("Default".to_string(), app_handle)
         .map_err(|e| format!("Failed to create default instance: {}", e))?;
 
-    let new_versions_dir = default_instance.game_dir.join("versions");
-    let new_libraries_dir = default_instance.game_dir.join("libraries");
-    let new_assets_dir = default_instance.game_dir.join("assets");
+    import_legacy_layout(&app_dir, &default_instance.game_dir, use_shared_caches)
+}
 
-    // Move legacy data
-    if old_versions_dir.exists() {
-        if new_versions_dir.exists() {
-            // Merge directories
-            copy_dir_all(&old_versions_dir, &new_versions_dir)
-                .map_err(|e| format!("Failed to migrate versions: {}", e))?;
-        } else {
-            fs::rename(&old_versions_dir, &new_versions_dir)
-                .map_err(|e| format!("Failed to migrate versions: {}", e))?;
+/// Move the game files a launcher that ran the game in `app_dir` left there into the
+/// instance in `instance_dir`. With `use_shared_caches` downloaded files stay where
+/// they are, as the shared caches, and only the version metadata is copied.
+fn import_legacy_layout(
+    app_dir: &Path,
+    instance_dir: &Path,
+    use_shared_caches: bool,
+) -> Result<(), String> {
+    let game_dir = game_dir_of(instance_dir);
+    for entry in LEGACY_GAME_ENTRIES {
+        let legacy = app_dir.join(entry);
+        if legacy.exists() {
+            move_into(&legacy, &game_dir.join(entry))
+                .map_err(|e| format!("Failed to migrate {}: {}", entry, e))?;
         }
     }
 
-    if old_libraries_dir.exists() {
-        if new_libraries_dir.exists() {
-            copy_dir_all(&old_libraries_dir, &new_libraries_dir)
-                .map_err(|e| format!("Failed to migrate libraries: {}", e))?;
-        } else {
-            fs::rename(&old_libraries_dir, &new_libraries_dir)
-                .map_err(|e| format!("Failed to migrate libraries: {}", e))?;
+    let legacy_versions = app_dir.join("versions");
+    if use_shared_caches {
+        let Ok(versions) = fs::read_dir(&legacy_versions) else {
+            return Ok(());
+        };
+        for version in versions.flatten() {
+            let id = version.file_name().to_string_lossy().into_owned();
+            let json_name = format!("{}.json", id);
+            let json = version.path().join(&json_name);
+            if !json.is_file() {
+                continue;
+            }
+            let target = instance_dir.join("versions").join(&id);
+            fs::create_dir_all(&target)
+                .and_then(|_| fs::copy(&json, target.join(&json_name)))
+                .map_err(|e| format!("Failed to migrate versions: {}", e))?;
         }
+        return Ok(());
     }
 
-    if old_assets_dir.exists() {
-        if new_assets_dir.exists() {
-            copy_dir_all(&old_assets_dir, &new_assets_dir)
-                .map_err(|e| format!("Failed to migrate assets: {}", e))?;
-        } else {
-            fs::rename(&old_assets_dir, &new_assets_dir)
-                .map_err(|e| format!("Failed to migrate assets: {}", e))?;
+    for folder in ["versions", "libraries", "assets"] {
+        let legacy = app_dir.join(folder);
+        if legacy.exists() {
+            move_into(&legacy, &instance_dir.join(folder))
+                .map_err(|e| format!("Failed to migrate {}: {}", folder, e))?;
         }
     }
-
     Ok(())
 }
 
+/// Move `from` to `to`, merging it into a directory already there
+fn move_into(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    if to.is_dir() && from.is_dir() {
+        copy_dir_all(from, to)?;
+        fs::remove_dir_all(from)
+    } else {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(from, to)
+    }
+}
+
 /// Migrate instance caches to shared global caches
 ///
 /// This function deduplicates versions, libraries, and assets from all instances
@@ -843,7 +1063,7 @@ pub fn migrate_to_shared_caches(
     app_handle: &AppHandle,
     instance_state: &InstanceState,
 ) -> Result<(usize, usize, usize, u64), String> {
-    let app_dir = app_handle.path().app_data_dir().unwrap();
+    let app_dir = crate::core::paths::data_root(app_handle);
 
     // Global shared cache directories
     let global_versions = app_dir.join("versions");
@@ -1048,3 +1268,239 @@ fn compute_file_sha1(path: &Path) -> Result<String, String> {
     hasher.update(&data);
     Ok(hex::encode(hasher.finalize()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn instance(id: &str, game_dir: PathBuf) -> Instance {
+        Instance {
+            notes: Some("notes".to_string()),
+            mod_loader: Some("fabric".to_string()),
+            mod_loader_version: Some("0.15.7".to_string()),
+            jvm_args_override: Some("-XX:+UseZGC".to_string()),
+            memory_override: Some(MemoryOverride {
+                min: 1024,
+                max: 4096,
+            }),
+            quick_play: Some(QuickPlay::Multiplayer {
+                address: "mc.example.com".to_string(),
+            }),
+            pre_launch_command: Some("mumble".to_string()),
+            ..Instance::for_test(id, game_dir)
+        }
+    }
+
+    fn state(root: &Path, instances: Vec<Instance>) -> InstanceState {
+        InstanceState {
+            instances: Mutex::new(InstanceConfig {
+                instances,
+                active_instance_id: None,
+            }),
+            file_path: root.join("instances.json"),
+            operation_locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[test]
+    fn instances_round_trip_through_the_index_and_their_own_file() {
+        let dir = TestDir::new("instances");
+        let game_dir = dir.path().join("instances").join("a");
+        InstanceState::create_instance_directory_structure(&game_dir).unwrap();
+        let saved = instance("a", game_dir.clone());
+        state(dir.path(), vec![saved.clone()]).save().unwrap();
+
+        let index: InstanceConfig =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("instances.json")).unwrap())
+                .unwrap();
+        assert_eq!(index.instances, std::slice::from_ref(&saved));
+        assert_eq!(read_instance_file(&game_dir), Some(saved.clone()));
+
+        // A copied directory is restored as the instance it now is
+        let copy = dir.path().join("instances").join("b");
+        fs::create_dir_all(&copy).unwrap();
+        fs::copy(game_dir.join(INSTANCE_FILE), copy.join(INSTANCE_FILE)).unwrap();
+        let restored = read_instance_file(&copy).unwrap();
+        assert_eq!(restored.id, "b");
+        assert_eq!(restored.game_dir, copy);
+        assert_eq!(restored.memory_override, saved.memory_override);
+    }
+
+    #[test]
+    fn instances_run_apart_on_shared_game_files() {
+        let dir = TestDir::new("instances");
+        let shared_root = dir.path().to_path_buf();
+        let version: crate::core::game_version::GameVersion = serde_json::from_str(
+            r#"{
+                "id": "1.20.4",
+                "mainClass": "net.minecraft.client.main.Main",
+                "libraries": [
+                    {"name": "com.mojang:logging:1.1.1", "downloads": {"artifact": {"path": "com/mojang/logging/1.1.1/logging-1.1.1.jar", "url": "https://libraries.minecraft.net/com/mojang/logging/1.1.1/logging-1.1.1.jar"}}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let launch = |id: &str| {
+            let instance_dir = dir.path().join("instances").join(id);
+            InstanceState::create_instance_directory_structure(&instance_dir).unwrap();
+            let paths = InstancePaths::new(&instance_dir, &shared_root, true);
+            let client_jar = paths.version_cache.join("1.20.4").join("1.20.4.jar");
+            let natives_dir = instance_dir.join("versions").join("1.20.4").join("natives");
+            let classpath = crate::core::jvm_args::build_classpath(
+                &version,
+                &crate::core::jvm_args::LaunchPaths {
+                    libraries_dir: &paths.libraries,
+                    client_jar: &client_jar,
+                    natives_dir: &natives_dir,
+                },
+                &crate::core::rules::RuleContext::current(),
+            );
+            (paths, classpath)
+        };
+
+        let (first, first_classpath) = launch("a");
+        let (second, second_classpath) = launch("b");
+        assert_eq!(first.game_dir, dir.path().join("instances/a/minecraft"));
+        assert_eq!(second.game_dir, dir.path().join("instances/b/minecraft"));
+        assert_eq!(first.saves, first.game_dir.join("saves"));
+        assert_eq!(first.assets, second.assets);
+        assert_eq!(first_classpath, second_classpath);
+        assert!(
+            first_classpath.contains(
+                &shared_root
+                    .join("libraries/com/mojang/logging/1.1.1/logging-1.1.1.jar")
+                    .to_string_lossy()
+                    .into_owned()
+            )
+        );
+
+        // Instances from before the minecraft folder keep running in their directory
+        let legacy = dir.path().join("instances").join("legacy");
+        fs::create_dir_all(legacy.join("saves")).unwrap();
+        assert_eq!(
+            InstancePaths::new(&legacy, &shared_root, true).game_dir,
+            legacy
+        );
+    }
+
+    #[test]
+    fn legacy_game_data_is_imported_into_an_instance() {
+        let dir = TestDir::new("instances");
+        let app_dir = dir.path().join("data");
+        fs::create_dir_all(app_dir.join("saves/World")).unwrap();
+        fs::write(app_dir.join("saves/World/level.dat"), "world").unwrap();
+        fs::write(app_dir.join("options.txt"), "fov:1.0").unwrap();
+        fs::create_dir_all(app_dir.join("versions/1.20.4")).unwrap();
+        fs::write(app_dir.join("versions/1.20.4/1.20.4.json"), "{}").unwrap();
+        fs::write(app_dir.join("versions/1.20.4/1.20.4.jar"), "jar").unwrap();
+        fs::create_dir_all(app_dir.join("libraries")).unwrap();
+
+        let shared = app_dir.join("instances").join("shared");
+        InstanceState::create_instance_directory_structure(&shared).unwrap();
+        import_legacy_layout(&app_dir, &shared, true).unwrap();
+        let game_dir = shared.join("minecraft");
+        assert_eq!(
+            fs::read_to_string(game_dir.join("saves/World/level.dat")).unwrap(),
+            "world"
+        );
+        assert!(game_dir.join("options.txt").is_file());
+        assert!(!app_dir.join("saves").exists());
+        // The old caches became the shared ones; the instance gets the metadata
+        assert!(shared.join("versions/1.20.4/1.20.4.json").is_file());
+        assert!(!shared.join("versions/1.20.4/1.20.4.jar").exists());
+        assert!(app_dir.join("versions/1.20.4/1.20.4.jar").is_file());
+        assert!(app_dir.join("libraries").is_dir());
+
+        let own = app_dir.join("instances").join("own");
+        InstanceState::create_instance_directory_structure(&own).unwrap();
+        import_legacy_layout(&app_dir, &own, false).unwrap();
+        assert!(own.join("versions/1.20.4/1.20.4.jar").is_file());
+        assert!(own.join("libraries").is_dir());
+        assert!(!app_dir.join("versions").exists());
+        assert!(!app_dir.join("libraries").exists());
+    }
+
+    #[test]
+    fn quick_play_default_is_checked_on_update() {
+        let dir = TestDir::new("instances");
+        let game_dir = dir.path().join("instances").join("a");
+        InstanceState::create_instance_directory_structure(&game_dir).unwrap();
+        let state = state(dir.path(), vec![instance("a", game_dir.clone())]);
+
+        let mut updated = instance("a", game_dir.clone());
+        updated.quick_play = Some(QuickPlay::Singleplayer {
+            world: "New World".to_string(),
+        });
+        state.update_instance(updated.clone()).unwrap();
+        assert_eq!(read_instance_file(&game_dir), Some(updated.clone()));
+
+        updated.quick_play = Some(QuickPlay::Multiplayer {
+            address: "mc.example.com:99999".to_string(),
+        });
+        assert!(state.update_instance(updated).is_err());
+        assert_eq!(
+            state.get_instance("a").unwrap().quick_play,
+            Some(QuickPlay::Singleplayer {
+                world: "New World".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn names_must_be_safe_file_names() {
+        for name in ["Survival", "1.20.4 Fabric", "整合包 (hard)", "Con Air"] {
+            assert_eq!(check_name_is_filesystem_safe(name), Ok(()), "{}", name);
+        }
+        for name in [
+            "Mods: Create",
+            "a/b",
+            "what?",
+            "tab\there",
+            "trailing.",
+            "con",
+            "LPT1.txt",
+            &"x".repeat(MAX_NAME_LENGTH + 1),
+        ] {
+            assert!(check_name_is_filesystem_safe(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn delete_waits_for_other_operations_and_only_removes_instance_directories() {
+        let dir = TestDir::new("instances");
+        let game_dir = dir.path().join("instances").join("a");
+        InstanceState::create_instance_directory_structure(&game_dir).unwrap();
+        let outside = dir.path().join("elsewhere");
+        fs::create_dir_all(&outside).unwrap();
+        let state = state(
+            dir.path(),
+            vec![
+                instance("a", game_dir.clone()),
+                instance("b", outside.clone()),
+            ],
+        );
+
+        state
+            .begin_operation("a", InstanceOperation::Launch)
+            .unwrap();
+        assert!(state.delete_instance("a").unwrap_err().contains("busy"));
+        assert!(state.get_instance("a").is_some());
+        state.end_operation("a");
+
+        state.delete_instance("a").unwrap();
+        assert!(state.get_instance("a").is_none());
+        assert!(!game_dir.exists());
+
+        // Listed, but not in the instances folder: forgotten, files kept
+        state.delete_instance("b").unwrap();
+        assert!(state.get_instance("b").is_none());
+        assert!(outside.exists());
+
+        // A failed delete doesn't leave the instance locked
+        assert!(state.delete_instance("b").is_err());
+        state
+            .begin_operation("b", InstanceOperation::Launch)
+            .unwrap();
+    }
+}
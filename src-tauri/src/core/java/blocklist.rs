@@ -0,0 +1,170 @@
+//! Java builds that must not run Minecraft.
+//!
+//! A few builds are known to crash the game; the user can block more by path or
+//! version. Resolution never picks a blocked installation, while lists shown in the UI
+//! keep them with a `blocked_reason` so they can be greyed out with an explanation.
+
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::path::Path;
+use ts_rs::TS;
+
+use super::JavaInstallation;
+use super::persistence::JavaSettings;
+use super::validation::{JavaVendor, compare_versions, version_parts};
+
+/// A build known to break Minecraft. Listing its `id` in the settings'
+/// `allowed_known_bad_builds` lets it be used anyway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/blocklist.ts")]
+pub struct KnownBadBuild {
+    pub id: &'static str,
+    /// `None` for builds of every vendor
+    pub vendor: Option<JavaVendor>,
+    /// Oldest affected version, inclusive; `None` for no lower bound
+    pub min_version: Option<&'static str>,
+    /// Newest affected version, inclusive; `None` for no upper bound
+    pub max_version: Option<&'static str>,
+    /// Only 32-bit builds are affected
+    pub only_32bit: bool,
+    pub reason: &'static str,
+}
+
+pub const KNOWN_BAD_BUILDS: &[KnownBadBuild] = &[
+    KnownBadBuild {
+        id: "java-16-ga",
+        vendor: None,
+        min_version: Some("16"),
+        max_version: Some("16.0.0"),
+        only_32bit: false,
+        reason: "The first Java 16 release has JIT bugs that crash Minecraft",
+    },
+    KnownBadBuild {
+        id: "32-bit",
+        vendor: None,
+        min_version: None,
+        max_version: None,
+        only_32bit: true,
+        reason: "32-bit Java can't give larger modpacks the memory they need",
+    },
+];
+
+impl KnownBadBuild {
+    fn matches(&self, java: &JavaInstallation) -> bool {
+        let version = version_parts(&java.version);
+        self.vendor
+            .is_none_or(|vendor| JavaVendor::from_vendor_name(&java.vendor) == Some(vendor))
+            && (!self.only_32bit || !java.is_64bit)
+            && self
+                .min_version
+                .is_none_or(|min| compare_versions(&version, &version_parts(min)) != Ordering::Less)
+            && self.max_version.is_none_or(|max| {
+                compare_versions(&version, &version_parts(max)) != Ordering::Greater
+            })
+    }
+}
+
+/// Why `java` must not be used, or `None` if it may
+pub fn blocked_reason(java: &JavaInstallation, settings: &JavaSettings) -> Option<String> {
+    if settings
+        .blocked_java_paths
+        .iter()
+        .any(|path| Path::new(path) == Path::new(&java.path))
+    {
+        return Some("Blocked in the Java settings".to_string());
+    }
+    let version = version_parts(&java.version);
+    if let Some(blocked) = settings.blocked_versions.iter().find(|blocked| {
+        let blocked = version_parts(blocked);
+        !blocked.is_empty() && version.starts_with(&blocked)
+    }) {
+        return Some(format!("Java {} is blocked in the Java settings", blocked));
+    }
+    KNOWN_BAD_BUILDS
+        .iter()
+        .filter(|build| {
+            !settings
+                .allowed_known_bad_builds
+                .iter()
+                .any(|id| id == build.id)
+        })
+        .find(|build| build.matches(java))
+        .map(|build| build.reason.to_string())
+}
+
+/// `installations` with `blocked_reason` filled in, for display
+pub fn flag_blocked(
+    mut installations: Vec<JavaInstallation>,
+    settings: &JavaSettings,
+) -> Vec<JavaInstallation> {
+    for java in &mut installations {
+        java.blocked_reason = blocked_reason(java, settings);
+    }
+    installations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installation(version: &str, is_64bit: bool) -> JavaInstallation {
+        JavaInstallation {
+            path: format!("/usr/lib/jvm/java-{}/bin/java", version),
+            version: version.to_string(),
+            arch: "x64".to_string(),
+            vendor: "Eclipse Adoptium".to_string(),
+            source: "system".to_string(),
+            is_64bit,
+            is_native_arch: true,
+            blocked_reason: None,
+        }
+    }
+
+    #[test]
+    fn versions_compare_by_numeric_parts() {
+        assert_eq!(version_parts("1.8.0_392"), vec![8, 0, 392]);
+        assert_eq!(version_parts("21.0.2+13"), vec![21, 0, 2]);
+        assert_eq!(version_parts("16"), vec![16]);
+        assert_eq!(compare_versions(&[16], &[16, 0, 0]), Ordering::Equal);
+        assert_eq!(
+            compare_versions(&[16, 0, 1], &[16, 0, 0]),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn known_bad_builds_can_be_allowed_one_by_one() {
+        let mut settings = JavaSettings::default();
+        assert!(blocked_reason(&installation("16", true), &settings).is_some());
+        assert!(blocked_reason(&installation("16.0.0", true), &settings).is_some());
+        assert!(blocked_reason(&installation("16.0.1", true), &settings).is_none());
+        assert!(blocked_reason(&installation("1.8.0_392", false), &settings).is_some());
+
+        settings.allowed_known_bad_builds = vec!["32-bit".to_string()];
+        assert!(blocked_reason(&installation("1.8.0_392", false), &settings).is_none());
+        assert!(blocked_reason(&installation("16", true), &settings).is_some());
+    }
+
+    #[test]
+    fn user_blocks_match_paths_and_version_prefixes() {
+        let settings = JavaSettings {
+            blocked_java_paths: vec!["/usr/lib/jvm/java-21.0.2/bin/java".to_string()],
+            blocked_versions: vec!["17.0".to_string()],
+            ..JavaSettings::default()
+        };
+        assert!(blocked_reason(&installation("21.0.2", true), &settings).is_some());
+        assert!(blocked_reason(&installation("17.0.10", true), &settings).is_some());
+        assert!(blocked_reason(&installation("17.1.0", true), &settings).is_none());
+
+        let flagged = flag_blocked(
+            vec![installation("17.0.10", true), installation("11.0.22", true)],
+            &settings,
+        );
+        assert_eq!(
+            flagged[0].blocked_reason.as_deref(),
+            Some("Java 17.0 is blocked in the Java settings")
+        );
+        assert_eq!(flagged[1].blocked_reason, None);
+    }
+}
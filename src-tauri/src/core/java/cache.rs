@@ -0,0 +1,354 @@
+//! On-disk cache helpers, and clearing the launcher's Java caches on request for when
+//! they get into a bad state.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use tauri::{AppHandle, Emitter};
+use ts_rs::TS;
+
+use super::{CATALOG_CACHE_DIR, JavaError, LEGACY_CATALOG_CACHE_FILE, get_java_install_dir};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Write `value` as gzip-compressed JSON, atomically
+pub fn write_json_gz(path: &Path, value: &impl Serialize) -> Result<(), JavaError> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    serde_json::to_writer(&mut encoder, value)
+        .map_err(|e| JavaError::serialization("Failed to encode cache file", e))?;
+    let content = encoder
+        .finish()
+        .map_err(|e| JavaError::io("Failed to compress cache file", e))?;
+    super::persistence::write_file_atomic(path, &content)
+        .map_err(|e| JavaError::io_at(format!("Failed to write {}", path.display()), path, e))
+}
+
+/// Contents of a JSON file written by `write_json_gz`, or of a plain uncompressed one
+pub fn read_json_file(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut content)
+            .ok()?;
+        Some(content)
+    } else {
+        String::from_utf8(bytes).ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/cache.ts")]
+pub enum CacheKind {
+    /// Cached release catalogs
+    Catalog,
+    /// Downloaded runtime archives and partial downloads left in the Java directory
+    Archives,
+    /// Results of running Java binaries, reused while a binary is unchanged
+    Validation,
+    /// Installations found by the last scan, and those remembered for startup
+    DetectionResults,
+    All,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/cache.ts")]
+pub struct ClearCachesReport {
+    pub removed_files: u32,
+    #[ts(type = "number")]
+    pub freed_bytes: u64,
+    /// Files left alone because a download is using them or they couldn't be removed
+    pub skipped_files: Vec<String>,
+}
+
+/// Archives an install is downloading or extracting right now
+static ARCHIVES_IN_USE: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(Default::default);
+
+/// Marks an archive as in use until dropped, so clearing caches skips it along with
+/// its `.part` and `.part.meta` files
+pub(crate) struct ArchiveInUse(PathBuf);
+
+impl ArchiveInUse {
+    pub(crate) fn register(archive_path: &Path) -> Self {
+        ARCHIVES_IN_USE
+            .lock()
+            .unwrap()
+            .insert(archive_path.to_path_buf());
+        Self(archive_path.to_path_buf())
+    }
+}
+
+impl Drop for ArchiveInUse {
+    fn drop(&mut self) {
+        ARCHIVES_IN_USE.lock().unwrap().remove(&self.0);
+    }
+}
+
+fn is_archive_in_use(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    ARCHIVES_IN_USE.lock().unwrap().iter().any(|archive| {
+        archive.parent() == path.parent()
+            && name.starts_with(&*archive.file_name().unwrap_or_default().to_string_lossy())
+    })
+}
+
+fn is_archive_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    [".tar.gz", ".tgz", ".zip", ".part", ".part.meta"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+/// Files directly inside `dir` matching `filter`
+fn files_in(dir: &Path, filter: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && filter(path))
+        .collect()
+}
+
+fn cache_files(data_dir: &Path, java_dir: &Path, kind: CacheKind) -> Vec<PathBuf> {
+    match kind {
+        CacheKind::Catalog => {
+            let mut files = files_in(&data_dir.join(CATALOG_CACHE_DIR), |_| true);
+            let legacy = data_dir.join(LEGACY_CATALOG_CACHE_FILE);
+            if legacy.is_file() {
+                files.push(legacy);
+            }
+            files
+        }
+        CacheKind::Archives => files_in(java_dir, is_archive_file),
+        CacheKind::Validation | CacheKind::DetectionResults => Vec::new(),
+        CacheKind::All => [
+            CacheKind::Catalog,
+            CacheKind::Archives,
+            CacheKind::Validation,
+            CacheKind::DetectionResults,
+        ]
+        .into_iter()
+        .flat_map(|kind| cache_files(data_dir, java_dir, kind))
+        .collect(),
+    }
+}
+
+fn clear_caches_in(data_dir: &Path, java_dir: &Path, kinds: &[CacheKind]) -> ClearCachesReport {
+    let files: BTreeSet<PathBuf> = kinds
+        .iter()
+        .flat_map(|&kind| cache_files(data_dir, java_dir, kind))
+        .collect();
+
+    let mut report = ClearCachesReport::default();
+    for path in files {
+        if is_archive_in_use(&path) {
+            report
+                .skipped_files
+                .push(path.to_string_lossy().into_owned());
+            continue;
+        }
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                report.removed_files += 1;
+                report.freed_bytes += size;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                log::warn!("Failed to remove {}: {}", path.display(), e);
+                report
+                    .skipped_files
+                    .push(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+    if clears(kinds, CacheKind::Catalog) {
+        super::forget_cached_catalogs(data_dir);
+    }
+    if clears(kinds, CacheKind::Validation) {
+        super::validation::forget_checked_installations();
+    }
+    if clears(kinds, CacheKind::DetectionResults) {
+        forget_detection_results(data_dir);
+    }
+    // Only goes if it is now empty
+    let _ = std::fs::remove_dir(data_dir.join(CATALOG_CACHE_DIR));
+    report
+}
+
+fn clears(kinds: &[CacheKind], kind: CacheKind) -> bool {
+    kinds.iter().any(|&k| k == kind || k == CacheKind::All)
+}
+
+/// Make the next detection scan again, and stop showing the installations remembered
+/// from the last full scan until it has run
+fn forget_detection_results(data_dir: &Path) {
+    super::forget_detected_installations();
+    let result = super::persistence::config_transaction_at(
+        &super::persistence::java_config_path_in(data_dir),
+        |_| {},
+        |config| {
+            config.known_installations.clear();
+            Ok(())
+        },
+    );
+    if let Err(e) = result {
+        log::warn!("Failed to forget remembered Java installations: {}", e);
+    }
+}
+
+/// Delete the given kinds of cached Java data and emit `java-caches-cleared` with the
+/// report. Files a running install is using are skipped, so this is safe to call
+/// during a download.
+pub fn clear_caches(
+    app_handle: &AppHandle,
+    kinds: &[CacheKind],
+) -> Result<ClearCachesReport, JavaError> {
+    let data_dir = crate::core::profiles::profile_dir(app_handle)?;
+    let report = clear_caches_in(&data_dir, &get_java_install_dir(app_handle)?, kinds);
+    let _ = app_handle.emit("java-caches-cleared", &report);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::java::known::KnownInstallation;
+    use crate::core::java::{JavaInstallation, persistence};
+    use crate::utils::test_dir::TestDir;
+
+    struct Dirs {
+        root: TestDir,
+        java: PathBuf,
+    }
+
+    impl Dirs {
+        fn new() -> Self {
+            let root = TestDir::new("java-cache");
+            let data = root.path();
+            let java = data.join("java");
+            std::fs::create_dir_all(data.join(CATALOG_CACHE_DIR)).unwrap();
+            std::fs::create_dir_all(java.join("temurin-17-jre/bin")).unwrap();
+            std::fs::write(java.join("temurin-17-jre/bin/java"), b"runtime").unwrap();
+            std::fs::write(
+                data.join(CATALOG_CACHE_DIR).join("adoptium-linux-x64.json"),
+                [0; 10],
+            )
+            .unwrap();
+            std::fs::write(data.join(LEGACY_CATALOG_CACHE_FILE), [0; 5]).unwrap();
+            std::fs::write(java.join("jdk-21.tar.gz"), [0; 100]).unwrap();
+            std::fs::write(java.join("jre-17.zip.part"), [0; 40]).unwrap();
+            std::fs::write(java.join("jre-17.zip.part.meta"), [0; 2]).unwrap();
+            Self { root, java }
+        }
+
+        fn data(&self) -> &Path {
+            self.root.path()
+        }
+    }
+
+    #[test]
+    fn json_gz_round_trips_and_plain_json_still_reads() {
+        let dirs = Dirs::new();
+        let value = serde_json::json!({ "releases": vec!["17.0.9+9"; 200] });
+
+        let compressed = dirs.data().join("value.json.gz");
+        write_json_gz(&compressed, &value).unwrap();
+        let bytes = std::fs::read(&compressed).unwrap();
+        assert!(bytes.starts_with(&GZIP_MAGIC));
+        assert!(bytes.len() < value.to_string().len());
+        let read: serde_json::Value =
+            serde_json::from_str(&read_json_file(&compressed).unwrap()).unwrap();
+        assert_eq!(read, value);
+
+        let plain = dirs.data().join("value.json");
+        std::fs::write(&plain, serde_json::to_string_pretty(&value).unwrap()).unwrap();
+        let read: serde_json::Value =
+            serde_json::from_str(&read_json_file(&plain).unwrap()).unwrap();
+        assert_eq!(read, value);
+        assert!(read_json_file(&dirs.data().join("missing.json")).is_none());
+    }
+
+    #[test]
+    fn clearing_one_kind_leaves_the_others() {
+        let dirs = Dirs::new();
+
+        let report = clear_caches_in(dirs.data(), &dirs.java, &[CacheKind::Catalog]);
+        assert_eq!(report.removed_files, 2);
+        assert_eq!(report.freed_bytes, 15);
+        assert!(!dirs.data().join(CATALOG_CACHE_DIR).exists());
+        assert!(dirs.java.join("jdk-21.tar.gz").exists());
+
+        let report = clear_caches_in(
+            dirs.data(),
+            &dirs.java,
+            &[
+                CacheKind::Archives,
+                CacheKind::Archives,
+                CacheKind::Validation,
+            ],
+        );
+        assert_eq!(report.removed_files, 3);
+        assert_eq!(report.freed_bytes, 142);
+        // Installed runtimes are not a cache
+        assert!(dirs.java.join("temurin-17-jre/bin/java").exists());
+    }
+
+    #[test]
+    fn clearing_detection_results_forgets_remembered_installations() {
+        let dirs = Dirs::new();
+        let config_path = persistence::java_config_path_in(dirs.data());
+        let java = dirs.java.join("temurin-17-jre/bin/java");
+        let known = KnownInstallation::capture(&JavaInstallation {
+            path: java.to_string_lossy().into_owned(),
+            version: "17.0.10".to_string(),
+            arch: "x64".to_string(),
+            vendor: "Eclipse Adoptium".to_string(),
+            source: "system".to_string(),
+            is_64bit: true,
+            is_native_arch: true,
+            blocked_reason: None,
+        })
+        .unwrap();
+        persistence::config_transaction_at(
+            &config_path,
+            |_| {},
+            |config| {
+                config.known_installations = vec![known];
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        clear_caches_in(dirs.data(), &dirs.java, &[CacheKind::Archives]);
+        let remembered = |path| persistence::load_java_config_from(path).0;
+        assert_eq!(remembered(&config_path).known_installations.len(), 1);
+
+        clear_caches_in(dirs.data(), &dirs.java, &[CacheKind::DetectionResults]);
+        assert!(remembered(&config_path).known_installations.is_empty());
+        assert!(java.exists());
+    }
+
+    #[test]
+    fn clearing_skips_archives_of_a_running_install() {
+        let dirs = Dirs::new();
+        let in_use = ArchiveInUse::register(&dirs.java.join("jre-17.zip"));
+
+        let report = clear_caches_in(dirs.data(), &dirs.java, &[CacheKind::All]);
+        assert_eq!(report.removed_files, 3);
+        assert_eq!(report.freed_bytes, 115);
+        assert_eq!(report.skipped_files.len(), 2);
+        assert!(dirs.java.join("jre-17.zip.part").exists());
+        assert!(dirs.java.join("jre-17.zip.part.meta").exists());
+
+        drop(in_use);
+        let report = clear_caches_in(dirs.data(), &dirs.java, &[CacheKind::All]);
+        assert_eq!(report.removed_files, 2);
+        assert!(report.skipped_files.is_empty());
+    }
+}
@@ -1,88 +1,594 @@
-use std::fmt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::Path;
+use ts_rs::TS;
+
+use crate::core::downloader::DownloadError;
+use crate::core::java::priority::describe_version_range;
+use crate::utils::error::{self, ErrorSource};
 
 /// Unified error type for Java component operations
 ///
 /// This enum represents all possible errors that can occur in the Java component,
 /// providing a consistent error handling interface across all modules.
-#[derive(Debug, Clone)]
+/// Variants that wrap a lower-level error keep it as their source; the message is
+/// what we were doing when it happened.
+/// Commands send it to the frontend as a [`JavaErrorPayload`].
+#[derive(Debug, Clone, PartialEq, Eq, TS, thiserror::Error)]
+#[ts(as = "JavaErrorPayload", export_to = "java/error.ts")]
 pub enum JavaError {
     // Java installation not found at the specified path
+    #[error("Java installation not found")]
     NotFound,
     // Invalid Java version format or unable to parse version
+    #[error("Invalid Java version: {0}")]
     InvalidVersion(String),
     // Java installation verification failed (e.g., -version command failed)
+    #[error("Java verification failed: {0}")]
     VerificationFailed(String),
     // Network error during API calls or downloads
-    NetworkError(String),
+    #[error("Network error: {message}{}", network_context(url, status, *attempts))]
+    NetworkError {
+        message: String,
+        url: Option<String>,
+        status: Option<u16>,
+        attempts: u32,
+        #[source]
+        source: Option<ErrorSource>,
+    },
     // File I/O error (reading, writing, or accessing files)
-    IoError(String),
+    #[error("I/O error: {0}")]
+    IoError(String, #[source] Option<ErrorSource>),
     // Timeout occurred during operation
-    Timeout(String),
+    #[error("Operation timeout: {0}")]
+    Timeout(String, #[source] Option<ErrorSource>),
     // Serialization/deserialization error
-    SerializationError(String),
+    #[error("Serialization error: {0}")]
+    SerializationError(String, #[source] Option<ErrorSource>),
     // Invalid configuration or parameters
+    #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
     // Download or installation failed
+    #[error("Download failed: {0}")]
     DownloadFailed(String),
     // Extraction or decompression failed
+    #[error("Extraction failed: {0}")]
     ExtractionFailed(String),
     // Checksum verification failed
+    #[error("Checksum mismatch: {0}")]
     ChecksumMismatch(String),
+    // The user cancelled the download
+    #[error("Download cancelled")]
+    Cancelled,
+    // The disk ran out of space while writing a download or an installation
+    #[error(
+        "Not enough disk space{}. Free up space on the drive and try again",
+        path.as_ref().map(|path| format!(" for {}", path)).unwrap_or_default()
+    )]
+    InsufficientDiskSpace {
+        path: Option<String>,
+        #[source]
+        source: Option<ErrorSource>,
+    },
+    // The OS refused access, often an antivirus blocking freshly written .exe/.dll files
+    #[error(
+        "Access{} was denied. Check the folder's permissions, or add an antivirus exception for the Java folder",
+        path.as_ref().map(|path| format!(" to {}", path)).unwrap_or_default()
+    )]
+    PermissionDenied {
+        path: Option<String>,
+        #[source]
+        source: Option<ErrorSource>,
+    },
+    // Another program holds the file open (a Windows sharing violation)
+    #[error(
+        "{} is in use by another program. Close it or wait for the antivirus scan to finish, then try again",
+        path.as_deref().unwrap_or("A file")
+    )]
+    FileLocked {
+        path: Option<String>,
+        #[source]
+        source: Option<ErrorSource>,
+    },
+    // The install location is on a read-only drive
+    #[error(
+        "{} is on a read-only drive. Choose a writable location for Java installations",
+        path.as_deref().unwrap_or("The Java folder")
+    )]
+    ReadOnlyFilesystem {
+        path: Option<String>,
+        #[source]
+        source: Option<ErrorSource>,
+    },
+    // Neither the OS nor the launcher's location gave a directory for its data
+    #[error(
+        "Can't find a folder for the launcher's data ({0}). Put a portable.flag file next to the launcher to keep its data there"
+    )]
+    DataDirUnavailable(String),
+    // Strict resolution found the explicitly configured Java unusable
+    #[error("The configured Java at {path} can't be used: {reason}")]
+    StrictResolutionFailed { path: String, reason: String },
+    // No Java within the version bounds of a launch was found
+    #[error(
+        "No compatible Java found: this version needs {}. Install it from the Java settings, or turn on installing missing Java automatically",
+        describe_version_range(Some(*required), *max)
+    )]
+    NoCompatibleJava { required: u32, max: Option<u32> },
     // Other unspecified errors
+    #[error("{0}")]
     Other(String),
 }
 
-impl fmt::Display for JavaError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+/// ` (url, HTTP status, n attempts)` after a network error, with whichever parts are known
+fn network_context(url: &Option<String>, status: &Option<u16>, attempts: u32) -> String {
+    let context: Vec<String> = url
+        .clone()
+        .into_iter()
+        .chain(status.map(|status| format!("HTTP {}", status)))
+        .chain((attempts > 1).then(|| format!("{} attempts", attempts)))
+        .collect();
+    if context.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", context.join(", "))
+    }
+}
+
+/// Which `JavaError` variant a payload came from, for the frontend to branch on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "java/error.ts")]
+pub enum JavaErrorKind {
+    NotFound,
+    InvalidVersion,
+    VerificationFailed,
+    NetworkError,
+    IoError,
+    Timeout,
+    SerializationError,
+    InvalidConfig,
+    DownloadFailed,
+    ExtractionFailed,
+    ChecksumMismatch,
+    Cancelled,
+    InsufficientDiskSpace,
+    PermissionDenied,
+    FileLocked,
+    ReadOnlyFilesystem,
+    DataDirUnavailable,
+    StrictResolutionFailed,
+    NoCompatibleJava,
+    Other,
+}
+
+impl JavaError {
+    /// A network error from a single attempt with no response to point at
+    pub fn network(message: impl Into<String>) -> Self {
+        JavaError::NetworkError {
+            message: message.into(),
+            url: None,
+            status: None,
+            attempts: 1,
+            source: None,
+        }
+    }
+
+    /// A network error from a single request to `url`, which answered with `status` if
+    /// it answered at all
+    pub fn network_at(message: impl Into<String>, url: &str, status: Option<u16>) -> Self {
+        JavaError::NetworkError {
+            message: message.into(),
+            url: Some(url.to_string()),
+            status,
+            attempts: 1,
+            source: None,
+        }
+    }
+
+    /// An I/O error from `context`, e.g. "Failed to create version directory". Full
+    /// disks, denied access and the like become their own variant.
+    pub fn io(
+        context: impl Into<String>,
+        err: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        let source = ErrorSource::new(err);
+        actionable_io_error(&source, None)
+            .unwrap_or_else(|| JavaError::IoError(context.into(), Some(source)))
+    }
+
+    /// A (de)serialization error from `context`
+    pub fn serialization(
+        context: impl Into<String>,
+        err: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        JavaError::SerializationError(context.into(), Some(ErrorSource::new(err)))
+    }
+
+    /// The same error, caused by `err`. Variants that never wrap another error are
+    /// returned unchanged.
+    pub fn with_source(mut self, err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        match &mut self {
+            JavaError::NetworkError { source, .. }
+            | JavaError::IoError(_, source)
+            | JavaError::Timeout(_, source)
+            | JavaError::SerializationError(_, source)
+            | JavaError::InsufficientDiskSpace { source, .. }
+            | JavaError::PermissionDenied { source, .. }
+            | JavaError::FileLocked { source, .. }
+            | JavaError::ReadOnlyFilesystem { source, .. } => *source = Some(ErrorSource::new(err)),
+            _ => {}
+        }
+        self
+    }
+
+    pub fn kind(&self) -> JavaErrorKind {
+        match self {
+            JavaError::NotFound => JavaErrorKind::NotFound,
+            JavaError::InvalidVersion(_) => JavaErrorKind::InvalidVersion,
+            JavaError::VerificationFailed(_) => JavaErrorKind::VerificationFailed,
+            JavaError::NetworkError { .. } => JavaErrorKind::NetworkError,
+            JavaError::IoError(..) => JavaErrorKind::IoError,
+            JavaError::Timeout(..) => JavaErrorKind::Timeout,
+            JavaError::SerializationError(..) => JavaErrorKind::SerializationError,
+            JavaError::InvalidConfig(_) => JavaErrorKind::InvalidConfig,
+            JavaError::DownloadFailed(_) => JavaErrorKind::DownloadFailed,
+            JavaError::ExtractionFailed(_) => JavaErrorKind::ExtractionFailed,
+            JavaError::ChecksumMismatch(_) => JavaErrorKind::ChecksumMismatch,
+            JavaError::Cancelled => JavaErrorKind::Cancelled,
+            JavaError::InsufficientDiskSpace { .. } => JavaErrorKind::InsufficientDiskSpace,
+            JavaError::PermissionDenied { .. } => JavaErrorKind::PermissionDenied,
+            JavaError::FileLocked { .. } => JavaErrorKind::FileLocked,
+            JavaError::ReadOnlyFilesystem { .. } => JavaErrorKind::ReadOnlyFilesystem,
+            JavaError::DataDirUnavailable(_) => JavaErrorKind::DataDirUnavailable,
+            JavaError::StrictResolutionFailed { .. } => JavaErrorKind::StrictResolutionFailed,
+            JavaError::NoCompatibleJava { .. } => JavaErrorKind::NoCompatibleJava,
+            JavaError::Other(_) => JavaErrorKind::Other,
+        }
+    }
+
+    /// Whether the error means the network is unreachable rather than a bad response
+    pub fn is_network(&self) -> bool {
+        matches!(
+            self,
+            JavaError::NetworkError { .. } | JavaError::Timeout(..)
+        )
+    }
+
+    /// Stable identifier for docs, support scripts and logs
+    pub fn code(&self) -> &'static str {
+        match self {
+            JavaError::NotFound => "JAVA_E_NOT_FOUND",
+            JavaError::InvalidVersion(_) => "JAVA_E_INVALID_VERSION",
+            JavaError::VerificationFailed(_) => "JAVA_E_VERIFICATION_FAILED",
+            JavaError::NetworkError { .. } => "JAVA_E_NETWORK",
+            JavaError::IoError(..) => "JAVA_E_IO",
+            JavaError::Timeout(..) => "JAVA_E_TIMEOUT",
+            JavaError::SerializationError(..) => "JAVA_E_SERIALIZATION",
+            JavaError::InvalidConfig(_) => "JAVA_E_INVALID_CONFIG",
+            JavaError::DownloadFailed(_) => "JAVA_E_DOWNLOAD_FAILED",
+            JavaError::ExtractionFailed(_) => "JAVA_E_EXTRACTION_FAILED",
+            JavaError::ChecksumMismatch(_) => "JAVA_E_CHECKSUM_MISMATCH",
+            JavaError::Cancelled => "JAVA_E_CANCELLED",
+            JavaError::InsufficientDiskSpace { .. } => "JAVA_E_DISK_FULL",
+            JavaError::PermissionDenied { .. } => "JAVA_E_PERMISSION_DENIED",
+            JavaError::FileLocked { .. } => "JAVA_E_FILE_LOCKED",
+            JavaError::ReadOnlyFilesystem { .. } => "JAVA_E_READ_ONLY_FILESYSTEM",
+            JavaError::DataDirUnavailable(_) => "JAVA_E_DATA_DIR_UNAVAILABLE",
+            JavaError::StrictResolutionFailed { .. } => "JAVA_E_STRICT_RESOLUTION_FAILED",
+            JavaError::NoCompatibleJava { .. } => "JAVA_E_NO_COMPATIBLE_JAVA",
+            JavaError::Other(_) => "JAVA_E_OTHER",
+        }
+    }
+
+    /// The message and its causes prefixed with the code, for logs and errors shown
+    /// as plain text
+    pub fn with_code(&self) -> String {
+        format!("[{}] {}", self.code(), error::display_chain(self))
+    }
+
+    /// Stable key the frontend translates, see `core::i18n`. Errors about a file have
+    /// an `_at` key, whose text names it, when the path is known.
+    pub fn i18n_key(&self) -> &'static str {
+        match self {
+            JavaError::NotFound => "java.error.not_found",
+            JavaError::InvalidVersion(_) => "java.error.invalid_version",
+            JavaError::VerificationFailed(_) => "java.error.verification_failed",
+            JavaError::NetworkError { .. } => "java.error.network",
+            JavaError::IoError(..) => "java.error.io",
+            JavaError::Timeout(..) => "java.error.timeout",
+            JavaError::SerializationError(..) => "java.error.serialization",
+            JavaError::InvalidConfig(_) => "java.error.invalid_config",
+            JavaError::DownloadFailed(_) => "java.error.download_failed",
+            JavaError::ExtractionFailed(_) => "java.error.extraction_failed",
+            JavaError::ChecksumMismatch(_) => "java.error.checksum_mismatch",
+            JavaError::Cancelled => "java.error.cancelled",
+            JavaError::InsufficientDiskSpace { path: None, .. } => "java.error.disk_full",
+            JavaError::InsufficientDiskSpace { .. } => "java.error.disk_full_at",
+            JavaError::PermissionDenied { path: None, .. } => "java.error.permission_denied",
+            JavaError::PermissionDenied { .. } => "java.error.permission_denied_at",
+            JavaError::FileLocked { path: None, .. } => "java.error.file_locked",
+            JavaError::FileLocked { .. } => "java.error.file_locked_at",
+            JavaError::ReadOnlyFilesystem { path: None, .. } => "java.error.read_only_filesystem",
+            JavaError::ReadOnlyFilesystem { .. } => "java.error.read_only_filesystem_at",
+            JavaError::DataDirUnavailable(_) => "java.error.data_dir_unavailable",
+            JavaError::StrictResolutionFailed { .. } => "java.error.strict_resolution_failed",
+            JavaError::NoCompatibleJava { max: None, .. } => "java.error.no_compatible_java",
+            JavaError::NoCompatibleJava {
+                required,
+                max: Some(max),
+            } if required == max => "java.error.no_compatible_java_exact",
+            JavaError::NoCompatibleJava { .. } => "java.error.no_compatible_java_range",
+            JavaError::Other(_) => "java.error.other",
+        }
+    }
+
+    /// Parameters of the `i18n_key` text; the detail itself stays untranslated
+    pub fn message_params(&self) -> BTreeMap<String, String> {
         match self {
-            JavaError::NotFound => write!(f, "Java installation not found"),
-            JavaError::InvalidVersion(msg) => write!(f, "Invalid Java version: {}", msg),
-            JavaError::VerificationFailed(msg) => write!(f, "Java verification failed: {}", msg),
-            JavaError::NetworkError(msg) => write!(f, "Network error: {}", msg),
-            JavaError::IoError(msg) => write!(f, "I/O error: {}", msg),
-            JavaError::Timeout(msg) => write!(f, "Operation timeout: {}", msg),
-            JavaError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
-            JavaError::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
-            JavaError::DownloadFailed(msg) => write!(f, "Download failed: {}", msg),
-            JavaError::ExtractionFailed(msg) => write!(f, "Extraction failed: {}", msg),
-            JavaError::ChecksumMismatch(msg) => write!(f, "Checksum mismatch: {}", msg),
-            JavaError::Other(msg) => write!(f, "{}", msg),
+            JavaError::NotFound | JavaError::Cancelled => BTreeMap::new(),
+            JavaError::InvalidVersion(detail)
+            | JavaError::VerificationFailed(detail)
+            | JavaError::IoError(detail, _)
+            | JavaError::Timeout(detail, _)
+            | JavaError::SerializationError(detail, _)
+            | JavaError::InvalidConfig(detail)
+            | JavaError::DownloadFailed(detail)
+            | JavaError::ExtractionFailed(detail)
+            | JavaError::ChecksumMismatch(detail)
+            | JavaError::DataDirUnavailable(detail)
+            | JavaError::Other(detail) => BTreeMap::from([("detail".to_string(), detail.clone())]),
+            JavaError::NetworkError {
+                message,
+                url,
+                status,
+                attempts,
+                ..
+            } => {
+                let mut params = BTreeMap::from([
+                    ("detail".to_string(), message.clone()),
+                    ("attempts".to_string(), attempts.to_string()),
+                ]);
+                if let Some(url) = url {
+                    params.insert("url".to_string(), url.clone());
+                }
+                if let Some(status) = status {
+                    params.insert("status".to_string(), status.to_string());
+                }
+                params
+            }
+            JavaError::InsufficientDiskSpace { path, .. }
+            | JavaError::PermissionDenied { path, .. }
+            | JavaError::FileLocked { path, .. }
+            | JavaError::ReadOnlyFilesystem { path, .. } => path
+                .iter()
+                .map(|path| ("path".to_string(), path.clone()))
+                .collect(),
+            JavaError::StrictResolutionFailed { path, reason } => BTreeMap::from([
+                ("path".to_string(), path.clone()),
+                ("reason".to_string(), reason.clone()),
+            ]),
+            JavaError::NoCompatibleJava { required, max } => {
+                let mut params = BTreeMap::from([("required".to_string(), required.to_string())]);
+                if let Some(max) = max {
+                    params.insert("max".to_string(), max.to_string());
+                }
+                params
+            }
+        }
+    }
+
+    /// An I/O error from `context` involving `path`, see [`JavaError::io`]
+    pub fn io_at(
+        context: impl Into<String>,
+        path: &Path,
+        err: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        let source = ErrorSource::new(err);
+        actionable_io_error(&source, Some(path))
+            .unwrap_or_else(|| JavaError::IoError(context.into(), Some(source)))
+    }
+}
+
+/// The variant telling the user what to do about a common OS error in the chain of
+/// `source`, whose raw message ("os error 28") wouldn't mean anything to them
+fn actionable_io_error(source: &ErrorSource, path: Option<&Path>) -> Option<JavaError> {
+    let err = error::find_in_chain::<std::io::Error>(source)?;
+    let path = path.map(|path| path.display().to_string());
+    let source = Some(source.clone());
+    Some(match err.kind() {
+        ErrorKind::StorageFull | ErrorKind::QuotaExceeded => {
+            JavaError::InsufficientDiskSpace { path, source }
+        }
+        ErrorKind::ReadOnlyFilesystem => JavaError::ReadOnlyFilesystem { path, source },
+        ErrorKind::ExecutableFileBusy => JavaError::FileLocked { path, source },
+        _ if is_sharing_violation(err) => JavaError::FileLocked { path, source },
+        ErrorKind::PermissionDenied => JavaError::PermissionDenied { path, source },
+        _ => return None,
+    })
+}
+
+/// `ERROR_SHARING_VIOLATION` or `ERROR_LOCK_VIOLATION`, which std reports as an
+/// uncategorized error
+#[cfg(windows)]
+fn is_sharing_violation(err: &std::io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(32 | 33))
+}
+
+#[cfg(not(windows))]
+fn is_sharing_violation(_err: &std::io::Error) -> bool {
+    false
+}
+
+/// A `JavaError` as sent to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/error.ts")]
+pub struct JavaErrorPayload {
+    pub kind: JavaErrorKind,
+    /// See `JavaError::code`
+    pub code: String,
+    /// English text, for logs and as a fallback
+    pub message: String,
+    pub message_key: String,
+    /// The variant's fields: `detail`, `path` and `reason` for strict resolution, or
+    /// `required` and `max` when no Java fits
+    pub params: BTreeMap<String, String>,
+    /// Messages of the errors that caused this one, outermost first
+    #[serde(default)]
+    pub details: Vec<String>,
+}
+
+impl From<&JavaError> for JavaErrorPayload {
+    fn from(err: &JavaError) -> Self {
+        JavaErrorPayload {
+            kind: err.kind(),
+            code: err.code().to_string(),
+            message: err.to_string(),
+            message_key: err.i18n_key().to_string(),
+            params: err.message_params(),
+            details: error::causes(err),
+        }
+    }
+}
+
+impl From<JavaErrorPayload> for JavaError {
+    fn from(payload: JavaErrorPayload) -> Self {
+        let mut params = payload.params;
+        let mut take = |name: &str| params.remove(name).unwrap_or_default();
+        let source = ErrorSource::from_chain(payload.details);
+        match payload.kind {
+            JavaErrorKind::NotFound => JavaError::NotFound,
+            JavaErrorKind::InvalidVersion => JavaError::InvalidVersion(take("detail")),
+            JavaErrorKind::VerificationFailed => JavaError::VerificationFailed(take("detail")),
+            JavaErrorKind::NetworkError => JavaError::NetworkError {
+                message: take("detail"),
+                url: Some(take("url")).filter(|url| !url.is_empty()),
+                status: take("status").parse().ok(),
+                attempts: take("attempts").parse().unwrap_or(1),
+                source,
+            },
+            JavaErrorKind::IoError => JavaError::IoError(take("detail"), source),
+            JavaErrorKind::Timeout => JavaError::Timeout(take("detail"), source),
+            JavaErrorKind::SerializationError => {
+                JavaError::SerializationError(take("detail"), source)
+            }
+            JavaErrorKind::InvalidConfig => JavaError::InvalidConfig(take("detail")),
+            JavaErrorKind::DownloadFailed => JavaError::DownloadFailed(take("detail")),
+            JavaErrorKind::ExtractionFailed => JavaError::ExtractionFailed(take("detail")),
+            JavaErrorKind::ChecksumMismatch => JavaError::ChecksumMismatch(take("detail")),
+            JavaErrorKind::Cancelled => JavaError::Cancelled,
+            JavaErrorKind::InsufficientDiskSpace => JavaError::InsufficientDiskSpace {
+                path: Some(take("path")).filter(|path| !path.is_empty()),
+                source,
+            },
+            JavaErrorKind::PermissionDenied => JavaError::PermissionDenied {
+                path: Some(take("path")).filter(|path| !path.is_empty()),
+                source,
+            },
+            JavaErrorKind::FileLocked => JavaError::FileLocked {
+                path: Some(take("path")).filter(|path| !path.is_empty()),
+                source,
+            },
+            JavaErrorKind::ReadOnlyFilesystem => JavaError::ReadOnlyFilesystem {
+                path: Some(take("path")).filter(|path| !path.is_empty()),
+                source,
+            },
+            JavaErrorKind::DataDirUnavailable => JavaError::DataDirUnavailable(take("detail")),
+            JavaErrorKind::StrictResolutionFailed => JavaError::StrictResolutionFailed {
+                path: take("path"),
+                reason: take("reason"),
+            },
+            JavaErrorKind::NoCompatibleJava => JavaError::NoCompatibleJava {
+                required: take("required").parse().unwrap_or(0),
+                max: take("max").parse().ok(),
+            },
+            JavaErrorKind::Other => JavaError::Other(take("detail")),
         }
     }
 }
 
-impl std::error::Error for JavaError {}
+impl Serialize for JavaError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JavaErrorPayload::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JavaError {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        JavaErrorPayload::deserialize(deserializer).map(JavaError::from)
+    }
+}
 
-/// Convert JavaError to String for Tauri command results
+/// Convert JavaError to String for Tauri command results, keeping its causes
 impl From<JavaError> for String {
     fn from(err: JavaError) -> Self {
-        err.to_string()
+        error::display_chain(&err)
     }
 }
 
 /// Convert std::io::Error to JavaError
 impl From<std::io::Error> for JavaError {
     fn from(err: std::io::Error) -> Self {
-        JavaError::IoError(err.to_string())
+        let message = err.to_string();
+        actionable_io_error(&ErrorSource::new(err), None)
+            .unwrap_or(JavaError::IoError(message, None))
     }
 }
 
 /// Convert serde_json::Error to JavaError
 impl From<serde_json::Error> for JavaError {
     fn from(err: serde_json::Error) -> Self {
-        JavaError::SerializationError(err.to_string())
+        JavaError::SerializationError(err.to_string(), None)
     }
 }
 
-/// Convert reqwest::Error to JavaError
+/// Convert reqwest::Error to JavaError, keeping it as the source so the underlying
+/// connection or TLS error isn't lost
 impl From<reqwest::Error> for JavaError {
     fn from(err: reqwest::Error) -> Self {
         if err.is_timeout() {
-            JavaError::Timeout(err.to_string())
-        } else if err.is_connect() || err.is_request() {
-            JavaError::NetworkError(err.to_string())
-        } else {
-            JavaError::NetworkError(err.to_string())
+            return JavaError::Timeout(
+                "Request timed out".to_string(),
+                Some(ErrorSource::new(err)),
+            );
+        }
+        JavaError::NetworkError {
+            message: "Request failed".to_string(),
+            url: err.url().map(|url| url.to_string()),
+            status: err.status().map(|status| status.as_u16()),
+            attempts: 1,
+            source: Some(ErrorSource::new(err)),
+        }
+    }
+}
+
+/// Convert a failed archive download to JavaError
+impl From<DownloadError> for JavaError {
+    fn from(err: DownloadError) -> Self {
+        match err {
+            DownloadError::Io { message, source } => source
+                .as_ref()
+                .and_then(|source| actionable_io_error(source, None))
+                .unwrap_or(JavaError::IoError(message, source)),
+            DownloadError::Request {
+                url,
+                status,
+                message,
+                source,
+            } => JavaError::NetworkError {
+                message,
+                url: Some(url).filter(|url| !url.is_empty()),
+                status,
+                attempts: 1,
+                source,
+            },
+            DownloadError::ChecksumMismatch { .. } => JavaError::ChecksumMismatch(err.to_string()),
+            DownloadError::Cancelled => JavaError::Cancelled,
+            DownloadError::Stalled { .. } => JavaError::Timeout(err.to_string(), None),
+            DownloadError::InvalidContent { .. } | DownloadError::SizeMismatch { .. } => {
+                JavaError::DownloadFailed(err.to_string())
+            }
         }
     }
 }
@@ -93,3 +599,277 @@ impl From<String> for JavaError {
         JavaError::Other(err)
     }
 }
+
+#[cfg(test)]
+pub(crate) fn every_variant() -> Vec<JavaError> {
+    let detail = || "detail".to_string();
+    vec![
+        JavaError::NotFound,
+        JavaError::InvalidVersion(detail()),
+        JavaError::VerificationFailed(detail()),
+        JavaError::network(detail()),
+        JavaError::IoError(detail(), None),
+        JavaError::Timeout(detail(), None),
+        JavaError::SerializationError(detail(), None),
+        JavaError::InvalidConfig(detail()),
+        JavaError::DownloadFailed(detail()),
+        JavaError::ExtractionFailed(detail()),
+        JavaError::ChecksumMismatch(detail()),
+        JavaError::Cancelled,
+        JavaError::InsufficientDiskSpace {
+            path: None,
+            source: None,
+        },
+        JavaError::InsufficientDiskSpace {
+            path: Some(detail()),
+            source: None,
+        },
+        JavaError::PermissionDenied {
+            path: None,
+            source: None,
+        },
+        JavaError::PermissionDenied {
+            path: Some(detail()),
+            source: None,
+        },
+        JavaError::FileLocked {
+            path: None,
+            source: None,
+        },
+        JavaError::FileLocked {
+            path: Some(detail()),
+            source: None,
+        },
+        JavaError::ReadOnlyFilesystem {
+            path: None,
+            source: None,
+        },
+        JavaError::ReadOnlyFilesystem {
+            path: Some(detail()),
+            source: None,
+        },
+        JavaError::DataDirUnavailable(detail()),
+        JavaError::StrictResolutionFailed {
+            path: detail(),
+            reason: detail(),
+        },
+        JavaError::NoCompatibleJava {
+            required: 21,
+            max: None,
+        },
+        JavaError::NoCompatibleJava {
+            required: 8,
+            max: Some(8),
+        },
+        JavaError::NoCompatibleJava {
+            required: 8,
+            max: Some(11),
+        },
+        JavaError::Other(detail()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn codes_are_unique() {
+        let errors = every_variant();
+        let codes: BTreeSet<_> = errors.iter().map(JavaError::code).collect();
+        let kinds: BTreeSet<_> = errors
+            .iter()
+            .map(|err| format!("{:?}", err.kind()))
+            .collect();
+        assert_eq!(codes.len(), kinds.len());
+        assert!(codes.iter().all(|code| code.starts_with("JAVA_E_")));
+        assert_eq!(
+            JavaError::NotFound.with_code(),
+            "[JAVA_E_NOT_FOUND] Java installation not found"
+        );
+    }
+
+    #[test]
+    fn errors_serialize_with_their_kind_and_round_trip() {
+        let err = JavaError::ChecksumMismatch("expected abc".to_string());
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "checksum_mismatch");
+        assert_eq!(json["code"], "JAVA_E_CHECKSUM_MISMATCH");
+        assert_eq!(json["message"], "Checksum mismatch: expected abc");
+        assert_eq!(json["messageKey"], "java.error.checksum_mismatch");
+        assert_eq!(json["params"]["detail"], "expected abc");
+        assert_eq!(serde_json::from_value::<JavaError>(json).unwrap(), err);
+
+        let err = JavaError::StrictResolutionFailed {
+            path: "/opt/java/bin/java".to_string(),
+            reason: "not found".to_string(),
+        };
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "strict_resolution_failed");
+        assert_eq!(serde_json::from_value::<JavaError>(json).unwrap(), err);
+    }
+
+    #[test]
+    fn network_errors_name_the_endpoint_on_one_line() {
+        assert_eq!(
+            JavaError::network("connection reset").to_string(),
+            "Network error: connection reset"
+        );
+
+        let err = JavaError::NetworkError {
+            message: "Service unavailable".to_string(),
+            url: Some("https://api.adoptium.net/v3/info/available_releases".to_string()),
+            status: Some(503),
+            attempts: 3,
+            source: None,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Network error: Service unavailable \
+             (https://api.adoptium.net/v3/info/available_releases, HTTP 503, 3 attempts)"
+        );
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["params"]["status"], "503");
+        assert_eq!(json["params"]["attempts"], "3");
+        assert_eq!(serde_json::from_value::<JavaError>(json).unwrap(), err);
+        assert_eq!(
+            serde_json::from_value::<JavaError>(
+                serde_json::to_value(JavaError::network("offline")).unwrap()
+            )
+            .unwrap(),
+            JavaError::network("offline")
+        );
+    }
+
+    #[test]
+    fn download_errors_keep_their_meaning() {
+        let err = JavaError::from(DownloadError::Request {
+            url: "https://example.com/jdk.tar.gz".to_string(),
+            status: Some(404),
+            message: "Server returned error: 404 Not Found".to_string(),
+            source: None,
+        });
+        assert_eq!(
+            err,
+            JavaError::NetworkError {
+                message: "Server returned error: 404 Not Found".to_string(),
+                url: Some("https://example.com/jdk.tar.gz".to_string()),
+                status: Some(404),
+                attempts: 1,
+                source: None,
+            }
+        );
+        assert_eq!(
+            JavaError::from(DownloadError::Cancelled),
+            JavaError::Cancelled
+        );
+        assert_eq!(
+            JavaError::from(DownloadError::ChecksumMismatch {
+                expected: "abc".to_string(),
+                actual: Some("def".to_string()),
+            })
+            .kind(),
+            JavaErrorKind::ChecksumMismatch
+        );
+        assert_eq!(
+            JavaError::from(DownloadError::Stalled { timeout_ms: 30_000 }).kind(),
+            JavaErrorKind::Timeout
+        );
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("part file is locked")]
+    struct Locked(#[source] std::io::Error);
+
+    #[test]
+    fn causes_survive_conversion_and_serialization() {
+        let os = std::io::Error::other("sector not found");
+        let download = DownloadError::io("Failed to open part file", Locked(os));
+        let err = JavaError::from(download);
+
+        assert_eq!(
+            error::chain(&err),
+            vec![
+                "I/O error: Failed to open part file",
+                "part file is locked",
+                "sector not found"
+            ]
+        );
+        assert_eq!(
+            err.with_code(),
+            "[JAVA_E_IO] I/O error: Failed to open part file: part file is locked: sector not found"
+        );
+
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["message"], "I/O error: Failed to open part file");
+        assert_eq!(
+            json["details"],
+            serde_json::json!(["part file is locked", "sector not found"])
+        );
+        let back: JavaError = serde_json::from_value(json).unwrap();
+        assert_eq!(back, err);
+        assert_eq!(error::chain(&back), error::chain(&err));
+    }
+
+    #[test]
+    fn common_os_errors_tell_the_user_what_to_do() {
+        use std::io::Error as IoError;
+
+        let full = JavaError::from(IoError::from(ErrorKind::StorageFull));
+        assert_eq!(full.kind(), JavaErrorKind::InsufficientDiskSpace);
+        assert_eq!(full.i18n_key(), "java.error.disk_full");
+
+        let denied = JavaError::io_at(
+            "Failed to create version directory",
+            Path::new("/opt/java/temurin-21"),
+            IoError::from(ErrorKind::PermissionDenied),
+        );
+        assert!(matches!(
+            &denied,
+            JavaError::PermissionDenied { path: Some(path), source: Some(_) }
+                if path == "/opt/java/temurin-21"
+        ));
+        assert_eq!(denied.i18n_key(), "java.error.permission_denied_at");
+        assert_eq!(denied.message_params()["path"], "/opt/java/temurin-21");
+        assert!(denied.to_string().contains("/opt/java/temurin-21"));
+
+        let read_only = JavaError::from(DownloadError::io(
+            "Failed to open part file",
+            IoError::from(ErrorKind::ReadOnlyFilesystem),
+        ));
+        assert_eq!(read_only.kind(), JavaErrorKind::ReadOnlyFilesystem);
+        assert_eq!(read_only.i18n_key(), "java.error.read_only_filesystem");
+
+        let missing = JavaError::io("Failed to read file", IoError::from(ErrorKind::NotFound));
+        assert_eq!(missing.kind(), JavaErrorKind::IoError);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn raw_unix_errors_are_mapped() {
+        use std::io::Error as IoError;
+
+        let kind = |code| JavaError::from(IoError::from_raw_os_error(code)).kind();
+        assert_eq!(kind(28), JavaErrorKind::InsufficientDiskSpace); // ENOSPC
+        assert_eq!(kind(13), JavaErrorKind::PermissionDenied); // EACCES
+        assert_eq!(kind(30), JavaErrorKind::ReadOnlyFilesystem); // EROFS
+        assert_eq!(kind(26), JavaErrorKind::FileLocked); // ETXTBSY
+        assert_eq!(
+            JavaError::from(IoError::from_raw_os_error(28)).i18n_key(),
+            "java.error.disk_full"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn raw_windows_errors_are_mapped() {
+        use std::io::Error as IoError;
+
+        let kind = |code| JavaError::from(IoError::from_raw_os_error(code)).kind();
+        assert_eq!(kind(112), JavaErrorKind::InsufficientDiskSpace); // ERROR_DISK_FULL
+        assert_eq!(kind(5), JavaErrorKind::PermissionDenied); // ERROR_ACCESS_DENIED
+        assert_eq!(kind(32), JavaErrorKind::FileLocked); // ERROR_SHARING_VIOLATION
+        assert_eq!(kind(33), JavaErrorKind::FileLocked); // ERROR_LOCK_VIOLATION
+    }
+}
@@ -0,0 +1,177 @@
+//! Installations found by the last full scan, kept across restarts.
+//!
+//! They are shown right away on startup, before anything is verified, and then checked
+//! in the background: entries whose binary is unchanged are kept as they are, changed
+//! ones are checked again and vanished ones dropped.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use ts_rs::TS;
+
+use super::validation::{self, binary_mtime};
+use super::{JavaInstallation, blocklist, persistence};
+
+/// A remembered installation and what its binary looked like when it was checked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/known.ts")]
+pub struct KnownInstallation {
+    pub installation: JavaInstallation,
+    pub canonical_path: String,
+    /// Modification time of the binary in seconds since the epoch
+    pub binary_mtime: u64,
+}
+
+/// Installations for the UI, with whether they were verified in this session
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/known.ts")]
+pub struct KnownInstallations {
+    pub installations: Vec<JavaInstallation>,
+    pub verified: bool,
+}
+
+impl KnownInstallation {
+    /// Remember `installation` as its binary is now; `None` if the binary is gone
+    pub fn capture(installation: &JavaInstallation) -> Option<Self> {
+        let canonical = std::fs::canonicalize(&installation.path).ok()?;
+        Some(Self {
+            installation: installation.clone(),
+            binary_mtime: binary_mtime(&canonical)?,
+            canonical_path: canonical.to_string_lossy().into_owned(),
+        })
+    }
+}
+
+/// What a cheap look at a remembered binary found
+#[derive(Debug, PartialEq, Eq)]
+enum Freshness {
+    Unchanged,
+    Changed,
+    Vanished,
+}
+
+fn freshness(known: &KnownInstallation) -> Freshness {
+    match binary_mtime(Path::new(&known.canonical_path)) {
+        None => Freshness::Vanished,
+        Some(mtime) if mtime == known.binary_mtime => Freshness::Unchanged,
+        Some(_) => Freshness::Changed,
+    }
+}
+
+/// Confirm each remembered entry, running Java again only for changed binaries
+async fn revalidate(known: Vec<KnownInstallation>) -> Vec<KnownInstallation> {
+    let mut verified = Vec::with_capacity(known.len());
+    for entry in known {
+        match freshness(&entry) {
+            Freshness::Unchanged => verified.push(entry),
+            Freshness::Changed => {
+                let path = PathBuf::from(&entry.canonical_path);
+                if let Some(installation) = validation::check_java_installation(&path).await
+                    && let Some(entry) = KnownInstallation::capture(&installation)
+                {
+                    verified.push(entry);
+                }
+            }
+            Freshness::Vanished => {}
+        }
+    }
+    verified
+}
+
+/// The remembered installations, unverified, for display while detection runs
+pub fn get_known_installations(app_handle: &AppHandle) -> KnownInstallations {
+    let config = persistence::load_java_config(app_handle);
+    KnownInstallations {
+        installations: blocklist::flag_blocked(
+            config
+                .known_installations
+                .into_iter()
+                .map(|known| known.installation)
+                .collect(),
+            &config.settings,
+        ),
+        verified: false,
+    }
+}
+
+/// Check the remembered installations in the background, storing and emitting
+/// `java-installations-changed` with the ones that are still there
+pub fn spawn_revalidation(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let config = persistence::load_java_config(&app_handle);
+        let known = config.known_installations;
+        let verified = revalidate(known.clone()).await;
+        if verified != known
+            && let Err(e) = persistence::set_known_installations(&app_handle, verified.clone())
+        {
+            log::warn!("Failed to store verified Java installations: {}", e);
+        }
+        let _ = app_handle.emit(
+            "java-installations-changed",
+            KnownInstallations {
+                installations: blocklist::flag_blocked(
+                    verified.into_iter().map(|k| k.installation).collect(),
+                    &config.settings,
+                ),
+                verified: true,
+            },
+        );
+    });
+}
+
+/// Remember the result of a full scan
+pub fn remember(app_handle: &AppHandle, installations: &[JavaInstallation]) {
+    let known = installations
+        .iter()
+        .filter_map(KnownInstallation::capture)
+        .collect();
+    if let Err(e) = persistence::set_known_installations(app_handle, known) {
+        log::warn!("Failed to store detected Java installations: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn installation(path: &Path) -> JavaInstallation {
+        JavaInstallation {
+            path: path.to_string_lossy().into_owned(),
+            version: "21.0.2".to_string(),
+            arch: "x64".to_string(),
+            vendor: "Eclipse Adoptium".to_string(),
+            source: "system".to_string(),
+            is_64bit: true,
+            is_native_arch: true,
+            blocked_reason: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn revalidation_keeps_unchanged_entries_and_drops_vanished_ones() {
+        let dir = TestDir::new("known");
+        let kept = dir.path().join("java-kept");
+        let removed = dir.path().join("java-removed");
+        std::fs::write(&kept, b"").unwrap();
+        std::fs::write(&removed, b"").unwrap();
+
+        let known: Vec<_> = [&kept, &removed]
+            .into_iter()
+            .map(|path| KnownInstallation::capture(&installation(path)).unwrap())
+            .collect();
+        std::fs::remove_file(&removed).unwrap();
+
+        assert_eq!(freshness(&known[0]), Freshness::Unchanged);
+        assert_eq!(freshness(&known[1]), Freshness::Vanished);
+        let mut touched = known[0].clone();
+        touched.binary_mtime -= 60;
+        assert_eq!(freshness(&touched), Freshness::Changed);
+
+        assert_eq!(revalidate(known.clone()).await, vec![known[0].clone()]);
+        // A changed file that no longer runs as Java is dropped too
+        assert!(revalidate(vec![touched]).await.is_empty());
+    }
+}
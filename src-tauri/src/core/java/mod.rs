@@ -2,15 +2,20 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tauri::{AppHandle, Emitter, Manager};
 
+pub mod blocklist;
+pub mod cache;
 pub mod detection;
 pub mod error;
+pub mod known;
 pub mod persistence;
 pub mod priority;
 pub mod provider;
 pub mod providers;
+pub mod requirements;
+pub mod transfer;
 pub mod validation;
 
-pub use error::JavaError;
+pub use error::{JavaError, JavaErrorKind};
 use ts_rs::TS;
 
 /// Remove the UNC prefix (\\?\) from Windows paths
@@ -25,14 +30,117 @@ pub fn strip_unc_prefix(path: PathBuf) -> PathBuf {
     path
 }
 
-use crate::core::downloader::{DownloadQueue, JavaDownloadProgress, PendingJavaDownload};
+use crate::core::downloader::{
+    DownloadQueue, JavaDownloadGuard, JavaDownloadProgress, PendingJavaDownload,
+};
 use crate::utils::zip;
 use provider::JavaProvider;
 use providers::AdoptiumProvider;
 
+/// Catalog cache lifetime unless `catalog_cache_ttl_secs` is set
 const CACHE_DURATION_SECS: u64 = 24 * 60 * 60;
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+/// Failed attempts after which a pending download is no longer resumed automatically
+pub const MAX_RESUME_ATTEMPTS: u32 = 3;
+
+/// One lock per version directory, so two installs of the same runtime don't
+/// download and extract over each other
+static INSTALL_LOCKS: std::sync::LazyLock<
+    std::sync::Mutex<std::collections::HashMap<PathBuf, std::sync::Arc<tokio::sync::Mutex<()>>>>,
+> = std::sync::LazyLock::new(Default::default);
+
+/// Set while a background catalog refresh runs, so expired-cache lookups don't
+/// start one each
+static CATALOG_REFRESHING: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+/// Clears `CATALOG_REFRESHING` when the background refresh ends, even by panic
+struct CatalogRefreshGuard;
+
+impl CatalogRefreshGuard {
+    fn acquire() -> Option<Self> {
+        CATALOG_REFRESHING
+            .compare_exchange(
+                false,
+                true,
+                std::sync::atomic::Ordering::AcqRel,
+                std::sync::atomic::Ordering::Acquire,
+            )
+            .ok()
+            .map(|_| Self)
+    }
+}
+
+impl Drop for CatalogRefreshGuard {
+    fn drop(&mut self) {
+        CATALOG_REFRESHING.store(false, std::sync::atomic::Ordering::Release);
+    }
+}
+
+/// Catalogs read from or written to the disk cache this session, by cache file, so
+/// lookups don't reread and reparse the file each time
+static CATALOG_MEMORY: std::sync::LazyLock<
+    std::sync::RwLock<std::collections::HashMap<PathBuf, JavaCatalog>>,
+> = std::sync::LazyLock::new(Default::default);
+
+/// Drop the catalogs held in memory for caches under `data_dir`, so the next lookup
+/// reads the disk cache
+pub(crate) fn forget_cached_catalogs(data_dir: &std::path::Path) {
+    CATALOG_MEMORY
+        .write()
+        .unwrap()
+        .retain(|path, _| !path.starts_with(data_dir));
+}
+
+/// The catalog fetch in flight, if any. Callers arriving while it runs await the
+/// same result instead of starting a fetch of their own.
+#[derive(Default)]
+struct CatalogFetches(std::sync::Mutex<Option<futures::future::Shared<CatalogRefresh>>>);
+
+impl CatalogFetches {
+    fn join(
+        &self,
+        start: impl FnOnce() -> CatalogRefresh,
+    ) -> futures::future::Shared<CatalogRefresh> {
+        use futures::FutureExt;
+
+        let mut current = self.0.lock().unwrap();
+        match current.as_ref() {
+            Some(fetch) if fetch.peek().is_none() => fetch.clone(),
+            _ => current.insert(start().shared()).clone(),
+        }
+    }
+}
+
+static CATALOG_FETCHES: std::sync::LazyLock<CatalogFetches> =
+    std::sync::LazyLock::new(Default::default);
+
+/// Installations found by the last scan, returned when an automatic detection is
+/// skipped because the last scan is recent
+static DETECTED_INSTALLATIONS: std::sync::RwLock<Option<Vec<JavaInstallation>>> =
+    std::sync::RwLock::new(None);
+
+/// Drop the remembered scan results, so the next detection scans again
+pub(crate) fn forget_detected_installations() {
+    *DETECTED_INSTALLATIONS.write().unwrap() = None;
+}
+
+/// Whether an automatic detection at `now` should scan again; a clock that went
+/// backwards always does
+fn should_redetect(last_detection_time: u64, now: u64, min_interval_secs: u32) -> bool {
+    now < last_detection_time || now - last_detection_time >= u64::from(min_interval_secs)
+}
+
+fn install_lock(version_dir: &std::path::Path) -> std::sync::Arc<tokio::sync::Mutex<()>> {
+    INSTALL_LOCKS
+        .lock()
+        .unwrap()
+        .entry(version_dir.to_path_buf())
+        .or_default()
+        .clone()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export, export_to = "java/core.ts")]
 pub struct JavaInstallation {
@@ -42,10 +150,22 @@ pub struct JavaInstallation {
     pub vendor: String,
     pub source: String,
     pub is_64bit: bool,
+    /// Built for the machine's architecture rather than run under emulation
+    #[serde(default = "assume_native_arch")]
+    pub is_native_arch: bool,
+    /// Why launches won't use it; only filled in on lists for display, see `blocklist`
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Installations remembered before `is_native_arch` existed count as native
+fn assume_native_arch() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
 #[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "java/core.ts")]
 pub enum ImageType {
     Jre,
     Jdk,
@@ -91,6 +211,50 @@ pub struct JavaCatalog {
     pub available_major_versions: Vec<u32>,
     pub lts_versions: Vec<u32>,
     pub cached_at: u64,
+    /// `ETag` of the release list response, used to revalidate the cache
+    #[serde(default)]
+    pub etag: Option<String>,
+    /// `Last-Modified` of the release list response, used to revalidate the cache
+    #[serde(default)]
+    pub last_modified: Option<String>,
+}
+
+impl JavaCatalog {
+    /// Seconds since the catalog was fetched or revalidated
+    pub fn age_secs(&self, now: u64) -> u64 {
+        now.saturating_sub(self.cached_at)
+    }
+
+    /// Whether the catalog is younger than `ttl_secs`
+    pub fn is_fresh(&self, now: u64, ttl_secs: u64) -> bool {
+        self.age_secs(now) < ttl_secs
+    }
+}
+
+/// A catalog as returned to the UI, with its age so it can show when it was updated
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "java/core.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct JavaCatalogResult {
+    #[serde(flatten)]
+    pub catalog: JavaCatalog,
+    #[ts(type = "number")]
+    pub age_secs: u64,
+    /// The cache had expired; a fresh catalog follows in `java-catalog-refreshed`
+    pub stale: bool,
+    /// The network was unreachable, so an expired cache was served as is
+    pub offline: bool,
+}
+
+impl JavaCatalogResult {
+    fn new(catalog: JavaCatalog, now: u64, stale: bool) -> Self {
+        Self {
+            age_secs: catalog.age_secs(now),
+            catalog,
+            stale,
+            offline: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, TS)]
@@ -105,108 +269,532 @@ pub struct JavaDownloadInfo {
     pub image_type: String,       // "jre" or "jdk"
 }
 
-pub fn get_java_install_dir(app_handle: &AppHandle) -> PathBuf {
-    app_handle.path().app_data_dir().unwrap().join("java")
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/core.ts")]
+pub enum ResumeJavaDownloadFailureReason {
+    /// The download or installation failed on this attempt
+    Failed,
+    /// Failed `MAX_RESUME_ATTEMPTS` times already, so it was not tried again
+    GivenUp,
+    /// The resume was cancelled while this download was running
+    Cancelled,
 }
 
-fn get_catalog_cache_path(app_handle: &AppHandle) -> PathBuf {
-    app_handle
-        .path()
-        .app_data_dir()
-        .unwrap()
-        .join("java_catalog_cache.json")
+impl From<&JavaError> for ResumeJavaDownloadFailureReason {
+    fn from(error: &JavaError) -> Self {
+        match error {
+            JavaError::Cancelled => ResumeJavaDownloadFailureReason::Cancelled,
+            _ => ResumeJavaDownloadFailureReason::Failed,
+        }
+    }
 }
 
-pub fn load_cached_catalog(app_handle: &AppHandle) -> Option<JavaCatalog> {
-    let cache_path = get_catalog_cache_path(app_handle);
-    if !cache_path.exists() {
-        return None;
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/core.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeJavaDownloadFailure {
+    /// The queue entry, including its attempt count and last error
+    pub download: PendingJavaDownload,
+    pub reason: ResumeJavaDownloadFailureReason,
+    /// Kind of the error this attempt failed with; `None` when it was not tried
+    pub error_kind: Option<JavaErrorKind>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/core.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeJavaDownloadsResult {
+    /// Id carried by the progress events of this resume
+    pub id: String,
+    pub installed: Vec<JavaInstallation>,
+    pub failed: Vec<ResumeJavaDownloadFailure>,
+}
+
+/// A finished install along with the id its progress events carried
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/core.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct JavaDownloadResult {
+    pub id: String,
+    pub installation: JavaInstallation,
+}
+
+/// Id for a new install or resume operation, used in its progress events and to cancel it
+pub fn new_download_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn ensure_not_cancelled(download_id: &str) -> Result<(), JavaError> {
+    if crate::core::downloader::is_java_download_cancelled(download_id) {
+        return Err(JavaError::Cancelled);
     }
+    Ok(())
+}
 
-    // Read cache file
-    let content = std::fs::read_to_string(&cache_path).ok()?;
-    let catalog: JavaCatalog = serde_json::from_str(&content).ok()?;
+/// Download and install a Java runtime as its own cancellable operation
+pub async fn install_java(
+    app_handle: &AppHandle,
+    major_version: u32,
+    image_type: ImageType,
+    custom_path: Option<PathBuf>,
+    download_segments: usize,
+) -> Result<JavaDownloadResult, JavaError> {
+    let id = new_download_id();
+    let _guard = JavaDownloadGuard::register(&id);
+    let installation = download_and_install_java(
+        app_handle,
+        &id,
+        major_version,
+        image_type,
+        custom_path,
+        download_segments,
+    )
+    .await?;
+    Ok(JavaDownloadResult { id, installation })
+}
 
-    // Get current time in seconds since UNIX_EPOCH
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+/// Install Java `major_version` for a launch that found none, with the configured
+/// image type. `download_id` lets the launch's UI cancel it.
+pub async fn install_java_for_launch(
+    app_handle: &AppHandle,
+    download_id: &str,
+    major_version: u32,
+    download_segments: usize,
+) -> Result<JavaInstallation, JavaError> {
+    let _guard = JavaDownloadGuard::register(download_id);
+    let settings = persistence::get_java_settings(app_handle);
+    // Adoptium is the only provider in `providers::PROVIDER_NAMES` so far
+    download_and_install_java_with_provider(
+        app_handle,
+        &AdoptiumProvider::new(),
+        download_id,
+        major_version,
+        settings.default_image_type,
+        None,
+        download_segments,
+    )
+    .await
+}
 
-    // Check if cache is still valid
-    if now - catalog.cached_at < CACHE_DURATION_SECS {
-        Some(catalog)
-    } else {
-        None
+/// Where runtimes are installed: the configured directory, or `java` in the app data
+pub fn get_java_install_dir(app_handle: &AppHandle) -> Result<PathBuf, JavaError> {
+    match persistence::get_java_settings(app_handle).install_dir {
+        Some(install_dir) => Ok(install_dir),
+        None => Ok(crate::core::paths::try_data_root(app_handle)?.join("java")),
+    }
+}
+
+/// Single-file cache written before catalogs were cached per provider
+const LEGACY_CATALOG_CACHE_FILE: &str = "java_catalog_cache.json";
+const CATALOG_CACHE_DIR: &str = "java_catalog_cache";
+
+/// Bump whenever `JavaCatalog` or `JavaReleaseInfo` change shape; cache files of an
+/// unknown version are discarded instead of being read with wrong defaults
+const CATALOG_CACHE_SCHEMA_VERSION: u32 = 2;
+
+/// On-disk form of a cached catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedCatalog {
+    schema_version: u32,
+    catalog: JavaCatalog,
+}
+
+/// Version 1 files were a bare `JavaCatalog` without a version
+impl From<JavaCatalog> for CachedCatalog {
+    fn from(catalog: JavaCatalog) -> Self {
+        Self {
+            schema_version: CATALOG_CACHE_SCHEMA_VERSION,
+            catalog,
+        }
     }
 }
 
-pub fn save_catalog_cache(app_handle: &AppHandle, catalog: &JavaCatalog) -> Result<(), String> {
-    let cache_path = get_catalog_cache_path(app_handle);
-    let content = serde_json::to_string_pretty(catalog).map_err(|e| e.to_string())?;
-    std::fs::write(&cache_path, content).map_err(|e| e.to_string())?;
+/// Parse a cache file of the current or a migratable version
+fn decode_catalog_cache(content: &str) -> Option<JavaCatalog> {
+    let value: serde_json::Value = serde_json::from_str(content).ok()?;
+    let cached = match value.get("schemaVersion").map(serde_json::Value::as_u64) {
+        None => CachedCatalog::from(serde_json::from_value::<JavaCatalog>(value).ok()?),
+        Some(Some(version)) if version == CATALOG_CACHE_SCHEMA_VERSION as u64 => {
+            serde_json::from_value(value).ok()?
+        }
+        Some(version) => {
+            log::info!(
+                "Discarding Java catalog cache with schema version {:?}",
+                version
+            );
+            return None;
+        }
+    };
+    Some(cached.catalog)
+}
+
+/// Catalog caches belong to the active profile
+fn app_data_dir(app_handle: &AppHandle) -> Result<PathBuf, JavaError> {
+    crate::core::profiles::profile_dir(app_handle)
+}
+
+/// Cache key of a provider's catalog, `<provider>-<os>-<arch>`, so a catalog is
+/// never served for another provider or platform
+pub fn catalog_cache_key(provider: &impl JavaProvider) -> String {
+    format!(
+        "{}-{}-{}",
+        provider.provider_name(),
+        provider.os_name(),
+        provider.arch_name()
+    )
+}
+
+fn catalog_cache_path(data_dir: &std::path::Path, key: &str) -> PathBuf {
+    data_dir
+        .join(CATALOG_CACHE_DIR)
+        .join(format!("{}.json.gz", key))
+}
+
+/// Uncompressed form written before catalogs were compressed, still read
+fn uncompressed_catalog_cache_path(data_dir: &std::path::Path, key: &str) -> PathBuf {
+    data_dir
+        .join(CATALOG_CACHE_DIR)
+        .join(format!("{}.json", key))
+}
+
+/// Move the legacy single-file cache, which always held Adoptium's catalog for this
+/// machine, under `legacy_key`. An entry already stored under that key wins.
+fn migrate_legacy_catalog_cache(data_dir: &std::path::Path, legacy_key: &str) {
+    let legacy_path = data_dir.join(LEGACY_CATALOG_CACHE_FILE);
+    if !legacy_path.exists() {
+        return;
+    }
+    if !catalog_cache_path(data_dir, legacy_key).exists()
+        && !uncompressed_catalog_cache_path(data_dir, legacy_key).exists()
+    {
+        let catalog =
+            cache::read_json_file(&legacy_path).and_then(|content| decode_catalog_cache(&content));
+        if let Some(catalog) = catalog
+            && let Err(e) = write_catalog_cache(data_dir, legacy_key, &catalog)
+        {
+            log::warn!("Failed to migrate Java catalog cache: {}", e.with_code());
+            return;
+        }
+    }
+    let _ = std::fs::remove_file(&legacy_path);
+}
+
+fn read_catalog_cache_in(data_dir: &std::path::Path, key: &str) -> Option<JavaCatalog> {
+    migrate_legacy_catalog_cache(data_dir, &catalog_cache_key(&AdoptiumProvider::new()));
+    let memory_key = catalog_cache_path(data_dir, key);
+    if let Some(catalog) = CATALOG_MEMORY.read().unwrap().get(&memory_key) {
+        return Some(catalog.clone());
+    }
+    let mut cache_path = memory_key.clone();
+    if !cache_path.exists() {
+        cache_path = uncompressed_catalog_cache_path(data_dir, key);
+    }
+    let content = cache::read_json_file(&cache_path)?;
+    let catalog = decode_catalog_cache(&content);
+    if catalog.is_none() {
+        // Unreadable or from an unknown version: drop it so it's refetched
+        let _ = std::fs::remove_file(&cache_path);
+    } else if let Some(catalog) = &catalog {
+        CATALOG_MEMORY
+            .write()
+            .unwrap()
+            .insert(memory_key, catalog.clone());
+    }
+    catalog
+}
+
+fn write_catalog_cache(
+    data_dir: &std::path::Path,
+    key: &str,
+    catalog: &JavaCatalog,
+) -> Result<(), JavaError> {
+    let cache_path = catalog_cache_path(data_dir, key);
+    std::fs::create_dir_all(cache_path.parent().unwrap())
+        .map_err(|e| JavaError::io("Failed to create the catalog cache directory", e))?;
+    let cached = CachedCatalog {
+        schema_version: CATALOG_CACHE_SCHEMA_VERSION,
+        catalog: catalog.clone(),
+    };
+    cache::write_json_gz(&cache_path, &cached)?;
+    let _ = std::fs::remove_file(uncompressed_catalog_cache_path(data_dir, key));
+    CATALOG_MEMORY
+        .write()
+        .unwrap()
+        .insert(cache_path, catalog.clone());
     Ok(())
 }
 
+/// Cached catalog of `provider` regardless of its age, for revalidation
+pub fn read_catalog_cache(
+    app_handle: &AppHandle,
+    provider: &impl JavaProvider,
+) -> Option<JavaCatalog> {
+    read_catalog_cache_in(
+        &app_data_dir(app_handle).ok()?,
+        &catalog_cache_key(provider),
+    )
+}
+
+/// Configured catalog cache lifetime, falling back to the default
+pub fn catalog_cache_ttl_secs(app_handle: &AppHandle) -> u64 {
+    app_handle
+        .try_state::<crate::core::config::ConfigState>()
+        .and_then(|state| state.config.lock().unwrap().catalog_cache_ttl_secs)
+        .unwrap_or(CACHE_DURATION_SECS)
+}
+
+pub fn save_catalog_cache(
+    app_handle: &AppHandle,
+    provider: &impl JavaProvider,
+    catalog: &JavaCatalog,
+) -> Result<(), JavaError> {
+    write_catalog_cache(
+        &app_data_dir(app_handle)?,
+        &catalog_cache_key(provider),
+        catalog,
+    )
+}
+
+/// Remove every cached catalog, including a not yet migrated legacy one
 #[allow(dead_code)]
 pub fn clear_catalog_cache(app_handle: &AppHandle) -> Result<(), String> {
-    let cache_path = get_catalog_cache_path(app_handle);
-    if cache_path.exists() {
-        std::fs::remove_file(&cache_path).map_err(|e| e.to_string())?;
+    clear_catalog_cache_in(&app_data_dir(app_handle)?)
+}
+
+fn clear_catalog_cache_in(data_dir: &std::path::Path) -> Result<(), String> {
+    forget_cached_catalogs(data_dir);
+    let cache_dir = data_dir.join(CATALOG_CACHE_DIR);
+    if cache_dir.exists() {
+        std::fs::remove_dir_all(&cache_dir).map_err(|e| e.to_string())?;
+    }
+    let legacy_path = data_dir.join(LEGACY_CATALOG_CACHE_FILE);
+    if legacy_path.exists() {
+        std::fs::remove_file(&legacy_path).map_err(|e| e.to_string())?;
     }
     Ok(())
 }
 
+/// Get the catalog, serving an expired cache straight away while it is refreshed in
+/// the background. The refreshed catalog is emitted as `java-catalog-refreshed`.
+/// A forced refresh, or having no cache at all, waits for the network.
 pub async fn fetch_java_catalog(
     app_handle: &AppHandle,
     force_refresh: bool,
-) -> Result<JavaCatalog, String> {
-    let provider = AdoptiumProvider::new();
-    provider
-        .fetch_catalog(app_handle, force_refresh)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<JavaCatalogResult, JavaError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let cached = read_catalog_cache(app_handle, &AdoptiumProvider::new());
+
+    let background_app = app_handle.clone();
+    let result = serve_catalog(
+        &CATALOG_FETCHES,
+        cached,
+        now,
+        catalog_cache_ttl_secs(app_handle),
+        force_refresh,
+        catalog_refresher(app_handle),
+        move |result| match result {
+            Ok(catalog) => emit_catalog_refreshed(&background_app, catalog, now),
+            Err(e) => {
+                log::warn!("Background Java catalog refresh failed: {}", e.with_code());
+                if e.is_network() {
+                    let _ = background_app.emit("java-catalog-offline", &e);
+                }
+            }
+        },
+    )
+    .await;
+
+    match result {
+        Ok(result) => Ok(result),
+        Err(CatalogFetchError::Offline(e, result)) => {
+            log::warn!(
+                "Serving expired Java catalog while offline: {}",
+                e.with_code()
+            );
+            let _ = app_handle.emit("java-catalog-offline", &e);
+            Ok(result)
+        }
+        Err(CatalogFetchError::Failed(e)) => Err(e),
+    }
+}
+
+/// Refresh the catalog in the background if the cache has expired, emitting
+/// `java-catalog-refreshed` on success. Meant for a scheduled refresh: failures,
+/// including being offline, are only logged, and nothing is fetched without a cache.
+pub async fn refresh_expired_catalog(app_handle: &AppHandle) {
+    let Some(cached) = read_catalog_cache(app_handle, &AdoptiumProvider::new()) else {
+        return;
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let background_app = app_handle.clone();
+    let _ = serve_catalog(
+        &CATALOG_FETCHES,
+        Some(cached),
+        now,
+        catalog_cache_ttl_secs(app_handle),
+        false,
+        catalog_refresher(app_handle),
+        move |result| match result {
+            Ok(catalog) => emit_catalog_refreshed(&background_app, catalog, now),
+            Err(e) => log::info!("Scheduled Java catalog refresh skipped: {}", e),
+        },
+    )
+    .await;
+}
+
+/// A pending catalog fetch, as handed to `serve_catalog`
+type CatalogRefresh =
+    std::pin::Pin<Box<dyn std::future::Future<Output = Result<JavaCatalog, JavaError>> + Send>>;
+
+/// Fetch the catalog from the provider and cache it
+fn catalog_refresher(app_handle: &AppHandle) -> impl FnOnce(Option<JavaCatalog>) -> CatalogRefresh {
+    let app_handle = app_handle.clone();
+    move |cached| {
+        Box::pin(async move {
+            let provider = AdoptiumProvider::new();
+            let catalog = provider
+                .refresh_catalog(&crate::core::http::client(), cached)
+                .await?;
+            let _ = save_catalog_cache(&app_handle, &provider, &catalog);
+            Ok(catalog)
+        })
+    }
+}
+
+fn emit_catalog_refreshed(app_handle: &AppHandle, catalog: JavaCatalog, now: u64) {
+    let _ = app_handle.emit(
+        "java-catalog-refreshed",
+        JavaCatalogResult::new(catalog, now, false),
+    );
+}
+
+/// Why `serve_catalog` couldn't hand out an up-to-date catalog
+#[derive(Debug)]
+enum CatalogFetchError {
+    /// The network is unreachable; the expired cache is served instead
+    Offline(JavaError, JavaCatalogResult),
+    Failed(JavaError),
+}
+
+/// Stale-while-revalidate over the catalog cache: fresh entries are returned as is,
+/// expired ones are returned flagged `stale` while `refresh` runs in the background
+/// and hands its result to `on_refreshed`; otherwise `refresh` is awaited, falling
+/// back to any cache, whatever its age, when the network is unreachable.
+async fn serve_catalog<R, Fut>(
+    fetches: &CatalogFetches,
+    cached: Option<JavaCatalog>,
+    now: u64,
+    ttl_secs: u64,
+    force_refresh: bool,
+    refresh: R,
+    on_refreshed: impl FnOnce(Result<JavaCatalog, JavaError>) + Send + 'static,
+) -> Result<JavaCatalogResult, CatalogFetchError>
+where
+    R: FnOnce(Option<JavaCatalog>) -> Fut,
+    Fut: std::future::Future<Output = Result<JavaCatalog, JavaError>> + Send + 'static,
+{
+    let cached = match cached {
+        Some(cached) if !force_refresh && cached.is_fresh(now, ttl_secs) => {
+            return Ok(JavaCatalogResult::new(cached, now, false));
+        }
+        Some(cached) if !force_refresh => {
+            // A refresh already in flight will emit its own result
+            if let Some(guard) = CatalogRefreshGuard::acquire() {
+                let stale = cached.clone();
+                let refreshing = fetches.join(|| Box::pin(refresh(Some(stale))));
+                tokio::spawn(async move {
+                    let _guard = guard;
+                    on_refreshed(refreshing.await);
+                });
+            }
+            return Ok(JavaCatalogResult::new(cached, now, true));
+        }
+        cached => cached,
+    };
+
+    let refreshing = fetches.join(|| Box::pin(refresh(None)));
+    match (refreshing.await, cached) {
+        (Ok(catalog), _) => Ok(JavaCatalogResult::new(catalog, now, false)),
+        (Err(e), Some(cached)) if e.is_network() => Err(CatalogFetchError::Offline(
+            e,
+            JavaCatalogResult {
+                offline: true,
+                ..JavaCatalogResult::new(cached, now, true)
+            },
+        )),
+        (Err(e), _) => Err(CatalogFetchError::Failed(e)),
+    }
 }
 
 pub async fn fetch_java_release(
     major_version: u32,
     image_type: ImageType,
-) -> Result<JavaDownloadInfo, String> {
+) -> Result<JavaDownloadInfo, JavaError> {
     let provider = AdoptiumProvider::new();
-    provider
-        .fetch_release(major_version, image_type)
-        .await
-        .map_err(|e| e.to_string())
+    provider.fetch_release(major_version, image_type).await
 }
 
-pub async fn fetch_available_versions() -> Result<Vec<u32>, String> {
+pub async fn fetch_available_versions() -> Result<Vec<u32>, JavaError> {
     let provider = AdoptiumProvider::new();
-    provider
-        .available_versions()
-        .await
-        .map_err(|e| e.to_string())
+    provider.available_versions().await
 }
 
 pub async fn download_and_install_java(
     app_handle: &AppHandle,
+    download_id: &str,
     major_version: u32,
     image_type: ImageType,
     custom_path: Option<PathBuf>,
-) -> Result<JavaInstallation, String> {
-    let provider = AdoptiumProvider::new();
+    download_segments: usize,
+) -> Result<JavaInstallation, JavaError> {
+    download_and_install_java_with_provider(
+        app_handle,
+        &AdoptiumProvider::new(),
+        download_id,
+        major_version,
+        image_type,
+        custom_path,
+        download_segments,
+    )
+    .await
+}
+
+pub async fn download_and_install_java_with_provider(
+    app_handle: &AppHandle,
+    provider: &impl JavaProvider,
+    download_id: &str,
+    major_version: u32,
+    image_type: ImageType,
+    custom_path: Option<PathBuf>,
+    download_segments: usize,
+) -> Result<JavaInstallation, JavaError> {
     let info = provider.fetch_release(major_version, image_type).await?;
     let file_name = info.file_name.clone();
 
-    let install_base = custom_path.unwrap_or_else(|| get_java_install_dir(app_handle));
+    let install_base = match custom_path {
+        Some(custom_path) => custom_path,
+        None => get_java_install_dir(app_handle)?,
+    };
     let version_dir = install_base.join(format!(
         "{}-{}-{}",
         provider.install_prefix(),
         major_version,
         image_type
     ));
+    let lock = install_lock(&version_dir);
+    let _guard = lock.lock().await;
 
-    std::fs::create_dir_all(&install_base)
-        .map_err(|e| format!("Failed to create installation directory: {}", e))?;
+    std::fs::create_dir_all(&install_base).map_err(|e| {
+        JavaError::io_at("Failed to create installation directory", &install_base, e)
+    })?;
 
     let mut queue = DownloadQueue::load(app_handle);
     queue.add(PendingJavaDownload {
@@ -221,16 +809,24 @@ pub async fn download_and_install_java(
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
+        attempts: 0,
+        last_error: None,
+        last_attempt_at: 0,
     });
     queue.save(app_handle)?;
 
     let archive_path = install_base.join(&info.file_name);
+    let _archive_in_use = cache::ArchiveInUse::register(&archive_path);
 
     let need_download = if archive_path.exists() {
         if let Some(expected_checksum) = &info.checksum {
-            let data = std::fs::read(&archive_path)
-                .map_err(|e| format!("Failed to read downloaded file: {}", e))?;
-            !crate::core::downloader::verify_checksum(&data, Some(expected_checksum), None)
+            !crate::core::downloader::verify_file_checksum(
+                &archive_path,
+                None,
+                Some(expected_checksum),
+                None,
+            )
+            .map_err(|e| JavaError::io_at("Failed to read downloaded file", &archive_path, e))?
         } else {
             false
         }
@@ -238,45 +834,62 @@ pub async fn download_and_install_java(
         true
     };
 
+    ensure_not_cancelled(download_id)?;
     if need_download {
         crate::core::downloader::download_with_resume(
             app_handle,
+            download_id,
             &info.download_url,
             &archive_path,
             info.checksum.as_deref(),
             info.file_size,
+            download_segments,
         )
-        .await?;
+        .await
+        .map_err(|e| {
+            if crate::core::downloader::is_java_download_cancelled(download_id) {
+                JavaError::Cancelled
+            } else {
+                JavaError::from(e)
+            }
+        })?;
     }
 
+    ensure_not_cancelled(download_id)?;
     let _ = app_handle.emit(
         "java-download-progress",
         JavaDownloadProgress {
+            id: download_id.to_string(),
             file_name: file_name.clone(),
             downloaded_bytes: info.file_size,
             total_bytes: info.file_size,
             speed_bytes_per_sec: 0,
             eta_seconds: 0,
             status: "Extracting".to_string(),
+            message_key: crate::core::downloader::java_download_status_key("Extracting"),
             percentage: 100.0,
         },
     );
 
     if version_dir.exists() {
-        std::fs::remove_dir_all(&version_dir)
-            .map_err(|e| format!("Failed to remove old version directory: {}", e))?;
+        std::fs::remove_dir_all(&version_dir).map_err(|e| {
+            JavaError::io_at("Failed to remove old version directory", &version_dir, e)
+        })?;
     }
 
     std::fs::create_dir_all(&version_dir)
-        .map_err(|e| format!("Failed to create version directory: {}", e))?;
+        .map_err(|e| JavaError::io_at("Failed to create version directory", &version_dir, e))?;
 
     let top_level_dir = if info.file_name.ends_with(".tar.gz") || info.file_name.ends_with(".tgz") {
-        zip::extract_tar_gz(&archive_path, &version_dir)?
+        zip::extract_tar_gz(&archive_path, &version_dir).map_err(JavaError::ExtractionFailed)?
     } else if info.file_name.ends_with(".zip") {
-        zip::extract_zip(&archive_path, &version_dir)?;
-        find_top_level_dir(&version_dir)?
+        zip::extract_zip(&archive_path, &version_dir).map_err(JavaError::ExtractionFailed)?;
+        find_top_level_dir(&version_dir).map_err(|e| JavaError::IoError(e, None))?
     } else {
-        return Err(format!("Unsupported archive format: {}", info.file_name));
+        return Err(JavaError::ExtractionFailed(format!(
+            "Unsupported archive format: {}",
+            info.file_name
+        )));
     };
 
     let _ = std::fs::remove_file(&archive_path);
@@ -295,18 +908,21 @@ pub async fn download_and_install_java(
     };
 
     if !java_bin.exists() {
-        return Err(format!(
+        return Err(JavaError::ExtractionFailed(format!(
             "Installation completed but Java executable not found: {}",
             java_bin.display()
-        ));
+        )));
     }
 
-    let java_bin = std::fs::canonicalize(&java_bin).map_err(|e| e.to_string())?;
+    ensure_not_cancelled(download_id)?;
+    let java_bin = std::fs::canonicalize(&java_bin)?;
     let java_bin = strip_unc_prefix(java_bin);
 
     let installation = validation::check_java_installation(&java_bin)
         .await
-        .ok_or_else(|| "Failed to verify Java installation".to_string())?;
+        .ok_or_else(|| {
+            JavaError::VerificationFailed("Failed to verify Java installation".to_string())
+        })?;
 
     queue.remove(major_version, &image_type.to_string());
     queue.save(app_handle)?;
@@ -314,12 +930,14 @@ pub async fn download_and_install_java(
     let _ = app_handle.emit(
         "java-download-progress",
         JavaDownloadProgress {
+            id: download_id.to_string(),
             file_name,
             downloaded_bytes: info.file_size,
             total_bytes: info.file_size,
             speed_bytes_per_sec: 0,
             eta_seconds: 0,
             status: "Completed".to_string(),
+            message_key: crate::core::downloader::java_download_status_key("Completed"),
             percentage: 100.0,
         },
     );
@@ -365,17 +983,77 @@ pub async fn detect_java_installations() -> Vec<JavaInstallation> {
     installations
 }
 
-pub async fn get_recommended_java(required_major_version: Option<u64>) -> Option<JavaInstallation> {
-    let installations = detect_java_installations().await;
+/// Java majors with long-term support
+const LTS_MAJORS: &[u32] = &[8, 11, 17, 21, 25];
+
+/// System Java meeting `required_major_version`, never of a blocked vendor or build.
+/// See `recommend_java` for which one.
+pub async fn get_recommended_java(
+    required_major_version: Option<u64>,
+    settings: &persistence::JavaSettings,
+    prefer_exact: bool,
+) -> Option<JavaInstallation> {
+    recommend_java(
+        detect_java_installations().await,
+        required_major_version,
+        settings,
+        prefer_exact,
+    )
+}
 
-    if let Some(required) = required_major_version {
-        installations.into_iter().find(|java| {
+/// With `prefer_exact` and a required major, a Java of exactly that major comes first,
+/// then the nearest LTS major above it, then the nearest other major, as newer majors
+/// can break older mod loaders. Without it the newest wins. Either way a preferred
+/// vendor and native 64-bit decide next, and the newest build of a major goes first.
+fn recommend_java(
+    installations: Vec<JavaInstallation>,
+    required_major_version: Option<u64>,
+    settings: &persistence::JavaSettings,
+    prefer_exact: bool,
+) -> Option<JavaInstallation> {
+    let closeness = |major: u32| match required_major_version {
+        Some(required) if prefer_exact => {
+            if u64::from(major) == required {
+                (0, 0)
+            } else if LTS_MAJORS.contains(&major) {
+                (1, major)
+            } else {
+                (2, major)
+            }
+        }
+        _ => (0, 0),
+    };
+    installations
+        .into_iter()
+        .filter(|java| {
             let major = validation::parse_java_version(&java.version);
-            major >= required as u32
+            required_major_version.is_none_or(|required| major >= required as u32)
         })
-    } else {
-        installations.into_iter().next()
-    }
+        .filter(|java| blocklist::blocked_reason(java, settings).is_none())
+        .filter_map(|java| {
+            let rank = validation::vendor_rank(
+                &java,
+                &settings.preferred_vendors,
+                &settings.blocked_vendors,
+            )?;
+            Some((rank, java))
+        })
+        .min_by(|(rank_a, a), (rank_b, b)| {
+            let key = |rank: usize, java: &JavaInstallation| {
+                (
+                    closeness(validation::parse_java_version(&java.version)),
+                    rank,
+                    validation::architecture_rank(java),
+                )
+            };
+            key(*rank_a, a).cmp(&key(*rank_b, b)).then_with(|| {
+                validation::compare_versions(
+                    &validation::version_parts(&b.version),
+                    &validation::version_parts(&a.version),
+                )
+            })
+        })
+        .map(|(_, java)| java)
 }
 
 pub async fn get_compatible_java(
@@ -405,11 +1083,44 @@ pub async fn is_java_compatible(
     }
 }
 
+/// Detection run on startup, window focus or opening settings: reuses the last scan's
+/// results if it ran less than `min_redetect_interval_secs` ago
+pub async fn detect_java_installations_if_stale(app_handle: &AppHandle) -> Vec<JavaInstallation> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let config = persistence::load_java_config(app_handle);
+    let cached = DETECTED_INSTALLATIONS.read().unwrap().clone();
+    if let Some(installations) = cached
+        && !should_redetect(
+            config.last_detection_time,
+            now,
+            config.settings.min_redetect_interval_secs,
+        )
+    {
+        return installations;
+    }
+    detect_all_java_installations(app_handle).await
+}
+
+/// Scan for installations now, remembering the results and when the scan ran
 pub async fn detect_all_java_installations(app_handle: &AppHandle) -> Vec<JavaInstallation> {
+    let installations = scan_java_installations(app_handle).await;
+    *DETECTED_INSTALLATIONS.write().unwrap() = Some(installations.clone());
+    if let Err(e) = persistence::update_last_detection_time(app_handle) {
+        log::warn!("Failed to record Java detection time: {}", e);
+    }
+    known::remember(app_handle, &installations);
+    installations
+}
+
+async fn scan_java_installations(app_handle: &AppHandle) -> Vec<JavaInstallation> {
     let mut installations = detect_java_installations().await;
 
-    let dropout_java_dir = get_java_install_dir(app_handle);
-    if dropout_java_dir.exists() {
+    if let Ok(dropout_java_dir) = get_java_install_dir(app_handle)
+        && dropout_java_dir.exists()
+    {
         if let Ok(entries) = std::fs::read_dir(&dropout_java_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
@@ -436,7 +1147,7 @@ pub async fn detect_all_java_installations(app_handle: &AppHandle) -> Vec<JavaIn
     installations
 }
 
-fn find_java_executable(dir: &PathBuf) -> Option<PathBuf> {
+pub(crate) fn find_java_executable(dir: &PathBuf) -> Option<PathBuf> {
     let bin_name = if cfg!(windows) { "java.exe" } else { "java" };
 
     let direct_bin = dir.join("bin").join(bin_name);
@@ -483,42 +1194,141 @@ fn find_java_executable(dir: &PathBuf) -> Option<PathBuf> {
 
 pub async fn resume_pending_downloads(
     app_handle: &AppHandle,
-) -> Result<Vec<JavaInstallation>, String> {
+    download_segments: usize,
+) -> Result<ResumeJavaDownloadsResult, JavaError> {
     let queue = DownloadQueue::load(app_handle);
-    let mut installed = Vec::new();
+    let mut result = ResumeJavaDownloadsResult {
+        id: new_download_id(),
+        ..Default::default()
+    };
+    let _guard = JavaDownloadGuard::register(&result.id);
 
-    for pending in queue.pending_downloads.iter() {
-        let image_type = if pending.image_type == "jdk" {
-            ImageType::Jdk
-        } else {
-            ImageType::Jre
-        };
+    for pending in queue.pending_downloads {
+        // A cancelled resume leaves the remaining entries queued as they were
+        if ensure_not_cancelled(&result.id).is_err() {
+            break;
+        }
+        if pending.attempts >= MAX_RESUME_ATTEMPTS {
+            result.failed.push(ResumeJavaDownloadFailure {
+                download: pending,
+                reason: ResumeJavaDownloadFailureReason::GivenUp,
+                error_kind: None,
+            });
+            continue;
+        }
+        match resume_download(app_handle, &result.id, &pending, download_segments).await {
+            Ok(installation) => result.installed.push(installation),
+            Err((download, error)) => result.failed.push(ResumeJavaDownloadFailure {
+                download,
+                reason: ResumeJavaDownloadFailureReason::from(&error),
+                error_kind: Some(error.kind()),
+            }),
+        }
+    }
 
-        match download_and_install_java(
-            app_handle,
+    Ok(result)
+}
+
+/// Install one queued download, recording a failure on the queue entry
+async fn resume_download(
+    app_handle: &AppHandle,
+    download_id: &str,
+    pending: &PendingJavaDownload,
+    download_segments: usize,
+) -> Result<JavaInstallation, (PendingJavaDownload, JavaError)> {
+    let image_type = if pending.image_type == "jdk" {
+        ImageType::Jdk
+    } else {
+        ImageType::Jre
+    };
+
+    let error = match download_and_install_java(
+        app_handle,
+        download_id,
+        pending.major_version,
+        image_type,
+        Some(PathBuf::from(&pending.install_path)),
+        download_segments,
+    )
+    .await
+    {
+        Ok(installation) => return Ok(installation),
+        Err(e) => e,
+    };
+    eprintln!(
+        "Failed to resume Java {} {} download: {}",
+        pending.major_version,
+        pending.image_type,
+        error.with_code()
+    );
+    // Cancelling is not a failure of the download itself
+    if let Err(cancelled) = ensure_not_cancelled(download_id) {
+        return Err((
+            PendingJavaDownload {
+                last_error: Some(cancelled.to_string()),
+                ..pending.clone()
+            },
+            cancelled,
+        ));
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut queue = DownloadQueue::load(app_handle);
+    let updated = queue
+        .record_failure(
             pending.major_version,
-            image_type,
-            Some(PathBuf::from(&pending.install_path)),
+            &pending.image_type,
+            crate::utils::error::display_chain(&error),
+            now,
         )
-        .await
-        {
-            Ok(installation) => {
-                installed.push(installation);
-            }
-            Err(e) => {
-                eprintln!(
-                    "Failed to resume Java {} {} download: {}",
-                    pending.major_version, pending.image_type, e
-                );
-            }
-        }
+        .cloned()
+        .unwrap_or_else(|| pending.clone());
+    if let Err(e) = queue.save(app_handle) {
+        eprintln!("Failed to save download queue: {}", e);
     }
+    Err((updated, error))
+}
+
+/// Try a pending download again even if it was given up on
+pub async fn retry_pending_download(
+    app_handle: &AppHandle,
+    major_version: u32,
+    image_type: &str,
+    download_segments: usize,
+) -> Result<JavaDownloadResult, JavaError> {
+    let mut queue = DownloadQueue::load(app_handle);
+    if !queue.reset_attempts(major_version, image_type) {
+        return Err(JavaError::InvalidConfig(format!(
+            "No pending download for Java {} {}",
+            major_version, image_type
+        )));
+    }
+    queue.save(app_handle)?;
+    let pending = queue
+        .pending_downloads
+        .into_iter()
+        .find(|d| d.major_version == major_version && d.image_type == image_type)
+        .unwrap();
+
+    let id = new_download_id();
+    let _guard = JavaDownloadGuard::register(&id);
+    let installation = resume_download(app_handle, &id, &pending, download_segments)
+        .await
+        .map_err(|(_, error)| error)?;
+    Ok(JavaDownloadResult { id, installation })
+}
 
-    Ok(installed)
+/// Cancel the install or resume with the given id, returning whether it was running
+pub fn cancel_download(download_id: &str) -> bool {
+    crate::core::downloader::cancel_java_download(download_id)
 }
 
-pub fn cancel_current_download() {
-    crate::core::downloader::cancel_java_download();
+/// Cancel every running install and resume, returning how many there were
+pub fn cancel_all_downloads() -> usize {
+    crate::core::downloader::cancel_all_java_downloads()
 }
 
 pub fn get_pending_downloads(app_handle: &AppHandle) -> Vec<PendingJavaDownload> {
@@ -526,13 +1336,483 @@ pub fn get_pending_downloads(app_handle: &AppHandle) -> Vec<PendingJavaDownload>
     queue.pending_downloads
 }
 
-#[allow(dead_code)]
 pub fn clear_pending_download(
     app_handle: &AppHandle,
     major_version: u32,
     image_type: &str,
-) -> Result<(), String> {
+) -> Result<(), JavaError> {
     let mut queue = DownloadQueue::load(app_handle);
     queue.remove(major_version, image_type);
-    queue.save(app_handle)
+    Ok(queue.save(app_handle)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn automatic_detection_waits_for_the_minimum_interval() {
+        let last_scan = 1_000_000;
+        assert!(!should_redetect(last_scan, last_scan, 600));
+        assert!(!should_redetect(last_scan, last_scan + 599, 600));
+        assert!(should_redetect(last_scan, last_scan + 600, 600));
+        assert!(should_redetect(last_scan, last_scan + 3600, 600));
+        // Never scanned, or the clock moved back past the last scan
+        assert!(should_redetect(0, last_scan, 600));
+        assert!(should_redetect(last_scan, last_scan - 1, 600));
+        // No limit
+        assert!(should_redetect(last_scan, last_scan, 0));
+    }
+
+    #[test]
+    fn resume_failure_reasons_follow_the_error_variant() {
+        let reason = |error: JavaError| ResumeJavaDownloadFailureReason::from(&error);
+        assert_eq!(
+            reason(JavaError::Cancelled),
+            ResumeJavaDownloadFailureReason::Cancelled
+        );
+        for error in [
+            JavaError::ChecksumMismatch("expected abc".to_string()),
+            JavaError::network("connection reset"),
+            JavaError::ExtractionFailed("cancelled by the archive".to_string()),
+        ] {
+            assert_eq!(reason(error), ResumeJavaDownloadFailureReason::Failed);
+        }
+    }
+
+    #[test]
+    fn recommendation_prefers_the_required_major_then_the_nearest_lts() {
+        let java = |version: &str| JavaInstallation {
+            path: format!("/usr/lib/jvm/java-{}/bin/java", version),
+            version: version.to_string(),
+            arch: "x64".to_string(),
+            vendor: "Eclipse Adoptium".to_string(),
+            source: "system".to_string(),
+            is_64bit: true,
+            is_native_arch: true,
+            blocked_reason: None,
+        };
+        let settings = persistence::JavaSettings::default();
+        let recommend = |versions: &[&str], required: Option<u64>, prefer_exact: bool| {
+            recommend_java(
+                versions.iter().map(|v| java(v)).collect(),
+                required,
+                &settings,
+                prefer_exact,
+            )
+            .map(|java| java.version)
+        };
+
+        let installed = ["23.0.1", "17.0.2", "17.0.10", "21.0.2"];
+        // The newest build of the exact major, not the newest Java
+        assert_eq!(
+            recommend(&installed, Some(17), true).as_deref(),
+            Some("17.0.10")
+        );
+        assert_eq!(
+            recommend(&installed, Some(17), false).as_deref(),
+            Some("23.0.1")
+        );
+        // No exact match: the nearest LTS above beats a nearer non-LTS major
+        assert_eq!(
+            recommend(&["23.0.1", "25.0.1", "19.0.2"], Some(18), true).as_deref(),
+            Some("25.0.1")
+        );
+        // Without an LTS the nearest major wins
+        assert_eq!(
+            recommend(&["23.0.1", "19.0.2"], Some(18), true).as_deref(),
+            Some("19.0.2")
+        );
+        assert_eq!(recommend(&["11.0.22"], Some(17), true), None);
+        assert_eq!(recommend(&installed, None, true).as_deref(), Some("23.0.1"));
+    }
+
+    #[test]
+    fn installs_of_the_same_version_dir_share_a_lock() {
+        let dir = std::env::temp_dir().join("dropout-install-lock/temurin-17-jre");
+        let other = std::env::temp_dir().join("dropout-install-lock/temurin-21-jre");
+
+        let lock = install_lock(&dir);
+        let _guard = lock.try_lock().unwrap();
+        assert!(install_lock(&dir).try_lock().is_err());
+        assert!(install_lock(&other).try_lock().is_ok());
+    }
+
+    #[test]
+    fn catalog_freshness_follows_the_ttl() {
+        let catalog = JavaCatalog {
+            cached_at: 1_000,
+            ..JavaCatalog::default()
+        };
+
+        assert_eq!(catalog.age_secs(4_600), 3_600);
+        assert!(catalog.is_fresh(4_600, CACHE_DURATION_SECS));
+        assert!(!catalog.is_fresh(4_600, 3_600));
+        assert!(catalog.is_fresh(4_599, 3_600));
+        // A clock that went backwards doesn't make the cache look ancient
+        assert_eq!(catalog.age_secs(500), 0);
+    }
+
+    #[test]
+    fn catalog_result_flattens_the_catalog() {
+        let result = JavaCatalogResult {
+            catalog: JavaCatalog {
+                cached_at: 1_000,
+                ..JavaCatalog::default()
+            },
+            age_secs: 60,
+            stale: true,
+            offline: false,
+        };
+        let json = serde_json::to_value(&result).unwrap();
+
+        assert_eq!(json["cachedAt"], 1_000);
+        assert_eq!(json["ageSecs"], 60);
+        assert_eq!(json["stale"], true);
+    }
+
+    fn catalog_at(cached_at: u64) -> JavaCatalog {
+        JavaCatalog {
+            available_major_versions: vec![cached_at as u32],
+            cached_at,
+            ..JavaCatalog::default()
+        }
+    }
+
+    #[test]
+    fn catalog_cache_round_trips_current_and_previous_versions() {
+        let dir = TestDir::new("catalog");
+        write_catalog_cache(dir.path(), "adoptium-linux-x64", &catalog_at(5)).unwrap();
+        let written =
+            cache::read_json_file(&catalog_cache_path(dir.path(), "adoptium-linux-x64")).unwrap();
+        assert!(written.contains("\"schemaVersion\":2"));
+        assert_eq!(
+            read_catalog_cache_in(dir.path(), "adoptium-linux-x64").map(|c| c.cached_at),
+            Some(5)
+        );
+
+        // An uncompressed file from before compression is read, and replaced on save
+        let uncompressed = uncompressed_catalog_cache_path(dir.path(), "zulu-linux-x64");
+        let cached = CachedCatalog::from(catalog_at(6));
+        std::fs::write(
+            &uncompressed,
+            serde_json::to_string_pretty(&cached).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            read_catalog_cache_in(dir.path(), "zulu-linux-x64").map(|c| c.cached_at),
+            Some(6)
+        );
+        write_catalog_cache(dir.path(), "zulu-linux-x64", &catalog_at(8)).unwrap();
+        assert!(!uncompressed.exists());
+        assert_eq!(
+            read_catalog_cache_in(dir.path(), "zulu-linux-x64").map(|c| c.cached_at),
+            Some(8)
+        );
+
+        // Version 1: a bare catalog
+        let v1 = r#"{"releases":[],"availableMajorVersions":[21],"ltsVersions":[21],"cachedAt":9}"#;
+        let catalog = decode_catalog_cache(v1).unwrap();
+        assert_eq!(catalog.cached_at, 9);
+        assert_eq!(catalog.available_major_versions, vec![21]);
+    }
+
+    #[test]
+    fn catalog_cache_of_unknown_version_is_discarded() {
+        let dir = TestDir::new("catalog");
+        let path = catalog_cache_path(dir.path(), "adoptium-linux-x64");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let future = serde_json::json!({
+            "schemaVersion": CATALOG_CACHE_SCHEMA_VERSION + 1,
+            "catalog": catalog_at(5),
+        });
+        std::fs::write(&path, future.to_string()).unwrap();
+
+        assert!(read_catalog_cache_in(dir.path(), "adoptium-linux-x64").is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn catalog_caches_are_isolated_by_key() {
+        let dir = TestDir::new("catalog");
+        write_catalog_cache(dir.path(), "adoptium-linux-aarch64", &catalog_at(1)).unwrap();
+        write_catalog_cache(dir.path(), "zulu-linux-x64", &catalog_at(2)).unwrap();
+
+        assert_eq!(
+            read_catalog_cache_in(dir.path(), "adoptium-linux-aarch64").map(|c| c.cached_at),
+            Some(1)
+        );
+        assert_eq!(
+            read_catalog_cache_in(dir.path(), "zulu-linux-x64").map(|c| c.cached_at),
+            Some(2)
+        );
+        assert!(read_catalog_cache_in(dir.path(), "adoptium-linux-x64").is_none());
+
+        std::fs::write(dir.path().join(LEGACY_CATALOG_CACHE_FILE), "{}").unwrap();
+        clear_catalog_cache_in(dir.path()).unwrap();
+        assert!(!dir.path().join(CATALOG_CACHE_DIR).exists());
+        assert!(!dir.path().join(LEGACY_CATALOG_CACHE_FILE).exists());
+    }
+
+    #[test]
+    fn legacy_catalog_cache_moves_under_the_adoptium_key() {
+        let dir = TestDir::new("catalog");
+        let legacy = serde_json::to_string(&catalog_at(7)).unwrap();
+        std::fs::write(dir.path().join(LEGACY_CATALOG_CACHE_FILE), legacy).unwrap();
+        let adoptium = catalog_cache_key(&AdoptiumProvider::new());
+
+        assert!(read_catalog_cache_in(dir.path(), "zulu-linux-x64").is_none());
+        assert!(!dir.path().join(LEGACY_CATALOG_CACHE_FILE).exists());
+        assert_eq!(
+            read_catalog_cache_in(dir.path(), &adoptium).map(|c| c.cached_at),
+            Some(7)
+        );
+
+        // A newer per-key entry isn't overwritten by a leftover legacy file
+        std::fs::write(
+            dir.path().join(LEGACY_CATALOG_CACHE_FILE),
+            serde_json::to_string(&catalog_at(3)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            read_catalog_cache_in(dir.path(), &adoptium).map(|c| c.cached_at),
+            Some(7)
+        );
+        assert!(!dir.path().join(LEGACY_CATALOG_CACHE_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn expired_catalog_is_served_while_refreshing_in_background() {
+        let fetches = CatalogFetches::default();
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        // A provider that takes its time
+        let slow_refresh = |calls: std::sync::Arc<std::sync::atomic::AtomicUsize>| {
+            move |_cached: Option<JavaCatalog>| async move {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                Ok(catalog_at(90_000))
+            }
+        };
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        let started = std::time::Instant::now();
+        let result = serve_catalog(
+            &fetches,
+            Some(catalog_at(1_000)),
+            90_000,
+            CACHE_DURATION_SECS,
+            false,
+            slow_refresh(calls.clone()),
+            move |result| {
+                let _ = tx.send(result);
+            },
+        )
+        .await
+        .unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_millis(100));
+        assert!(result.stale);
+        assert_eq!(result.catalog.cached_at, 1_000);
+        assert_eq!(result.age_secs, 89_000);
+
+        // A second lookup while the refresh runs doesn't start another one
+        let again = serve_catalog(
+            &fetches,
+            Some(catalog_at(1_000)),
+            90_000,
+            CACHE_DURATION_SECS,
+            false,
+            slow_refresh(calls.clone()),
+            |_| panic!("only the first refresh reports back"),
+        )
+        .await
+        .unwrap();
+        assert!(again.stale);
+
+        let refreshed = rx.await.unwrap().unwrap();
+        assert_eq!(refreshed.cached_at, 90_000);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_catalog_is_served_when_offline() {
+        let fetches = CatalogFetches::default();
+        let offline =
+            |_cached: Option<JavaCatalog>| async { Err(JavaError::network("connection refused")) };
+
+        let result = serve_catalog(
+            &fetches,
+            Some(catalog_at(1_000)),
+            900_000,
+            CACHE_DURATION_SECS,
+            true,
+            offline,
+            |_| {},
+        )
+        .await;
+        let Err(CatalogFetchError::Offline(e, result)) = result else {
+            panic!("expected the expired cache to be served");
+        };
+        assert!(e.is_network());
+        assert!(result.stale && result.offline);
+        assert_eq!(result.catalog.cached_at, 1_000);
+
+        // Nothing to fall back to
+        let result = serve_catalog(
+            &fetches,
+            None,
+            900_000,
+            CACHE_DURATION_SECS,
+            false,
+            offline,
+            |_| {},
+        )
+        .await;
+        assert!(matches!(result, Err(CatalogFetchError::Failed(_))));
+
+        // A bad response isn't hidden behind the cache
+        let broken = |_cached: Option<JavaCatalog>| async {
+            Err(JavaError::SerializationError(
+                "unexpected token".to_string(),
+                None,
+            ))
+        };
+        let result = serve_catalog(
+            &fetches,
+            Some(catalog_at(1_000)),
+            900_000,
+            CACHE_DURATION_SECS,
+            true,
+            broken,
+            |_| {},
+        )
+        .await;
+        assert!(matches!(result, Err(CatalogFetchError::Failed(_))));
+    }
+
+    #[tokio::test]
+    async fn fresh_missing_and_forced_catalog_lookups_do_not_go_stale() {
+        let fetches = CatalogFetches::default();
+        let refresh = |_cached: Option<JavaCatalog>| async { Ok(catalog_at(5_000)) };
+        let fresh = serve_catalog(
+            &fetches,
+            Some(catalog_at(4_000)),
+            5_000,
+            CACHE_DURATION_SECS,
+            false,
+            refresh,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert!(!fresh.stale);
+        assert_eq!(fresh.catalog.cached_at, 4_000);
+
+        let missing = serve_catalog(
+            &fetches,
+            None,
+            5_000,
+            CACHE_DURATION_SECS,
+            false,
+            refresh,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert!(!missing.stale);
+        assert_eq!(missing.catalog.cached_at, 5_000);
+
+        let forced = serve_catalog(
+            &fetches,
+            Some(catalog_at(4_000)),
+            5_000,
+            CACHE_DURATION_SECS,
+            true,
+            refresh,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert!(!forced.stale);
+        assert_eq!(forced.catalog.cached_at, 5_000);
+    }
+
+    #[tokio::test]
+    async fn concurrent_catalog_lookups_share_one_fetch() {
+        let fetches = CatalogFetches::default();
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counting_refresh = || {
+            let calls = calls.clone();
+            move |_cached: Option<JavaCatalog>| async move {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                Ok(catalog_at(5_000))
+            }
+        };
+
+        let (first, second, forced) = tokio::join!(
+            serve_catalog(
+                &fetches,
+                None,
+                5_000,
+                CACHE_DURATION_SECS,
+                false,
+                counting_refresh(),
+                |_| {}
+            ),
+            serve_catalog(
+                &fetches,
+                None,
+                5_000,
+                CACHE_DURATION_SECS,
+                false,
+                counting_refresh(),
+                |_| {}
+            ),
+            serve_catalog(
+                &fetches,
+                Some(catalog_at(1_000)),
+                5_000,
+                CACHE_DURATION_SECS,
+                true,
+                counting_refresh(),
+                |_| {}
+            ),
+        );
+        for result in [first, second, forced] {
+            assert_eq!(result.unwrap().catalog.cached_at, 5_000);
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Once it's done, the next lookup fetches again
+        serve_catalog(
+            &fetches,
+            None,
+            5_000,
+            CACHE_DURATION_SECS,
+            false,
+            counting_refresh(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn catalog_cache_is_read_from_disk_once() {
+        let dir = TestDir::new("catalog");
+        let key = "adoptium-linux-x64";
+        write_catalog_cache(dir.path(), key, &catalog_at(42)).unwrap();
+
+        // Served from memory even with the file gone
+        std::fs::remove_file(catalog_cache_path(dir.path(), key)).unwrap();
+        assert_eq!(
+            read_catalog_cache_in(dir.path(), key).map(|c| c.cached_at),
+            Some(42)
+        );
+
+        clear_catalog_cache_in(dir.path()).unwrap();
+        assert!(read_catalog_cache_in(dir.path(), key).is_none());
+    }
 }
@@ -1,91 +1,695 @@
+use crate::core::http::{self, NetworkSettings};
+use crate::core::java::blocklist::KNOWN_BAD_BUILDS;
 use crate::core::java::error::JavaError;
+use crate::core::java::known::KnownInstallation;
+use crate::core::java::providers::PROVIDER_NAMES;
+use crate::core::java::validation::{self, JavaVendor};
+use crate::core::java::{ImageType, JavaInstallation};
+use crate::utils::error;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use tauri::{AppHandle, Emitter};
 use ts_rs::TS;
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+/// Version of the `java_config.json` layout written by this build. Bump it with a
+/// migration in `migrate_java_config` whenever a change needs more than a serde default.
+pub const JAVA_CONFIG_SCHEMA_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "java/persistence.ts")]
 pub struct JavaConfig {
+    /// Files from before versioning have none and count as version 1
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub user_defined_paths: Vec<String>,
     pub preferred_java_path: Option<String>,
     pub last_detection_time: u64,
+    #[serde(default)]
+    pub network: NetworkSettings,
+    #[serde(default)]
+    pub settings: JavaSettings,
+    /// Java settings of individual instances, by instance id
+    #[serde(default)]
+    pub instance_java_overrides: HashMap<String, InstanceJavaOverride>,
+    /// Result of the last full scan, shown before detection runs on startup
+    #[serde(default)]
+    pub known_installations: Vec<KnownInstallation>,
+    /// Java each instance last launched with, by instance id
+    #[serde(default)]
+    pub last_used_java: HashMap<String, LastUsedJava>,
+}
+
+/// Java an instance last launched successfully with, tried again on its next launch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/persistence.ts")]
+pub struct LastUsedJava {
+    pub java_path: String,
+    pub java_version: String,
+    /// Unix time of the launch, in seconds
+    pub used_at: u64,
+}
+
+/// Java an instance launches with instead of the global choice, e.g. a modpack that
+/// needs Java 8 at a specific path
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/persistence.ts")]
+pub struct InstanceJavaOverride {
+    /// Java executable to use
+    pub path: Option<String>,
+    /// Lowest major version allowed, instead of the game's requirement
+    pub min_version: Option<u32>,
+    /// Highest major version allowed
+    pub max_version: Option<u32>,
+    /// Extra JVM arguments, separated by whitespace
+    pub jvm_args: Option<String>,
+    /// Fail the launch instead of falling back when the configured Java can't be
+    /// used; `None` follows `JavaSettings::strict_resolution`
+    pub strict: Option<bool>,
 }
 
 impl Default for JavaConfig {
     fn default() -> Self {
         Self {
+            schema_version: JAVA_CONFIG_SCHEMA_VERSION,
             user_defined_paths: Vec::new(),
             preferred_java_path: None,
             last_detection_time: 0,
+            network: NetworkSettings::default(),
+            settings: JavaSettings::default(),
+            instance_java_overrides: HashMap::new(),
+            known_installations: Vec::new(),
+            last_used_java: HashMap::new(),
         }
     }
 }
 
-fn get_java_config_path(app_handle: &AppHandle) -> PathBuf {
-    app_handle
-        .path()
-        .app_data_dir()
-        .unwrap()
-        .join("java_config.json")
+impl JavaConfig {
+    /// Copy that is safe to hand to the UI, without the proxy password
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if let Some(proxy) = &mut config.network.proxy {
+            proxy.password = None;
+        }
+        config
+    }
+
+    /// Remember that `instance_id` launched with `java` at `now`
+    pub fn remember_last_used(&mut self, instance_id: &str, java: &JavaInstallation, now: u64) {
+        self.last_used_java.insert(
+            instance_id.to_string(),
+            LastUsedJava {
+                java_path: java.path.clone(),
+                java_version: java.version.clone(),
+                used_at: now,
+            },
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "java/persistence.ts")]
+pub enum DetectionStrategy {
+    /// Search the usual install locations as well as the user's paths
+    #[default]
+    Auto,
+    /// Only use the paths the user added
+    Manual,
+}
+
+/// A place launch resolution looks for Java, see `JavaSettings::resolution_order`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/persistence.ts")]
+pub enum JavaSourcePriority {
+    /// The instance's own Java path
+    InstanceOverride,
+    /// The Java path in the launcher settings
+    GlobalOverride,
+    /// The Java the instance last launched with
+    LastUsed,
+    /// The preferred Java path
+    Preferred,
+    /// Paths the user added
+    UserDefined,
+    /// Runtimes DropOut downloaded
+    Managed,
+    /// Everything else detection finds
+    System,
+}
+
+impl JavaSourcePriority {
+    pub const DEFAULT_ORDER: [JavaSourcePriority; 7] = [
+        JavaSourcePriority::InstanceOverride,
+        JavaSourcePriority::GlobalOverride,
+        JavaSourcePriority::LastUsed,
+        JavaSourcePriority::Preferred,
+        JavaSourcePriority::UserDefined,
+        JavaSourcePriority::Managed,
+        JavaSourcePriority::System,
+    ];
+
+    /// Whether the source is a Java the user picked, rather than one that was found
+    pub fn is_explicit(self) -> bool {
+        matches!(
+            self,
+            JavaSourcePriority::InstanceOverride
+                | JavaSourcePriority::GlobalOverride
+                | JavaSourcePriority::Preferred
+        )
+    }
+}
+
+/// Launcher-wide Java preferences
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "java/persistence.ts")]
+pub struct JavaSettings {
+    /// Provider catalogs and downloads come from, one of `providers::PROVIDER_NAMES`
+    pub provider: String,
+    pub default_image_type: ImageType,
+    /// Where runtimes are installed; `None` uses `java` in the app data directory
+    #[ts(type = "string | null")]
+    pub install_dir: Option<PathBuf>,
+    /// Resume queued Java downloads shortly after startup
+    pub auto_resume_downloads: bool,
+    /// Offer early-access builds alongside general availability releases
+    pub include_ea: bool,
+    pub detection_strategy: DetectionStrategy,
+    /// Automatic detection reuses the last scan's results for this long
+    pub min_redetect_interval_secs: u32,
+    /// Sources launch resolution tries, first to last; ones left out are skipped
+    pub resolution_order: Vec<JavaSourcePriority>,
+    /// Download the required Java when a launch finds no compatible one
+    pub auto_install_missing_java: bool,
+    /// Vendors chosen over others among compatible installations, most wanted first
+    pub preferred_vendors: Vec<JavaVendor>,
+    /// Vendors never chosen
+    pub blocked_vendors: Vec<JavaVendor>,
+    /// When a Java was picked explicitly but can't be used, fail the launch instead of
+    /// falling back to detected ones
+    pub strict_resolution: bool,
+    /// Java executables never used
+    pub blocked_java_paths: Vec<String>,
+    /// Java versions never used, e.g. `17.0.1`; `17` blocks all of Java 17
+    pub blocked_versions: Vec<String>,
+    /// Ids of `blocklist::KNOWN_BAD_BUILDS` entries to use anyway
+    pub allowed_known_bad_builds: Vec<String>,
+}
+
+impl Default for JavaSettings {
+    fn default() -> Self {
+        Self {
+            provider: "adoptium".to_string(),
+            default_image_type: ImageType::Jre,
+            install_dir: None,
+            auto_resume_downloads: true,
+            include_ea: false,
+            detection_strategy: DetectionStrategy::Auto,
+            min_redetect_interval_secs: 600,
+            resolution_order: JavaSourcePriority::DEFAULT_ORDER.to_vec(),
+            auto_install_missing_java: false,
+            preferred_vendors: Vec::new(),
+            blocked_vendors: Vec::new(),
+            strict_resolution: false,
+            blocked_java_paths: Vec::new(),
+            blocked_versions: Vec::new(),
+            allowed_known_bad_builds: Vec::new(),
+        }
+    }
+}
+
+/// Changes to `JavaSettings`; fields left out are kept as they are, so pages editing
+/// different settings don't overwrite each other
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "java/persistence.ts")]
+pub struct JavaSettingsPatch {
+    pub provider: Option<String>,
+    pub default_image_type: Option<ImageType>,
+    /// An empty path goes back to the default directory
+    #[ts(type = "string | null")]
+    pub install_dir: Option<PathBuf>,
+    pub auto_resume_downloads: Option<bool>,
+    pub include_ea: Option<bool>,
+    pub detection_strategy: Option<DetectionStrategy>,
+    pub min_redetect_interval_secs: Option<u32>,
+    pub resolution_order: Option<Vec<JavaSourcePriority>>,
+    pub auto_install_missing_java: Option<bool>,
+    pub preferred_vendors: Option<Vec<JavaVendor>>,
+    pub blocked_vendors: Option<Vec<JavaVendor>>,
+    pub strict_resolution: Option<bool>,
+    pub blocked_java_paths: Option<Vec<String>>,
+    pub blocked_versions: Option<Vec<String>>,
+    pub allowed_known_bad_builds: Option<Vec<String>>,
+}
+
+impl JavaSettings {
+    /// Apply the fields set in `patch`, rejecting an unknown provider
+    pub fn apply(&mut self, patch: JavaSettingsPatch) -> Result<(), JavaError> {
+        if let Some(provider) = patch.provider {
+            if !PROVIDER_NAMES.contains(&provider.as_str()) {
+                return Err(JavaError::InvalidConfig(format!(
+                    "Unknown Java provider: {}",
+                    provider
+                )));
+            }
+            self.provider = provider;
+        }
+        if let Some(image_type) = patch.default_image_type {
+            self.default_image_type = image_type;
+        }
+        if let Some(install_dir) = patch.install_dir {
+            self.install_dir = (!install_dir.as_os_str().is_empty()).then_some(install_dir);
+        }
+        if let Some(auto_resume) = patch.auto_resume_downloads {
+            self.auto_resume_downloads = auto_resume;
+        }
+        if let Some(include_ea) = patch.include_ea {
+            self.include_ea = include_ea;
+        }
+        if let Some(strategy) = patch.detection_strategy {
+            self.detection_strategy = strategy;
+        }
+        if let Some(interval) = patch.min_redetect_interval_secs {
+            self.min_redetect_interval_secs = interval;
+        }
+        if let Some(order) = patch.resolution_order {
+            validate_resolution_order(&order)?;
+            self.resolution_order = order;
+        }
+        if let Some(auto_install) = patch.auto_install_missing_java {
+            self.auto_install_missing_java = auto_install;
+        }
+        if let Some(strict) = patch.strict_resolution {
+            self.strict_resolution = strict;
+        }
+        if let Some(paths) = patch.blocked_java_paths {
+            self.blocked_java_paths = paths;
+        }
+        if let Some(versions) = patch.blocked_versions {
+            self.blocked_versions = versions;
+        }
+        if let Some(allowed) = patch.allowed_known_bad_builds {
+            if let Some(unknown) = allowed
+                .iter()
+                .find(|id| !KNOWN_BAD_BUILDS.iter().any(|build| build.id == id.as_str()))
+            {
+                return Err(JavaError::InvalidConfig(format!(
+                    "Unknown known-bad Java build: {}",
+                    unknown
+                )));
+            }
+            self.allowed_known_bad_builds = allowed;
+        }
+        let preferred = patch
+            .preferred_vendors
+            .unwrap_or_else(|| self.preferred_vendors.clone());
+        let blocked = patch
+            .blocked_vendors
+            .unwrap_or_else(|| self.blocked_vendors.clone());
+        if let Some(vendor) = preferred.iter().find(|vendor| blocked.contains(vendor)) {
+            return Err(JavaError::InvalidConfig(format!(
+                "Java vendor {:?} can't be both preferred and blocked",
+                vendor
+            )));
+        }
+        self.preferred_vendors = preferred;
+        self.blocked_vendors = blocked;
+        Ok(())
+    }
+}
+
+/// Unknown sources already fail to deserialize; this rejects an empty order and
+/// sources listed twice
+fn validate_resolution_order(order: &[JavaSourcePriority]) -> Result<(), JavaError> {
+    if order.is_empty() {
+        return Err(JavaError::InvalidConfig(
+            "Java resolution order must list at least one source".to_string(),
+        ));
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = order.iter().find(|source| !seen.insert(**source)) {
+        return Err(JavaError::InvalidConfig(format!(
+            "Java source {:?} is listed more than once",
+            duplicate
+        )));
+    }
+    Ok(())
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// Version 1 had no `schema_version` and could lack `network` and
+/// `last_detection_time`
+fn migrate_v1_to_v2(config: &mut serde_json::Map<String, serde_json::Value>) {
+    config
+        .entry("user_defined_paths")
+        .or_insert_with(|| serde_json::json!([]));
+    config
+        .entry("preferred_java_path")
+        .or_insert(serde_json::Value::Null);
+    config
+        .entry("last_detection_time")
+        .or_insert_with(|| serde_json::json!(0));
+    config
+        .entry("network")
+        .or_insert_with(|| serde_json::to_value(NetworkSettings::default()).unwrap());
+}
+
+/// Version 3 added the `LastUsed` source; orders saved before it get it right after
+/// the global override, where the default order has it
+fn migrate_v2_to_v3(config: &mut serde_json::Map<String, serde_json::Value>) {
+    let Some(order) = config
+        .get_mut("settings")
+        .and_then(|settings| settings.get_mut("resolutionOrder"))
+        .and_then(|order| order.as_array_mut())
+    else {
+        return;
+    };
+    let last_used = serde_json::json!("LastUsed");
+    if order.contains(&last_used) {
+        return;
+    }
+    let at = order
+        .iter()
+        .position(|source| source == "GlobalOverride")
+        .map_or(0, |i| i + 1);
+    order.insert(at, last_used);
+}
+
+/// Bring a parsed config file up to `JAVA_CONFIG_SCHEMA_VERSION`, one version at a time
+pub(super) fn migrate_java_config(mut value: serde_json::Value) -> Result<JavaConfig, JavaError> {
+    let config = value.as_object_mut().ok_or_else(|| {
+        JavaError::SerializationError("Java config is not a JSON object".to_string(), None)
+    })?;
+    let version = config
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1) as u32;
+    if version > JAVA_CONFIG_SCHEMA_VERSION {
+        log::warn!(
+            "Java config is schema version {}, newer than {}; reading what is understood",
+            version,
+            JAVA_CONFIG_SCHEMA_VERSION
+        );
+    }
+    if version < 2 {
+        migrate_v1_to_v2(config);
+    }
+    if version < 3 {
+        migrate_v2_to_v3(config);
+    }
+    config.insert(
+        "schema_version".to_string(),
+        serde_json::json!(version.max(JAVA_CONFIG_SCHEMA_VERSION)),
+    );
+    serde_json::from_value(value)
+        .map_err(|e| JavaError::serialization("Failed to read the Java config", e))
+}
+
+/// Keep an unreadable config as `<name>.corrupt-<unix time>` so saving defaults over
+/// it doesn't lose what the user had. `what` names the config in the log.
+pub(crate) fn set_aside_corrupt_config(config_path: &Path, what: &str) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut backup_name = config_path.file_name().unwrap_or_default().to_os_string();
+    backup_name.push(format!(".corrupt-{}", timestamp));
+    let backup_path = config_path.with_file_name(backup_name);
+    match std::fs::copy(config_path, &backup_path) {
+        Ok(_) => log::warn!("Kept the unreadable {} as {}", what, backup_path.display()),
+        Err(e) => log::warn!("Failed to keep a copy of the unreadable {}: {}", what, e),
+    }
+}
+
+/// Write `content` to a sibling temp file, sync it and rename it over `path`, so
+/// readers see either the old or the new file, never a half-written one
+pub fn write_file_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = std::fs::File::create(&tmp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&tmp_path, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp_path);
+    })
 }
 
+pub(super) fn get_java_config_path(app_handle: &AppHandle) -> Result<PathBuf, JavaError> {
+    Ok(java_config_path_in(&crate::core::profiles::profile_dir(
+        app_handle,
+    )?))
+}
+
+/// Java config of the profile whose data lives in `profile_dir`
+pub(super) fn java_config_path_in(profile_dir: &Path) -> PathBuf {
+    profile_dir.join("java_config.json")
+}
+
+/// Set once the user has been told their config came from the backup
+static BACKUP_RESTORE_REPORTED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+fn backup_path(config_path: &Path) -> PathBuf {
+    let mut name = config_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    config_path.with_file_name(name)
+}
+
+/// Load the Java config. If it is unreadable but the backup isn't, the backup is used
+/// and `java-config-restored` is emitted once so the user knows settings may be
+/// slightly stale. Without a data directory to read it from, the defaults are used.
 pub fn load_java_config(app_handle: &AppHandle) -> JavaConfig {
-    let config_path = get_java_config_path(app_handle);
-    if !config_path.exists() {
+    let Ok(config_path) = get_java_config_path(app_handle) else {
         return JavaConfig::default();
+    };
+    let (config, from_backup) = current_config_at(&config_path);
+    if from_backup && !BACKUP_RESTORE_REPORTED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+        let _ = app_handle.emit("java-config-restored", ());
     }
+    config
+}
 
-    match std::fs::read_to_string(&config_path) {
-        Ok(content) => match serde_json::from_str(&content) {
-            Ok(config) => config,
-            Err(err) => {
-                // Log the error but don't panic - return default config
-                log::warn!(
-                    "Failed to parse Java config at {}: {}. Using default configuration.",
-                    config_path.display(),
-                    err
-                );
-                JavaConfig::default()
-            }
-        },
-        Err(err) => {
-            log::warn!(
-                "Failed to read Java config at {}: {}. Using default configuration.",
-                config_path.display(),
-                err
-            );
-            JavaConfig::default()
+/// Java configs in use, by file. They are read from disk once and every change goes
+/// through `config_transaction_at` under this lock, so concurrent changes can't
+/// overwrite each other.
+static JAVA_CONFIGS: LazyLock<Mutex<HashMap<PathBuf, JavaConfig>>> =
+    LazyLock::new(Default::default);
+
+/// The config at `config_path`, and whether it was just loaded from the backup
+pub(super) fn current_config_at(config_path: &Path) -> (JavaConfig, bool) {
+    let mut configs = JAVA_CONFIGS.lock().unwrap();
+    if let Some(config) = configs.get(config_path) {
+        return (config.clone(), false);
+    }
+    let (config, from_backup) = load_java_config_from(config_path);
+    configs.insert(config_path.to_path_buf(), config.clone());
+    (config, from_backup)
+}
+
+/// Read the Java config from disk again, dropping the copy in memory, and emit
+/// `java-config-changed`
+pub fn reload_java_config(app_handle: &AppHandle) -> Result<JavaConfig, JavaError> {
+    let config_path = get_java_config_path(app_handle)?;
+    JAVA_CONFIGS.lock().unwrap().remove(&config_path);
+    let config = load_java_config(app_handle);
+    notify_config_changed(app_handle, &config);
+    Ok(config)
+}
+
+fn read_java_config(config_path: &Path) -> Result<JavaConfig, JavaError> {
+    let content = std::fs::read_to_string(config_path).map_err(|e| {
+        JavaError::io_at(
+            format!("Failed to read {}", config_path.display()),
+            config_path,
+            e,
+        )
+    })?;
+    let value = serde_json::from_str(&content)
+        .map_err(|e| JavaError::serialization("The Java config is not valid JSON", e))?;
+    migrate_java_config(value)
+}
+
+/// The config, and whether it came from the backup
+pub(super) fn load_java_config_from(config_path: &Path) -> (JavaConfig, bool) {
+    if !config_path.exists() {
+        return (JavaConfig::default(), false);
+    }
+
+    let err = match read_java_config(config_path) {
+        Ok(config) => return (config, false),
+        Err(err) => err,
+    };
+    log::warn!(
+        "Failed to load Java config at {}: {}",
+        config_path.display(),
+        err.with_code()
+    );
+    set_aside_corrupt_config(config_path, "Java config");
+
+    let backup_path = backup_path(config_path);
+    match read_java_config(&backup_path) {
+        Ok(config) => {
+            log::warn!("Using the Java config backup at {}", backup_path.display());
+            (config, true)
+        }
+        Err(_) => {
+            log::warn!("No usable Java config backup. Using default configuration.");
+            (JavaConfig::default(), false)
         }
     }
 }
 
-pub fn save_java_config(app_handle: &AppHandle, config: &JavaConfig) -> Result<(), JavaError> {
-    let config_path = get_java_config_path(app_handle);
+/// Emit `java-config-changed` with the saved config, so other open views can update
+pub(super) fn notify_config_changed(app_handle: &AppHandle, config: &JavaConfig) {
+    let _ = app_handle.emit("java-config-changed", config.redacted());
+}
+
+/// Make any number of changes to the config in `f`, then save it and emit
+/// `java-config-changed` once. Nothing is saved if `f` fails or changes nothing.
+pub fn with_config_transaction<T>(
+    app_handle: &AppHandle,
+    f: impl FnOnce(&mut JavaConfig) -> Result<T, JavaError>,
+) -> Result<T, JavaError> {
+    config_transaction_at(
+        &get_java_config_path(app_handle)?,
+        |config| notify_config_changed(app_handle, config),
+        f,
+    )
+}
+
+pub(super) fn config_transaction_at<T>(
+    config_path: &Path,
+    notify: impl FnOnce(&JavaConfig),
+    f: impl FnOnce(&mut JavaConfig) -> Result<T, JavaError>,
+) -> Result<T, JavaError> {
+    let mut configs = JAVA_CONFIGS.lock().unwrap();
+    let current = configs
+        .entry(config_path.to_path_buf())
+        .or_insert_with(|| load_java_config_from(config_path).0);
+    let mut config = current.clone();
+    let result = f(&mut config)?;
+    if config != *current {
+        save_java_config_to(config_path, &config)?;
+        *current = config;
+        notify(current);
+    }
+    Ok(result)
+}
+
+/// Write the config, first copying the current file to the backup if it is readable
+pub(super) fn save_java_config_to(
+    config_path: &Path,
+    config: &JavaConfig,
+) -> Result<(), JavaError> {
     let content = serde_json::to_string_pretty(config)?;
 
     std::fs::create_dir_all(config_path.parent().ok_or_else(|| {
         JavaError::InvalidConfig("Java config path has no parent directory".to_string())
     })?)?;
 
-    std::fs::write(&config_path, content)?;
+    if read_java_config(config_path).is_ok()
+        && let Err(e) = std::fs::copy(config_path, backup_path(config_path))
+    {
+        log::warn!("Failed to back up the Java config: {}", e);
+    }
+    write_file_atomic(config_path, content.as_bytes())?;
     Ok(())
 }
 
-#[allow(dead_code)]
-pub fn add_user_defined_path(app_handle: &AppHandle, path: String) -> Result<(), JavaError> {
-    let mut config = load_java_config(app_handle);
-    if !config.user_defined_paths.contains(&path) {
-        config.user_defined_paths.push(path);
-    }
-    save_java_config(app_handle, &config)
+/// Replace the Java config with its backup, for manual recovery
+pub fn restore_java_config_backup(app_handle: &AppHandle) -> Result<JavaConfig, JavaError> {
+    let config_path = get_java_config_path(app_handle)?;
+    let config = restore_java_config_backup_at(&config_path)?;
+    JAVA_CONFIGS
+        .lock()
+        .unwrap()
+        .insert(config_path, config.clone());
+    notify_config_changed(app_handle, &config);
+    Ok(config)
+}
+
+fn restore_java_config_backup_at(config_path: &Path) -> Result<JavaConfig, JavaError> {
+    let backup_path = backup_path(config_path);
+    let config = read_java_config(&backup_path).map_err(|e| {
+        JavaError::InvalidConfig(format!(
+            "No usable Java config backup: {}",
+            error::display_chain(&e)
+        ))
+    })?;
+    let content = std::fs::read(&backup_path)?;
+    write_file_atomic(config_path, &content)?;
+    Ok(config)
+}
+
+/// Canonical Java executable for a path to either the binary or a Java home
+fn resolve_java_executable(path: &Path) -> Result<PathBuf, JavaError> {
+    let executable = if path.is_dir() {
+        super::find_java_executable(&path.to_path_buf()).ok_or(JavaError::NotFound)?
+    } else if path.is_file() {
+        path.to_path_buf()
+    } else {
+        return Err(JavaError::NotFound);
+    };
+    let canonical = std::fs::canonicalize(&executable)?;
+    Ok(super::strip_unc_prefix(canonical))
+}
+
+/// Whether `executable` is already among the user's paths, comparing canonical forms
+fn is_user_defined_path(config: &JavaConfig, executable: &Path) -> bool {
+    config.user_defined_paths.iter().any(|existing| {
+        std::fs::canonicalize(existing)
+            .map(super::strip_unc_prefix)
+            .is_ok_and(|existing| existing == executable)
+    })
+}
+
+/// Check a Java binary or Java home picked by the user and remember its executable.
+/// Returns the installation so it can be shown straight away.
+pub async fn add_user_defined_path(
+    app_handle: &AppHandle,
+    path: String,
+) -> Result<JavaInstallation, JavaError> {
+    let executable = resolve_java_executable(Path::new(&path))?;
+    let installation = validation::check_java_installation(&executable)
+        .await
+        .ok_or_else(|| {
+            JavaError::VerificationFailed(format!(
+                "{} did not report a Java version",
+                executable.display()
+            ))
+        })?;
+
+    with_config_transaction(app_handle, |config| {
+        if is_user_defined_path(config, &executable) {
+            return Err(JavaError::InvalidConfig(format!(
+                "{} has already been added",
+                executable.display()
+            )));
+        }
+        config
+            .user_defined_paths
+            .push(executable.to_string_lossy().into_owned());
+        Ok(())
+    })?;
+    Ok(installation)
 }
 
 #[allow(dead_code)]
 pub fn remove_user_defined_path(app_handle: &AppHandle, path: &str) -> Result<(), JavaError> {
-    let mut config = load_java_config(app_handle);
-    config.user_defined_paths.retain(|p| p != path);
-    save_java_config(app_handle, &config)
+    with_config_transaction(app_handle, |config| {
+        config.user_defined_paths.retain(|p| p != path);
+        Ok(())
+    })
 }
 
 #[allow(dead_code)]
@@ -93,9 +697,10 @@ pub fn set_preferred_java_path(
     app_handle: &AppHandle,
     path: Option<String>,
 ) -> Result<(), JavaError> {
-    let mut config = load_java_config(app_handle);
-    config.preferred_java_path = path;
-    save_java_config(app_handle, &config)
+    with_config_transaction(app_handle, |config| {
+        config.preferred_java_path = path;
+        Ok(())
+    })
 }
 
 #[allow(dead_code)]
@@ -104,12 +709,488 @@ pub fn get_preferred_java_path(app_handle: &AppHandle) -> Option<String> {
     config.preferred_java_path
 }
 
-#[allow(dead_code)]
 pub fn update_last_detection_time(app_handle: &AppHandle) -> Result<(), JavaError> {
-    let mut config = load_java_config(app_handle);
-    config.last_detection_time = std::time::SystemTime::now()
+    let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map_err(|e| JavaError::Other(format!("System time error: {}", e)))?
         .as_secs();
-    save_java_config(app_handle, &config)
+    with_config_transaction(app_handle, |config| {
+        config.last_detection_time = now;
+        Ok(())
+    })
+}
+
+pub fn set_known_installations(
+    app_handle: &AppHandle,
+    known: Vec<KnownInstallation>,
+) -> Result<(), JavaError> {
+    with_config_transaction(app_handle, |config| {
+        config.known_installations = known;
+        Ok(())
+    })
+}
+
+pub fn get_instance_java_override(
+    app_handle: &AppHandle,
+    instance_id: &str,
+) -> Option<InstanceJavaOverride> {
+    load_java_config(app_handle)
+        .instance_java_overrides
+        .remove(instance_id)
+}
+
+pub fn set_instance_java_override(
+    app_handle: &AppHandle,
+    instance_id: &str,
+    java_override: InstanceJavaOverride,
+) -> Result<(), JavaError> {
+    with_config_transaction(app_handle, |config| {
+        config
+            .instance_java_overrides
+            .insert(instance_id.to_string(), java_override);
+        Ok(())
+    })
+}
+
+/// Forget an instance's override, e.g. once the instance is deleted
+pub fn remove_instance_java_override(
+    app_handle: &AppHandle,
+    instance_id: &str,
+) -> Result<(), JavaError> {
+    with_config_transaction(app_handle, |config| {
+        config.instance_java_overrides.remove(instance_id);
+        Ok(())
+    })
+}
+
+/// Remember the Java an instance just launched with, so its next launch tries it first
+pub fn record_last_used_java(
+    app_handle: &AppHandle,
+    instance_id: &str,
+    java: &JavaInstallation,
+) -> Result<(), JavaError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| JavaError::Other(format!("System time error: {}", e)))?
+        .as_secs();
+    with_config_transaction(app_handle, |config| {
+        config.remember_last_used(instance_id, java, now);
+        Ok(())
+    })
+}
+
+/// Forget the Java an instance last launched with
+pub fn clear_last_used_java(app_handle: &AppHandle, instance_id: &str) -> Result<(), JavaError> {
+    with_config_transaction(app_handle, |config| {
+        config.last_used_java.remove(instance_id);
+        Ok(())
+    })
+}
+
+pub fn get_java_settings(app_handle: &AppHandle) -> JavaSettings {
+    load_java_config(app_handle).settings
+}
+
+/// Apply `patch` to the saved settings and emit `java-settings-changed` with the result
+pub fn update_java_settings(
+    app_handle: &AppHandle,
+    patch: JavaSettingsPatch,
+) -> Result<JavaSettings, JavaError> {
+    let settings = with_config_transaction(app_handle, |config| {
+        config.settings.apply(patch)?;
+        Ok(config.settings.clone())
+    })?;
+    let _ = app_handle.emit("java-settings-changed", &settings);
+    Ok(settings)
+}
+
+pub fn get_network_settings(app_handle: &AppHandle) -> NetworkSettings {
+    load_java_config(app_handle).network
+}
+
+/// Validate and persist network settings; they are not applied here
+pub fn set_network_settings(
+    app_handle: &AppHandle,
+    settings: NetworkSettings,
+) -> Result<(), JavaError> {
+    settings.validate().map_err(JavaError::InvalidConfig)?;
+    http::build_client(&settings).map_err(JavaError::InvalidConfig)?;
+    with_config_transaction(app_handle, |config| {
+        config.network = settings;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn v1_config_is_migrated_keeping_the_preferred_path() {
+        let dir = TestDir::new("java-config");
+        let path = dir.path().join("java_config.json");
+        std::fs::write(
+            &path,
+            r#"{"user_defined_paths":["/opt/jdk"],"preferred_java_path":"/opt/jdk/bin/java"}"#,
+        )
+        .unwrap();
+
+        let (config, _) = load_java_config_from(&path);
+        assert_eq!(config.schema_version, JAVA_CONFIG_SCHEMA_VERSION);
+        assert_eq!(
+            config.preferred_java_path.as_deref(),
+            Some("/opt/jdk/bin/java")
+        );
+        assert_eq!(config.user_defined_paths, vec!["/opt/jdk".to_string()]);
+        assert_eq!(config.last_detection_time, 0);
+
+        std::fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+        let (reloaded, _) = load_java_config_from(&path);
+        assert_eq!(reloaded.schema_version, JAVA_CONFIG_SCHEMA_VERSION);
+        assert_eq!(reloaded.preferred_java_path, config.preferred_java_path);
+    }
+
+    #[test]
+    fn v2_resolution_order_gains_last_used_after_the_global_override() {
+        let config = migrate_java_config(serde_json::json!({
+            "schema_version": 2,
+            "user_defined_paths": [],
+            "preferred_java_path": null,
+            "last_detection_time": 0,
+            "settings": { "resolutionOrder": ["System", "GlobalOverride", "Managed"] }
+        }))
+        .unwrap();
+        assert_eq!(
+            config.settings.resolution_order,
+            vec![
+                JavaSourcePriority::System,
+                JavaSourcePriority::GlobalOverride,
+                JavaSourcePriority::LastUsed,
+                JavaSourcePriority::Managed,
+            ]
+        );
+        assert!(config.last_used_java.is_empty());
+    }
+
+    #[test]
+    fn unreadable_config_is_kept_aside() {
+        let dir = TestDir::new("java-config");
+        let path = dir.path().join("java_config.json");
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let (config, from_backup) = load_java_config_from(&path);
+        assert!(config.preferred_java_path.is_none());
+        assert!(!from_backup);
+        let backups: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .filter(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .starts_with("java_config.json.corrupt-")
+            })
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(backups[0].path()).unwrap(),
+            "{ not json"
+        );
+    }
+
+    #[test]
+    fn corrupt_config_falls_back_to_the_backup() {
+        let dir = TestDir::new("java-config");
+        let path = dir.path().join("java_config.json");
+        let config = |java: &str| JavaConfig {
+            preferred_java_path: Some(java.to_string()),
+            ..JavaConfig::default()
+        };
+        save_java_config_to(&path, &config("/old/java")).unwrap();
+        // The first save has nothing to back up
+        assert!(!backup_path(&path).exists());
+        save_java_config_to(&path, &config("/new/java")).unwrap();
+
+        std::fs::write(&path, r#"{"user_defined_paths": ["#).unwrap();
+        let (loaded, from_backup) = load_java_config_from(&path);
+        assert!(from_backup);
+        assert_eq!(loaded.preferred_java_path.as_deref(), Some("/old/java"));
+
+        // The corrupt primary isn't backed up over the good copy
+        save_java_config_to(&path, &loaded).unwrap();
+        let (loaded, from_backup) = load_java_config_from(&path);
+        assert!(!from_backup);
+        assert_eq!(loaded.preferred_java_path.as_deref(), Some("/old/java"));
+        assert_eq!(
+            read_java_config(&backup_path(&path))
+                .unwrap()
+                .preferred_java_path
+                .as_deref(),
+            Some("/old/java")
+        );
+    }
+
+    #[test]
+    fn backup_can_be_restored_by_hand() {
+        let dir = TestDir::new("java-config");
+        let path = dir.path().join("java_config.json");
+        assert!(restore_java_config_backup_at(&path).is_err());
+
+        save_java_config_to(
+            &path,
+            &JavaConfig {
+                last_detection_time: 10,
+                ..JavaConfig::default()
+            },
+        )
+        .unwrap();
+        save_java_config_to(
+            &path,
+            &JavaConfig {
+                last_detection_time: 20,
+                ..JavaConfig::default()
+            },
+        )
+        .unwrap();
+
+        let restored = restore_java_config_backup_at(&path).unwrap();
+        assert_eq!(restored.last_detection_time, 10);
+        assert_eq!(read_java_config(&path).unwrap().last_detection_time, 10);
+    }
+
+    #[test]
+    fn instance_java_overrides_round_trip_and_can_be_removed() {
+        let dir = TestDir::new("java-config");
+        let path = dir.path().join("java_config.json");
+        let java8 = InstanceJavaOverride {
+            path: Some("/opt/jdk8/bin/java".to_string()),
+            min_version: Some(8),
+            max_version: Some(8),
+            jvm_args: Some("-XX:+UseG1GC -Xss2m".to_string()),
+            strict: Some(true),
+        };
+        let mut config = JavaConfig::default();
+        config
+            .instance_java_overrides
+            .insert("modpack".to_string(), java8.clone());
+        config.instance_java_overrides.insert(
+            "vanilla".to_string(),
+            InstanceJavaOverride {
+                min_version: Some(21),
+                ..Default::default()
+            },
+        );
+        save_java_config_to(&path, &config).unwrap();
+
+        let (mut loaded, _) = load_java_config_from(&path);
+        assert_eq!(loaded.instance_java_overrides.get("modpack"), Some(&java8));
+        assert_eq!(loaded.instance_java_overrides.len(), 2);
+
+        // Deleting an instance drops its override only
+        loaded.instance_java_overrides.remove("vanilla");
+        save_java_config_to(&path, &loaded).unwrap();
+        let (loaded, _) = load_java_config_from(&path);
+        assert!(!loaded.instance_java_overrides.contains_key("vanilla"));
+        assert_eq!(loaded.instance_java_overrides.get("modpack"), Some(&java8));
+    }
+
+    #[test]
+    fn user_defined_paths_resolve_to_a_canonical_executable() {
+        let dir = TestDir::new("java-config");
+        assert!(matches!(
+            resolve_java_executable(&dir.path().join("typo")),
+            Err(JavaError::NotFound)
+        ));
+        // A directory without a Java in it
+        assert!(matches!(
+            resolve_java_executable(dir.path()),
+            Err(JavaError::NotFound)
+        ));
+
+        let bin_name = if cfg!(windows) { "java.exe" } else { "java" };
+        let home = dir.path().join("jdk-17");
+        std::fs::create_dir_all(home.join("bin")).unwrap();
+        std::fs::write(home.join("bin").join(bin_name), b"").unwrap();
+        let executable = resolve_java_executable(&home).unwrap();
+        assert_eq!(
+            executable,
+            resolve_java_executable(&home.join("bin/../bin").join(bin_name)).unwrap()
+        );
+
+        let config = JavaConfig {
+            user_defined_paths: vec![
+                home.join("bin")
+                    .join(".")
+                    .join(bin_name)
+                    .to_string_lossy()
+                    .into_owned(),
+            ],
+            ..JavaConfig::default()
+        };
+        assert!(is_user_defined_path(&config, &executable));
+        assert!(!is_user_defined_path(&JavaConfig::default(), &executable));
+    }
+
+    #[test]
+    fn settings_default_when_missing_and_patches_touch_only_given_fields() {
+        let dir = TestDir::new("java-config");
+        let path = dir.path().join("java_config.json");
+        std::fs::write(&path, r#"{"schema_version":2,"user_defined_paths":[]}"#).unwrap();
+        let (config, _) = load_java_config_from(&path);
+        assert_eq!(config.settings, JavaSettings::default());
+
+        let mut settings = JavaSettings {
+            install_dir: Some(PathBuf::from("/data/java")),
+            ..JavaSettings::default()
+        };
+        settings
+            .apply(JavaSettingsPatch {
+                include_ea: Some(true),
+                ..Default::default()
+            })
+            .unwrap();
+        settings
+            .apply(JavaSettingsPatch {
+                default_image_type: Some(ImageType::Jdk),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(settings.include_ea);
+        assert_eq!(settings.default_image_type, ImageType::Jdk);
+        assert_eq!(settings.install_dir, Some(PathBuf::from("/data/java")));
+
+        settings
+            .apply(JavaSettingsPatch {
+                install_dir: Some(PathBuf::new()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(settings.install_dir, None);
+
+        let unknown = settings.apply(JavaSettingsPatch {
+            provider: Some("nowhere".to_string()),
+            ..Default::default()
+        });
+        assert!(matches!(unknown, Err(JavaError::InvalidConfig(_))));
+        assert_eq!(settings.provider, "adoptium");
+
+        let duplicate = settings.apply(JavaSettingsPatch {
+            resolution_order: Some(vec![
+                JavaSourcePriority::System,
+                JavaSourcePriority::Managed,
+                JavaSourcePriority::System,
+            ]),
+            ..Default::default()
+        });
+        assert!(matches!(duplicate, Err(JavaError::InvalidConfig(_))));
+        assert_eq!(
+            settings.resolution_order,
+            JavaSourcePriority::DEFAULT_ORDER.to_vec()
+        );
+        assert!(
+            serde_json::from_str::<JavaSettingsPatch>(r#"{"resolutionOrder":["Cloud"]}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn config_transaction_saves_and_notifies_once() {
+        let dir = TestDir::new("java-config");
+        let path = dir.path().join("java_config.json");
+        let mut notified = Vec::new();
+        config_transaction_at(
+            &path,
+            |config| notified.push(config.clone()),
+            |config| {
+                config.preferred_java_path = Some("/opt/jdk/bin/java".to_string());
+                config
+                    .user_defined_paths
+                    .push("/opt/jdk/bin/java".to_string());
+                config.settings.include_ea = true;
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(notified.len(), 1);
+        assert!(notified[0].settings.include_ea);
+        let (saved, _) = load_java_config_from(&path);
+        assert_eq!(saved, notified[0]);
+
+        // Unchanged or failed transactions stay quiet
+        let mut count = 0;
+        config_transaction_at(&path, |_| count += 1, |_| Ok(())).unwrap();
+        let failed: Result<(), _> = config_transaction_at(
+            &path,
+            |_| count += 1,
+            |config| {
+                config.user_defined_paths.clear();
+                Err(JavaError::NotFound)
+            },
+        );
+        assert!(failed.is_err());
+        assert_eq!(count, 0);
+        assert_eq!(load_java_config_from(&path).0, saved);
+    }
+
+    #[test]
+    fn redacted_config_has_no_proxy_password() {
+        let mut config = JavaConfig::default();
+        config.network.proxy = Some(http::ProxyConfig {
+            scheme: http::ProxyScheme::Http,
+            host: "proxy.example.com".to_string(),
+            port: 3128,
+            username: Some("user".to_string()),
+            password: Some("hunter2".to_string()),
+        });
+        let proxy = config.redacted().network.proxy.unwrap();
+        assert_eq!(proxy.username.as_deref(), Some("user"));
+        assert_eq!(proxy.password, None);
+    }
+
+    #[test]
+    fn concurrent_changes_are_not_lost() {
+        let dir = TestDir::new("java-config");
+        let path = dir.path().join("java_config.json");
+        let threads: Vec<_> = (0..100)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let java = format!("/opt/jdk-{}/bin/java", i);
+                    config_transaction_at(
+                        &path,
+                        |_| {},
+                        |config| {
+                            config.user_defined_paths.push(java.clone());
+                            Ok(())
+                        },
+                    )
+                    .unwrap();
+                    if i % 2 == 1 {
+                        config_transaction_at(
+                            &path,
+                            |_| {},
+                            |config| {
+                                config.user_defined_paths.retain(|p| *p != java);
+                                Ok(())
+                            },
+                        )
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let (config, _) = current_config_at(&path);
+        let mut paths = config.user_defined_paths.clone();
+        paths.sort();
+        let mut expected: Vec<_> = (0..100)
+            .step_by(2)
+            .map(|i| format!("/opt/jdk-{}/bin/java", i))
+            .collect();
+        expected.sort();
+        assert_eq!(paths, expected);
+        assert_eq!(load_java_config_from(&path).0, config);
+    }
 }
@@ -1,52 +1,447 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use tauri::AppHandle;
+use ts_rs::TS;
 
 use crate::core::java::JavaInstallation;
-use crate::core::java::persistence;
-use crate::core::java::validation;
+use crate::core::java::error::JavaError;
+use crate::core::java::persistence::{self, JavaConfig, JavaSettings, JavaSourcePriority};
+use crate::core::java::{blocklist, validation};
 
+/// Most steps a trace keeps, so a machine with many installations can't make it huge
+pub const MAX_RESOLUTION_STEPS: usize = 50;
+
+/// What resolution made of one candidate, or of a source without any
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+#[ts(export, tag = "kind", export_to = "java/priority.ts")]
+pub enum ResolutionOutcome {
+    Accepted,
+    /// Runs, but its major version is outside the bounds
+    RejectedVersion {
+        found: u32,
+        required: Option<u32>,
+        max: Option<u32>,
+    },
+    /// Doesn't run as Java
+    RejectedInvalid {
+        reason: String,
+    },
+    /// Its vendor is blocked
+    RejectedVendor {
+        vendor: String,
+    },
+    /// The build is known to break Minecraft or the user blocked it
+    RejectedBlocked {
+        reason: String,
+    },
+    /// The source had no candidates
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/priority.ts")]
+pub struct ResolutionStep {
+    pub source: JavaSourcePriority,
+    pub candidate_path: Option<String>,
+    pub outcome: ResolutionOutcome,
+}
+
+/// The Java chosen for a launch and how it was found
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/priority.ts")]
+pub struct JavaResolution {
+    pub selected: Option<JavaInstallation>,
+    pub steps: Vec<ResolutionStep>,
+    /// Steps were left out to stay within `MAX_RESOLUTION_STEPS`
+    pub truncated: bool,
+    /// The rejected explicitly configured Java that stopped a strict resolution
+    pub strict_failure: Option<ResolutionStep>,
+    /// Shortcomings of the selected Java, e.g. being 32-bit, that nothing better
+    /// avoided
+    pub warnings: Vec<String>,
+}
+
+impl JavaResolution {
+    /// The error a launch fails with when strict resolution stopped
+    pub fn strict_error(&self) -> Option<JavaError> {
+        let step = self.strict_failure.as_ref()?;
+        let reason = match &step.outcome {
+            ResolutionOutcome::RejectedVersion {
+                found,
+                required,
+                max,
+            } => format!(
+                "it is Java {}, but {} is required",
+                found,
+                describe_version_range(*required, *max)
+            ),
+            ResolutionOutcome::RejectedInvalid { reason } => reason.clone(),
+            ResolutionOutcome::RejectedVendor { vendor } => {
+                format!("its vendor {} is blocked", vendor)
+            }
+            ResolutionOutcome::RejectedBlocked { reason } => format!("it is blocked: {}", reason),
+            ResolutionOutcome::Accepted | ResolutionOutcome::Skipped => return None,
+        };
+        Some(JavaError::StrictResolutionFailed {
+            path: step.candidate_path.clone().unwrap_or_default(),
+            reason,
+        })
+    }
+
+    /// The Java to launch a version needing Java `required` up to `max` with, or the
+    /// error the launch fails with
+    pub fn launch_java(
+        &self,
+        required: u32,
+        max: Option<u32>,
+    ) -> Result<&JavaInstallation, JavaError> {
+        match (&self.selected, self.strict_error()) {
+            (Some(java), _) => Ok(java),
+            (None, Some(error)) => Err(error),
+            (None, None) => Err(JavaError::NoCompatibleJava { required, max }),
+        }
+    }
+
+    fn record(
+        &mut self,
+        source: JavaSourcePriority,
+        candidate_path: Option<String>,
+        outcome: ResolutionOutcome,
+    ) {
+        // The last slot is kept for the accepted candidate
+        let limit = if outcome == ResolutionOutcome::Accepted {
+            MAX_RESOLUTION_STEPS
+        } else {
+            MAX_RESOLUTION_STEPS - 1
+        };
+        if self.steps.len() < limit {
+            self.steps.push(ResolutionStep {
+                source,
+                candidate_path,
+                outcome,
+            });
+        } else {
+            self.truncated = true;
+        }
+    }
+}
+
+/// A path a source offers, checked or not
+struct Candidate {
+    path: String,
+    java: Result<JavaInstallation, String>,
+}
+
+impl From<JavaInstallation> for Candidate {
+    fn from(java: JavaInstallation) -> Self {
+        Self {
+            path: java.path.clone(),
+            java: Ok(java),
+        }
+    }
+}
+
+/// What a launch needs Java for
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JavaRequest<'a> {
+    pub instance_id: Option<&'a str>,
+    /// The Java path in the launcher settings
+    pub global_java_path: Option<&'a str>,
+    pub required_major_version: Option<u64>,
+    pub max_major_version: Option<u32>,
+}
+
+/// Find the Java to launch with by trying the sources in the configured
+/// `resolution_order`, taking the newest fitting Java of the first source that has
+/// one. The instance override's version bounds, if set, replace the game's.
 pub async fn resolve_java_for_launch(
     app_handle: &AppHandle,
-    instance_java_override: Option<&str>,
-    global_java_path: Option<&str>,
-    required_major_version: Option<u64>,
-    max_major_version: Option<u32>,
-) -> Option<JavaInstallation> {
-    if let Some(override_path) = instance_java_override {
-        if !override_path.is_empty() {
-            let path_buf = std::path::PathBuf::from(override_path);
-            if let Some(java) = validation::check_java_installation(&path_buf).await {
-                if is_version_compatible(&java, required_major_version, max_major_version) {
-                    return Some(java);
+    request: JavaRequest<'_>,
+) -> JavaResolution {
+    resolve_with(
+        persistence::load_java_config(app_handle),
+        &super::get_java_install_dir(app_handle).unwrap_or_default(),
+        request,
+        &|| super::detect_all_java_installations(app_handle),
+        &validate_installed,
+    )
+    .await
+}
+
+/// `resolve_java_for_launch` with the given config and managed runtime directory,
+/// `detect` scanning for installations and `validate` checking a single path
+async fn resolve_with<D, DF, V, VF>(
+    mut config: JavaConfig,
+    managed_dir: &Path,
+    request: JavaRequest<'_>,
+    detect: &D,
+    validate: &V,
+) -> JavaResolution
+where
+    D: Fn() -> DF,
+    DF: std::future::Future<Output = Vec<JavaInstallation>>,
+    V: Fn(PathBuf) -> VF,
+    VF: std::future::Future<Output = Option<JavaInstallation>>,
+{
+    let instance_override = request
+        .instance_id
+        .and_then(|id| config.instance_java_overrides.get(id).cloned());
+    let (required_major_version, max_major_version) = match &instance_override {
+        Some(o) => (
+            o.min_version
+                .map(u64::from)
+                .or(request.required_major_version),
+            o.max_version.or(request.max_major_version),
+        ),
+        None => (request.required_major_version, request.max_major_version),
+    };
+    let strict = instance_override.as_ref().and_then(|o| o.strict);
+    let override_path = instance_override.and_then(|o| o.path);
+    if let Some(strict) = strict {
+        config.settings.strict_resolution = strict;
+    }
+    let last_used_path = request
+        .instance_id
+        .and_then(|id| config.last_used_java.get(id))
+        .map(|last_used| last_used.java_path.clone());
+    // One scan serves both the managed and the system source
+    let detected = tokio::sync::OnceCell::new();
+    let (override_path, last_used_path, config, detected) =
+        (&override_path, &last_used_path, &config, &detected);
+    let global_java_path = request.global_java_path;
+
+    resolve_in_order(
+        &config.settings,
+        required_major_version,
+        max_major_version,
+        |source| async move {
+            match source {
+                JavaSourcePriority::InstanceOverride => {
+                    check_path(validate, override_path.as_deref()).await
+                }
+                JavaSourcePriority::GlobalOverride => check_path(validate, global_java_path).await,
+                JavaSourcePriority::LastUsed => {
+                    check_path(validate, last_used_path.as_deref()).await
+                }
+                JavaSourcePriority::Preferred => {
+                    check_path(validate, config.preferred_java_path.as_deref()).await
+                }
+                JavaSourcePriority::UserDefined => {
+                    user_defined_java(validate, &config.user_defined_paths).await
+                }
+                JavaSourcePriority::Managed | JavaSourcePriority::System => {
+                    let managed = source == JavaSourcePriority::Managed;
+                    detected
+                        .get_or_init(detect)
+                        .await
+                        .iter()
+                        .filter(|java| is_managed(java, managed_dir) == managed)
+                        .cloned()
+                        .map(Candidate::from)
+                        .collect()
                 }
             }
+        },
+    )
+    .await
+}
+
+/// Sent as `launch-java-install` while a launch installs the Java it needs, so the UI
+/// can show it and cancel the download
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/priority.ts")]
+pub struct LaunchJavaInstall {
+    pub instance_id: String,
+    pub major_version: u32,
+    pub download_id: String,
+}
+
+/// Resolve Java for a launch and, if none fits and `install` is given, install one and
+/// resolve again. There is a single install attempt; its failure or cancellation is
+/// the error.
+pub async fn resolve_or_install<R, I>(
+    mut resolve: impl FnMut() -> R,
+    install: Option<impl FnOnce() -> I>,
+) -> Result<JavaResolution, String>
+where
+    R: std::future::Future<Output = JavaResolution>,
+    I: std::future::Future<Output = Result<JavaInstallation, String>>,
+{
+    let resolution = resolve().await;
+    match install {
+        Some(install) if resolution.selected.is_none() && resolution.strict_failure.is_none() => {
+            install().await?;
+            Ok(resolve().await)
         }
+        _ => Ok(resolution),
     }
+}
 
-    if let Some(global_path) = global_java_path {
-        if !global_path.is_empty() {
-            let path_buf = std::path::PathBuf::from(global_path);
-            if let Some(java) = validation::check_java_installation(&path_buf).await {
-                if is_version_compatible(&java, required_major_version, max_major_version) {
-                    return Some(java);
+/// Go through the sources in the settings' `resolution_order`, selecting from the first
+/// that offers a Java within the version bounds the one of the most preferred vendor,
+/// newest first. Blocked vendors and builds are never selected. Everything looked at is
+/// noted.
+///
+/// With `strict_resolution` the explicitly configured sources go first, and the first
+/// of them that offers a Java decides: if that Java can't be used resolution stops
+/// there with `strict_failure` set instead of falling back.
+async fn resolve_in_order<F>(
+    settings: &JavaSettings,
+    required_major_version: Option<u64>,
+    max_major_version: Option<u32>,
+    mut candidates: impl FnMut(JavaSourcePriority) -> F,
+) -> JavaResolution
+where
+    F: std::future::Future<Output = Vec<Candidate>>,
+{
+    let mut resolution = JavaResolution::default();
+    let vendor_rank = |java: &JavaInstallation| {
+        validation::vendor_rank(java, &settings.preferred_vendors, &settings.blocked_vendors)
+    };
+    let mut order = settings.resolution_order.clone();
+    if settings.strict_resolution {
+        order.sort_by_key(|source| !source.is_explicit());
+    }
+    for source in order {
+        let mut candidates = candidates(source).await;
+        if candidates.is_empty() {
+            resolution.record(source, None, ResolutionOutcome::Skipped);
+            continue;
+        }
+        // Preferred vendors first, then native 64-bit builds, and newest first among
+        // equals; ones that don't run last. Blocked vendors go before all, so the trace
+        // shows them rejected.
+        candidates.sort_by_key(|candidate| match &candidate.java {
+            Ok(java) => (
+                false,
+                vendor_rank(java).map_or(0, |rank| rank + 1),
+                validation::architecture_rank(java),
+                std::cmp::Reverse(validation::parse_java_version(&java.version)),
+            ),
+            Err(_) => (true, usize::MAX, u8::MAX, std::cmp::Reverse(0)),
+        });
+        for candidate in candidates {
+            let blocked = candidate
+                .java
+                .as_ref()
+                .ok()
+                .and_then(|java| blocklist::blocked_reason(java, settings));
+            let outcome = match (&candidate.java, blocked) {
+                (Err(reason), _) => ResolutionOutcome::RejectedInvalid {
+                    reason: reason.clone(),
+                },
+                (Ok(_), Some(reason)) => ResolutionOutcome::RejectedBlocked { reason },
+                (Ok(java), None) if vendor_rank(java).is_none() => {
+                    ResolutionOutcome::RejectedVendor {
+                        vendor: java.vendor.clone(),
+                    }
+                }
+                (Ok(java), None)
+                    if !is_version_compatible(java, required_major_version, max_major_version) =>
+                {
+                    ResolutionOutcome::RejectedVersion {
+                        found: validation::parse_java_version(&java.version),
+                        required: required_major_version.map(|v| v as u32),
+                        max: max_major_version,
+                    }
                 }
+                (Ok(_), None) => ResolutionOutcome::Accepted,
+            };
+            let accepted = outcome == ResolutionOutcome::Accepted;
+            resolution.record(source, Some(candidate.path), outcome);
+            if accepted {
+                resolution.warnings = candidate.java.as_ref().map(warnings).unwrap_or_default();
+                resolution.selected = candidate.java.ok();
+                return resolution;
             }
         }
+        if settings.strict_resolution && source.is_explicit() {
+            resolution.strict_failure = resolution.steps.last().cloned();
+            return resolution;
+        }
     }
+    resolution
+}
 
-    let preferred = persistence::get_preferred_java_path(app_handle);
-    if let Some(pref_path) = preferred {
-        let path_buf = std::path::PathBuf::from(&pref_path);
-        if let Some(java) = validation::check_java_installation(&path_buf).await {
-            if is_version_compatible(&java, required_major_version, max_major_version) {
-                return Some(java);
-            }
-        }
+/// Why the selected `java` may run badly
+fn warnings(java: &JavaInstallation) -> Vec<String> {
+    if !java.is_64bit {
+        vec![
+            "The selected Java is 32-bit; launches with more than 1.5 GB of heap will fail"
+                .to_string(),
+        ]
+    } else if !java.is_native_arch {
+        vec![format!(
+            "The selected Java is built for {}, not this machine's {}, and runs slower under emulation",
+            java.arch,
+            validation::host_architecture()
+        )]
+    } else {
+        Vec::new()
     }
+}
+
+/// e.g. "Java 8", "Java 17 to 21" or "Java 17 or newer"
+pub(crate) fn describe_version_range(required: Option<u32>, max: Option<u32>) -> String {
+    match (required, max) {
+        (Some(min), Some(max)) if min == max => format!("Java {}", min),
+        (Some(min), Some(max)) => format!("Java {} to {}", min, max),
+        (Some(min), None) => format!("Java {} or newer", min),
+        (None, Some(max)) => format!("Java {} or older", max),
+        (None, None) => "any Java".to_string(),
+    }
+}
 
-    let installations = super::detect_all_java_installations(app_handle).await;
-    installations
-        .into_iter()
-        .find(|java| is_version_compatible(java, required_major_version, max_major_version))
+async fn validate_installed(path: PathBuf) -> Option<JavaInstallation> {
+    validation::check_java_installation_cached(&path).await
+}
+
+async fn check<V, VF>(validate: &V, path: &str) -> Candidate
+where
+    V: Fn(PathBuf) -> VF,
+    VF: std::future::Future<Output = Option<JavaInstallation>>,
+{
+    Candidate {
+        path: path.to_string(),
+        java: validate(PathBuf::from(path))
+            .await
+            .ok_or_else(|| "Not a working Java executable".to_string()),
+    }
+}
+
+async fn check_path<V, VF>(validate: &V, path: Option<&str>) -> Vec<Candidate>
+where
+    V: Fn(PathBuf) -> VF,
+    VF: std::future::Future<Output = Option<JavaInstallation>>,
+{
+    match path.filter(|path| !path.is_empty()) {
+        Some(path) => vec![check(validate, path).await],
+        None => Vec::new(),
+    }
+}
+
+/// The paths the user added
+async fn user_defined_java<V, VF>(validate: &V, paths: &[String]) -> Vec<Candidate>
+where
+    V: Fn(PathBuf) -> VF,
+    VF: std::future::Future<Output = Option<JavaInstallation>>,
+{
+    let mut candidates = Vec::new();
+    for path in paths {
+        candidates.push(check(validate, path).await);
+    }
+    candidates
+}
+
+/// Whether `java` is a runtime DropOut installed under `managed_dir`
+fn is_managed(java: &JavaInstallation, managed_dir: &Path) -> bool {
+    let managed_dir =
+        std::fs::canonicalize(managed_dir).unwrap_or_else(|_| managed_dir.to_path_buf());
+    Path::new(&java.path).starts_with(managed_dir)
 }
 
 fn is_version_compatible(
@@ -57,3 +452,799 @@ fn is_version_compatible(
     let major = validation::parse_java_version(&java.version);
     validation::is_version_compatible(major, required_major_version, max_major_version)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::java::validation::JavaVendor;
+    use crate::utils::test_dir::TestDir;
+    use std::collections::HashMap;
+
+    fn installation(path: &str, version: &str) -> JavaInstallation {
+        JavaInstallation {
+            path: path.to_string(),
+            version: version.to_string(),
+            arch: "x64".to_string(),
+            vendor: "Eclipse Adoptium".to_string(),
+            source: "system".to_string(),
+            is_64bit: true,
+            is_native_arch: true,
+            blocked_reason: None,
+        }
+    }
+
+    fn ordered(order: &[JavaSourcePriority]) -> JavaSettings {
+        JavaSettings {
+            resolution_order: order.to_vec(),
+            ..JavaSettings::default()
+        }
+    }
+
+    fn candidates(installations: Vec<JavaInstallation>) -> Vec<Candidate> {
+        installations.into_iter().map(Candidate::from).collect()
+    }
+
+    fn step(
+        source: JavaSourcePriority,
+        candidate_path: Option<&str>,
+        outcome: ResolutionOutcome,
+    ) -> ResolutionStep {
+        ResolutionStep {
+            source,
+            candidate_path: candidate_path.map(str::to_string),
+            outcome,
+        }
+    }
+
+    #[tokio::test]
+    async fn trace_explains_each_candidate_until_one_is_accepted() {
+        let resolution = resolve_in_order(
+            &ordered(&JavaSourcePriority::DEFAULT_ORDER),
+            Some(8),
+            Some(8),
+            |source| {
+                std::future::ready(match source {
+                    JavaSourcePriority::InstanceOverride => vec![Candidate {
+                        path: "/opt/broken/bin/java".to_string(),
+                        java: Err("Not a working Java executable".to_string()),
+                    }],
+                    JavaSourcePriority::System => candidates(vec![
+                        installation("/usr/lib/jvm/java-8/bin/java", "1.8.0_392"),
+                        installation("/usr/lib/jvm/java-21/bin/java", "21.0.2"),
+                    ]),
+                    _ => Vec::new(),
+                })
+            },
+        )
+        .await;
+
+        assert_eq!(
+            resolution.selected.unwrap().path,
+            "/usr/lib/jvm/java-8/bin/java"
+        );
+        assert_eq!(
+            resolution.steps,
+            vec![
+                step(
+                    JavaSourcePriority::InstanceOverride,
+                    Some("/opt/broken/bin/java"),
+                    ResolutionOutcome::RejectedInvalid {
+                        reason: "Not a working Java executable".to_string()
+                    }
+                ),
+                step(
+                    JavaSourcePriority::GlobalOverride,
+                    None,
+                    ResolutionOutcome::Skipped
+                ),
+                step(
+                    JavaSourcePriority::LastUsed,
+                    None,
+                    ResolutionOutcome::Skipped
+                ),
+                step(
+                    JavaSourcePriority::Preferred,
+                    None,
+                    ResolutionOutcome::Skipped
+                ),
+                step(
+                    JavaSourcePriority::UserDefined,
+                    None,
+                    ResolutionOutcome::Skipped
+                ),
+                step(
+                    JavaSourcePriority::Managed,
+                    None,
+                    ResolutionOutcome::Skipped
+                ),
+                step(
+                    JavaSourcePriority::System,
+                    Some("/usr/lib/jvm/java-21/bin/java"),
+                    ResolutionOutcome::RejectedVersion {
+                        found: 21,
+                        required: Some(8),
+                        max: Some(8)
+                    }
+                ),
+                step(
+                    JavaSourcePriority::System,
+                    Some("/usr/lib/jvm/java-8/bin/java"),
+                    ResolutionOutcome::Accepted
+                ),
+            ]
+        );
+        assert!(!resolution.truncated);
+    }
+
+    #[tokio::test]
+    async fn trace_is_bounded_but_keeps_the_accepted_step() {
+        let resolution = resolve_in_order(
+            &ordered(&[JavaSourcePriority::System]),
+            Some(8),
+            Some(8),
+            |_| {
+                let mut installations: Vec<_> = (0..80)
+                    .map(|i| installation(&format!("/jvm/{}/bin/java", i), "11.0.2"))
+                    .collect();
+                installations.push(installation("/jvm/8/bin/java", "1.8.0_392"));
+                std::future::ready(candidates(installations))
+            },
+        )
+        .await;
+        assert_eq!(resolution.selected.unwrap().path, "/jvm/8/bin/java");
+        assert!(resolution.truncated);
+        assert_eq!(resolution.steps.len(), MAX_RESOLUTION_STEPS);
+        assert_eq!(
+            resolution.steps.last().unwrap().outcome,
+            ResolutionOutcome::Accepted
+        );
+
+        let resolution = resolve_in_order(
+            &ordered(&[JavaSourcePriority::System]),
+            Some(25),
+            None,
+            |_| {
+                std::future::ready(candidates(
+                    (0..80)
+                        .map(|i| installation(&format!("/jvm/{}/bin/java", i), "21.0.2"))
+                        .collect(),
+                ))
+            },
+        )
+        .await;
+        assert!(resolution.selected.is_none());
+        assert!(resolution.truncated);
+        assert_eq!(resolution.steps.len(), MAX_RESOLUTION_STEPS - 1);
+    }
+
+    /// Provider serving a fixed set of releases, counting what it was asked for
+    struct MockProvider {
+        releases: Vec<u32>,
+        requests: std::sync::Mutex<Vec<u32>>,
+    }
+
+    impl crate::core::java::provider::JavaProvider for MockProvider {
+        async fn fetch_release(
+            &self,
+            major_version: u32,
+            image_type: crate::core::java::ImageType,
+        ) -> Result<crate::core::java::JavaDownloadInfo, crate::core::java::JavaError> {
+            self.requests.lock().unwrap().push(major_version);
+            if !self.releases.contains(&major_version) {
+                return Err(crate::core::java::JavaError::NotFound);
+            }
+            Ok(crate::core::java::JavaDownloadInfo {
+                version: format!("{}.0.1+12", major_version),
+                release_name: format!("jdk-{}.0.1+12", major_version),
+                download_url: format!("https://example.invalid/{}", major_version),
+                file_name: format!("mock-{}.tar.gz", major_version),
+                file_size: 1,
+                checksum: None,
+                image_type: image_type.to_string(),
+            })
+        }
+
+        async fn available_versions(&self) -> Result<Vec<u32>, crate::core::java::JavaError> {
+            Ok(self.releases.clone())
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn os_name(&self) -> &'static str {
+            "linux"
+        }
+
+        fn arch_name(&self) -> &'static str {
+            "x64"
+        }
+
+        fn install_prefix(&self) -> &'static str {
+            "mock"
+        }
+    }
+
+    /// Launch resolution over a simulated managed directory that `install` adds to
+    async fn launch_with(
+        provider: &MockProvider,
+        managed: &std::sync::Mutex<Vec<JavaInstallation>>,
+        major_version: u32,
+        auto_install: bool,
+        cancelled: bool,
+    ) -> Result<JavaResolution, String> {
+        use crate::core::java::provider::JavaProvider;
+
+        let resolve = || async {
+            let installed = managed.lock().unwrap().clone();
+            resolve_in_order(
+                &ordered(&[JavaSourcePriority::Managed]),
+                Some(u64::from(major_version)),
+                None,
+                |_| std::future::ready(candidates(installed.clone())),
+            )
+            .await
+        };
+        let install = auto_install.then_some(|| async {
+            let info = provider
+                .fetch_release(major_version, crate::core::java::ImageType::Jre)
+                .await?;
+            if cancelled {
+                return Err("Download cancelled".to_string());
+            }
+            let java = installation(
+                &format!("/data/java/mock-{}-jre/bin/java", major_version),
+                &info.version,
+            );
+            managed.lock().unwrap().push(java.clone());
+            Ok(java)
+        });
+        resolve_or_install(resolve, install).await
+    }
+
+    #[tokio::test]
+    async fn missing_java_is_installed_once_when_enabled() {
+        let provider = MockProvider {
+            releases: vec![17, 21],
+            requests: Default::default(),
+        };
+        let managed = std::sync::Mutex::new(Vec::new());
+
+        let resolution = launch_with(&provider, &managed, 21, false, false)
+            .await
+            .unwrap();
+        assert!(resolution.selected.is_none());
+        assert!(provider.requests.lock().unwrap().is_empty());
+
+        let resolution = launch_with(&provider, &managed, 21, true, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            resolution.selected.unwrap().path,
+            "/data/java/mock-21-jre/bin/java"
+        );
+        assert_eq!(*provider.requests.lock().unwrap(), vec![21]);
+
+        // Now installed, so the next launch doesn't download again
+        launch_with(&provider, &managed, 21, true, false)
+            .await
+            .unwrap();
+        assert_eq!(*provider.requests.lock().unwrap(), vec![21]);
+    }
+
+    #[tokio::test]
+    async fn failed_or_cancelled_install_aborts_the_launch() {
+        let provider = MockProvider {
+            releases: vec![17],
+            requests: Default::default(),
+        };
+        let managed = std::sync::Mutex::new(Vec::new());
+
+        assert!(
+            launch_with(&provider, &managed, 25, true, false)
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            launch_with(&provider, &managed, 17, true, true)
+                .await
+                .unwrap_err(),
+            "Download cancelled"
+        );
+        // One attempt per launch
+        assert_eq!(*provider.requests.lock().unwrap(), vec![25, 17]);
+        assert!(managed.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn blocked_vendors_are_skipped_and_preferred_ones_ranked_first() {
+        let mut temurin = installation("/usr/lib/jvm/temurin-17/bin/java", "17.0.10");
+        temurin.vendor = "Temurin (Eclipse)".to_string();
+        let mut oracle = installation("/usr/lib/jvm/oracle-21/bin/java", "21.0.2");
+        oracle.vendor = "Java SE Development Kit (Oracle)".to_string();
+        let mut unknown = installation("/opt/custom-22/bin/java", "22.0.1");
+        unknown.vendor = "Unknown".to_string();
+        let resolve = async |settings: &JavaSettings, installations: &[JavaInstallation]| {
+            resolve_in_order(settings, Some(17), None, |_| {
+                std::future::ready(candidates(installations.to_vec()))
+            })
+            .await
+        };
+        let system = ordered(&[JavaSourcePriority::System]);
+        let block_oracle = JavaSettings {
+            blocked_vendors: vec![JavaVendor::Oracle],
+            ..system.clone()
+        };
+
+        let resolution = resolve(&block_oracle, &[temurin.clone(), oracle.clone()]).await;
+        assert_eq!(resolution.selected.unwrap().path, temurin.path);
+        assert_eq!(
+            resolution.steps[0].outcome,
+            ResolutionOutcome::RejectedVendor {
+                vendor: oracle.vendor.clone()
+            }
+        );
+
+        // Without preferences the newest wins, and unknown vendors count as neutral
+        let all = [temurin.clone(), oracle.clone(), unknown.clone()];
+        let pick = async |settings: &JavaSettings, installations: &[JavaInstallation]| {
+            resolve(settings, installations)
+                .await
+                .selected
+                .unwrap()
+                .path
+        };
+        assert_eq!(pick(&system, &all).await, unknown.path);
+        // A preferred vendor beats newer ones
+        let prefer_temurin = JavaSettings {
+            preferred_vendors: vec![JavaVendor::Temurin],
+            ..block_oracle.clone()
+        };
+        assert_eq!(pick(&prefer_temurin, &all).await, temurin.path);
+        assert_eq!(
+            pick(&prefer_temurin, &[oracle.clone(), unknown.clone()]).await,
+            unknown.path
+        );
+    }
+
+    #[tokio::test]
+    async fn known_bad_builds_lose_to_older_working_ones() {
+        let java16 = installation("/usr/lib/jvm/java-16/bin/java", "16");
+        let java11 = installation("/usr/lib/jvm/java-11/bin/java", "11.0.22");
+        let resolve = async |settings: &JavaSettings| {
+            resolve_in_order(settings, Some(8), None, |_| {
+                std::future::ready(candidates(vec![java16.clone(), java11.clone()]))
+            })
+            .await
+        };
+        let system = ordered(&[JavaSourcePriority::System]);
+
+        let resolution = resolve(&system).await;
+        assert!(matches!(
+            &resolution.steps[0].outcome,
+            ResolutionOutcome::RejectedBlocked { .. }
+        ));
+        assert_eq!(resolution.selected.unwrap().path, java11.path);
+
+        let allowed = JavaSettings {
+            allowed_known_bad_builds: vec!["java-16-ga".to_string()],
+            ..system.clone()
+        };
+        assert_eq!(resolve(&allowed).await.selected.unwrap().path, java16.path);
+    }
+
+    #[tokio::test]
+    async fn native_64_bit_java_is_preferred_over_emulated_and_32_bit() {
+        let mut native = installation("/usr/lib/jvm/native-17/bin/java", "17.0.10");
+        native.arch = validation::host_architecture().to_string();
+        let mut emulated = installation("/usr/lib/jvm/emulated-21/bin/java", "21.0.2");
+        emulated.is_native_arch = false;
+        let mut x86 = installation("/usr/lib/jvm/x86-22/bin/java", "22.0.1");
+        x86.arch = "x86".to_string();
+        x86.is_64bit = false;
+        let settings = JavaSettings {
+            allowed_known_bad_builds: vec!["32-bit".to_string()],
+            ..ordered(&[JavaSourcePriority::System])
+        };
+        let resolve = async |installations: &[JavaInstallation]| {
+            resolve_in_order(&settings, Some(17), None, |_| {
+                std::future::ready(candidates(installations.to_vec()))
+            })
+            .await
+        };
+
+        // Each tier wins over the ones below it despite being older
+        let resolution = resolve(&[x86.clone(), emulated.clone(), native.clone()]).await;
+        assert!(resolution.warnings.is_empty());
+        assert_eq!(resolution.selected.unwrap().path, native.path);
+
+        let resolution = resolve(&[x86.clone(), emulated.clone()]).await;
+        assert_eq!(resolution.warnings.len(), 1);
+        assert!(resolution.warnings[0].contains("emulation"));
+        assert_eq!(resolution.selected.unwrap().path, emulated.path);
+
+        let resolution = resolve(std::slice::from_ref(&x86)).await;
+        assert_eq!(resolution.warnings.len(), 1);
+        assert!(resolution.warnings[0].contains("32-bit"));
+        assert_eq!(resolution.selected.unwrap().path, x86.path);
+
+        // The tiers only order compatible candidates
+        let resolution = resolve(&[
+            native.clone(),
+            installation("/opt/jdk-11/bin/java", "11.0.22"),
+        ])
+        .await;
+        assert_eq!(resolution.selected.unwrap().path, native.path);
+    }
+
+    #[tokio::test]
+    async fn resolution_order_decides_between_managed_and_system_java() {
+        let sources = HashMap::from([
+            (
+                JavaSourcePriority::Managed,
+                vec![installation("/data/java/temurin-17/bin/java", "17.0.10")],
+            ),
+            (
+                JavaSourcePriority::System,
+                vec![
+                    installation("/usr/lib/jvm/java-21/bin/java", "21.0.2"),
+                    installation("/usr/lib/jvm/java-8/bin/java", "1.8.0_392"),
+                ],
+            ),
+        ]);
+        let resolve = async |order: &[JavaSourcePriority], max: Option<u32>| {
+            resolve_in_order(&ordered(order), Some(17), max, |source| {
+                std::future::ready(candidates(
+                    sources.get(&source).cloned().unwrap_or_default(),
+                ))
+            })
+            .await
+            .selected
+            .map(|java| java.path)
+        };
+
+        assert_eq!(
+            resolve(&JavaSourcePriority::DEFAULT_ORDER, None)
+                .await
+                .unwrap(),
+            "/data/java/temurin-17/bin/java"
+        );
+        let system_first = [JavaSourcePriority::System, JavaSourcePriority::Managed];
+        assert_eq!(
+            resolve(&system_first, None).await.unwrap(),
+            "/usr/lib/jvm/java-21/bin/java"
+        );
+        // A source without a fitting Java falls through to the next
+        assert_eq!(
+            resolve(&system_first, Some(17)).await.unwrap(),
+            "/data/java/temurin-17/bin/java"
+        );
+        assert_eq!(resolve(&[JavaSourcePriority::System], Some(17)).await, None);
+    }
+
+    #[tokio::test]
+    async fn launch_fails_with_the_java_the_version_needs() {
+        let system = installation("/usr/lib/jvm/java-8/bin/java", "1.8.0_392");
+        let settings = ordered(&[JavaSourcePriority::System]);
+        let resolution = resolve_in_order(&settings, Some(21), None, |_| {
+            std::future::ready(candidates(vec![system.clone()]))
+        })
+        .await;
+        let error = resolution.launch_java(21, None).unwrap_err();
+        assert_eq!(
+            error,
+            JavaError::NoCompatibleJava {
+                required: 21,
+                max: None
+            }
+        );
+        assert!(error.to_string().contains("needs Java 21 or newer"));
+
+        let resolution = resolve_in_order(&settings, Some(8), Some(8), |_| {
+            std::future::ready(candidates(vec![system.clone()]))
+        })
+        .await;
+        assert_eq!(resolution.launch_java(8, Some(8)), Ok(&system));
+    }
+
+    #[tokio::test]
+    async fn strict_resolution_stops_at_an_unusable_explicit_java() {
+        let global = installation("/opt/jdk-21/bin/java", "21.0.2");
+        let system = installation("/usr/lib/jvm/java-8/bin/java", "1.8.0_392");
+        let resolve = async |strict: bool, global_java: Option<Candidate>| {
+            let settings = JavaSettings {
+                strict_resolution: strict,
+                ..ordered(&[
+                    JavaSourcePriority::System,
+                    JavaSourcePriority::InstanceOverride,
+                    JavaSourcePriority::GlobalOverride,
+                ])
+            };
+            let mut global_java = global_java;
+            resolve_in_order(&settings, Some(8), Some(8), |source| {
+                std::future::ready(match source {
+                    JavaSourcePriority::GlobalOverride => global_java.take().into_iter().collect(),
+                    JavaSourcePriority::System => candidates(vec![system.clone()]),
+                    _ => Vec::new(),
+                })
+            })
+            .await
+        };
+
+        // Lenient falls back to the system Java 8 despite the configured Java 21
+        let lenient = resolve(false, Some(Candidate::from(global.clone()))).await;
+        assert!(lenient.strict_error().is_none());
+        assert_eq!(lenient.selected.unwrap().path, system.path);
+
+        let strict = resolve(true, Some(Candidate::from(global.clone()))).await;
+        assert!(strict.selected.is_none());
+        assert_eq!(
+            strict.strict_failure,
+            Some(step(
+                JavaSourcePriority::GlobalOverride,
+                Some("/opt/jdk-21/bin/java"),
+                ResolutionOutcome::RejectedVersion {
+                    found: 21,
+                    required: Some(8),
+                    max: Some(8)
+                }
+            ))
+        );
+        // The explicit sources are decided on first, so detection never ran
+        assert!(
+            strict
+                .steps
+                .iter()
+                .all(|step| step.source != JavaSourcePriority::System)
+        );
+        assert_eq!(
+            strict.strict_error().unwrap().to_string(),
+            "The configured Java at /opt/jdk-21/bin/java can't be used: it is Java 21, but Java 8 is required"
+        );
+
+        let broken = Candidate {
+            path: "/opt/missing/bin/java".to_string(),
+            java: Err("Not a working Java executable".to_string()),
+        };
+        let strict = resolve(true, Some(broken)).await;
+        assert!(matches!(
+            strict.strict_error(),
+            Some(JavaError::StrictResolutionFailed { path, reason })
+                if path == "/opt/missing/bin/java" && reason == "Not a working Java executable"
+        ));
+
+        // Without an explicit Java, strict resolution detects like lenient does
+        let strict = resolve(true, None).await;
+        assert_eq!(strict.selected.unwrap().path, system.path);
+        assert!(strict.strict_failure.is_none());
+    }
+
+    #[tokio::test]
+    async fn strict_failure_is_not_auto_installed_over() {
+        let resolution = JavaResolution {
+            strict_failure: Some(step(
+                JavaSourcePriority::InstanceOverride,
+                Some("/opt/jdk-8/bin/java"),
+                ResolutionOutcome::RejectedInvalid {
+                    reason: "Not a working Java executable".to_string(),
+                },
+            )),
+            ..JavaResolution::default()
+        };
+        let resolution = resolve_or_install(
+            || std::future::ready(resolution.clone()),
+            Some(|| async { panic!("strict failures must not install Java") }),
+        )
+        .await
+        .unwrap();
+        assert!(resolution.strict_error().is_some());
+    }
+
+    #[tokio::test]
+    async fn launch_resolution_uses_the_saved_config_and_then_the_last_used_java() {
+        let dir = TestDir::new("resolve");
+        let app_data = dir.path();
+        let runtime_bin = app_data.join("java").join("temurin-17").join("bin");
+        std::fs::create_dir_all(&runtime_bin).unwrap();
+        let managed_dir = app_data.join("java");
+        let managed_java = managed_dir
+            .join("temurin-17")
+            .join("bin")
+            .join("java")
+            .to_string_lossy()
+            .into_owned();
+        std::fs::write(&managed_java, "").unwrap();
+
+        let config_path = app_data.join("java_config.json");
+        let mut saved = JavaConfig::default();
+        saved.instance_java_overrides.insert(
+            "pack".to_string(),
+            persistence::InstanceJavaOverride {
+                path: Some("/opt/removed-jdk/bin/java".to_string()),
+                ..Default::default()
+            },
+        );
+        persistence::save_java_config_to(&config_path, &saved).unwrap();
+
+        // Only these paths are working Java; nothing is run
+        let installations = HashMap::from([
+            (managed_java.clone(), installation(&managed_java, "17.0.10")),
+            (
+                "/usr/lib/jvm/java-21/bin/java".to_string(),
+                installation("/usr/lib/jvm/java-21/bin/java", "21.0.2"),
+            ),
+        ]);
+        let validate =
+            |path: PathBuf| std::future::ready(installations.get(path.to_str().unwrap()).cloned());
+        let scans = std::sync::atomic::AtomicUsize::new(0);
+        let detect = || {
+            scans.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            std::future::ready(installations.values().cloned().collect::<Vec<_>>())
+        };
+        let request = JavaRequest {
+            instance_id: Some("pack"),
+            global_java_path: Some(""),
+            required_major_version: Some(17),
+            max_major_version: None,
+        };
+        let resolve = async || {
+            let config = persistence::current_config_at(&config_path).0;
+            resolve_with(config, &managed_dir, request, &detect, &validate).await
+        };
+
+        let resolution = resolve().await;
+        assert_eq!(
+            resolution.steps[0],
+            step(
+                JavaSourcePriority::InstanceOverride,
+                Some("/opt/removed-jdk/bin/java"),
+                ResolutionOutcome::RejectedInvalid {
+                    reason: "Not a working Java executable".to_string()
+                }
+            )
+        );
+        let java = resolution.selected.unwrap();
+        assert_eq!(java.path, managed_java);
+        assert_eq!(
+            resolution.steps.last().unwrap().source,
+            JavaSourcePriority::Managed
+        );
+        assert_eq!(scans.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // What the launch records once the game runs is tried before any scan
+        persistence::config_transaction_at(
+            &config_path,
+            |_| {},
+            |config| {
+                config.remember_last_used("pack", &java, 1_700_000_000);
+                Ok(())
+            },
+        )
+        .unwrap();
+        let resolution = resolve().await;
+        assert_eq!(
+            resolution.steps.last(),
+            Some(&step(
+                JavaSourcePriority::LastUsed,
+                Some(&managed_java),
+                ResolutionOutcome::Accepted
+            ))
+        );
+        assert_eq!(scans.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    /// A script that answers `-version` like a JDK of the given version
+    #[cfg(unix)]
+    fn fake_java(dir: &Path, name: &str, version: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join(name);
+        std::fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\necho 'openjdk version \"{}\"' >&2\necho 'OpenJDK 64-Bit Server VM' >&2\n",
+                version
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    async fn best_user_defined_java(
+        paths: &[String],
+        required_major_version: Option<u64>,
+        max_major_version: Option<u32>,
+    ) -> Option<JavaInstallation> {
+        resolve_in_order(
+            &ordered(&[JavaSourcePriority::UserDefined]),
+            required_major_version,
+            max_major_version,
+            |_| user_defined_java(&validate_installed, paths),
+        )
+        .await
+        .selected
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn user_defined_paths_are_filtered_by_the_version_requirement() {
+        let dir = TestDir::new("user-java");
+        let config = JavaConfig {
+            user_defined_paths: vec![
+                fake_java(dir.path(), "java8", "1.8.0_392"),
+                fake_java(dir.path(), "java21", "21.0.2"),
+                dir.path().join("missing").to_string_lossy().into_owned(),
+            ],
+            ..JavaConfig::default()
+        };
+
+        // An old pack capped at Java 8 gets the user's Java 8
+        let java = best_user_defined_java(&config.user_defined_paths, Some(8), Some(8))
+            .await
+            .unwrap();
+        assert_eq!(java.version, "1.8.0_392");
+        assert_eq!(java.path, config.user_defined_paths[0]);
+
+        // Without a cap the newest compatible one wins
+        let java = best_user_defined_java(&config.user_defined_paths, Some(8), None)
+            .await
+            .unwrap();
+        assert_eq!(java.version, "21.0.2");
+
+        assert!(
+            best_user_defined_java(&config.user_defined_paths, Some(25), None)
+                .await
+                .is_none()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn last_used_java_falls_back_when_its_runtime_is_removed() {
+        let dir = TestDir::new("last-used");
+        let mut config = JavaConfig::default();
+        config.remember_last_used(
+            "pack",
+            &installation(&fake_java(dir.path(), "java17", "17.0.10"), "17.0.10"),
+            1_700_000_000,
+        );
+        let system = installation("/usr/lib/jvm/java-21/bin/java", "21.0.2");
+        let settings = ordered(&[JavaSourcePriority::LastUsed, JavaSourcePriority::System]);
+        let resolve = async |config: &JavaConfig| {
+            let last_used = config
+                .last_used_java
+                .get("pack")
+                .map(|l| l.java_path.clone());
+            let (last_used, system) = (&last_used, &system);
+            resolve_in_order(&settings, Some(17), None, |source| async move {
+                match source {
+                    JavaSourcePriority::LastUsed => {
+                        check_path(&validate_installed, last_used.as_deref()).await
+                    }
+                    _ => candidates(vec![system.clone()]),
+                }
+            })
+            .await
+        };
+
+        let java = resolve(&config).await.selected.unwrap();
+        assert_eq!(java.path, config.last_used_java["pack"].java_path);
+
+        std::fs::remove_dir_all(dir.path()).unwrap();
+        let resolution = resolve(&config).await;
+        assert!(matches!(
+            resolution.steps[0].outcome,
+            ResolutionOutcome::RejectedInvalid { .. }
+        ));
+        let java = resolution.selected.unwrap();
+        assert_eq!(java.path, system.path);
+
+        // The next successful launch replaces the memory
+        config.remember_last_used("pack", &java, 1_700_000_100);
+        let last_used = &config.last_used_java["pack"];
+        assert_eq!(last_used.java_path, system.path);
+        assert_eq!(last_used.java_version, "21.0.2");
+        assert_eq!(last_used.used_at, 1_700_000_100);
+    }
+}
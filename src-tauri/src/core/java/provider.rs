@@ -1,26 +1,10 @@
-use crate::core::java::{ImageType, JavaCatalog, JavaDownloadInfo, JavaError};
-use tauri::AppHandle;
+use crate::core::java::{ImageType, JavaDownloadInfo, JavaError};
 
 /// Trait for Java distribution providers (e.g., Adoptium, Corretto)
 ///
-/// Implementations handle fetching Java catalogs and release information
-/// from different distribution providers.
+/// Implementations handle fetching release information from different
+/// distribution providers.
 pub trait JavaProvider: Send + Sync {
-    /// Fetch the Java catalog (all available versions for this provider)
-    ///
-    /// # Arguments
-    /// * `app_handle` - The Tauri app handle for cache access
-    /// * `force_refresh` - If true, bypass cache and fetch fresh data
-    ///
-    /// # Returns
-    /// * `Ok(JavaCatalog)` with available versions
-    /// * `Err(JavaError)` if fetch or parsing fails
-    async fn fetch_catalog(
-        &self,
-        app_handle: &AppHandle,
-        force_refresh: bool,
-    ) -> Result<JavaCatalog, JavaError>;
-
     /// Fetch a specific Java release
     ///
     /// # Arguments
@@ -44,7 +28,6 @@ pub trait JavaProvider: Send + Sync {
     async fn available_versions(&self) -> Result<Vec<u32>, JavaError>;
 
     /// Get provider name (e.g., "adoptium", "corretto")
-    #[allow(dead_code)]
     fn provider_name(&self) -> &'static str;
 
     /// Get OS name for this provider's API
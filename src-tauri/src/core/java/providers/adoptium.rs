@@ -1,9 +1,7 @@
 use crate::core::java::error::JavaError;
 use crate::core::java::provider::JavaProvider;
-use crate::core::java::save_catalog_cache;
 use crate::core::java::{ImageType, JavaCatalog, JavaDownloadInfo, JavaReleaseInfo};
 use serde::Deserialize;
-use tauri::AppHandle;
 use ts_rs::TS;
 
 const ADOPTIUM_API_BASE: &str = "https://api.adoptium.net/v3";
@@ -58,50 +56,78 @@ pub struct AvailableReleases {
     pub most_recent_feature_release: Option<u32>,
 }
 
-pub struct AdoptiumProvider;
+pub struct AdoptiumProvider {
+    api_base: String,
+}
 
 impl AdoptiumProvider {
     pub fn new() -> Self {
-        Self
+        Self {
+            api_base: ADOPTIUM_API_BASE.to_string(),
+        }
     }
-}
 
-impl Default for AdoptiumProvider {
-    fn default() -> Self {
-        Self::new()
+    #[cfg(test)]
+    fn with_api_base(api_base: String) -> Self {
+        Self { api_base }
     }
-}
 
-impl JavaProvider for AdoptiumProvider {
-    async fn fetch_catalog(
+    /// Fetch the catalog from the API. When `cached` carries validators the release
+    /// list is requested conditionally, and a 304 keeps the cached releases as they are.
+    pub(crate) async fn refresh_catalog(
         &self,
-        app_handle: &AppHandle,
-        force_refresh: bool,
+        client: &reqwest::Client,
+        cached: Option<JavaCatalog>,
     ) -> Result<JavaCatalog, JavaError> {
-        if !force_refresh {
-            if let Some(cached) = crate::core::java::load_cached_catalog(app_handle) {
+        let os = self.os_name();
+        let arch = self.arch_name();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let releases_url = format!("{}/info/available_releases", self.api_base);
+        let mut request = client
+            .get(&releases_url)
+            .header("Accept", "application/json");
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = request.send().await.map_err(|e| {
+            JavaError::network_at(
+                format!("Failed to fetch available releases: {}", e),
+                &releases_url,
+                None,
+            )
+        })?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(mut cached) = cached {
+                cached.cached_at = now;
                 return Ok(cached);
             }
         }
 
-        let os = self.os_name();
-        let arch = self.arch_name();
-        let client = reqwest::Client::new();
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
 
-        let releases_url = format!("{}/info/available_releases", ADOPTIUM_API_BASE);
-        let available: AvailableReleases = client
-            .get(&releases_url)
-            .header("Accept", "application/json")
-            .send()
-            .await
-            .map_err(|e| {
-                JavaError::NetworkError(format!("Failed to fetch available releases: {}", e))
-            })?
+        let available: AvailableReleases = response
             .json::<AvailableReleases>()
             .await
-            .map_err(|e| {
-                JavaError::SerializationError(format!("Failed to parse available releases: {}", e))
-            })?;
+            .map_err(|e| JavaError::serialization("Failed to parse available releases", e))?;
 
         // Parallelize HTTP requests for better performance
         let mut fetch_tasks = Vec::new();
@@ -112,7 +138,7 @@ impl JavaProvider for AdoptiumProvider {
                 let image_type = image_type.to_string();
                 let url = format!(
                     "{}/assets/latest/{}/hotspot?os={}&architecture={}&image_type={}",
-                    ADOPTIUM_API_BASE, major_version, os, arch, image_type
+                    self.api_base, major_version, os, arch, image_type
                 );
                 let client = client.clone();
                 let is_lts = available.available_lts_releases.contains(&major_version);
@@ -191,7 +217,7 @@ impl JavaProvider for AdoptiumProvider {
                     // Task completed but returned None, should not happen in current implementation
                 }
                 Err(e) => {
-                    return Err(JavaError::NetworkError(format!(
+                    return Err(JavaError::network(format!(
                         "Failed to join Adoptium catalog fetch task: {}",
                         e
                     )));
@@ -199,23 +225,24 @@ impl JavaProvider for AdoptiumProvider {
             }
         }
 
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let catalog = JavaCatalog {
+        Ok(JavaCatalog {
             releases,
             available_major_versions: available.available_releases,
             lts_versions: available.available_lts_releases,
             cached_at: now,
-        };
-
-        let _ = save_catalog_cache(app_handle, &catalog);
+            etag,
+            last_modified,
+        })
+    }
+}
 
-        Ok(catalog)
+impl Default for AdoptiumProvider {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
+impl JavaProvider for AdoptiumProvider {
     async fn fetch_release(
         &self,
         major_version: u32,
@@ -226,28 +253,31 @@ impl JavaProvider for AdoptiumProvider {
 
         let url = format!(
             "{}/assets/latest/{}/hotspot?os={}&architecture={}&image_type={}",
-            ADOPTIUM_API_BASE, major_version, os, arch, image_type
+            self.api_base, major_version, os, arch, image_type
         );
 
-        let client = reqwest::Client::new();
+        let client = crate::core::http::client();
         let response = client
             .get(&url)
             .header("Accept", "application/json")
             .send()
             .await
-            .map_err(|e| JavaError::NetworkError(format!("Network request failed: {}", e)))?;
+            .map_err(|e| {
+                JavaError::network_at("Network request failed", &url, None).with_source(e)
+            })?;
 
         if !response.status().is_success() {
-            return Err(JavaError::NetworkError(format!(
-                "Adoptium API returned error: {} - The version/platform might be unavailable",
-                response.status()
-            )));
+            return Err(JavaError::network_at(
+                "Adoptium API returned an error - The version/platform might be unavailable",
+                &url,
+                Some(response.status().as_u16()),
+            ));
         }
 
-        let assets: Vec<AdoptiumAsset> =
-            response.json::<Vec<AdoptiumAsset>>().await.map_err(|e| {
-                JavaError::SerializationError(format!("Failed to parse API response: {}", e))
-            })?;
+        let assets: Vec<AdoptiumAsset> = response
+            .json::<Vec<AdoptiumAsset>>()
+            .await
+            .map_err(|e| JavaError::serialization("Failed to parse API response", e))?;
 
         let asset = assets
             .into_iter()
@@ -266,17 +296,21 @@ impl JavaProvider for AdoptiumProvider {
     }
 
     async fn available_versions(&self) -> Result<Vec<u32>, JavaError> {
-        let url = format!("{}/info/available_releases", ADOPTIUM_API_BASE);
+        let url = format!("{}/info/available_releases", self.api_base);
 
-        let response = reqwest::get(url)
+        let response = crate::core::http::client()
+            .get(&url)
+            .send()
             .await
-            .map_err(|e| JavaError::NetworkError(format!("Network request failed: {}", e)))?;
-
-        let releases: AvailableReleases =
-            response.json::<AvailableReleases>().await.map_err(|e| {
-                JavaError::SerializationError(format!("Failed to parse response: {}", e))
+            .map_err(|e| {
+                JavaError::network_at("Network request failed", &url, None).with_source(e)
             })?;
 
+        let releases: AvailableReleases = response
+            .json::<AvailableReleases>()
+            .await
+            .map_err(|e| JavaError::serialization("Failed to parse response", e))?;
+
         Ok(releases.available_releases)
     }
 
@@ -338,3 +372,121 @@ impl JavaProvider for AdoptiumProvider {
         "temurin"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mock_server::{MockServer, Response};
+
+    const ETAG: &str = "\"releases-v2\"";
+    const LAST_MODIFIED: &str = "Tue, 14 Oct 2025 08:00:00 GMT";
+    const RELEASES_PATH: &str = "/info/available_releases";
+
+    /// A tiny Adoptium API: the release list honours `If-None-Match` and
+    /// `If-Modified-Since` against `ETAG` and `LAST_MODIFIED`
+    async fn spawn_api() -> MockServer {
+        MockServer::builder()
+            .route(RELEASES_PATH, |request| {
+                if request.header("if-none-match") == Some(ETAG)
+                    || request.header("if-modified-since") == Some(LAST_MODIFIED)
+                {
+                    return Response::status(304);
+                }
+                let body = r#"{"available_releases":[17,21],"available_lts_releases":[17,21],"most_recent_lts":21,"most_recent_feature_release":21}"#;
+                Response::ok(body)
+                    .header("Content-Type", "application/json")
+                    .header("ETag", ETAG)
+                    .header("Last-Modified", LAST_MODIFIED)
+            })
+            .fallback(|_| {
+                let body = r#"[{"binary":{"os":"linux","architecture":"x64","image_type":"jre","package":{"name":"jre.tar.gz","link":"https://example.com/jre.tar.gz","size":1000,"checksum":null}},"release_name":"jdk-21.0.1+12","version":{"major":21,"minor":0,"security":1,"semver":"21.0.1+12","openjdk_version":"21.0.1+12"}}]"#;
+                Response::ok(body).header("Content-Type", "application/json")
+            })
+            .start()
+            .await
+    }
+
+    /// Requests `api` answered with release assets
+    fn asset_hits(api: &MockServer) -> usize {
+        api.requests().len() - api.hits(RELEASES_PATH)
+    }
+
+    fn cached_catalog(etag: Option<&str>, last_modified: Option<&str>) -> JavaCatalog {
+        JavaCatalog {
+            releases: vec![JavaReleaseInfo {
+                major_version: 17,
+                image_type: "jre".to_string(),
+                version: "17.0.9+9".to_string(),
+                release_name: "jdk-17.0.9+9".to_string(),
+                release_date: None,
+                file_size: 1000,
+                checksum: None,
+                download_url: "https://example.com/old.tar.gz".to_string(),
+                is_lts: true,
+                is_available: true,
+                architecture: "x64".to_string(),
+            }],
+            available_major_versions: vec![17],
+            lts_versions: vec![17],
+            cached_at: 1,
+            etag: etag.map(str::to_string),
+            last_modified: last_modified.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn unchanged_catalog_is_revalidated_without_refetching_assets() {
+        let api = spawn_api().await;
+        let provider = AdoptiumProvider::with_api_base(api.url(""));
+        let client = crate::core::http::client();
+
+        for cached in [
+            cached_catalog(Some(ETAG), None),
+            cached_catalog(None, Some(LAST_MODIFIED)),
+        ] {
+            let catalog = provider
+                .refresh_catalog(&client, Some(cached))
+                .await
+                .unwrap();
+
+            assert!(catalog.cached_at > 1);
+            assert_eq!(catalog.available_major_versions, vec![17]);
+            assert_eq!(
+                catalog.releases[0].download_url,
+                "https://example.com/old.tar.gz"
+            );
+        }
+        assert_eq!(api.hits(RELEASES_PATH), 2);
+        assert_eq!(asset_hits(&api), 0);
+    }
+
+    #[tokio::test]
+    async fn changed_catalog_is_fetched_again_with_new_validators() {
+        let api = spawn_api().await;
+        let provider = AdoptiumProvider::with_api_base(api.url(""));
+        let client = crate::core::http::client();
+
+        for cached in [Some(cached_catalog(Some("\"releases-v1\""), None)), None] {
+            let catalog = provider.refresh_catalog(&client, cached).await.unwrap();
+
+            assert_eq!(catalog.available_major_versions, vec![17, 21]);
+            assert_eq!(catalog.releases.len(), 4);
+            assert!(catalog.releases.iter().all(|release| release.is_available));
+            assert_eq!(catalog.etag.as_deref(), Some(ETAG));
+            assert_eq!(catalog.last_modified.as_deref(), Some(LAST_MODIFIED));
+        }
+        assert_eq!(api.hits(RELEASES_PATH), 2);
+        assert_eq!(asset_hits(&api), 8);
+    }
+
+    #[test]
+    fn catalog_cache_without_validators_still_loads() {
+        let json =
+            r#"{"releases":[],"availableMajorVersions":[17],"ltsVersions":[17],"cachedAt":42}"#;
+        let catalog: JavaCatalog = serde_json::from_str(json).unwrap();
+
+        assert_eq!(catalog.cached_at, 42);
+        assert_eq!(catalog.etag, None);
+        assert_eq!(catalog.last_modified, None);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod adoptium;
 
 pub use adoptium::AdoptiumProvider;
+
+/// Names of the providers a catalog can be fetched from
+pub const PROVIDER_NAMES: &[&str] = &["adoptium"];
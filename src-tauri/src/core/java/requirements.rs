@@ -0,0 +1,124 @@
+//! Which Java a Minecraft version needs.
+//!
+//! The `javaVersion` of a version JSON is authoritative; the table of known cutoffs
+//! covers versions whose JSON isn't available (or predates the field).
+
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Java major versions a Minecraft version runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/requirements.ts")]
+pub struct VersionRequirement {
+    pub min_major: u32,
+    /// Newest major known to work; `None` if newer ones are fine
+    pub recommended_max_major: Option<u32>,
+}
+
+/// Known cutoffs, newest first: releases from the version on need the requirement
+const CUTOFFS: &[((u32, u32, u32), VersionRequirement)] = &[
+    ((1, 20, 5), requirement(21, None)),
+    ((1, 17, 0), requirement(17, None)),
+    // 1.13 dropped LaunchWrapper, which (like the Forge built on it) breaks on Java 9+
+    ((1, 13, 0), requirement(8, Some(11))),
+    ((0, 0, 0), requirement(8, Some(8))),
+];
+
+const fn requirement(min_major: u32, recommended_max_major: Option<u32>) -> VersionRequirement {
+    VersionRequirement {
+        min_major,
+        recommended_max_major,
+    }
+}
+
+/// `(major, minor, patch)` of a release, pre-release or release candidate id such as
+/// `1.20.4`, `1.21-pre1` or `1.20.5-rc2`; `None` for snapshots and other ids
+fn release_number(mc_version: &str) -> Option<(u32, u32, u32)> {
+    let release = mc_version.split('-').next()?;
+    let mut parts = release.split('.').map(|part| part.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    parts.next().is_none().then_some((major, minor, patch))
+}
+
+/// Alpha, beta, classic and indev ids, all long before `javaVersion` existed
+fn is_pre_release_era(mc_version: &str) -> bool {
+    ["a1.", "b1.", "c0.", "rd-", "inf-", "in-"]
+        .iter()
+        .any(|prefix| mc_version.starts_with(prefix))
+}
+
+/// Java needed by `mc_version`. A `javaVersion` from the version JSON sets the minimum,
+/// keeping the table's maximum only where it still allows that minimum. Versions the
+/// table can't place, like weekly snapshots, get the newest requirement.
+pub fn required_java_for_minecraft(
+    mc_version: &str,
+    version_json_java: Option<u32>,
+) -> VersionRequirement {
+    let from_table = if is_pre_release_era(mc_version) {
+        CUTOFFS[CUTOFFS.len() - 1].1
+    } else {
+        match release_number(mc_version) {
+            Some(release) => CUTOFFS
+                .iter()
+                .find(|(cutoff, _)| release >= *cutoff)
+                .map(|(_, requirement)| *requirement)
+                .unwrap_or(CUTOFFS[CUTOFFS.len() - 1].1),
+            None => CUTOFFS[0].1,
+        }
+    };
+    match version_json_java {
+        Some(min_major) => VersionRequirement {
+            min_major,
+            recommended_max_major: from_table
+                .recommended_max_major
+                .filter(|max| *max >= min_major),
+        },
+        None => from_table,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minecraft_versions_map_to_java_requirements() {
+        let cases = [
+            ("1.8.9", None, 8, Some(8)),
+            ("1.12.2", Some(8), 8, Some(8)),
+            ("1.12.2-forge-14.23.5.2860", None, 8, Some(8)),
+            ("b1.7.3", None, 8, Some(8)),
+            ("1.13", None, 8, Some(11)),
+            ("1.16.5", Some(8), 8, Some(11)),
+            ("1.17", None, 17, None),
+            ("1.17.1", Some(16), 16, None),
+            ("1.18.2", None, 17, None),
+            ("1.20.4", None, 17, None),
+            ("1.20.5-rc1", None, 21, None),
+            ("1.20.5", Some(21), 21, None),
+            ("1.21-pre1", None, 21, None),
+            ("1.21.4", None, 21, None),
+            // Snapshots use the JSON value, or the newest cutoff without one
+            ("21w10a", Some(16), 16, None),
+            ("24w14a", None, 21, None),
+            ("25w02a", Some(21), 21, None),
+            // The JSON wins over the table
+            ("1.16.5", Some(17), 17, None),
+        ];
+        for (mc_version, json_java, min_major, recommended_max_major) in cases {
+            assert_eq!(
+                required_java_for_minecraft(mc_version, json_java),
+                VersionRequirement {
+                    min_major,
+                    recommended_max_major
+                },
+                "{} with javaVersion {:?}",
+                mc_version,
+                json_java
+            );
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Moving settings between machines as a single file.
+//!
+//! An export bundles `java_config.json` into one versioned document and lists the
+//! absolute paths in it, which may not exist on the machine it is imported on.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use ts_rs::TS;
+
+use super::error::JavaError;
+use super::persistence::{self, JavaConfig};
+use super::validation;
+
+/// Bump when the export layout changes; newer exports are refused
+pub const SETTINGS_EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsExport {
+    format_version: u32,
+    exported_at: u64,
+    /// Kept as JSON so configs of older schema versions go through the usual migration
+    java_config: serde_json::Value,
+    /// Absolute paths in the export that belong to the exporting machine
+    machine_specific_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/transfer.ts")]
+pub enum ImportMode {
+    /// Add to the current settings; imported values win where both have one
+    #[default]
+    Merge,
+    /// Discard the current settings
+    Replace,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "java/transfer.ts")]
+pub struct ImportOptions {
+    pub mode: ImportMode,
+    /// Leave out Java paths that don't hold a working Java here, and other paths that
+    /// don't exist
+    pub skip_missing_paths: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "java/transfer.ts")]
+pub struct ImportReport {
+    pub applied: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+fn machine_specific_paths(config: &JavaConfig) -> Vec<String> {
+    let mut paths = config.user_defined_paths.clone();
+    paths.extend(config.preferred_java_path.clone());
+    paths.extend(
+        config
+            .instance_java_overrides
+            .values()
+            .filter_map(|o| o.path.clone()),
+    );
+    paths.extend(
+        config
+            .settings
+            .install_dir
+            .as_ref()
+            .map(|dir| dir.to_string_lossy().into_owned()),
+    );
+    paths.extend(config.network.extra_ca_certificates.iter().cloned());
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Write the settings at `config_path` to `export_path`
+fn export_settings_from(config_path: &Path, export_path: &Path) -> Result<(), JavaError> {
+    let (mut config, _) = persistence::current_config_at(config_path);
+    // Scan results only mean something on this machine
+    config.known_installations.clear();
+    let export = SettingsExport {
+        format_version: SETTINGS_EXPORT_VERSION,
+        exported_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        machine_specific_paths: machine_specific_paths(&config),
+        java_config: serde_json::to_value(&config)?,
+    };
+    let content = serde_json::to_string_pretty(&export)?;
+    persistence::write_file_atomic(export_path, content.as_bytes())?;
+    Ok(())
+}
+
+fn read_export(export_path: &Path) -> Result<JavaConfig, JavaError> {
+    let content = std::fs::read_to_string(export_path)?;
+    let export: SettingsExport = serde_json::from_str(&content)?;
+    if export.format_version > SETTINGS_EXPORT_VERSION {
+        return Err(JavaError::InvalidConfig(format!(
+            "Settings were exported by a newer version of DropOut (format {})",
+            export.format_version
+        )));
+    }
+    persistence::migrate_java_config(export.java_config)
+}
+
+/// Whether `path` runs as Java here; only checked when missing paths are skipped
+async fn is_working_java(options: &ImportOptions, path: &str) -> bool {
+    !options.skip_missing_paths
+        || validation::check_java_installation(&PathBuf::from(path))
+            .await
+            .is_some()
+}
+
+/// Keep the item if paths aren't checked or `present`, noting what happened
+fn keep(report: &mut ImportReport, options: &ImportOptions, item: String, present: bool) -> bool {
+    if !options.skip_missing_paths || present {
+        report.applied.push(item);
+        true
+    } else {
+        report.skipped.push(item);
+        false
+    }
+}
+
+/// Combine `imported` into `current` according to `options`
+async fn merge_imported(
+    current: JavaConfig,
+    imported: JavaConfig,
+    options: &ImportOptions,
+) -> (JavaConfig, ImportReport) {
+    let mut report = ImportReport::default();
+    let mut config = match options.mode {
+        ImportMode::Merge => current,
+        ImportMode::Replace => JavaConfig::default(),
+    };
+
+    for path in imported.user_defined_paths {
+        if config.user_defined_paths.contains(&path) {
+            continue;
+        }
+        let present = is_working_java(options, &path).await;
+        if keep(&mut report, options, format!("Java path {}", path), present) {
+            config.user_defined_paths.push(path);
+        }
+    }
+
+    if let Some(path) = imported.preferred_java_path {
+        let present = is_working_java(options, &path).await;
+        if keep(
+            &mut report,
+            options,
+            format!("preferred Java {}", path),
+            present,
+        ) {
+            config.preferred_java_path = Some(path);
+        }
+    }
+
+    for (instance_id, mut java_override) in imported.instance_java_overrides {
+        if let Some(path) = java_override.path.clone()
+            && !keep(
+                &mut report,
+                options,
+                format!("Java path {} of instance {}", path, instance_id),
+                is_working_java(options, &path).await,
+            )
+        {
+            java_override.path = None;
+        }
+        report
+            .applied
+            .push(format!("Java override of instance {}", instance_id));
+        config
+            .instance_java_overrides
+            .insert(instance_id, java_override);
+    }
+
+    let mut settings = imported.settings;
+    if let Some(dir) = settings.install_dir.clone()
+        && !keep(
+            &mut report,
+            options,
+            format!("install directory {}", dir.display()),
+            dir.is_dir(),
+        )
+    {
+        settings.install_dir = config.settings.install_dir.clone();
+    }
+    config.settings = settings;
+    report.applied.push("Java settings".to_string());
+
+    let mut network = imported.network;
+    network.extra_ca_certificates.retain(|cert| {
+        keep(
+            &mut report,
+            options,
+            format!("certificate {}", cert),
+            Path::new(cert).is_file(),
+        )
+    });
+    config.network = network;
+    report.applied.push("network settings".to_string());
+
+    (config, report)
+}
+
+/// Import into the config at `config_path`, calling `notify` if it changed. Checking
+/// paths can take a while, so changes made meanwhile are replaced by the import.
+async fn import_settings_into(
+    config_path: &Path,
+    export_path: &Path,
+    options: &ImportOptions,
+    notify: impl FnOnce(&JavaConfig),
+) -> Result<(JavaConfig, ImportReport), JavaError> {
+    let imported = read_export(export_path)?;
+    let (current, _) = persistence::current_config_at(config_path);
+    let (config, report) = merge_imported(current, imported, options).await;
+    persistence::config_transaction_at(config_path, notify, |current| {
+        *current = config.clone();
+        Ok(())
+    })?;
+    Ok((config, report))
+}
+
+/// Write the launcher's settings to `path` as a single file
+pub fn export_settings(app_handle: &AppHandle, path: &Path) -> Result<(), JavaError> {
+    export_settings_from(&persistence::get_java_config_path(app_handle)?, path)
+}
+
+/// Apply settings exported with `export_settings` and emit `java-settings-changed`.
+/// Returns the imported config along with what was applied and skipped.
+pub async fn import_settings(
+    app_handle: &AppHandle,
+    path: &Path,
+    options: &ImportOptions,
+) -> Result<(JavaConfig, ImportReport), JavaError> {
+    let (config, report) = import_settings_into(
+        &persistence::get_java_config_path(app_handle)?,
+        path,
+        options,
+        |config| persistence::notify_config_changed(app_handle, config),
+    )
+    .await?;
+    let _ = app_handle.emit("java-settings-changed", &config.settings);
+    Ok((config, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::java::persistence::{InstanceJavaOverride, JavaSettings};
+    use crate::utils::test_dir::TestDir;
+
+    fn exported_config() -> JavaConfig {
+        let mut config = JavaConfig {
+            user_defined_paths: vec!["/nowhere/jdk-17/bin/java".to_string()],
+            preferred_java_path: Some("/nowhere/jdk-17/bin/java".to_string()),
+            settings: JavaSettings {
+                include_ea: true,
+                install_dir: Some(PathBuf::from("/nowhere/java")),
+                ..JavaSettings::default()
+            },
+            ..JavaConfig::default()
+        };
+        config.instance_java_overrides.insert(
+            "modpack".to_string(),
+            InstanceJavaOverride {
+                path: Some("/nowhere/jdk-8/bin/java".to_string()),
+                max_version: Some(8),
+                ..Default::default()
+            },
+        );
+        config
+    }
+
+    #[tokio::test]
+    async fn export_round_trips_into_another_app_data_dir() {
+        let (old_machine, new_machine) = (
+            TestDir::new("settings-transfer"),
+            TestDir::new("settings-transfer"),
+        );
+        let old_config = old_machine.path().join("java_config.json");
+        persistence::save_java_config_to(&old_config, &exported_config()).unwrap();
+        let export_path = old_machine.path().join("dropout-settings.json");
+        export_settings_from(&old_config, &export_path).unwrap();
+
+        let export: SettingsExport =
+            serde_json::from_str(&std::fs::read_to_string(&export_path).unwrap()).unwrap();
+        assert_eq!(export.format_version, SETTINGS_EXPORT_VERSION);
+        assert!(
+            export
+                .machine_specific_paths
+                .contains(&"/nowhere/jdk-8/bin/java".to_string())
+        );
+
+        let new_config = new_machine.path().join("java_config.json");
+        let (config, report) =
+            import_settings_into(&new_config, &export_path, &ImportOptions::default(), |_| {})
+                .await
+                .unwrap();
+        assert!(report.skipped.is_empty());
+        assert_eq!(
+            config.user_defined_paths,
+            exported_config().user_defined_paths
+        );
+        assert_eq!(config.settings, exported_config().settings);
+        let (saved, _) = persistence::load_java_config_from(&new_config);
+        assert_eq!(
+            saved.instance_java_overrides,
+            exported_config().instance_java_overrides
+        );
+    }
+
+    #[tokio::test]
+    async fn import_can_skip_paths_missing_on_this_machine() {
+        let dir = TestDir::new("settings-transfer");
+        let config_path = dir.path().join("java_config.json");
+        persistence::save_java_config_to(
+            &config_path,
+            &JavaConfig {
+                user_defined_paths: vec!["/kept/java".to_string()],
+                ..JavaConfig::default()
+            },
+        )
+        .unwrap();
+        let export_path = dir.path().join("export.json");
+        let other = dir.path().join("other_config.json");
+        persistence::save_java_config_to(&other, &exported_config()).unwrap();
+        export_settings_from(&other, &export_path).unwrap();
+
+        let options = ImportOptions {
+            mode: ImportMode::Merge,
+            skip_missing_paths: true,
+        };
+        let (config, report) = import_settings_into(&config_path, &export_path, &options, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(config.user_defined_paths, vec!["/kept/java".to_string()]);
+        assert_eq!(config.preferred_java_path, None);
+        assert_eq!(config.settings.install_dir, None);
+        assert!(config.settings.include_ea);
+        let modpack = &config.instance_java_overrides["modpack"];
+        assert_eq!(modpack.path, None);
+        assert_eq!(modpack.max_version, Some(8));
+        assert_eq!(report.skipped.len(), 4);
+
+        let replaced = ImportOptions {
+            mode: ImportMode::Replace,
+            skip_missing_paths: true,
+        };
+        let (config, _) = import_settings_into(&config_path, &export_path, &replaced, |_| {})
+            .await
+            .unwrap();
+        assert!(config.user_defined_paths.is_empty());
+    }
+
+    #[test]
+    fn exports_from_a_newer_version_are_refused() {
+        let dir = TestDir::new("settings-transfer");
+        let export_path = dir.path().join("export.json");
+        std::fs::write(
+            &export_path,
+            serde_json::json!({
+                "formatVersion": SETTINGS_EXPORT_VERSION + 1,
+                "exportedAt": 0,
+                "javaConfig": {},
+                "machineSpecificPaths": [],
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert!(matches!(
+            read_export(&export_path),
+            Err(JavaError::InvalidConfig(_))
+        ));
+    }
+}
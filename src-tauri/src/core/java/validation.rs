@@ -1,10 +1,15 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{LazyLock, Mutex};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
 use crate::core::java::JavaInstallation;
 
 pub async fn check_java_installation(path: &PathBuf) -> Option<JavaInstallation> {
@@ -14,6 +19,44 @@ pub async fn check_java_installation(path: &PathBuf) -> Option<JavaInstallation>
         .ok()?
 }
 
+/// Results of `check_java_installation_cached` by path, with the binary's modification
+/// time when it was checked
+static CHECKED_INSTALLATIONS: LazyLock<Mutex<HashMap<PathBuf, (u64, JavaInstallation)>>> =
+    LazyLock::new(Default::default);
+
+/// Drop the remembered check results, so every binary is run again
+pub(crate) fn forget_checked_installations() {
+    CHECKED_INSTALLATIONS.lock().unwrap().clear();
+}
+
+/// Modification time of the file at `path` in seconds since the epoch
+pub fn binary_mtime(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(
+        modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs(),
+    )
+}
+
+/// `check_java_installation`, reusing the last result for a binary that hasn't been
+/// modified since
+pub async fn check_java_installation_cached(path: &PathBuf) -> Option<JavaInstallation> {
+    let mtime = binary_mtime(path)?;
+    if let Some((checked_mtime, java)) = CHECKED_INSTALLATIONS.lock().unwrap().get(path)
+        && *checked_mtime == mtime
+    {
+        return Some(java.clone());
+    }
+    let java = check_java_installation(path).await?;
+    CHECKED_INSTALLATIONS
+        .lock()
+        .unwrap()
+        .insert(path.clone(), (mtime, java.clone()));
+    Some(java)
+}
+
 fn check_java_installation_blocking(path: &PathBuf) -> Option<JavaInstallation> {
     let mut cmd = Command::new(path);
     cmd.arg("-version");
@@ -30,6 +73,7 @@ fn check_java_installation_blocking(path: &PathBuf) -> Option<JavaInstallation>
     let arch = extract_architecture(&version_output);
     let vendor = extract_vendor(&version_output);
     let is_64bit = version_output.to_lowercase().contains("64-bit") || arch == "aarch64";
+    let is_native_arch = is_native_arch(&arch);
 
     Some(JavaInstallation {
         path: path.to_string_lossy().to_string(),
@@ -38,6 +82,8 @@ fn check_java_installation_blocking(path: &PathBuf) -> Option<JavaInstallation>
         vendor,
         source: "system".to_string(),
         is_64bit,
+        is_native_arch,
+        blocked_reason: None,
     })
 }
 
@@ -54,6 +100,34 @@ pub fn parse_version_string(output: &str) -> Option<String> {
     None
 }
 
+/// Numeric parts of a version, with legacy `1.8.0_392` read as `8.0.392`. Anything
+/// from the first other character on, like a `+13` build suffix, is ignored.
+pub fn version_parts(version: &str) -> Vec<u32> {
+    let numeric = version
+        .split(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_'))
+        .next()
+        .unwrap_or_default();
+    let mut parts: Vec<u32> = numeric
+        .split(['.', '_'])
+        .map_while(|part| part.parse().ok())
+        .collect();
+    if parts.len() > 1 && parts[0] == 1 {
+        parts.remove(0);
+    }
+    parts
+}
+
+/// Compare as if the shorter version had trailing zeros, so `16` equals `16.0.0`
+pub fn compare_versions(a: &[u32], b: &[u32]) -> Ordering {
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let part = |v: &[u32]| v.get(i).copied().unwrap_or(0);
+            part(a).cmp(&part(b))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
 pub fn parse_java_version(version: &str) -> u32 {
     let parts: Vec<&str> = version.split('.').collect();
     if let Some(first) = parts.first() {
@@ -70,6 +144,30 @@ pub fn parse_java_version(version: &str) -> u32 {
     }
 }
 
+/// The machine's architecture, named like `extract_architecture` names a JVM's
+pub fn host_architecture() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        other => other,
+    }
+}
+
+/// Whether a JVM for `arch` runs without emulation; 32-bit x86 does on x64 machines
+pub fn is_native_arch(arch: &str) -> bool {
+    let host = host_architecture();
+    arch == host || (host == "x64" && arch == "x86")
+}
+
+/// Resolution tier of `java` among compatible candidates: native 64-bit first, then
+/// emulated 64-bit, then 32-bit
+pub fn architecture_rank(java: &JavaInstallation) -> u8 {
+    match (java.is_64bit, java.is_native_arch) {
+        (true, true) => 0,
+        (true, false) => 1,
+        (false, _) => 2,
+    }
+}
+
 pub fn extract_architecture(version_output: &str) -> String {
     if version_output.contains("64-Bit") {
         "x64".to_string()
@@ -133,6 +231,78 @@ pub fn extract_vendor(version_output: &str) -> String {
     "Unknown".to_string()
 }
 
+/// Distribution an installation comes from, as far as it can be told from the vendor
+/// name detection reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "java/core.ts")]
+pub enum JavaVendor {
+    Temurin,
+    Corretto,
+    Dragonwell,
+    GraalVm,
+    Oracle,
+    Kona,
+    Liberica,
+    Mandrel,
+    Microsoft,
+    SapMachine,
+    Semeru,
+    Zulu,
+    Trava,
+    BiSheng,
+    /// Plain OpenJDK builds without a more specific vendor
+    OpenJdk,
+}
+
+impl JavaVendor {
+    /// Vendor of a `JavaInstallation::vendor` name; `None` for unknown vendors
+    pub fn from_vendor_name(vendor: &str) -> Option<Self> {
+        // Specific vendors first, as some names also say OpenJDK
+        const KEYWORDS: &[(&str, JavaVendor)] = &[
+            ("temurin", JavaVendor::Temurin),
+            ("adoptium", JavaVendor::Temurin),
+            ("corretto", JavaVendor::Corretto),
+            ("dragonwell", JavaVendor::Dragonwell),
+            ("graalvm", JavaVendor::GraalVm),
+            ("oracle", JavaVendor::Oracle),
+            ("kona", JavaVendor::Kona),
+            ("liberica", JavaVendor::Liberica),
+            ("mandrel", JavaVendor::Mandrel),
+            ("microsoft", JavaVendor::Microsoft),
+            ("sapmachine", JavaVendor::SapMachine),
+            ("semeru", JavaVendor::Semeru),
+            ("zulu", JavaVendor::Zulu),
+            ("trava", JavaVendor::Trava),
+            ("bisheng", JavaVendor::BiSheng),
+            ("openjdk", JavaVendor::OpenJdk),
+        ];
+        let lower = vendor.to_lowercase();
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| lower.contains(keyword))
+            .map(|(_, vendor)| *vendor)
+    }
+}
+
+/// Where `java` ranks by vendor: its position in `preferred`, after all of them if its
+/// vendor isn't preferred or is unknown, and `None` if the vendor is blocked
+pub fn vendor_rank(
+    java: &JavaInstallation,
+    preferred: &[JavaVendor],
+    blocked: &[JavaVendor],
+) -> Option<usize> {
+    match JavaVendor::from_vendor_name(&java.vendor) {
+        Some(vendor) if blocked.contains(&vendor) => None,
+        Some(vendor) => Some(
+            preferred
+                .iter()
+                .position(|p| *p == vendor)
+                .unwrap_or(preferred.len()),
+        ),
+        None => Some(preferred.len()),
+    }
+}
+
 pub fn is_version_compatible(
     major: u32,
     required_major_version: Option<u64>,
@@ -0,0 +1,460 @@
+//! JVM side of the launch command: everything before the main class.
+//!
+//! The version JSON's `jvm` arguments come first, then memory, GC defaults and the
+//! user's own arguments. The user wins conflicts: their `-Xmx`/`-Xms` replace the
+//! configured memory and any GC flag of theirs turns the defaults off.
+
+use std::path::Path;
+
+use crate::core::config::LauncherConfig;
+use crate::core::game_version::GameVersion;
+use crate::core::instance::Instance;
+use crate::core::java::persistence::InstanceJavaOverride;
+use crate::core::maven;
+use crate::core::rules::{self, RuleContext};
+
+/// G1 settings the official launcher passes too
+const DEFAULT_GC_ARGS: &[&str] = &[
+    "-XX:+UnlockExperimentalVMOptions",
+    "-XX:+UseG1GC",
+    "-XX:G1NewSizePercent=20",
+    "-XX:G1ReservePercent=20",
+    "-XX:MaxGCPauseMillis=50",
+    "-XX:G1HeapRegionSize=32M",
+];
+
+/// Files the JVM arguments point at
+pub struct LaunchPaths<'a> {
+    pub libraries_dir: &'a Path,
+    pub client_jar: &'a Path,
+    pub natives_dir: &'a Path,
+}
+
+/// Libraries that apply in `context` followed by the client jar, joined with the
+/// path separator of the OS in `context`
+pub fn build_classpath(
+    version: &GameVersion,
+    paths: &LaunchPaths,
+    context: &RuleContext,
+) -> String {
+    let separator = if context.os_name == "windows" {
+        ";"
+    } else {
+        ":"
+    };
+    let mut entries = Vec::new();
+    for lib in &version.libraries {
+        if !rules::is_library_allowed(&lib.rules, context) {
+            continue;
+        }
+        let path = match &lib.downloads {
+            // Natives-only libraries of older versions have no artifact
+            Some(downloads) => downloads.artifact.as_ref().map(|artifact| {
+                paths.libraries_dir.join(
+                    artifact
+                        .path
+                        .clone()
+                        .unwrap_or_else(|| format!("{}.jar", lib.name)),
+                )
+            }),
+            // Mod loader libraries only give a maven coordinate
+            None => maven::get_library_path(&lib.name, paths.libraries_dir),
+        };
+        if let Some(path) = path {
+            entries.push(path.to_string_lossy().into_owned());
+        }
+    }
+    entries.push(paths.client_jar.to_string_lossy().into_owned());
+    entries.join(separator)
+}
+
+/// Split user-supplied arguments like a shell would: on whitespace, except inside
+/// single or double quotes, with `\` escaping the next character outside single quotes
+pub fn split_args(input: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_arg = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// The user's extra JVM arguments for `instance`: the Java override's, or the
+/// instance's own for instances that predate Java overrides
+fn user_args(instance: &Instance, java_override: Option<&InstanceJavaOverride>) -> Vec<String> {
+    java_override
+        .and_then(|java_override| java_override.jvm_args.as_deref())
+        .or(instance.jvm_args_override.as_deref())
+        .map(split_args)
+        .unwrap_or_default()
+}
+
+/// Every JVM argument for launching `version` in `instance`, up to but excluding the
+/// main class
+pub fn build_jvm_args(
+    config: &LauncherConfig,
+    instance: &Instance,
+    java_override: Option<&InstanceJavaOverride>,
+    version: &GameVersion,
+    paths: &LaunchPaths,
+    context: &RuleContext,
+) -> Vec<String> {
+    let classpath = build_classpath(version, paths, context);
+    let natives_path = paths.natives_dir.to_string_lossy().into_owned();
+    let libraries_path = paths.libraries_dir.to_string_lossy().into_owned();
+    let replacements = [
+        ("${natives_directory}", natives_path.as_str()),
+        ("${classpath}", classpath.as_str()),
+        ("${launcher_name}", "DropOut"),
+        ("${launcher_version}", env!("CARGO_PKG_VERSION")),
+        // Used by Forge and NeoForge for their module path
+        ("${library_directory}", libraries_path.as_str()),
+        (
+            "${classpath_separator}",
+            if context.os_name == "windows" {
+                ";"
+            } else {
+                ":"
+            },
+        ),
+        ("${version_name}", version.id.as_str()),
+    ];
+
+    let mut args = Vec::new();
+    let jvm_arguments = version.arguments.as_ref().and_then(|a| a.jvm.as_ref());
+    for entry in jvm_arguments
+        .map(|jvm| rules::applicable_arguments(jvm, context))
+        .unwrap_or_default()
+    {
+        for value in entry.values {
+            let arg = replacements.iter().fold(value, |arg, (key, replacement)| {
+                arg.replace(key, replacement)
+            });
+            // Memory is set below
+            if !arg.starts_with("-Xmx") && !arg.starts_with("-Xms") {
+                args.push(arg);
+            }
+        }
+    }
+
+    let user = user_args(instance, java_override);
+    let (min_memory, max_memory) = instance
+        .memory_override
+        .as_ref()
+        .map(|memory| (memory.min, memory.max))
+        .unwrap_or((config.min_memory, config.max_memory));
+    if !user.iter().any(|arg| arg.starts_with("-Xmx")) {
+        args.push(format!("-Xmx{}M", max_memory));
+    }
+    if !user.iter().any(|arg| arg.starts_with("-Xms")) {
+        args.push(format!("-Xms{}M", min_memory));
+    }
+    let user_picked_gc = user
+        .iter()
+        .any(|arg| arg.starts_with("-XX:+Use") && arg.ends_with("GC"));
+    if !user_picked_gc {
+        args.extend(DEFAULT_GC_ARGS.iter().map(|arg| arg.to_string()));
+    }
+    args.extend(user);
+
+    // Versions before 1.13 have no `jvm` arguments at all
+    if !args
+        .iter()
+        .any(|arg| arg.starts_with("-Djava.library.path"))
+    {
+        args.push(format!("-Djava.library.path={}", natives_path));
+    }
+    // JavaFX Prism rendering order, unless the user set it:
+    // Direct3D is only available on Windows
+    if !args.iter().any(|arg| arg.starts_with("-Dprism.order=")) {
+        args.push(if context.os_name == "windows" {
+            "-Dprism.order=d3d,es2,sw".to_string()
+        } else {
+            "-Dprism.order=es2,sw".to_string()
+        });
+    }
+    if !args.iter().any(|arg| arg == "-cp" || arg == "-classpath") {
+        args.push("-cp".to_string());
+        args.push(classpath);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::instance::MemoryOverride;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    /// Trimmed from the 1.20.4 version JSON
+    const VANILLA_1_20_4: &str = r#"{
+        "id": "1.20.4",
+        "mainClass": "net.minecraft.client.main.Main",
+        "arguments": {
+            "game": [],
+            "jvm": [
+                {"rules": [{"action": "allow", "os": {"name": "osx"}}], "value": ["-XstartOnFirstThread"]},
+                {"rules": [{"action": "allow", "os": {"name": "windows"}}], "value": "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump"},
+                {"rules": [{"action": "allow", "os": {"arch": "x86"}}], "value": "-Xss1M"},
+                "-Djava.library.path=${natives_directory}",
+                "-Dminecraft.launcher.brand=${launcher_name}",
+                "-cp",
+                "${classpath}"
+            ]
+        },
+        "libraries": [
+            {"name": "com.mojang:logging:1.1.1", "downloads": {"artifact": {"path": "com/mojang/logging/1.1.1/logging-1.1.1.jar", "url": "https://libraries.minecraft.net/com/mojang/logging/1.1.1/logging-1.1.1.jar"}}},
+            {"name": "org.lwjgl:lwjgl:3.3.2:natives-macos-arm64", "rules": [{"action": "allow", "os": {"name": "osx"}}], "downloads": {"artifact": {"path": "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-macos-arm64.jar", "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-macos-arm64.jar"}}},
+            {"name": "org.lwjgl:lwjgl:3.3.2:natives-windows", "rules": [{"action": "allow", "os": {"name": "windows"}}], "downloads": {"artifact": {"path": "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-windows.jar", "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-windows.jar"}}}
+        ]
+    }"#;
+
+    fn platform(os_name: &str, arch: &str) -> RuleContext {
+        RuleContext {
+            os_name: os_name.to_string(),
+            os_version: "10.0".to_string(),
+            arch: arch.to_string(),
+            features: HashMap::new(),
+        }
+    }
+
+    fn instance() -> Instance {
+        Instance::for_test("test", PathBuf::from("/mc"))
+    }
+
+    fn jar(libraries: &Path, path: &str) -> String {
+        libraries.join(path).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn builds_exact_arguments_for_1_20_4_on_each_os() {
+        let version: GameVersion = serde_json::from_str(VANILLA_1_20_4).unwrap();
+        let libraries = Path::new("/mc/libraries");
+        let client = Path::new("/mc/versions/1.20.4/1.20.4.jar");
+        let natives = Path::new("/mc/versions/1.20.4/natives");
+        let paths = LaunchPaths {
+            libraries_dir: libraries,
+            client_jar: client,
+            natives_dir: natives,
+        };
+        let logging = jar(libraries, "com/mojang/logging/1.1.1/logging-1.1.1.jar");
+        let client = client.to_string_lossy().into_owned();
+        let natives = format!("-Djava.library.path={}", natives.display());
+        let config = LauncherConfig::default();
+        let expected = |platform_args: &[&str], classpath: String, prism: &str| {
+            let mut expected: Vec<String> = platform_args.iter().map(|a| a.to_string()).collect();
+            expected.extend([
+                natives.clone(),
+                "-Dminecraft.launcher.brand=DropOut".to_string(),
+                "-cp".to_string(),
+                classpath,
+                "-Xmx2048M".to_string(),
+                "-Xms1024M".to_string(),
+            ]);
+            expected.extend(DEFAULT_GC_ARGS.iter().map(|a| a.to_string()));
+            expected.push(prism.to_string());
+            expected
+        };
+
+        let linux = build_jvm_args(
+            &config,
+            &instance(),
+            None,
+            &version,
+            &paths,
+            &platform("linux", "x86_64"),
+        );
+        assert_eq!(
+            linux,
+            expected(
+                &[],
+                format!("{}:{}", logging, client),
+                "-Dprism.order=es2,sw"
+            )
+        );
+
+        let mac = build_jvm_args(
+            &config,
+            &instance(),
+            None,
+            &version,
+            &paths,
+            &platform("osx", "aarch64"),
+        );
+        let mac_natives = jar(
+            libraries,
+            "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-macos-arm64.jar",
+        );
+        assert_eq!(
+            mac,
+            expected(
+                &["-XstartOnFirstThread"],
+                format!("{}:{}:{}", logging, mac_natives, client),
+                "-Dprism.order=es2,sw"
+            )
+        );
+
+        let windows = build_jvm_args(
+            &config,
+            &instance(),
+            None,
+            &version,
+            &paths,
+            &platform("windows", "x86"),
+        );
+        let windows_natives = jar(
+            libraries,
+            "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-windows.jar",
+        );
+        assert_eq!(
+            windows,
+            expected(
+                &[
+                    "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump",
+                    "-Xss1M"
+                ],
+                format!("{};{};{}", logging, windows_natives, client),
+                "-Dprism.order=d3d,es2,sw"
+            )
+        );
+    }
+
+    #[test]
+    fn user_memory_and_gc_flags_win() {
+        let version: GameVersion = serde_json::from_str(VANILLA_1_20_4).unwrap();
+        let paths = LaunchPaths {
+            libraries_dir: Path::new("/mc/libraries"),
+            client_jar: Path::new("/mc/client.jar"),
+            natives_dir: Path::new("/mc/natives"),
+        };
+        let linux = platform("linux", "x86_64");
+        let mut instance = instance();
+        instance.memory_override = Some(MemoryOverride {
+            min: 512,
+            max: 4096,
+        });
+
+        let args = build_jvm_args(
+            &LauncherConfig::default(),
+            &instance,
+            None,
+            &version,
+            &paths,
+            &linux,
+        );
+        assert!(args.contains(&"-Xmx4096M".to_string()));
+        assert!(args.contains(&"-Xms512M".to_string()));
+
+        let java_override = InstanceJavaOverride {
+            jvm_args: Some("-Xmx6G -XX:+UseZGC \"-Dtitle=My Pack\"".to_string()),
+            ..InstanceJavaOverride::default()
+        };
+        let args = build_jvm_args(
+            &LauncherConfig::default(),
+            &instance,
+            Some(&java_override),
+            &version,
+            &paths,
+            &linux,
+        );
+        let xmx: Vec<&String> = args.iter().filter(|a| a.starts_with("-Xmx")).collect();
+        assert_eq!(xmx, vec!["-Xmx6G"]);
+        assert!(args.contains(&"-Xms512M".to_string()));
+        assert!(args.contains(&"-Dtitle=My Pack".to_string()));
+        assert!(!args.iter().any(|a| a == "-XX:+UseG1GC"));
+    }
+
+    #[test]
+    fn fills_in_forge_module_path_placeholders() {
+        let version: GameVersion = serde_json::from_value(serde_json::json!({
+            "id": "1.20.4-forge-49.0.38",
+            "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+            "arguments": {"game": [], "jvm": [
+                "-DlibraryDirectory=${library_directory}",
+                "-p",
+                "${library_directory}/cpw/mods/bootstraplauncher/1.1.2/bootstraplauncher-1.1.2.jar${classpath_separator}${library_directory}/cpw/mods/securejarhandler/2.1.24/securejarhandler-2.1.24.jar",
+                "-DignoreList=client-extra,${version_name}.jar"
+            ]},
+            "libraries": []
+        }))
+        .unwrap();
+        let paths = LaunchPaths {
+            libraries_dir: Path::new("/mc/libraries"),
+            client_jar: Path::new("/mc/versions/1.20.4/1.20.4.jar"),
+            natives_dir: Path::new("/mc/natives"),
+        };
+
+        let linux = build_jvm_args(
+            &LauncherConfig::default(),
+            &instance(),
+            None,
+            &version,
+            &paths,
+            &platform("linux", "x86_64"),
+        );
+        assert_eq!(
+            linux[..4],
+            [
+                "-DlibraryDirectory=/mc/libraries",
+                "-p",
+                "/mc/libraries/cpw/mods/bootstraplauncher/1.1.2/bootstraplauncher-1.1.2.jar:/mc/libraries/cpw/mods/securejarhandler/2.1.24/securejarhandler-2.1.24.jar",
+                "-DignoreList=client-extra,1.20.4-forge-49.0.38.jar",
+            ]
+        );
+
+        let windows = build_jvm_args(
+            &LauncherConfig::default(),
+            &instance(),
+            None,
+            &version,
+            &paths,
+            &platform("windows", "x86_64"),
+        );
+        assert!(windows[2].contains(".jar;/mc/libraries/"), "{}", windows[2]);
+    }
+
+    #[test]
+    fn splits_user_arguments_like_a_shell() {
+        assert_eq!(
+            split_args(r#"  -Xss2m "-Dpath=C:\\Program Files" '-Dq="x"' -Da=b\ c "" "#),
+            vec![
+                "-Xss2m",
+                r"-Dpath=C:\Program Files",
+                r#"-Dq="x""#,
+                "-Da=b c",
+                ""
+            ]
+        );
+        assert!(split_args("   ").is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+//! Per-instance record of launches that didn't end cleanly, so a crash can still be
+//! looked into after the launcher was restarted.
+//!
+//! Kept as `launch_history.json` in the instance directory, next to the game files it
+//! describes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+use crate::core::game_process::ExitKind;
+use crate::core::java::JavaInstallation;
+
+/// Number of records kept per instance; older ones are dropped
+pub const HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "launch_history.ts")]
+pub struct LaunchRecord {
+    /// Unix time the game exited
+    #[ts(type = "number")]
+    pub finished_at: u64,
+    pub version_id: String,
+    pub exit_code: Option<i32>,
+    pub kind: ExitKind,
+    #[ts(type = "number")]
+    pub duration_secs: u64,
+    /// Java the game ran with
+    pub java: JavaInstallation,
+    pub crash_report_path: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LaunchHistory {
+    /// Oldest first
+    records: VecDeque<LaunchRecord>,
+}
+
+fn history_path(instance_dir: &Path) -> PathBuf {
+    instance_dir.join("launch_history.json")
+}
+
+fn load(instance_dir: &Path) -> LaunchHistory {
+    std::fs::read_to_string(history_path(instance_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Append `record` to the history of the instance in `instance_dir`
+pub fn record(instance_dir: &Path, record: LaunchRecord) -> Result<(), String> {
+    let mut history = load(instance_dir);
+    history.records.push_back(record);
+    while history.records.len() > HISTORY_LIMIT {
+        history.records.pop_front();
+    }
+    let content = serde_json::to_string_pretty(&history).map_err(|e| e.to_string())?;
+    crate::core::java::persistence::write_file_atomic(
+        &history_path(instance_dir),
+        content.as_bytes(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Recorded launches of the instance in `instance_dir`, newest first
+pub fn entries(instance_dir: &Path) -> Vec<LaunchRecord> {
+    load(instance_dir).records.into_iter().rev().collect()
+}
@@ -29,6 +29,9 @@ pub struct Version {
     #[serde(rename = "type")]
     pub type_: String,
     pub url: String,
+    /// SHA-1 of the version JSON at `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<String>,
     pub time: String,
     #[serde(rename = "releaseTime")]
     pub release_time: String,
@@ -43,7 +46,12 @@ pub struct Version {
 
 pub async fn fetch_version_manifest() -> Result<VersionManifest, Box<dyn Error + Send + Sync>> {
     let url = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
-    let resp = reqwest::get(url).await?.json::<VersionManifest>().await?;
+    let resp = crate::core::http::client()
+        .get(url)
+        .send()
+        .await?
+        .json::<VersionManifest>()
+        .await?;
     Ok(resp)
 }
 
@@ -76,13 +84,27 @@ pub async fn load_local_version(
     Ok(version)
 }
 
+/// The Minecraft version `version_id` is built on: the version its local JSON
+/// inherits from, or `version_id` itself for vanilla versions.
+///
+/// [`load_version`] clears `inheritsFrom` when it merges the parents in, so
+/// this reads the JSON as installed.
+pub async fn minecraft_version(game_dir: &std::path::Path, version_id: &str) -> String {
+    match load_local_version(game_dir, version_id).await {
+        Ok(version) => version.inherits_from,
+        Err(_) => None,
+    }
+    .unwrap_or_else(|| version_id.to_string())
+}
+
 /// Fetch a version JSON from Mojang's servers.
 ///
 /// # Arguments
 /// * `version_id` - The version ID to fetch
 ///
 /// # Returns
-/// The parsed `GameVersion` from Mojang's API.
+/// The parsed `GameVersion` from Mojang's API, after checking it against the
+/// SHA-1 listed in the manifest.
 pub async fn fetch_vanilla_version(
     version_id: &str,
 ) -> Result<GameVersion, Box<dyn Error + Send + Sync>> {
@@ -96,12 +118,33 @@ pub async fn fetch_vanilla_version(
         .ok_or_else(|| format!("Version {} not found in manifest", version_id))?;
 
     // Fetch the actual version JSON
-    let resp = reqwest::get(&version_entry.url)
+    let body = crate::core::http::client()
+        .get(&version_entry.url)
+        .send()
         .await?
-        .json::<GameVersion>()
+        .error_for_status()?
+        .bytes()
         .await?;
+    if let Some(expected) = &version_entry.sha1 {
+        verify_sha1(version_id, &body, expected)?;
+    }
 
-    Ok(resp)
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Check a downloaded version JSON against the SHA-1 from the manifest
+fn verify_sha1(version_id: &str, body: &[u8], expected: &str) -> Result<(), String> {
+    use sha1::Digest;
+
+    let actual = hex::encode(sha1::Sha1::digest(body));
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Version {} JSON failed verification: expected SHA-1 {}, got {}",
+            version_id, expected, actual
+        ))
+    }
 }
 
 /// Find the root vanilla version by following the inheritance chain.
@@ -144,7 +187,8 @@ pub async fn find_root_version(
 /// Load a version, checking local first, then fetching from remote if needed.
 ///
 /// For modded versions (those with `inheritsFrom`), this will also resolve
-/// the inheritance chain.
+/// the inheritance chain. Parents fetched from Mojang are saved locally so the
+/// next launch doesn't fetch them again.
 ///
 /// # Arguments
 /// * `game_dir` - The .minecraft directory path
@@ -170,7 +214,11 @@ pub async fn load_version(
         // Load the parent version
         let parent = match load_local_version(game_dir, &parent_id).await {
             Ok(v) => v,
-            Err(_) => fetch_vanilla_version(&parent_id).await?,
+            Err(_) => {
+                let fetched = fetch_vanilla_version(&parent_id).await?;
+                save_local_version(game_dir, &fetched).await?;
+                fetched
+            }
         };
 
         // Merge child into parent
@@ -233,3 +281,56 @@ pub async fn list_local_versions(
 
     Ok(versions)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn version_json_is_checked_against_the_manifest_sha1() {
+        let body = br#"{"id":"1.20.4"}"#;
+        let sha1 = "d10736751cd1b0d6bb9a1a9f51ead50ecf8ff7ce";
+        assert!(verify_sha1("1.20.4", body, sha1).is_ok());
+        assert!(verify_sha1("1.20.4", body, &sha1.to_uppercase()).is_ok());
+        let err = verify_sha1("1.20.4", b"{}", sha1).unwrap_err();
+        assert!(err.contains("1.20.4"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn modded_versions_are_built_on_the_version_they_inherit_from() {
+        let dir = TestDir::new("manifest");
+        let fabric = "fabric-loader-0.15.11-1.20.1";
+        for (id, json) in [
+            (
+                fabric,
+                serde_json::json!({
+                    "id": fabric,
+                    "inheritsFrom": "1.20.1",
+                    "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+                    "libraries": [],
+                }),
+            ),
+            (
+                "1.20.1",
+                serde_json::json!({
+                    "id": "1.20.1",
+                    "mainClass": "net.minecraft.client.main.Main",
+                    "libraries": [],
+                }),
+            ),
+        ] {
+            let versions = dir.path().join("versions").join(id);
+            std::fs::create_dir_all(&versions).unwrap();
+            std::fs::write(versions.join(format!("{}.json", id)), json.to_string()).unwrap();
+        }
+
+        // The merged version no longer says what it inherits from
+        let merged = load_version(dir.path(), fabric).await.unwrap();
+        assert_eq!(merged.inherits_from, None);
+        assert_eq!(minecraft_version(dir.path(), fabric).await, "1.20.1");
+        assert_eq!(minecraft_version(dir.path(), "1.20.1").await, "1.20.1");
+        // Not installed: taken as a vanilla version
+        assert_eq!(minecraft_version(dir.path(), "1.21").await, "1.21");
+    }
+}
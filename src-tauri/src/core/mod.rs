@@ -1,15 +1,31 @@
 pub mod account_storage;
+pub mod assets;
 pub mod assistant;
 pub mod auth;
 pub mod config;
+pub mod crash;
+pub mod download_history;
 pub mod downloader;
 pub mod fabric;
 pub mod forge;
+pub mod forge_installer;
+pub mod game_args;
+pub mod game_files;
+pub mod game_process;
 pub mod game_version;
+pub mod hooks;
+pub mod http;
+pub mod i18n;
 pub mod instance;
 pub mod java;
+pub mod jvm_args;
+pub mod launch_history;
 pub mod manifest;
 pub mod maven;
 pub mod modpack;
+pub mod natives;
+pub mod paths;
+pub mod profiles;
+pub mod quick_play;
 pub mod rules;
 pub mod version_merge;
@@ -111,7 +111,7 @@ impl CurseForgeApi {
 
 impl Default for CurseForgeApi {
     fn default() -> Self {
-        Self::new(reqwest::Client::new())
+        Self::new(crate::core::http::client())
     }
 }
 
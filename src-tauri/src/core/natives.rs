@@ -0,0 +1,289 @@
+//! Native libraries (LWJGL and friends) extracted before launch.
+//!
+//! Before 1.19 natives ship as classified jars next to the regular artifact, picked
+//! through the library's `natives` map (`"linux": "natives-linux"`, with `${arch}`
+//! standing for 32 or 64). From 1.19 on they are plain artifacts such as
+//! `org.lwjgl:lwjgl:3.3.1:natives-macos-arm64` whose rules select the right one.
+//! Either way the jars are unpacked into a natives directory that the game finds
+//! through `-Djava.library.path`.
+
+use std::path::{Path, PathBuf};
+
+use crate::core::game_version::{DownloadArtifact, GameVersion, Library};
+use crate::core::rules::{self, RuleContext};
+use crate::utils::zip;
+
+/// `natives` map key of the current OS
+fn os_key() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "osx"
+    } else {
+        "linux"
+    }
+}
+
+/// Classifier keys to try for this OS and architecture, best match first
+fn classifier_candidates(lib: &Library) -> Vec<String> {
+    let arch = std::env::consts::ARCH;
+    let mut candidates = Vec::new();
+    if let Some(classifier) = lib
+        .natives
+        .as_ref()
+        .and_then(|natives| natives.get(os_key()))
+        .and_then(|classifier| classifier.as_str())
+    {
+        let bits = if cfg!(target_pointer_width = "64") {
+            "64"
+        } else {
+            "32"
+        };
+        let classifier = classifier.replace("${arch}", bits);
+        // Prefer an Apple silicon build when the library publishes one
+        if cfg!(target_os = "macos") && arch == "aarch64" {
+            candidates.push(format!("{}-arm64", classifier));
+        }
+        candidates.push(classifier);
+    }
+
+    if cfg!(target_os = "linux") {
+        candidates.push("natives-linux".to_string());
+        candidates.push(format!("natives-linux-{}", arch));
+        if arch == "aarch64" {
+            candidates.push("natives-linux-arm64".to_string());
+        }
+    } else if cfg!(target_os = "windows") {
+        candidates.push("natives-windows".to_string());
+        candidates.push(format!("natives-windows-{}", arch));
+    } else if cfg!(target_os = "macos") {
+        if arch == "aarch64" {
+            candidates.push("natives-macos-arm64".to_string());
+        }
+        candidates.push("natives-osx".to_string());
+        candidates.push("natives-macos".to_string());
+        candidates.push(format!("natives-macos-{}", arch));
+    }
+    candidates
+}
+
+/// The classified native jar of `lib` for this machine, if it has one
+pub fn native_artifact(lib: &Library) -> Option<DownloadArtifact> {
+    let classifiers = lib.downloads.as_ref()?.classifiers.as_ref()?;
+    classifier_candidates(lib).into_iter().find_map(|key| {
+        serde_json::from_value::<DownloadArtifact>(classifiers.get(&key)?.clone()).ok()
+    })
+}
+
+/// Where the classified native jar of `lib` is stored under `libraries_dir`
+pub fn native_artifact_path(
+    lib: &Library,
+    artifact: &DownloadArtifact,
+    libraries_dir: &Path,
+) -> PathBuf {
+    libraries_dir.join(
+        artifact
+            .path
+            .clone()
+            .unwrap_or_else(|| format!("{}-natives.jar", lib.name)),
+    )
+}
+
+/// Native jar of `lib` to extract: its classified jar, or the artifact itself when the
+/// library is a 1.19+ `natives-*` artifact
+fn native_jar(lib: &Library, libraries_dir: &Path) -> Option<PathBuf> {
+    if let Some(artifact) = native_artifact(lib) {
+        return Some(native_artifact_path(lib, &artifact, libraries_dir));
+    }
+    let classifier = lib.name.split(':').nth(3)?;
+    if !classifier.starts_with("natives-") {
+        return None;
+    }
+    let artifact = lib.downloads.as_ref()?.artifact.as_ref()?;
+    Some(libraries_dir.join(artifact.path.as_ref()?))
+}
+
+/// Extract the native jars of `version` into `natives_dir`, emptying it first so
+/// natives of another version can't be picked up.
+///
+/// Entries listed in a library's `extract.exclude` are skipped; libraries without
+/// extract rules skip `META-INF/`. Jars that aren't on disk are ignored. Returns how
+/// many jars were extracted.
+pub fn extract_natives(
+    version: &GameVersion,
+    libraries_dir: &Path,
+    natives_dir: &Path,
+    rule_context: &RuleContext,
+) -> Result<usize, String> {
+    if natives_dir.exists() {
+        std::fs::remove_dir_all(natives_dir).map_err(|e| {
+            format!(
+                "Failed to clean natives directory {}: {}",
+                natives_dir.display(),
+                e
+            )
+        })?;
+    }
+    std::fs::create_dir_all(natives_dir).map_err(|e| {
+        format!(
+            "Failed to create natives directory {}: {}",
+            natives_dir.display(),
+            e
+        )
+    })?;
+
+    let mut extracted = 0;
+    for lib in &version.libraries {
+        if !rules::is_library_allowed(&lib.rules, rule_context) {
+            continue;
+        }
+        let Some(jar) = native_jar(lib, libraries_dir) else {
+            continue;
+        };
+        if !jar.exists() {
+            continue;
+        }
+        let exclude = match &lib.extract {
+            Some(extract) => extract.exclude.clone(),
+            None => vec!["META-INF/".to_string()],
+        };
+        zip::extract_zip_filtered(&jar, natives_dir, |name| {
+            exclude
+                .iter()
+                .any(|prefix| name.starts_with(prefix.as_str()))
+        })?;
+        extracted += 1;
+    }
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use std::io::Write;
+
+    fn write_jar(path: &Path, entries: &[&str]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut writer = ::zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = ::zip::write::SimpleFileOptions::default();
+        for entry in entries {
+            writer.start_file(*entry, options).unwrap();
+            writer.write_all(b"native").unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn classified_library(name: &str, path: &str, extract: &str) -> serde_json::Value {
+        let artifact = |os: &str| {
+            serde_json::json!({
+                "path": format!("{}-{}.jar", path, os),
+                "url": format!("https://libraries.minecraft.net/{}-{}.jar", path, os),
+            })
+        };
+        serde_json::json!({
+            "name": name,
+            "natives": {"linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows-${arch}"},
+            "extract": {"exclude": [extract]},
+            "downloads": {"classifiers": {
+                "natives-linux": artifact("natives-linux"),
+                "natives-osx": artifact("natives-osx"),
+                "natives-windows-32": artifact("natives-windows-32"),
+                "natives-windows-64": artifact("natives-windows-64"),
+            }}
+        })
+    }
+
+    fn current_classifier() -> &'static str {
+        if cfg!(target_os = "windows") {
+            if cfg!(target_pointer_width = "64") {
+                "natives-windows-64"
+            } else {
+                "natives-windows-32"
+            }
+        } else if cfg!(target_os = "macos") {
+            "natives-osx"
+        } else {
+            "natives-linux"
+        }
+    }
+
+    #[test]
+    fn picks_the_classifier_for_this_machine() {
+        let lib: Library = serde_json::from_value(classified_library(
+            "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+            "lwjgl-platform",
+            "META-INF/",
+        ))
+        .unwrap();
+        let artifact = native_artifact(&lib).unwrap();
+        assert_eq!(
+            artifact.path.as_deref(),
+            Some(format!("lwjgl-platform-{}.jar", current_classifier()).as_str())
+        );
+
+        let plain: Library =
+            serde_json::from_value(serde_json::json!({"name": "com.google.code.gson:gson:2.8.0"}))
+                .unwrap();
+        assert!(native_artifact(&plain).is_none());
+        assert!(native_jar(&plain, Path::new("libraries")).is_none());
+    }
+
+    #[test]
+    fn extracts_natives_honouring_exclude_rules() {
+        let root = TestDir::new("natives");
+        let libraries_dir = root.path().join("libraries");
+        let natives_dir = root.path().join("natives");
+        let classifier = current_classifier();
+
+        write_jar(
+            &libraries_dir.join(format!("lwjgl-platform-{}.jar", classifier)),
+            &["liblwjgl.so", "lwjgl.dll", "META-INF/MANIFEST.MF"],
+        );
+        write_jar(
+            &libraries_dir.join(format!("jinput-platform-{}.jar", classifier)),
+            &["libjinput.so", "META-INF/MANIFEST.MF", "jinput/README.txt"],
+        );
+        write_jar(
+            &libraries_dir.join("org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-test.jar"),
+            &["liblwjgl3.so", "META-INF/MANIFEST.MF"],
+        );
+        std::fs::create_dir_all(&natives_dir).unwrap();
+        std::fs::write(natives_dir.join("stale.so"), b"old").unwrap();
+
+        let version: GameVersion = serde_json::from_value(serde_json::json!({
+            "id": "1.12.2",
+            "mainClass": "net.minecraft.client.main.Main",
+            "libraries": [
+                classified_library("org.lwjgl.lwjgl:lwjgl-platform:2.9.4", "lwjgl-platform", "META-INF/"),
+                classified_library("net.java.jinput:jinput-platform:2.0.5", "jinput-platform", "jinput/"),
+                {"name": "org.lwjgl:lwjgl:3.3.1:natives-test", "downloads": {"artifact": {
+                    "path": "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-test.jar",
+                    "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-test.jar"
+                }}},
+                {"name": "org.lwjgl:lwjgl:3.3.1:natives-elsewhere", "rules": [{"action": "allow", "os": {"name": "no-such-os"}}], "downloads": {"artifact": {
+                    "path": "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-test.jar",
+                    "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-test.jar"
+                }}}
+            ]
+        }))
+        .unwrap();
+
+        let extracted = extract_natives(
+            &version,
+            &libraries_dir,
+            &natives_dir,
+            &RuleContext::current(),
+        )
+        .unwrap();
+        assert_eq!(extracted, 3);
+        assert!(!natives_dir.join("stale.so").exists());
+        assert!(natives_dir.join("liblwjgl.so").exists());
+        assert!(natives_dir.join("lwjgl.dll").exists());
+        assert!(natives_dir.join("libjinput.so").exists());
+        assert!(natives_dir.join("liblwjgl3.so").exists());
+        // Only skipped where the library's rules say so
+        assert!(natives_dir.join("META-INF/MANIFEST.MF").exists());
+        assert!(!natives_dir.join("jinput").exists());
+    }
+}
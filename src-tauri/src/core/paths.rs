@@ -0,0 +1,128 @@
+//! Where the launcher keeps its data.
+//!
+//! Normally that is the OS app-data directory. In portable mode, switched on by a
+//! `portable.flag` file next to the executable or the `--portable` argument, it is
+//! `<exe dir>/data` instead, so a copy on a USB stick carries its config, caches and
+//! managed Java with it.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+use crate::core::java::JavaError;
+
+pub const PORTABLE_FLAG_FILE: &str = "portable.flag";
+pub const PORTABLE_ARG: &str = "--portable";
+const PORTABLE_DATA_DIR: &str = "data";
+
+/// Resolved on first use and kept for the rest of the run
+static DATA_ROOT: OnceLock<Result<PathBuf, JavaError>> = OnceLock::new();
+
+/// Data directory for the given environment. `app_data_dir` asks the OS for its
+/// app-data directory and is only called outside portable mode. Falls back to the
+/// portable layout when the OS has none to offer; fails when the executable's
+/// location is unknown too, rather than writing wherever the working directory is.
+fn resolve_data_root(
+    app_data_dir: impl FnOnce() -> tauri::Result<PathBuf>,
+    exe_dir: Option<&Path>,
+    portable_arg: bool,
+) -> Result<PathBuf, JavaError> {
+    let portable_dir = exe_dir.map(|dir| dir.join(PORTABLE_DATA_DIR));
+    let portable =
+        portable_arg || exe_dir.is_some_and(|dir| dir.join(PORTABLE_FLAG_FILE).is_file());
+    if portable && let Some(portable_dir) = portable_dir {
+        return Ok(portable_dir);
+    }
+    let err = match app_data_dir() {
+        Ok(app_data_dir) => return Ok(app_data_dir),
+        Err(err) => err,
+    };
+    match portable_dir {
+        Some(portable_dir) => {
+            log::warn!(
+                "No app data directory available ({}), storing data next to the launcher",
+                err
+            );
+            Ok(portable_dir)
+        }
+        None => Err(JavaError::DataDirUnavailable(err.to_string())),
+    }
+}
+
+/// Root directory every file the launcher writes lives under
+pub fn try_data_root(app_handle: &AppHandle) -> Result<PathBuf, JavaError> {
+    DATA_ROOT
+        .get_or_init(|| {
+            let exe_dir = std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(Path::to_path_buf));
+            let portable_arg = std::env::args().any(|arg| arg == PORTABLE_ARG);
+            let root = resolve_data_root(
+                || app_handle.path().app_data_dir(),
+                exe_dir.as_deref(),
+                portable_arg,
+            );
+            match &root {
+                Ok(root) => log::info!("Data directory: {}", root.display()),
+                Err(e) => log::error!("{}", e.with_code()),
+            }
+            root
+        })
+        .clone()
+}
+
+/// [`try_data_root`] for callers that can't report an error, falling back to `data`
+/// in the working directory. Startup tells the user when that happens.
+pub fn data_root(app_handle: &AppHandle) -> PathBuf {
+    try_data_root(app_handle).unwrap_or_else(|_| PathBuf::from(PORTABLE_DATA_DIR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn unavailable() -> tauri::Result<PathBuf> {
+        Err(tauri::Error::UnknownPath)
+    }
+
+    #[test]
+    fn data_root_follows_the_portable_flag_and_argument() {
+        let dir = TestDir::new("paths");
+        let exe_dir = dir.path();
+        let app_data = PathBuf::from("/home/user/.local/share/com.dropout.launcher");
+        let os_dir = || Ok(app_data.clone());
+
+        assert_eq!(
+            resolve_data_root(os_dir, Some(exe_dir), false).unwrap(),
+            app_data
+        );
+        assert_eq!(
+            resolve_data_root(os_dir, Some(exe_dir), true).unwrap(),
+            exe_dir.join("data")
+        );
+
+        std::fs::write(exe_dir.join(PORTABLE_FLAG_FILE), b"").unwrap();
+        assert_eq!(
+            resolve_data_root(os_dir, Some(exe_dir), false).unwrap(),
+            exe_dir.join("data")
+        );
+    }
+
+    #[test]
+    fn data_root_without_an_app_data_dir_stays_next_to_the_launcher() {
+        let dir = TestDir::new("paths");
+        let exe_dir = dir.path();
+        assert_eq!(
+            resolve_data_root(unavailable, Some(exe_dir), false).unwrap(),
+            exe_dir.join("data")
+        );
+    }
+
+    #[test]
+    fn data_root_fails_when_nothing_can_be_resolved() {
+        let err = resolve_data_root(unavailable, None, true).unwrap_err();
+        assert!(matches!(err, JavaError::DataDirUnavailable(_)));
+        assert_eq!(err.code(), "JAVA_E_DATA_DIR_UNAVAILABLE");
+    }
+}
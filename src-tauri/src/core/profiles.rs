@@ -0,0 +1,251 @@
+//! Configuration profiles, e.g. "stable" and "testing" with a different preferred Java,
+//! provider and instance overrides.
+//!
+//! Each profile has its own Java config and catalog caches. The `default` profile lives
+//! directly in the data root, where those files were kept before profiles existed;
+//! the others live in `profiles/<name>`. Managed Java installs stay shared.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::AppHandle;
+use ts_rs::TS;
+
+use crate::core::java::JavaError;
+use crate::core::paths;
+
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+const ACTIVE_PROFILE_FILE: &str = "active_profile";
+const MAX_PROFILE_NAME_LEN: usize = 64;
+
+/// Active profile, read from `active_profile` on first use
+static ACTIVE_PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "profiles.ts")]
+pub struct Profile {
+    pub name: String,
+    pub active: bool,
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid profile name \"{}\": use up to {} letters, digits, '-' or '_'",
+            name, MAX_PROFILE_NAME_LEN
+        ))
+    }
+}
+
+fn profile_dir_in(data_root: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        data_root.to_path_buf()
+    } else {
+        data_root.join(PROFILES_DIR).join(name)
+    }
+}
+
+fn profile_exists_in(data_root: &Path, name: &str) -> bool {
+    validate_name(name).is_ok() && profile_dir_in(data_root, name).is_dir()
+}
+
+/// Profile named in `active_profile`, or the default one if that is missing or gone
+fn read_active_profile(data_root: &Path) -> String {
+    std::fs::read_to_string(data_root.join(ACTIVE_PROFILE_FILE))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| profile_exists_in(data_root, name))
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+fn list_profiles_in(data_root: &Path, active: &str) -> Vec<Profile> {
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    if let Ok(entries) = std::fs::read_dir(data_root.join(PROFILES_DIR)) {
+        let mut others: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name != DEFAULT_PROFILE && validate_name(name).is_ok())
+            .collect();
+        others.sort();
+        names.extend(others);
+    }
+    names
+        .into_iter()
+        .map(|name| Profile {
+            active: name == active,
+            name,
+        })
+        .collect()
+}
+
+/// Create a profile, starting from a copy of `copy_from`'s Java config if given
+fn create_profile_in(data_root: &Path, name: &str, copy_from: Option<&str>) -> Result<(), String> {
+    validate_name(name)?;
+    if profile_exists_in(data_root, name) {
+        return Err(format!("Profile \"{}\" already exists", name));
+    }
+    if let Some(source) = copy_from
+        && !profile_exists_in(data_root, source)
+    {
+        return Err(format!("Profile \"{}\" does not exist", source));
+    }
+
+    let dir = profile_dir_in(data_root, name);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    if let Some(source) = copy_from {
+        let config = profile_dir_in(data_root, source).join("java_config.json");
+        if config.is_file() {
+            std::fs::copy(&config, dir.join("java_config.json")).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn delete_profile_in(data_root: &Path, name: &str, active: &str) -> Result<(), String> {
+    validate_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile can't be deleted".to_string());
+    }
+    if name == active {
+        return Err(format!(
+            "Profile \"{}\" is active; switch to another profile first",
+            name
+        ));
+    }
+    if !profile_exists_in(data_root, name) {
+        return Err(format!("Profile \"{}\" does not exist", name));
+    }
+    std::fs::remove_dir_all(profile_dir_in(data_root, name)).map_err(|e| e.to_string())
+}
+
+pub fn active_profile(app_handle: &AppHandle) -> String {
+    if let Some(name) = ACTIVE_PROFILE.read().unwrap().as_ref() {
+        return name.clone();
+    }
+    let name = read_active_profile(&paths::data_root(app_handle));
+    ACTIVE_PROFILE.write().unwrap().get_or_insert(name).clone()
+}
+
+/// Directory holding the active profile's Java config and caches
+pub fn profile_dir(app_handle: &AppHandle) -> Result<PathBuf, JavaError> {
+    Ok(profile_dir_in(
+        &paths::try_data_root(app_handle)?,
+        &active_profile(app_handle),
+    ))
+}
+
+pub fn list_profiles(app_handle: &AppHandle) -> Vec<Profile> {
+    list_profiles_in(&paths::data_root(app_handle), &active_profile(app_handle))
+}
+
+pub fn create_profile(
+    app_handle: &AppHandle,
+    name: &str,
+    copy_from: Option<&str>,
+) -> Result<(), String> {
+    create_profile_in(&paths::data_root(app_handle), name, copy_from)
+}
+
+pub fn delete_profile(app_handle: &AppHandle, name: &str) -> Result<(), String> {
+    delete_profile_in(
+        &paths::data_root(app_handle),
+        name,
+        &active_profile(app_handle),
+    )
+}
+
+/// Make `name` the active profile. Refused while downloads are running, since they
+/// write to the caches and queue of the profile they started in.
+pub fn switch_profile(app_handle: &AppHandle, name: &str) -> Result<(), String> {
+    let data_root = paths::data_root(app_handle);
+    if !profile_exists_in(&data_root, name) {
+        return Err(format!("Profile \"{}\" does not exist", name));
+    }
+    if crate::core::downloader::DOWNLOAD_CONTROLLER.has_tasks() {
+        return Err(
+            "Can't switch profiles while downloads are running; wait for them or cancel them first"
+                .to_string(),
+        );
+    }
+    crate::core::java::persistence::write_file_atomic(
+        &data_root.join(ACTIVE_PROFILE_FILE),
+        name.as_bytes(),
+    )
+    .map_err(|e| e.to_string())?;
+    *ACTIVE_PROFILE.write().unwrap() = Some(name.to_string());
+    crate::core::java::forget_detected_installations();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn profiles_are_created_from_a_copy_and_listed() {
+        let dir = TestDir::new("profiles");
+        let root = dir.path();
+        std::fs::write(
+            root.join("java_config.json"),
+            br#"{"user_defined_paths":[]}"#,
+        )
+        .unwrap();
+
+        create_profile_in(root, "testing", Some(DEFAULT_PROFILE)).unwrap();
+        create_profile_in(root, "stable", None).unwrap();
+        assert_eq!(
+            std::fs::read(root.join("profiles/testing/java_config.json")).unwrap(),
+            br#"{"user_defined_paths":[]}"#
+        );
+        assert!(!root.join("profiles/stable/java_config.json").exists());
+
+        let names: Vec<_> = list_profiles_in(root, "stable")
+            .into_iter()
+            .map(|p| (p.name, p.active))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("default".to_string(), false),
+                ("stable".to_string(), true),
+                ("testing".to_string(), false),
+            ]
+        );
+
+        assert!(create_profile_in(root, "testing", None).is_err());
+        assert!(create_profile_in(root, "../escape", None).is_err());
+        assert!(create_profile_in(root, "other", Some("missing")).is_err());
+    }
+
+    #[test]
+    fn active_profile_falls_back_to_default() {
+        let dir = TestDir::new("profiles");
+        let root = dir.path();
+        assert_eq!(read_active_profile(root), DEFAULT_PROFILE);
+
+        create_profile_in(root, "testing", None).unwrap();
+        std::fs::write(root.join(ACTIVE_PROFILE_FILE), "testing\n").unwrap();
+        assert_eq!(read_active_profile(root), "testing");
+        assert_eq!(
+            profile_dir_in(root, "testing"),
+            root.join("profiles/testing")
+        );
+        assert_eq!(profile_dir_in(root, DEFAULT_PROFILE), root);
+
+        assert!(delete_profile_in(root, "testing", "testing").is_err());
+        assert!(delete_profile_in(root, DEFAULT_PROFILE, "testing").is_err());
+        delete_profile_in(root, "testing", DEFAULT_PROFILE).unwrap();
+        assert_eq!(read_active_profile(root), DEFAULT_PROFILE);
+    }
+}
@@ -0,0 +1,281 @@
+//! Quick Play: starting the game straight into a world, a server or a Realm.
+//!
+//! Versions from 1.20 on list `--quickPlaySingleplayer`, `--quickPlayMultiplayer` and
+//! `--quickPlayRealms` in their version JSON, behind feature rules. Versions from 1.6
+//! on only take `--server`/`--port`, so they can join a server but not open a world;
+//! older ones always start at the title screen.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use ts_rs::TS;
+
+use crate::core::game_version::{GameVersion, Rule};
+
+/// Port a server address without one is joined on
+pub const DEFAULT_SERVER_PORT: u16 = 25565;
+
+/// Where the game goes once it has started
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[ts(export, export_to = "quick_play.ts")]
+pub enum QuickPlay {
+    /// Folder name of the world in `saves`
+    Singleplayer {
+        world: String,
+    },
+    /// `host`, `host:port` or `[ipv6]:port`
+    Multiplayer {
+        address: String,
+    },
+    Realms {
+        id: String,
+    },
+}
+
+impl QuickPlay {
+    /// Feature the version JSON puts the argument for this target behind
+    pub fn feature(&self) -> &'static str {
+        match self {
+            Self::Singleplayer { .. } => "is_quick_play_singleplayer",
+            Self::Multiplayer { .. } => "is_quick_play_multiplayer",
+            Self::Realms { .. } => "is_quick_play_realms",
+        }
+    }
+
+    /// Placeholder the argument takes the target from, without `${}`
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            Self::Singleplayer { .. } => "quickPlaySingleplayer",
+            Self::Multiplayer { .. } => "quickPlayMultiplayer",
+            Self::Realms { .. } => "quickPlayRealms",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Singleplayer { world } => world,
+            Self::Multiplayer { address } => address,
+            Self::Realms { id } => id,
+        }
+    }
+
+    /// Check there is somewhere to go, and for a server that its address parses
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Singleplayer { world } if world.trim().is_empty() => {
+                Err("Quick Play needs a world to open".to_string())
+            }
+            Self::Multiplayer { address } => parse_server_address(address).map(|_| ()),
+            Self::Realms { id } if id.trim().is_empty() => {
+                Err("Quick Play needs a Realm ID".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for QuickPlay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Singleplayer { world } => write!(f, "world \"{}\"", world),
+            Self::Multiplayer { address } => write!(f, "server {}", address),
+            Self::Realms { id } => write!(f, "Realm {}", id),
+        }
+    }
+}
+
+/// How a version can be started into a Quick Play target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    /// Through the `--quickPlay*` arguments of its version JSON
+    QuickPlay,
+    /// Through `--server` and `--port`
+    LegacyServer,
+    Unsupported,
+}
+
+/// How `version` can go to `target`
+pub fn support(version: &GameVersion, target: &QuickPlay) -> Support {
+    let game = version.arguments.as_ref().and_then(|a| a.game.as_ref());
+    if game.is_some_and(|game| has_feature_rule(game, target.feature())) {
+        return Support::QuickPlay;
+    }
+    // 1.6 and later parse `--name value` options; before that the arguments were
+    // positional, starting with the player name
+    let takes_options = version.arguments.is_some()
+        || version
+            .minecraft_arguments
+            .as_deref()
+            .is_some_and(|args| args.contains("--username"));
+    match target {
+        QuickPlay::Multiplayer { .. } if takes_options => Support::LegacyServer,
+        _ => Support::Unsupported,
+    }
+}
+
+/// Whether an entry of the `game` arguments has a rule on `feature`
+fn has_feature_rule(game: &serde_json::Value, feature: &str) -> bool {
+    let Some(entries) = game.as_array() else {
+        return false;
+    };
+    entries
+        .iter()
+        .filter_map(|entry| entry.get("rules"))
+        .filter_map(|rules| serde_json::from_value::<Vec<Rule>>(rules.clone()).ok())
+        .flatten()
+        .any(|rule| {
+            rule.features
+                .as_ref()
+                .and_then(|features| features.as_object())
+                .is_some_and(|features| features.contains_key(feature))
+        })
+}
+
+/// `--server` and `--port` for joining `address`
+pub fn legacy_server_args(address: &str) -> Result<Vec<String>, String> {
+    let (host, port) = parse_server_address(address)?;
+    Ok(vec![
+        "--server".to_string(),
+        host,
+        "--port".to_string(),
+        port.to_string(),
+    ])
+}
+
+/// Split a server address into host and port, the port defaulting to
+/// [`DEFAULT_SERVER_PORT`]. IPv6 addresses take a port only in brackets.
+pub fn parse_server_address(address: &str) -> Result<(String, u16), String> {
+    let trimmed = address.trim();
+    let invalid = || format!("Invalid server address \"{}\"", address);
+    let (host, port) = match trimmed.strip_prefix('[') {
+        Some(rest) => {
+            let (host, after) = rest.split_once(']').ok_or_else(invalid)?;
+            match after {
+                "" => (host, None),
+                _ => (host, Some(after.strip_prefix(':').ok_or_else(invalid)?)),
+            }
+        }
+        None => match trimmed.rsplit_once(':') {
+            // Several colons without brackets: a bare IPv6 address
+            Some((host, _)) if host.contains(':') => (trimmed, None),
+            Some((host, port)) => (host, Some(port)),
+            None => (trimmed, None),
+        },
+    };
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err(invalid());
+    }
+    let port = match port {
+        Some(port) => port
+            .parse::<u16>()
+            .ok()
+            .filter(|port| *port != 0)
+            .ok_or_else(invalid)?,
+        None => DEFAULT_SERVER_PORT,
+    };
+    Ok((host.to_string(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::FeatureFlags;
+
+    fn server(address: &str) -> QuickPlay {
+        QuickPlay::Multiplayer {
+            address: address.to_string(),
+        }
+    }
+
+    #[test]
+    fn server_addresses_default_to_the_standard_port() {
+        let parse = |address| parse_server_address(address).unwrap();
+        assert_eq!(
+            parse("mc.example.com"),
+            ("mc.example.com".to_string(), 25565)
+        );
+        assert_eq!(
+            parse(" mc.example.com:25570 "),
+            ("mc.example.com".to_string(), 25570)
+        );
+        assert_eq!(parse("127.0.0.1:1"), ("127.0.0.1".to_string(), 1));
+        assert_eq!(parse("[::1]:25570"), ("::1".to_string(), 25570));
+        assert_eq!(parse("[::1]"), ("::1".to_string(), 25565));
+        assert_eq!(parse("2001:db8::1"), ("2001:db8::1".to_string(), 25565));
+
+        for bad in [
+            "",
+            ":25565",
+            "mc.example.com:",
+            "mc.example.com:0",
+            "mc.example.com:65536",
+            "mc.example.com:port",
+            "[::1",
+            "[::1]25565",
+            "mc example.com",
+        ] {
+            assert!(parse_server_address(bad).is_err(), "{:?} was accepted", bad);
+        }
+    }
+
+    #[test]
+    fn targets_need_somewhere_to_go() {
+        assert!(server("mc.example.com").validate().is_ok());
+        assert!(server("mc.example.com:x").validate().is_err());
+        let world = QuickPlay::Singleplayer {
+            world: "New World".to_string(),
+        };
+        assert!(world.validate().is_ok());
+        let empty = QuickPlay::Realms {
+            id: " ".to_string(),
+        };
+        assert_eq!(empty.validate().unwrap_err(), "Quick Play needs a Realm ID");
+    }
+
+    #[test]
+    fn targets_are_tagged_by_type() {
+        let json = serde_json::to_value(server("mc.example.com")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "multiplayer", "address": "mc.example.com"})
+        );
+        let parsed: QuickPlay = serde_json::from_value(
+            serde_json::json!({"type": "singleplayer", "world": "New World"}),
+        )
+        .unwrap();
+        assert_eq!(
+            parsed,
+            QuickPlay::Singleplayer {
+                world: "New World".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn feature_flags_pick_a_target() {
+        let flags = FeatureFlags {
+            quick_play_enabled: true,
+            quick_play_multiplayer_server: Some("mc.example.com".to_string()),
+            ..FeatureFlags::default()
+        };
+        // Singleplayer is selected but has no world, so the server is used
+        assert_eq!(flags.quick_play(), Some(server("mc.example.com")));
+
+        let world = FeatureFlags {
+            quick_play_path: Some("New World".to_string()),
+            ..flags.clone()
+        };
+        assert_eq!(
+            world.quick_play(),
+            Some(QuickPlay::Singleplayer {
+                world: "New World".to_string()
+            })
+        );
+
+        let disabled = FeatureFlags {
+            quick_play_enabled: false,
+            ..world
+        };
+        assert_eq!(disabled.quick_play(), None);
+    }
+}
@@ -1,136 +1,446 @@
-use crate::core::config::FeatureFlags;
-use crate::core::game_version::Rule;
-use std::env;
+//! The `rules` attached to libraries and arguments in version JSONs.
+//!
+//! A list of rules starts out disallowing; every rule whose conditions match sets the
+//! result to its own action, so the last matching rule wins. No rules at all means
+//! allowed. A rule matches when its `os` (name, version regex, arch) describes this
+//! machine and every listed feature has the required value for this launch.
 
-pub fn is_library_allowed(rules: &Option<Vec<Rule>>, features: Option<&FeatureFlags>) -> bool {
-    // If no rules, it's allowed by default
-    let Some(rules) = rules else {
-        return true;
-    };
+use crate::core::config::{FeatureFlags, LauncherConfig};
+use crate::core::game_version::{OsRule, Rule};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// What rules are checked against: the platform plus the options of a launch
+#[derive(Debug, Clone)]
+pub struct RuleContext {
+    /// `windows`, `osx` or `linux`, as spelled in version JSONs
+    pub os_name: String,
+    /// Matched against the `os.version` regex, e.g. `10.0` on Windows 10 and 11
+    pub os_version: String,
+    /// `std::env::consts::ARCH` style, e.g. `x86_64` or `aarch64`
+    pub arch: String,
+    /// Values of features such as `is_demo_user`; unlisted features are off
+    pub features: HashMap<String, bool>,
+}
+
+impl RuleContext {
+    /// This machine, with every feature off
+    pub fn current() -> Self {
+        RuleContext {
+            os_name: current_os_name().to_string(),
+            os_version: current_os_version().to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            features: HashMap::new(),
+        }
+    }
+
+    /// This machine, with the features a launch with `flags` turns on. Quick Play
+    /// features depend on the version and are turned on when building its arguments.
+    pub fn with_features(flags: &FeatureFlags) -> Self {
+        let features = [("is_demo_user", flags.demo_user)];
+        RuleContext {
+            features: features
+                .into_iter()
+                .map(|(name, on)| (name.to_string(), on))
+                .collect(),
+            ..RuleContext::current()
+        }
+    }
 
+    /// Context for launching with `config`; `has_custom_resolution` is on only when
+    /// the window size was changed
+    pub fn for_launch(config: &LauncherConfig) -> Self {
+        let mut context = RuleContext::with_features(&config.feature_flags);
+        context.features.insert(
+            "has_custom_resolution".to_string(),
+            config.custom_resolution().is_some(),
+        );
+        context
+    }
+
+    fn feature(&self, name: &str) -> bool {
+        self.features.get(name).copied().unwrap_or(false)
+    }
+}
+
+fn current_os_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "osx"
+    } else {
+        "linux"
+    }
+}
+
+/// Best-effort OS version, read once; empty when it can't be determined
+fn current_os_version() -> &'static str {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    VERSION.get_or_init(|| detect_os_version().unwrap_or_default())
+}
+
+#[cfg(target_os = "macos")]
+fn detect_os_version() -> Option<String> {
+    let output = std::process::Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn detect_os_version() -> Option<String> {
+    // "Microsoft Windows [Version 10.0.22631.2861]"
+    let output = std::process::Command::new("cmd")
+        .args(["/C", "ver"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let version = text.split("Version").nth(1)?;
+    Some(version.trim().trim_end_matches(']').trim().to_string())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn detect_os_version() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string())
+}
+
+/// Whether `rules` allow their library or argument in `context`
+pub fn evaluate(rules: &[Rule], context: &RuleContext) -> bool {
     if rules.is_empty() {
         return true;
     }
+    rules
+        .iter()
+        .rfind(|rule| rule_matches(rule, context))
+        .is_some_and(|rule| rule.action == "allow")
+}
+
+/// [`evaluate`] for the optional `rules` of a library
+pub fn is_library_allowed(rules: &Option<Vec<Rule>>, context: &RuleContext) -> bool {
+    rules
+        .as_deref()
+        .is_none_or(|rules| evaluate(rules, context))
+}
 
-    // Default depends on the first rule theoretically, but usually "allow" if no "disallow" matches?
-    // Actually MC logic: implicit disallow? No, implicit allow usually?
-    // Official launcher Rule logic:
-    // "Libraries are allowed unless restricted by a rule."
-    // Actually detailed logic:
-    // Check all rules. if action is "allow" and condition matches, allowed = true.
-    // if action is "disallow" and condition matches, allowed = false.
-    // Typically base state is false if rules exist? No.
-    // Let's check common pattern.
-    // Usually: [ {action: allow}, {action: disallow, os: "osx"} ]
-    // This implies base allowed, but OS X disallowed.
-    // Pattern 2: [ {action: allow, os: "osx"} ]
-    // This implies ONLY osx allowed?
-
-    // Correct logic:
-    // If rules are present, start with result = false (deny all).
-    // Loop through rules. If a rule applies (os matches), update result to (action == "allow").
-    // Wait, let's verify.
-    // If the list is [ {action: allow} ], result becomes true.
-    // If list is [ {action: allow}, {action: disallow, os: "osx"} ].
-    // On Linux: Rule 1 matches -> true. Rule 2 (osx) doesn't match -> ignore. Final: true.
-    // On OSX: Rule 1 matches -> true. Rule 2 matches -> false. Final: false.
-
-    // So: Start false. Apply rules in order.
-
-    let mut allowed = false;
-
-    for rule in rules {
-        if rule_matches(rule, features) {
-            allowed = rule.action == "allow";
+fn rule_matches(rule: &Rule, context: &RuleContext) -> bool {
+    if let Some(features) = &rule.features {
+        let Some(features) = features.as_object() else {
+            // Malformed features object
+            return false;
+        };
+        let all_match = features
+            .iter()
+            .all(|(name, required)| required.as_bool().unwrap_or(false) == context.feature(name));
+        if !all_match {
+            return false;
         }
     }
-    allowed
-}
-
-fn rule_matches(rule: &Rule, features: Option<&FeatureFlags>) -> bool {
-    // Feature-based rules: apply only if all listed features evaluate to true
-    if let Some(f) = &rule.features {
-        if let Some(map) = f.as_object() {
-            // If no feature flags provided, we cannot satisfy feature rules
-            let ctx = match features {
-                Some(ff) => ff,
-                None => return false,
-            };
-
-            for (key, val) in map.iter() {
-                let required = val.as_bool().unwrap_or(false);
-                // Map known features
-                let actual = match key.as_str() {
-                    "is_demo_user" => ctx.demo_user,
-                    "has_quick_plays_support" => ctx.quick_play_enabled,
-                    "is_quick_play_singleplayer" => {
-                        ctx.quick_play_enabled && ctx.quick_play_singleplayer
-                    }
-                    "is_quick_play_multiplayer" => {
-                        ctx.quick_play_enabled
-                            && ctx
-                                .quick_play_multiplayer_server
-                                .as_ref()
-                                .map(|s| !s.is_empty())
-                                .unwrap_or(false)
-                    }
-                    _ => false,
-                };
-                if required && !actual {
-                    return false;
-                }
-                if !required && actual {
-                    // If rule specifies feature must be false, but it's true, do not match
-                    return false;
-                }
-            }
-        } else {
-            // Malformed features object
+    rule.os.as_ref().is_none_or(|os| os_matches(os, context))
+}
+
+fn os_matches(os: &OsRule, context: &RuleContext) -> bool {
+    if let Some(name) = &os.name {
+        // Some patched JSONs spell the arch into the name, e.g. "osx-arm64"
+        let (name, arch) = match name.split_once('-') {
+            Some((name, arch)) => (name, Some(arch)),
+            None => (name.as_str(), None),
+        };
+        let name = if name == "macos" { "osx" } else { name };
+        if name != context.os_name || arch.is_some_and(|arch| !arch_matches(arch, &context.arch)) {
             return false;
         }
     }
+    if let Some(arch) = &os.arch
+        && !arch_matches(arch, &context.arch)
+    {
+        return false;
+    }
+    if let Some(version) = &os.version {
+        // An unparseable pattern can't be said to match
+        let matches = regex::Regex::new(version)
+            .map(|pattern| pattern.is_match(&context.os_version))
+            .unwrap_or(false);
+        if !matches {
+            return false;
+        }
+    }
+    true
+}
 
-    match &rule.os {
-        None => true, // No OS condition means it applies to all
-        Some(os_rule) => {
-            // Check OS name
-            if let Some(os_name) = &os_rule.name {
-                let os_match = match os_name.as_str() {
-                    "osx" | "macos" => env::consts::OS == "macos",
-                    "linux" => env::consts::OS == "linux",
-                    "windows" => env::consts::OS == "windows",
-                    _ => false, // Unknown OS name in rule
-                };
-
-                if !os_match {
-                    return false;
-                }
-            }
+/// Whether the `arch` of a rule names the architecture `current` (Rust spelling)
+fn arch_matches(arch: &str, current: &str) -> bool {
+    normalize_arch(arch) == normalize_arch(current)
+}
 
-            // Check architecture if specified
-            if let Some(arch) = &os_rule.arch {
-                let current_arch = env::consts::ARCH;
-                // Strict match: only exact architecture or known compatibility mapping
-                let compatible = match (arch.as_str(), current_arch) {
-                    ("x86_64", "x86_64") => true,
-                    ("x86", "x86") => true,
-                    ("aarch64", "aarch64") => true,
-                    // Treat "x86" not as matching x86_64 (be strict)
-                    _ => arch == current_arch,
-                };
-                if !compatible {
-                    return false;
-                }
-            }
+fn normalize_arch(arch: &str) -> &str {
+    match arch {
+        "x86_64" | "amd64" | "x64" => "x86_64",
+        "x86" | "i386" | "i686" | "32" => "x86",
+        "aarch64" | "arm64" => "aarch64",
+        "arm" | "arm32" => "arm",
+        other => other,
+    }
+}
 
-            // Check version if specified (for OS version compatibility)
-            if let Some(_version) = &os_rule.version {
-                // Version checking would require parsing OS version strings
-                // For now, we accept all versions (conservative approach)
-                // In the future, parse version and compare
-            }
+/// One entry of a `jvm` or `game` argument list that applies in a context
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgumentEntry {
+    /// The strings of the entry, before placeholder substitution
+    pub values: Vec<String>,
+    /// Whether the entry had rules; such entries are optional and can be dropped as a
+    /// whole, e.g. when a placeholder can't be filled
+    pub conditional: bool,
+}
+
+/// The entries of a `jvm` or `game` argument list that apply in `context`, in order.
+///
+/// Entries are either plain strings or `{"rules": [...], "value": "..." | [...]}`;
+/// entries whose rules can't be parsed are left out.
+pub fn applicable_arguments(
+    arguments: &serde_json::Value,
+    context: &RuleContext,
+) -> Vec<ArgumentEntry> {
+    let Some(list) = arguments.as_array() else {
+        return Vec::new();
+    };
+    let mut applicable = Vec::new();
+    for item in list {
+        if let Some(arg) = item.as_str() {
+            applicable.push(ArgumentEntry {
+                values: vec![arg.to_string()],
+                conditional: false,
+            });
+            continue;
+        }
+        let Some(object) = item.as_object() else {
+            continue;
+        };
+        let allowed = match object.get("rules") {
+            Some(rules) => serde_json::from_value::<Vec<Rule>>(rules.clone())
+                .is_ok_and(|rules| evaluate(&rules, context)),
+            None => true,
+        };
+        if !allowed {
+            continue;
+        }
+        let values = match object.get("value") {
+            Some(serde_json::Value::String(arg)) => vec![arg.clone()],
+            Some(serde_json::Value::Array(values)) => values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect(),
+            _ => continue,
+        };
+        applicable.push(ArgumentEntry {
+            values,
+            conditional: object.contains_key("rules"),
+        });
+    }
+    applicable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            true
+    fn context(os_name: &str, os_version: &str, arch: &str, features: &[&str]) -> RuleContext {
+        RuleContext {
+            os_name: os_name.to_string(),
+            os_version: os_version.to_string(),
+            arch: arch.to_string(),
+            features: features
+                .iter()
+                .map(|name| (name.to_string(), true))
+                .collect(),
         }
     }
+
+    fn flatten(entries: Vec<ArgumentEntry>) -> Vec<String> {
+        entries.into_iter().flat_map(|entry| entry.values).collect()
+    }
+
+    fn rules(json: &str) -> Vec<Rule> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn rules_from_real_version_jsons() {
+        let windows = context("windows", "10.0.22631", "x86_64", &[]);
+        let windows_32 = context("windows", "6.1.7601", "x86", &[]);
+        let mac_intel = context("osx", "10.5.8", "x86_64", &[]);
+        let mac_arm = context("osx", "14.2.1", "aarch64", &[]);
+        let linux = context("linux", "6.6.8-arch1-1", "x86_64", &[]);
+        let linux_arm = context("linux", "6.1.0-rpi7", "aarch64", &[]);
+        let demo = context("linux", "6.6.8", "x86_64", &["is_demo_user"]);
+        let resolution = context("linux", "6.6.8", "x86_64", &["has_custom_resolution"]);
+
+        // (rules, contexts that allow, contexts that disallow)
+        let cases: Vec<(&str, Vec<&RuleContext>, Vec<&RuleContext>)> = vec![
+            // No rules
+            ("[]", vec![&linux, &windows], vec![]),
+            // 1.8.9 lwjgl-platform: everywhere but osx
+            (
+                r#"[{"action": "allow"}, {"action": "disallow", "os": {"name": "osx"}}]"#,
+                vec![&linux, &windows],
+                vec![&mac_intel, &mac_arm],
+            ),
+            // 1.8.9 twitch natives: osx only
+            (
+                r#"[{"action": "allow", "os": {"name": "osx"}}]"#,
+                vec![&mac_intel, &mac_arm],
+                vec![&linux, &windows],
+            ),
+            // 1.7.10 lwjgl on Leopard: disallowed by os.version
+            (
+                r#"[{"action": "allow"}, {"action": "disallow", "os": {"name": "osx", "version": "^10\\.5\\.\\d$"}}]"#,
+                vec![&mac_arm, &linux],
+                vec![&mac_intel],
+            ),
+            // 1.13 JVM args: -Dos.name=Windows 10 only on Windows 10+
+            (
+                r#"[{"action": "allow", "os": {"name": "windows", "version": "^10\\."}}]"#,
+                vec![&windows],
+                vec![&windows_32, &linux],
+            ),
+            // 1.13 JVM args: -Xss1M on 32-bit
+            (
+                r#"[{"action": "allow", "os": {"arch": "x86"}}]"#,
+                vec![&windows_32],
+                vec![&windows, &linux],
+            ),
+            // 1.19+ natives-linux-arm64 in patched JSONs
+            (
+                r#"[{"action": "allow", "os": {"name": "linux-arm64"}}]"#,
+                vec![&linux_arm],
+                vec![&linux, &mac_arm],
+            ),
+            // 1.20 game args: --demo
+            (
+                r#"[{"action": "allow", "features": {"is_demo_user": true}}]"#,
+                vec![&demo],
+                vec![&linux, &resolution],
+            ),
+            // 1.20 game args: --width/--height
+            (
+                r#"[{"action": "allow", "features": {"has_custom_resolution": true}}]"#,
+                vec![&resolution],
+                vec![&linux, &demo],
+            ),
+            // A feature required to be off
+            (
+                r#"[{"action": "allow", "features": {"is_demo_user": false}}]"#,
+                vec![&linux],
+                vec![&demo],
+            ),
+            // Malformed
+            (
+                r#"[{"action": "allow", "features": true}]"#,
+                vec![],
+                vec![&linux],
+            ),
+        ];
+
+        for (json, allowed, disallowed) in cases {
+            let rules = rules(json);
+            for context in allowed {
+                assert!(
+                    evaluate(&rules, context),
+                    "{} should allow {:?}",
+                    json,
+                    context
+                );
+            }
+            for context in disallowed {
+                assert!(
+                    !evaluate(&rules, context),
+                    "{} should disallow {:?}",
+                    json,
+                    context
+                );
+            }
+        }
+        assert!(is_library_allowed(&None, &linux));
+    }
+
+    #[test]
+    fn conditional_arguments_keep_order_and_skip_what_does_not_apply() {
+        // Trimmed from the 1.20.4 version JSON
+        let jvm: serde_json::Value = serde_json::from_str(
+            r#"[
+                {"rules": [{"action": "allow", "os": {"name": "osx"}}], "value": ["-XstartOnFirstThread"]},
+                {"rules": [{"action": "allow", "os": {"name": "windows"}}], "value": "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump"},
+                {"rules": [{"action": "allow", "os": {"arch": "x86"}}], "value": "-Xss1M"},
+                "-Djava.library.path=${natives_directory}",
+                "-cp",
+                "${classpath}",
+                {"rules": "broken", "value": "-Dnever"}
+            ]"#,
+        )
+        .unwrap();
+        let game: serde_json::Value = serde_json::from_str(
+            r#"[
+                "--username", "${auth_player_name}",
+                {"rules": [{"action": "allow", "features": {"is_demo_user": true}}], "value": "--demo"},
+                {"rules": [{"action": "allow", "features": {"has_custom_resolution": true}}], "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]}
+            ]"#,
+        )
+        .unwrap();
+
+        let mac = context("osx", "14.2.1", "aarch64", &["has_custom_resolution"]);
+        assert_eq!(
+            flatten(applicable_arguments(&jvm, &mac)),
+            vec![
+                "-XstartOnFirstThread",
+                "-Djava.library.path=${natives_directory}",
+                "-cp",
+                "${classpath}"
+            ]
+        );
+        let game_entries = applicable_arguments(&game, &mac);
+        assert!(game_entries.last().unwrap().conditional);
+        assert!(!game_entries[0].conditional);
+        assert_eq!(
+            flatten(game_entries),
+            vec![
+                "--username",
+                "${auth_player_name}",
+                "--width",
+                "${resolution_width}",
+                "--height",
+                "${resolution_height}"
+            ]
+        );
+
+        let windows_32 = context("windows", "10.0", "x86", &["is_demo_user"]);
+        assert_eq!(flatten(applicable_arguments(&jvm, &windows_32)).len(), 5);
+        assert_eq!(
+            flatten(applicable_arguments(&game, &windows_32)),
+            vec!["--username", "${auth_player_name}", "--demo"]
+        );
+    }
+
+    #[test]
+    fn feature_flags_turn_on_matching_features() {
+        let flags = FeatureFlags {
+            demo_user: true,
+            quick_play_enabled: true,
+            quick_play_multiplayer_server: Some("mc.example.com".to_string()),
+            ..FeatureFlags::default()
+        };
+        let context = RuleContext::with_features(&flags);
+        assert!(context.feature("is_demo_user"));
+        // Left to the game arguments, which know whether the version has Quick Play
+        assert!(!context.feature("has_quick_plays_support"));
+        assert!(!context.feature("is_quick_play_multiplayer"));
+        assert!(!context.feature("has_custom_resolution"));
+
+        let mut config = LauncherConfig::default();
+        assert!(!RuleContext::for_launch(&config).feature("has_custom_resolution"));
+        config.width = 1920;
+        config.height = 1080;
+        assert!(RuleContext::for_launch(&config).feature("has_custom_resolution"));
+    }
 }
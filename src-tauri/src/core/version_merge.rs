@@ -6,13 +6,15 @@
 //! their parent versions to create a complete, launchable version profile.
 
 use crate::core::game_version::{Arguments, GameVersion};
+use std::collections::HashSet;
 use std::error::Error;
 
 /// Merge a child version (mod loader) with its parent version (vanilla).
 ///
 /// The merging follows these rules:
 /// 1. Child's `mainClass` overrides parent's
-/// 2. Child's libraries are prepended to parent's (mod loader classes take priority)
+/// 2. Child's libraries are prepended to parent's (mod loader classes take priority);
+///    a parent library the child also declares, in any version, is dropped
 /// 3. Arguments are merged (child's additions come after parent's)
 /// 4. Parent provides `downloads`, `assetIndex`, `javaVersion` if child doesn't have them
 ///
@@ -23,9 +25,20 @@ use std::error::Error;
 /// # Returns
 /// A merged `GameVersion` that can be used for launching.
 pub fn merge_versions(child: GameVersion, parent: GameVersion) -> GameVersion {
-    // Libraries: child libraries first (mod loader takes priority in classpath)
+    // Libraries: child libraries first (mod loader takes priority in classpath),
+    // and a parent library the child overrides (e.g. a newer ASM) is left out
+    let child_keys: HashSet<String> = child
+        .libraries
+        .iter()
+        .map(|lib| library_key(&lib.name))
+        .collect();
     let mut merged_libraries = child.libraries;
-    merged_libraries.extend(parent.libraries);
+    merged_libraries.extend(
+        parent
+            .libraries
+            .into_iter()
+            .filter(|lib| !child_keys.contains(&library_key(&lib.name))),
+    );
 
     // Arguments: merge both game and JVM arguments
     let merged_arguments = merge_arguments(child.arguments, parent.arguments);
@@ -53,6 +66,15 @@ pub fn merge_versions(child: GameVersion, parent: GameVersion) -> GameVersion {
     }
 }
 
+/// A library's maven coordinate without its version, `group:artifact[:classifier]`
+fn library_key(name: &str) -> String {
+    let mut parts: Vec<&str> = name.split(':').collect();
+    if parts.len() >= 3 {
+        parts.remove(2);
+    }
+    parts.join(":")
+}
+
 /// Merge argument objects from child and parent versions.
 ///
 /// Both game and JVM arguments are merged, with parent arguments coming first
@@ -149,6 +171,7 @@ mod tests {
             rules: None,
             natives: None,
             url: None,
+            extract: None,
         }
     }
 
@@ -243,4 +266,117 @@ mod tests {
         assert!(needs_inheritance_resolution(&with_inheritance));
         assert!(!needs_inheritance_resolution(&without_inheritance));
     }
+
+    /// Trimmed copy of Mojang's 1.8.9 JSON, hashes blanked: legacy `minecraftArguments`
+    const VANILLA_1_8_9: &str = r#"{
+        "id": "1.8.9",
+        "type": "release",
+        "assets": "1.8",
+        "mainClass": "net.minecraft.client.main.Main",
+        "minecraftArguments": "--username ${auth_player_name} --version ${version_name} --gameDir ${game_directory} --assetsDir ${assets_root} --assetIndex ${assets_index_name} --uuid ${auth_uuid} --accessToken ${auth_access_token} --userProperties ${user_properties} --userType ${user_type}",
+        "javaVersion": {"component": "jre-legacy", "majorVersion": 8},
+        "assetIndex": {"id": "1.8", "sha1": "0000000000000000000000000000000000000000", "size": 78494, "totalSize": 114885064, "url": "https://launchermeta.mojang.com/v1/packages/0000000000000000000000000000000000000000/1.8.json"},
+        "downloads": {"client": {"sha1": "0000000000000000000000000000000000000000", "size": 8461484, "url": "https://launcher.mojang.com/v1/objects/0000000000000000000000000000000000000000/client.jar"}},
+        "libraries": [
+            {"name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209", "natives": {"linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows"}, "rules": [{"action": "allow"}, {"action": "disallow", "os": {"name": "osx"}}]}
+        ]
+    }"#;
+
+    /// Trimmed copy of Mojang's 1.20.4 JSON, hashes blanked: rule-based `arguments`
+    const VANILLA_1_20_4: &str = r#"{
+        "id": "1.20.4",
+        "type": "release",
+        "assets": "12",
+        "mainClass": "net.minecraft.client.main.Main",
+        "javaVersion": {"component": "java-runtime-gamma", "majorVersion": 17},
+        "arguments": {
+            "game": ["--username", "${auth_player_name}", {"rules": [{"action": "allow", "features": {"is_demo_user": true}}], "value": "--demo"}],
+            "jvm": [{"rules": [{"action": "allow", "os": {"name": "osx"}}], "value": ["-XstartOnFirstThread"]}, "-cp", "${classpath}"]
+        },
+        "assetIndex": {"id": "12", "sha1": "0000000000000000000000000000000000000000", "size": 441479, "totalSize": 624942043, "url": "https://piston-meta.mojang.com/v1/packages/0000000000000000000000000000000000000000/12.json"},
+        "downloads": {"client": {"sha1": "0000000000000000000000000000000000000000", "size": 24445539, "url": "https://piston-data.mojang.com/v1/objects/0000000000000000000000000000000000000000/client.jar"}},
+        "libraries": [
+            {"name": "org.ow2.asm:asm:9.3", "downloads": {"artifact": {"path": "org/ow2/asm/asm/9.3/asm-9.3.jar", "sha1": "0000000000000000000000000000000000000000", "size": 122004, "url": "https://libraries.minecraft.net/org/ow2/asm/asm/9.3/asm-9.3.jar"}}},
+            {"name": "org.lwjgl:lwjgl:3.3.2", "downloads": {"artifact": {"path": "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2.jar", "sha1": "0000000000000000000000000000000000000000", "size": 786440, "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2.jar"}}},
+            {"name": "org.lwjgl:lwjgl:3.3.2:natives-linux", "downloads": {"artifact": {"path": "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-linux.jar", "sha1": "0000000000000000000000000000000000000000", "size": 110704, "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-linux.jar"}}, "rules": [{"action": "allow", "os": {"name": "linux"}}]}
+        ]
+    }"#;
+
+    /// Trimmed Fabric loader profile for 1.20.4
+    const FABRIC_1_20_4: &str = r#"{
+        "id": "fabric-loader-0.15.7-1.20.4",
+        "inheritsFrom": "1.20.4",
+        "type": "release",
+        "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        "arguments": {"game": [], "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "]},
+        "libraries": [
+            {"name": "org.ow2.asm:asm:9.6", "url": "https://maven.fabricmc.net/"},
+            {"name": "net.fabricmc:fabric-loader:0.15.7", "url": "https://maven.fabricmc.net/"}
+        ]
+    }"#;
+
+    fn parse(json: &str) -> GameVersion {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn legacy_and_modern_version_jsons_parse() {
+        let legacy = parse(VANILLA_1_8_9);
+        assert!(legacy.arguments.is_none());
+        assert!(
+            legacy
+                .minecraft_arguments
+                .as_deref()
+                .unwrap()
+                .starts_with("--username ${auth_player_name}")
+        );
+        assert_eq!(legacy.java_version.unwrap().major_version, 8);
+        assert!(legacy.libraries[0].natives.is_some());
+
+        let modern = parse(VANILLA_1_20_4);
+        assert!(modern.minecraft_arguments.is_none());
+        let arguments = modern.arguments.unwrap();
+        assert_eq!(arguments.game.unwrap().as_array().unwrap().len(), 3);
+        assert_eq!(arguments.jvm.unwrap().as_array().unwrap().len(), 3);
+        assert_eq!(modern.java_version.unwrap().major_version, 17);
+        assert_eq!(modern.asset_index.unwrap().id, "12");
+    }
+
+    #[tokio::test]
+    async fn fabric_profile_resolves_onto_its_parent() {
+        let merged = resolve_inheritance(parse(FABRIC_1_20_4), |id| async move {
+            assert_eq!(id, "1.20.4");
+            Ok(parse(VANILLA_1_20_4))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(merged.id, "fabric-loader-0.15.7-1.20.4");
+        assert_eq!(
+            merged.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+        assert!(merged.inherits_from.is_none());
+        assert_eq!(merged.java_version.unwrap().major_version, 17);
+        assert_eq!(merged.assets.as_deref(), Some("12"));
+        assert!(merged.downloads.is_some());
+
+        // Fabric's ASM replaces the vanilla one; natives keep their own entry
+        let names: Vec<&str> = merged.libraries.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "org.ow2.asm:asm:9.6",
+                "net.fabricmc:fabric-loader:0.15.7",
+                "org.lwjgl:lwjgl:3.3.2",
+                "org.lwjgl:lwjgl:3.3.2:natives-linux",
+            ]
+        );
+
+        // Parent arguments first, then the loader's
+        let jvm = merged.arguments.unwrap().jvm.unwrap();
+        let jvm = jvm.as_array().unwrap();
+        assert_eq!(jvm.len(), 4);
+        assert_eq!(jvm[3], "-DFabricMcEmu= net.minecraft.client.main.Main ");
+    }
 }
@@ -2,18 +2,11 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use serde::{Deserialize, Serialize};
-use std::process::Stdio;
 use std::sync::Mutex;
 use tauri::{Emitter, Manager, State, Window}; // Added Emitter
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
-use tokio::sync::Mutex as AsyncMutex;
 use tokio::time::{Duration, sleep};
 use ts_rs::TS; // Added Serialize
 
-#[cfg(target_os = "windows")]
-use std::os::windows::process::CommandExt;
-
 /// Helper macro to emit launcher log events
 macro_rules! emit_log {
     ($window:expr, $msg:expr) => {
@@ -44,59 +37,34 @@ impl MsRefreshTokenState {
     }
 }
 
-struct RunningGameProcess {
-    child: Child,
-    instance_id: String,
-    version_id: String,
-}
-
-pub struct GameProcessState {
-    running_game: AsyncMutex<Option<RunningGameProcess>>,
-}
-
-impl Default for GameProcessState {
-    fn default() -> Self {
-        Self::new()
+/// Log every failed download and turn an unsuccessful batch into an error
+fn check_download_report(
+    window: &Window,
+    report: &core::downloader::DownloadBatchReport,
+) -> Result<(), String> {
+    if report.is_success() {
+        return Ok(());
     }
-}
-
-impl GameProcessState {
-    pub fn new() -> Self {
-        Self {
-            running_game: AsyncMutex::new(None),
-        }
+    for failure in &report.failed {
+        emit_log!(
+            window,
+            format!(
+                "Failed to download {}: {}",
+                failure.task.url,
+                utils::error::display_chain(&failure.error)
+            )
+        );
     }
-}
-
-#[derive(Debug, Clone, Serialize, TS)]
-#[serde(rename_all = "camelCase")]
-#[ts(export, export_to = "core.ts")]
-struct GameExitedEvent {
-    instance_id: String,
-    version_id: String,
-    exit_code: Option<i32>,
-    was_stopped: bool,
-}
-
-/// Check if a string contains unresolved placeholders in the form ${...}
-///
-/// After the replacement phase, if a string still contains ${...}, it means
-/// that placeholder variable was not found in the replacements map and is
-/// therefore unresolved. We should skip adding such arguments to avoid
-/// passing malformed arguments to the game launcher.
-fn has_unresolved_placeholder(s: &str) -> bool {
-    // Look for the opening sequence
-    if let Some(start_pos) = s.find("${") {
-        // Check if there's a closing brace after the opening sequence
-        if s[start_pos + 2..].find('}').is_some() {
-            // Found a complete ${...} pattern - this is an unresolved placeholder
-            return true;
-        }
-        // Found ${ but no closing } - also treat as unresolved/malformed
-        return true;
+    if !report.failed.is_empty() {
+        return Err(format!(
+            "{} file(s) failed to download",
+            report.failed.len()
+        ));
     }
-    // No ${ found - the string is fully resolved
-    false
+    Err(format!(
+        "Downloads cancelled with {} file(s) remaining",
+        report.paused.len() + report.cancelled.len()
+    ))
 }
 
 fn resolve_minecraft_version(version_id: &str) -> String {
@@ -122,6 +90,74 @@ fn resolve_minecraft_version(version_id: &str) -> String {
     }
 }
 
+/// Java major version bounds for launching a game version, from the `javaVersion` of
+/// the version file (after merging with its parent) and what is known about its
+/// Minecraft version
+fn java_version_bounds(
+    minecraft_version: &str,
+    version_details: &core::game_version::GameVersion,
+) -> (Option<u64>, Option<u32>) {
+    let requirement = core::java::requirements::required_java_for_minecraft(
+        minecraft_version,
+        version_details
+            .java_version
+            .as_ref()
+            .map(|jv| jv.major_version as u32),
+    );
+    (
+        Some(u64::from(requirement.min_major)),
+        requirement.recommended_max_major,
+    )
+}
+
+/// The Java override of `instance`, first moving the Java path instances used to keep
+/// themselves into one
+fn instance_java_override(
+    app_handle: &tauri::AppHandle,
+    instance: &core::instance::Instance,
+) -> Result<Option<core::java::persistence::InstanceJavaOverride>, String> {
+    match core::java::persistence::get_instance_java_override(app_handle, &instance.id) {
+        None if instance
+            .java_path_override
+            .as_deref()
+            .is_some_and(|p| !p.is_empty()) =>
+        {
+            let java_override = core::java::persistence::InstanceJavaOverride {
+                path: instance.java_path_override.clone(),
+                ..Default::default()
+            };
+            core::java::persistence::set_instance_java_override(
+                app_handle,
+                &instance.id,
+                java_override.clone(),
+            )?;
+            Ok(Some(java_override))
+        }
+        java_override => Ok(java_override),
+    }
+}
+
+/// Resolve the Java for launching `instance_id` within the given version bounds; both
+/// `resolve_java` and `start_game` go through here, so they always choose alike
+async fn resolve_java_within(
+    app_handle: &tauri::AppHandle,
+    config: &core::config::LauncherConfig,
+    instance_id: Option<&str>,
+    required_major_version: Option<u64>,
+    max_major_version: Option<u32>,
+) -> core::java::priority::JavaResolution {
+    core::java::priority::resolve_java_for_launch(
+        app_handle,
+        core::java::priority::JavaRequest {
+            instance_id,
+            global_java_path: Some(&config.java_path),
+            required_major_version,
+            max_major_version,
+        },
+    )
+    .await
+}
+
 #[tauri::command]
 #[dropout_macros::api]
 async fn start_game(
@@ -129,11 +165,15 @@ async fn start_game(
     auth_state: State<'_, core::auth::AccountState>,
     config_state: State<'_, core::config::ConfigState>,
     assistant_state: State<'_, core::assistant::AssistantState>,
-    game_process_state: State<'_, GameProcessState>,
+    game_process_state: State<'_, core::game_process::GameProcessState>,
     instance_state: State<'_, core::instance::InstanceState>,
     instance_id: String,
     version_id: String,
-) -> Result<String, String> {
+    // Launch with this saved account instead of the active one
+    account_id: Option<String>,
+    // Start in this world or server instead of the instance's default
+    quick_play: Option<core::quick_play::QuickPlay>,
+) -> Result<core::game_process::LaunchHandle, String> {
     emit_log!(
         window,
         format!(
@@ -142,66 +182,60 @@ async fn start_game(
         )
     );
 
-    let stale_instance_to_unlock = {
-        let mut running_game = game_process_state.running_game.lock().await;
-
-        if let Some(existing_game) = running_game.as_mut() {
-            match existing_game.child.try_wait() {
-                Ok(Some(status)) => {
-                    emit_log!(
-                        window,
-                        format!(
-                            "Clearing stale game process for instance {} (exit code: {:?})",
-                            existing_game.instance_id,
-                            status.code()
-                        )
-                    );
-                    let stale_instance_id = existing_game.instance_id.clone();
-                    *running_game = None;
-                    Some(stale_instance_id)
-                }
-                Ok(None) => {
-                    return Err(format!(
-                        "A game is already running for instance {}",
-                        existing_game.instance_id
-                    ));
-                }
-                Err(error) => {
-                    emit_log!(
-                        window,
-                        format!(
-                            "Clearing broken game process state for instance {}: {}",
-                            existing_game.instance_id, error
-                        )
-                    );
-                    let stale_instance_id = existing_game.instance_id.clone();
-                    *running_game = None;
-                    Some(stale_instance_id)
-                }
-            }
-        } else {
-            None
+    match game_process_state.poll(&instance_id).await {
+        core::game_process::GameStatus::NotRunning => {}
+        core::game_process::GameStatus::Running => {
+            return Err(format!(
+                "A game is already running for instance {}",
+                instance_id
+            ));
+        }
+        core::game_process::GameStatus::Exited(game, status) => {
+            emit_log!(
+                window,
+                format!(
+                    "Clearing stale game process for instance {} (exit code: {:?})",
+                    instance_id,
+                    status.code()
+                )
+            );
+            core::game_process::finish_game(&instance_id, &game, Some(&status), false);
+            instance_state.end_operation(&instance_id);
+        }
+        core::game_process::GameStatus::Failed(game, error) => {
+            emit_log!(
+                window,
+                format!(
+                    "Clearing broken game process state for instance {}: {}",
+                    instance_id, error
+                )
+            );
+            core::game_process::finish_game(&instance_id, &game, None, false);
+            instance_state.end_operation(&instance_id);
         }
-    };
-
-    if let Some(stale_instance_id) = stale_instance_to_unlock {
-        instance_state.end_operation(&stale_instance_id);
     }
 
-    // Check for active account
+    // The chosen account, or else the active one
     emit_log!(window, "Checking for active account...".to_string());
-    let mut account = auth_state
-        .active_account
-        .lock()
-        .unwrap()
-        .clone()
-        .ok_or("No active account found. Please login first.")?;
+    let active_account = auth_state.active_account.lock().unwrap().clone();
+    let is_active = account_id.is_none() || active_account.as_ref().map(|a| a.uuid()) == account_id;
+    let mut account = match &account_id {
+        Some(account_id) if !is_active => {
+            let app_dir = core::paths::data_root(window.app_handle());
+            core::account_storage::AccountStorage::new(app_dir)
+                .get_account(account_id)
+                .map(|stored| stored.to_account())
+                .ok_or_else(|| format!("Account {} not found", account_id))?
+        }
+        _ => active_account.ok_or("No active account found. Please login first.")?,
+    };
 
     // Check if Microsoft account token is expired and refresh if needed
     if let core::auth::Account::Microsoft(ms_account) = &account {
         if core::auth::is_token_expired(ms_account.expires_at) {
             emit_log!(window, "Token expired, refreshing...".to_string());
             match core::auth::refresh_full_auth(
+                &core::auth::AuthEndpoints::default(),
                 &ms_account
                     .refresh_token
                     .clone()
@@ -209,9 +243,19 @@ async fn start_game(
             )
             .await
             {
-                Ok((refreshed_account, _new_ms_refresh)) => {
+                Ok((refreshed_account, new_ms_refresh)) => {
                     let refreshed_account = core::auth::Account::Microsoft(refreshed_account);
-                    *auth_state.active_account.lock().unwrap() = Some(refreshed_account.clone());
+                    // Keep the new tokens, the old refresh token may no longer work
+                    let app_dir = core::paths::data_root(window.app_handle());
+                    if let Err(e) = core::account_storage::AccountStorage::new(app_dir)
+                        .update_account(&refreshed_account, Some(new_ms_refresh))
+                    {
+                        emit_log!(window, format!("Failed to save refreshed account: {}", e));
+                    }
+                    if is_active {
+                        *auth_state.active_account.lock().unwrap() =
+                            Some(refreshed_account.clone());
+                    }
                     account = refreshed_account;
                     emit_log!(window, "Token refreshed successfully".to_string());
                 }
@@ -232,7 +276,7 @@ async fn start_game(
     let app_handle = window.app_handle();
     instance_state.begin_operation(&instance_id, core::instance::InstanceOperation::Launch)?;
 
-    let launch_result: Result<String, String> = async {
+    let launch_result: Result<core::game_process::LaunchHandle, String> = async {
     emit_log!(window, format!("Java path: {}", config.java_path));
     emit_log!(
         window,
@@ -240,7 +284,8 @@ async fn start_game(
     );
 
     let resolved_paths = instance_state.resolve_paths(&instance_id, &config, &app_handle)?;
-    let game_dir = resolved_paths.root.clone();
+    let instance_dir = resolved_paths.root.clone();
+    let game_dir = resolved_paths.game_dir.clone();
 
     // Ensure game directory exists
     tokio::fs::create_dir_all(&game_dir)
@@ -255,15 +300,11 @@ async fn start_game(
         format!("Loading version details for {}...", version_id)
     );
 
-    // First, load the local version to get the original inheritsFrom value
-    // (before merge clears it)
-    let original_inherits_from =
-        match core::manifest::load_local_version(&game_dir, &version_id).await {
-            Ok(local_version) => local_version.inherits_from.clone(),
-            Err(_) => None,
-        };
+    // Determine the actual minecraft version for client.jar
+    // (for modded versions, this is the parent vanilla version)
+    let minecraft_version = core::manifest::minecraft_version(&instance_dir, &version_id).await;
 
-    let version_details = core::manifest::load_version(&game_dir, &version_id)
+    let version_details = core::manifest::load_version(&instance_dir, &version_id)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -275,76 +316,75 @@ async fn start_game(
         )
     );
 
-    // Determine the actual minecraft version for client.jar
-    // (for modded versions, this is the parent vanilla version)
-    let minecraft_version = original_inherits_from.unwrap_or_else(|| version_id.clone());
-
-    // Get required Java version from version file's javaVersion field
-    // The version file (after merging with parent) should contain the correct javaVersion
-    let required_java_major = version_details
-        .java_version
-        .as_ref()
-        .map(|jv| jv.major_version);
-
-    // For older Minecraft versions (1.13.x and below), if javaVersion specifies Java 8,
-    // we should only allow Java 8 (not higher) due to compatibility issues with old Forge
-    // For newer versions, javaVersion.majorVersion is the minimum required version
-    let max_java_major = if let Some(required) = required_java_major {
-        // If version file specifies Java 8, enforce it as maximum (old versions need exactly Java 8)
-        // For Java 9+, allow that version or higher
-        if required <= 8 {
-            Some(8)
-        } else {
-            None // No upper bound for Java 9+
-        }
-    } else {
+    let (required_java_major, max_java_major) =
+        java_version_bounds(&minecraft_version, &version_details);
+    if version_details.java_version.is_none() {
         // If version file doesn't specify javaVersion, this shouldn't happen for modern versions
-        // But if it does, we can't determine compatibility - log a warning
+        // But if it does, fall back to what is known about the Minecraft version
         emit_log!(
             window,
-            "Warning: Version file does not specify javaVersion. Using system default Java."
-                .to_string()
+            format!(
+                "Warning: Version file does not specify javaVersion. Using the Java requirement known for Minecraft {}.",
+                minecraft_version
+            )
         );
-        None
-    };
+    }
 
-    // Resolve Java using priority-based resolution
-    // Priority: instance override > global config > user preference > auto-detect
-    // TODO: refactor into a separate function
+    // Resolve Java the same way `resolve_java` does, in the order of the Java settings
     let instance = instance_state
         .get_instance(&instance_id)
         .ok_or_else(|| format!("Instance {} not found", instance_id))?;
-
-    let java_installation = core::java::priority::resolve_java_for_launch(
-        app_handle,
-        instance.java_path_override.as_deref(),
-        Some(&config.java_path),
-        required_java_major,
-        max_java_major,
-    )
-    .await
-    .ok_or_else(|| {
-        let version_constraint = if let Some(max) = max_java_major {
-            if let Some(min) = required_java_major {
-                if min == max as u64 {
-                    format!("Java {}", min)
-                } else {
-                    format!("Java {} to {}", min, max)
-                }
-            } else {
-                format!("Java {} (or lower)", max)
-            }
-        } else if let Some(min) = required_java_major {
-            format!("Java {} or higher", min)
-        } else {
-            "any Java version".to_string()
-        };
-
-        format!(
-            "No compatible Java installation found. This version requires {}. Please install a compatible Java version in settings.",
-            version_constraint
+    let java_override = instance_java_override(app_handle, &instance)?;
+
+    let resolve = || {
+        resolve_java_within(
+            app_handle,
+            &config,
+            Some(&instance_id),
+            required_java_major,
+            max_java_major,
         )
-    })?;
+    };
+    let required_major = required_java_major.unwrap_or(8) as u32;
+    let auto_install = core::java::persistence::get_java_settings(app_handle)
+        .auto_install_missing_java
+        .then_some(|| async {
+            let major_version = required_major;
+            let download_id = core::java::new_download_id();
+            emit_log!(
+                window,
+                format!(
+                    "No compatible Java found, installing Java {}...",
+                    major_version
+                )
+            );
+            let _ = window.emit(
+                "launch-java-install",
+                core::java::priority::LaunchJavaInstall {
+                    instance_id: instance_id.clone(),
+                    major_version,
+                    download_id: download_id.clone(),
+                },
+            );
+            core::java::install_java_for_launch(
+                app_handle,
+                &download_id,
+                major_version,
+                config.java_download_segment_count(),
+            )
+            .await
+            .map_err(|e| format!("Failed to install Java {}: {}", major_version, e))
+        });
+    let resolution = core::java::priority::resolve_or_install(resolve, auto_install).await?;
+    // Typed, so the UI can point at the configured Java that failed or say which
+    // Java to install
+    let java_installation = match resolution.launch_java(required_major, max_java_major) {
+        Ok(java) => java.clone(),
+        Err(error) => {
+            let _ = window.emit("launch-java-error", &error);
+            return Err(error.with_code());
+        }
+    };
 
     emit_log!(
         window,
@@ -353,204 +393,54 @@ async fn start_game(
             java_installation.version, java_installation.path
         )
     );
+    for warning in &resolution.warnings {
+        emit_log!(window, format!("Warning: {}", warning));
+    }
 
-    let java_path_to_use = java_installation.path;
+    let java_path_to_use = java_installation.path.clone();
 
     // 2. Prepare download tasks
     emit_log!(window, "Preparing download tasks...".to_string());
-    let mut download_tasks = Vec::new();
-
-    // --- Client Jar ---
-    // Get downloads from version_details (may be inherited)
-    let downloads = version_details
-        .downloads
-        .as_ref()
-        .ok_or("Version has no downloads information")?;
-    let client_jar = &downloads.client;
     let mut client_path = resolved_paths.version_cache.clone();
     client_path.push(&minecraft_version);
     client_path.push(format!("{}.jar", minecraft_version));
-
-    download_tasks.push(core::downloader::DownloadTask {
-        url: client_jar.url.clone(),
-        path: client_path.clone(),
-        sha1: client_jar.sha1.clone(),
-        sha256: None,
-    });
-
-    // --- Libraries ---
-    println!("Processing libraries...");
     let libraries_dir = resolved_paths.libraries.clone();
-    let mut native_libs_paths = Vec::new(); // Store paths to native jars for extraction
-
-    for lib in &version_details.libraries {
-        if core::rules::is_library_allowed(&lib.rules, Some(&config.feature_flags)) {
-            // 1. Standard Library - check for explicit downloads first
-            if let Some(downloads) = &lib.downloads {
-                if let Some(artifact) = &downloads.artifact {
-                    let path_str = artifact
-                        .path
-                        .clone()
-                        .unwrap_or_else(|| format!("{}.jar", lib.name));
-
-                    let mut lib_path = libraries_dir.clone();
-                    lib_path.push(path_str);
-
-                    download_tasks.push(core::downloader::DownloadTask {
-                        url: artifact.url.clone(),
-                        path: lib_path,
-                        sha1: artifact.sha1.clone(),
-                        sha256: None,
-                    });
-                }
-
-                // 2. Native Library (classifiers)
-                // e.g. "natives-linux": { ... }
-                if let Some(classifiers) = &downloads.classifiers {
-                    // Determine candidate keys based on OS and architecture
-                    let arch = std::env::consts::ARCH;
-                    let mut candidates: Vec<String> = Vec::new();
-                    if cfg!(target_os = "linux") {
-                        candidates.push("natives-linux".to_string());
-                        candidates.push(format!("natives-linux-{}", arch));
-                        if arch == "aarch64" {
-                            candidates.push("natives-linux-arm64".to_string());
-                        }
-                    } else if cfg!(target_os = "windows") {
-                        candidates.push("natives-windows".to_string());
-                        candidates.push(format!("natives-windows-{}", arch));
-                    } else if cfg!(target_os = "macos") {
-                        candidates.push("natives-osx".to_string());
-                        candidates.push("natives-macos".to_string());
-                        candidates.push(format!("natives-macos-{}", arch));
-                    }
+    let rule_context = core::rules::RuleContext::for_launch(&config);
 
-                    // Pick the first available classifier key
-                    let mut chosen: Option<core::game_version::DownloadArtifact> = None;
-                    for key in candidates {
-                        if let Some(native_artifact_value) = classifiers.get(&key) {
-                            if let Ok(artifact) =
-                                serde_json::from_value::<core::game_version::DownloadArtifact>(
-                                    native_artifact_value.clone(),
-                                )
-                            {
-                                chosen = Some(artifact);
-                                break;
-                            }
-                        }
-                    }
-
-                    if let Some(native_artifact) = chosen {
-                        let path_str = native_artifact.path.clone().unwrap(); // Natives usually have path
-                        let mut native_path = libraries_dir.clone();
-                        native_path.push(&path_str);
-
-                        download_tasks.push(core::downloader::DownloadTask {
-                            url: native_artifact.url,
-                            path: native_path.clone(),
-                            sha1: native_artifact.sha1,
-                            sha256: None,
-                        });
-
-                        native_libs_paths.push(native_path);
-                    }
-                }
-            } else {
-                // 3. Library without explicit downloads (mod loader libraries)
-                // Use Maven coordinate resolution
-                if let Some(url) =
-                    core::maven::resolve_library_url(&lib.name, None, lib.url.as_deref())
-                {
-                    if let Some(lib_path) = core::maven::get_library_path(&lib.name, &libraries_dir)
-                    {
-                        download_tasks.push(core::downloader::DownloadTask {
-                            url,
-                            path: lib_path,
-                            sha1: None, // Maven libraries often don't have SHA1 in the JSON
-                            sha256: None,
-                        });
-                    }
-                }
-            }
-        }
-    }
+    // --- Client Jar and Libraries ---
+    let mut download_tasks = core::game_files::client_and_library_tasks(
+        &version_details,
+        &client_path,
+        &libraries_dir,
+        &rule_context,
+    )?;
 
     // --- Assets ---
     println!("Fetching asset index...");
     let assets_dir = resolved_paths.assets.clone();
-    let objects_dir = assets_dir.join("objects");
-    let indexes_dir = assets_dir.join("indexes");
 
     // Get asset index (may be inherited from parent)
     let asset_index = version_details
         .asset_index
         .as_ref()
         .ok_or("Version has no asset index information")?;
-
-    // Download Asset Index JSON
-    let asset_index_path = indexes_dir.join(format!("{}.json", asset_index.id));
-
-    // Check if index exists or download it
-    // Note: We need the content of this file to parse it.
-    // If we just add it to download_tasks, we can't parse it *now*.
-    // So we must download it immediately (await) before processing objects.
-
-    let asset_index_content: String = if asset_index_path.exists() {
-        tokio::fs::read_to_string(&asset_index_path)
-            .await
-            .map_err(|e| e.to_string())?
-    } else {
-        println!("Downloading asset index from {}", asset_index.url);
-        let content = reqwest::get(&asset_index.url)
-            .await
-            .map_err(|e| e.to_string())?
-            .text()
-            .await
-            .map_err(|e| e.to_string())?;
-
-        // Save it for next time
-        tokio::fs::create_dir_all(&indexes_dir)
-            .await
-            .map_err(|e| e.to_string())?;
-        tokio::fs::write(&asset_index_path, &content)
-            .await
-            .map_err(|e| e.to_string())?;
-        content
-    };
-
-    #[derive(serde::Deserialize, Debug)]
-    struct AssetObject {
-        hash: String,
-        #[allow(dead_code)]
-        size: u64,
-    }
-
-    #[derive(serde::Deserialize, Debug)]
-    struct AssetIndexJson {
-        objects: std::collections::HashMap<String, AssetObject>,
+    let asset_index_parsed =
+        core::assets::load_asset_index(asset_index, &assets_dir.join("indexes")).await?;
+    if asset_index_parsed.is_legacy() {
+        emit_log!(
+            window,
+            format!(
+                "Warning: asset index {} uses the pre-1.7 layout, which isn't supported yet; sounds and languages may be missing",
+                asset_index.id
+            )
+        );
     }
 
-    let asset_index_parsed: AssetIndexJson =
-        serde_json::from_str(&asset_index_content).map_err(|e| e.to_string())?;
-
     println!("Processing {} assets...", asset_index_parsed.objects.len());
-
-    for (_name, object) in asset_index_parsed.objects {
-        let hash = object.hash;
-        let prefix = &hash[0..2];
-        let path = objects_dir.join(prefix).join(&hash);
-        let url = format!(
-            "https://resources.download.minecraft.net/{}/{}",
-            prefix, hash
-        );
-
-        download_tasks.push(core::downloader::DownloadTask {
-            url,
-            path,
-            sha1: Some(hash),
-            sha256: None,
-        });
-    }
+    download_tasks.extend(core::assets::asset_tasks(
+        &asset_index_parsed,
+        &assets_dir.join("objects"),
+    ));
 
     emit_log!(
         window,
@@ -568,218 +458,84 @@ async fn start_game(
             config.download_threads
         )
     );
-    core::downloader::download_files(
-        window.clone(),
-        download_tasks,
-        config.download_threads as usize,
-    )
-    .await
-    .map_err(|e| e.to_string())?;
+    let report =
+        core::game_files::download_with_settings(window.clone(), download_tasks, &config).await?;
+    check_download_report(&window, &report)?;
     emit_log!(window, "All downloads completed successfully".to_string());
 
     // 5. Extract Natives
     emit_log!(window, "Extracting native libraries...".to_string());
-    let natives_dir = game_dir.join("versions").join(&version_id).join("natives");
+    let natives_dir = instance_dir
+        .join("versions")
+        .join(&version_id)
+        .join("natives");
+
+    let extracted = core::natives::extract_natives(
+        &version_details,
+        &libraries_dir,
+        &natives_dir,
+        &rule_context,
+    )?;
+    println!("Extracted {} native jar(s) to {:?}", extracted, natives_dir);
+
+    // 6. JVM arguments, including the classpath
+    let paths = core::jvm_args::LaunchPaths {
+        libraries_dir: &libraries_dir,
+        client_jar: &client_path,
+        natives_dir: &natives_dir,
+    };
+    let mut args = core::jvm_args::build_jvm_args(
+        &config,
+        &instance,
+        java_override.as_ref(),
+        &version_details,
+        &paths,
+        &rule_context,
+    );
 
-    // Clean old natives if they exist to prevent conflicts
-    if natives_dir.exists() {
-        tokio::fs::remove_dir_all(&natives_dir)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-    tokio::fs::create_dir_all(&natives_dir)
-        .await
-        .map_err(|e| e.to_string())?;
+    // 7. Main Class
+    args.push(version_details.main_class.clone());
 
-    for path in native_libs_paths {
-        if path.exists() {
-            println!("Extracting native: {:?}", path);
-            utils::zip::extract_zip(&path, &natives_dir)?;
-        }
+    // 8. Game Arguments
+    let quick_play = quick_play
+        .or_else(|| instance.quick_play.clone())
+        .or_else(|| config.feature_flags.quick_play());
+    if let Some(target) = &quick_play {
+        target.validate()?;
     }
-
-    // 6. Construct Classpath
-    let cp_separator = if cfg!(target_os = "windows") {
-        ";"
-    } else {
-        ":"
+    let launch = core::game_args::GameLaunchContext {
+        account: &account,
+        version_name: &version_id,
+        game_dir: &game_dir,
+        assets_root: &assets_dir,
+        assets_index_name: &asset_index.id,
+        resolution: config.custom_resolution(),
+        quick_play: quick_play.as_ref(),
     };
-    let mut classpath_entries = Vec::new();
-
-    // Add libraries
-    for lib in &version_details.libraries {
-        if core::rules::is_library_allowed(&lib.rules, Some(&config.feature_flags)) {
-            if let Some(downloads) = &lib.downloads {
-                // Standard library with explicit downloads
-                if let Some(artifact) = &downloads.artifact {
-                    let path_str = artifact
-                        .path
-                        .clone()
-                        .unwrap_or_else(|| format!("{}.jar", lib.name));
-                    let lib_path = libraries_dir.join(path_str);
-                    classpath_entries.push(lib_path.to_string_lossy().to_string());
-                }
-            } else {
-                // Library without explicit downloads (mod loader libraries)
-                // Use Maven coordinate resolution
-                if let Some(lib_path) = core::maven::get_library_path(&lib.name, &libraries_dir) {
-                    classpath_entries.push(lib_path.to_string_lossy().to_string());
-                }
-            }
-        }
-    }
-    // Add client jar
-    classpath_entries.push(client_path.to_string_lossy().to_string());
-
-    let classpath = classpath_entries.join(cp_separator);
-
-    // 7. Prepare Arguments
-    let mut args = Vec::new();
-    let natives_path = natives_dir.to_string_lossy().to_string();
-
-    // 7a. JVM Arguments - Parse from version.json for full compatibility
-    // First add arguments from version.json if available
-    if let Some(args_obj) = &version_details.arguments {
-        if let Some(jvm_args) = &args_obj.jvm {
-            parse_jvm_arguments(
-                jvm_args,
-                &mut args,
-                &natives_path,
-                &classpath,
-                &config.feature_flags,
+    let game_args = core::game_args::build_game_args(&version_details, &launch, &rule_context)?;
+    if let Some(target) = &quick_play {
+        if game_args.quick_play_unsupported {
+            emit_log!(
+                window,
+                format!(
+                    "{} has no Quick Play to {}, starting at the title screen",
+                    version_id, target
+                )
             );
-        }
-    }
-
-    // Add memory settings (these override any defaults)
-    args.push(format!("-Xmx{}M", config.max_memory));
-    args.push(format!("-Xms{}M", config.min_memory));
-
-    // Ensure natives path is set if not already in jvm args
-    if !args.iter().any(|a| a.contains("-Djava.library.path")) {
-        args.push(format!("-Djava.library.path={}", natives_path));
-    }
-
-    // Add GPU acceleration parameters if not already set by user
-    // JavaFX Prism rendering pipeline settings for hardware acceleration
-    // Only set prism.order if user hasn't already specified it
-    if !args.iter().any(|a| a.contains("-Dprism.order=")) {
-        // Platform-specific rendering order:
-        // - Windows: d3d (Direct3D) > es2 (OpenGL ES 2) > sw (software)
-        // - Linux/macOS: es2 > sw (no Direct3D available)
-        if cfg!(target_os = "windows") {
-            args.push("-Dprism.order=d3d,es2,sw".to_string());
         } else {
-            args.push("-Dprism.order=es2,sw".to_string());
+            emit_log!(window, format!("Quick Play: starting in {}", target));
         }
     }
-
-    // Ensure classpath is set if not already
-    if !args.iter().any(|a| a == "-cp" || a == "-classpath") {
-        args.push("-cp".to_string());
-        args.push(classpath.clone());
-    }
-
-    // 7b. Main Class
-    args.push(version_details.main_class.clone());
-
-    // 7c. Game Arguments
-    // Replacements map
-    let mut replacements = std::collections::HashMap::new();
-    replacements.insert("${auth_player_name}", account.username());
-    replacements.insert("${version_name}", version_id.clone());
-    replacements.insert("${game_directory}", game_dir.to_string_lossy().to_string());
-    replacements.insert("${assets_root}", assets_dir.to_string_lossy().to_string());
-    replacements.insert("${assets_index_name}", asset_index.id.clone());
-    replacements.insert("${auth_uuid}", account.uuid());
-    replacements.insert("${auth_access_token}", account.access_token());
-    // Set user_type dynamically: "msa" for Microsoft accounts, "legacy" for offline
-    let user_type = match &account {
-        core::auth::Account::Microsoft(_) => "msa",
-        core::auth::Account::Offline(_) => "legacy",
-    };
-    replacements.insert("${user_type}", user_type.to_string());
-    // Use version_type from version JSON if available, fallback to "release"
-    let version_type_str = version_details
-        .version_type
-        .clone()
-        .unwrap_or_else(|| "release".to_string());
-    replacements.insert("${version_type}", version_type_str);
-    replacements.insert("${user_properties}", "{}".to_string()); // Correctly pass empty JSON object for user properties
-
-    if let Some(minecraft_arguments) = &version_details.minecraft_arguments {
-        // Legacy string
-        for part in minecraft_arguments.split_whitespace() {
-            let mut arg = part.to_string();
-            for (key, val) in &replacements {
-                arg = arg.replace(key, val);
-            }
-            args.push(arg);
-        }
-    } else if let Some(args_obj) = &version_details.arguments {
-        if let Some(game_args) = &args_obj.game {
-            // Can be array of strings or objects
-            if let Some(list) = game_args.as_array() {
-                for item in list {
-                    if let Some(s) = item.as_str() {
-                        let mut arg = s.to_string();
-                        for (key, val) in &replacements {
-                            arg = arg.replace(key, val);
-                        }
-                        args.push(arg);
-                    } else if let Some(obj) = item.as_object() {
-                        // Check rules
-                        // Simplified: if it has "value", and rules pass.
-                        // For now, assuming rules pass if no "rules" field or simplistic check
-                        // Ideally we should implement a helper to check rules for args just like libs
-
-                        let allow = if let Some(rules_val) = obj.get("rules") {
-                            if let Ok(rules) = serde_json::from_value::<Vec<core::game_version::Rule>>(
-                                rules_val.clone(),
-                            ) {
-                                core::rules::is_library_allowed(
-                                    &Some(rules),
-                                    Some(&config.feature_flags),
-                                )
-                            } else {
-                                true // Parse error, assume allow? or disallow.
-                            }
-                        } else {
-                            true
-                        };
-
-                        if allow {
-                            if let Some(val) = obj.get("value") {
-                                if let Some(s) = val.as_str() {
-                                    let mut arg = s.to_string();
-                                    for (key, replacement) in &replacements {
-                                        arg = arg.replace(key, replacement);
-                                    }
-                                    // Skip arguments with unresolved placeholders
-                                    if !has_unresolved_placeholder(&arg) {
-                                        args.push(arg);
-                                    }
-                                } else if let Some(arr) = val.as_array() {
-                                    for sub in arr {
-                                        if let Some(s) = sub.as_str() {
-                                            let mut arg = s.to_string();
-                                            for (key, replacement) in &replacements {
-                                                arg = arg.replace(key, replacement);
-                                            }
-                                            // Skip arguments with unresolved placeholders
-                                            if !has_unresolved_placeholder(&arg) {
-                                                args.push(arg);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    for placeholder in &game_args.skipped {
+        emit_log!(
+            window,
+            format!(
+                "Skipping optional game argument: no value for ${{{}}}",
+                placeholder
+            )
+        );
     }
+    args.extend(game_args.args);
 
     emit_log!(
         window,
@@ -840,27 +596,50 @@ async fn start_game(
     let java_command = format!("{} {}", java_path_to_use, masked_args_str.join(" "));
     emit_log!(window, format!("Java Command: {}", java_command));
 
+    // Hooks run in the instance directory, with the launch in INST_* variables
+    let hook_context = core::hooks::HookContext::for_launch(
+        &instance,
+        &instance_dir,
+        &game_dir,
+        &java_path_to_use,
+        &minecraft_version,
+    );
+    if let Some(hook) = core::hooks::Hook::new(
+        core::hooks::HookKind::PreLaunch,
+        instance.pre_launch_command.as_deref(),
+        instance.use_shell,
+        &hook_context,
+    ) {
+        emit_log!(window, format!("Running pre-launch hook: {}", hook.command));
+        let window_log = window.clone();
+        hook.run(
+            &instance_id,
+            Some(core::hooks::PRE_LAUNCH_TIMEOUT),
+            move |batch| {
+                let _ = window_log.emit("game-log", batch);
+            },
+        )
+        .await?;
+    }
+    let post_exit = core::hooks::Hook::new(
+        core::hooks::HookKind::PostExit,
+        instance.post_exit_command.as_deref(),
+        instance.use_shell,
+        &hook_context,
+    );
+
     // Spawn the process
+    let java_executable = core::game_process::game_executable(&java_path_to_use);
     emit_log!(
         window,
-        format!("Starting Java process: {}", java_path_to_use)
+        format!("Starting Java process: {}", java_executable.display())
+    );
+    let mut command = core::game_process::game_command(
+        &java_executable,
+        &args,
+        &game_dir,
+        !config.keep_game_running_on_exit,
     );
-    let mut command = Command::new(&java_path_to_use);
-    command.args(&args);
-    command.current_dir(&game_dir); // Run in game directory
-    command.stdout(Stdio::piped());
-    command.stderr(Stdio::piped());
-
-    // On Windows, use CREATE_NO_WINDOW flag to hide the console window
-    #[cfg(target_os = "windows")]
-    {
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        command.creation_flags(CREATE_NO_WINDOW);
-        emit_log!(
-            window,
-            "Applied CREATE_NO_WINDOW flag for Windows".to_string()
-        );
-    }
 
     // On Linux, inject GPU environment variables for hybrid graphics support
     // but do not override values that are already set in the environment.
@@ -894,11 +673,22 @@ async fn start_game(
     }
 
     // Spawn and handle output
+    let started_at = std::time::SystemTime::now();
     let mut child = command
         .spawn()
-        .map_err(|e| format!("Failed to launch Java at '{}': {}\nPlease check your Java installation and path configuration in Settings.", java_path_to_use, e))?;
+        .map_err(|e| format!("Failed to launch Java at '{}': {}\nPlease check your Java installation and path configuration in Settings.", java_executable.display(), e))?;
 
     emit_log!(window, "Java process started successfully".to_string());
+    if let Err(e) = core::java::persistence::record_last_used_java(
+        app_handle,
+        &instance_id,
+        &java_installation,
+    ) {
+        eprintln!(
+            "Warning: Failed to remember the Java of {}: {}",
+            instance_id, e
+        );
+    }
 
     let stdout = child
         .stdout
@@ -908,15 +698,27 @@ async fn start_game(
         .stderr
         .take()
         .expect("child did not have a handle to stderr");
-
-    {
-        let mut running_game = game_process_state.running_game.lock().await;
-        *running_game = Some(RunningGameProcess {
-            child,
-            instance_id: instance_id.clone(),
-            version_id: version_id.clone(),
-        });
-    }
+    let log_tail = core::game_process::LogTail::default();
+    let handle = core::game_process::LaunchHandle {
+        instance_id: instance_id.clone(),
+        pid: child.id(),
+    };
+    game_process_state
+        .register(
+            &instance_id,
+            core::game_process::RunningGame {
+                child,
+                version_id: version_id.clone(),
+                started_at,
+                instance_dir: instance_dir.clone(),
+                game_dir: game_dir.clone(),
+                natives_dir: natives_dir.clone(),
+                java: java_installation.clone(),
+                log_tail: log_tail.clone(),
+                post_exit,
+            },
+        )
+        .await;
 
     // Emit launcher log that game is running
     emit_log!(
@@ -924,91 +726,77 @@ async fn start_game(
         "Game is now running, capturing output...".to_string()
     );
 
-    let window_rx = window.clone();
+    let window_log = window.clone();
     let assistant_arc = assistant_state.assistant.clone();
-    tokio::spawn(async move {
-        let mut reader = BufReader::new(stdout).lines();
-        while let Ok(Some(line)) = reader.next_line().await {
-            assistant_arc.lock().unwrap().add_log(line.clone());
-            let _ = window_rx.emit("game-stdout", line);
-        }
-        // Emit log when stdout stream ends (game closing)
-        let _ = window_rx.emit("launcher-log", "Game stdout stream ended");
-    });
-
-    let window_rx_err = window.clone();
-    let assistant_arc_err = assistant_state.assistant.clone();
-    let window_exit = window.clone();
-    let app_handle_exit = app_handle.clone();
-    tokio::spawn(async move {
-        let mut reader = BufReader::new(stderr).lines();
-        while let Ok(Some(line)) = reader.next_line().await {
-            assistant_arc_err.lock().unwrap().add_log(line.clone());
-            let _ = window_rx_err.emit("game-stderr", line);
-        }
-        // Emit log when stderr stream ends
-        let _ = window_rx_err.emit("launcher-log", "Game stderr stream ended");
-    });
+    let output = core::game_process::forward_output(
+        &instance_id,
+        stdout,
+        stderr,
+        move |event| {
+            log_tail.push(&event.line);
+            assistant_arc.lock().unwrap().add_log(event.line.clone());
+        },
+        move |batch| {
+            let _ = window_log.emit("game-log", batch);
+        },
+    );
 
     // Monitor game process exit
+    let window_exit = window.clone();
     let launch_instance_id = instance_id.clone();
-    let launch_version_id = version_id.clone();
     tokio::spawn(async move {
         loop {
-            let exit_event = {
-                let state: State<'_, GameProcessState> = app_handle_exit.state();
-                let mut running_game = state.running_game.lock().await;
-
-                let Some(active_game) = running_game.as_mut() else {
-                    break;
-                };
-
-                if active_game.instance_id != launch_instance_id {
-                    break;
+            let state: State<'_, core::game_process::GameProcessState> =
+                window_exit.app_handle().state();
+            let (game, status) = match state.poll(&launch_instance_id).await {
+                // Stopped through stop_game, which reports the exit itself
+                core::game_process::GameStatus::NotRunning => break,
+                core::game_process::GameStatus::Running => {
+                    sleep(Duration::from_millis(500)).await;
+                    continue;
+                }
+                core::game_process::GameStatus::Exited(game, status) => (game, Some(status)),
+                core::game_process::GameStatus::Failed(game, error) => {
+                    let _ = window_exit.emit(
+                        "launcher-log",
+                        format!("Error waiting for game process: {}", error),
+                    );
+                    (game, None)
                 }
+            };
 
-                match active_game.child.try_wait() {
-                    Ok(Some(status)) => {
-                        let exit_code = status.code();
-                        *running_game = None;
-                        Some(GameExitedEvent {
-                            instance_id: launch_instance_id.clone(),
-                            version_id: launch_version_id.clone(),
-                            exit_code,
-                            was_stopped: false,
-                        })
-                    }
-                    Ok(None) => None,
-                    Err(error) => {
-                        let _ = window_exit.emit(
-                            "launcher-log",
-                            format!("Error waiting for game process: {}", error),
-                        );
-                        *running_game = None;
-                        Some(GameExitedEvent {
-                            instance_id: launch_instance_id.clone(),
-                            version_id: launch_version_id.clone(),
-                            exit_code: None,
-                            was_stopped: false,
-                        })
-                    }
-                }
-            };
-
-            if let Some(event) = exit_event {
-                let msg = format!(
-                    "Game process exited for instance {} with status {:?}",
-                    event.instance_id, event.exit_code
+            // Deliver the last lines before reporting the exit, unless something the
+            // game started keeps its output open
+            let _ = tokio::time::timeout(Duration::from_secs(2), output).await;
+            let event = core::game_process::finish_game(
+                &launch_instance_id,
+                &game,
+                status.as_ref(),
+                false,
+            );
+            let msg = format!(
+                "Game process exited for instance {} with status {:?} ({:?}) after {}s",
+                event.instance_id, event.exit_code, event.kind, event.duration_secs
+            );
+            let _ = window_exit.emit("launcher-log", &msg);
+            if let Some(crash_report) = &event.crash_report_path {
+                let _ = window_exit.emit(
+                    "launcher-log",
+                    format!("The game wrote a crash report: {}", crash_report),
+                );
+            }
+            for hint in &event.hints {
+                let _ = window_exit.emit(
+                    "launcher-log",
+                    format!("Possible cause: {} {:?}", hint.message_key, hint.params),
                 );
-                let _ = window_exit.emit("launcher-log", &msg);
-                let _ = window_exit.emit("game-exited", &event);
-
-                let state: State<core::instance::InstanceState> = window_exit.app_handle().state();
-                state.end_operation(&event.instance_id);
-                break;
             }
+            let _ = window_exit.emit("game-exited", &event);
+            run_post_exit_hook(&window_exit, &event.instance_id, &game).await;
 
-            sleep(Duration::from_millis(500)).await;
+            let state: State<core::instance::InstanceState> = window_exit.app_handle().state();
+            state.end_operation(&event.instance_id);
+            break;
         }
     });
 
@@ -1018,7 +806,11 @@ async fn start_game(
         let _ = instance_state.update_instance(instance);
     }
 
-    Ok(format!("Launched Minecraft {} successfully!", version_id))
+    emit_log!(
+        window,
+        format!("Launched Minecraft {} successfully!", version_id)
+    );
+    Ok(handle)
     }
     .await;
 
@@ -1033,24 +825,22 @@ async fn start_game(
 #[dropout_macros::api]
 async fn stop_game(
     window: Window,
-    game_process_state: State<'_, GameProcessState>,
+    game_process_state: State<'_, core::game_process::GameProcessState>,
     instance_state: State<'_, core::instance::InstanceState>,
+    instance_id: String,
 ) -> Result<String, String> {
-    let mut running_game = {
-        let mut state = game_process_state.running_game.lock().await;
-        state.take().ok_or("No running game process found")?
-    };
+    let mut running_game = game_process_state
+        .take(&instance_id)
+        .await
+        .ok_or("No running game process found")?;
 
     emit_log!(
         window,
-        format!(
-            "Stopping game process for instance {}...",
-            running_game.instance_id
-        )
+        format!("Stopping game process for instance {}...", instance_id)
     );
 
-    let exit_code = match running_game.child.try_wait() {
-        Ok(Some(status)) => status.code(),
+    let status = match running_game.child.try_wait() {
+        Ok(Some(status)) => status,
         Ok(None) => {
             running_game
                 .child
@@ -1062,99 +852,71 @@ async fn stop_game(
                 .wait()
                 .await
                 .map_err(|e| format!("Failed while waiting for the game to stop: {}", e))?
-                .code()
         }
         Err(error) => {
             return Err(format!("Failed to inspect running game process: {}", error));
         }
     };
 
-    let event = GameExitedEvent {
-        instance_id: running_game.instance_id.clone(),
-        version_id: running_game.version_id.clone(),
-        exit_code,
-        was_stopped: true,
-    };
-
+    let event = core::game_process::finish_game(&instance_id, &running_game, Some(&status), true);
     let _ = window.emit("game-exited", &event);
-    instance_state.end_operation(&running_game.instance_id);
-
-    Ok(format!(
+    let message = format!(
         "Stopped Minecraft {} for instance {}",
-        running_game.version_id, running_game.instance_id
-    ))
+        running_game.version_id, instance_id
+    );
+
+    // The instance stays busy until the post-exit hook is done
+    if running_game.post_exit.is_some() {
+        tokio::spawn(async move {
+            run_post_exit_hook(&window, &instance_id, &running_game).await;
+            let state: State<core::instance::InstanceState> = window.app_handle().state();
+            state.end_operation(&instance_id);
+        });
+    } else {
+        instance_state.end_operation(&instance_id);
+    }
+
+    Ok(message)
 }
 
-/// Parse JVM arguments from version.json
-fn parse_jvm_arguments(
-    jvm_args: &serde_json::Value,
-    args: &mut Vec<String>,
-    natives_path: &str,
-    classpath: &str,
-    feature_flags: &core::config::FeatureFlags,
+/// Run the post-exit hook of a game that has ended, if it has one
+async fn run_post_exit_hook(
+    window: &Window,
+    instance_id: &str,
+    game: &core::game_process::RunningGame,
 ) {
-    let mut replacements = std::collections::HashMap::new();
-    replacements.insert("${natives_directory}", natives_path.to_string());
-    replacements.insert("${classpath}", classpath.to_string());
-    replacements.insert("${launcher_name}", "DropOut".to_string());
-    replacements.insert("${launcher_version}", env!("CARGO_PKG_VERSION").to_string());
-
-    if let Some(list) = jvm_args.as_array() {
-        for item in list {
-            if let Some(s) = item.as_str() {
-                // Simple string argument
-                let mut arg = s.to_string();
-                for (key, val) in &replacements {
-                    arg = arg.replace(key, val);
-                }
-                // Skip memory args as we set them explicitly
-                if !arg.starts_with("-Xmx") && !arg.starts_with("-Xms") {
-                    args.push(arg);
-                }
-            } else if let Some(obj) = item.as_object() {
-                // Conditional argument with rules
-                let allow = if let Some(rules_val) = obj.get("rules") {
-                    if let Ok(rules) =
-                        serde_json::from_value::<Vec<core::game_version::Rule>>(rules_val.clone())
-                    {
-                        core::rules::is_library_allowed(&Some(rules), Some(feature_flags))
-                    } else {
-                        false
-                    }
-                } else {
-                    true
-                };
-
-                if allow {
-                    if let Some(val) = obj.get("value") {
-                        if let Some(s) = val.as_str() {
-                            let mut arg = s.to_string();
-                            for (key, replacement) in &replacements {
-                                arg = arg.replace(key, replacement);
-                            }
-                            if !arg.starts_with("-Xmx") && !arg.starts_with("-Xms") {
-                                args.push(arg);
-                            }
-                        } else if let Some(arr) = val.as_array() {
-                            for sub in arr {
-                                if let Some(s) = sub.as_str() {
-                                    let mut arg = s.to_string();
-                                    for (key, replacement) in &replacements {
-                                        arg = arg.replace(key, replacement);
-                                    }
-                                    if !arg.starts_with("-Xmx") && !arg.starts_with("-Xms") {
-                                        args.push(arg);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    let Some(hook) = &game.post_exit else {
+        return;
+    };
+    emit_log!(window, format!("Running post-exit hook: {}", hook.command));
+    let window_log = window.clone();
+    let result = hook
+        .run(
+            instance_id,
+            Some(core::hooks::POST_EXIT_TIMEOUT),
+            move |batch| {
+                let _ = window_log.emit("game-log", batch);
+            },
+        )
+        .await;
+    if let Err(e) = result {
+        emit_log!(window, format!("{}", e));
     }
 }
 
+/// Launches of an instance that crashed or were killed, newest first
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_launch_history(
+    instance_state: State<'_, core::instance::InstanceState>,
+    instance_id: String,
+) -> Result<Vec<core::launch_history::LaunchRecord>, String> {
+    let instance = instance_state
+        .get_instance(&instance_id)
+        .ok_or_else(|| format!("Instance {} not found", instance_id))?;
+    Ok(core::launch_history::entries(&instance.game_dir))
+}
+
 #[tauri::command]
 #[dropout_macros::api]
 async fn get_versions() -> Result<Vec<core::manifest::Version>, String> {
@@ -1314,191 +1076,48 @@ async fn install_version(
         );
 
         // Determine the actual minecraft version for client.jar
-        let minecraft_version = version_details
-            .inherits_from
-            .clone()
-            .unwrap_or_else(|| version_id.clone());
+        let minecraft_version = core::manifest::minecraft_version(&game_dir, &version_id).await;
 
         // Prepare download tasks
         emit_log!(window, "Preparing download tasks...".to_string());
-        let mut download_tasks = Vec::new();
-
-        // --- Client Jar ---
-        let downloads = version_details
-            .downloads
-            .as_ref()
-            .ok_or("Version has no downloads information")?;
-        let client_jar = &downloads.client;
         let mut client_path = resolved_paths.version_cache.clone();
         client_path.push(&minecraft_version);
         client_path.push(format!("{}.jar", minecraft_version));
 
-        download_tasks.push(core::downloader::DownloadTask {
-            url: client_jar.url.clone(),
-            path: client_path.clone(),
-            sha1: client_jar.sha1.clone(),
-            sha256: None,
-        });
-
-        // --- Libraries ---
-        let libraries_dir = resolved_paths.libraries.clone();
-
-        for lib in &version_details.libraries {
-            if core::rules::is_library_allowed(&lib.rules, Some(&config.feature_flags)) {
-                if let Some(downloads) = &lib.downloads {
-                    if let Some(artifact) = &downloads.artifact {
-                        let path_str = artifact
-                            .path
-                            .clone()
-                            .unwrap_or_else(|| format!("{}.jar", lib.name));
-
-                        let mut lib_path = libraries_dir.clone();
-                        lib_path.push(path_str);
-
-                        download_tasks.push(core::downloader::DownloadTask {
-                            url: artifact.url.clone(),
-                            path: lib_path,
-                            sha1: artifact.sha1.clone(),
-                            sha256: None,
-                        });
-                    }
-
-                    // Native Library (classifiers)
-                    if let Some(classifiers) = &downloads.classifiers {
-                        // Determine candidate keys based on OS and architecture
-                        let arch = std::env::consts::ARCH;
-                        let mut candidates: Vec<String> = Vec::new();
-                        if cfg!(target_os = "linux") {
-                            candidates.push("natives-linux".to_string());
-                            candidates.push(format!("natives-linux-{}", arch));
-                            if arch == "aarch64" {
-                                candidates.push("natives-linux-arm64".to_string());
-                            }
-                        } else if cfg!(target_os = "windows") {
-                            candidates.push("natives-windows".to_string());
-                            candidates.push(format!("natives-windows-{}", arch));
-                        } else if cfg!(target_os = "macos") {
-                            candidates.push("natives-osx".to_string());
-                            candidates.push("natives-macos".to_string());
-                            candidates.push(format!("natives-macos-{}", arch));
-                        }
-
-                        // Pick the first available classifier key
-                        let mut chosen: Option<core::game_version::DownloadArtifact> = None;
-                        for key in candidates {
-                            if let Some(native_artifact_value) = classifiers.get(&key) {
-                                if let Ok(artifact) =
-                                    serde_json::from_value::<core::game_version::DownloadArtifact>(
-                                        native_artifact_value.clone(),
-                                    )
-                                {
-                                    chosen = Some(artifact);
-                                    break;
-                                }
-                            }
-                        }
-
-                        if let Some(native_artifact) = chosen {
-                            let path_str = native_artifact.path.clone().unwrap();
-                            let mut native_path = libraries_dir.clone();
-                            native_path.push(&path_str);
-
-                            download_tasks.push(core::downloader::DownloadTask {
-                                url: native_artifact.url,
-                                path: native_path.clone(),
-                                sha1: native_artifact.sha1,
-                                sha256: None,
-                            });
-                        }
-                    }
-                } else {
-                    // Library without explicit downloads (mod loader libraries)
-                    if let Some(url) =
-                        core::maven::resolve_library_url(&lib.name, None, lib.url.as_deref())
-                    {
-                        if let Some(lib_path) =
-                            core::maven::get_library_path(&lib.name, &libraries_dir)
-                        {
-                            download_tasks.push(core::downloader::DownloadTask {
-                                url,
-                                path: lib_path,
-                                sha1: None,
-                                sha256: None,
-                            });
-                        }
-                    }
-                }
-            }
-        }
+        // --- Client Jar and Libraries ---
+        let mut download_tasks = core::game_files::client_and_library_tasks(
+            &version_details,
+            &client_path,
+            &resolved_paths.libraries,
+            &core::rules::RuleContext::for_launch(&config),
+        )?;
 
         // --- Assets ---
-        let assets_dir = resolved_paths.assets.clone();
-        let objects_dir = assets_dir.join("objects");
-        let indexes_dir = assets_dir.join("indexes");
-
         let asset_index = version_details
             .asset_index
             .as_ref()
             .ok_or("Version has no asset index information")?;
-
-        let asset_index_path = indexes_dir.join(format!("{}.json", asset_index.id));
-
-        let asset_index_content: String = if asset_index_path.exists() {
-            tokio::fs::read_to_string(&asset_index_path)
-                .await
-                .map_err(|e| e.to_string())?
-        } else {
-            emit_log!(window, format!("Downloading asset index..."));
-            let content = reqwest::get(&asset_index.url)
-                .await
-                .map_err(|e| e.to_string())?
-                .text()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            tokio::fs::create_dir_all(&indexes_dir)
-                .await
-                .map_err(|e| e.to_string())?;
-            tokio::fs::write(&asset_index_path, &content)
-                .await
-                .map_err(|e| e.to_string())?;
-            content
-        };
-
-        #[derive(serde::Deserialize)]
-        struct AssetObject {
-            hash: String,
-        }
-
-        #[derive(serde::Deserialize)]
-        struct AssetIndexJson {
-            objects: std::collections::HashMap<String, AssetObject>,
+        let asset_index_parsed =
+            core::assets::load_asset_index(asset_index, &resolved_paths.assets.join("indexes"))
+                .await?;
+        if asset_index_parsed.is_legacy() {
+            emit_log!(
+                window,
+                format!(
+                    "Warning: asset index {} uses the pre-1.7 layout, which isn't supported yet; sounds and languages may be missing",
+                    asset_index.id
+                )
+            );
         }
 
-        let asset_index_parsed: AssetIndexJson =
-            serde_json::from_str(&asset_index_content).map_err(|e| e.to_string())?;
-
         emit_log!(
             window,
             format!("Processing {} assets...", asset_index_parsed.objects.len())
         );
-
-        for (_name, object) in asset_index_parsed.objects {
-            let hash = object.hash;
-            let prefix = &hash[0..2];
-            let path = objects_dir.join(prefix).join(&hash);
-            let url = format!(
-                "https://resources.download.minecraft.net/{}/{}",
-                prefix, hash
-            );
-
-            download_tasks.push(core::downloader::DownloadTask {
-                url,
-                path,
-                sha1: Some(hash),
-                sha256: None,
-            });
-        }
+        download_tasks.extend(core::assets::asset_tasks(
+            &asset_index_parsed,
+            &resolved_paths.assets.join("objects"),
+        ));
 
         emit_log!(
             window,
@@ -1516,13 +1135,9 @@ async fn install_version(
                 config.download_threads
             )
         );
-        core::downloader::download_files(
-            window.clone(),
-            download_tasks,
-            config.download_threads as usize,
-        )
-        .await
-        .map_err(|e| e.to_string())?;
+        let report =
+        core::game_files::download_with_settings(window.clone(), download_tasks, &config).await?;
+        check_download_report(&window, &report)?;
 
         emit_log!(
             window,
@@ -1547,6 +1162,79 @@ async fn install_version(
     install_result
 }
 
+/// Hash the asset objects of an installed version and report how many need downloading
+#[tauri::command]
+#[dropout_macros::api]
+async fn verify_game_files(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceState>,
+    instance_id: String,
+    version_id: String,
+) -> Result<core::assets::AssetReport, String> {
+    let config = config_state.config.lock().unwrap().clone();
+    let app_handle = window.app_handle();
+    let resolved_paths = instance_state.resolve_paths(&instance_id, &config, app_handle)?;
+    let version_details = core::manifest::load_version(&resolved_paths.root, &version_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (report, _) = core::assets::check_assets(&version_details, &resolved_paths.assets).await?;
+    emit_log!(
+        window,
+        format!(
+            "Checked {} assets of {}: {} missing, {} corrupt",
+            report.total, version_id, report.missing, report.corrupt
+        )
+    );
+    Ok(report)
+}
+
+/// Download the missing or corrupt asset objects of an installed version again
+#[tauri::command]
+#[dropout_macros::api]
+async fn repair_game_files(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceState>,
+    instance_id: String,
+    version_id: String,
+) -> Result<core::assets::AssetReport, String> {
+    let config = config_state.config.lock().unwrap().clone();
+    let app_handle = window.app_handle();
+    instance_state.begin_operation(&instance_id, core::instance::InstanceOperation::Install)?;
+
+    let repair_result: Result<core::assets::AssetReport, String> = async {
+        let resolved_paths = instance_state.resolve_paths(&instance_id, &config, app_handle)?;
+        let version_details = core::manifest::load_version(&resolved_paths.root, &version_id)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        emit_log!(window, format!("Checking game files of {}...", version_id));
+        let (report, batch) = core::assets::prepare_assets(
+            window.clone(),
+            &version_details,
+            &resolved_paths.assets,
+            &config,
+        )
+        .await?;
+        check_download_report(&window, &batch)?;
+        emit_log!(
+            window,
+            format!(
+                "Repaired {} of {} assets",
+                report.repaired,
+                report.needs_download()
+            )
+        );
+        Ok(report)
+    }
+    .await;
+
+    instance_state.end_operation(&instance_id);
+    repair_result
+}
+
 #[tauri::command]
 #[dropout_macros::api]
 async fn login_offline(
@@ -1554,23 +1242,83 @@ async fn login_offline(
     state: State<'_, core::auth::AccountState>,
     username: String,
 ) -> Result<core::auth::Account, String> {
-    let uuid = core::auth::generate_offline_uuid(&username);
-    let account = core::auth::Account::Offline(core::auth::OfflineAccount { username, uuid });
+    let username = username.trim().to_string();
+    core::auth::validate_offline_username(&username)?;
+
+    let app_handle = window.app_handle();
+    let app_dir = core::paths::data_root(app_handle);
+    let storage = core::account_storage::AccountStorage::new(app_dir);
+    let account = core::auth::Account::Offline(storage.offline_account(&username));
 
     *state.active_account.lock().unwrap() = Some(account.clone());
 
     // Save to storage
-    let app_handle = window.app_handle();
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
-    let storage = core::account_storage::AccountStorage::new(app_dir);
     storage.add_or_update_account(&account, None)?;
 
     Ok(account)
 }
 
+/// All saved accounts
+#[tauri::command]
+#[dropout_macros::api]
+async fn list_accounts(window: Window) -> Result<Vec<core::auth::Account>, String> {
+    let app_dir = core::paths::data_root(window.app_handle());
+    let storage = core::account_storage::AccountStorage::new(app_dir);
+    Ok(storage
+        .get_all_accounts()
+        .iter()
+        .map(core::account_storage::StoredAccount::to_account)
+        .collect())
+}
+
+/// Make a saved account the one games are launched with
+#[tauri::command]
+#[dropout_macros::api]
+async fn set_active_account(
+    window: Window,
+    state: State<'_, core::auth::AccountState>,
+    ms_refresh_state: State<'_, MsRefreshTokenState>,
+    account_id: String,
+) -> Result<core::auth::Account, String> {
+    let app_dir = core::paths::data_root(window.app_handle());
+    let storage = core::account_storage::AccountStorage::new(app_dir);
+    storage.set_active_account(&account_id)?;
+    let (stored, ms_refresh) = storage.get_active_account().ok_or("Account not found")?;
+
+    let account = stored.to_account();
+    *state.active_account.lock().unwrap() = Some(account.clone());
+    *ms_refresh_state.token.lock().unwrap() = ms_refresh;
+    Ok(account)
+}
+
+/// Forget a saved account; removing the active one activates the next saved one
+#[tauri::command]
+#[dropout_macros::api]
+async fn remove_account(
+    window: Window,
+    state: State<'_, core::auth::AccountState>,
+    ms_refresh_state: State<'_, MsRefreshTokenState>,
+    account_id: String,
+) -> Result<(), String> {
+    let app_dir = core::paths::data_root(window.app_handle());
+    let storage = core::account_storage::AccountStorage::new(app_dir);
+    storage.remove_account(&account_id)?;
+
+    let was_active = state
+        .active_account
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|a| a.uuid() == account_id);
+    if was_active {
+        let next = storage.get_active_account();
+        *ms_refresh_state.token.lock().unwrap() =
+            next.as_ref().and_then(|(_, ms_refresh)| ms_refresh.clone());
+        *state.active_account.lock().unwrap() = next.map(|(stored, _)| stored.to_account());
+    }
+    Ok(())
+}
+
 #[tauri::command]
 #[dropout_macros::api]
 async fn get_active_account(
@@ -1595,10 +1343,7 @@ async fn logout(window: Window, state: State<'_, core::auth::AccountState>) -> R
     // Remove from storage
     if let Some(uuid) = uuid {
         let app_handle = window.app_handle();
-        let app_dir = app_handle
-            .path()
-            .app_data_dir()
-            .map_err(|e| e.to_string())?;
+        let app_dir = core::paths::data_root(app_handle);
         let storage = core::account_storage::AccountStorage::new(app_dir);
         storage.remove_account(&uuid)?;
     }
@@ -1614,17 +1359,276 @@ async fn get_settings(
     Ok(state.config.lock().unwrap().clone())
 }
 
+/// Push the download-related settings into the downloader's global state
+fn apply_download_settings(config: &core::config::LauncherConfig) {
+    core::downloader::DOWNLOAD_RATE_LIMITER.set_limit(config.max_download_speed_bytes_per_sec);
+    core::downloader::set_progress_throttle(
+        config.progress_event_interval_ms,
+        config.progress_event_bytes,
+    );
+    core::downloader::set_url_rewrite_rules(config.url_rewrite_rules.clone());
+}
+
 #[tauri::command]
 #[dropout_macros::api]
 async fn save_settings(
+    app_handle: tauri::AppHandle,
     state: State<'_, core::config::ConfigState>,
     config: core::config::LauncherConfig,
 ) -> Result<(), String> {
-    *state.config.lock().unwrap() = config;
+    config.validate()?;
+    apply_download_settings(&config);
+    *state.config.lock().unwrap() = config.clone();
+    state.save()?;
+    let _ = app_handle.emit("settings-changed", &config);
+    Ok(())
+}
+
+/// Change the global download speed limit; in-flight downloads pick it up immediately
+#[tauri::command]
+#[dropout_macros::api]
+async fn set_download_speed_limit(
+    state: State<'_, core::config::ConfigState>,
+    bytes_per_sec: Option<u64>,
+) -> Result<(), String> {
+    core::downloader::DOWNLOAD_RATE_LIMITER.set_limit(bytes_per_sec);
+    state
+        .config
+        .lock()
+        .unwrap()
+        .max_download_speed_bytes_per_sec = bytes_per_sec;
+    state.save()
+}
+
+fn network_config(
+    app_handle: &tauri::AppHandle,
+    config: &core::config::LauncherConfig,
+) -> core::http::NetworkConfig {
+    core::http::NetworkConfig {
+        settings: core::java::persistence::get_network_settings(app_handle),
+        read_timeout_secs: config.download_stall_timeout_secs,
+        download_concurrency: config.download_threads,
+        max_download_speed_bytes_per_sec: config.max_download_speed_bytes_per_sec,
+    }
+}
+
+/// Get the proxy, TLS, timeout and download settings together. The proxy password is
+/// left out.
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_network_config(
+    app_handle: tauri::AppHandle,
+    state: State<'_, core::config::ConfigState>,
+) -> Result<core::http::NetworkConfig, String> {
+    let config = state.config.lock().unwrap().clone();
+    Ok(network_config(&app_handle, &config).redacted())
+}
+
+/// Validate, save and apply network settings, then emit `network-config-changed`
+/// without the proxy password. Requests and downloads use them right away.
+#[tauri::command]
+#[dropout_macros::api]
+async fn update_network_config(
+    app_handle: tauri::AppHandle,
+    state: State<'_, core::config::ConfigState>,
+    mut network: core::http::NetworkConfig,
+) -> Result<core::http::NetworkConfig, String> {
+    network
+        .settings
+        .keep_password_from(&core::java::persistence::get_network_settings(&app_handle));
+    network.validate()?;
+    core::java::persistence::set_network_settings(&app_handle, network.settings.clone())?;
+    core::http::apply_settings(&network.settings)?;
+    core::downloader::DOWNLOAD_RATE_LIMITER.set_limit(network.max_download_speed_bytes_per_sec);
+    {
+        let mut config = state.config.lock().unwrap();
+        config.download_stall_timeout_secs = network.read_timeout_secs;
+        config.download_threads = network.download_concurrency;
+        config.max_download_speed_bytes_per_sec = network.max_download_speed_bytes_per_sec;
+    }
     state.save()?;
+
+    let redacted = network.redacted();
+    let _ = app_handle.emit("network-config-changed", &redacted);
+    Ok(redacted)
+}
+
+/// Get network (proxy) settings
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_network_settings(
+    app_handle: tauri::AppHandle,
+) -> Result<core::http::NetworkSettings, String> {
+    Ok(core::java::persistence::get_network_settings(&app_handle))
+}
+
+/// Save network settings; subsequent requests use them immediately
+#[tauri::command]
+#[dropout_macros::api]
+async fn save_network_settings(
+    app_handle: tauri::AppHandle,
+    settings: core::http::NetworkSettings,
+) -> Result<(), String> {
+    core::java::persistence::set_network_settings(&app_handle, settings.clone())?;
+    core::http::apply_settings(&settings)
+}
+
+/// Add a Java binary or Java home picked by the user, returning the validated installation
+#[tauri::command]
+#[dropout_macros::api]
+async fn add_user_java_path(
+    app_handle: tauri::AppHandle,
+    path: String,
+) -> Result<core::java::JavaInstallation, core::java::JavaError> {
+    core::java::persistence::add_user_defined_path(&app_handle, path).await
+}
+
+/// Export the launcher's settings to a single file
+#[tauri::command]
+#[dropout_macros::api]
+async fn export_settings(app_handle: tauri::AppHandle, path: String) -> Result<(), String> {
+    core::java::transfer::export_settings(&app_handle, std::path::Path::new(&path))?;
+    Ok(())
+}
+
+/// Import settings exported with `export_settings`, reporting what was applied and skipped
+#[tauri::command]
+#[dropout_macros::api]
+async fn import_settings(
+    app_handle: tauri::AppHandle,
+    path: String,
+    options: core::java::transfer::ImportOptions,
+) -> Result<core::java::transfer::ImportReport, String> {
+    let (config, report) =
+        core::java::transfer::import_settings(&app_handle, std::path::Path::new(&path), &options)
+            .await?;
+    core::http::apply_settings(&config.network)?;
+    Ok(report)
+}
+
+/// Get the Java override of an instance, if it has one
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_instance_java_override(
+    app_handle: tauri::AppHandle,
+    instance_id: String,
+) -> Result<Option<core::java::persistence::InstanceJavaOverride>, core::java::JavaError> {
+    Ok(core::java::persistence::get_instance_java_override(
+        &app_handle,
+        &instance_id,
+    ))
+}
+
+/// Set the Java an instance launches with
+#[tauri::command]
+#[dropout_macros::api]
+async fn set_instance_java_override(
+    app_handle: tauri::AppHandle,
+    instance_id: String,
+    java_override: core::java::persistence::InstanceJavaOverride,
+) -> Result<(), core::java::JavaError> {
+    core::java::persistence::set_instance_java_override(&app_handle, &instance_id, java_override)?;
+    Ok(())
+}
+
+/// Make an instance use the global Java choice again
+#[tauri::command]
+#[dropout_macros::api]
+async fn remove_instance_java_override(
+    app_handle: tauri::AppHandle,
+    instance_id: String,
+) -> Result<(), core::java::JavaError> {
+    core::java::persistence::remove_instance_java_override(&app_handle, &instance_id)?;
     Ok(())
 }
 
+/// Forget the Java an instance last launched with, so its next launch resolves from scratch
+#[tauri::command]
+#[dropout_macros::api]
+async fn clear_last_used_java(
+    app_handle: tauri::AppHandle,
+    instance_id: String,
+) -> Result<(), core::java::JavaError> {
+    core::java::persistence::clear_last_used_java(&app_handle, &instance_id)?;
+    Ok(())
+}
+
+/// Read the Java config from disk again, e.g. after editing it by hand
+#[tauri::command]
+#[dropout_macros::api]
+async fn reload_java_config(
+    app_handle: tauri::AppHandle,
+) -> Result<core::java::persistence::JavaConfig, core::java::JavaError> {
+    let config = core::java::persistence::reload_java_config(&app_handle)?;
+    core::http::apply_settings(&config.network)?;
+    Ok(config.redacted())
+}
+
+/// List configuration profiles
+#[tauri::command]
+#[dropout_macros::api]
+async fn list_profiles(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<core::profiles::Profile>, String> {
+    Ok(core::profiles::list_profiles(&app_handle))
+}
+
+/// Create a profile, optionally starting from a copy of another one's Java config
+#[tauri::command]
+#[dropout_macros::api]
+async fn create_profile(
+    app_handle: tauri::AppHandle,
+    name: String,
+    copy_from: Option<String>,
+) -> Result<(), String> {
+    core::profiles::create_profile(&app_handle, &name, copy_from.as_deref())
+}
+
+/// Switch to another profile and load its Java config
+#[tauri::command]
+#[dropout_macros::api]
+async fn switch_profile(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<core::java::persistence::JavaConfig, String> {
+    core::profiles::switch_profile(&app_handle, &name)?;
+    let config = core::java::persistence::reload_java_config(&app_handle)?;
+    core::http::apply_settings(&config.network)?;
+    Ok(config.redacted())
+}
+
+/// Delete a profile other than the active or default one
+#[tauri::command]
+#[dropout_macros::api]
+async fn delete_profile(app_handle: tauri::AppHandle, name: String) -> Result<(), String> {
+    core::profiles::delete_profile(&app_handle, &name)
+}
+
+/// Replace the Java config with its backup from the previous save
+#[tauri::command]
+#[dropout_macros::api]
+async fn restore_java_config_backup(
+    app_handle: tauri::AppHandle,
+) -> Result<core::java::persistence::JavaConfig, core::java::JavaError> {
+    let config = core::java::persistence::restore_java_config_backup(&app_handle)?;
+    core::http::apply_settings(&config.network)?;
+    Ok(config)
+}
+
+/// Describe the proxy configuration picked up from settings and the environment
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_network_diagnostics() -> Result<Vec<String>, String> {
+    Ok(core::http::diagnostics())
+}
+
+/// Check that the given network settings can reach the internet
+#[tauri::command]
+#[dropout_macros::api]
+async fn test_proxy(settings: core::http::NetworkSettings) -> Result<(), String> {
+    core::http::test_connection(&settings).await
+}
+
 #[tauri::command]
 #[dropout_macros::api]
 async fn get_config_path(state: State<'_, core::config::ConfigState>) -> Result<String, String> {
@@ -1648,13 +1652,13 @@ async fn save_raw_config(
     // Validate JSON
     let new_config: core::config::LauncherConfig =
         serde_json::from_str(&content).map_err(|e| format!("Invalid JSON: {}", e))?;
+    new_config.validate()?;
 
     // Save to file
-    tokio::fs::write(&state.file_path, &content)
-        .await
-        .map_err(|e| e.to_string())?;
+    core::config::save_config_to(&new_config, &state.file_path)?;
 
     // Update in-memory state
+    apply_download_settings(&new_config);
     *state.config.lock().unwrap() = new_config;
 
     Ok(())
@@ -1663,7 +1667,7 @@ async fn save_raw_config(
 #[tauri::command]
 #[dropout_macros::api]
 async fn start_microsoft_login() -> Result<core::auth::DeviceCodeResponse, String> {
-    core::auth::start_device_flow().await
+    Ok(core::auth::start_device_flow(&core::auth::AuthEndpoints::default()).await?)
 }
 
 #[tauri::command]
@@ -1680,123 +1684,257 @@ async fn complete_microsoft_login(
     };
 
     // 1. Poll (once) for token
+    let endpoints = core::auth::AuthEndpoints::default();
     emit_progress("Receiving token from Microsoft...");
-    let token_resp = core::auth::exchange_code_for_token(&device_code).await?;
+    let token_resp = core::auth::exchange_code_for_token(&endpoints, &device_code).await?;
     emit_progress("Token received successfully!");
 
     // Store MS refresh token
     let ms_refresh_token = token_resp.refresh_token.clone();
     *ms_refresh_state.token.lock().unwrap() = ms_refresh_token.clone();
 
-    // 2. Xbox Live Auth
-    emit_progress("Authenticating with Xbox Live...");
-    let (xbl_token, uhs) = core::auth::method_xbox_live(&token_resp.access_token).await?;
-    emit_progress("Xbox Live authentication successful!");
-
-    // 3. XSTS Auth
-    emit_progress("Authenticating with XSTS...");
-    let xsts_token = core::auth::method_xsts(&xbl_token).await?;
-    emit_progress("XSTS authentication successful!");
-
-    // 4. Minecraft Auth
-    emit_progress("Authenticating with Minecraft...");
-    let mc_token = core::auth::login_minecraft(&xsts_token, &uhs).await?;
-    emit_progress("Minecraft authentication successful!");
-
-    // 5. Get Profile
-    emit_progress("Fetching Minecraft profile...");
-    let profile = core::auth::fetch_profile(&mc_token).await?;
-    emit_progress(&format!("Welcome, {}!", profile.name));
-
-    // 6. Create Account
-    let account = core::auth::Account::Microsoft(core::auth::MicrosoftAccount {
-        username: profile.name,
-        uuid: profile.id,
-        access_token: mc_token, // This is the MC Access Token
-        refresh_token: token_resp.refresh_token.clone(),
-        expires_at: (std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            + token_resp.expires_in) as i64,
-    });
+    // 2. Xbox Live, XSTS, Minecraft and the profile
+    let ms_account = core::auth::authenticate(&endpoints, &token_resp, |step| {
+        emit_progress(step.message())
+    })
+    .await?;
+    emit_progress(&format!("Welcome, {}!", ms_account.username));
+    let account = core::auth::Account::Microsoft(ms_account);
 
     // 7. Save to state
     *state.active_account.lock().unwrap() = Some(account.clone());
 
     // 8. Save to storage
     let app_handle = window.app_handle();
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
+    let app_dir = core::paths::data_root(app_handle);
     let storage = core::account_storage::AccountStorage::new(app_dir);
     storage.add_or_update_account(&account, ms_refresh_token)?;
 
     Ok(account)
 }
 
-/// Refresh token for current Microsoft account
+/// Refresh token for current Microsoft account
+#[tauri::command]
+#[dropout_macros::api]
+async fn refresh_account(
+    window: Window,
+    state: State<'_, core::auth::AccountState>,
+    ms_refresh_state: State<'_, MsRefreshTokenState>,
+) -> Result<core::auth::Account, String> {
+    // Get stored MS refresh token
+    let app_handle = window.app_handle();
+    let app_dir = core::paths::data_root(app_handle);
+    let storage = core::account_storage::AccountStorage::new(app_dir.clone());
+
+    let (_stored_account, ms_refresh) = storage
+        .get_active_account()
+        .ok_or("No active account found")?;
+
+    let ms_refresh_token = ms_refresh.ok_or("No refresh token available")?;
+
+    // Perform full refresh
+    let (new_account, new_ms_refresh) =
+        core::auth::refresh_full_auth(&core::auth::AuthEndpoints::default(), &ms_refresh_token)
+            .await?;
+    let account = core::auth::Account::Microsoft(new_account);
+
+    // Update state
+    *state.active_account.lock().unwrap() = Some(account.clone());
+    *ms_refresh_state.token.lock().unwrap() = Some(new_ms_refresh.clone());
+
+    // Update storage
+    storage.add_or_update_account(&account, Some(new_ms_refresh))?;
+
+    Ok(account)
+}
+
+/// Messages for the `message_key`s in events and errors, in the given locale
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_message_catalog(
+    locale: String,
+) -> Result<std::collections::HashMap<String, String>, String> {
+    Ok(core::i18n::messages(core::i18n::Locale::from_tag(&locale)))
+}
+
+/// Detect Java installations on the system, reusing a recent scan's results
+#[tauri::command]
+#[dropout_macros::api]
+async fn detect_all_java_installations(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<core::java::JavaInstallation>, String> {
+    let installations = core::java::detect_java_installations_if_stale(&app_handle).await;
+    let settings = core::java::persistence::get_java_settings(&app_handle);
+    Ok(core::java::blocklist::flag_blocked(
+        installations,
+        &settings,
+    ))
+}
+
+/// Alias for detect_all_java_installations (for backward compatibility)
+#[tauri::command]
+#[dropout_macros::api]
+async fn detect_java(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<core::java::JavaInstallation>, core::java::JavaError> {
+    let installations = core::java::detect_java_installations_if_stale(&app_handle).await;
+    let settings = core::java::persistence::get_java_settings(&app_handle);
+    Ok(core::java::blocklist::flag_blocked(
+        installations,
+        &settings,
+    ))
+}
+
+/// Installations found by the last scan in any session, before they are verified;
+/// `java-installations-changed` follows with the checked list
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_known_java_installations(
+    app_handle: tauri::AppHandle,
+) -> Result<core::java::known::KnownInstallations, core::java::JavaError> {
+    Ok(core::java::known::get_known_installations(&app_handle))
+}
+
+/// How the Java for a launch would be chosen now, step by step. The version bounds are
+/// `min_major`/`max_major` if either is given, else those of `mc_version`, else those
+/// of the instance's version. Launches resolve the same way and, once the game has
+/// started, remember the chosen Java as the instance's last used one.
+#[tauri::command]
+#[dropout_macros::api]
+async fn resolve_java(
+    app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceState>,
+    instance_id: Option<String>,
+    mc_version: Option<String>,
+    min_major: Option<u32>,
+    max_major: Option<u32>,
+) -> Result<core::java::priority::JavaResolution, core::java::JavaError> {
+    let config = config_state.config.lock().unwrap().clone();
+    let instance = instance_id
+        .as_deref()
+        .map(|id| {
+            instance_state
+                .get_instance(id)
+                .ok_or_else(|| format!("Instance {} not found", id))
+        })
+        .transpose()?;
+    if let Some(instance) = &instance {
+        instance_java_override(&app_handle, instance)?;
+    }
+    let (required_java_major, max_java_major) = if min_major.is_some() || max_major.is_some() {
+        (min_major.map(u64::from), max_major)
+    } else if let Some(mc_version) = &mc_version {
+        let requirement = core::java::requirements::required_java_for_minecraft(mc_version, None);
+        (
+            Some(u64::from(requirement.min_major)),
+            requirement.recommended_max_major,
+        )
+    } else if let Some(instance) = &instance
+        && let Some(version_id) = &instance.version_id
+    {
+        let game_dir = instance_state
+            .resolve_paths(&instance.id, &config, &app_handle)?
+            .root;
+        let minecraft_version = core::manifest::minecraft_version(&game_dir, version_id).await;
+        let version_details = core::manifest::load_version(&game_dir, version_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        java_version_bounds(&minecraft_version, &version_details)
+    } else {
+        (None, None)
+    };
+    Ok(resolve_java_within(
+        &app_handle,
+        &config,
+        instance_id.as_deref(),
+        required_java_major,
+        max_java_major,
+    )
+    .await)
+}
+
+/// How the Java for launching the instance would be chosen now, step by step
 #[tauri::command]
 #[dropout_macros::api]
-async fn refresh_account(
-    window: Window,
-    state: State<'_, core::auth::AccountState>,
-    ms_refresh_state: State<'_, MsRefreshTokenState>,
-) -> Result<core::auth::Account, String> {
-    // Get stored MS refresh token
-    let app_handle = window.app_handle();
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
-    let storage = core::account_storage::AccountStorage::new(app_dir.clone());
-
-    let (_stored_account, ms_refresh) = storage
-        .get_active_account()
-        .ok_or("No active account found")?;
-
-    let ms_refresh_token = ms_refresh.ok_or("No refresh token available")?;
-
-    // Perform full refresh
-    let (new_account, new_ms_refresh) = core::auth::refresh_full_auth(&ms_refresh_token).await?;
-    let account = core::auth::Account::Microsoft(new_account);
-
-    // Update state
-    *state.active_account.lock().unwrap() = Some(account.clone());
-    *ms_refresh_state.token.lock().unwrap() = Some(new_ms_refresh.clone());
-
-    // Update storage
-    storage.add_or_update_account(&account, Some(new_ms_refresh))?;
-
-    Ok(account)
+async fn explain_java_resolution(
+    app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceState>,
+    instance_id: String,
+) -> Result<core::java::priority::JavaResolution, core::java::JavaError> {
+    resolve_java(
+        app_handle,
+        config_state,
+        instance_state,
+        Some(instance_id),
+        None,
+        None,
+        None,
+    )
+    .await
 }
 
-/// Detect Java installations on the system
+/// How the Java for a Minecraft version would be chosen, outside of any instance
 #[tauri::command]
 #[dropout_macros::api]
-async fn detect_all_java_installations(
+async fn resolve_java_for_minecraft(
     app_handle: tauri::AppHandle,
-) -> Result<Vec<core::java::JavaInstallation>, String> {
-    Ok(core::java::detect_all_java_installations(&app_handle).await)
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceState>,
+    mc_version: String,
+) -> Result<core::java::priority::JavaResolution, core::java::JavaError> {
+    resolve_java(
+        app_handle,
+        config_state,
+        instance_state,
+        None,
+        Some(mc_version),
+        None,
+        None,
+    )
+    .await
 }
 
-/// Alias for detect_all_java_installations (for backward compatibility)
+/// Scan for Java installations now, however recently the last scan ran
 #[tauri::command]
 #[dropout_macros::api]
-async fn detect_java(
+async fn rescan_java_installations(
     app_handle: tauri::AppHandle,
-) -> Result<Vec<core::java::JavaInstallation>, String> {
-    Ok(core::java::detect_all_java_installations(&app_handle).await)
+) -> Result<Vec<core::java::JavaInstallation>, core::java::JavaError> {
+    let installations = core::java::detect_all_java_installations(&app_handle).await;
+    let settings = core::java::persistence::get_java_settings(&app_handle);
+    Ok(core::java::blocklist::flag_blocked(
+        installations,
+        &settings,
+    ))
+}
+
+/// Java builds known to break Minecraft, which resolution skips unless allowed
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_known_bad_java_builds()
+-> Result<Vec<core::java::blocklist::KnownBadBuild>, core::java::JavaError> {
+    Ok(core::java::blocklist::KNOWN_BAD_BUILDS.to_vec())
 }
 
-/// Get recommended Java for a specific Minecraft version
+/// Java to suggest when creating an instance that needs `required_major_version`.
+/// The required major or its nearest LTS is preferred; `prefer_exact: false` takes the
+/// newest compatible Java instead.
 #[tauri::command]
 #[dropout_macros::api]
 async fn get_recommended_java(
+    app_handle: tauri::AppHandle,
     required_major_version: Option<u64>,
-) -> Result<Option<core::java::JavaInstallation>, String> {
-    Ok(core::java::get_recommended_java(required_major_version).await)
+    prefer_exact: Option<bool>,
+) -> Result<Option<core::java::JavaInstallation>, core::java::JavaError> {
+    let settings = core::java::persistence::get_java_settings(&app_handle);
+    Ok(core::java::get_recommended_java(
+        required_major_version,
+        &settings,
+        prefer_exact.unwrap_or(true),
+    )
+    .await)
 }
 
 /// Get Adoptium Java download info
@@ -1805,14 +1943,12 @@ async fn get_recommended_java(
 async fn fetch_adoptium_java(
     major_version: u32,
     image_type: String,
-) -> Result<core::java::JavaDownloadInfo, String> {
+) -> Result<core::java::JavaDownloadInfo, core::java::JavaError> {
     let img_type = match image_type.to_lowercase().as_str() {
         "jdk" => core::java::ImageType::Jdk,
         _ => core::java::ImageType::Jre,
     };
-    core::java::fetch_java_release(major_version, img_type)
-        .await
-        .map_err(|e| e.to_string())
+    core::java::fetch_java_release(major_version, img_type).await
 }
 
 /// Download and install Adoptium Java
@@ -1820,27 +1956,29 @@ async fn fetch_adoptium_java(
 #[dropout_macros::api]
 async fn download_adoptium_java(
     app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
     major_version: u32,
     image_type: String,
     custom_path: Option<String>,
-) -> Result<core::java::JavaInstallation, String> {
+) -> Result<core::java::JavaDownloadResult, core::java::JavaError> {
     let img_type = match image_type.to_lowercase().as_str() {
         "jdk" => core::java::ImageType::Jdk,
         _ => core::java::ImageType::Jre,
     };
     let path = custom_path.map(std::path::PathBuf::from);
-    core::java::download_and_install_java(&app_handle, major_version, img_type, path)
-        .await
-        .map_err(|e| e.to_string())
+    let segments = config_state
+        .config
+        .lock()
+        .unwrap()
+        .java_download_segment_count();
+    core::java::install_java(&app_handle, major_version, img_type, path, segments).await
 }
 
 /// Get available Adoptium Java versions
 #[tauri::command]
 #[dropout_macros::api]
-async fn fetch_available_java_versions() -> Result<Vec<u32>, String> {
-    core::java::fetch_available_versions()
-        .await
-        .map_err(|e| e.to_string())
+async fn fetch_available_java_versions() -> Result<Vec<u32>, core::java::JavaError> {
+    core::java::fetch_available_versions().await
 }
 
 /// Fetch Java catalog with platform availability (uses cache)
@@ -1848,10 +1986,8 @@ async fn fetch_available_java_versions() -> Result<Vec<u32>, String> {
 #[dropout_macros::api]
 async fn fetch_java_catalog(
     app_handle: tauri::AppHandle,
-) -> Result<core::java::JavaCatalog, String> {
-    core::java::fetch_java_catalog(&app_handle, false)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<core::java::JavaCatalogResult, core::java::JavaError> {
+    core::java::fetch_java_catalog(&app_handle, false).await
 }
 
 /// Refresh Java catalog (bypass cache)
@@ -1859,36 +1995,249 @@ async fn fetch_java_catalog(
 #[dropout_macros::api]
 async fn refresh_java_catalog(
     app_handle: tauri::AppHandle,
-) -> Result<core::java::JavaCatalog, String> {
-    core::java::fetch_java_catalog(&app_handle, true)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<core::java::JavaCatalogResult, core::java::JavaError> {
+    core::java::fetch_java_catalog(&app_handle, true).await
+}
+
+/// Delete cached Java data of the given kinds; files a running download is
+/// using are skipped and listed in the report
+#[tauri::command]
+#[dropout_macros::api]
+async fn clear_java_caches(
+    app_handle: tauri::AppHandle,
+    kinds: Vec<core::java::cache::CacheKind>,
+) -> Result<core::java::cache::ClearCachesReport, core::java::JavaError> {
+    core::java::cache::clear_caches(&app_handle, &kinds)
+}
+
+/// Cancel a Java download by the id carried in its progress events.
+/// Without an id every Java download is cancelled, as this command used to do;
+/// prefer `cancel_all_java_downloads` for that.
+#[tauri::command]
+#[dropout_macros::api]
+async fn cancel_java_download(download_id: Option<String>) -> Result<(), core::java::JavaError> {
+    let Some(download_id) = download_id else {
+        core::java::cancel_all_downloads();
+        return Ok(());
+    };
+    if !core::java::cancel_download(&download_id) {
+        return Err(core::java::JavaError::Other(format!(
+            "No running Java download with id {}",
+            download_id
+        )));
+    }
+    Ok(())
+}
+
+/// Cancel every running Java install and resume
+#[tauri::command]
+#[dropout_macros::api]
+async fn cancel_all_java_downloads() -> Result<usize, core::java::JavaError> {
+    Ok(core::java::cancel_all_downloads())
+}
+
+/// Pause the running game file downloads
+#[tauri::command]
+#[dropout_macros::api]
+async fn pause_downloads() -> Result<(), String> {
+    core::downloader::DOWNLOAD_CONTROLLER.pause();
+    Ok(())
+}
+
+/// Resume paused game file downloads
+#[tauri::command]
+#[dropout_macros::api]
+async fn resume_downloads() -> Result<(), String> {
+    core::downloader::DOWNLOAD_CONTROLLER.resume();
+    Ok(())
+}
+
+/// Cancel the running game file downloads, keeping partial files
+#[tauri::command]
+#[dropout_macros::api]
+async fn cancel_downloads() -> Result<(), String> {
+    core::downloader::DOWNLOAD_CONTROLLER.cancel();
+    Ok(())
 }
 
-/// Cancel current Java download
+/// Cancel a single game file download by the task id from its progress events
 #[tauri::command]
 #[dropout_macros::api]
-async fn cancel_java_download() -> Result<(), String> {
-    core::java::cancel_current_download();
+async fn cancel_download(task_id: String) -> Result<(), String> {
+    core::downloader::DOWNLOAD_CONTROLLER.cancel_task(&task_id);
     Ok(())
 }
 
+/// Recent downloads, newest first
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_download_history(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<core::download_history::DownloadHistoryEntry>, String> {
+    Ok(core::download_history::entries(&app_handle))
+}
+
+#[tauri::command]
+#[dropout_macros::api]
+async fn clear_download_history(app_handle: tauri::AppHandle) -> Result<(), String> {
+    core::download_history::clear(&app_handle)
+}
+
 /// Get pending Java downloads
 #[tauri::command]
 #[dropout_macros::api]
 async fn get_pending_java_downloads(
     app_handle: tauri::AppHandle,
-) -> Result<Vec<core::downloader::PendingJavaDownload>, String> {
+) -> Result<Vec<core::downloader::PendingJavaDownload>, core::java::JavaError> {
     Ok(core::java::get_pending_downloads(&app_handle))
 }
 
+/// Turn resuming queued Java downloads at startup on or off
+#[tauri::command]
+#[dropout_macros::api]
+async fn set_auto_resume_downloads(
+    app_handle: tauri::AppHandle,
+    enabled: bool,
+) -> Result<(), String> {
+    core::java::persistence::update_java_settings(
+        &app_handle,
+        core::java::persistence::JavaSettingsPatch {
+            auto_resume_downloads: Some(enabled),
+            ..Default::default()
+        },
+    )?;
+    Ok(())
+}
+
+/// Get the launcher-wide Java settings
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_java_settings(
+    app_handle: tauri::AppHandle,
+) -> Result<core::java::persistence::JavaSettings, core::java::JavaError> {
+    Ok(core::java::persistence::get_java_settings(&app_handle))
+}
+
+/// Change the Java settings present in `patch`, leaving the others alone
+#[tauri::command]
+#[dropout_macros::api]
+async fn update_java_settings(
+    app_handle: tauri::AppHandle,
+    patch: core::java::persistence::JavaSettingsPatch,
+) -> Result<core::java::persistence::JavaSettings, core::java::JavaError> {
+    core::java::persistence::update_java_settings(&app_handle, patch)
+}
+
+/// Delay before queued Java downloads are resumed, so they don't compete with
+/// the UI loading
+const AUTO_RESUME_DELAY: Duration = Duration::from_secs(5);
+
+/// Resume queued Java downloads in the background, then emit `java-resume-complete`
+fn spawn_auto_resume(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        sleep(AUTO_RESUME_DELAY).await;
+
+        let config_state: State<core::config::ConfigState> = app_handle.state();
+        let segments = config_state
+            .config
+            .lock()
+            .unwrap()
+            .java_download_segment_count();
+        let enabled = core::java::persistence::get_java_settings(&app_handle).auto_resume_downloads;
+        // The setting may have been switched off since launch
+        if !enabled || core::java::get_pending_downloads(&app_handle).is_empty() {
+            return;
+        }
+
+        println!("[Startup] Resuming pending Java downloads");
+        match core::java::resume_pending_downloads(&app_handle, segments).await {
+            Ok(result) => {
+                let _ = app_handle.emit("java-resume-complete", result);
+            }
+            Err(e) => eprintln!("[Startup] Failed to resume Java downloads: {}", e),
+        }
+    });
+}
+
+/// Delay before an expired Java catalog is refreshed in the background, so it
+/// doesn't compete with startup
+const CATALOG_REFRESH_DELAY: Duration = Duration::from_secs(60);
+
+/// Refresh an expired Java catalog once per session; an open Java page picks it
+/// up from `java-catalog-refreshed`
+fn spawn_catalog_refresh(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        sleep(CATALOG_REFRESH_DELAY).await;
+
+        let config_state: State<core::config::ConfigState> = app_handle.state();
+        // The setting may have been switched off since launch
+        if !config_state.config.lock().unwrap().background_refresh {
+            return;
+        }
+        core::java::refresh_expired_catalog(&app_handle).await;
+    });
+}
+
+/// Remove stale, orphaned and duplicate entries from the Java download queue
+#[tauri::command]
+#[dropout_macros::api]
+async fn prune_java_download_queue(
+    app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
+) -> Result<usize, core::java::JavaError> {
+    let max_age_days = config_state
+        .config
+        .lock()
+        .unwrap()
+        .pending_java_download_max_age_days;
+    core::downloader::DownloadQueue::prune(
+        &app_handle,
+        Duration::from_secs(u64::from(max_age_days) * 24 * 60 * 60),
+    )
+    .map_err(core::java::JavaError::from)
+}
+
 /// Resume pending Java downloads
 #[tauri::command]
 #[dropout_macros::api]
 async fn resume_java_downloads(
     app_handle: tauri::AppHandle,
-) -> Result<Vec<core::java::JavaInstallation>, String> {
-    core::java::resume_pending_downloads(&app_handle).await
+    config_state: State<'_, core::config::ConfigState>,
+) -> Result<core::java::ResumeJavaDownloadsResult, core::java::JavaError> {
+    let segments = config_state
+        .config
+        .lock()
+        .unwrap()
+        .java_download_segment_count();
+    core::java::resume_pending_downloads(&app_handle, segments).await
+}
+
+/// Retry a pending Java download, including one that was given up on
+#[tauri::command]
+#[dropout_macros::api]
+async fn retry_pending_java_download(
+    app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
+    major_version: u32,
+    image_type: String,
+) -> Result<core::java::JavaDownloadResult, core::java::JavaError> {
+    let segments = config_state
+        .config
+        .lock()
+        .unwrap()
+        .java_download_segment_count();
+    core::java::retry_pending_download(&app_handle, major_version, &image_type, segments).await
+}
+
+/// Drop a pending Java download from the queue
+#[tauri::command]
+#[dropout_macros::api]
+async fn discard_pending_java_download(
+    app_handle: tauri::AppHandle,
+    major_version: u32,
+    image_type: String,
+) -> Result<(), core::java::JavaError> {
+    core::java::clear_pending_download(&app_handle, major_version, &image_type)
 }
 
 /// Get Minecraft versions supported by Fabric
@@ -2283,6 +2632,17 @@ async fn get_forge_versions_for_game(
         .map_err(|e| e.to_string())
 }
 
+/// Get available NeoForge versions for a specific Minecraft version
+#[tauri::command]
+#[dropout_macros::api]
+async fn get_neoforge_versions_for_game(
+    game_version: String,
+) -> Result<Vec<core::forge::ForgeVersion>, String> {
+    core::forge::fetch_neoforge_versions(&game_version)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Install Forge for a specific Minecraft version
 #[tauri::command]
 #[dropout_macros::api]
@@ -2293,87 +2653,169 @@ async fn install_forge(
     instance_id: String,
     game_version: String,
     forge_version: String,
+) -> Result<core::forge::InstalledForgeVersion, String> {
+    install_forge_loader(
+        &window,
+        &config_state,
+        &instance_state,
+        &instance_id,
+        &game_version,
+        core::forge::ForgeLoader::Forge,
+        &forge_version,
+    )
+    .await
+}
+
+/// Install NeoForge for a specific Minecraft version
+#[tauri::command]
+#[dropout_macros::api]
+async fn install_neoforge(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceState>,
+    instance_id: String,
+    game_version: String,
+    neoforge_version: String,
+) -> Result<core::forge::InstalledForgeVersion, String> {
+    install_forge_loader(
+        &window,
+        &config_state,
+        &instance_state,
+        &instance_id,
+        &game_version,
+        core::forge::ForgeLoader::NeoForge,
+        &neoforge_version,
+    )
+    .await
+}
+
+/// Install Forge or NeoForge from its installer: download the vanilla client and
+/// every library, run the installer's processors and register the loader's version
+/// on the instance
+async fn install_forge_loader(
+    window: &Window,
+    config_state: &core::config::ConfigState,
+    instance_state: &core::instance::InstanceState,
+    instance_id: &str,
+    game_version: &str,
+    loader: core::forge::ForgeLoader,
+    version: &str,
 ) -> Result<core::forge::InstalledForgeVersion, String> {
     emit_log!(
         window,
         format!(
-            "Installing Forge {} for Minecraft {} in instance {}...",
-            forge_version, game_version, instance_id
+            "Installing {} {} for Minecraft {} in instance {}...",
+            loader.name(),
+            version,
+            game_version,
+            instance_id
         )
     );
 
-    instance_state.begin_operation(&instance_id, core::instance::InstanceOperation::Install)?;
+    instance_state.begin_operation(instance_id, core::instance::InstanceOperation::Install)?;
 
+    let config = config_state.config.lock().unwrap().clone();
+    let app_handle = window.app_handle();
+    let mut work_dir = None;
     let install_result: Result<core::forge::InstalledForgeVersion, String> = async {
-        let game_dir = instance_state
-            .get_instance_game_dir(&instance_id)
-            .ok_or_else(|| format!("Instance {} not found", instance_id))?;
-
-        // Get Java path from config or detect
-        let config = config_state.config.lock().unwrap().clone();
-        let app_handle = window.app_handle();
-        let java_path_str = if !config.java_path.is_empty() && config.java_path != "java" {
-            config.java_path.clone()
-        } else {
-            // Try to find a suitable Java installation
-            let javas = core::java::detect_all_java_installations(app_handle).await;
-            if let Some(java) = javas.first() {
-                java.path.clone()
-            } else {
-                return Err(
-                    "No Java installation found. Please configure Java in settings.".to_string(),
-                );
-            }
-        };
-        let java_path = utils::path::normalize_java_path(&java_path_str)?;
+        let paths = instance_state.resolve_paths(instance_id, &config, app_handle)?;
+        let rule_context = core::rules::RuleContext::for_launch(&config);
 
-        emit_log!(window, "Running Forge installer...".to_string());
-
-        // Run the Forge installer to properly patch the client
-        core::forge::run_forge_installer(&game_dir, &game_version, &forge_version, &java_path)
+        // The processors patch the vanilla client, so it has to be there first
+        let vanilla = core::manifest::load_version(&paths.root, game_version)
             .await
-            .map_err(|e| format!("Forge installer failed: {}", e))?;
+            .map_err(|e| e.to_string())?;
+        let client_path = paths
+            .version_cache
+            .join(game_version)
+            .join(format!("{}.jar", game_version));
+        let mut tasks = core::game_files::client_and_library_tasks(
+            &vanilla,
+            &client_path,
+            &paths.libraries,
+            &rule_context,
+        )?;
 
         emit_log!(
             window,
-            "Forge installer completed, creating version profile...".to_string()
+            format!("Downloading {} installer...", loader.name())
         );
+        let dir = work_dir.insert(paths.root.join(".forge-installer"));
+        let installer_path = core::forge::download_installer(loader, game_version, version, dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        let installer = core::forge_installer::ForgeInstaller::open(&installer_path)?;
+        tasks.extend(installer.library_tasks(&paths.libraries, &rule_context)?);
 
-        // Check if the version JSON already exists
-        let version_id = core::forge::generate_version_id(&game_version, &forge_version);
-        let json_path = game_dir
-            .join("versions")
-            .join(&version_id)
-            .join(format!("{}.json", version_id));
-
-        let result = if json_path.exists() {
-            // Version JSON was created by the installer, load it
-            emit_log!(
-                window,
-                "Using version profile created by Forge installer".to_string()
-            );
-            core::forge::InstalledForgeVersion {
-                id: version_id,
-                minecraft_version: game_version.clone(),
-                forge_version: forge_version.clone(),
-                path: json_path,
-            }
-        } else {
-            // Installer didn't create JSON, create it manually
-            core::forge::install_forge(&game_dir, &game_version, &forge_version)
-                .await
-                .map_err(|e| e.to_string())?
+        emit_log!(
+            window,
+            format!("Downloading {} libraries...", loader.name())
+        );
+        let report =
+            core::game_files::download_with_settings(window.clone(), tasks, &config).await?;
+        check_download_report(window, &report)?;
+
+        // The processors run on the Java the game will launch with
+        let (required_java_major, max_java_major) = java_version_bounds(game_version, &vanilla);
+        let required_major = required_java_major.unwrap_or(8) as u32;
+        let resolution = resolve_java_within(
+            app_handle,
+            &config,
+            Some(instance_id),
+            required_java_major,
+            max_java_major,
+        )
+        .await;
+        let java = resolution
+            .launch_java(required_major, max_java_major)
+            .map_err(|error| error.with_code())?;
+
+        let env = core::forge_installer::ProcessorEnv {
+            java: std::path::Path::new(&java.path),
+            libraries_dir: &paths.libraries,
+            client_jar: &client_path,
+            root: &paths.root,
+            work_dir: dir,
+        };
+        installer
+            .run_processors(&env, |progress| {
+                emit_log!(
+                    window,
+                    format!(
+                        "{} processor {}/{}: {}{}",
+                        loader.name(),
+                        progress.step,
+                        progress.total,
+                        progress.task.as_deref().unwrap_or(&progress.processor),
+                        if progress.skipped {
+                            " (already done)"
+                        } else {
+                            ""
+                        }
+                    )
+                );
+                let _ = window.emit("forge-install-progress", &progress);
+            })
+            .await
+            .map_err(|e| format!("{} installation failed: {}", loader.name(), e))?;
+
+        let json_path = installer.save_version(&paths.root)?;
+        let result = core::forge::InstalledForgeVersion {
+            id: installer.version_id()?.to_string(),
+            minecraft_version: game_version.to_string(),
+            forge_version: version.to_string(),
+            path: json_path,
         };
 
         emit_log!(
             window,
-            format!("Forge installed successfully: {}", result.id)
+            format!("{} installed successfully: {}", loader.name(), result.id)
         );
 
         // Update Instance's mod_loader metadata and version_id
-        if let Some(mut instance) = instance_state.get_instance(&instance_id) {
-            instance.mod_loader = Some("forge".to_string());
-            instance.mod_loader_version = Some(forge_version.clone());
+        if let Some(mut instance) = instance_state.get_instance(instance_id) {
+            instance.mod_loader = Some(loader.id().to_string());
+            instance.mod_loader_version = Some(version.to_string());
             instance.version_id = Some(result.id.clone());
             instance_state.update_instance(instance)?;
         }
@@ -2385,7 +2827,10 @@ async fn install_forge(
     }
     .await;
 
-    instance_state.end_operation(&instance_id);
+    if let Some(dir) = work_dir {
+        let _ = tokio::fs::remove_dir_all(dir).await;
+    }
+    instance_state.end_operation(instance_id);
     install_result
 }
 
@@ -2403,10 +2848,9 @@ struct GithubRelease {
 #[tauri::command]
 #[dropout_macros::api]
 async fn get_github_releases() -> Result<Vec<GithubRelease>, String> {
-    let client = reqwest::Client::new();
+    let client = core::http::client();
     let res = client
         .get("https://api.github.com/repos/HydroRoll-Team/DropOut/releases")
-        .header("User-Agent", "DropOut-Launcher")
         .send()
         .await
         .map_err(|e| e.to_string())?;
@@ -2464,7 +2908,7 @@ async fn upload_to_pastebin(
         )
     };
 
-    let client = reqwest::Client::new();
+    let client = core::http::client();
 
     match service.as_str() {
         "pastebin.com" => {
@@ -2576,10 +3020,26 @@ async fn create_instance(
 #[tauri::command]
 #[dropout_macros::api]
 async fn delete_instance(
+    app_handle: tauri::AppHandle,
     state: State<'_, core::instance::InstanceState>,
     instance_id: String,
 ) -> Result<(), String> {
-    state.delete_instance(&instance_id)
+    state.delete_instance(&instance_id)?;
+    if let Err(e) =
+        core::java::persistence::remove_instance_java_override(&app_handle, &instance_id)
+    {
+        eprintln!(
+            "Warning: Failed to remove Java override of {}: {}",
+            instance_id, e
+        );
+    }
+    if let Err(e) = core::java::persistence::clear_last_used_java(&app_handle, &instance_id) {
+        eprintln!(
+            "Warning: Failed to forget the last used Java of {}: {}",
+            instance_id, e
+        );
+    }
+    Ok(())
 }
 
 /// Update an instance
@@ -2865,24 +3325,48 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .manage(core::auth::AccountState::new())
         .manage(MsRefreshTokenState::new())
-        .manage(GameProcessState::new())
+        .manage(core::game_process::GameProcessState::new())
         .manage(core::assistant::AssistantState::new())
         .setup(|app| {
+            // Everything below reads and writes under the data directory, so say up
+            // front when there isn't one rather than failing file by file
+            if let Err(e) = core::paths::try_data_root(app.handle()) {
+                eprintln!("[Startup] Error: {}", e.with_code());
+                let _ = app.emit("data-dir-unavailable", &e);
+            }
+
             let config_state = core::config::ConfigState::new(app.handle());
+            apply_download_settings(&config_state.config.lock().unwrap());
+            let (pending_max_age_days, use_shared_caches) = {
+                let config = config_state.config.lock().unwrap();
+                (
+                    config.pending_java_download_max_age_days,
+                    config.use_shared_caches,
+                )
+            };
             app.manage(config_state);
 
+            let network = core::java::persistence::get_network_settings(app.handle());
+            if let Err(e) = core::http::apply_settings(&network) {
+                eprintln!("[Startup] Warning: Ignoring network settings: {}", e);
+            }
+
             // Initialize instance state
             let instance_state = core::instance::InstanceState::new(app.handle());
 
             // Migrate legacy data if needed
-            if let Err(e) = core::instance::migrate_legacy_data(app.handle(), &instance_state) {
+            if let Err(e) = core::instance::migrate_legacy_data(
+                app.handle(),
+                &instance_state,
+                use_shared_caches,
+            ) {
                 eprintln!("[Startup] Warning: Failed to migrate legacy data: {}", e);
             }
 
             app.manage(instance_state);
 
             // Load saved account on startup
-            let app_dir = app.path().app_data_dir().unwrap();
+            let app_dir = core::paths::data_root(app.handle());
             let storage = core::account_storage::AccountStorage::new(app_dir);
 
             if let Some((stored_account, ms_refresh)) = storage.get_active_account() {
@@ -2899,11 +3383,37 @@ fn main() {
                 println!("[Startup] Loaded saved account");
             }
 
-            // Check for pending Java downloads and notify frontend
+            // Drop queue entries that can no longer be resumed, then notify frontend
+            // about the rest
+            match core::downloader::DownloadQueue::prune(
+                app.handle(),
+                Duration::from_secs(u64::from(pending_max_age_days) * 24 * 60 * 60),
+            ) {
+                Ok(0) => {}
+                Ok(removed) => {
+                    println!("[Startup] Pruned {} stale Java download(s)", removed)
+                }
+                Err(e) => eprintln!("[Startup] Warning: Failed to prune download queue: {}", e),
+            }
+            core::java::known::spawn_revalidation(app.handle().clone());
+
             let pending = core::java::get_pending_downloads(app.app_handle());
             if !pending.is_empty() {
                 println!("[Startup] Found {} pending Java download(s)", pending.len());
                 let _ = app.emit("pending-java-downloads", pending.len());
+                if core::java::persistence::get_java_settings(app.handle()).auto_resume_downloads {
+                    spawn_auto_resume(app.handle().clone());
+                }
+            }
+
+            if app
+                .state::<core::config::ConfigState>()
+                .config
+                .lock()
+                .unwrap()
+                .background_refresh
+            {
+                spawn_catalog_refresh(app.handle().clone());
             }
 
             Ok(())
@@ -2911,19 +3421,44 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             start_game,
             stop_game,
+            get_launch_history,
             get_versions,
             get_versions_of_instance,
             check_version_installed,
             install_version,
+            verify_game_files,
+            repair_game_files,
             list_installed_versions,
             get_version_java_version,
             get_version_metadata,
             delete_version,
             login_offline,
             get_active_account,
+            list_accounts,
+            set_active_account,
+            remove_account,
             logout,
             get_settings,
             save_settings,
+            set_download_speed_limit,
+            get_network_settings,
+            get_network_config,
+            update_network_config,
+            save_network_settings,
+            test_proxy,
+            get_network_diagnostics,
+            restore_java_config_backup,
+            reload_java_config,
+            list_profiles,
+            create_profile,
+            switch_profile,
+            delete_profile,
+            add_user_java_path,
+            export_settings,
+            import_settings,
+            get_instance_java_override,
+            set_instance_java_override,
+            remove_instance_java_override,
             get_config_path,
             read_raw_config,
             save_raw_config,
@@ -2932,15 +3467,37 @@ fn main() {
             refresh_account,
             // Java commands
             detect_java,
+            get_message_catalog,
+            get_known_java_installations,
+            resolve_java,
+            explain_java_resolution,
+            resolve_java_for_minecraft,
+            rescan_java_installations,
+            clear_last_used_java,
+            get_known_bad_java_builds,
             get_recommended_java,
             fetch_adoptium_java,
             download_adoptium_java,
             fetch_available_java_versions,
             fetch_java_catalog,
             refresh_java_catalog,
+            clear_java_caches,
             cancel_java_download,
+            cancel_all_java_downloads,
+            get_download_history,
+            clear_download_history,
+            pause_downloads,
+            resume_downloads,
+            cancel_downloads,
+            cancel_download,
             get_pending_java_downloads,
             resume_java_downloads,
+            prune_java_download_queue,
+            set_auto_resume_downloads,
+            get_java_settings,
+            update_java_settings,
+            retry_pending_java_download,
+            discard_pending_java_download,
             // Fabric commands
             get_fabric_game_versions,
             get_fabric_loader_versions,
@@ -2951,7 +3508,9 @@ fn main() {
             // Forge commands
             get_forge_game_versions,
             get_forge_versions_for_game,
+            get_neoforge_versions_for_game,
             install_forge,
+            install_neoforge,
             get_github_releases,
             upload_to_pastebin,
             assistant_check_health,
@@ -2976,6 +3535,18 @@ fn main() {
             delete_instance_file,
             open_file_explorer
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                let keep_running = app_handle
+                    .state::<core::config::ConfigState>()
+                    .config
+                    .lock()
+                    .unwrap()
+                    .keep_game_running_on_exit;
+                let games = app_handle.state::<core::game_process::GameProcessState>();
+                tauri::async_runtime::block_on(games.shutdown(keep_running));
+            }
+        });
 }
@@ -0,0 +1,136 @@
+//! Checks that every ts-rs type is exported under the bindings root, and writes the
+//! `index.ts` barrel of each bindings directory so the frontend can import any
+//! backend type from `@/types`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// A `#[derive(TS)]` item and the `export_to` of its `#[ts(...)]` attributes
+struct TsItem {
+    name: String,
+    source: PathBuf,
+    export_to: Option<String>,
+}
+
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            rust_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+fn derives_ts(line: &str) -> bool {
+    line.starts_with("#[derive(")
+        && line
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|word| word == "TS")
+}
+
+fn export_to(attr: &str) -> Option<String> {
+    let rest = &attr[attr.find("export_to")?..];
+    let start = rest.find('"')? + 1;
+    let end = start + rest[start..].find('"')?;
+    Some(rest[start..end].to_string())
+}
+
+fn ts_items(source: &Path) -> Vec<TsItem> {
+    let content = std::fs::read_to_string(source).unwrap();
+    let lines: Vec<&str> = content.lines().map(str::trim).collect();
+    let mut items = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if !derives_ts(line) {
+            continue;
+        }
+        let mut exported_to = None;
+        for line in &lines[i + 1..] {
+            if line.starts_with("#[ts(") && line.contains("export") {
+                exported_to = export_to(line).or(exported_to);
+            } else if !line.starts_with("#[") {
+                let name = line
+                    .split_whitespace()
+                    .skip_while(|word| !matches!(*word, "struct" | "enum"))
+                    .nth(1)
+                    .unwrap_or(line)
+                    .trim_end_matches(['{', ';', '('])
+                    .split('<')
+                    .next()
+                    .unwrap()
+                    .to_string();
+                items.push(TsItem {
+                    name,
+                    source: source.to_path_buf(),
+                    export_to: exported_to,
+                });
+                break;
+            }
+        }
+    }
+    items
+}
+
+/// `index.ts` contents per bindings directory, re-exporting its files and subdirectories
+fn barrels(export_paths: &BTreeSet<String>) -> BTreeMap<PathBuf, BTreeSet<String>> {
+    let mut barrels: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+    for export_path in export_paths {
+        let mut path = Path::new(export_path).with_extension("");
+        while let Some(parent) = path.parent() {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            barrels
+                .entry(parent.to_path_buf())
+                .or_default()
+                .insert(name);
+            path = parent.to_path_buf();
+        }
+    }
+    barrels
+}
+
+#[test]
+fn bindings_are_exported_under_the_root_and_indexed() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut sources = Vec::new();
+    rust_files(&manifest_dir.join("src"), &mut sources);
+    sources.sort();
+    let items: Vec<TsItem> = sources.iter().flat_map(|s| ts_items(s)).collect();
+    assert!(!items.is_empty());
+
+    let mut export_paths = BTreeSet::new();
+    for item in &items {
+        let export_to = item.export_to.as_deref().unwrap_or_else(|| {
+            panic!(
+                "{} in {} derives TS but isn't exported to a bindings file",
+                item.name,
+                item.source.display()
+            )
+        });
+        let relative = Path::new(export_to);
+        assert!(
+            export_to.ends_with(".ts")
+                && relative.is_relative()
+                && relative
+                    .components()
+                    .all(|c| matches!(c, std::path::Component::Normal(_))),
+            "{} is exported to {}, outside the bindings root",
+            item.name,
+            export_to
+        );
+        export_paths.insert(export_to.to_string());
+    }
+
+    let bindings_root = manifest_dir.join("../packages/ui/src/types/bindings");
+    for (dir, entries) in barrels(&export_paths) {
+        let content: String = entries
+            .iter()
+            .map(|entry| format!("export * from \"./{}\";\n", entry))
+            .collect();
+        let index = bindings_root.join(dir).join("index.ts");
+        if std::fs::read_to_string(&index).ok().as_deref() != Some(content.as_str()) {
+            std::fs::create_dir_all(index.parent().unwrap()).unwrap();
+            std::fs::write(&index, content).unwrap();
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Helpers for errors that wrap a lower-level cause.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+/// The lower-level error behind one of our errors, shared so the outer error stays
+/// `Clone`. Any two sources compare equal, so errors compare by their own fields
+/// rather than by what caused them.
+#[derive(Clone)]
+pub struct ErrorSource(Arc<dyn Error + Send + Sync>);
+
+impl ErrorSource {
+    pub fn new(error: impl Error + Send + Sync + 'static) -> Self {
+        ErrorSource(Arc::new(error))
+    }
+
+    /// Rebuilds a source from the messages of its chain, e.g. after a round trip
+    /// through the frontend; `None` for an empty chain
+    pub fn from_chain(messages: Vec<String>) -> Option<Self> {
+        let link = messages.into_iter().rev().fold(None, |source, message| {
+            Some(ChainLink {
+                message,
+                source: source.map(Box::new),
+            })
+        })?;
+        Some(ErrorSource::new(link))
+    }
+}
+
+/// One message of a chain rebuilt by [`ErrorSource::from_chain`]
+#[derive(Debug)]
+struct ChainLink {
+    message: String,
+    source: Option<Box<ChainLink>>,
+}
+
+impl fmt::Display for ChainLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ChainLink {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|link| link as _)
+    }
+}
+
+impl fmt::Debug for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for ErrorSource {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl PartialEq for ErrorSource {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ErrorSource {}
+
+/// Serialized as its chain, see [`chain`]
+impl Serialize for ErrorSource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        chain(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ErrorSource::from_chain(Vec::deserialize(deserializer)?)
+            .ok_or_else(|| serde::de::Error::custom("empty error chain"))
+    }
+}
+
+/// Messages of `error` and everything that caused it, outermost first
+pub fn chain(error: &(dyn Error + 'static)) -> Vec<String> {
+    std::iter::successors(Some(error), |&error| error.source())
+        .map(|error| error.to_string())
+        .collect()
+}
+
+/// `error` and its causes on one line, for logs
+pub fn display_chain(error: &(dyn Error + 'static)) -> String {
+    chain(error).join(": ")
+}
+
+/// The first error of type `T` in the chain of `error`, looking inside [`ErrorSource`]s
+pub fn find_in_chain<'a, T: Error + 'static>(error: &'a (dyn Error + 'static)) -> Option<&'a T> {
+    std::iter::successors(Some(error), |&error| error.source()).find_map(|error| {
+        error.downcast_ref::<T>().or_else(|| {
+            error
+                .downcast_ref::<ErrorSource>()
+                .and_then(|source| source.0.downcast_ref::<T>())
+        })
+    })
+}
+
+/// Messages of the causes of `error`, without `error` itself
+pub fn causes(error: &(dyn Error + 'static)) -> Vec<String> {
+    error.source().map(chain).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("outer")]
+    struct Outer(#[source] ErrorSource);
+
+    #[test]
+    fn chains_walk_every_source() {
+        let inner = std::io::Error::other("disk on fire");
+        let err = Outer(ErrorSource::new(inner));
+        assert_eq!(chain(&err), vec!["outer", "disk on fire"]);
+        assert_eq!(display_chain(&err), "outer: disk on fire");
+        assert_eq!(causes(&err), vec!["disk on fire"]);
+    }
+
+    #[test]
+    fn chains_survive_serialization() {
+        let source = ErrorSource::from_chain(vec!["middle".to_string(), "root".to_string()]);
+        let err = Outer(source.unwrap());
+        assert_eq!(display_chain(&err), "outer: middle: root");
+
+        let json = serde_json::to_value(&err.0).unwrap();
+        assert_eq!(json, serde_json::json!(["middle", "root"]));
+        let source: ErrorSource = serde_json::from_value(json).unwrap();
+        assert_eq!(chain(&source), vec!["middle", "root"]);
+        assert!(ErrorSource::from_chain(Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,309 @@
+//! A local HTTP/1.1 server for tests, answering requests from a route table
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request as the server received it
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Request path without the query string
+    pub path: String,
+    /// Request line and headers, exactly as sent
+    pub head: String,
+    /// How many requests for this path arrived so far, this one included
+    pub hit: usize,
+}
+
+impl Request {
+    /// Value of the header `name`, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+}
+
+/// What to send back for a request
+#[derive(Debug, Clone)]
+pub struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    /// `Content-Length` to announce; `None` leaves the header out
+    content_length: Option<usize>,
+    delay: Option<Duration>,
+    stall: bool,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        let body = body.into();
+        Self {
+            status,
+            headers: Vec::new(),
+            content_length: Some(body.len()),
+            body,
+            delay: None,
+            stall: false,
+        }
+    }
+
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::new(200, body)
+    }
+
+    /// An empty response with `status`
+    pub fn status(status: u16) -> Self {
+        Self::new(status, Vec::new())
+    }
+
+    pub fn header(mut self, name: &str, value: impl ToString) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Announce a `Content-Length` other than the body's, or none at all
+    pub fn content_length(mut self, length: Option<usize>) -> Self {
+        self.content_length = length;
+        self
+    }
+
+    /// Wait this long before answering
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Keep the connection open and silent after sending the response
+    pub fn stall(mut self) -> Self {
+        self.stall = true;
+        self
+    }
+}
+
+type Handler = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
+
+pub struct MockServerBuilder {
+    ip: String,
+    routes: HashMap<String, Handler>,
+    fallback: Handler,
+    keep_alive: bool,
+}
+
+impl MockServerBuilder {
+    /// Answer requests for exactly `path` with `handler`
+    pub fn route(
+        mut self,
+        path: &str,
+        handler: impl Fn(&Request) -> Response + Send + Sync + 'static,
+    ) -> Self {
+        self.routes.insert(path.to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Answer requests no route matches; by default they get a 404
+    pub fn fallback(
+        mut self,
+        handler: impl Fn(&Request) -> Response + Send + Sync + 'static,
+    ) -> Self {
+        self.fallback = Arc::new(handler);
+        self
+    }
+
+    /// Listen on `ip` instead of 127.0.0.1
+    pub fn bind(mut self, ip: &str) -> Self {
+        self.ip = ip.to_string();
+        self
+    }
+
+    /// Answer further requests on a connection instead of closing it
+    pub fn keep_alive(mut self) -> Self {
+        self.keep_alive = true;
+        self
+    }
+
+    pub async fn start(self) -> MockServer {
+        let listener = TcpListener::bind((self.ip.as_str(), 0)).await.unwrap();
+        let server = MockServer {
+            addr: listener.local_addr().unwrap(),
+            requests: Arc::default(),
+            connections: Arc::default(),
+            in_flight: Arc::default(),
+            peak_in_flight: Arc::default(),
+        };
+        let state = Arc::new(Serving {
+            routes: self.routes,
+            fallback: self.fallback,
+            keep_alive: self.keep_alive,
+            requests: server.requests.clone(),
+            in_flight: server.in_flight.clone(),
+            peak_in_flight: server.peak_in_flight.clone(),
+        });
+        let connections = server.connections.clone();
+
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                connections.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(state.clone().serve(socket));
+            }
+        });
+
+        server
+    }
+}
+
+struct Serving {
+    routes: HashMap<String, Handler>,
+    fallback: Handler,
+    keep_alive: bool,
+    requests: Arc<Mutex<Vec<Request>>>,
+    in_flight: Arc<AtomicUsize>,
+    peak_in_flight: Arc<AtomicUsize>,
+}
+
+impl Serving {
+    async fn serve(self: Arc<Self>, mut socket: TcpStream) {
+        while let Some(request) = self.read_request(&mut socket).await {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak_in_flight.fetch_max(now, Ordering::SeqCst);
+
+            let handler = self.routes.get(&request.path).unwrap_or(&self.fallback);
+            let response = handler(&request);
+            if let Some(delay) = response.delay {
+                tokio::time::sleep(delay).await;
+            }
+            let sent = self.write_response(&mut socket, &response).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if response.stall {
+                std::future::pending::<()>().await;
+            }
+            if sent.is_err() || !self.keep_alive {
+                break;
+            }
+        }
+        let _ = socket.shutdown().await;
+    }
+
+    async fn read_request(&self, socket: &mut TcpStream) -> Option<Request> {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        let head_end = loop {
+            if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end;
+            }
+            match socket.read(&mut buf).await {
+                Ok(0) | Err(_) => return None,
+                Ok(n) => data.extend_from_slice(&buf[..n]),
+            }
+        };
+        let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+        let mut body_read = data.len() - (head_end + 4);
+
+        let target = head.split_whitespace().nth(1).unwrap_or("/");
+        let path = target.split('?').next().unwrap_or_default().to_string();
+        let mut request = Request { path, head, hit: 0 };
+
+        // Drain the body, so closing the connection doesn't reset it
+        let length = request
+            .header("content-length")
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        while body_read < length {
+            match socket.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => body_read += n,
+            }
+        }
+
+        let mut requests = self.requests.lock().unwrap();
+        request.hit = requests.iter().filter(|r| r.path == request.path).count() + 1;
+        requests.push(request.clone());
+        Some(request)
+    }
+
+    async fn write_response(
+        &self,
+        socket: &mut TcpStream,
+        response: &Response,
+    ) -> std::io::Result<()> {
+        let reason = reqwest::StatusCode::from_u16(response.status)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or("Unknown");
+        let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason);
+        for (name, value) in &response.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if let Some(length) = response.content_length {
+            head.push_str(&format!("Content-Length: {}\r\n", length));
+        }
+        if !self.keep_alive {
+            head.push_str("Connection: close\r\n");
+        }
+        head.push_str("\r\n");
+
+        socket.write_all(head.as_bytes()).await?;
+        socket.write_all(&response.body).await
+    }
+}
+
+/// A running server; it stops with the test's runtime
+pub struct MockServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<Request>>>,
+    connections: Arc<AtomicUsize>,
+    in_flight: Arc<AtomicUsize>,
+    peak_in_flight: Arc<AtomicUsize>,
+}
+
+impl MockServer {
+    pub fn builder() -> MockServerBuilder {
+        MockServerBuilder {
+            ip: "127.0.0.1".to_string(),
+            routes: HashMap::new(),
+            fallback: Arc::new(|_| Response::status(404)),
+            keep_alive: false,
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// `http://<addr><path>`
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// Every request received so far, in order
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Number of requests received for `path`
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.path == path)
+            .count()
+    }
+
+    /// Number of connections accepted so far
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// Most requests that were being answered at the same time
+    pub fn peak_in_flight(&self) -> usize {
+        self.peak_in_flight.load(Ordering::SeqCst)
+    }
+}
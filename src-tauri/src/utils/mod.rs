@@ -1,6 +1,12 @@
 #[cfg(test)]
 pub mod api;
-pub mod path;
+#[cfg(test)]
+mod bindings;
+pub mod error;
+#[cfg(test)]
+pub mod mock_server;
+#[cfg(test)]
+pub mod test_dir;
 pub mod zip;
 
 // File system related utility functions
@@ -0,0 +1,26 @@
+//! Scratch directories for tests, removed again when the test is done
+
+use std::path::{Path, PathBuf};
+
+/// A fresh directory under the system temp dir, deleted on drop
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+    /// Create `dropout-<name>-<uuid>`. The path is canonical, so it compares equal to
+    /// paths a child process reports, such as its working directory.
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("dropout-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir.canonicalize().unwrap())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
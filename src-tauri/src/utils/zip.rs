@@ -4,6 +4,17 @@ use std::path::Path;
 use tar::Archive;
 
 pub fn extract_zip(zip_path: &Path, extract_to: &Path) -> Result<(), String> {
+    extract_zip_filtered(zip_path, extract_to, |name| name.contains("META-INF"))
+}
+
+/// Extract a zip archive, leaving out the entries for which `skip` returns true.
+///
+/// `skip` gets the entry name as stored in the archive, e.g. `META-INF/MANIFEST.MF`.
+pub fn extract_zip_filtered(
+    zip_path: &Path,
+    extract_to: &Path,
+    skip: impl Fn(&str) -> bool,
+) -> Result<(), String> {
     let file = fs::File::open(zip_path)
         .map_err(|e| format!("Failed to open zip {}: {}", zip_path.display(), e))?;
     let mut archive =
@@ -18,8 +29,7 @@ pub fn extract_zip(zip_path: &Path, extract_to: &Path) -> Result<(), String> {
             None => continue,
         };
 
-        // Skip META-INF
-        if outpath.to_string_lossy().contains("META-INF") {
+        if skip(file.name()) {
             continue;
         }
 